@@ -0,0 +1,169 @@
+//! Builds the deposits which register new validators with the deposit contract.
+use bls::{get_withdrawal_credentials, Keypair, PublicKey};
+use serde_derive::Serialize;
+use ssz::Encode;
+use tree_hash::TreeHash;
+use types::{ChainSpec, DepositData, EthSpec, Fork, Hash256, Signature};
+
+/// The selector of the deposit contract's `deposit(bytes,bytes,bytes)` function, being the first
+/// four bytes of the Keccak-256 hash of its signature.
+pub const DEPOSIT_FUNCTION_SELECTOR: [u8; 4] = [0xc4, 0x7e, 0x30, 0x0d];
+/// The number of Wei in a Gwei. Deposit amounts are in Gwei, transaction values in Wei.
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+const ABI_WORD_LEN: usize = 32;
+
+/// A deposit together with the eth1 transaction which makes it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DepositTransaction {
+    pub pubkey: String,
+    pub withdrawal_credentials: Hash256,
+    /// The deposit amount, in Gwei.
+    pub amount: u64,
+    pub signature: String,
+    /// The tree hash root of the `DepositData`, as it will appear in the deposit contract's log.
+    pub deposit_data_root: Hash256,
+    /// The value of the transaction, in Wei. Decimal, as it may exceed a `u64`.
+    pub value: String,
+    /// The `0x`-prefixed calldata of a call to the deposit contract.
+    pub calldata: String,
+}
+
+impl DepositTransaction {
+    pub fn new(deposit_data: &DepositData) -> Self {
+        Self {
+            pubkey: deposit_data.pubkey.as_hex_string(),
+            withdrawal_credentials: deposit_data.withdrawal_credentials,
+            amount: deposit_data.amount,
+            signature: format!("0x{}", hex::encode(deposit_data.signature.as_ssz_bytes())),
+            deposit_data_root: Hash256::from_slice(&deposit_data.tree_hash_root()),
+            value: (u128::from(deposit_data.amount) * WEI_PER_GWEI).to_string(),
+            calldata: format!("0x{}", hex::encode(deposit_calldata(deposit_data))),
+        }
+    }
+}
+
+/// Returns the `DepositData` which registers `signing_keypair` as a validator with `amount` Gwei,
+/// withdrawable by the holder of the secret key of `withdrawal_pubkey`.
+///
+/// The deposit is signed by `signing_keypair`, as proof of possession of its secret key.
+pub fn build_deposit_data<E: EthSpec>(
+    signing_keypair: &Keypair,
+    withdrawal_pubkey: &PublicKey,
+    amount: u64,
+    spec: &ChainSpec,
+) -> DepositData {
+    let mut deposit_data = DepositData {
+        pubkey: signing_keypair.pk.clone(),
+        withdrawal_credentials: Hash256::from_slice(&get_withdrawal_credentials(
+            withdrawal_pubkey,
+            spec.bls_withdrawal_prefix_byte,
+        )),
+        amount,
+        signature: Signature::empty_signature(),
+    };
+
+    let genesis_epoch = E::genesis_epoch();
+    deposit_data.signature = deposit_data.create_signature(
+        &signing_keypair.sk,
+        genesis_epoch,
+        &Fork::genesis(genesis_epoch),
+        spec,
+    );
+
+    deposit_data
+}
+
+/// Returns the calldata of a call to `deposit(pubkey, withdrawal_credentials, signature)` on the
+/// deposit contract. The amount is not an argument; it is the value of the transaction.
+pub fn deposit_calldata(deposit_data: &DepositData) -> Vec<u8> {
+    let mut calldata = DEPOSIT_FUNCTION_SELECTOR.to_vec();
+    calldata.append(&mut abi_encode_bytes(&[
+        &deposit_data.pubkey.as_ssz_bytes(),
+        deposit_data.withdrawal_credentials.as_bytes(),
+        &deposit_data.signature.as_ssz_bytes(),
+    ]));
+    calldata
+}
+
+/// ABI-encodes `args` as a tuple of dynamic `bytes`: a word giving the offset of each argument,
+/// followed by each argument's length and its bytes, right-padded to a whole number of words.
+fn abi_encode_bytes(args: &[&[u8]]) -> Vec<u8> {
+    let mut head = vec![];
+    let mut tail = vec![];
+
+    for arg in args {
+        head.append(&mut abi_word(args.len() * ABI_WORD_LEN + tail.len()));
+        tail.append(&mut abi_word(arg.len()));
+        tail.extend_from_slice(arg);
+        let padding = (ABI_WORD_LEN - arg.len() % ABI_WORD_LEN) % ABI_WORD_LEN;
+        tail.resize(tail.len() + padding, 0);
+    }
+
+    head.append(&mut tail);
+    head
+}
+
+/// Encodes `n` as a big-endian 32-byte word.
+fn abi_word(n: usize) -> Vec<u8> {
+    let mut word = vec![0; ABI_WORD_LEN - 8];
+    word.extend_from_slice(&(n as u64).to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_hash::SignedRoot;
+    use types::{Domain, MinimalEthSpec};
+
+    #[test]
+    fn deposit_signature_verifies() {
+        let spec = MinimalEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let deposit_data = build_deposit_data::<MinimalEthSpec>(
+            &keypair,
+            &keypair.pk,
+            spec.max_effective_balance,
+            &spec,
+        );
+
+        let genesis_epoch = MinimalEthSpec::genesis_epoch();
+        let domain = spec.get_domain(
+            genesis_epoch,
+            Domain::Deposit,
+            &Fork::genesis(genesis_epoch),
+        );
+        assert!(deposit_data
+            .signature
+            .verify(&deposit_data.signed_root(), domain, &keypair.pk));
+    }
+
+    #[test]
+    fn encodes_calldata() {
+        let spec = MinimalEthSpec::default_spec();
+        let keypair = Keypair::random();
+        let deposit_data =
+            build_deposit_data::<MinimalEthSpec>(&keypair, &keypair.pk, 32_000_000_000, &spec);
+
+        let calldata = deposit_calldata(&deposit_data);
+        let word = |i: usize| &calldata[4 + i * 32..4 + (i + 1) * 32];
+
+        // Selector, three offsets, then 48, 32 and 96 byte arguments each after their length.
+        assert_eq!(
+            calldata.len(),
+            4 + 3 * 32 + (32 + 64) + (32 + 32) + (32 + 96)
+        );
+        assert_eq!(&calldata[0..4], &DEPOSIT_FUNCTION_SELECTOR);
+        assert_eq!(word(0), &abi_word(0x60)[..]);
+        assert_eq!(word(1), &abi_word(0xc0)[..]);
+        assert_eq!(word(2), &abi_word(0x100)[..]);
+        assert_eq!(word(3), &abi_word(48)[..]);
+        assert_eq!(
+            &calldata[4 + 4 * 32..4 + 4 * 32 + 48],
+            &keypair.pk.as_ssz_bytes()[..]
+        );
+
+        let transaction = DepositTransaction::new(&deposit_data);
+        assert_eq!(transaction.value, "32000000000000000000");
+    }
+}
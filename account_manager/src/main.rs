@@ -1,13 +1,23 @@
-use bls::Keypair;
-use clap::{App, Arg, SubCommand};
+mod deposit;
+mod wallet;
+
+use bls::{Keypair, PublicKey};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use deposit::DepositTransaction;
 use eth2_config::get_data_dir;
-use slog::{crit, debug, info, o, Drain};
+use slog::{crit, debug, info, o, warn, Drain};
+use ssz::Decode;
+use std::fs;
 use std::path::PathBuf;
 use types::test_utils::generate_deterministic_keypair;
+use types::{EthSpec, MainnetEthSpec};
 use validator_client::Config as ValidatorClientConfig;
+use wallet::{path_to_string, seed_from_mnemonic, validator_key_path, KeyType, ValidatorKeys};
 
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse-account-manager";
 pub const CLIENT_CONFIG_FILENAME: &str = "account-manager.toml";
+pub const DEPOSIT_DATA_FILENAME: &str = "deposit-data.json";
+pub const DEPOSITS_FILENAME: &str = "deposits.json";
 
 fn main() {
     // Logging
@@ -59,6 +69,80 @@ fn main() {
                         .default_value("1"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("new_mnemonic")
+                .about("Prints a new 24-word mnemonic, from which validator keys may be derived")
+                .version("0.0.1")
+                .author("Sigma Prime <contact@sigmaprime.io>"),
+        )
+        .subcommand(
+            SubCommand::with_name("derive")
+                .about("Derives validator keys from a mnemonic (EIP-2333/2334), saving a keystore and deposit data for each")
+                .version("0.0.1")
+                .author("Sigma Prime <contact@sigmaprime.io>")
+                .arg(
+                    Arg::with_name("mnemonic")
+                        .long("mnemonic")
+                        .short("m")
+                        .value_name("FILE")
+                        .help("A file containing the mnemonic.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("validator index")
+                        .long("index")
+                        .short("i")
+                        .value_name("index")
+                        .help("The index of the first validator to derive.")
+                        .takes_value(true)
+                        .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("validator count")
+                        .long("validator_count")
+                        .short("n")
+                        .value_name("validator_count")
+                        .help("The number of consecutive validators to derive.")
+                        .takes_value(true)
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::with_name("deposit amount")
+                        .long("deposit_amount")
+                        .value_name("GWEI")
+                        .help("The amount of each deposit. Defaults to the maximum effective balance.")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit_data")
+                .about("Builds a signed deposit and its eth1 transaction for each validator in the data directory")
+                .version("0.0.1")
+                .author("Sigma Prime <contact@sigmaprime.io>")
+                .arg(
+                    Arg::with_name("withdrawal pubkey")
+                        .long("withdrawal_pubkey")
+                        .value_name("HEX")
+                        .help("The public key which may withdraw the deposits. Defaults to each validator's own key.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("deposit amount")
+                        .long("deposit_amount")
+                        .value_name("GWEI")
+                        .help("The amount of each deposit. Defaults to the maximum effective balance.")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .value_name("FILE")
+                        .help("The file to which the deposits are written. Defaults to `deposits.json` in the data directory.")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
 
     let data_dir = match get_data_dir(&matches, PathBuf::from(DEFAULT_DATA_DIR)) {
@@ -94,6 +178,23 @@ fn main() {
 
     match matches.subcommand() {
         ("generate", Some(_)) => generate_random(&client_config, &log),
+        ("new_mnemonic", Some(_)) => {
+            warn!(
+                log,
+                "Anyone with this mnemonic controls the keys derived from it. Store it offline."
+            );
+            println!("{}", wallet::new_mnemonic().phrase());
+        }
+        ("derive", Some(m)) => {
+            if let Err(e) = derive_from_mnemonic(m, &client_config, &log) {
+                crit!(log, "Failed to derive keys"; "error" => e);
+            }
+        }
+        ("deposit_data", Some(m)) => {
+            if let Err(e) = write_deposit_data(m, &client_config, &log) {
+                crit!(log, "Failed to build deposit data"; "error" => e);
+            }
+        }
         ("generate_deterministic", Some(m)) => {
             if let Some(string) = m.value_of("validator index") {
                 let i: usize = string.parse().expect("Invalid validator index");
@@ -139,6 +240,144 @@ fn generate_deterministic(
     )
 }
 
+/// Derives the keys of each requested validator from a mnemonic, saving the signing key to a
+/// keystore and the deposit which registers it beside it.
+///
+/// Withdrawal keys are not saved; they may be derived from the mnemonic when needed.
+fn derive_from_mnemonic(
+    matches: &ArgMatches,
+    config: &ValidatorClientConfig,
+    log: &slog::Logger,
+) -> Result<(), String> {
+    let spec = MainnetEthSpec::default_spec();
+
+    let mnemonic_path = matches
+        .value_of("mnemonic")
+        .ok_or_else(|| "No mnemonic file supplied".to_string())?;
+    let first_index: u32 = matches
+        .value_of("validator index")
+        .unwrap_or("0")
+        .parse()
+        .map_err(|_| "Invalid validator index".to_string())?;
+    let count: u32 = matches
+        .value_of("validator count")
+        .unwrap_or("1")
+        .parse()
+        .map_err(|_| "Invalid validator count".to_string())?;
+    let amount = match matches.value_of("deposit amount") {
+        Some(amount) => amount
+            .parse()
+            .map_err(|_| "Invalid deposit amount".to_string())?,
+        None => spec.max_effective_balance,
+    };
+
+    let phrase = fs::read_to_string(mnemonic_path)
+        .map_err(|e| format!("Unable to read mnemonic file: {:?}", e))?;
+    let seed = seed_from_mnemonic(&phrase, "").map_err(|e| format!("Invalid mnemonic: {:?}", e))?;
+
+    for index in first_index..first_index + count {
+        let keys = ValidatorKeys::derive(&seed, index)
+            .map_err(|e| format!("Unable to derive keys: {:?}", e))?;
+
+        let keystore_path = config
+            .save_key(&keys.signing)
+            .map_err(|e| format!("Unable to save keystore: {:?}", e))?;
+
+        let deposit_data = deposit::build_deposit_data::<MainnetEthSpec>(
+            &keys.signing,
+            &keys.withdrawal.pk,
+            amount,
+            &spec,
+        );
+        let deposit_data_path = keystore_path.with_file_name(DEPOSIT_DATA_FILENAME);
+        let json = serde_json::to_string_pretty(&DepositTransaction::new(&deposit_data))
+            .map_err(|e| format!("Unable to serialize deposit data: {:?}", e))?;
+        fs::write(&deposit_data_path, json)
+            .map_err(|e| format!("Unable to save deposit data: {:?}", e))?;
+
+        info!(
+            log,
+            "Derived validator";
+            "index" => keys.index,
+            "pubkey" => keys.signing.pk.as_hex_string(),
+            "signing_path" => path_to_string(&validator_key_path(index, KeyType::Signing)),
+            "withdrawal_path" => path_to_string(&validator_key_path(index, KeyType::Withdrawal)),
+            "deposit_data" => format!("{:?}", deposit_data_path)
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds a deposit, and the eth1 transaction which makes it, for every validator whose keystore
+/// is in the data directory, writing them all to a single JSON file.
+fn write_deposit_data(
+    matches: &ArgMatches,
+    config: &ValidatorClientConfig,
+    log: &slog::Logger,
+) -> Result<(), String> {
+    let spec = MainnetEthSpec::default_spec();
+
+    let withdrawal_pubkey = matches
+        .value_of("withdrawal pubkey")
+        .map(|pubkey| {
+            hex::decode(pubkey.trim_start_matches("0x"))
+                .map_err(|e| format!("{:?}", e))
+                .and_then(|bytes| PublicKey::from_ssz_bytes(&bytes).map_err(|e| format!("{:?}", e)))
+                .map_err(|e| format!("Invalid withdrawal public key: {}", e))
+        })
+        .transpose()?;
+    let amount = match matches.value_of("deposit amount") {
+        Some(amount) => amount
+            .parse()
+            .map_err(|_| "Invalid deposit amount".to_string())?,
+        None => spec.max_effective_balance,
+    };
+    let output = matches
+        .value_of("output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.data_dir.join(DEPOSITS_FILENAME));
+
+    let keypairs = config
+        .fetch_keys(log)
+        .ok_or_else(|| "No keystores found in the data directory".to_string())?;
+
+    if withdrawal_pubkey.is_none() {
+        warn!(
+            log,
+            "No withdrawal key supplied; each validator's signing key may withdraw its deposit"
+        );
+    }
+
+    let transactions: Vec<DepositTransaction> = keypairs
+        .iter()
+        .map(|keypair| {
+            let withdrawal_pubkey = withdrawal_pubkey.as_ref().unwrap_or(&keypair.pk);
+            let deposit_data = deposit::build_deposit_data::<MainnetEthSpec>(
+                keypair,
+                withdrawal_pubkey,
+                amount,
+                &spec,
+            );
+            DepositTransaction::new(&deposit_data)
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&transactions)
+        .map_err(|e| format!("Unable to serialize deposits: {:?}", e))?;
+    fs::write(&output, json).map_err(|e| format!("Unable to save deposits: {:?}", e))?;
+
+    info!(
+        log,
+        "Deposits saved";
+        "count" => transactions.len(),
+        "amount" => amount,
+        "path" => format!("{:?}", output)
+    );
+
+    Ok(())
+}
+
 fn save_key(keypair: &Keypair, config: &ValidatorClientConfig, log: &slog::Logger) {
     let key_path: PathBuf = config
         .save_key(&keypair)
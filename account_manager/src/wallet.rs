@@ -0,0 +1,272 @@
+//! Hierarchical deterministic validator keys, derived from a BIP-39 mnemonic.
+//!
+//! Keys are derived following EIP-2333 and located by the paths of EIP-2334, so the same
+//! mnemonic yields the same keys in any compliant tool.
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use bls::{Keypair, PublicKey, SecretKey, BLS_SECRET_KEY_BYTE_SIZE};
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+/// The `purpose` level of every EIP-2334 path.
+pub const PURPOSE: u32 = 12381;
+/// The `coin_type` level of EIP-2334 paths, identifying Ethereum 2.0.
+pub const COIN_TYPE: u32 = 3600;
+
+/// EIP-2333 requires at least 256 bits of entropy in the seed.
+const MIN_SEED_LEN: usize = 32;
+/// The number of bytes of key material reduced modulo the curve order.
+const KEY_MATERIAL_LEN: usize = 48;
+/// The number of 32-byte chunks in each half of a Lamport secret key.
+const LAMPORT_CHUNKS: usize = 255;
+const HASH_LEN: usize = 32;
+/// The order of the BLS12-381 subgroup, in hex.
+const CURVE_ORDER: &[u8] = b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    InvalidMnemonic(String),
+    SeedTooShort(usize),
+    InvalidSecretKey(String),
+}
+
+/// The role of a key derived for a validator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyType {
+    /// Controls the withdrawal of the validator's balance. Need not be kept online.
+    Withdrawal,
+    /// Signs blocks and attestations, as the validator client's voting key.
+    Signing,
+}
+
+/// Returns the EIP-2334 path of the key of `key_type` for the validator numbered `index`:
+/// `m/12381/3600/<index>/0` for withdrawal and `m/12381/3600/<index>/0/0` for signing.
+pub fn validator_key_path(index: u32, key_type: KeyType) -> Vec<u32> {
+    let mut path = vec![PURPOSE, COIN_TYPE, index, 0];
+    if key_type == KeyType::Signing {
+        path.push(0);
+    }
+    path
+}
+
+/// Formats `path` in the conventional form, e.g. `m/12381/3600/0/0/0`.
+pub fn path_to_string(path: &[u32]) -> String {
+    path.iter()
+        .fold("m".to_string(), |s, level| format!("{}/{}", s, level))
+}
+
+/// Generates a new 24-word English mnemonic from the operating system's randomness.
+pub fn new_mnemonic() -> Mnemonic {
+    Mnemonic::new(MnemonicType::Words24, Language::English)
+}
+
+/// Returns the BIP-39 seed of the English mnemonic `phrase`, protected by `passphrase`.
+pub fn seed_from_mnemonic(phrase: &str, passphrase: &str) -> Result<Vec<u8>, Error> {
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| Error::InvalidMnemonic(format!("{}", e)))?;
+    Ok(Seed::new(&mnemonic, passphrase).as_bytes().to_vec())
+}
+
+/// The withdrawal and signing keys of a single validator.
+pub struct ValidatorKeys {
+    pub index: u32,
+    pub withdrawal: Keypair,
+    pub signing: Keypair,
+}
+
+impl ValidatorKeys {
+    /// Derives the keys of the validator numbered `index` from `seed`.
+    pub fn derive(seed: &[u8], index: u32) -> Result<Self, Error> {
+        Ok(Self {
+            index,
+            withdrawal: derive_keypair(seed, &validator_key_path(index, KeyType::Withdrawal))?,
+            signing: derive_keypair(seed, &validator_key_path(index, KeyType::Signing))?,
+        })
+    }
+}
+
+/// Derives the keypair at `path` from `seed`.
+pub fn derive_keypair(seed: &[u8], path: &[u32]) -> Result<Keypair, Error> {
+    let sk = path.iter().fold(derive_master_sk(seed)?, |parent, index| {
+        derive_child_sk(&parent, *index)
+    });
+
+    let bytes = to_be_bytes(&sk, BLS_SECRET_KEY_BYTE_SIZE);
+    let sk =
+        SecretKey::from_bytes(&bytes).map_err(|e| Error::InvalidSecretKey(format!("{:?}", e)))?;
+    let pk = PublicKey::from_secret_key(&sk);
+
+    Ok(Keypair { sk, pk })
+}
+
+fn derive_master_sk(seed: &[u8]) -> Result<BigUint, Error> {
+    if seed.len() < MIN_SEED_LEN {
+        return Err(Error::SeedTooShort(seed.len()));
+    }
+    Ok(hkdf_mod_r(seed))
+}
+
+fn derive_child_sk(parent_sk: &BigUint, index: u32) -> BigUint {
+    hkdf_mod_r(&parent_sk_to_lamport_pk(parent_sk, index))
+}
+
+/// Hashes `ikm` to a non-zero secret key, uniformly distributed modulo the curve order.
+fn hkdf_mod_r(ikm: &[u8]) -> BigUint {
+    let curve_order = BigUint::parse_bytes(CURVE_ORDER, 16).expect("curve order is valid hex");
+
+    let mut ikm = ikm.to_vec();
+    ikm.push(0);
+    // An empty key_info, followed by the output length as two big-endian bytes.
+    let info = [0, KEY_MATERIAL_LEN as u8];
+
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-").to_vec();
+    loop {
+        let prk = hkdf_extract(&salt, &ikm);
+        let okm = hkdf_expand(&prk, &info, KEY_MATERIAL_LEN);
+        let sk = BigUint::from_bytes_be(&okm) % &curve_order;
+        if sk.bits() != 0 {
+            return sk;
+        }
+        salt = Sha256::digest(&salt).to_vec();
+    }
+}
+
+/// Compresses the Lamport public key of `parent_sk` at `index`, which is the input to the
+/// derivation of the child.
+fn parent_sk_to_lamport_pk(parent_sk: &BigUint, index: u32) -> Vec<u8> {
+    let salt = index.to_be_bytes();
+    let ikm = to_be_bytes(parent_sk, HASH_LEN);
+    let not_ikm: Vec<u8> = ikm.iter().map(|b| !b).collect();
+
+    let lamport_pk: Vec<u8> = ikm_to_lamport_sk(&ikm, &salt)
+        .chunks(HASH_LEN)
+        .chain(ikm_to_lamport_sk(&not_ikm, &salt).chunks(HASH_LEN))
+        .flat_map(|chunk| Sha256::digest(chunk).to_vec())
+        .collect();
+
+    Sha256::digest(&lamport_pk).to_vec()
+}
+
+/// Returns a Lamport secret key, as `LAMPORT_CHUNKS` concatenated 32-byte chunks.
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Vec<u8> {
+    let prk = hkdf_extract(salt, ikm);
+    hkdf_expand(&prk, &[], HASH_LEN * LAMPORT_CHUNKS)
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.input(message);
+    mac.result().code().to_vec()
+}
+
+/// HKDF-Extract of RFC 5869, with SHA-256.
+fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    hmac_sha256(salt, ikm)
+}
+
+/// HKDF-Expand of RFC 5869, with SHA-256. `len` must not exceed 255 hashes.
+fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    assert!(
+        len <= 255 * HASH_LEN,
+        "HKDF output is limited to 255 hashes"
+    );
+
+    let mut okm = Vec::with_capacity(len + HASH_LEN);
+    let mut block = vec![];
+
+    for counter in 1..=255u8 {
+        if okm.len() >= len {
+            break;
+        }
+        let mut message = block;
+        message.extend_from_slice(info);
+        message.push(counter);
+        block = hmac_sha256(prk, &message);
+        okm.extend_from_slice(&block);
+    }
+
+    okm.truncate(len);
+    okm
+}
+
+/// Returns `n` as big-endian bytes, left-padded with zeros to `len`.
+fn to_be_bytes(n: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = n.to_bytes_be();
+    let mut padded = vec![0; len - bytes.len()];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(n: &str) -> BigUint {
+        BigUint::parse_bytes(n.as_bytes(), 10).unwrap()
+    }
+
+    fn hex_seed(seed: &str) -> Vec<u8> {
+        hex::decode(seed).unwrap()
+    }
+
+    /// Test case 0 of EIP-2333.
+    #[test]
+    fn eip_2333_case_0() {
+        let seed = hex_seed("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+
+        let master_sk = derive_master_sk(&seed).unwrap();
+        assert_eq!(
+            master_sk,
+            decimal("6083874454709270928345386274498605044986640685124978867557563392430687146096")
+        );
+        assert_eq!(
+            derive_child_sk(&master_sk, 0),
+            decimal(
+                "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+            )
+        );
+    }
+
+    /// Test case 1 of EIP-2333.
+    #[test]
+    fn eip_2333_case_1() {
+        let seed = hex_seed("3141592653589793238462643383279502884197169399375105820974944592");
+
+        let master_sk = derive_master_sk(&seed).unwrap();
+        assert_eq!(
+            master_sk,
+            decimal(
+                "29757020647961307431480504535336562678282505419141012933316116377660817309383"
+            )
+        );
+        assert_eq!(
+            derive_child_sk(&master_sk, 3_141_592_653),
+            decimal(
+                "25457201688850691947727629385191704516744796114925897962676248250929345014287"
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_short_seeds() {
+        assert_eq!(derive_master_sk(&[0; 31]), Err(Error::SeedTooShort(31)));
+    }
+
+    #[test]
+    fn formats_eip_2334_paths() {
+        assert_eq!(
+            path_to_string(&validator_key_path(7, KeyType::Signing)),
+            "m/12381/3600/7/0/0"
+        );
+        assert_eq!(
+            path_to_string(&validator_key_path(7, KeyType::Withdrawal)),
+            "m/12381/3600/7/0"
+        );
+    }
+
+    #[test]
+    fn signing_and_withdrawal_keys_differ() {
+        let keys = ValidatorKeys::derive(&[42; 32], 0).unwrap();
+        assert_ne!(keys.signing.pk, keys.withdrawal.pk);
+    }
+}
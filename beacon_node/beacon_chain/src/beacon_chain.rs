@@ -1,27 +1,53 @@
 use crate::checkpoint::CheckPoint;
 use crate::errors::{BeaconChainError as Error, BlockProductionError};
+use crate::events::{EventHandler, EventKind};
 use crate::iter::{BlockIterator, BlockRootsIterator};
+use crate::latest_messages::{LatestMessage, LatestMessages};
 use crate::metrics::Metrics;
-use crate::persisted_beacon_chain::{PersistedBeaconChain, BEACON_CHAIN_DB_KEY};
+use crate::persisted_beacon_chain::{
+    PersistedBeaconChain, PersistedForkChoice, PersistedLatestMessage, BEACON_CHAIN_DB_KEY,
+};
+use crate::validator_monitor::ValidatorMonitor;
+use crate::validator_pubkey_cache::ValidatorPubkeyCache;
+use crate::weak_subjectivity::WeakSubjectivityCheckpoint;
+use bls::{verify_signature_sets, SignatureSet};
 use fork_choice::{ForkChoice, ForkChoiceError};
-use log::{debug, trace};
+use log::{debug, error, trace, warn};
+use lru::LruCache;
+use merkle_proof::{MerkleTreeOverlay, ProofBundle};
 use operation_pool::DepositInsertStatus;
-use operation_pool::OperationPool;
-use parking_lot::{RwLock, RwLockReadGuard};
+use operation_pool::{OperationPool, PersistedOperationPool};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rayon::prelude::*;
+use slasher::{Slasher, Slashings};
 use slot_clock::SlotClock;
+use state_processing::common::{convert_to_indexed, get_attesting_indices_unsorted};
 use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, DepositValidationError,
     ExitValidationError, ProposerSlashingValidationError, TransferValidationError,
 };
+use state_processing::per_block_processing::{
+    attestation_signature_set, validate_attestation_time_independent_only_without_signature,
+    verify_attestation_signature,
+};
 use state_processing::{
     per_block_processing, per_block_processing_without_verifying_block_signature,
     per_slot_processing, BlockProcessingError,
 };
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use store::{Error as DBError, Store};
+use store::archive::{self, ArchivedSlot, StateDiff, StateStorage, MAX_DIFF_DEPTH};
+use store::{Error as DBError, Store, StoreItem};
 use tree_hash::TreeHash;
 use types::*;
 
+/// The maximum number of `BeaconState`s held in `BeaconChain::state_cache`.
+pub const STATE_CACHE_SIZE: usize = 8;
+/// The maximum number of `BeaconState`s held in `BeaconChain::replayed_states`.
+pub const REPLAYED_STATE_CACHE_SIZE: usize = 4;
+/// The most slots archived by `BeaconChain::update_archive` at once.
+pub const ARCHIVE_BATCH_SLOTS: u64 = 512;
+
 #[derive(Debug, PartialEq)]
 pub enum BlockProcessingOutcome {
     /// Block was valid and imported into the block graph.
@@ -43,6 +69,21 @@ pub enum BlockProcessingOutcome {
     BlockIsAlreadyKnown,
     /// The block could not be applied to the state, it is invalid.
     PerBlockProcessingError(BlockProcessingError),
+    /// The chain of the block has the block root `found` at the epoch of the weak subjectivity
+    /// `checkpoint`, so it is not the canonical chain.
+    WeakSubjectivityConflict {
+        checkpoint: WeakSubjectivityCheckpoint,
+        found: Hash256,
+    },
+}
+
+/// An archived state, and the roots it is indexed under.
+struct ArchivedState<E: EthSpec> {
+    block_root: Hash256,
+    state_root: Hash256,
+    state: BeaconState<E>,
+    /// The number of diffs between `state` and a state stored in full.
+    depth: u64,
 }
 
 pub trait BeaconChainTypes {
@@ -50,6 +91,7 @@ pub trait BeaconChainTypes {
     type SlotClock: slot_clock::SlotClock;
     type ForkChoice: fork_choice::ForkChoice<Self::Store>;
     type EthSpec: types::EthSpec;
+    type EventHandler: EventHandler<Self::EthSpec>;
 }
 
 /// Represents the "Beacon Chain" component of Ethereum 2.0. Allows import of blocks and block
@@ -71,11 +113,46 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     state: RwLock<BeaconState<T::EthSpec>>,
     /// The root of the genesis block.
     genesis_block_root: Hash256,
+    /// The root of the oldest block known to fork choice. This is the genesis block, unless the
+    /// chain was started from a trusted checkpoint.
+    anchor_block_root: Hash256,
+    /// The slot of the block at `self.anchor_block_root`.
+    anchor_slot: Slot,
+    /// The slot and parent root of the oldest block in the store. Historical blocks are
+    /// back-filled from this point towards genesis.
+    oldest_block: RwLock<(Slot, Hash256)>,
     /// A state-machine that is updated with information from the network and chooses a canonical
     /// head block.
     pub fork_choice: RwLock<T::ForkChoice>,
+    /// The latest message of each validator, as seen in verified attestations. Only messages newer
+    /// than those known are given to `self.fork_choice`.
+    latest_messages: RwLock<LatestMessages>,
+    /// A cache of recently used states, keyed by state root, consulted before `self.store`.
+    state_cache: Mutex<LruCache<Hash256, Arc<BeaconState<T::EthSpec>>>>,
+    /// A cache of states reconstructed by `Self::state_at_slot`, keyed by the root of the latest
+    /// block at or before the slot, and the slot.
+    replayed_states: Mutex<LruCache<(Hash256, Slot), Arc<BeaconState<T::EthSpec>>>>,
+    /// A copy of `self.state`, advanced to the next slot ahead of time by
+    /// `Self::advance_state_to_next_slot`.
+    advanced_state: Mutex<Option<BeaconState<T::EthSpec>>>,
     /// Stores metrics about this `BeaconChain`.
     pub metrics: Metrics,
+    /// Tracks the performance of validators chosen by the user.
+    pub validator_monitor: ValidatorMonitor,
+    /// Maps validator indices to public keys and back. Extended from the head state whenever a
+    /// lookup misses.
+    pubkey_cache: RwLock<ValidatorPubkeyCache>,
+    /// How the states of finalized slots are archived, if this is an archive node.
+    archive: RwLock<Option<StateStorage>>,
+    /// Checks the verified attestations and blocks for slashable offences, if enabled.
+    slasher: RwLock<Option<Arc<Slasher<T::Store>>>>,
+    /// The block root through which the canonical chain must pass, if one is trusted.
+    wss_checkpoint: RwLock<Option<WeakSubjectivityCheckpoint>>,
+    /// The indices of the finalized state proven by each `EventKind::ProofBundle`. No such events
+    /// are emitted while it is empty.
+    proof_event_indices: RwLock<Vec<u64>>,
+    /// Receives events such as head changes and finalization, e.g., for a websocket server.
+    pub event_handler: T::EventHandler,
 }
 
 impl<T: BeaconChainTypes> BeaconChain<T> {
@@ -87,6 +164,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         genesis_block: BeaconBlock,
         spec: ChainSpec,
         fork_choice: T::ForkChoice,
+        event_handler: T::EventHandler,
     ) -> Result<Self, Error> {
         let state_root = genesis_state.canonical_root();
         store.put(&state_root, &genesis_state)?;
@@ -115,8 +193,104 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             state: RwLock::new(genesis_state),
             canonical_head,
             genesis_block_root,
+            anchor_block_root: genesis_block_root,
+            anchor_slot: genesis_block.slot,
+            oldest_block: RwLock::new((genesis_block.slot, genesis_block.previous_block_root)),
+            fork_choice: RwLock::new(fork_choice),
+            state_cache: Mutex::new(LruCache::new(STATE_CACHE_SIZE)),
+            replayed_states: Mutex::new(LruCache::new(REPLAYED_STATE_CACHE_SIZE)),
+            advanced_state: Mutex::new(None),
+            metrics: Metrics::new()?,
+            validator_monitor: ValidatorMonitor::default(),
+            pubkey_cache: RwLock::new(ValidatorPubkeyCache::default()),
+            latest_messages: RwLock::new(LatestMessages::default()),
+            archive: RwLock::new(None),
+            slasher: RwLock::new(None),
+            wss_checkpoint: RwLock::new(None),
+            proof_event_indices: RwLock::new(vec![]),
+            event_handler,
+        })
+    }
+
+    /// Instantiate a new Beacon Chain from a trusted, finalized checkpoint instead of genesis.
+    ///
+    /// The `checkpoint_block` becomes the anchor of fork choice and the store; blocks prior to it
+    /// are unknown and must be back-filled separately if they are required.
+    ///
+    /// Returns an error if `checkpoint_state` is not the post-state of `checkpoint_block`.
+    pub fn from_checkpoint(
+        store: Arc<T::Store>,
+        slot_clock: T::SlotClock,
+        mut checkpoint_state: BeaconState<T::EthSpec>,
+        checkpoint_block: BeaconBlock,
+        spec: ChainSpec,
+        mut fork_choice: T::ForkChoice,
+        event_handler: T::EventHandler,
+    ) -> Result<Self, Error> {
+        let state_root = checkpoint_state.canonical_root();
+        if checkpoint_block.state_root != state_root {
+            return Err(Error::InvalidCheckpoint(format!(
+                "Block state root {} does not match state root {}",
+                checkpoint_block.state_root, state_root
+            )));
+        }
+        if checkpoint_block.slot != checkpoint_state.slot {
+            return Err(Error::InvalidCheckpoint(format!(
+                "Block slot {} does not match state slot {}",
+                checkpoint_block.slot, checkpoint_state.slot
+            )));
+        }
+
+        store.put(&state_root, &checkpoint_state)?;
+
+        let anchor_block_root = checkpoint_block.block_header().canonical_root();
+        store.put(&anchor_block_root, &checkpoint_block)?;
+
+        fork_choice.add_anchor_block(&checkpoint_block, &anchor_block_root, &spec)?;
+
+        // The genesis block root is only known if the checkpoint is recent enough that it is
+        // still in `latest_block_roots`.
+        let genesis_block_root = checkpoint_state
+            .get_block_root(spec.genesis_slot)
+            .map(|root| *root)
+            .unwrap_or(spec.zero_hash);
+
+        let canonical_head = RwLock::new(CheckPoint::new(
+            checkpoint_block.clone(),
+            anchor_block_root,
+            checkpoint_state.clone(),
+            state_root,
+        ));
+
+        checkpoint_state.build_all_caches(&spec)?;
+
+        Ok(Self {
+            spec,
+            store,
+            slot_clock,
+            op_pool: OperationPool::new(),
+            state: RwLock::new(checkpoint_state),
+            canonical_head,
+            genesis_block_root,
+            anchor_block_root,
+            anchor_slot: checkpoint_block.slot,
+            oldest_block: RwLock::new((
+                checkpoint_block.slot,
+                checkpoint_block.previous_block_root,
+            )),
             fork_choice: RwLock::new(fork_choice),
+            state_cache: Mutex::new(LruCache::new(STATE_CACHE_SIZE)),
+            replayed_states: Mutex::new(LruCache::new(REPLAYED_STATE_CACHE_SIZE)),
+            advanced_state: Mutex::new(None),
             metrics: Metrics::new()?,
+            validator_monitor: ValidatorMonitor::default(),
+            pubkey_cache: RwLock::new(ValidatorPubkeyCache::default()),
+            latest_messages: RwLock::new(LatestMessages::default()),
+            archive: RwLock::new(None),
+            slasher: RwLock::new(None),
+            wss_checkpoint: RwLock::new(None),
+            proof_event_indices: RwLock::new(vec![]),
+            event_handler,
         })
     }
 
@@ -124,6 +298,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     pub fn from_store(
         store: Arc<T::Store>,
         spec: ChainSpec,
+        event_handler: T::EventHandler,
     ) -> Result<Option<BeaconChain<T>>, Error> {
         let key = Hash256::from_slice(&BEACON_CHAIN_DB_KEY.as_bytes());
         let p: PersistedBeaconChain<T> = match store.get(&key) {
@@ -138,18 +313,68 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             spec.seconds_per_slot,
         );
 
-        let fork_choice = T::ForkChoice::new(store.clone());
+        let mut fork_choice = T::ForkChoice::new(store.clone());
+
+        let anchor_block: BeaconBlock = store
+            .get(&p.anchor_block_root)?
+            .ok_or_else(|| Error::MissingBeaconBlock(p.anchor_block_root))?;
+        fork_choice.add_anchor_block(&anchor_block, &p.anchor_block_root, &spec)?;
+        // A chain persisted without fork choice, as by a release which did not persist it, gives
+        // fork choice its canonical chain instead.
+        let block_roots = if p.fork_choice.block_roots.is_empty() {
+            blocks_since_anchor(
+                &*store,
+                p.canonical_head.beacon_block_root,
+                p.anchor_block_root,
+            )?
+        } else {
+            p.fork_choice.block_roots
+        };
+        for block_root in &block_roots {
+            let block: BeaconBlock = store
+                .get(block_root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(*block_root))?;
+            fork_choice.add_block(&block, block_root, &spec)?;
+        }
+
+        let mut latest_messages = LatestMessages::default();
+        for message in &p.fork_choice.latest_messages {
+            latest_messages.update(
+                message.validator_index as usize,
+                LatestMessage {
+                    epoch: message.epoch,
+                    root: message.root,
+                },
+            );
+            fork_choice.set_latest_vote(message.validator_index, &message.root);
+        }
+
+        let op_pool = p.op_pool.into_operation_pool(&p.state, &spec);
 
         Ok(Some(BeaconChain {
             spec,
             store,
             slot_clock,
-            op_pool: OperationPool::default(),
+            op_pool,
             canonical_head: RwLock::new(p.canonical_head),
             state: RwLock::new(p.state),
             fork_choice: RwLock::new(fork_choice),
             genesis_block_root: p.genesis_block_root,
+            anchor_block_root: p.anchor_block_root,
+            anchor_slot: anchor_block.slot,
+            oldest_block: RwLock::new((p.oldest_block_slot, p.oldest_block_parent)),
+            state_cache: Mutex::new(LruCache::new(STATE_CACHE_SIZE)),
+            replayed_states: Mutex::new(LruCache::new(REPLAYED_STATE_CACHE_SIZE)),
+            advanced_state: Mutex::new(None),
             metrics: Metrics::new()?,
+            validator_monitor: ValidatorMonitor::default(),
+            pubkey_cache: RwLock::new(ValidatorPubkeyCache::default()),
+            latest_messages: RwLock::new(latest_messages),
+            archive: RwLock::new(None),
+            slasher: RwLock::new(None),
+            wss_checkpoint: RwLock::new(None),
+            proof_event_indices: RwLock::new(vec![]),
+            event_handler,
         }))
     }
 
@@ -157,7 +382,24 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     pub fn persist(&self) -> Result<(), Error> {
         let p: PersistedBeaconChain<T> = PersistedBeaconChain {
             canonical_head: self.canonical_head.read().clone(),
+            op_pool: PersistedOperationPool::from_operation_pool(&self.op_pool),
+            fork_choice: PersistedForkChoice {
+                block_roots: self.fork_choice.read().block_roots(),
+                latest_messages: self
+                    .latest_messages
+                    .read()
+                    .iter()
+                    .map(|(index, message)| PersistedLatestMessage {
+                        validator_index: index as u64,
+                        epoch: message.epoch,
+                        root: message.root,
+                    })
+                    .collect(),
+            },
             genesis_block_root: self.genesis_block_root,
+            anchor_block_root: self.anchor_block_root,
+            oldest_block_slot: self.oldest_block.read().0,
+            oldest_block_parent: self.oldest_block.read().1,
             state: self.state.read().clone(),
         };
 
@@ -167,6 +409,52 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Returns the slot of the oldest block in the store and the root of its parent.
+    ///
+    /// If the parent root is `spec.zero_hash`, the oldest block is the genesis block and there is
+    /// no history left to back-fill.
+    pub fn oldest_block(&self) -> (Slot, Hash256) {
+        *self.oldest_block.read()
+    }
+
+    /// Returns `true` if all blocks from the anchor back to genesis are in the store.
+    pub fn is_backfill_complete(&self) -> bool {
+        self.oldest_block().1 == self.spec.zero_hash
+    }
+
+    /// Stores blocks which precede the oldest known block, without replaying any state
+    /// transitions.
+    ///
+    /// `blocks` may be in any order but must form an unbroken chain of parent roots ending at the
+    /// parent of the oldest known block. Blocks newer than the oldest known block are ignored.
+    ///
+    /// Returns the number of blocks imported.
+    pub fn import_historical_blocks(&self, mut blocks: Vec<BeaconBlock>) -> Result<usize, Error> {
+        let mut oldest_block = self.oldest_block.write();
+        let (oldest_slot, mut expected_root) = *oldest_block;
+
+        blocks.retain(|block| block.slot < oldest_slot);
+        blocks.sort_by(|a, b| b.slot.cmp(&a.slot));
+
+        for block in &blocks {
+            let block_root = block.block_header().canonical_root();
+
+            if block_root != expected_root {
+                return Err(Error::HistoricalBlockMismatch {
+                    expected: expected_root,
+                    found: block_root,
+                });
+            }
+
+            self.store.put(&block_root, block)?;
+
+            expected_root = block.previous_block_root;
+            *oldest_block = (block.slot, expected_root);
+        }
+
+        Ok(blocks.len())
+    }
+
     /// Returns the beacon block body for each beacon block root in `roots`.
     ///
     /// Fails if any root in `roots` does not have a corresponding block.
@@ -225,6 +513,97 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(self.store.get(block_root)?)
     }
 
+    /// Returns the state at the given root, if any.
+    ///
+    /// Recently used states are served from an in-memory cache, avoiding repeated reads and
+    /// decoding of the same (e.g., epoch boundary) states from `self.store`.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error.
+    pub fn get_state(
+        &self,
+        state_root: &Hash256,
+    ) -> Result<Option<Arc<BeaconState<T::EthSpec>>>, Error> {
+        if let Some(state) = self.state_cache.lock().get(state_root) {
+            return Ok(Some(state.clone()));
+        }
+
+        match self.store.get(state_root)? {
+            Some(state) => {
+                let state = Arc::new(state);
+                self.state_cache.lock().put(*state_root, state.clone());
+                Ok(Some(state))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the state at `slot` on the canonical chain, which must precede the present slot.
+    ///
+    /// The state is reconstructed from the nearest stored state before it, replaying at most
+    /// `max_replay_slots` slots, unless it has been archived. Reconstructed states are cached.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error, including if the replay would be too long.
+    pub fn state_at_slot(
+        &self,
+        slot: Slot,
+        max_replay_slots: u64,
+    ) -> Result<Option<Arc<BeaconState<T::EthSpec>>>, Error> {
+        // The iterator yields the root of the block at the slot before the one it is given.
+        let block_root = match self.rev_iter_block_roots(slot + 1).next() {
+            Some(block_root) => block_root,
+            None => return Ok(None),
+        };
+
+        let key = (block_root, slot);
+        if let Some(state) = self.replayed_states.lock().get(&key) {
+            return Ok(Some(state.clone()));
+        }
+
+        match self
+            .store
+            .get_state_at_slot(block_root, slot, max_replay_slots, &self.spec)?
+        {
+            Some(state) => {
+                let state = Arc::new(state);
+                self.replayed_states.lock().put(key, state.clone());
+                Ok(Some(state))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the finalized epoch and block root of the head, and the state root and state of
+    /// that block.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error, or an error if the finalized block or its state is not
+    /// stored.
+    pub fn finalized_state(
+        &self,
+    ) -> Result<(Epoch, Hash256, Hash256, Arc<BeaconState<T::EthSpec>>), Error> {
+        let (finalized_epoch, finalized_root) = {
+            let head = self.head();
+            (
+                head.beacon_state.finalized_epoch,
+                head.beacon_state.finalized_root,
+            )
+        };
+
+        let block = self
+            .get_block(&finalized_root)?
+            .ok_or(Error::MissingBeaconBlock(finalized_root))?;
+        let state = self
+            .get_state(&block.state_root)?
+            .ok_or(Error::MissingBeaconState(block.state_root))?;
+
+        Ok((finalized_epoch, finalized_root, block.state_root, state))
+    }
+
     /// Update the canonical head to `new_head`.
     fn update_canonical_head(&self, new_head: CheckPoint<T::EthSpec>) -> Result<(), Error> {
         // Update the checkpoint that stores the head of the chain at the time it received the
@@ -247,6 +626,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
             state.build_all_caches(&self.spec)?;
 
+            if let Err(e) = self.validator_monitor.process_state(&state, &self.metrics) {
+                warn!("Unable to update validator monitor: {:?}", e);
+            }
+
             state
         };
 
@@ -272,6 +655,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.canonical_head.read()
     }
 
+    /// Returns the digest of the head fork and the genesis block, which peers must share with us
+    /// in order to follow the same chain.
+    pub fn fork_digest(&self) -> ForkDigest {
+        ForkData {
+            current_version: self.head().beacon_state.fork.current_version,
+            genesis_root: self.genesis_block_root,
+        }
+        .fork_digest()
+    }
+
     /// Returns the slot of the highest block in the canonical chain.
     pub fn best_slot(&self) -> Slot {
         self.canonical_head.read().beacon_block.slot
@@ -288,6 +681,24 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         let mut state = self.state.write();
 
+        // Use the state prepared by `Self::advance_state_to_next_slot`, if it was built upon the
+        // same block as `state` and has been advanced no further than the present slot.
+        if state.slot < present_slot {
+            let mut advanced_state = self.advanced_state.lock();
+
+            let is_usable = advanced_state.as_ref().map_or(false, |advanced| {
+                advanced.slot > state.slot
+                    && advanced.slot <= present_slot
+                    && have_same_latest_block(advanced, &state)
+            });
+
+            if is_usable {
+                if let Some(advanced) = advanced_state.take() {
+                    *state = advanced;
+                }
+            }
+        }
+
         // If required, transition the new state to the present slot.
         for _ in state.slot.as_u64()..present_slot.as_u64() {
             // Ensure the next epoch state caches are built in case of an epoch transition.
@@ -301,6 +712,41 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Prepares a copy of the head state for the slot after the present slot, so the next call
+    /// to `Self::catchup_state` need not perform any (possibly epoch) transitions or build any
+    /// caches.
+    ///
+    /// Intended to be called on a background task shortly before the start of each slot, leaving
+    /// only operation packing and signing for block production in that slot. The prepared state
+    /// is not used if the head changes before the slot starts.
+    pub fn advance_state_to_next_slot(&self) -> Result<(), Error> {
+        let spec = &self.spec;
+
+        let next_slot = match self.slot_clock.present_slot() {
+            Ok(Some(slot)) => slot + 1,
+            _ => return Err(Error::UnableToReadSlot),
+        };
+
+        let mut state = self.state.read().clone();
+
+        if state.slot >= next_slot {
+            return Ok(());
+        }
+
+        for _ in state.slot.as_u64()..next_slot.as_u64() {
+            // Ensure the next epoch state caches are built in case of an epoch transition.
+            state.build_committee_cache(RelativeEpoch::Next, spec)?;
+
+            per_slot_processing(&mut state, spec)?;
+        }
+
+        state.build_all_caches(spec)?;
+
+        *self.advanced_state.lock() = Some(state);
+
+        Ok(())
+    }
+
     /// Build all of the caches on the current state.
     ///
     /// Ideally this shouldn't be required, however we leave it here for testing.
@@ -312,20 +758,86 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
     /// Returns the validator index (if any) for the given public key.
     ///
-    /// Information is retrieved from the present `beacon_state.validator_registry`.
+    /// Information is retrieved from the pubkey cache, which is extended from the present
+    /// `beacon_state.validator_registry` if the key is not yet known.
     pub fn validator_index(&self, pubkey: &PublicKey) -> Option<usize> {
-        for (i, validator) in self
-            .head()
-            .beacon_state
-            .validator_registry
-            .iter()
-            .enumerate()
-        {
-            if validator.pubkey == *pubkey {
-                return Some(i);
+        let cached = self.pubkey_cache.read().get_index(pubkey);
+        let index = cached.or_else(|| self.import_head_pubkeys().get_index(pubkey))?;
+
+        // The cache may know of validators from a former head with a longer registry.
+        if index < self.head().beacon_state.validator_registry.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the public key of the validator at `index`, if any.
+    ///
+    /// Keys are retrieved from the pubkey cache, without locking or copying a state, and are
+    /// returned for any validator in the registry of the present or a former head. Callers must
+    /// check `index` against the registry of the state they are working with.
+    pub fn validator_pubkey(&self, index: usize) -> Option<PublicKey> {
+        let cached = self.pubkey_cache.read().get(index).cloned();
+        cached.or_else(|| self.import_head_pubkeys().get(index).cloned())
+    }
+
+    /// Extends the pubkey cache from the present `beacon_state.validator_registry`.
+    ///
+    /// The cache is always locked before the head, never after.
+    fn import_head_pubkeys(&self) -> RwLockWriteGuard<ValidatorPubkeyCache> {
+        let mut cache = self.pubkey_cache.write();
+        cache.import(&self.head().beacon_state.validator_registry);
+        cache
+    }
+
+    /// Returns, for each of `validator_indices`, `true` if the validator attested in `epoch` or
+    /// proposed a block during it.
+    ///
+    /// Only attestations which have been included in the canonical chain are counted.
+    /// Information is read from the present `beacon_state`, so only the present and prior epoch
+    /// are available.
+    pub fn validator_liveness(
+        &self,
+        epoch: Epoch,
+        validator_indices: &[usize],
+    ) -> Result<Vec<bool>, Error> {
+        self.ensure_state_caches_are_built()?;
+        let state = self.state.read();
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+
+        let relative_epoch = RelativeEpoch::from_epoch(state.current_epoch(), epoch)
+            .map_err(BeaconStateError::from)?;
+        let pending_attestations = match relative_epoch {
+            RelativeEpoch::Current => &state.current_epoch_attestations,
+            RelativeEpoch::Previous => &state.previous_epoch_attestations,
+            RelativeEpoch::Next => return Ok(vec![false; validator_indices.len()]),
+        };
+
+        let mut live = HashSet::new();
+        for attestation in pending_attestations {
+            live.extend(get_attesting_indices_unsorted(
+                &state,
+                &attestation.data,
+                &attestation.aggregation_bitfield,
+            )?);
+        }
+
+        // A slot had a block if its block root differs from that of the prior slot. The block
+        // root of the present slot is not yet known.
+        for slot in epoch.slot_iter(slots_per_epoch) {
+            if slot == self.spec.genesis_slot || slot >= state.slot {
+                continue;
+            }
+            if state.get_block_root(slot)? != state.get_block_root(slot - 1)? {
+                live.insert(state.get_beacon_proposer_index(slot, relative_epoch, &self.spec)?);
             }
         }
-        None
+
+        Ok(validator_indices
+            .iter()
+            .map(|index| live.contains(index))
+            .collect())
     }
 
     /// Reads the slot clock, returns `None` if the slot is unavailable.
@@ -470,12 +982,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.metrics.attestation_processing_requests.inc();
         let timer = self.metrics.attestation_processing_times.start_timer();
 
+        let state = self.state.read();
         let result = self
             .op_pool
-            .insert_attestation(attestation, &*self.state.read(), &self.spec);
+            .insert_attestation(attestation.clone(), &*state, &self.spec);
 
         if result.is_ok() {
             self.metrics.attestation_processing_successes.inc();
+            self.apply_attestation_to_fork_choice(&*state, &attestation);
+            self.send_attestation_to_slasher(&*state, &attestation);
         }
 
         timer.observe_duration();
@@ -483,6 +998,159 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         result
     }
 
+    /// Accept a batch of attestations from the network, returning a result for each.
+    ///
+    /// All attestations are checked against a single read of the current state and their
+    /// signatures are verified together. If the batch of signatures is invalid, each signature is
+    /// verified alone to find the invalid attestations. Valid attestations are then added to the
+    /// `op_pool` without being checked again.
+    pub fn process_attestation_batch(
+        &self,
+        attestations: Vec<Attestation>,
+    ) -> Vec<Result<(), AttestationValidationError>> {
+        self.metrics
+            .attestation_processing_requests
+            .inc_by(attestations.len() as i64);
+        let timer = self.metrics.attestation_processing_times.start_timer();
+
+        let state = self.state.read();
+
+        let checked: Vec<Result<Option<SignatureSet>, AttestationValidationError>> = attestations
+            .par_iter()
+            .map(|attestation| {
+                validate_attestation_time_independent_only_without_signature(
+                    &*state,
+                    attestation,
+                    &self.spec,
+                )?;
+                attestation_signature_set(&*state, attestation, &self.spec)
+            })
+            .collect();
+
+        // Attestations without a set (`Ok(false)`) must have their signature verified alone.
+        let mut sets = vec![];
+        let in_batch: Vec<Result<bool, AttestationValidationError>> = checked
+            .into_iter()
+            .map(|result| {
+                result.map(|set| match set {
+                    Some(set) => {
+                        sets.push(set);
+                        true
+                    }
+                    None => false,
+                })
+            })
+            .collect();
+        let batch_is_valid = verify_signature_sets(&sets);
+
+        let verified: Vec<Result<(), AttestationValidationError>> = attestations
+            .par_iter()
+            .zip(in_batch)
+            .map(|(attestation, in_batch)| match in_batch {
+                Ok(true) if batch_is_valid => Ok(()),
+                Ok(_) => verify_attestation_signature(&*state, attestation, &self.spec),
+                Err(e) => Err(e),
+            })
+            .collect();
+
+        let results: Vec<_> = attestations
+            .into_iter()
+            .zip(verified)
+            .map(|(attestation, verified)| {
+                verified.map(|()| {
+                    self.apply_attestation_to_fork_choice(&*state, &attestation);
+                    self.send_attestation_to_slasher(&*state, &attestation);
+                    self.op_pool
+                        .insert_validated_attestation(attestation, &*state, &self.spec);
+                })
+            })
+            .collect();
+
+        let successes = results.iter().filter(|result| result.is_ok()).count();
+        self.metrics
+            .attestation_processing_successes
+            .inc_by(successes as i64);
+
+        timer.observe_duration();
+
+        results
+    }
+
+    /// Records the latest message of each validator attesting to `attestation`, which has been
+    /// verified against `state`, and sets the fork choice vote of each validator whose message is
+    /// newer than any seen before, so that fork choice weighs exactly the latest messages.
+    ///
+    /// Votes for unknown blocks are ignored, as fork choice cannot weigh them. Failures are logged,
+    /// since the attestation itself is valid.
+    fn apply_attestation_to_fork_choice(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        attestation: &Attestation,
+    ) {
+        if let Err(e) = self.try_apply_attestation_to_fork_choice(state, attestation) {
+            warn!(
+                "Unable to apply attestation to fork choice: {:?}, block root: {}",
+                e, attestation.data.beacon_block_root
+            );
+        }
+    }
+
+    fn try_apply_attestation_to_fork_choice(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        attestation: &Attestation,
+    ) -> Result<(), Error> {
+        let message = LatestMessage {
+            epoch: attestation.data.target_epoch,
+            root: attestation.data.beacon_block_root,
+        };
+        if !self.store.exists::<BeaconBlock>(&message.root)? {
+            return Ok(());
+        }
+
+        let attesting_indices = get_attesting_indices_unsorted(
+            state,
+            &attestation.data,
+            &attestation.aggregation_bitfield,
+        )?;
+        let updated: Vec<usize> = {
+            let mut latest_messages = self.latest_messages.write();
+            attesting_indices
+                .into_iter()
+                .filter(|&index| latest_messages.update(index, message))
+                .collect()
+        };
+
+        if !updated.is_empty() {
+            let mut fork_choice = self.fork_choice.write();
+            for index in updated {
+                fork_choice.set_latest_vote(index as u64, &message.root);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the latest message of the validator at `index`, as seen in verified attestations.
+    pub fn latest_message(&self, index: usize) -> Option<LatestMessage> {
+        self.latest_messages.read().get(index).cloned()
+    }
+
+    /// Queues `attestation`, which has been verified against `state`, to be checked by the
+    /// slasher, if enabled.
+    fn send_attestation_to_slasher(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        attestation: &Attestation,
+    ) {
+        if let Some(slasher) = &*self.slasher.read() {
+            match convert_to_indexed(state, attestation) {
+                Ok(indexed) => slasher.accept_attestation(indexed),
+                Err(e) => warn!("Unable to send attestation to slasher: {:?}", e),
+            }
+        }
+    }
+
     /// Accept some deposit and queue it for inclusion in an appropriate block.
     pub fn process_deposit(
         &self,
@@ -577,15 +1245,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // It is an error because if know the parent block we should also know the parent state.
         let parent_state_root = parent_block.state_root;
         let parent_state = self
-            .store
-            .get(&parent_state_root)?
+            .get_state(&parent_state_root)?
             .ok_or_else(|| Error::DBInconsistent(format!("Missing state {}", parent_state_root)))?;
 
         // TODO: check the block proposer signature BEFORE doing a state transition. This will
         // significantly lower exposure surface to DoS attacks.
 
         // Transition the parent state to the block slot.
-        let mut state: BeaconState<T::EthSpec> = parent_state;
+        let mut state: BeaconState<T::EthSpec> = (*parent_state).clone();
+
+        // A state read from the database has an empty aggregate pubkey cache; share the one used
+        // by the head for gossip instead.
+        state.aggregate_pubkey_cache = self.state.read().aggregate_pubkey_cache.clone();
+
         for _ in state.slot.as_u64()..block.slot.as_u64() {
             per_slot_processing(&mut state, &self.spec)?;
         }
@@ -608,14 +1280,37 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             return Ok(BlockProcessingOutcome::StateRootMismatch);
         }
 
+        if let Some(checkpoint) = *self.wss_checkpoint.read() {
+            if let Err(found) = checkpoint.check(block_root, &state) {
+                error!(
+                    "Rejected block {} at slot {}: its chain has block {} at epoch {}, but the \
+                     weak subjectivity checkpoint is {}",
+                    block_root, block.slot, found, checkpoint.epoch, checkpoint.root
+                );
+                return Ok(BlockProcessingOutcome::WeakSubjectivityConflict { checkpoint, found });
+            }
+        }
+
+        if let Some(slasher) = &*self.slasher.read() {
+            let proposer_index =
+                state.get_beacon_proposer_index(block.slot, RelativeEpoch::Current, &self.spec)?;
+            slasher.accept_block_header(proposer_index as u64, block.block_header());
+        }
+
         // Store the block and state.
         self.store.put(&block_root, &block)?;
         self.store.put(&state_root, &state)?;
+        let state = Arc::new(state);
+        self.state_cache.lock().put(state_root, state.clone());
 
         // Register the new block with the fork choice service.
         self.fork_choice
             .write()
             .add_block(&block, &block_root, &self.spec)?;
+        for attestation in &block.body.attestations {
+            self.apply_attestation_to_fork_choice(&state, attestation);
+            self.send_attestation_to_slasher(&state, attestation);
+        }
 
         // Execute the fork choice algorithm, enthroning a new head if discovered.
         //
@@ -636,9 +1331,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// The produced block will not be inherently valid, it must be signed by a block producer.
     /// Block signing is out of the scope of this function and should be done by a separate program.
+    ///
+    /// The `graffiti` chosen by the block producer is included in the block body as-is.
     pub fn produce_block(
         &self,
         randao_reveal: Signature,
+        graffiti: [u8; 32],
     ) -> Result<(BeaconBlock, BeaconState<T::EthSpec>), BlockProductionError> {
         debug!("Producing block at slot {}...", self.state.read().slot);
         self.metrics.block_production_requests.inc();
@@ -674,8 +1372,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     deposit_root: Hash256::zero(),
                     block_hash: Hash256::zero(),
                 },
-                // TODO: badass Lighthouse graffiti
-                graffiti: [0; 32],
+                graffiti,
                 proposer_slashings,
                 attester_slashings,
                 attestations: self
@@ -714,9 +1411,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let timer = self.metrics.fork_choice_times.start_timer();
 
         let justified_root = {
-            let root = self.head().beacon_state.current_justified_root;
-            if root == self.spec.zero_hash {
-                self.genesis_block_root
+            let state = &self.head().beacon_state;
+            let root = state.current_justified_root;
+            let justified_slot = state
+                .current_justified_epoch
+                .start_slot(T::EthSpec::slots_per_epoch());
+
+            // Fork choice knows nothing prior to the anchor, so start from the anchor if the
+            // justified block precedes it.
+            if root == self.spec.zero_hash || justified_slot < self.anchor_slot {
+                self.anchor_block_root
             } else {
                 root
             }
@@ -741,27 +1445,430 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .ok_or_else(|| Error::MissingBeaconBlock(beacon_block_root))?;
 
             let beacon_state_root = beacon_block.state_root;
-            let beacon_state: BeaconState<T::EthSpec> = self
-                .store
-                .get(&beacon_state_root)?
-                .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?;
+            let beacon_state: BeaconState<T::EthSpec> = (*self
+                .get_state(&beacon_state_root)?
+                .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?)
+            .clone();
+
+            let previous_head_beacon_block_root = self.head().beacon_block_root;
+            let previous_finalized_epoch = self.head().beacon_state.finalized_epoch;
 
             // If we switched to a new chain (instead of building atop the present chain).
-            if self.head().beacon_block_root != beacon_block.previous_block_root {
+            let reorg = previous_head_beacon_block_root != beacon_block.previous_block_root;
+            if reorg {
                 self.metrics.fork_choice_reorg_count.inc();
             };
 
+            let _ = self.event_handler.register(EventKind::BeaconHeadChanged {
+                reorg,
+                slot: beacon_block.slot,
+                current_head_beacon_block_root: beacon_block_root,
+                previous_head_beacon_block_root,
+            });
+
+            let finalized = beacon_state.finalized_epoch > previous_finalized_epoch;
+            if finalized {
+                let _ = self.event_handler.register(EventKind::BeaconFinalization {
+                    epoch: beacon_state.finalized_epoch,
+                    root: beacon_state.finalized_root,
+                });
+            }
+
             self.update_canonical_head(CheckPoint {
                 beacon_block,
                 beacon_block_root,
                 beacon_state,
                 beacon_state_root,
             })?;
+
+            if finalized {
+                if let Err(e) = self.update_archive() {
+                    warn!("Unable to archive finalized slots: {:?}", e);
+                }
+                if let Err(e) = self.register_proof_bundle() {
+                    warn!("Unable to prove the finalized state: {:?}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes this an archive node, which retains the state of every finalized slot and indexes
+    /// the block and state of each by slot.
+    ///
+    /// Slots are archived as they are finalized, starting from the anchor block, or from where a
+    /// previous run left off.
+    pub fn enable_archive(&self, storage: StateStorage) {
+        *self.archive.write() = Some(storage);
+    }
+
+    /// Enables the slasher, which checks every verified attestation and block from now on.
+    ///
+    /// Checks are made, and the resulting slashings added to the `op_pool`, by
+    /// `Self::process_slasher_queue`.
+    pub fn enable_slasher(&self, slasher: Arc<Slasher<T::Store>>) {
+        *self.slasher.write() = Some(slasher);
+    }
+
+    /// Checks the attestations and blocks queued for the slasher, if enabled, and adds any
+    /// slashings found to the `op_pool`.
+    ///
+    /// Returns the slashings found, including any rejected by the `op_pool`, e.g. because the
+    /// validators are already slashed.
+    pub fn process_slasher_queue(&self) -> Result<Slashings, Error> {
+        let slasher = match &*self.slasher.read() {
+            Some(slasher) => slasher.clone(),
+            None => return Ok(Slashings::default()),
+        };
+
+        let current_epoch = self.present_slot().epoch(T::EthSpec::slots_per_epoch());
+        let slashings = slasher.process_queued(current_epoch)?;
+
+        for slashing in &slashings.attester_slashings {
+            if let Err(e) = self.process_attester_slashing(slashing.clone()) {
+                debug!("Attester slashing not added to op pool: {:?}", e);
+            }
+        }
+        for slashing in &slashings.proposer_slashings {
+            if let Err(e) = self.process_proposer_slashing(slashing.clone()) {
+                debug!("Proposer slashing not added to op pool: {:?}", e);
+            }
+        }
+
+        Ok(slashings)
+    }
+
+    /// Emits an `EventKind::ProofBundle` of the nodes at `indices` of the finalized state each time
+    /// the finalized checkpoint changes. An empty `indices` stops the events.
+    ///
+    /// Returns an error if any index cannot be proven by `BeaconState::prove`.
+    pub fn set_proof_event_indices(&self, indices: Vec<u64>) -> Result<(), Error> {
+        if let Some(&index) = indices
+            .iter()
+            .find(|&&index| !BeaconState::<T::EthSpec>::is_attached(index))
+        {
+            return Err(BeaconStateError::UnsupportedProofIndex(index).into());
+        }
+
+        *self.proof_event_indices.write() = indices;
+
+        Ok(())
+    }
+
+    /// Registers an `EventKind::ProofBundle` against the state of the finalized block of the
+    /// head, if any indices are to be proven.
+    fn register_proof_bundle(&self) -> Result<(), Error> {
+        let indices = self.proof_event_indices.read().clone();
+        if indices.is_empty() {
+            return Ok(());
+        }
+
+        let (finalized_epoch, finalized_root, state_root, state) = self.finalized_state()?;
+        let partial = state.prove(&indices)?;
+
+        let _ = self.event_handler.register(EventKind::ProofBundle {
+            finalized_epoch,
+            finalized_root,
+            state_root,
+            bundle: ProofBundle {
+                partial,
+                links: vec![],
+            },
+        });
+
+        Ok(())
+    }
+
+    /// Sets the weak subjectivity checkpoint, through which the canonical chain must pass. Blocks
+    /// whose chain does not are rejected from now on.
+    ///
+    /// Blocks already imported whose chain does not pass through the checkpoint, e.g., as the node
+    /// followed another chain before the checkpoint was set, are removed from fork choice and the
+    /// store, and the head is chosen again from the rest.
+    ///
+    /// Returns an error if the anchor itself conflicts with the checkpoint, leaving no chain to
+    /// follow. The store must then be discarded.
+    pub fn set_wss_checkpoint(&self, checkpoint: WeakSubjectivityCheckpoint) -> Result<(), Error> {
+        *self.wss_checkpoint.write() = Some(checkpoint);
+
+        let removed = self.exclude_wss_conflicts(checkpoint)?;
+        let head_removed = removed.contains(&self.head().beacon_block_root);
+        if head_removed {
+            // Fork choice starts from the head's justified block, which may have been removed.
+            self.update_canonical_head(self.anchor_checkpoint()?)?;
+        }
+        self.fork_choice()?;
+
+        let head = self.head();
+        self.check_wss_checkpoint(head.beacon_block_root, &head.beacon_state)
+    }
+
+    /// Rebuilds fork choice without the blocks whose chain does not pass through `checkpoint`,
+    /// keeping the latest votes for the rest, then deletes those blocks and their states. Returns
+    /// the roots of the blocks removed.
+    ///
+    /// The root at the checkpoint slot of the chain of each block is found from its ancestors in
+    /// fork choice, and only that of the anchor from its state.
+    fn exclude_wss_conflicts(
+        &self,
+        checkpoint: WeakSubjectivityCheckpoint,
+    ) -> Result<HashSet<Hash256>, Error> {
+        let checkpoint_slot = checkpoint.epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let anchor = self.anchor_checkpoint()?;
+
+        // The root at the checkpoint slot of the chain of each block which has reached it.
+        let mut roots_at_checkpoint = HashMap::new();
+        if anchor.beacon_block.slot >= checkpoint_slot {
+            let root = match checkpoint.check(anchor.beacon_block_root, &anchor.beacon_state) {
+                Ok(()) => checkpoint.root,
+                Err(found) => found,
+            };
+            roots_at_checkpoint.insert(anchor.beacon_block_root, root);
+        }
+
+        let mut fork_choice = self.fork_choice.write();
+        let mut rebuilt = T::ForkChoice::new(self.store.clone());
+        rebuilt.add_anchor_block(&anchor.beacon_block, &anchor.beacon_block_root, &self.spec)?;
+
+        let mut removed = HashSet::new();
+        let mut removed_blocks = vec![];
+        // Parents come before their children, so the chain of each parent is known.
+        for block_root in fork_choice.block_roots() {
+            let block: BeaconBlock = self
+                .store
+                .get(&block_root)?
+                .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+
+            let root_at_checkpoint = match roots_at_checkpoint.get(&block.previous_block_root) {
+                Some(root) => Some(*root),
+                None if block.slot == checkpoint_slot => Some(block_root),
+                // The checkpoint slot was skipped, leaving the parent as its latest block.
+                None if block.slot > checkpoint_slot => Some(block.previous_block_root),
+                None => None,
+            };
+            if let Some(root) = root_at_checkpoint {
+                roots_at_checkpoint.insert(block_root, root);
+                if root != checkpoint.root {
+                    removed.insert(block_root);
+                    removed_blocks.push((block_root, block));
+                    continue;
+                }
+            }
+
+            rebuilt.add_block(&block, &block_root, &self.spec)?;
+        }
+
+        for (index, message) in self.latest_messages.read().iter() {
+            if !removed.contains(&message.root) {
+                rebuilt.set_latest_vote(index as u64, &message.root);
+            }
+        }
+        *fork_choice = rebuilt;
+        drop(fork_choice);
+
+        // Attestations to a block no longer in the store are not applied to fork choice.
+        for (block_root, block) in removed_blocks {
+            warn!(
+                "Removing block {} at slot {}, as its chain does not pass through the weak \
+                 subjectivity checkpoint {} at epoch {}",
+                block_root, block.slot, checkpoint.root, checkpoint.epoch
+            );
+            self.store.delete::<BeaconBlock>(&block_root)?;
+            self.store
+                .delete::<BeaconState<T::EthSpec>>(&block.state_root)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Returns the anchor block, with its root and state.
+    fn anchor_checkpoint(&self) -> Result<CheckPoint<T::EthSpec>, Error> {
+        let beacon_block: BeaconBlock = self
+            .store
+            .get(&self.anchor_block_root)?
+            .ok_or_else(|| Error::MissingBeaconBlock(self.anchor_block_root))?;
+        let beacon_state_root = beacon_block.state_root;
+        let beacon_state = (*self
+            .get_state(&beacon_state_root)?
+            .ok_or_else(|| Error::MissingBeaconState(beacon_state_root))?)
+        .clone();
+
+        Ok(CheckPoint {
+            beacon_block,
+            beacon_block_root: self.anchor_block_root,
+            beacon_state,
+            beacon_state_root,
+        })
+    }
+
+    /// Checks that the chain of `state`, the post-state of the block at `block_root`, passes
+    /// through the weak subjectivity checkpoint, if set.
+    fn check_wss_checkpoint(
+        &self,
+        block_root: Hash256,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<(), Error> {
+        match *self.wss_checkpoint.read() {
+            Some(checkpoint) => checkpoint
+                .check(block_root, state)
+                .map_err(|found| Error::WeakSubjectivityConflict { checkpoint, found }),
+            None => Ok(()),
         }
+    }
+
+    /// Returns the roots of the block and state at `slot`, if it has been archived.
+    pub fn archived_slot(&self, slot: Slot) -> Result<Option<ArchivedSlot>, Error> {
+        Ok(archive::get_archived_slot(&*self.store, slot)?)
+    }
+
+    /// Returns the state at `slot`, if it has been archived.
+    pub fn archived_state(&self, slot: Slot) -> Result<Option<BeaconState<T::EthSpec>>, Error> {
+        match self.archived_slot(slot)? {
+            Some(archived) => Ok(archive::get_archived_state(
+                &*self.store,
+                &archived.state_root,
+            )?),
+            None => Ok(None),
+        }
+    }
+
+    /// Archives the finalized slots after the archive tip, if this is an archive node.
+    ///
+    /// The state of each skipped slot is rebuilt from the state of the slot before it. At most
+    /// `ARCHIVE_BATCH_SLOTS` slots are archived per call, so that an archive enabled on an
+    /// existing chain catches up over several finalizations rather than stalling fork choice.
+    fn update_archive(&self) -> Result<(), Error> {
+        let storage = match *self.archive.read() {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+
+        let finalized_slot = self
+            .head()
+            .beacon_state
+            .finalized_epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        let tip = archive::get_archive_tip(&*self.store)?;
+        let start_slot = tip.map_or(self.anchor_slot, |tip| tip + 1);
+        if start_slot > finalized_slot {
+            return Ok(());
+        }
+        let end_slot = std::cmp::min(finalized_slot, start_slot + ARCHIVE_BATCH_SLOTS - 1);
+
+        // The iterator yields the root of the block at the slot before the one it is given.
+        let slot_count = (end_slot - start_slot).as_usize() + 1;
+        let mut block_roots: Vec<Hash256> = self
+            .rev_iter_block_roots(end_slot + 1)
+            .take(slot_count)
+            .collect();
+        if block_roots.len() != slot_count {
+            return Err(Error::DBInconsistent(format!(
+                "Missing block roots between slots {} and {}",
+                start_slot, end_slot
+            )));
+        }
+        block_roots.reverse();
+
+        // The archived state of the slot before the next to be archived.
+        let mut previous = match tip {
+            Some(tip) => {
+                let archived = archive::get_archived_slot(&*self.store, tip)?.ok_or_else(|| {
+                    Error::DBInconsistent(format!("Archive tip {} is not indexed", tip))
+                })?;
+                let (mut bytes, depth) =
+                    archive::get_state_bytes(&*self.store, &archived.state_root)?
+                        .ok_or_else(|| Error::MissingBeaconState(archived.state_root))?;
+                Some(ArchivedState {
+                    block_root: archived.block_root,
+                    state_root: archived.state_root,
+                    state: BeaconState::from_store_bytes(&mut bytes)?,
+                    depth,
+                })
+            }
+            None => None,
+        };
+
+        for (slot, block_root) in (start_slot.as_u64()..).map(Slot::new).zip(block_roots) {
+            let archived = match previous {
+                // The slot was skipped.
+                Some(ref previous) if previous.block_root == block_root => {
+                    self.archive_skipped_slot(previous, storage)?
+                }
+                _ => {
+                    let block = self
+                        .get_block(&block_root)?
+                        .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+                    let state = self
+                        .store
+                        .get(&block.state_root)?
+                        .ok_or_else(|| Error::MissingBeaconState(block.state_root))?;
+                    ArchivedState {
+                        block_root,
+                        state_root: block.state_root,
+                        state,
+                        depth: 0,
+                    }
+                }
+            };
+
+            archive::put_archived_slot(
+                &*self.store,
+                slot,
+                &ArchivedSlot {
+                    block_root,
+                    state_root: archived.state_root,
+                },
+            )?;
+            previous = Some(archived);
+        }
+
+        archive::put_archive_tip(&*self.store, end_slot)?;
 
         Ok(())
     }
 
+    /// Rebuilds and stores the state of the skipped slot after `previous`.
+    fn archive_skipped_slot(
+        &self,
+        previous: &ArchivedState<T::EthSpec>,
+        storage: StateStorage,
+    ) -> Result<ArchivedState<T::EthSpec>, Error> {
+        let mut state = previous.state.clone();
+        // Ensure the next epoch state caches are built in case of an epoch transition.
+        state.build_committee_cache(RelativeEpoch::Next, &self.spec)?;
+        per_slot_processing(&mut state, &self.spec)?;
+        let state_root = state.canonical_root();
+
+        let depth = if self.store.exists::<BeaconState<T::EthSpec>>(&state_root)? {
+            0
+        } else {
+            match storage {
+                StateStorage::Diffs if previous.depth < MAX_DIFF_DEPTH => {
+                    let diff = StateDiff::new(
+                        previous.state_root,
+                        previous.depth,
+                        &previous.state.as_store_bytes(),
+                        &state.as_store_bytes(),
+                    );
+                    self.store.put(&state_root, &diff)?;
+                    diff.depth
+                }
+                _ => {
+                    self.store.put(&state_root, &state)?;
+                    0
+                }
+            }
+        };
+
+        Ok(ArchivedState {
+            block_root: previous.block_root,
+            state_root,
+            state,
+            depth,
+        })
+    }
+
     /// Returns `true` if the given block root has not been processed.
     pub fn is_new_block_root(&self, beacon_block_root: &Hash256) -> Result<bool, Error> {
         Ok(!self.store.exists::<BeaconBlock>(beacon_block_root)?)
@@ -790,6 +1897,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 break; // Genesis has been reached.
             }
 
+            if last_slot.beacon_block_root == self.anchor_block_root {
+                break; // The checkpoint anchor has been reached, prior blocks are unknown.
+            }
+
             let beacon_block: BeaconBlock =
                 self.store.get(&beacon_block_root)?.ok_or_else(|| {
                     Error::DBInconsistent(format!("Missing block {}", beacon_block_root))
@@ -833,3 +1944,320 @@ impl From<BeaconStateError> for Error {
         Error::BeaconStateError(e)
     }
 }
+
+/// Returns the roots of the blocks after the anchor block, up to and including the block at
+/// `head_block_root`, each after its parent.
+fn blocks_since_anchor<S: Store>(
+    store: &S,
+    head_block_root: Hash256,
+    anchor_block_root: Hash256,
+) -> Result<Vec<Hash256>, Error> {
+    let mut block_roots = vec![];
+    let mut block_root = head_block_root;
+
+    while block_root != anchor_block_root {
+        let block: BeaconBlock = store
+            .get(&block_root)?
+            .ok_or_else(|| Error::MissingBeaconBlock(block_root))?;
+        block_roots.push(block_root);
+        block_root = block.previous_block_root;
+    }
+
+    block_roots.reverse();
+    Ok(block_roots)
+}
+
+/// Returns `true` if `a` and `b` have the same latest block header.
+///
+/// The `state_root` of the header is ignored, since it is filled in by the first
+/// `per_slot_processing` after the block.
+fn have_same_latest_block<E: EthSpec>(a: &BeaconState<E>, b: &BeaconState<E>) -> bool {
+    let (a, b) = (&a.latest_block_header, &b.latest_block_header);
+
+    a.slot == b.slot
+        && a.previous_block_root == b.previous_block_root
+        && a.block_body_root == b.block_body_root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::NullEventHandler;
+    use fork_choice::OptimizedLMDGhost;
+    use slot_clock::TestingSlotClock;
+    use std::marker::PhantomData;
+    use store::MemoryStore;
+    use types::test_utils::TestingBeaconStateBuilder;
+
+    struct TestTypes<E>(PhantomData<E>);
+
+    impl<E: EthSpec> BeaconChainTypes for TestTypes<E> {
+        type Store = MemoryStore;
+        type SlotClock = TestingSlotClock;
+        type ForkChoice = OptimizedLMDGhost<MemoryStore, E>;
+        type EthSpec = E;
+        type EventHandler = NullEventHandler<E>;
+    }
+
+    type TestChain = BeaconChain<TestTypes<MinimalEthSpec>>;
+
+    /// Returns a state at `slot` with its latest block, as if the block had been applied to it.
+    fn checkpoint(slot: Slot, spec: &ChainSpec) -> (BeaconState<MinimalEthSpec>, BeaconBlock) {
+        let mut builder =
+            TestingBeaconStateBuilder::from_single_keypair(8, &Keypair::random(), spec);
+        builder.teleport_to_slot(slot);
+        let (mut state, _) = builder.build();
+
+        let mut block = BeaconBlock::empty(spec);
+        block.slot = slot;
+        block.previous_block_root = Hash256::from_slice(&[1; 32]);
+        state.latest_block_header = block.temporary_block_header(spec);
+        block.state_root = state.canonical_root();
+
+        (state, block)
+    }
+
+    fn from_checkpoint(
+        store: Arc<MemoryStore>,
+        state: BeaconState<MinimalEthSpec>,
+        block: BeaconBlock,
+        spec: &ChainSpec,
+    ) -> Result<TestChain, Error> {
+        let slot_clock = TestingSlotClock::new(spec.genesis_slot, state.genesis_time, 6);
+        let fork_choice = OptimizedLMDGhost::new(store.clone());
+        BeaconChain::from_checkpoint(
+            store,
+            slot_clock,
+            state,
+            block,
+            spec.clone(),
+            fork_choice,
+            NullEventHandler::default(),
+        )
+    }
+
+    #[test]
+    fn starts_from_checkpoint() {
+        let spec = MinimalEthSpec::default_spec();
+        let slot = Slot::new(MinimalEthSpec::slots_per_epoch() * 4);
+        let (state, block) = checkpoint(slot, &spec);
+        let block_root = block.block_header().canonical_root();
+        let state_root = block.state_root;
+
+        let store = Arc::new(MemoryStore::open());
+        let chain = from_checkpoint(store.clone(), state, block.clone(), &spec).unwrap();
+
+        assert_eq!(chain.head().beacon_block_root, block_root);
+        assert_eq!(chain.head().beacon_state_root, state_root);
+        assert_eq!(chain.oldest_block(), (slot, block.previous_block_root));
+        assert!(!chain.is_backfill_complete());
+        assert_eq!(store.get::<BeaconBlock>(&block_root).unwrap(), Some(block));
+        assert!(store
+            .get::<BeaconState<MinimalEthSpec>>(&state_root)
+            .unwrap()
+            .is_some());
+
+        // Fork choice knows only the anchor, so it must keep it as the head.
+        chain.fork_choice().unwrap();
+        assert_eq!(chain.head().beacon_block_root, block_root);
+    }
+
+    #[test]
+    fn restores_checkpoint_chain_from_store() {
+        let spec = MinimalEthSpec::default_spec();
+        let slot = Slot::new(MinimalEthSpec::slots_per_epoch() * 4);
+        let (state, block) = checkpoint(slot, &spec);
+        let block_root = block.block_header().canonical_root();
+
+        let store = Arc::new(MemoryStore::open());
+        from_checkpoint(store.clone(), state, block.clone(), &spec)
+            .unwrap()
+            .persist()
+            .unwrap();
+
+        let chain: TestChain = BeaconChain::from_store(store, spec, NullEventHandler::default())
+            .unwrap()
+            .expect("persisted chain should be found");
+
+        assert_eq!(chain.head().beacon_block_root, block_root);
+        assert_eq!(chain.oldest_block(), (slot, block.previous_block_root));
+        chain.fork_choice().unwrap();
+        assert_eq!(chain.head().beacon_block_root, block_root);
+    }
+
+    #[test]
+    fn restores_fork_choice_from_store() {
+        let spec = MinimalEthSpec::default_spec();
+        let slot = Slot::new(MinimalEthSpec::slots_per_epoch() * 4);
+        let (state, block) = checkpoint(slot, &spec);
+        let anchor_root = block.block_header().canonical_root();
+
+        let store = Arc::new(MemoryStore::open());
+        let chain = from_checkpoint(store.clone(), state, block.clone(), &spec).unwrap();
+
+        // Two competing children of the anchor, one with a child of its own.
+        let child = |parent: Hash256, slot: Slot, graffiti: u8| {
+            let mut child = block.clone();
+            child.slot = slot;
+            child.previous_block_root = parent;
+            child.body.graffiti = [graffiti; 32];
+            let root = child.block_header().canonical_root();
+            store.put(&root, &child).unwrap();
+            chain
+                .fork_choice
+                .write()
+                .add_block(&child, &root, &spec)
+                .unwrap();
+            root
+        };
+        let a = child(anchor_root, slot + 1, 1);
+        let b = child(anchor_root, slot + 1, 2);
+        let c = child(a, slot + 2, 3);
+
+        chain.latest_messages.write().update(
+            0,
+            LatestMessage {
+                epoch: slot.epoch(MinimalEthSpec::slots_per_epoch()),
+                root: c,
+            },
+        );
+        chain.persist().unwrap();
+
+        let restored: TestChain =
+            BeaconChain::from_store(store.clone(), spec.clone(), NullEventHandler::default())
+                .unwrap()
+                .unwrap();
+
+        let mut block_roots = restored.fork_choice.read().block_roots();
+        block_roots.sort();
+        let mut expected = vec![a, b, c];
+        expected.sort();
+        assert_eq!(block_roots, expected);
+        assert_eq!(
+            restored.latest_message(0).map(|message| message.root),
+            Some(c)
+        );
+        assert_eq!(restored.latest_message(1), None);
+    }
+
+    #[test]
+    fn removes_branches_conflicting_with_wss_checkpoint() {
+        let spec = MinimalEthSpec::default_spec();
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+        let slot = Slot::new(slots_per_epoch * 4);
+        let (state, block) = checkpoint(slot, &spec);
+        let anchor_root = block.block_header().canonical_root();
+
+        let store = Arc::new(MemoryStore::open());
+        let chain = from_checkpoint(store.clone(), state.clone(), block.clone(), &spec).unwrap();
+
+        // Each block has a state of its own, so that removing one leaves the others.
+        let child = |parent: Hash256, slot: Slot, graffiti: u8| {
+            let mut child = block.clone();
+            child.slot = slot;
+            child.previous_block_root = parent;
+            child.body.graffiti = [graffiti; 32];
+            let mut state = state.clone();
+            state.latest_block_header = child.temporary_block_header(&spec);
+            child.state_root = state.canonical_root();
+            store.put(&child.state_root, &state).unwrap();
+
+            let root = child.block_header().canonical_root();
+            store.put(&root, &child).unwrap();
+            chain
+                .fork_choice
+                .write()
+                .add_block(&child, &root, &spec)
+                .unwrap();
+            root
+        };
+        let vote = |index: usize, root: Hash256| {
+            chain.latest_messages.write().update(
+                index,
+                LatestMessage {
+                    epoch: slot.epoch(slots_per_epoch),
+                    root,
+                },
+            );
+            chain
+                .fork_choice
+                .write()
+                .set_latest_vote(index as u64, &root);
+        };
+
+        // Two blocks at the checkpoint slot, each with a child, and a chain which skips the slot.
+        let checkpoint_slot = slot + slots_per_epoch;
+        let a = child(anchor_root, checkpoint_slot, 1);
+        let b = child(anchor_root, checkpoint_slot, 2);
+        let c = child(a, checkpoint_slot + 1, 3);
+        let d = child(b, checkpoint_slot + 2, 4);
+        let e = child(anchor_root, checkpoint_slot - 1, 5);
+        let f = child(e, checkpoint_slot + 1, 6);
+        vote(0, d);
+        vote(1, d);
+        vote(2, c);
+        chain.fork_choice().unwrap();
+        assert_eq!(chain.head().beacon_block_root, d);
+
+        chain
+            .set_wss_checkpoint(WeakSubjectivityCheckpoint {
+                root: a,
+                epoch: checkpoint_slot.epoch(slots_per_epoch),
+            })
+            .unwrap();
+
+        assert_eq!(chain.head().beacon_block_root, c);
+        let mut block_roots = chain.fork_choice.read().block_roots();
+        block_roots.sort();
+        let mut expected = vec![a, c, e];
+        expected.sort();
+        assert_eq!(block_roots, expected);
+        for root in &[b, d, f] {
+            assert!(!store.exists::<BeaconBlock>(root).unwrap());
+        }
+        assert!(store.exists::<BeaconBlock>(&e).unwrap());
+    }
+
+    #[test]
+    fn finds_blocks_since_anchor() {
+        let spec = MinimalEthSpec::default_spec();
+        let store = MemoryStore::open();
+
+        let mut roots = vec![];
+        let mut parent = Hash256::zero();
+        for slot in 0..4 {
+            let mut block = BeaconBlock::empty(&spec);
+            block.slot = Slot::new(slot);
+            block.previous_block_root = parent;
+            parent = block.block_header().canonical_root();
+            store.put(&parent, &block).unwrap();
+            roots.push(parent);
+        }
+
+        assert_eq!(
+            blocks_since_anchor(&store, roots[3], roots[1]),
+            Ok(roots[2..].to_vec())
+        );
+        assert_eq!(blocks_since_anchor(&store, roots[1], roots[1]), Ok(vec![]));
+    }
+
+    #[test]
+    fn rejects_inconsistent_checkpoint() {
+        let spec = MinimalEthSpec::default_spec();
+        let slot = Slot::new(MinimalEthSpec::slots_per_epoch() * 4);
+
+        let (state, mut block) = checkpoint(slot, &spec);
+        block.state_root = Hash256::from_slice(&[42; 32]);
+        match from_checkpoint(Arc::new(MemoryStore::open()), state, block, &spec) {
+            Err(Error::InvalidCheckpoint(_)) => {}
+            other => panic!("expected InvalidCheckpoint, got {:?}", other.map(|_| ())),
+        }
+
+        let (state, mut block) = checkpoint(slot, &spec);
+        block.slot = slot + 1;
+        match from_checkpoint(Arc::new(MemoryStore::open()), state, block, &spec) {
+            Err(Error::InvalidCheckpoint(_)) => {}
+            other => panic!("expected InvalidCheckpoint, got {:?}", other.map(|_| ())),
+        }
+    }
+}
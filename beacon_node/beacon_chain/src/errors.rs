@@ -1,5 +1,7 @@
 use crate::metrics::Error as MetricsError;
+use crate::WeakSubjectivityCheckpoint;
 use fork_choice::ForkChoiceError;
+use slasher::Error as SlasherError;
 use state_processing::BlockProcessingError;
 use state_processing::SlotProcessingError;
 use types::*;
@@ -27,9 +29,21 @@ pub enum BeaconChainError {
     MissingBeaconState(Hash256),
     SlotProcessingError(SlotProcessingError),
     MetricsError(String),
+    InvalidCheckpoint(String),
+    HistoricalBlockMismatch {
+        expected: Hash256,
+        found: Hash256,
+    },
+    SlasherError(SlasherError),
+    /// The chain has the block root `found` at the epoch of the weak subjectivity `checkpoint`.
+    WeakSubjectivityConflict {
+        checkpoint: WeakSubjectivityCheckpoint,
+        found: Hash256,
+    },
 }
 
 easy_from_to!(SlotProcessingError, BeaconChainError);
+easy_from_to!(SlasherError, BeaconChainError);
 
 impl From<MetricsError> for BeaconChainError {
     fn from(e: MetricsError) -> BeaconChainError {
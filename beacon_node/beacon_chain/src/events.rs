@@ -0,0 +1,62 @@
+use merkle_proof::ProofBundle;
+use serde_derive::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use types::{Epoch, EthSpec, Hash256, Slot};
+
+/// Receives events produced by the `BeaconChain`, such as a change of head or finalization.
+///
+/// Implementations should not block; they are called whilst the chain is processing blocks.
+pub trait EventHandler<T: EthSpec>: Sized + Send + Sync + Clone {
+    fn register(&self, kind: EventKind<T>) -> Result<(), String>;
+}
+
+/// An `EventHandler` which discards all events.
+pub struct NullEventHandler<T: EthSpec>(PhantomData<T>);
+
+impl<T: EthSpec> EventHandler<T> for NullEventHandler<T> {
+    fn register(&self, _kind: EventKind<T>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<T: EthSpec> Clone for NullEventHandler<T> {
+    fn clone(&self) -> Self {
+        NullEventHandler(PhantomData)
+    }
+}
+
+impl<T: EthSpec> Default for NullEventHandler<T> {
+    fn default() -> Self {
+        NullEventHandler(PhantomData)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(
+    bound = "T: EthSpec",
+    rename_all = "snake_case",
+    tag = "event",
+    content = "data"
+)]
+pub enum EventKind<T: EthSpec> {
+    BeaconHeadChanged {
+        reorg: bool,
+        slot: Slot,
+        current_head_beacon_block_root: Hash256,
+        previous_head_beacon_block_root: Hash256,
+    },
+    BeaconFinalization {
+        epoch: Epoch,
+        root: Hash256,
+    },
+    /// A proof of the indices given to `BeaconChain::set_proof_event_indices`, against the state
+    /// of the newly finalized block.
+    ProofBundle {
+        finalized_epoch: Epoch,
+        finalized_root: Hash256,
+        state_root: Hash256,
+        bundle: ProofBundle,
+    },
+    #[serde(skip)]
+    _Phantom(PhantomData<T>),
+}
@@ -0,0 +1,156 @@
+//! Proofs against the states of finalized slots.
+//!
+//! Only the states of blocks are stored, so the state of a skipped slot is rebuilt by replaying
+//! from the nearest stored state before it (see `Store::get_state_at_slot`). The number of slots a
+//! single request may replay is limited, and proofs are cached, since finalized states never
+//! change. An archive node retains the state of every finalized slot, so no replay is needed.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use lru::LruCache;
+use merkle_proof::{metrics, SerializedPartial};
+use parking_lot::Mutex;
+use state_processing::SlotProcessingError;
+use std::sync::Arc;
+use store::Error as DBError;
+use types::*;
+
+/// The number of proofs kept by a `HistoricalProofService`.
+pub const PROOF_CACHE_SIZE: usize = 256;
+/// The default number of slots a single request may replay.
+pub const DEFAULT_MAX_REPLAY_SLOTS: u64 = 64;
+
+#[derive(Debug, PartialEq)]
+pub enum HistoricalProofError {
+    /// Only the states of finalized slots are served.
+    NotFinalized {
+        slot: Slot,
+        finalized_slot: Slot,
+    },
+    /// The block at or before the slot is not known, e.g. as it precedes the oldest block.
+    UnknownSlot(Slot),
+    /// Building the state would replay more than `max` slots.
+    ReplayTooLong {
+        slot: Slot,
+        replay_slots: u64,
+        max: u64,
+    },
+    /// No state is stored at or before the slot to replay from. The root is that of the oldest
+    /// block reached.
+    MissingState(Hash256),
+    /// The state cannot prove the node at this generalized index.
+    UnsupportedIndex(u64),
+    BeaconChainError(BeaconChainError),
+    BeaconStateError(BeaconStateError),
+    SlotProcessingError(SlotProcessingError),
+}
+
+impl From<BeaconChainError> for HistoricalProofError {
+    fn from(e: BeaconChainError) -> HistoricalProofError {
+        match e {
+            BeaconChainError::DBError(DBError::ReplayTooLong {
+                slot,
+                replay_slots,
+                max,
+            }) => HistoricalProofError::ReplayTooLong {
+                slot,
+                replay_slots,
+                max,
+            },
+            BeaconChainError::DBError(DBError::NoRestorePoint(root)) => {
+                HistoricalProofError::MissingState(root)
+            }
+            e => HistoricalProofError::BeaconChainError(e),
+        }
+    }
+}
+
+impl From<BeaconStateError> for HistoricalProofError {
+    fn from(e: BeaconStateError) -> HistoricalProofError {
+        match e {
+            BeaconStateError::UnsupportedProofIndex(index) => {
+                HistoricalProofError::UnsupportedIndex(index)
+            }
+            e => HistoricalProofError::BeaconStateError(e),
+        }
+    }
+}
+
+impl From<SlotProcessingError> for HistoricalProofError {
+    fn from(e: SlotProcessingError) -> HistoricalProofError {
+        HistoricalProofError::SlotProcessingError(e)
+    }
+}
+
+/// A proof against the state at a finalized slot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalProof {
+    pub state_root: Hash256,
+    pub proof: SerializedPartial,
+}
+
+/// Answers proof requests for the states of finalized slots.
+pub struct HistoricalProofService<T: BeaconChainTypes> {
+    beacon_chain: Arc<BeaconChain<T>>,
+    /// The most slots a single request may replay to build a state.
+    max_replay_slots: u64,
+    cache: Mutex<LruCache<(Slot, Vec<u64>), HistoricalProof>>,
+}
+
+impl<T: BeaconChainTypes> HistoricalProofService<T> {
+    pub fn new(beacon_chain: Arc<BeaconChain<T>>, max_replay_slots: u64) -> Self {
+        Self {
+            beacon_chain,
+            max_replay_slots,
+            cache: Mutex::new(LruCache::new(PROOF_CACHE_SIZE)),
+        }
+    }
+
+    /// Returns a proof of the nodes at `indices` of the state at `slot`, which must be finalized.
+    ///
+    /// The state is rebuilt from the nearest stored state before it, unless it is archived.
+    pub fn prove(
+        &self,
+        slot: Slot,
+        indices: &[u64],
+    ) -> Result<HistoricalProof, HistoricalProofError> {
+        let key = (slot, indices.to_vec());
+        if let Some(proof) = self.cache.lock().get(&key) {
+            metrics::record_cache_hit();
+            return Ok(proof.clone());
+        }
+        metrics::record_cache_miss();
+
+        let finalized_slot = self
+            .beacon_chain
+            .head()
+            .beacon_state
+            .finalized_epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        if slot > finalized_slot {
+            return Err(HistoricalProofError::NotFinalized {
+                slot,
+                finalized_slot,
+            });
+        }
+
+        let state = self.state_at_slot(slot)?;
+        let proof = HistoricalProof {
+            state_root: state.canonical_root(),
+            proof: state.prove(indices)?,
+        };
+
+        self.cache.lock().put(key, proof.clone());
+
+        Ok(proof)
+    }
+
+    /// Returns the state at `slot`, replaying blocks and empty slots from the nearest stored
+    /// state before it.
+    fn state_at_slot(
+        &self,
+        slot: Slot,
+    ) -> Result<Arc<BeaconState<T::EthSpec>>, HistoricalProofError> {
+        self.beacon_chain
+            .state_at_slot(slot, self.max_replay_slots)?
+            .ok_or(HistoricalProofError::UnknownSlot(slot))
+    }
+}
@@ -0,0 +1,86 @@
+use types::{Epoch, Hash256};
+
+/// The latest vote of a validator: the block it attested to, and the target epoch of that
+/// attestation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatestMessage {
+    pub epoch: Epoch,
+    pub root: Hash256,
+}
+
+/// The latest message of each validator, by validator index.
+///
+/// Fork choice weighs each validator's vote by its latest message only, so an attestation need
+/// only be passed on if it is newer than the message already known. Checking this is a lookup by
+/// index, rather than a comparison of the heights of the blocks voted for.
+#[derive(Default)]
+pub struct LatestMessages {
+    messages: Vec<Option<LatestMessage>>,
+}
+
+impl LatestMessages {
+    /// Records `message` as the latest of validator `index` if its epoch is later than that of
+    /// the message known, returning `true` if so.
+    pub fn update(&mut self, index: usize, message: LatestMessage) -> bool {
+        if index >= self.messages.len() {
+            self.messages.resize(index + 1, None);
+        }
+
+        if self.messages[index].map_or(false, |latest| latest.epoch >= message.epoch) {
+            return false;
+        }
+
+        self.messages[index] = Some(message);
+        true
+    }
+
+    /// Returns the latest message of validator `index`.
+    pub fn get(&self, index: usize) -> Option<&LatestMessage> {
+        self.messages.get(index).and_then(Option::as_ref)
+    }
+
+    /// Returns each validator index with a latest message, with its message, in ascending order
+    /// of index.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &LatestMessage)> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, message)| message.as_ref().map(|message| (index, message)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(epoch: u64, root: u8) -> LatestMessage {
+        LatestMessage {
+            epoch: Epoch::new(epoch),
+            root: Hash256::from([root; 32]),
+        }
+    }
+
+    #[test]
+    fn keeps_latest_epoch() {
+        let mut messages = LatestMessages::default();
+
+        assert!(messages.update(3, message(2, 1)));
+        assert_eq!(messages.get(3), Some(&message(2, 1)));
+        assert_eq!(messages.get(0), None);
+        assert_eq!(messages.get(4), None);
+
+        // A message of the same or an earlier epoch is ignored.
+        assert!(!messages.update(3, message(2, 2)));
+        assert!(!messages.update(3, message(1, 3)));
+        assert_eq!(messages.get(3), Some(&message(2, 1)));
+
+        assert!(messages.update(3, message(3, 4)));
+        assert_eq!(messages.get(3), Some(&message(3, 4)));
+
+        assert!(messages.update(1, message(1, 5)));
+        assert_eq!(
+            messages.iter().collect::<Vec<_>>(),
+            vec![(1, &message(1, 5)), (3, &message(3, 4))]
+        );
+    }
+}
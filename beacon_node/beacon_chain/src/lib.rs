@@ -1,15 +1,29 @@
 mod beacon_chain;
 mod checkpoint;
 mod errors;
+pub mod events;
+mod historical_proofs;
 pub mod iter;
+mod latest_messages;
 mod metrics;
 mod persisted_beacon_chain;
+mod validator_monitor;
+mod validator_pubkey_cache;
+mod weak_subjectivity;
 
 pub use self::beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
 pub use self::checkpoint::CheckPoint;
 pub use self::errors::{BeaconChainError, BlockProductionError};
+pub use self::historical_proofs::{
+    HistoricalProof, HistoricalProofError, HistoricalProofService, DEFAULT_MAX_REPLAY_SLOTS,
+};
+pub use self::latest_messages::LatestMessage;
+pub use self::validator_monitor::{EpochSummary, ValidatorMonitor, ValidatorPerformance};
+pub use self::validator_pubkey_cache::ValidatorPubkeyCache;
+pub use self::weak_subjectivity::WeakSubjectivityCheckpoint;
 pub use fork_choice;
 pub use parking_lot;
+pub use slasher;
 pub use slot_clock;
 pub use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, DepositValidationError,
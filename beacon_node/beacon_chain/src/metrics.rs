@@ -1,5 +1,7 @@
 pub use prometheus::Error;
-use prometheus::{Histogram, HistogramOpts, IntCounter, Opts, Registry};
+use prometheus::{
+    Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+};
 
 pub struct Metrics {
     pub block_processing_requests: IntCounter,
@@ -19,6 +21,10 @@ pub struct Metrics {
     pub fork_choice_reorg_count: IntCounter,
     pub fork_choice_times: Histogram,
     pub operations_per_block_attestation: Histogram,
+    pub validator_monitor_balance: IntGaugeVec,
+    pub validator_monitor_balance_delta: IntGaugeVec,
+    pub validator_monitor_inclusion_distance: IntGaugeVec,
+    pub validator_monitor_missed_attestations: IntCounterVec,
 }
 
 impl Metrics {
@@ -116,6 +122,34 @@ impl Metrics {
                 );
                 Histogram::with_opts(opts)?
             },
+            validator_monitor_balance: {
+                let opts = Opts::new(
+                    "validator_monitor_balance",
+                    "balance_of_monitored_validator_in_gwei",
+                );
+                IntGaugeVec::new(opts, &["pubkey"])?
+            },
+            validator_monitor_balance_delta: {
+                let opts = Opts::new(
+                    "validator_monitor_balance_delta",
+                    "change_in_balance_of_monitored_validator_over_last_epoch_in_gwei",
+                );
+                IntGaugeVec::new(opts, &["pubkey"])?
+            },
+            validator_monitor_inclusion_distance: {
+                let opts = Opts::new(
+                    "validator_monitor_inclusion_distance",
+                    "latest_attestation_inclusion_distance_of_monitored_validator",
+                );
+                IntGaugeVec::new(opts, &["pubkey"])?
+            },
+            validator_monitor_missed_attestations: {
+                let opts = Opts::new(
+                    "validator_monitor_missed_attestations",
+                    "epochs_without_an_included_attestation_by_monitored_validator",
+                );
+                IntCounterVec::new(opts, &["pubkey"])?
+            },
         })
     }
 
@@ -137,6 +171,10 @@ impl Metrics {
         registry.register(Box::new(self.fork_choice_reorg_count.clone()))?;
         registry.register(Box::new(self.fork_choice_times.clone()))?;
         registry.register(Box::new(self.operations_per_block_attestation.clone()))?;
+        registry.register(Box::new(self.validator_monitor_balance.clone()))?;
+        registry.register(Box::new(self.validator_monitor_balance_delta.clone()))?;
+        registry.register(Box::new(self.validator_monitor_inclusion_distance.clone()))?;
+        registry.register(Box::new(self.validator_monitor_missed_attestations.clone()))?;
 
         Ok(())
     }
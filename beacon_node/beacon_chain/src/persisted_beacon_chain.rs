@@ -1,20 +1,41 @@
 use crate::{BeaconChainTypes, CheckPoint};
+use operation_pool::PersistedOperationPool;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use store::{DBColumn, Error as StoreError, StoreItem};
-use types::{BeaconState, Hash256};
+use types::{BeaconState, Epoch, Hash256, Slot};
 
-/// 32-byte key for accessing the `PersistedBeaconChain`.
-pub const BEACON_CHAIN_DB_KEY: &str = "PERSISTEDBEACONCHAINPERSISTEDBEA";
+pub use store::BEACON_CHAIN_DB_KEY;
 
 #[derive(Encode, Decode)]
 pub struct PersistedBeaconChain<T: BeaconChainTypes> {
     pub canonical_head: CheckPoint<T::EthSpec>,
-    // TODO: operations pool.
+    pub op_pool: PersistedOperationPool,
+    pub fork_choice: PersistedForkChoice,
     pub genesis_block_root: Hash256,
+    pub anchor_block_root: Hash256,
+    pub oldest_block_slot: Slot,
+    pub oldest_block_parent: Hash256,
     pub state: BeaconState<T::EthSpec>,
 }
 
+/// What fork choice knows besides the anchor block, from which it is rebuilt on startup.
+#[derive(Encode, Decode)]
+pub struct PersistedForkChoice {
+    /// The roots of the blocks given to fork choice, each after its parent.
+    pub block_roots: Vec<Hash256>,
+    /// The latest message of each validator which has one.
+    pub latest_messages: Vec<PersistedLatestMessage>,
+}
+
+/// The latest message of the validator at `validator_index`.
+#[derive(Encode, Decode)]
+pub struct PersistedLatestMessage {
+    pub validator_index: u64,
+    pub epoch: Epoch,
+    pub root: Hash256,
+}
+
 impl<T: BeaconChainTypes> StoreItem for PersistedBeaconChain<T> {
     fn db_column() -> DBColumn {
         DBColumn::BeaconChain
@@ -0,0 +1,257 @@
+//! Tracks the performance of a chosen set of validators, epoch by epoch: whether their
+//! attestations were included, how quickly, and how their balances changed.
+use crate::metrics::Metrics;
+use parking_lot::RwLock;
+use serde_derive::Serialize;
+use state_processing::common::get_attesting_indices_unsorted;
+use std::collections::{BTreeMap, HashMap};
+use types::*;
+
+/// The number of past epochs for which summaries are retained.
+const MAX_SUMMARY_EPOCHS: u64 = 64;
+
+/// The performance of a single validator during a single epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EpochSummary {
+    pub epoch: Epoch,
+    /// `true` if an attestation by the validator, targeting `epoch`, has been included.
+    pub attested: bool,
+    /// The fewest slots between any such attestation and the block which included it.
+    pub inclusion_distance: Option<u64>,
+    /// The balance following the epoch transition at the end of `epoch`, in Gwei.
+    pub balance: u64,
+    /// The change in balance over the epoch transition at the end of `epoch`, if the balance
+    /// before it is known.
+    pub balance_delta: Option<i64>,
+}
+
+/// The summaries of a single monitored validator, as served by the HTTP API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidatorPerformance {
+    pub pubkey: PublicKey,
+    /// The index of the validator, if it is in the registry.
+    pub index: Option<usize>,
+    /// The number of epochs, since monitoring began, in which no attestation was included.
+    pub missed_attestations: u64,
+    /// The retained summaries, oldest first.
+    pub epochs: Vec<EpochSummary>,
+}
+
+#[derive(Default)]
+struct MonitoredValidator {
+    index: Option<usize>,
+    missed_attestations: u64,
+    /// The balance at the start of each epoch.
+    balances: BTreeMap<Epoch, u64>,
+    summaries: BTreeMap<Epoch, EpochSummary>,
+    /// Summaries up to and including this epoch can no longer change and have been counted
+    /// towards `missed_attestations`.
+    settled_epoch: Option<Epoch>,
+}
+
+impl MonitoredValidator {
+    /// Counts the summaries prior to `epoch` towards `missed_attestations`, returning the number
+    /// of newly counted misses.
+    fn settle_prior_to(&mut self, epoch: Epoch) -> u64 {
+        let settled_epoch = self.settled_epoch;
+        let missed = self
+            .summaries
+            .range(..epoch)
+            .filter(|(e, _)| settled_epoch.map_or(true, |settled| **e > settled))
+            .filter(|(_, summary)| !summary.attested)
+            .count() as u64;
+
+        if let Some((e, _)) = self.summaries.range(..epoch).next_back() {
+            self.settled_epoch = Some(*e);
+        }
+        self.missed_attestations += missed;
+
+        missed
+    }
+
+    fn prune(&mut self, current_epoch: Epoch) {
+        let oldest = current_epoch.saturating_sub(MAX_SUMMARY_EPOCHS);
+        self.balances = self.balances.split_off(&oldest);
+        self.summaries = self.summaries.split_off(&oldest);
+    }
+}
+
+/// Monitors a set of validators, updated from each new head state.
+#[derive(Default)]
+pub struct ValidatorMonitor {
+    validators: RwLock<HashMap<PublicKey, MonitoredValidator>>,
+}
+
+impl ValidatorMonitor {
+    /// Begins monitoring each of `pubkeys`.
+    pub fn add_validators<I: IntoIterator<Item = PublicKey>>(&self, pubkeys: I) {
+        let mut validators = self.validators.write();
+        for pubkey in pubkeys {
+            validators.entry(pubkey).or_default();
+        }
+    }
+
+    /// Returns the performance of every monitored validator.
+    pub fn performance(&self) -> Vec<ValidatorPerformance> {
+        let mut performance: Vec<ValidatorPerformance> = self
+            .validators
+            .read()
+            .iter()
+            .map(|(pubkey, validator)| ValidatorPerformance {
+                pubkey: pubkey.clone(),
+                index: validator.index,
+                missed_attestations: validator.missed_attestations,
+                epochs: validator.summaries.values().cloned().collect(),
+            })
+            .collect();
+        performance.sort_by_key(|p| p.index);
+
+        performance
+    }
+
+    /// Updates the summaries of the previous epoch of `state`, which must have its caches built.
+    ///
+    /// Attestations for the previous epoch may be included throughout the current epoch, so its
+    /// summaries are recomputed with each new state. Once a state in a later epoch is seen, they
+    /// are settled and any missed attestations are counted.
+    pub fn process_state<E: EthSpec>(
+        &self,
+        state: &BeaconState<E>,
+        metrics: &Metrics,
+    ) -> Result<(), BeaconStateError> {
+        let mut validators = self.validators.write();
+        if validators.is_empty() {
+            return Ok(());
+        }
+
+        let current_epoch = state.current_epoch();
+        let previous_epoch = state.previous_epoch();
+
+        // The smallest inclusion delay of any included attestation, by validator index.
+        let mut inclusion_distances: HashMap<usize, u64> = HashMap::new();
+        for attestation in &state.previous_epoch_attestations {
+            for index in get_attesting_indices_unsorted(
+                state,
+                &attestation.data,
+                &attestation.aggregation_bitfield,
+            )? {
+                let distance = inclusion_distances
+                    .entry(index)
+                    .or_insert(attestation.inclusion_delay);
+                *distance = std::cmp::min(*distance, attestation.inclusion_delay);
+            }
+        }
+
+        for (pubkey, validator) in validators.iter_mut() {
+            let label = pubkey.as_hex_string();
+
+            let index = match state.get_validator_index(pubkey)? {
+                Some(index) => index,
+                None => continue,
+            };
+            validator.index = Some(index);
+
+            let balance = state.balances[index];
+            validator.balances.insert(current_epoch, balance);
+            metrics
+                .validator_monitor_balance
+                .with_label_values(&[&label])
+                .set(balance as i64);
+
+            // There is no epoch prior to genesis to summarize.
+            if previous_epoch == current_epoch {
+                continue;
+            }
+
+            let balance_delta = validator
+                .balances
+                .get(&previous_epoch)
+                .map(|previous_balance| balance as i64 - *previous_balance as i64);
+            let inclusion_distance = inclusion_distances.get(&index).cloned();
+
+            validator.summaries.insert(
+                previous_epoch,
+                EpochSummary {
+                    epoch: previous_epoch,
+                    attested: inclusion_distance.is_some(),
+                    inclusion_distance,
+                    balance,
+                    balance_delta,
+                },
+            );
+
+            if let Some(distance) = inclusion_distance {
+                metrics
+                    .validator_monitor_inclusion_distance
+                    .with_label_values(&[&label])
+                    .set(distance as i64);
+            }
+            if let Some(delta) = balance_delta {
+                metrics
+                    .validator_monitor_balance_delta
+                    .with_label_values(&[&label])
+                    .set(delta);
+            }
+
+            let missed = validator.settle_prior_to(previous_epoch);
+            metrics
+                .validator_monitor_missed_attestations
+                .with_label_values(&[&label])
+                .inc_by(missed as i64);
+
+            validator.prune(current_epoch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(epoch: u64, attested: bool) -> EpochSummary {
+        EpochSummary {
+            epoch: Epoch::new(epoch),
+            attested,
+            inclusion_distance: if attested { Some(1) } else { None },
+            balance: 32_000_000_000,
+            balance_delta: None,
+        }
+    }
+
+    #[test]
+    fn settles_each_epoch_once() {
+        let mut validator = MonitoredValidator::default();
+        for (epoch, attested) in &[(1, true), (2, false), (3, false)] {
+            validator
+                .summaries
+                .insert(Epoch::new(*epoch), summary(*epoch, *attested));
+        }
+
+        // Epoch 3 may still change, so only epoch 2 is counted.
+        assert_eq!(validator.settle_prior_to(Epoch::new(3)), 1);
+        assert_eq!(validator.settle_prior_to(Epoch::new(3)), 0);
+        assert_eq!(validator.settle_prior_to(Epoch::new(4)), 1);
+        assert_eq!(validator.missed_attestations, 2);
+    }
+
+    #[test]
+    fn prunes_old_epochs() {
+        let mut validator = MonitoredValidator::default();
+        for epoch in 0..100 {
+            validator.balances.insert(Epoch::new(epoch), epoch);
+            validator
+                .summaries
+                .insert(Epoch::new(epoch), summary(epoch, true));
+        }
+
+        validator.prune(Epoch::new(100));
+
+        assert_eq!(validator.summaries.len(), MAX_SUMMARY_EPOCHS as usize);
+        assert_eq!(
+            validator.balances.keys().next(),
+            Some(&Epoch::new(100 - MAX_SUMMARY_EPOCHS))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use types::{PublicKey, Validator};
+
+/// Maps validator indices to their decompressed public keys, and back.
+///
+/// A validator's key never changes, and the registry of every fork is a prefix of the registry
+/// of any fork which has processed more deposits, so the cache is only ever extended. Callers
+/// must bound indices by the registry of the state they are working with.
+#[derive(Default)]
+pub struct ValidatorPubkeyCache {
+    pubkeys: Vec<PublicKey>,
+    indices: HashMap<PublicKey, usize>,
+}
+
+impl ValidatorPubkeyCache {
+    /// Adds any validators in `registry` beyond those already known.
+    pub fn import(&mut self, registry: &[Validator]) {
+        for validator in registry.iter().skip(self.pubkeys.len()) {
+            self.indices
+                .insert(validator.pubkey.clone(), self.pubkeys.len());
+            self.pubkeys.push(validator.pubkey.clone());
+        }
+    }
+
+    /// Returns the key of the validator at `index`.
+    pub fn get(&self, index: usize) -> Option<&PublicKey> {
+        self.pubkeys.get(index)
+    }
+
+    /// Returns the index of the validator with `pubkey`.
+    pub fn get_index(&self, pubkey: &PublicKey) -> Option<usize> {
+        self.indices.get(pubkey).cloned()
+    }
+
+    /// The number of validators known.
+    pub fn len(&self) -> usize {
+        self.pubkeys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pubkeys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Keypair;
+
+    fn registry(n: usize) -> Vec<Validator> {
+        (0..n)
+            .map(|_| Validator {
+                pubkey: Keypair::random().pk,
+                ..Validator::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grows_with_registry() {
+        let registry = registry(5);
+        let mut cache = ValidatorPubkeyCache::default();
+
+        cache.import(&registry[..3]);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.get(3), None);
+        assert_eq!(cache.get_index(&registry[4].pubkey), None);
+
+        cache.import(&registry);
+        for (i, validator) in registry.iter().enumerate() {
+            assert_eq!(cache.get(i), Some(&validator.pubkey));
+            assert_eq!(cache.get_index(&validator.pubkey), Some(i));
+        }
+
+        // A shorter registry, from a fork with fewer deposits, does not shrink the cache.
+        cache.import(&registry[..2]);
+        assert_eq!(cache.len(), 5);
+    }
+}
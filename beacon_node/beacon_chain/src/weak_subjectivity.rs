@@ -0,0 +1,94 @@
+use serde_derive::{Deserialize, Serialize};
+use types::{BeaconState, Epoch, EthSpec, Hash256};
+
+/// A trusted block root at the start of an epoch, through which the canonical chain must pass.
+///
+/// A node syncing from far behind cannot tell the canonical chain from a long-range attack with
+/// old keys, so it is instead told which chain to follow by a checkpoint from a trusted source.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeakSubjectivityCheckpoint {
+    pub root: Hash256,
+    pub epoch: Epoch,
+}
+
+impl WeakSubjectivityCheckpoint {
+    /// Checks that the chain of `state`, the post-state of the block at `block_root`, passes
+    /// through the checkpoint, returning the root at the checkpoint epoch of the chain if not.
+    ///
+    /// A chain which has not reached the checkpoint epoch passes, as does one whose block root at
+    /// that epoch is no longer in `state`. Any such chain was checked as it passed the epoch.
+    pub fn check<T: EthSpec>(
+        &self,
+        block_root: Hash256,
+        state: &BeaconState<T>,
+    ) -> Result<(), Hash256> {
+        if state.finalized_epoch == self.epoch && state.finalized_root != self.root {
+            return Err(state.finalized_root);
+        }
+
+        let slot = self.epoch.start_slot(T::slots_per_epoch());
+        let root = if state.slot == slot {
+            block_root
+        } else {
+            match state.get_block_root(slot) {
+                Ok(root) => *root,
+                Err(_) => return Ok(()),
+            }
+        };
+
+        if root == self.root {
+            Ok(())
+        } else {
+            Err(root)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::TestingBeaconStateBuilder;
+    use types::{MinimalEthSpec, Slot};
+
+    #[test]
+    fn checks_the_root_at_the_epoch() {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _) = builder.build();
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+
+        let checkpoint = WeakSubjectivityCheckpoint {
+            root: Hash256::from([1; 32]),
+            epoch: Epoch::new(2),
+        };
+        let other_root = Hash256::from([2; 32]);
+
+        // The epoch has not been reached.
+        state.slot = Slot::new(2 * slots_per_epoch - 1);
+        assert_eq!(checkpoint.check(other_root, &state), Ok(()));
+
+        // The block at the start of the epoch.
+        state.slot = Slot::new(2 * slots_per_epoch);
+        assert_eq!(checkpoint.check(checkpoint.root, &state), Ok(()));
+        assert_eq!(checkpoint.check(other_root, &state), Err(other_root));
+
+        // A later block.
+        state.slot = Slot::new(2 * slots_per_epoch + 3);
+        state
+            .set_block_root(Slot::new(2 * slots_per_epoch), checkpoint.root)
+            .unwrap();
+        assert_eq!(checkpoint.check(other_root, &state), Ok(()));
+        state
+            .set_block_root(Slot::new(2 * slots_per_epoch), other_root)
+            .unwrap();
+        assert_eq!(checkpoint.check(checkpoint.root, &state), Err(other_root));
+
+        // A conflicting finalized checkpoint.
+        state
+            .set_block_root(Slot::new(2 * slots_per_epoch), checkpoint.root)
+            .unwrap();
+        state.finalized_epoch = checkpoint.epoch;
+        state.finalized_root = other_root;
+        assert_eq!(checkpoint.check(checkpoint.root, &state), Err(other_root));
+    }
+}
@@ -1,26 +1,39 @@
 use beacon_chain::{
-    fork_choice::OptimizedLMDGhost, slot_clock::SystemTimeSlotClock, store::Store, BeaconChain,
-    BeaconChainTypes,
+    fork_choice::OptimizedLMDGhost,
+    slot_clock::SystemTimeSlotClock,
+    store::{get_clean_shutdown, set_clean_shutdown, Store},
+    BeaconChain, BeaconChainTypes,
 };
 use fork_choice::ForkChoice;
-use slog::{info, Logger};
+use slog::{crit, info, warn, Logger};
 use slot_clock::SlotClock;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use tree_hash::TreeHash;
-use types::{test_utils::TestingBeaconStateBuilder, BeaconBlock, ChainSpec, EthSpec, Hash256};
+use types::{
+    test_utils::TestingBeaconStateBuilder, BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256,
+};
+use websocket_server::WebSocketSender;
 
 /// The number initial validators when starting the `Minimal`.
-const TESTNET_VALIDATOR_COUNT: usize = 16;
+pub const TESTNET_VALIDATOR_COUNT: usize = 16;
 
 /// Provides a new, initialized `BeaconChain`
 pub trait InitialiseBeaconChain<T: BeaconChainTypes> {
     fn initialise_beacon_chain(
         store: Arc<T::Store>,
         spec: ChainSpec,
+        checkpoint: Option<(BeaconState<T::EthSpec>, BeaconBlock)>,
+        event_handler: T::EventHandler,
         log: Logger,
     ) -> BeaconChain<T> {
-        maybe_load_from_store_for_testnet::<_, T::Store, T::EthSpec>(store, spec, log)
+        maybe_load_from_store_for_testnet::<_, T::Store, T::EthSpec>(
+            store,
+            spec,
+            checkpoint,
+            event_handler,
+            log,
+        )
     }
 }
 
@@ -35,20 +48,52 @@ impl<S: Store, E: EthSpec + Clone> BeaconChainTypes for ClientType<S, E> {
     type SlotClock = SystemTimeSlotClock;
     type ForkChoice = OptimizedLMDGhost<S, E>;
     type EthSpec = E;
+    type EventHandler = WebSocketSender<E>;
 }
 impl<T: Store, E: EthSpec, X: BeaconChainTypes> InitialiseBeaconChain<X> for ClientType<T, E> {}
 
-/// Loads a `BeaconChain` from `store`, if it exists. Otherwise, create a new chain from genesis.
+/// Loads a `BeaconChain` from `store`, if it exists. Otherwise, create a new chain from the
+/// `checkpoint`, if supplied, or from genesis.
+///
+/// If the previous process did not shut down cleanly, a chain loaded from `store` is only used if
+/// its head block and state were written before the process exited.
 fn maybe_load_from_store_for_testnet<T, U: Store, V: EthSpec>(
     store: Arc<U>,
     spec: ChainSpec,
+    checkpoint: Option<(BeaconState<T::EthSpec>, BeaconBlock)>,
+    event_handler: T::EventHandler,
     log: Logger,
 ) -> BeaconChain<T>
 where
     T: BeaconChainTypes<Store = U>,
     T::ForkChoice: ForkChoice<U>,
 {
-    if let Ok(Some(beacon_chain)) = BeaconChain::from_store(store.clone(), spec.clone()) {
+    let clean_shutdown = get_clean_shutdown(&*store).unwrap_or(false);
+    // The flag is only set again once the client has persisted its state during shutdown.
+    if let Err(e) = set_clean_shutdown(&*store, false) {
+        warn!(log, "Unable to clear clean shutdown flag"; "error" => format!("{:?}", e));
+    }
+
+    // A stored chain which cannot be loaded is not replaced, as that would discard it.
+    let from_store = BeaconChain::from_store(store.clone(), spec.clone(), event_handler.clone())
+        .unwrap_or_else(|e| {
+            crit!(log, "Unable to load BeaconChain from store"; "error" => format!("{:?}", e));
+            panic!("Terminate rather than overwrite a stored chain which fails to load")
+        })
+        .filter(|beacon_chain| {
+            if clean_shutdown {
+                return true;
+            }
+
+            warn!(log, "Previous shutdown was unclean, checking stored chain");
+            let head_is_stored = head_is_stored(beacon_chain);
+            if !head_is_stored {
+                warn!(log, "Stored chain head is missing, discarding stored chain");
+            }
+            head_is_stored
+        });
+
+    if let Some(beacon_chain) = from_store {
         info!(
             log,
             "Loaded BeaconChain from store";
@@ -56,7 +101,37 @@ where
             "best_slot" => beacon_chain.best_slot(),
         );
 
+        if checkpoint.is_some() {
+            warn!(log, "Ignoring checkpoint, chain already exists in store");
+        }
+
         beacon_chain
+    } else if let Some((checkpoint_state, checkpoint_block)) = checkpoint {
+        info!(
+            log,
+            "Initializing new BeaconChain from checkpoint";
+            "slot" => checkpoint_block.slot,
+            "state_root" => format!("{}", checkpoint_block.state_root),
+        );
+
+        let slot_clock = T::SlotClock::new(
+            spec.genesis_slot,
+            checkpoint_state.genesis_time,
+            spec.seconds_per_slot,
+        );
+        let fork_choice = T::ForkChoice::new(store.clone());
+
+        //TODO: Handle error correctly
+        BeaconChain::from_checkpoint(
+            store,
+            slot_clock,
+            checkpoint_state,
+            checkpoint_block,
+            spec,
+            fork_choice,
+            event_handler,
+        )
+        .expect("Terminate if beacon chain generation from checkpoint fails")
     } else {
         info!(log, "Initializing new BeaconChain from genesis");
         let state_builder = TestingBeaconStateBuilder::from_default_keypairs_file_if_exists(
@@ -86,7 +161,24 @@ where
             genesis_block,
             spec,
             fork_choice,
+            event_handler,
         )
         .expect("Terminate if beacon chain generation fails")
     }
 }
+
+/// Returns `true` if the head block and state of `beacon_chain` are present in its store.
+fn head_is_stored<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) -> bool {
+    let head = beacon_chain.head();
+
+    let block_stored = beacon_chain
+        .store
+        .exists::<BeaconBlock>(&head.beacon_block_root)
+        .unwrap_or(false);
+    let state_stored = beacon_chain
+        .store
+        .exists::<BeaconState<T::EthSpec>>(&head.beacon_state_root)
+        .unwrap_or(false);
+
+    block_stored && state_stored
+}
@@ -1,9 +1,13 @@
+use beacon_chain::WeakSubjectivityCheckpoint;
 use clap::ArgMatches;
 use http_server::HttpServerConfig;
 use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
+use ssz::Decode;
 use std::fs;
 use std::path::PathBuf;
+use types::{Epoch, Hash256, PublicKey};
+use websocket_server::WebSocketConfig;
 
 /// The core configuration of a Lighthouse beacon node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,9 +15,31 @@ pub struct ClientConfig {
     pub data_dir: PathBuf,
     pub db_type: String,
     db_name: String,
+    /// The codec with which an on-disk database compresses blocks and states, as parsed by
+    /// `store::compression::Compression`.
+    pub db_compression: String,
     pub network: network::NetworkConfig,
     pub rpc: rpc::RPCConfig,
     pub http: HttpServerConfig,
+    pub websocket_server: WebSocketConfig,
+    /// Path or URL of a trusted, finalized SSZ `BeaconState` from which to start the chain.
+    pub checkpoint_state: Option<String>,
+    /// Path or URL of the SSZ `BeaconBlock` matching `checkpoint_state`.
+    pub checkpoint_block: Option<String>,
+    /// A trusted block root and epoch through which the canonical chain must pass.
+    pub wss_checkpoint: Option<WeakSubjectivityCheckpoint>,
+    /// Validators whose performance is tracked by the validator monitor.
+    pub validator_monitor_pubkeys: Vec<PublicKey>,
+    /// If `true`, the state of every finalized slot is retained and indexed by slot.
+    pub archive: bool,
+    /// If `true`, archived states of skipped slots are stored as diffs rather than in full.
+    pub archive_diffs: bool,
+    /// If `true`, verified attestations and blocks are checked for slashable offences, and any
+    /// slashings found are included in produced blocks.
+    pub slasher: bool,
+    slasher_db_name: String,
+    /// The number of epochs of attestations checked for surround votes by the slasher.
+    pub slasher_history_length: u64,
 }
 
 impl Default for ClientConfig {
@@ -22,11 +48,22 @@ impl Default for ClientConfig {
             data_dir: PathBuf::from(".lighthouse"),
             db_type: "disk".to_string(),
             db_name: "chain_db".to_string(),
+            db_compression: "snappy".to_string(),
             // Note: there are no default bootnodes specified.
             // Once bootnodes are established, add them here.
             network: NetworkConfig::new(vec![]),
             rpc: rpc::RPCConfig::default(),
             http: HttpServerConfig::default(),
+            websocket_server: WebSocketConfig::default(),
+            checkpoint_state: None,
+            checkpoint_block: None,
+            wss_checkpoint: None,
+            validator_monitor_pubkeys: vec![],
+            archive: false,
+            archive_diffs: false,
+            slasher: false,
+            slasher_db_name: "slasher_db".to_string(),
+            slasher_history_length: beacon_chain::slasher::DEFAULT_HISTORY_LENGTH,
         }
     }
 }
@@ -38,6 +75,13 @@ impl ClientConfig {
             .and_then(|path| Some(path.join(&self.db_name)))
     }
 
+    /// Returns the path to which the slasher may initialize its on-disk database, which is
+    /// separate to that of the chain.
+    pub fn slasher_db_path(&self) -> Option<PathBuf> {
+        self.data_dir()
+            .and_then(|path| Some(path.join(&self.slasher_db_name)))
+    }
+
     /// Returns the core path for the client.
     pub fn data_dir(&self) -> Option<PathBuf> {
         let path = dirs::home_dir()?.join(&self.data_dir);
@@ -58,10 +102,86 @@ impl ClientConfig {
             self.db_type = dir.to_string();
         }
 
+        if let Some(compression) = args.value_of("db-compression") {
+            self.db_compression = compression.to_string();
+        }
+
+        if let Some(state) = args.value_of("checkpoint-state") {
+            self.checkpoint_state = Some(state.to_string());
+        }
+
+        if let Some(block) = args.value_of("checkpoint-block") {
+            self.checkpoint_block = Some(block.to_string());
+        }
+
+        if let Some(checkpoint) = args.value_of("wss-checkpoint") {
+            self.wss_checkpoint = Some(parse_wss_checkpoint(checkpoint)?);
+        }
+
+        if let Some(pubkeys) = args.value_of("validator-monitor-pubkeys") {
+            self.validator_monitor_pubkeys = pubkeys
+                .split(',')
+                .map(|pubkey| {
+                    hex::decode(pubkey.trim_start_matches("0x"))
+                        .ok()
+                        .and_then(|bytes| PublicKey::from_ssz_bytes(&bytes).ok())
+                        .ok_or("validator-monitor-pubkeys contains an invalid public key")
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        if args.is_present("archive") {
+            self.archive = true;
+        }
+
+        if args.is_present("archive-diffs") {
+            self.archive_diffs = true;
+        }
+
+        if args.is_present("slasher") {
+            self.slasher = true;
+        }
+
+        if let Some(length) = args.value_of("slasher-history-length") {
+            self.slasher_history_length = length
+                .parse()
+                .map_err(|_| "slasher-history-length is not u64")?;
+        }
+
+        if self.archive_diffs && !self.archive {
+            return Err("archive-diffs requires archive");
+        }
+
+        if self.checkpoint_state.is_some() != self.checkpoint_block.is_some() {
+            return Err("checkpoint-state and checkpoint-block must be supplied together");
+        }
+
         self.network.apply_cli_args(args)?;
         self.rpc.apply_cli_args(args)?;
         self.http.apply_cli_args(args)?;
+        self.websocket_server.apply_cli_args(args)?;
 
         Ok(())
     }
 }
+
+/// Parses a weak subjectivity checkpoint of the form `block_root:epoch`, e.g., `0x1a2b...:1024`.
+fn parse_wss_checkpoint(checkpoint: &str) -> Result<WeakSubjectivityCheckpoint, &'static str> {
+    let mut parts = checkpoint.split(':');
+    let (root, epoch) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(root), Some(epoch), None) => (root, epoch),
+        _ => return Err("wss-checkpoint must be of the form block_root:epoch"),
+    };
+
+    let root = hex::decode(root.trim_start_matches("0x"))
+        .ok()
+        .filter(|bytes| bytes.len() == 32)
+        .map(|bytes| Hash256::from_slice(&bytes))
+        .ok_or("wss-checkpoint has an invalid block root")?;
+    let epoch = epoch
+        .parse()
+        .map(Epoch::new)
+        .map_err(|_| "wss-checkpoint epoch is not u64")?;
+
+    Ok(WeakSubjectivityCheckpoint { root, epoch })
+}
@@ -4,25 +4,35 @@ mod beacon_chain_types;
 mod client_config;
 pub mod error;
 pub mod notifier;
+mod weak_subjectivity;
 
+use beacon_chain::slasher::Slasher;
+use beacon_chain::store::{archive::StateStorage, set_clean_shutdown};
 use beacon_chain::BeaconChain;
 use exit_future::Signal;
 use futures::{future::Future, Stream};
 use network::Service as NetworkService;
 use prometheus::Registry;
-use slog::{error, info, o};
+use slog::{error, info, o, warn};
 use slot_clock::SlotClock;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::runtime::TaskExecutor;
 use tokio::timer::Interval;
+use types::EthSpec;
 
 pub use beacon_chain::BeaconChainTypes;
 pub use beacon_chain_types::ClientType;
 pub use beacon_chain_types::InitialiseBeaconChain;
+pub use beacon_chain_types::TESTNET_VALIDATOR_COUNT;
 pub use client_config::ClientConfig;
 pub use eth2_config::Eth2Config;
+pub use websocket_server::WebSocketSender;
+
+/// The state for the next slot is prepared `1 / STATE_ADVANCE_LOOKAHEAD_DIVISOR` of a slot before
+/// that slot starts.
+const STATE_ADVANCE_LOOKAHEAD_DIVISOR: u32 = 4;
 
 /// Main beacon node client service. This provides the connection and initialisation of the clients
 /// sub-services in multiple threads.
@@ -37,6 +47,8 @@ pub struct Client<T: BeaconChainTypes> {
     pub rpc_exit_signal: Option<Signal>,
     /// Signal to terminate the HTTP server.
     pub http_exit_signal: Option<Signal>,
+    /// Signal to terminate the WebSocket server.
+    pub websocket_exit_signal: Option<Signal>,
     /// Signal to terminate the slot timer.
     pub slot_timer_exit_signal: Option<Signal>,
     /// The clients logger.
@@ -47,13 +59,19 @@ pub struct Client<T: BeaconChainTypes> {
 
 impl<T> Client<T>
 where
-    T: BeaconChainTypes + InitialiseBeaconChain<T> + Clone + 'static,
+    T: BeaconChainTypes<EventHandler = WebSocketSender<<T as BeaconChainTypes>::EthSpec>>
+        + InitialiseBeaconChain<T>
+        + Clone
+        + 'static,
 {
     /// Generate an instance of the client. Spawn and link all internal sub-processes.
+    ///
+    /// The slasher is enabled if a `slasher_store`, separate to `store`, is supplied.
     pub fn new(
         client_config: ClientConfig,
         eth2_config: Eth2Config,
         store: T::Store,
+        slasher_store: Option<T::Store>,
         log: slog::Logger,
         executor: &TaskExecutor,
     ) -> error::Result<Self> {
@@ -61,12 +79,90 @@ where
         let store = Arc::new(store);
         let seconds_per_slot = eth2_config.spec.seconds_per_slot;
 
+        // Load the trusted checkpoint, if one was supplied.
+        let checkpoint = match (
+            &client_config.checkpoint_state,
+            &client_config.checkpoint_block,
+        ) {
+            (Some(state), Some(block)) => {
+                info!(log, "Loading checkpoint"; "state" => state, "block" => block);
+                Some(weak_subjectivity::load_checkpoint(state, block)?)
+            }
+            _ => None,
+        };
+
+        // Start the WebSocket server first, so that it may receive events from the chain.
+        let (websocket_sender, websocket_exit_signal) = if client_config.websocket_server.enabled {
+            let (sender, exit_signal) = websocket_server::start_server(
+                &client_config.websocket_server,
+                executor,
+                &log.new(o!("Service" => "WebSocket")),
+            )?;
+            (sender, Some(exit_signal))
+        } else {
+            (WebSocketSender::dummy(), None)
+        };
+
         // Load a `BeaconChain` from the store, or create a new one if it does not exist.
         let beacon_chain = Arc::new(T::initialise_beacon_chain(
             store,
             eth2_config.spec.clone(),
+            checkpoint,
+            websocket_sender,
             log.clone(),
         ));
+        beacon_chain
+            .validator_monitor
+            .add_validators(client_config.validator_monitor_pubkeys.clone());
+
+        if let Some(checkpoint) = client_config.wss_checkpoint {
+            info!(
+                log,
+                "Enforcing weak subjectivity checkpoint";
+                "root" => format!("{}", checkpoint.root),
+                "epoch" => checkpoint.epoch
+            );
+            beacon_chain.set_wss_checkpoint(checkpoint).map_err(|e| {
+                format!(
+                    "The chain in the database conflicts with the weak subjectivity checkpoint, \
+                     so it must be deleted and synced again: {:?}",
+                    e
+                )
+            })?;
+        }
+
+        if client_config.websocket_server.enabled
+            && !client_config.websocket_server.proof_indices.is_empty()
+        {
+            beacon_chain
+                .set_proof_event_indices(client_config.websocket_server.proof_indices.clone())
+                .map_err(|e| format!("Unable to push proofs to WebSocket clients: {:?}", e))?;
+        }
+
+        if client_config.archive {
+            let storage = if client_config.archive_diffs {
+                StateStorage::Diffs
+            } else {
+                StateStorage::Full
+            };
+            info!(log, "Archiving finalized states"; "storage" => format!("{:?}", storage));
+            beacon_chain.enable_archive(storage);
+        }
+
+        let slasher_enabled = slasher_store.is_some();
+        if let Some(slasher_store) = slasher_store {
+            info!(
+                log,
+                "Slasher enabled";
+                "history_length" => client_config.slasher_history_length
+            );
+            beacon_chain.enable_slasher(Arc::new(Slasher::new(
+                slasher_store,
+                client_config.slasher_history_length,
+                T::EthSpec::slots_per_epoch(),
+            )));
+        }
+
         // Registry all beacon chain metrics with the global registry.
         beacon_chain
             .metrics
@@ -155,11 +251,74 @@ where
 
             let chain = beacon_chain.clone();
             let log = log.new(o!("Service" => "SlotTimer"));
+            executor.spawn(
+                exit.clone()
+                    .until(
+                        interval
+                            .for_each(move |_| {
+                                do_state_catchup(&chain, &log);
+
+                                Ok(())
+                            })
+                            .map_err(|_| ()),
+                    )
+                    .map(|_| ()),
+            );
+
+            // Check the attestations and blocks verified during each slot for slashable
+            // offences, half way through the next.
+            if slasher_enabled {
+                let interval = {
+                    let slot_duration = Duration::from_secs(seconds_per_slot);
+                    Interval::new(
+                        Instant::now() + duration_to_next_slot + slot_duration / 2,
+                        slot_duration,
+                    )
+                };
+
+                let chain = beacon_chain.clone();
+                let log = log.new(o!("Service" => "Slasher"));
+                executor.spawn(
+                    exit.clone()
+                        .until(
+                            interval
+                                .for_each(move |_| {
+                                    do_slasher_processing(&chain, &log);
+
+                                    Ok(())
+                                })
+                                .map_err(|_| ()),
+                        )
+                        .map(|_| ()),
+                );
+            }
+
+            // Set up the state advance interval - run `STATE_ADVANCE_LOOKAHEAD` before the start
+            // of each slot, so that block production need not wait for any state transitions.
+            let interval = {
+                let slot_duration = Duration::from_secs(seconds_per_slot);
+                let lookahead = slot_duration / STATE_ADVANCE_LOOKAHEAD_DIVISOR;
+                let first_advance = if duration_to_next_slot > lookahead {
+                    duration_to_next_slot - lookahead
+                } else {
+                    duration_to_next_slot + slot_duration - lookahead
+                };
+                Interval::new(Instant::now() + first_advance, slot_duration)
+            };
+
+            let chain = beacon_chain.clone();
+            let log = log.new(o!("Service" => "StateAdvance"));
             executor.spawn(
                 exit.until(
                     interval
                         .for_each(move |_| {
-                            do_state_catchup(&chain, &log);
+                            if let Err(e) = chain.advance_state_to_next_slot() {
+                                error!(
+                                    log,
+                                    "StateAdvanceFailed";
+                                    "error" => format!("{:?}", e)
+                                );
+                            }
 
                             Ok(())
                         })
@@ -174,6 +333,7 @@ where
             beacon_chain,
             http_exit_signal,
             rpc_exit_signal,
+            websocket_exit_signal,
             slot_timer_exit_signal: Some(slot_timer_exit_signal),
             log,
             network,
@@ -182,11 +342,45 @@ where
     }
 }
 
+impl<T: BeaconChainTypes> Client<T> {
+    /// Returns the beacon chain of the running client.
+    pub fn beacon_chain(&self) -> Arc<BeaconChain<T>> {
+        self.beacon_chain.clone()
+    }
+}
+
 impl<T: BeaconChainTypes> Drop for Client<T> {
+    /// Stops all services, then writes the beacon chain (including the operation pool) to its
+    /// store and marks the store as having been shut down cleanly.
+    ///
+    /// Services are stopped first so that no new blocks or operations are imported whilst the
+    /// chain is being persisted.
     fn drop(&mut self) {
-        // Save the beacon chain to it's store before dropping.
-        let _result = self.beacon_chain.persist();
-        dbg!("Saved BeaconChain to store");
+        info!(self.log, "Stopping services");
+
+        let exit_signals = vec![
+            self.slot_timer_exit_signal.take(),
+            self.rpc_exit_signal.take(),
+            self.http_exit_signal.take(),
+            self.websocket_exit_signal.take(),
+        ];
+        exit_signals.into_iter().flatten().for_each(Signal::fire);
+        self.network.shutdown();
+
+        if let Err(e) = self.beacon_chain.persist() {
+            error!(self.log, "Unable to persist BeaconChain"; "error" => format!("{:?}", e));
+            return;
+        }
+        info!(self.log, "Saved BeaconChain to store");
+
+        match set_clean_shutdown(&*self.beacon_chain.store, true) {
+            Ok(()) => info!(self.log, "Shutdown complete"),
+            Err(e) => error!(
+                self.log,
+                "Unable to mark clean shutdown";
+                "error" => format!("{:?}", e)
+            ),
+        }
     }
 }
 
@@ -217,3 +411,23 @@ fn do_state_catchup<T: BeaconChainTypes>(chain: &Arc<BeaconChain<T>>, log: &slog
         };
     }
 }
+
+fn do_slasher_processing<T: BeaconChainTypes>(chain: &Arc<BeaconChain<T>>, log: &slog::Logger) {
+    match chain.process_slasher_queue() {
+        Ok(slashings) => {
+            if !slashings.is_empty() {
+                warn!(
+                    log,
+                    "Slashable offences detected";
+                    "attester_slashings" => slashings.attester_slashings.len(),
+                    "proposer_slashings" => slashings.proposer_slashings.len(),
+                );
+            }
+        }
+        Err(e) => error!(
+            log,
+            "SlasherFailed";
+            "error" => format!("{:?}", e)
+        ),
+    }
+}
@@ -0,0 +1,90 @@
+//! Loading of a trusted, finalized `BeaconState` and `BeaconBlock` pair from which a beacon node
+//! may start syncing instead of from genesis.
+use ssz::Decode;
+use std::fs::File;
+use std::io::Read;
+use types::{BeaconBlock, BeaconState, EthSpec};
+
+/// Load an SSZ-encoded checkpoint state and block.
+///
+/// Each source is either a path on the local filesystem or a `http://`/`https://` URL.
+pub fn load_checkpoint<E: EthSpec>(
+    state_source: &str,
+    block_source: &str,
+) -> Result<(BeaconState<E>, BeaconBlock), String> {
+    let state = BeaconState::from_ssz_bytes(&read_source(state_source)?)
+        .map_err(|e| format!("Unable to decode checkpoint state: {:?}", e))?;
+    let block = BeaconBlock::from_ssz_bytes(&read_source(block_source)?)
+        .map_err(|e| format!("Unable to decode checkpoint block: {:?}", e))?;
+
+    Ok((state, block))
+}
+
+/// Read all bytes from either a URL or a file.
+fn read_source(source: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+
+    if source.starts_with("http://") || source.starts_with("https://") {
+        reqwest::get(source)
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.copy_to(&mut bytes))
+            .map_err(|e| format!("Unable to download {}: {:?}", source, e))?;
+    } else {
+        File::open(source)
+            .and_then(|mut file| file.read_to_end(&mut bytes))
+            .map_err(|e| format!("Unable to read {}: {:?}", source, e))?;
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::Encode;
+    use std::fs;
+    use tempfile::TempDir;
+    use types::test_utils::TestingBeaconStateBuilder;
+    use types::{Keypair, MinimalEthSpec};
+
+    fn write(dir: &TempDir, name: &str, bytes: &[u8]) -> String {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn loads_checkpoint_from_files() {
+        let spec = MinimalEthSpec::default_spec();
+        let (state, _) = TestingBeaconStateBuilder::<MinimalEthSpec>::from_single_keypair(
+            1,
+            &Keypair::random(),
+            &spec,
+        )
+        .build();
+        let block = BeaconBlock::empty(&spec);
+
+        let dir = TempDir::new().unwrap();
+        let state_path = write(&dir, "state.ssz", &state.as_ssz_bytes());
+        let block_path = write(&dir, "block.ssz", &block.as_ssz_bytes());
+
+        let (loaded_state, loaded_block) =
+            load_checkpoint::<MinimalEthSpec>(&state_path, &block_path).unwrap();
+        assert_eq!(loaded_state, state);
+        assert_eq!(loaded_block, block);
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_sources() {
+        let spec = MinimalEthSpec::default_spec();
+        let dir = TempDir::new().unwrap();
+        let block_path = write(&dir, "block.ssz", &BeaconBlock::empty(&spec).as_ssz_bytes());
+        let invalid_path = write(&dir, "invalid.ssz", &[1, 2, 3]);
+        let missing_path = dir.path().join("missing.ssz");
+
+        assert!(
+            load_checkpoint::<MinimalEthSpec>(missing_path.to_str().unwrap(), &block_path).is_err()
+        );
+        assert!(load_checkpoint::<MinimalEthSpec>(&invalid_path, &block_path).is_err());
+    }
+}
@@ -1,4 +1,8 @@
+use crate::error;
+use crate::gossip::{GossipCodec, PubsubMessage};
 use crate::rpc::{RPCEvent, RPCMessage, Rpc};
+use crate::static_peers::StaticPeers;
+use crate::transport::select_dial_addresses;
 use crate::NetworkConfig;
 use futures::prelude::*;
 use libp2p::{
@@ -13,9 +17,8 @@ use libp2p::{
     NetworkBehaviour, PeerId,
 };
 use slog::{debug, o, trace, warn};
-use ssz::{ssz_encode, Decode, DecodeError, Encode};
-use types::{Attestation, BeaconBlock};
-use types::{Topic, TopicHash};
+use types::{ForkDigest, Topic, TopicHash};
+use void::Void;
 
 /// Builds the network behaviour for the libp2p Swarm.
 /// Implements gossipsub message routing.
@@ -33,6 +36,11 @@ pub struct Behaviour<TSubstream: AsyncRead + AsyncWrite> {
     // TODO: Keepalive, likely remove this later.
     // TODO: Make the ping time customizeable.
     ping: Ping<TSubstream>,
+    /// Keeps connections open to the configured static peers.
+    static_peers: StaticPeers<TSubstream>,
+    /// Encodes and decodes gossip on the topics of the local fork.
+    #[behaviour(ignore)]
+    gossip_codec: GossipCodec,
     #[behaviour(ignore)]
     events: Vec<BehaviourEvent>,
     /// Logger for behaviour actions.
@@ -49,7 +57,7 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<GossipsubE
             GossipsubEvent::Message(gs_msg) => {
                 trace!(self.log, "Received GossipEvent"; "msg" => format!("{:?}", gs_msg));
 
-                let pubsub_message = match PubsubMessage::from_ssz_bytes(&gs_msg.data) {
+                let pubsub_message = match self.gossip_codec.decode(&gs_msg.topics, &gs_msg.data) {
                     //TODO: Punish peer on error
                     Err(e) => {
                         warn!(
@@ -121,24 +129,47 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<PingEvent>
     }
 }
 
+impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<Void>
+    for Behaviour<TSubstream>
+{
+    fn inject_event(&mut self, event: Void) {
+        void::unreachable(event)
+    }
+}
+
 impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
-    pub fn new(local_public_key: PublicKey, net_conf: &NetworkConfig, log: &slog::Logger) -> Self {
+    pub fn new(
+        local_public_key: PublicKey,
+        net_conf: &NetworkConfig,
+        fork_digest: ForkDigest,
+        log: &slog::Logger,
+    ) -> error::Result<Self> {
         let local_peer_id = local_public_key.clone().into_peer_id();
         let identify_config = net_conf.identify_config.clone();
         let behaviour_log = log.new(o!());
 
-        Behaviour {
+        let static_peers = select_dial_addresses(
+            net_conf
+                .libp2p_addresses()
+                .map_err(|e| format!("Invalid static peer multiaddr: {:?}", e))?,
+            &net_conf.transport_preference,
+        );
+        let trusted_peers = net_conf.trusted_peers()?;
+
+        Ok(Behaviour {
             gossipsub: Gossipsub::new(local_peer_id, net_conf.gs_config.clone()),
-            serenity_rpc: Rpc::new(log),
+            serenity_rpc: Rpc::new(trusted_peers, log),
             identify: Identify::new(
                 identify_config.version,
                 identify_config.user_agent,
                 local_public_key,
             ),
             ping: Ping::new(),
+            static_peers: StaticPeers::new(static_peers, log),
+            gossip_codec: GossipCodec::new(fork_digest),
             events: Vec::new(),
             log: behaviour_log,
-        }
+        })
     }
 
     /// Consumes the events list when polled.
@@ -165,12 +196,11 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
         self.serenity_rpc.send_rpc(peer_id, rpc_event);
     }
 
-    /// Publishes a message on the pubsub (gossipsub) behaviour.
-    pub fn publish(&mut self, topics: Vec<Topic>, message: PubsubMessage) {
-        let message_bytes = ssz_encode(&message);
-        for topic in topics {
-            self.gossipsub.publish(topic, message_bytes.clone());
-        }
+    /// Publishes a message on the pubsub (gossipsub) behaviour, on the topic of its kind.
+    pub fn publish(&mut self, message: PubsubMessage) {
+        let topic = self.gossip_codec.topic(&message);
+        let message_bytes = self.gossip_codec.encode(&message);
+        self.gossipsub.publish(topic, message_bytes);
     }
 }
 
@@ -186,87 +216,3 @@ pub enum BehaviourEvent {
         message: Box<PubsubMessage>,
     },
 }
-
-/// Messages that are passed to and from the pubsub (Gossipsub) behaviour.
-#[derive(Debug, Clone, PartialEq)]
-pub enum PubsubMessage {
-    /// Gossipsub message providing notification of a new block.
-    Block(BeaconBlock),
-    /// Gossipsub message providing notification of a new attestation.
-    Attestation(Attestation),
-}
-
-//TODO: Correctly encode/decode enums. Prefixing with integer for now.
-impl Encode for PubsubMessage {
-    fn is_ssz_fixed_len() -> bool {
-        false
-    }
-
-    fn ssz_append(&self, buf: &mut Vec<u8>) {
-        let offset = <u32 as Encode>::ssz_fixed_len() + <Vec<u8> as Encode>::ssz_fixed_len();
-
-        let mut encoder = ssz::SszEncoder::container(buf, offset);
-
-        match self {
-            PubsubMessage::Block(block_gossip) => {
-                encoder.append(&0_u32);
-
-                // Encode the gossip as a Vec<u8>;
-                encoder.append(&block_gossip.as_ssz_bytes());
-            }
-            PubsubMessage::Attestation(attestation_gossip) => {
-                encoder.append(&1_u32);
-
-                // Encode the gossip as a Vec<u8>;
-                encoder.append(&attestation_gossip.as_ssz_bytes());
-            }
-        }
-
-        encoder.finalize();
-    }
-}
-
-impl Decode for PubsubMessage {
-    fn is_ssz_fixed_len() -> bool {
-        false
-    }
-
-    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
-        let mut builder = ssz::SszDecoderBuilder::new(&bytes);
-
-        builder.register_type::<u32>()?;
-        builder.register_type::<Vec<u8>>()?;
-
-        let mut decoder = builder.build()?;
-
-        let id: u32 = decoder.decode_next()?;
-        let body: Vec<u8> = decoder.decode_next()?;
-
-        match id {
-            0 => Ok(PubsubMessage::Block(BeaconBlock::from_ssz_bytes(&body)?)),
-            1 => Ok(PubsubMessage::Attestation(Attestation::from_ssz_bytes(
-                &body,
-            )?)),
-            _ => Err(DecodeError::BytesInvalid(
-                "Invalid PubsubMessage id".to_string(),
-            )),
-        }
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use types::*;
-
-    #[test]
-    fn ssz_encoding() {
-        let original = PubsubMessage::Block(BeaconBlock::empty(&MainnetEthSpec::default_spec()));
-
-        let encoded = ssz_encode(&original);
-
-        let decoded = PubsubMessage::from_ssz_bytes(&encoded).unwrap();
-
-        assert_eq!(original, decoded);
-    }
-}
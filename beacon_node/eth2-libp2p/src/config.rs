@@ -1,6 +1,12 @@
+use crate::gossip::MAX_GOSSIP_SIZE;
+use crate::topics::GossipKind;
+use crate::transport::{self, TransportKind};
 use clap::ArgMatches;
 use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder};
+use libp2p::PeerId;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
 use types::multiaddr::{Error as MultiaddrError, Multiaddr};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -17,10 +23,22 @@ pub struct Config {
     pub identify_config: IdentifyConfig,
     /// List of nodes to initially connect to.
     boot_nodes: Vec<String>,
+    /// List of nodes to stay connected to, which are redialed whenever they disconnect.
+    libp2p_addresses: Vec<String>,
+    /// List of peer ids which are exempt from rate limiting.
+    trusted_peers: Vec<String>,
     /// Client version
     pub client_version: String,
-    /// List of topics to subscribe to as strings
-    pub topics: Vec<String>,
+    /// The kinds of gossiped message to subscribe to, on the topics of the local fork.
+    pub topics: Vec<GossipKind>,
+    /// The transports over which to dial a peer with several addresses, most preferred first.
+    pub transport_preference: Vec<TransportKind>,
+    /// Also listen on `/ip6/::` wherever `/ip4/0.0.0.0` is a listen address.
+    pub dual_stack: bool,
+    /// Map the TCP listen ports on the local router via UPnP, or NAT-PMP if UPnP is unavailable.
+    pub port_mapping: bool,
+    /// The router to request NAT-PMP mappings from, if it is not found via UPnP.
+    pub nat_pmp_gateway: Option<Ipv4Addr>,
 }
 
 impl Default for Config {
@@ -29,12 +47,18 @@ impl Default for Config {
         Config {
             listen_addresses: vec!["/ip4/127.0.0.1/tcp/9000".to_string()],
             gs_config: GossipsubConfigBuilder::new()
-                .max_gossip_size(4_000_000)
+                .max_gossip_size(MAX_GOSSIP_SIZE)
                 .build(),
             identify_config: IdentifyConfig::default(),
             boot_nodes: vec![],
+            libp2p_addresses: vec![],
+            trusted_peers: vec![],
             client_version: version::version(),
-            topics: vec![String::from("beacon_chain")],
+            topics: GossipKind::all(),
+            transport_preference: transport::default_preference(),
+            dual_stack: false,
+            port_mapping: false,
+            nat_pmp_gateway: None,
         }
     }
 }
@@ -51,10 +75,39 @@ impl Config {
         self.listen_addresses.iter().map(|s| s.parse()).collect()
     }
 
+    /// Returns the addresses to listen on, including the IPv6 counterparts of unspecified IPv4
+    /// addresses if `dual_stack` is set.
+    pub fn listen_multiaddrs(&self) -> Result<Vec<Multiaddr>, MultiaddrError> {
+        let addresses = self.listen_addresses()?;
+        if self.dual_stack {
+            Ok(transport::with_dual_stack(addresses))
+        } else {
+            Ok(addresses)
+        }
+    }
+
     pub fn boot_nodes(&self) -> Result<Vec<Multiaddr>, MultiaddrError> {
         self.boot_nodes.iter().map(|s| s.parse()).collect()
     }
 
+    pub fn libp2p_addresses(&self) -> Result<Vec<Multiaddr>, MultiaddrError> {
+        self.libp2p_addresses.iter().map(|s| s.parse()).collect()
+    }
+
+    pub fn trusted_peers(&self) -> Result<HashSet<PeerId>, String> {
+        self.trusted_peers
+            .iter()
+            .map(|s| {
+                s.parse()
+                    .map_err(|e| format!("Invalid trusted peer id {}: {:?}", s, e))
+            })
+            .collect()
+    }
+
+    pub fn set_listen_addresses(&mut self, listen_addresses: Vec<String>) {
+        self.listen_addresses = listen_addresses;
+    }
+
     pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), &'static str> {
         if let Some(listen_address_str) = args.value_of("listen-address") {
             let listen_addresses = listen_address_str.split(',').map(Into::into).collect();
@@ -66,6 +119,45 @@ impl Config {
             self.boot_nodes = boot_addresses;
         }
 
+        if let Some(libp2p_addresses_str) = args.value_of("libp2p-addresses") {
+            let libp2p_addresses = libp2p_addresses_str.split(',').map(Into::into).collect();
+            self.libp2p_addresses = libp2p_addresses;
+        }
+
+        if let Some(trusted_peers_str) = args.value_of("trusted-peers") {
+            let trusted_peers = trusted_peers_str.split(',').map(Into::into).collect();
+            self.trusted_peers = trusted_peers;
+        }
+
+        if let Some(preference_str) = args.value_of("transport-preference") {
+            self.transport_preference = preference_str
+                .split(',')
+                .map(|name| {
+                    TransportKind::from_name(name)
+                        .ok_or("transport-preference contains an unknown transport")
+                })
+                .collect::<Result<_, _>>()?;
+            if !self.transport_preference.iter().all(|t| t.is_supported()) {
+                return Err("transport-preference contains quic, which is not supported yet");
+            }
+        }
+
+        if args.is_present("dual-stack") {
+            self.dual_stack = true;
+        }
+
+        if args.is_present("port-mapping") {
+            self.port_mapping = true;
+        }
+
+        if let Some(gateway_str) = args.value_of("nat-pmp-gateway") {
+            self.nat_pmp_gateway = Some(
+                gateway_str
+                    .parse()
+                    .map_err(|_| "nat-pmp-gateway is not an IPv4 address")?,
+            );
+        }
+
         Ok(())
     }
 }
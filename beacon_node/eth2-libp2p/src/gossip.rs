@@ -0,0 +1,188 @@
+//! The encoding of gossiped messages, whose type is given by the topic on which they are
+//! published rather than by the message itself.
+//!
+//! A message is the snappy-compressed SSZ bytes of its container, published on the topic of its
+//! kind on the local fork. Messages on the topics of other forks are rejected before they are
+//! decompressed.
+use crate::topics::{GossipKind, GossipTopic};
+use ssz::{Decode, Encode};
+use std::collections::HashMap;
+use types::{Attestation, BeaconBlock, ForkDigest, Topic, TopicHash};
+
+/// The maximum size of a gossiped message, both as published and once decompressed.
+pub const MAX_GOSSIP_SIZE: usize = 4_000_000;
+
+/// Messages that are passed to and from the pubsub (Gossipsub) behaviour.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PubsubMessage {
+    /// Gossipsub message providing notification of a new block.
+    Block(BeaconBlock),
+    /// Gossipsub message providing notification of a new attestation.
+    Attestation(Attestation),
+}
+
+impl PubsubMessage {
+    /// Returns the kind of the message, which determines the topic on which it is published.
+    pub fn kind(&self) -> GossipKind {
+        match self {
+            PubsubMessage::Block(_) => GossipKind::BeaconBlock,
+            PubsubMessage::Attestation(_) => GossipKind::BeaconAttestation,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GossipDecodeError {
+    /// None of the topics of the message is known, e.g., as it was published on another fork.
+    UnknownTopic(Vec<TopicHash>),
+    /// The message would decompress to more than `MAX_GOSSIP_SIZE` bytes.
+    PayloadTooLarge,
+    SnappyError(snap::Error),
+    SSZDecodeError(ssz::DecodeError),
+}
+
+impl From<snap::Error> for GossipDecodeError {
+    fn from(e: snap::Error) -> GossipDecodeError {
+        GossipDecodeError::SnappyError(e)
+    }
+}
+
+impl From<ssz::DecodeError> for GossipDecodeError {
+    fn from(e: ssz::DecodeError) -> GossipDecodeError {
+        GossipDecodeError::SSZDecodeError(e)
+    }
+}
+
+/// Encodes messages for, and decodes messages from, the gossip topics of a single fork.
+pub struct GossipCodec {
+    fork_digest: ForkDigest,
+    /// The topic of each kind of message on the fork, by the hash with which messages arrive.
+    topics: HashMap<TopicHash, GossipTopic>,
+}
+
+impl GossipCodec {
+    /// Returns a codec for the fork identified by `fork_digest`.
+    pub fn new(fork_digest: ForkDigest) -> Self {
+        let topics = GossipKind::all()
+            .into_iter()
+            .map(|kind| {
+                let topic = GossipTopic::new(fork_digest, kind);
+                (topic.topic().hash().clone(), topic)
+            })
+            .collect();
+
+        GossipCodec {
+            fork_digest,
+            topics,
+        }
+    }
+
+    /// Returns the topic on which `message` is published.
+    pub fn topic(&self, message: &PubsubMessage) -> Topic {
+        GossipTopic::new(self.fork_digest, message.kind()).topic()
+    }
+
+    /// Encodes `message` for publishing on its topic.
+    pub fn encode(&self, message: &PubsubMessage) -> Vec<u8> {
+        let bytes = match message {
+            PubsubMessage::Block(block) => block.as_ssz_bytes(),
+            PubsubMessage::Attestation(attestation) => attestation.as_ssz_bytes(),
+        };
+
+        snap::Encoder::new()
+            .compress_vec(&bytes)
+            .expect("the input is below the maximum snappy block size")
+    }
+
+    /// Decodes a message received on `topics`, as the container given by the first of them which
+    /// is a topic of this fork.
+    pub fn decode(
+        &self,
+        topics: &[TopicHash],
+        data: &[u8],
+    ) -> Result<PubsubMessage, GossipDecodeError> {
+        let topic = topics
+            .iter()
+            .find_map(|hash| self.topics.get(hash))
+            .ok_or_else(|| GossipDecodeError::UnknownTopic(topics.to_vec()))?;
+
+        if snap::decompress_len(data)? > MAX_GOSSIP_SIZE {
+            return Err(GossipDecodeError::PayloadTooLarge);
+        }
+        let bytes = snap::Decoder::new().decompress_vec(data)?;
+
+        match topic.kind {
+            GossipKind::BeaconBlock => {
+                Ok(PubsubMessage::Block(BeaconBlock::from_ssz_bytes(&bytes)?))
+            }
+            GossipKind::BeaconAttestation => Ok(PubsubMessage::Attestation(
+                Attestation::from_ssz_bytes(&bytes)?,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::*;
+
+    fn block_message() -> PubsubMessage {
+        PubsubMessage::Block(BeaconBlock::empty(&MainnetEthSpec::default_spec()))
+    }
+
+    #[test]
+    fn round_trip_on_the_topic_of_the_kind() {
+        let codec = GossipCodec::new([1, 2, 3, 4]);
+        let message = block_message();
+
+        let topic = codec.topic(&message);
+        assert_eq!(
+            topic.hash(),
+            GossipTopic::new([1, 2, 3, 4], GossipKind::BeaconBlock)
+                .topic()
+                .hash()
+        );
+
+        let data = codec.encode(&message);
+        assert_eq!(
+            codec.decode(&[topic.hash().clone()], &data).unwrap(),
+            message
+        );
+
+        // The container is given by the topic, so a block does not decode as an attestation.
+        let attestation_topic =
+            GossipTopic::new([1, 2, 3, 4], GossipKind::BeaconAttestation).topic();
+        match codec.decode(&[attestation_topic.hash().clone()], &data) {
+            Err(GossipDecodeError::SSZDecodeError(_)) => {}
+            other => panic!("expected SSZDecodeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_other_forks() {
+        let message = block_message();
+        let other_codec = GossipCodec::new([0; 4]);
+        let topic = other_codec.topic(&message);
+        let data = other_codec.encode(&message);
+
+        match GossipCodec::new([1, 2, 3, 4]).decode(&[topic.hash().clone()], &data) {
+            Err(GossipDecodeError::UnknownTopic(_)) => {}
+            other => panic!("expected UnknownTopic, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_payload() {
+        let codec = GossipCodec::new([0; 4]);
+        let topic = codec.topic(&block_message());
+        let data = snap::Encoder::new()
+            .compress_vec(&vec![0; MAX_GOSSIP_SIZE + 1])
+            .unwrap();
+
+        match codec.decode(&[topic.hash().clone()], &data) {
+            Err(GossipDecodeError::PayloadTooLarge) => {}
+            other => panic!("expected PayloadTooLarge, got {:?}", other),
+        }
+    }
+}
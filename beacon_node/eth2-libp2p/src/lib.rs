@@ -5,11 +5,15 @@
 pub mod behaviour;
 mod config;
 pub mod error;
+pub mod gossip;
 pub mod rpc;
 mod service;
+mod static_peers;
+pub mod topics;
+pub mod transport;
 
-pub use behaviour::PubsubMessage;
 pub use config::Config as NetworkConfig;
+pub use gossip::PubsubMessage;
 pub use libp2p::{
     gossipsub::{GossipsubConfig, GossipsubConfigBuilder},
     PeerId,
@@ -17,5 +21,7 @@ pub use libp2p::{
 pub use rpc::RPCEvent;
 pub use service::Libp2pEvent;
 pub use service::Service;
+pub use topics::{GossipKind, GossipTopic};
+pub use transport::TransportKind;
 pub use types::multiaddr;
 pub use types::Multiaddr;
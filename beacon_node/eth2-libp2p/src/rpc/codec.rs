@@ -0,0 +1,119 @@
+//! The encodings which may be applied to the SSZ bytes of an `RPCEvent`, as negotiated by the
+//! protocol id.
+use super::protocol::DecodeError;
+
+/// The protocol id for SSZ bytes compressed with snappy. This is preferred over `PROTOCOL_SSZ`.
+pub const PROTOCOL_SSZ_SNAPPY: &[u8] = b"/eth/serenity/rpc/1.0.0/ssz_snappy";
+/// The protocol id for uncompressed SSZ bytes.
+pub const PROTOCOL_SSZ: &[u8] = b"/eth/serenity/rpc/1.0.0";
+
+/// All supported protocol ids, most preferred first.
+pub const SUPPORTED_PROTOCOLS: &[&[u8]] = &[PROTOCOL_SSZ_SNAPPY, PROTOCOL_SSZ];
+
+/// The maximum size of a decoded payload, checked before any decompression takes place.
+pub const MAX_PAYLOAD_SIZE: usize = 4_194_304; // 4M
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    SSZ,
+    SSZSnappy,
+}
+
+impl Encoding {
+    /// Returns the encoding for the negotiated `protocol` id.
+    pub fn from_protocol(protocol: &[u8]) -> Self {
+        if protocol == PROTOCOL_SSZ_SNAPPY {
+            Encoding::SSZSnappy
+        } else {
+            Encoding::SSZ
+        }
+    }
+
+    /// Returns the maximum size of a received packet, i.e., of `MAX_PAYLOAD_SIZE` bytes once
+    /// encoded. Snappy may expand an incompressible payload, so its packets may exceed
+    /// `MAX_PAYLOAD_SIZE`; their decompressed size is checked by `Self::decode`.
+    pub fn max_packet_size(self) -> usize {
+        match self {
+            Encoding::SSZ => MAX_PAYLOAD_SIZE,
+            Encoding::SSZSnappy => snap::max_compress_len(MAX_PAYLOAD_SIZE),
+        }
+    }
+
+    /// Encodes the SSZ `bytes` of a message for sending.
+    pub fn encode(self, bytes: Vec<u8>) -> Vec<u8> {
+        match self {
+            Encoding::SSZ => bytes,
+            Encoding::SSZSnappy => snap::Encoder::new()
+                .compress_vec(&bytes)
+                .expect("the input is below the maximum snappy block size"),
+        }
+    }
+
+    /// Decodes a received `packet` into SSZ bytes.
+    ///
+    /// Snappy payloads carry a length prefix with their decompressed size, which is checked against
+    /// `MAX_PAYLOAD_SIZE` so that a small packet cannot decompress into an arbitrarily large buffer.
+    pub fn decode(self, packet: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            Encoding::SSZ => Ok(packet),
+            Encoding::SSZSnappy => {
+                if snap::decompress_len(&packet)? > MAX_PAYLOAD_SIZE {
+                    return Err(DecodeError::PayloadTooLarge);
+                }
+                Ok(snap::Decoder::new().decompress_vec(&packet)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snappy_round_trip() {
+        let bytes = vec![42; 1024];
+
+        let encoded = Encoding::SSZSnappy.encode(bytes.clone());
+        assert!(encoded.len() < bytes.len());
+        assert_eq!(Encoding::SSZSnappy.decode(encoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn snappy_round_trips_incompressible_payload() {
+        // Bytes from a xorshift generator, which snappy cannot compress.
+        let mut x: u64 = 0x2545_f491_4f6c_dd1d;
+        let bytes: Vec<u8> = (0..MAX_PAYLOAD_SIZE)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+
+        let encoded = Encoding::SSZSnappy.encode(bytes.clone());
+        assert!(encoded.len() > MAX_PAYLOAD_SIZE);
+        assert!(encoded.len() <= Encoding::SSZSnappy.max_packet_size());
+        assert_eq!(Encoding::SSZSnappy.decode(encoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn snappy_rejects_oversized_payload() {
+        let encoded = Encoding::SSZSnappy.encode(vec![0; MAX_PAYLOAD_SIZE + 1]);
+
+        match Encoding::SSZSnappy.decode(encoded) {
+            Err(DecodeError::PayloadTooLarge) => {}
+            other => panic!("expected PayloadTooLarge, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encoding_follows_protocol() {
+        assert_eq!(
+            Encoding::from_protocol(PROTOCOL_SSZ_SNAPPY),
+            Encoding::SSZSnappy
+        );
+        assert_eq!(Encoding::from_protocol(PROTOCOL_SSZ), Encoding::SSZ);
+    }
+}
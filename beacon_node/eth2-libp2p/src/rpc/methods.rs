@@ -1,8 +1,9 @@
 //!Available RPC methods types and ids.
 
+pub use lightclient_protocol::{ProofPush, ProofSubscription};
 use ssz::{impl_decode_via_from, impl_encode_via_from};
 use ssz_derive::{Decode, Encode};
-use types::{BeaconBlockBody, BeaconBlockHeader, Epoch, Hash256, Slot};
+use types::{BeaconBlockBody, BeaconBlockHeader, Bitfield, Epoch, ForkDigest, Hash256, Slot};
 
 #[derive(Debug)]
 /// Available Serenity Libp2p RPC methods
@@ -11,6 +12,10 @@ pub enum RPCMethod {
     Hello,
     /// Terminate a connection providing a reason.
     Goodbye,
+    /// Exchange `MetaData` sequence numbers, to learn when a peer's `MetaData` has changed.
+    Ping,
+    /// Requests a peer's `MetaData`.
+    MetaData,
     /// Requests a number of beacon block roots.
     BeaconBlockRoots,
     /// Requests a number of beacon block headers.
@@ -19,6 +24,13 @@ pub enum RPCMethod {
     BeaconBlockBodies,
     /// Requests values for a merkle proof for the current blocks state root.
     BeaconChainState, // Note: experimental, not complete.
+    /// Subscribes to proofs against the state of each newly finalized block. The response echoes
+    /// the accepted subscription.
+    ProofSubscription,
+    /// Pushes a proof to a subscriber when finality advances. It has no response.
+    ProofPush,
+    /// An error response to any request.
+    Error,
     /// Unknown method received.
     Unknown,
 }
@@ -28,10 +40,15 @@ impl From<u16> for RPCMethod {
         match method_id {
             0 => RPCMethod::Hello,
             1 => RPCMethod::Goodbye,
+            2 => RPCMethod::Ping,
+            3 => RPCMethod::MetaData,
             10 => RPCMethod::BeaconBlockRoots,
             11 => RPCMethod::BeaconBlockHeaders,
             12 => RPCMethod::BeaconBlockBodies,
             13 => RPCMethod::BeaconChainState,
+            14 => RPCMethod::ProofSubscription,
+            15 => RPCMethod::ProofPush,
+            255 => RPCMethod::Error,
 
             _ => RPCMethod::Unknown,
         }
@@ -43,10 +60,15 @@ impl Into<u16> for RPCMethod {
         match self {
             RPCMethod::Hello => 0,
             RPCMethod::Goodbye => 1,
+            RPCMethod::Ping => 2,
+            RPCMethod::MetaData => 3,
             RPCMethod::BeaconBlockRoots => 10,
             RPCMethod::BeaconBlockHeaders => 11,
             RPCMethod::BeaconBlockBodies => 12,
             RPCMethod::BeaconChainState => 13,
+            RPCMethod::ProofSubscription => 14,
+            RPCMethod::ProofPush => 15,
+            RPCMethod::Error => 255,
             _ => 0,
         }
     }
@@ -56,10 +78,14 @@ impl Into<u16> for RPCMethod {
 pub enum RPCRequest {
     Hello(HelloMessage),
     Goodbye(GoodbyeReason),
+    Ping(Ping),
+    MetaData,
     BeaconBlockRoots(BeaconBlockRootsRequest),
     BeaconBlockHeaders(BeaconBlockHeadersRequest),
     BeaconBlockBodies(BeaconBlockBodiesRequest),
     BeaconChainState(BeaconChainStateRequest),
+    ProofSubscription(ProofSubscription),
+    ProofPush(ProofPush),
 }
 
 impl RPCRequest {
@@ -67,10 +93,14 @@ impl RPCRequest {
         let method = match self {
             RPCRequest::Hello(_) => RPCMethod::Hello,
             RPCRequest::Goodbye(_) => RPCMethod::Goodbye,
+            RPCRequest::Ping(_) => RPCMethod::Ping,
+            RPCRequest::MetaData => RPCMethod::MetaData,
             RPCRequest::BeaconBlockRoots(_) => RPCMethod::BeaconBlockRoots,
             RPCRequest::BeaconBlockHeaders(_) => RPCMethod::BeaconBlockHeaders,
             RPCRequest::BeaconBlockBodies(_) => RPCMethod::BeaconBlockBodies,
             RPCRequest::BeaconChainState(_) => RPCMethod::BeaconChainState,
+            RPCRequest::ProofSubscription(_) => RPCMethod::ProofSubscription,
+            RPCRequest::ProofPush(_) => RPCMethod::ProofPush,
         };
         method.into()
     }
@@ -79,20 +109,28 @@ impl RPCRequest {
 #[derive(Debug, Clone)]
 pub enum RPCResponse {
     Hello(HelloMessage),
+    Pong(Ping),
+    MetaData(MetaData),
     BeaconBlockRoots(BeaconBlockRootsResponse),
     BeaconBlockHeaders(BeaconBlockHeadersResponse),
     BeaconBlockBodies(BeaconBlockBodiesResponse),
     BeaconChainState(BeaconChainStateResponse),
+    ProofSubscription(ProofSubscription),
+    Error(ErrorResponse),
 }
 
 impl RPCResponse {
     pub fn method_id(&self) -> u16 {
         let method = match self {
             RPCResponse::Hello(_) => RPCMethod::Hello,
+            RPCResponse::Pong(_) => RPCMethod::Ping,
+            RPCResponse::MetaData(_) => RPCMethod::MetaData,
             RPCResponse::BeaconBlockRoots(_) => RPCMethod::BeaconBlockRoots,
             RPCResponse::BeaconBlockHeaders(_) => RPCMethod::BeaconBlockHeaders,
             RPCResponse::BeaconBlockBodies(_) => RPCMethod::BeaconBlockBodies,
             RPCResponse::BeaconChainState(_) => RPCMethod::BeaconChainState,
+            RPCResponse::ProofSubscription(_) => RPCMethod::ProofSubscription,
+            RPCResponse::Error(_) => RPCMethod::Error,
         };
         method.into()
     }
@@ -103,6 +141,8 @@ impl RPCResponse {
 /// The HELLO request/response handshake message.
 #[derive(Encode, Decode, Clone, Debug)]
 pub struct HelloMessage {
+    /// The digest of the peer's current fork and genesis block.
+    pub fork_digest: ForkDigest,
     /// The network ID of the peer.
     pub network_id: u8,
     /// The peers last finalized root.
@@ -153,6 +193,87 @@ impl Into<u64> for GoodbyeReason {
 impl_encode_via_from!(GoodbyeReason, u64);
 impl_decode_via_from!(GoodbyeReason, u64);
 
+/// The body of a `Ping` request and its response.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct Ping {
+    /// The `MetaData::seq_number` of the sender.
+    pub data: u64,
+}
+
+/// Information a peer advertises about itself, which may change while connected.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct MetaData {
+    /// Incremented each time any other field changes.
+    pub seq_number: u64,
+    /// The attestation subnets the peer is subscribed to.
+    ///
+    /// Attestation subnets are not yet implemented, so this is always empty.
+    pub attnets: Bitfield,
+}
+
+impl Default for MetaData {
+    fn default() -> Self {
+        Self {
+            seq_number: 0,
+            attnets: Bitfield::new(),
+        }
+    }
+}
+
+/// The reason a request could not be served.
+///
+/// Note: as with `GoodbyeReason`, any unknown code resolves to `RPCErrorCode::Unknown`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RPCErrorCode {
+    InvalidRequest,
+    ServerError,
+    RateLimited,
+    Unknown,
+}
+
+impl From<u64> for RPCErrorCode {
+    fn from(id: u64) -> RPCErrorCode {
+        match id {
+            1 => RPCErrorCode::InvalidRequest,
+            2 => RPCErrorCode::ServerError,
+            139 => RPCErrorCode::RateLimited,
+            _ => RPCErrorCode::Unknown,
+        }
+    }
+}
+
+impl Into<u64> for RPCErrorCode {
+    fn into(self) -> u64 {
+        match self {
+            RPCErrorCode::Unknown => 0,
+            RPCErrorCode::InvalidRequest => 1,
+            RPCErrorCode::ServerError => 2,
+            RPCErrorCode::RateLimited => 139,
+        }
+    }
+}
+
+impl_encode_via_from!(RPCErrorCode, u64);
+impl_decode_via_from!(RPCErrorCode, u64);
+
+/// Sent in place of a response when a request cannot be served.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct ErrorResponse {
+    /// The reason the request failed.
+    pub code: RPCErrorCode,
+    /// A human-readable, UTF-8 error message.
+    pub message: Vec<u8>,
+}
+
+impl ErrorResponse {
+    pub fn new(code: RPCErrorCode, message: &str) -> Self {
+        Self {
+            code,
+            message: message.as_bytes().to_vec(),
+        }
+    }
+}
+
 /// Request a number of beacon block roots from a peer.
 #[derive(Encode, Decode, Clone, Debug, PartialEq)]
 pub struct BeaconBlockRootsRequest {
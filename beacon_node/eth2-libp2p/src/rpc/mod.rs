@@ -1,9 +1,11 @@
+mod codec;
 /// RPC Protocol over libp2p.
 ///
 /// This is purpose built for Ethereum 2.0 serenity and the protocol listens on
-/// `/eth/serenity/rpc/1.0.0`
+/// `/eth/serenity/rpc/1.0.0/ssz_snappy`, falling back to the uncompressed `/eth/serenity/rpc/1.0.0`.
 pub mod methods;
 mod protocol;
+mod rate_limiter;
 
 use futures::prelude::*;
 use libp2p::core::protocols_handler::{OneShotHandler, ProtocolsHandler};
@@ -11,9 +13,11 @@ use libp2p::core::swarm::{
     ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters,
 };
 use libp2p::{Multiaddr, PeerId};
-pub use methods::{HelloMessage, RPCMethod, RPCRequest, RPCResponse};
+pub use methods::{ErrorResponse, HelloMessage, RPCErrorCode, RPCMethod, RPCRequest, RPCResponse};
 pub use protocol::{RPCEvent, RPCProtocol, RequestId};
-use slog::o;
+use rate_limiter::{RateLimitedErr, RateLimiter};
+use slog::{debug, o};
+use std::collections::HashSet;
 use std::marker::PhantomData;
 use tokio::io::{AsyncRead, AsyncWrite};
 
@@ -23,19 +27,25 @@ use tokio::io::{AsyncRead, AsyncWrite};
 pub struct Rpc<TSubstream> {
     /// Queue of events to processed.
     events: Vec<NetworkBehaviourAction<RPCEvent, RPCMessage>>,
+    /// Limits the rate of incoming requests from each peer.
+    limiter: RateLimiter,
+    /// Peers which are exempt from rate limiting.
+    trusted_peers: HashSet<PeerId>,
     /// Pins the generic substream.
     marker: PhantomData<TSubstream>,
     /// Slog logger for RPC behaviour.
-    _log: slog::Logger,
+    log: slog::Logger,
 }
 
 impl<TSubstream> Rpc<TSubstream> {
-    pub fn new(log: &slog::Logger) -> Self {
+    pub fn new(trusted_peers: HashSet<PeerId>, log: &slog::Logger) -> Self {
         let log = log.new(o!("Service" => "Libp2p-RPC"));
         Rpc {
             events: Vec::new(),
+            limiter: RateLimiter::default(),
+            trusted_peers,
             marker: PhantomData,
-            _log: log,
+            log,
         }
     }
 
@@ -72,7 +82,9 @@ where
         }
     }
 
-    fn inject_disconnected(&mut self, _: &PeerId, _: ConnectedPoint) {}
+    fn inject_disconnected(&mut self, peer_id: &PeerId, _: ConnectedPoint) {
+        self.limiter.prune_peer(peer_id);
+    }
 
     fn inject_node_event(
         &mut self,
@@ -85,6 +97,41 @@ where
             OneShotEvent::Sent => return,
         };
 
+        // refuse requests that exceed the peers quota, without passing them to the user
+        if let RPCEvent::Request { id, body, .. } = &event {
+            let allowed = if self.trusted_peers.contains(&source) {
+                Ok(())
+            } else {
+                self.limiter.allows(&source, body)
+            };
+
+            if let Err(e) = allowed {
+                debug!(
+                    self.log,
+                    "RPC request rate limited";
+                    "peer" => format!("{:?}", source),
+                    "method_id" => body.method_id(),
+                    "reason" => format!("{:?}", e)
+                );
+
+                let message = match e {
+                    RateLimitedErr::TooLarge => "Request exceeds quota".to_string(),
+                    RateLimitedErr::TooSoon(wait) => format!("Wait {}ms", wait.as_millis()),
+                };
+                let result =
+                    RPCResponse::Error(ErrorResponse::new(RPCErrorCode::RateLimited, &message));
+                self.events.push(NetworkBehaviourAction::SendEvent {
+                    peer_id: source,
+                    event: RPCEvent::Response {
+                        id: *id,
+                        method_id: result.method_id(),
+                        result,
+                    },
+                });
+                return;
+            }
+        }
+
         // send the event to the user
         self.events
             .push(NetworkBehaviourAction::GenerateEvent(RPCMessage::RPC(
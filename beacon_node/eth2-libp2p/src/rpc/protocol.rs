@@ -1,15 +1,13 @@
+use super::codec::{Encoding, SUPPORTED_PROTOCOLS};
 use super::methods::*;
 use libp2p::core::{upgrade, InboundUpgrade, OutboundUpgrade, UpgradeInfo};
 use ssz::{impl_decode_via_from, impl_encode_via_from, ssz_encode, Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use std::hash::{Hash, Hasher};
 use std::io;
-use std::iter;
+use std::vec;
 use tokio::io::{AsyncRead, AsyncWrite};
 
-/// The maximum bytes that can be sent across the RPC.
-const MAX_READ_SIZE: usize = 4_194_304; // 4M
-
 /// Implementation of the `ConnectionUpgrade` for the rpc protocol.
 
 #[derive(Debug, Clone)]
@@ -17,11 +15,11 @@ pub struct RPCProtocol;
 
 impl UpgradeInfo for RPCProtocol {
     type Info = &'static [u8];
-    type InfoIter = iter::Once<Self::Info>;
+    type InfoIter = vec::IntoIter<Self::Info>;
 
     #[inline]
     fn protocol_info(&self) -> Self::InfoIter {
-        iter::once(b"/eth/serenity/rpc/1.0.0")
+        SUPPORTED_PROTOCOLS.to_vec().into_iter()
     }
 }
 
@@ -93,15 +91,15 @@ pub enum RPCEvent {
 
 impl UpgradeInfo for RPCEvent {
     type Info = &'static [u8];
-    type InfoIter = iter::Once<Self::Info>;
+    type InfoIter = vec::IntoIter<Self::Info>;
 
     #[inline]
     fn protocol_info(&self) -> Self::InfoIter {
-        iter::once(b"/eth/serenity/rpc/1.0.0")
+        SUPPORTED_PROTOCOLS.to_vec().into_iter()
     }
 }
 
-type FnDecodeRPCEvent = fn(Vec<u8>, ()) -> Result<RPCEvent, DecodeError>;
+type FnDecodeRPCEvent = fn(Vec<u8>, Encoding) -> Result<RPCEvent, DecodeError>;
 
 impl<TSocket> InboundUpgrade<TSocket> for RPCProtocol
 where
@@ -109,10 +107,21 @@ where
 {
     type Output = RPCEvent;
     type Error = DecodeError;
-    type Future = upgrade::ReadOneThen<upgrade::Negotiated<TSocket>, (), FnDecodeRPCEvent>;
-
-    fn upgrade_inbound(self, socket: upgrade::Negotiated<TSocket>, _: Self::Info) -> Self::Future {
-        upgrade::read_one_then(socket, MAX_READ_SIZE, (), |packet, ()| Ok(decode(packet)?))
+    type Future = upgrade::ReadOneThen<upgrade::Negotiated<TSocket>, Encoding, FnDecodeRPCEvent>;
+
+    fn upgrade_inbound(
+        self,
+        socket: upgrade::Negotiated<TSocket>,
+        protocol: Self::Info,
+    ) -> Self::Future {
+        let encoding = Encoding::from_protocol(protocol);
+        // The decompressed size of the packet is bounded by `Encoding::decode`.
+        upgrade::read_one_then(
+            socket,
+            encoding.max_packet_size(),
+            encoding,
+            |packet, encoding| decode(encoding.decode(packet)?),
+        )
     }
 }
 
@@ -137,6 +146,8 @@ fn decode(packet: Vec<u8>) -> Result<RPCEvent, DecodeError> {
         let body = match RPCMethod::from(msg.other) {
             RPCMethod::Hello => RPCRequest::Hello(HelloMessage::from_ssz_bytes(&msg.bytes)?),
             RPCMethod::Goodbye => RPCRequest::Goodbye(GoodbyeReason::from_ssz_bytes(&msg.bytes)?),
+            RPCMethod::Ping => RPCRequest::Ping(Ping::from_ssz_bytes(&msg.bytes)?),
+            RPCMethod::MetaData => RPCRequest::MetaData,
             RPCMethod::BeaconBlockRoots => {
                 RPCRequest::BeaconBlockRoots(BeaconBlockRootsRequest::from_ssz_bytes(&msg.bytes)?)
             }
@@ -149,6 +160,12 @@ fn decode(packet: Vec<u8>) -> Result<RPCEvent, DecodeError> {
             RPCMethod::BeaconChainState => {
                 RPCRequest::BeaconChainState(BeaconChainStateRequest::from_ssz_bytes(&msg.bytes)?)
             }
+            RPCMethod::ProofSubscription => {
+                RPCRequest::ProofSubscription(ProofSubscription::from_ssz_bytes(&msg.bytes)?)
+            }
+            RPCMethod::ProofPush => RPCRequest::ProofPush(ProofPush::from_ssz_bytes(&msg.bytes)?),
+            // Errors are only ever sent as responses.
+            RPCMethod::Error => return Err(DecodeError::UnknownRPCMethod),
             RPCMethod::Unknown => return Err(DecodeError::UnknownRPCMethod),
         };
 
@@ -162,6 +179,8 @@ fn decode(packet: Vec<u8>) -> Result<RPCEvent, DecodeError> {
     else {
         let result = match RPCMethod::from(msg.other) {
             RPCMethod::Hello => RPCResponse::Hello(HelloMessage::from_ssz_bytes(&msg.bytes)?),
+            RPCMethod::Ping => RPCResponse::Pong(Ping::from_ssz_bytes(&msg.bytes)?),
+            RPCMethod::MetaData => RPCResponse::MetaData(MetaData::from_ssz_bytes(&msg.bytes)?),
             RPCMethod::BeaconBlockRoots => {
                 RPCResponse::BeaconBlockRoots(BeaconBlockRootsResponse::from_ssz_bytes(&msg.bytes)?)
             }
@@ -174,8 +193,12 @@ fn decode(packet: Vec<u8>) -> Result<RPCEvent, DecodeError> {
             RPCMethod::BeaconChainState => {
                 RPCResponse::BeaconChainState(BeaconChainStateResponse::from_ssz_bytes(&msg.bytes)?)
             }
-            // We should never receive a goodbye response; it is invalid.
-            RPCMethod::Goodbye => return Err(DecodeError::UnknownRPCMethod),
+            RPCMethod::ProofSubscription => {
+                RPCResponse::ProofSubscription(ProofSubscription::from_ssz_bytes(&msg.bytes)?)
+            }
+            RPCMethod::Error => RPCResponse::Error(ErrorResponse::from_ssz_bytes(&msg.bytes)?),
+            // We should never receive a goodbye or proof push response; they are invalid.
+            RPCMethod::Goodbye | RPCMethod::ProofPush => return Err(DecodeError::UnknownRPCMethod),
             RPCMethod::Unknown => return Err(DecodeError::UnknownRPCMethod),
         };
 
@@ -196,8 +219,12 @@ where
     type Future = upgrade::WriteOne<upgrade::Negotiated<TSocket>>;
 
     #[inline]
-    fn upgrade_outbound(self, socket: upgrade::Negotiated<TSocket>, _: Self::Info) -> Self::Future {
-        let bytes = ssz_encode(&self);
+    fn upgrade_outbound(
+        self,
+        socket: upgrade::Negotiated<TSocket>,
+        protocol: Self::Info,
+    ) -> Self::Future {
+        let bytes = Encoding::from_protocol(protocol).encode(ssz_encode(&self));
         upgrade::write_one(socket, bytes)
     }
 }
@@ -220,10 +247,15 @@ impl Encode for RPCEvent {
                 bytes: match body {
                     RPCRequest::Hello(body) => body.as_ssz_bytes(),
                     RPCRequest::Goodbye(body) => body.as_ssz_bytes(),
+                    RPCRequest::Ping(body) => body.as_ssz_bytes(),
+                    // A `MetaData` request has no body.
+                    RPCRequest::MetaData => vec![],
                     RPCRequest::BeaconBlockRoots(body) => body.as_ssz_bytes(),
                     RPCRequest::BeaconBlockHeaders(body) => body.as_ssz_bytes(),
                     RPCRequest::BeaconBlockBodies(body) => body.as_ssz_bytes(),
                     RPCRequest::BeaconChainState(body) => body.as_ssz_bytes(),
+                    RPCRequest::ProofSubscription(body) => body.as_ssz_bytes(),
+                    RPCRequest::ProofPush(body) => body.as_ssz_bytes(),
                 },
             },
             RPCEvent::Response {
@@ -236,10 +268,14 @@ impl Encode for RPCEvent {
                 other: *method_id,
                 bytes: match result {
                     RPCResponse::Hello(response) => response.as_ssz_bytes(),
+                    RPCResponse::Pong(response) => response.as_ssz_bytes(),
+                    RPCResponse::MetaData(response) => response.as_ssz_bytes(),
                     RPCResponse::BeaconBlockRoots(response) => response.as_ssz_bytes(),
                     RPCResponse::BeaconBlockHeaders(response) => response.as_ssz_bytes(),
                     RPCResponse::BeaconBlockBodies(response) => response.as_ssz_bytes(),
                     RPCResponse::BeaconChainState(response) => response.as_ssz_bytes(),
+                    RPCResponse::ProofSubscription(response) => response.as_ssz_bytes(),
+                    RPCResponse::Error(response) => response.as_ssz_bytes(),
                 },
             },
         };
@@ -252,6 +288,9 @@ impl Encode for RPCEvent {
 pub enum DecodeError {
     ReadError(upgrade::ReadOneError),
     SSZDecodeError(ssz::DecodeError),
+    SnappyError(snap::Error),
+    /// The decompressed payload would exceed the maximum payload size.
+    PayloadTooLarge,
     UnknownRPCMethod,
 }
 
@@ -262,6 +301,13 @@ impl From<upgrade::ReadOneError> for DecodeError {
     }
 }
 
+impl From<snap::Error> for DecodeError {
+    #[inline]
+    fn from(err: snap::Error) -> Self {
+        DecodeError::SnappyError(err)
+    }
+}
+
 impl From<ssz::DecodeError> for DecodeError {
     #[inline]
     fn from(err: ssz::DecodeError) -> Self {
@@ -0,0 +1,213 @@
+//! Per-peer, per-method token-bucket rate limiting for incoming RPC requests.
+use super::methods::{RPCMethod, RPCRequest};
+use libp2p::PeerId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The number of tokens a request may consume within some period of time.
+///
+/// Tokens are replenished continuously, such that a bucket goes from empty to full over
+/// `replenish_all_every`.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    /// The maximum number of tokens a bucket may hold.
+    pub max_tokens: u64,
+    /// The time required to fully replenish an empty bucket.
+    pub replenish_all_every: Duration,
+}
+
+impl Quota {
+    pub const fn n_every(max_tokens: u64, seconds: u64) -> Self {
+        Quota {
+            max_tokens,
+            replenish_all_every: Duration::from_secs(seconds),
+        }
+    }
+}
+
+/// A request was refused by the `RateLimiter`.
+#[derive(Debug, PartialEq)]
+pub enum RateLimitedErr {
+    /// The request would never be allowed, regardless of how long the peer waits.
+    TooLarge,
+    /// The request may be allowed after waiting for the given duration.
+    TooSoon(Duration),
+}
+
+/// A token bucket for a single peer and method.
+struct Bucket {
+    tokens: f64,
+    last_update: Instant,
+}
+
+/// Limits the rate at which each peer may make each kind of RPC request.
+///
+/// The cost of a request is the number of items it asks for (e.g., the number of block roots),
+/// so that a peer cannot exhaust our resources with a few large requests.
+pub struct RateLimiter {
+    hello: Quota,
+    goodbye: Quota,
+    ping: Quota,
+    meta_data: Quota,
+    block_roots: Quota,
+    block_headers: Quota,
+    block_bodies: Quota,
+    chain_state: Quota,
+    proof_subscription: Quota,
+    proof_push: Quota,
+    buckets: HashMap<(PeerId, u16), Bucket>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter {
+            hello: Quota::n_every(2, 10),
+            goodbye: Quota::n_every(1, 10),
+            ping: Quota::n_every(2, 10),
+            meta_data: Quota::n_every(2, 5),
+            block_roots: Quota::n_every(4096, 10),
+            block_headers: Quota::n_every(1024, 10),
+            block_bodies: Quota::n_every(1024, 10),
+            chain_state: Quota::n_every(1, 10),
+            proof_subscription: Quota::n_every(2, 10),
+            proof_push: Quota::n_every(2, 10),
+            buckets: HashMap::new(),
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Returns the quota which applies to `method`.
+    fn quota(&self, method: &RPCMethod) -> Quota {
+        match method {
+            RPCMethod::Hello => self.hello,
+            RPCMethod::Goodbye => self.goodbye,
+            RPCMethod::Ping => self.ping,
+            RPCMethod::MetaData => self.meta_data,
+            RPCMethod::BeaconBlockRoots => self.block_roots,
+            RPCMethod::BeaconBlockHeaders => self.block_headers,
+            RPCMethod::BeaconBlockBodies => self.block_bodies,
+            RPCMethod::BeaconChainState => self.chain_state,
+            RPCMethod::ProofSubscription => self.proof_subscription,
+            RPCMethod::ProofPush => self.proof_push,
+            // Unknown methods cannot be decoded, so this is never reached. Deny them anyway.
+            RPCMethod::Error | RPCMethod::Unknown => Quota::n_every(0, 1),
+        }
+    }
+
+    /// Returns `Ok(())` and consumes tokens if `peer_id` may make `request` now.
+    pub fn allows(&mut self, peer_id: &PeerId, request: &RPCRequest) -> Result<(), RateLimitedErr> {
+        self.allows_at(peer_id, request, Instant::now())
+    }
+
+    fn allows_at(
+        &mut self,
+        peer_id: &PeerId,
+        request: &RPCRequest,
+        now: Instant,
+    ) -> Result<(), RateLimitedErr> {
+        let method_id = request.method_id();
+        let quota = self.quota(&RPCMethod::from(method_id));
+        let cost = request_cost(request);
+
+        if cost > quota.max_tokens {
+            return Err(RateLimitedErr::TooLarge);
+        }
+
+        let bucket = self
+            .buckets
+            .entry((peer_id.clone(), method_id))
+            .or_insert_with(|| Bucket {
+                tokens: quota.max_tokens as f64,
+                last_update: now,
+            });
+
+        // Replenish the tokens accrued since the last request.
+        let elapsed = now.duration_since(bucket.last_update);
+        let tokens_per_sec =
+            quota.max_tokens as f64 / duration_as_secs_f64(quota.replenish_all_every);
+        bucket.tokens = (bucket.tokens + duration_as_secs_f64(elapsed) * tokens_per_sec)
+            .min(quota.max_tokens as f64);
+        bucket.last_update = now;
+
+        if bucket.tokens >= cost as f64 {
+            bucket.tokens -= cost as f64;
+            Ok(())
+        } else {
+            let wait_secs = (cost as f64 - bucket.tokens) / tokens_per_sec;
+            Err(RateLimitedErr::TooSoon(Duration::from_millis(
+                (wait_secs * 1000.0).ceil() as u64,
+            )))
+        }
+    }
+
+    /// Forget all buckets for `peer_id`, e.g., once it disconnects.
+    pub fn prune_peer(&mut self, peer_id: &PeerId) {
+        self.buckets.retain(|(peer, _), _| peer != peer_id);
+    }
+}
+
+/// The number of tokens consumed by `request`.
+fn request_cost(request: &RPCRequest) -> u64 {
+    match request {
+        RPCRequest::BeaconBlockRoots(req) => req.count,
+        RPCRequest::BeaconBlockHeaders(req) => req.max_headers,
+        RPCRequest::BeaconBlockBodies(req) => req.block_roots.len() as u64,
+        _ => 1,
+    }
+}
+
+fn duration_as_secs_f64(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::methods::BeaconBlockRootsRequest;
+    use super::*;
+    use types::Slot;
+
+    fn roots_request(count: u64) -> RPCRequest {
+        RPCRequest::BeaconBlockRoots(BeaconBlockRootsRequest {
+            start_slot: Slot::new(0),
+            count,
+        })
+    }
+
+    #[test]
+    fn limits_and_replenishes() {
+        let mut limiter = RateLimiter::default();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(limiter.allows_at(&peer, &roots_request(4096), now), Ok(()));
+        assert_eq!(
+            limiter.allows_at(&peer, &roots_request(1), now),
+            Err(RateLimitedErr::TooSoon(Duration::from_millis(3)))
+        );
+
+        // Other peers have their own buckets.
+        assert_eq!(
+            limiter.allows_at(&PeerId::random(), &roots_request(1), now),
+            Ok(())
+        );
+
+        // Half the bucket has been replenished after half the period.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            limiter.allows_at(&peer, &roots_request(2048), later),
+            Ok(())
+        );
+        assert!(limiter.allows_at(&peer, &roots_request(1), later).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_requests() {
+        let mut limiter = RateLimiter::default();
+
+        assert_eq!(
+            limiter.allows(&PeerId::random(), &roots_request(4097)),
+            Err(RateLimitedErr::TooLarge)
+        );
+    }
+}
@@ -1,8 +1,11 @@
-use crate::behaviour::{Behaviour, BehaviourEvent, PubsubMessage};
+use crate::behaviour::{Behaviour, BehaviourEvent};
 use crate::error;
+use crate::gossip::PubsubMessage;
 use crate::multiaddr::Protocol;
 use crate::rpc::RPCEvent;
-use crate::NetworkConfig;
+use crate::topics::GossipTopic;
+use crate::transport::{self, select_dial_addresses};
+use crate::{Multiaddr, NetworkConfig};
 use futures::prelude::*;
 use futures::Stream;
 use libp2p::core::{
@@ -15,9 +18,10 @@ use libp2p::core::{
 use libp2p::identify::protocol::IdentifyInfo;
 use libp2p::{core, secio, PeerId, Swarm, Transport};
 use slog::{debug, info, trace, warn};
+use std::collections::HashSet;
 use std::io::{Error, ErrorKind};
 use std::time::Duration;
-use types::{TopicBuilder, TopicHash};
+use types::{ForkDigest, TopicHash};
 
 type Libp2pStream = Boxed<(PeerId, StreamMuxerBox), Error>;
 type Libp2pBehaviour = Behaviour<Substream<StreamMuxerBox>>;
@@ -34,7 +38,13 @@ pub struct Service {
 }
 
 impl Service {
-    pub fn new(config: NetworkConfig, log: slog::Logger) -> error::Result<Self> {
+    /// Starts the libp2p service, subscribing to the configured topics on the fork identified by
+    /// `fork_digest`.
+    pub fn new(
+        config: NetworkConfig,
+        fork_digest: ForkDigest,
+        log: slog::Logger,
+    ) -> error::Result<Self> {
         debug!(log, "Libp2p Service starting");
 
         // TODO: Currently using secp256k1 key pairs. Wire protocol specifies RSA. Waiting for this
@@ -50,31 +60,49 @@ impl Service {
             // Set up the transport
             let transport = build_transport(local_private_key);
             // Set up gossipsub routing
-            let behaviour = Behaviour::new(local_public_key.clone(), &config, &log);
+            let behaviour = Behaviour::new(local_public_key.clone(), &config, fork_digest, &log)?;
             // Set up Topology
             let topology = local_peer_id.clone();
             Swarm::new(transport, behaviour, topology)
         };
 
         // listen on all addresses
+        let mut ipv6_ports = HashSet::new();
         for address in config
-            .listen_addresses()
+            .listen_multiaddrs()
             .map_err(|e| format!("Invalid listen multiaddr: {}", e))?
         {
+            if !transport::is_supported(&address) {
+                warn!(log, "Cannot listen on: {} : QUIC is not supported", address);
+                continue;
+            }
             match Swarm::listen_on(&mut swarm, address.clone()) {
                 Ok(mut listen_addr) => {
+                    if let Some(port) = transport::unspecified_ipv6_tcp_port(&address) {
+                        ipv6_ports.insert(port);
+                    }
                     listen_addr.append(Protocol::P2p(local_peer_id.clone().into()));
                     info!(log, "Listening on: {}", listen_addr);
                 }
+                // An IPv6 socket on the same port may already accept IPv4 connections.
+                Err(err)
+                    if transport::unspecified_ipv4_tcp_port(&address)
+                        .map_or(false, |port| ipv6_ports.contains(&port)) =>
+                {
+                    debug!(
+                        log,
+                        "Not listening on: {} : {:?}, IPv4 is accepted over IPv6", address, err
+                    )
+                }
                 Err(err) => warn!(log, "Cannot listen on: {} : {:?}", address, err),
             };
         }
         // connect to boot nodes - these are currently stored as multiaddrs
         // Once we have discovery, can set to peerId
-        for bootnode in config
+        let bootnodes = config
             .boot_nodes()
-            .map_err(|e| format!("Invalid boot node multiaddr: {:?}", e))?
-        {
+            .map_err(|e| format!("Invalid boot node multiaddr: {:?}", e))?;
+        for bootnode in select_dial_addresses(bootnodes, &config.transport_preference) {
             match Swarm::dial_addr(&mut swarm, bootnode.clone()) {
                 Ok(()) => debug!(log, "Dialing bootnode: {}", bootnode),
                 Err(err) => debug!(
@@ -86,13 +114,13 @@ impl Service {
 
         // subscribe to default gossipsub topics
         let mut subscribed_topics = vec![];
-        for topic in config.topics {
-            let t = TopicBuilder::new(topic.to_string()).build();
-            if swarm.subscribe(t) {
-                trace!(log, "Subscribed to topic: {:?}", topic);
-                subscribed_topics.push(topic);
+        for kind in config.topics {
+            let topic = GossipTopic::new(fork_digest, kind);
+            if swarm.subscribe(topic.topic()) {
+                trace!(log, "Subscribed to topic: {:?}", topic.name());
+                subscribed_topics.push(topic.name());
             } else {
-                warn!(log, "Could not subscribe to topic: {:?}", topic)
+                warn!(log, "Could not subscribe to topic: {:?}", topic.name())
             }
         }
         info!(log, "Subscribed to topics: {:?}", subscribed_topics);
@@ -103,6 +131,13 @@ impl Service {
             log,
         })
     }
+
+    /// Advertises `address` to identified peers as one on which this node is reachable, e.g. a
+    /// port mapped on the local router.
+    pub fn add_external_address(&mut self, address: Multiaddr) {
+        info!(self.log, "External address: {}", address);
+        Swarm::add_external_address(&mut self.swarm, address);
+    }
 }
 
 impl Stream for Service {
@@ -150,6 +185,9 @@ impl Stream for Service {
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, secio as the encryption layer, and
 /// mplex or yamux as the multiplexing layer.
+///
+/// With the `memory-transport` feature, in-process `/memory/<port>` addresses are also supported,
+/// allowing many nodes to be connected within a single process. This is only intended for testing.
 fn build_transport(local_private_key: identity::Keypair) -> Boxed<(PeerId, StreamMuxerBox), Error> {
     // TODO: The Wire protocol currently doesn't specify encryption and this will need to be customised
     // in the future.
@@ -160,7 +198,9 @@ fn build_transport(local_private_key: identity::Keypair) -> Boxed<(PeerId, Strea
         let trans_clone = transport.clone();
         transport.or_transport(websocket::WsConfig::new(trans_clone))
     };
-    transport
+    #[cfg(feature = "memory-transport")]
+    let transport = transport.or_transport(core::transport::MemoryTransport::default());
+    let transport = transport
         .with_upgrade(secio::SecioConfig::new(local_private_key))
         .and_then(move |out, endpoint| {
             let peer_id = out.remote_key.into_peer_id();
@@ -177,8 +217,8 @@ fn build_transport(local_private_key: identity::Keypair) -> Boxed<(PeerId, Strea
                 .map(|(id, muxer)| (id, core::muxing::StreamMuxerBox::new(muxer)))
         })
         .with_timeout(Duration::from_secs(20))
-        .map_err(|err| Error::new(ErrorKind::Other, err))
-        .boxed()
+        .map_err(|err| Error::new(ErrorKind::Other, err));
+    transport.boxed()
 }
 
 /// Events that can be obtained from polling the Libp2p Service.
@@ -0,0 +1,219 @@
+//! Keeps connections open to a configured set of peers, redialing with exponential backoff
+//! whenever a connection drops or a dial fails.
+use futures::prelude::*;
+use libp2p::core::protocols_handler::{DummyProtocolsHandler, ProtocolsHandler};
+use libp2p::core::swarm::{
+    ConnectedPoint, NetworkBehaviour, NetworkBehaviourAction, PollParameters,
+};
+use libp2p::{Multiaddr, PeerId};
+use slog::{debug, o, warn};
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::timer::Delay;
+use void::Void;
+
+/// The delay before the first redial of a disconnected peer.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// The longest delay between redials of a disconnected peer.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// The connection state of a single static peer.
+struct StaticPeer {
+    address: Multiaddr,
+    connected: bool,
+    /// The delay to apply after the next failed dial.
+    backoff: Duration,
+    /// The earliest time at which the peer may next be dialed.
+    next_dial: Instant,
+}
+
+/// A network behaviour which dials each static address on startup and redials any which become
+/// disconnected.
+pub struct StaticPeers<TSubstream> {
+    peers: Vec<StaticPeer>,
+    /// Wakes the task when the next redial is due.
+    delay: Delay,
+    marker: PhantomData<TSubstream>,
+    log: slog::Logger,
+}
+
+impl<TSubstream> StaticPeers<TSubstream> {
+    pub fn new(addresses: Vec<Multiaddr>, log: &slog::Logger) -> Self {
+        let now = Instant::now();
+        let peers = addresses
+            .into_iter()
+            .map(|address| StaticPeer {
+                address,
+                connected: false,
+                backoff: INITIAL_BACKOFF,
+                next_dial: now,
+            })
+            .collect();
+
+        StaticPeers {
+            peers,
+            delay: Delay::new(now),
+            marker: PhantomData,
+            log: log.new(o!("Service" => "StaticPeers")),
+        }
+    }
+
+    /// Records a connection established by dialing `address`.
+    fn on_connected(&mut self, address: &Multiaddr) {
+        if let Some(peer) = self.peers.iter_mut().find(|p| p.address == *address) {
+            peer.connected = true;
+            peer.backoff = INITIAL_BACKOFF;
+        }
+    }
+
+    /// Records that the connection established by dialing `address` has closed, scheduling a
+    /// redial.
+    fn on_disconnected(&mut self, address: &Multiaddr, now: Instant) {
+        if let Some(peer) = self.peers.iter_mut().find(|p| p.address == *address) {
+            peer.connected = false;
+            peer.next_dial = now + peer.backoff;
+        }
+    }
+
+    /// Returns the address of a disconnected peer which is due to be dialed, if any, and
+    /// schedules its next dial should this one fail.
+    fn next_dial(&mut self, now: Instant) -> Option<Multiaddr> {
+        let peer = self
+            .peers
+            .iter_mut()
+            .find(|p| !p.connected && p.next_dial <= now)?;
+
+        peer.next_dial = now + peer.backoff;
+        peer.backoff = std::cmp::min(peer.backoff * 2, MAX_BACKOFF);
+
+        Some(peer.address.clone())
+    }
+
+    /// The earliest time at which a disconnected peer is due to be dialed.
+    fn next_deadline(&self) -> Option<Instant> {
+        self.peers
+            .iter()
+            .filter(|p| !p.connected)
+            .map(|p| p.next_dial)
+            .min()
+    }
+}
+
+impl<TSubstream> NetworkBehaviour for StaticPeers<TSubstream>
+where
+    TSubstream: AsyncRead + AsyncWrite,
+{
+    type ProtocolsHandler = DummyProtocolsHandler<TSubstream>;
+    type OutEvent = Void;
+
+    fn new_handler(&mut self) -> Self::ProtocolsHandler {
+        DummyProtocolsHandler::default()
+    }
+
+    fn addresses_of_peer(&mut self, _peer_id: &PeerId) -> Vec<Multiaddr> {
+        Vec::new()
+    }
+
+    fn inject_connected(&mut self, _peer_id: PeerId, connected_point: ConnectedPoint) {
+        if let ConnectedPoint::Dialer { address } = connected_point {
+            self.on_connected(&address);
+        }
+    }
+
+    fn inject_disconnected(&mut self, peer_id: &PeerId, connected_point: ConnectedPoint) {
+        if let ConnectedPoint::Dialer { address } = connected_point {
+            debug!(
+                self.log,
+                "Peer disconnected";
+                "peer" => format!("{:?}", peer_id),
+                "address" => format!("{}", address)
+            );
+            self.on_disconnected(&address, Instant::now());
+        }
+    }
+
+    fn inject_node_event(
+        &mut self,
+        _peer_id: PeerId,
+        event: <Self::ProtocolsHandler as ProtocolsHandler>::OutEvent,
+    ) {
+        void::unreachable(event)
+    }
+
+    fn poll(
+        &mut self,
+        _: &mut PollParameters<'_>,
+    ) -> Async<
+        NetworkBehaviourAction<
+            <Self::ProtocolsHandler as ProtocolsHandler>::InEvent,
+            Self::OutEvent,
+        >,
+    > {
+        if let Some(address) = self.next_dial(Instant::now()) {
+            debug!(self.log, "Dialing static peer"; "address" => format!("{}", address));
+            return Async::Ready(NetworkBehaviourAction::DialAddress { address });
+        }
+
+        // Register for a wake-up when the next dial is due.
+        if let Some(deadline) = self.next_deadline() {
+            self.delay.reset(deadline);
+            if let Err(e) = self.delay.poll() {
+                warn!(self.log, "Static peer timer failed"; "error" => format!("{:?}", e));
+            }
+        }
+
+        Async::NotReady
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::Discard;
+
+    fn static_peers(addresses: &[&str]) -> StaticPeers<()> {
+        let log = slog::Logger::root(Discard, o!());
+        let addresses = addresses.iter().map(|a| a.parse().unwrap()).collect();
+        StaticPeers::new(addresses, &log)
+    }
+
+    #[test]
+    fn dials_all_peers_on_startup() {
+        let mut peers = static_peers(&["/memory/1", "/memory/2"]);
+        let now = Instant::now();
+
+        assert_eq!(peers.next_dial(now), Some("/memory/1".parse().unwrap()));
+        assert_eq!(peers.next_dial(now), Some("/memory/2".parse().unwrap()));
+        assert_eq!(peers.next_dial(now), None);
+    }
+
+    #[test]
+    fn failed_dials_back_off_exponentially() {
+        let mut peers = static_peers(&["/memory/1"]);
+        let now = Instant::now();
+
+        assert!(peers.next_dial(now).is_some());
+        assert!(peers.next_dial(now + INITIAL_BACKOFF / 2).is_none());
+        assert!(peers.next_dial(now + INITIAL_BACKOFF).is_some());
+        assert_eq!(
+            peers.next_deadline(),
+            Some(now + INITIAL_BACKOFF + INITIAL_BACKOFF * 2)
+        );
+    }
+
+    #[test]
+    fn redials_after_disconnection() {
+        let address: Multiaddr = "/memory/1".parse().unwrap();
+        let mut peers = static_peers(&["/memory/1"]);
+        let now = Instant::now();
+
+        peers.next_dial(now);
+        peers.on_connected(&address);
+        assert_eq!(peers.next_deadline(), None);
+
+        peers.on_disconnected(&address, now);
+        assert!(peers.next_dial(now).is_none());
+        assert_eq!(peers.next_dial(now + INITIAL_BACKOFF), Some(address));
+    }
+}
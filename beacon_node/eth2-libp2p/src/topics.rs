@@ -0,0 +1,91 @@
+//! Gossipsub topic names, which are prefixed with the fork digest so that nodes on different
+//! forks or chains do not share gossip.
+use serde_derive::{Deserialize, Serialize};
+use types::{ForkDigest, Topic, TopicBuilder};
+
+/// The topic on which blocks are gossiped.
+pub const BEACON_BLOCK_TOPIC: &str = "beacon_block";
+/// The topic on which attestations are gossiped.
+pub const BEACON_ATTESTATION_TOPIC: &str = "beacon_attestation";
+
+/// The kinds of message which are gossiped, each on its own topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GossipKind {
+    BeaconBlock,
+    BeaconAttestation,
+}
+
+impl GossipKind {
+    /// Every kind of gossiped message.
+    pub fn all() -> Vec<GossipKind> {
+        vec![GossipKind::BeaconBlock, GossipKind::BeaconAttestation]
+    }
+
+    /// Returns the topic name of this kind, without the fork digest prefix.
+    pub fn name(self) -> &'static str {
+        match self {
+            GossipKind::BeaconBlock => BEACON_BLOCK_TOPIC,
+            GossipKind::BeaconAttestation => BEACON_ATTESTATION_TOPIC,
+        }
+    }
+}
+
+/// The topic of a kind of message on the fork identified by `fork_digest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GossipTopic {
+    pub fork_digest: ForkDigest,
+    pub kind: GossipKind,
+}
+
+impl GossipTopic {
+    pub fn new(fork_digest: ForkDigest, kind: GossipKind) -> Self {
+        GossipTopic { fork_digest, kind }
+    }
+
+    /// Returns the full topic name, e.g., `/eth2/b5303f2a/beacon_block`.
+    pub fn name(&self) -> String {
+        topic_name(self.fork_digest, self.kind.name())
+    }
+
+    /// Returns the gossipsub topic.
+    pub fn topic(&self) -> Topic {
+        TopicBuilder::new(self.name()).build()
+    }
+}
+
+/// Returns the full topic name for `name` on the fork identified by `fork_digest`, e.g.,
+/// `/eth2/b5303f2a/beacon_block`.
+pub fn topic_name(fork_digest: ForkDigest, name: &str) -> String {
+    let digest: String = fork_digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("/eth2/{}/{}", digest, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_name_is_prefixed_with_hex_digest() {
+        assert_eq!(
+            GossipTopic::new([0xb5, 0x30, 0x3f, 0x2a], GossipKind::BeaconBlock).name(),
+            "/eth2/b5303f2a/beacon_block"
+        );
+        assert_ne!(
+            GossipTopic::new([0; 4], GossipKind::BeaconBlock)
+                .topic()
+                .hash(),
+            GossipTopic::new([1, 0, 0, 0], GossipKind::BeaconBlock)
+                .topic()
+                .hash()
+        );
+        assert_ne!(
+            GossipTopic::new([0; 4], GossipKind::BeaconBlock)
+                .topic()
+                .hash(),
+            GossipTopic::new([0; 4], GossipKind::BeaconAttestation)
+                .topic()
+                .hash()
+        );
+    }
+}
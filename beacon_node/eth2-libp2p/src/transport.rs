@@ -0,0 +1,233 @@
+//! Selection between the transports and addresses over which peers may be dialed, and on which
+//! the node listens.
+//!
+//! A peer may advertise both a TCP and a QUIC address. QUIC multiplexes streams without
+//! head-of-line blocking, which matters when streaming blocks and proofs over lossy links, but the
+//! pinned libp2p revision does not provide it yet, so QUIC addresses are recognised only to be
+//! skipped. The configured preference decides which address of each peer is dialed.
+use crate::multiaddr::{Multiaddr, Protocol};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::Ipv6Addr;
+
+/// A transport over which a peer may be dialed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    Tcp,
+    Quic,
+}
+
+impl TransportKind {
+    /// Returns the transport used to dial `address`, if it is one which may be preferred.
+    ///
+    /// QUIC is the only transport which runs over UDP.
+    pub fn of(address: &Multiaddr) -> Option<TransportKind> {
+        address.iter().find_map(|protocol| match protocol {
+            Protocol::Tcp(_) => Some(TransportKind::Tcp),
+            Protocol::Udp(_) => Some(TransportKind::Quic),
+            _ => None,
+        })
+    }
+
+    /// Parses a transport name, as given on the command line.
+    pub fn from_name(name: &str) -> Option<TransportKind> {
+        match name {
+            "tcp" => Some(TransportKind::Tcp),
+            "quic" => Some(TransportKind::Quic),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the node is able to dial or listen on this transport.
+    pub fn is_supported(self) -> bool {
+        match self {
+            TransportKind::Tcp => true,
+            TransportKind::Quic => false,
+        }
+    }
+}
+
+/// The default transport preference: TCP, the only supported transport.
+pub fn default_preference() -> Vec<TransportKind> {
+    vec![TransportKind::Tcp]
+}
+
+/// Returns `true` if `address` may be dialed or listened on by the node.
+pub fn is_supported(address: &Multiaddr) -> bool {
+    TransportKind::of(address).map_or(true, TransportKind::is_supported)
+}
+
+/// Returns the addresses to dial, most preferred first.
+///
+/// Addresses of unsupported transports are dropped. Of the addresses which share a `/p2p` peer
+/// id, only the most preferred is kept. Addresses of transports absent from `preference` (e.g.
+/// `/memory`) follow all others, and otherwise the given order is kept.
+pub fn select_dial_addresses(
+    addresses: Vec<Multiaddr>,
+    preference: &[TransportKind],
+) -> Vec<Multiaddr> {
+    let rank = |address: &Multiaddr| {
+        TransportKind::of(address)
+            .and_then(|kind| preference.iter().position(|preferred| *preferred == kind))
+            .unwrap_or_else(|| preference.len())
+    };
+
+    let mut addresses: Vec<Multiaddr> = addresses.into_iter().filter(is_supported).collect();
+    addresses.sort_by_key(rank);
+
+    let mut dialed_peers = HashSet::new();
+    addresses
+        .into_iter()
+        .filter(|address| match peer_id_bytes(address) {
+            Some(peer_id) => dialed_peers.insert(peer_id),
+            None => true,
+        })
+        .collect()
+}
+
+/// Returns `addresses` with an IPv6 counterpart (`/ip6/::`) of each unspecified IPv4 address
+/// (`/ip4/0.0.0.0`), so the node is reachable over both IPv4 and IPv6.
+///
+/// Each counterpart precedes its IPv4 address. Where IPv6 sockets also accept IPv4 connections
+/// (e.g. the Linux default), listening on the IPv4 address then fails, which is harmless.
+pub fn with_dual_stack(addresses: Vec<Multiaddr>) -> Vec<Multiaddr> {
+    let mut dual_stack = Vec::with_capacity(addresses.len() * 2);
+
+    for address in addresses {
+        let counterpart: Multiaddr = address
+            .iter()
+            .map(|protocol| match protocol {
+                Protocol::Ip4(ip) if ip.is_unspecified() => Protocol::Ip6(Ipv6Addr::UNSPECIFIED),
+                protocol => protocol,
+            })
+            .collect();
+
+        if counterpart != address && !dual_stack.contains(&counterpart) {
+            dual_stack.push(counterpart);
+        }
+        if !dual_stack.contains(&address) {
+            dual_stack.push(address);
+        }
+    }
+
+    dual_stack
+}
+
+/// Returns the TCP port of `address` if it is an unspecified IPv6 address, e.g. `/ip6/::/tcp/9000`.
+pub fn unspecified_ipv6_tcp_port(address: &Multiaddr) -> Option<u16> {
+    let mut protocols = address.iter();
+    match (protocols.next(), protocols.next()) {
+        (Some(Protocol::Ip6(ip)), Some(Protocol::Tcp(port))) if ip.is_unspecified() => Some(port),
+        _ => None,
+    }
+}
+
+/// Returns the TCP port of `address` if it is an unspecified IPv4 address, e.g.
+/// `/ip4/0.0.0.0/tcp/9000`.
+pub fn unspecified_ipv4_tcp_port(address: &Multiaddr) -> Option<u16> {
+    let mut protocols = address.iter();
+    match (protocols.next(), protocols.next()) {
+        (Some(Protocol::Ip4(ip)), Some(Protocol::Tcp(port))) if ip.is_unspecified() => Some(port),
+        _ => None,
+    }
+}
+
+/// Returns the TCP port of `address`, if it has one.
+pub fn tcp_port(address: &Multiaddr) -> Option<u16> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::Tcp(port) => Some(port),
+        _ => None,
+    })
+}
+
+/// Returns the bytes of the `/p2p` peer id of `address`, if it has one.
+fn peer_id_bytes(address: &Multiaddr) -> Option<Vec<u8>> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::P2p(multihash) => Some(multihash.as_bytes().to_vec()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::PeerId;
+
+    fn addresses(addresses: &[String]) -> Vec<Multiaddr> {
+        addresses.iter().map(|a| a.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn classifies_addresses() {
+        let tcp: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+        let quic: Multiaddr = "/ip4/127.0.0.1/udp/9000/quic".parse().unwrap();
+        let memory: Multiaddr = "/memory/1".parse().unwrap();
+
+        assert_eq!(TransportKind::of(&tcp), Some(TransportKind::Tcp));
+        assert_eq!(TransportKind::of(&quic), Some(TransportKind::Quic));
+        assert_eq!(TransportKind::of(&memory), None);
+        assert!(!is_supported(&quic));
+        assert!(is_supported(&memory));
+    }
+
+    #[test]
+    fn prefers_one_address_per_peer() {
+        let peer = PeerId::random().to_base58();
+        let other = PeerId::random().to_base58();
+        let given = addresses(&[
+            "/memory/1".to_string(),
+            format!("/ip4/127.0.0.1/tcp/9000/p2p/{}", peer),
+            format!("/ip4/127.0.0.1/udp/9000/quic/p2p/{}", peer),
+            format!("/ip4/127.0.0.2/tcp/9000/p2p/{}", other),
+        ]);
+
+        // QUIC addresses are unsupported, so dropped even when preferred.
+        let selected =
+            select_dial_addresses(given.clone(), &[TransportKind::Quic, TransportKind::Tcp]);
+        assert_eq!(
+            selected,
+            vec![given[1].clone(), given[3].clone(), given[0].clone()]
+        );
+
+        let selected = select_dial_addresses(given.clone(), &[TransportKind::Tcp]);
+        assert_eq!(
+            selected,
+            vec![given[1].clone(), given[3].clone(), given[0].clone()]
+        );
+    }
+
+    #[test]
+    fn adds_ipv6_counterparts() {
+        let given = addresses(&[
+            "/ip4/0.0.0.0/tcp/9000".to_string(),
+            "/ip4/127.0.0.1/tcp/9001".to_string(),
+            "/ip6/::/tcp/9002".to_string(),
+            "/ip4/0.0.0.0/tcp/9002".to_string(),
+        ]);
+
+        assert_eq!(
+            with_dual_stack(given),
+            addresses(&[
+                "/ip6/::/tcp/9000".to_string(),
+                "/ip4/0.0.0.0/tcp/9000".to_string(),
+                "/ip4/127.0.0.1/tcp/9001".to_string(),
+                "/ip6/::/tcp/9002".to_string(),
+                "/ip4/0.0.0.0/tcp/9002".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_ports() {
+        let ipv4: Multiaddr = "/ip4/0.0.0.0/tcp/9000".parse().unwrap();
+        let ipv6: Multiaddr = "/ip6/::/tcp/9001".parse().unwrap();
+        let local: Multiaddr = "/ip6/::1/tcp/9002".parse().unwrap();
+
+        assert_eq!(unspecified_ipv4_tcp_port(&ipv4), Some(9000));
+        assert_eq!(unspecified_ipv6_tcp_port(&ipv6), Some(9001));
+        assert_eq!(unspecified_ipv6_tcp_port(&local), None);
+        assert_eq!(unspecified_ipv4_tcp_port(&ipv6), None);
+        assert_eq!(tcp_port(&local), Some(9002));
+    }
+}
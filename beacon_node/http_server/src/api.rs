@@ -1,28 +1,66 @@
-use crate::{key::BeaconChainKey, map_persistent_err_to_500};
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use crate::endpoint::{Endpoint, Method};
+use crate::error::ApiError;
+use crate::finality_stream::handle_finality_stream;
+use crate::key::{BeaconChainKey, HistoricalProofsKey};
+use crate::map_persistent_err_to_500;
+use crate::openapi;
+use crate::proof_stream::handle_proof_stream;
+use beacon_chain::{BeaconChain, BeaconChainTypes, HistoricalProofError, HistoricalProofService};
+use bls::PublicKey;
 use iron::prelude::*;
 use iron::{
     headers::{CacheControl, CacheDirective, ContentType},
     status::Status,
     AfterMiddleware, Handler, IronResult, Request, Response,
 };
+use lightclient_protocol::{
+    ErrorCode, HeaderUpdate, HistoricalProofRequest, ProofRequest, ProofResponse,
+    ValidatorStatusProof, MAX_MESSAGE_BYTES, MAX_PROOF_INDICES,
+};
+use merkle_proof::{verify_partial, Multiproof, SerializedPartial};
 use persistent::Read;
 use router::Router;
+use serde_derive::{Deserialize, Serialize};
 use serde_json::json;
+use ssz::Encode;
+use std::io::Read as IoRead;
 use std::sync::Arc;
+use types::{
+    validator_status_indices, AttestationDuty, BeaconStateError, Epoch, EthSpec, Hash256,
+    RelativeEpoch, Slot,
+};
+
+/// The longest request body the HTTP API reads.
+///
+/// JSON encodes roots and proofs as hex, at over twice their size as SSZ, so this is a few times
+/// `MAX_MESSAGE_BYTES`.
+const MAX_REQUEST_BODY_BYTES: usize = 4 * MAX_MESSAGE_BYTES;
 
 /// Yields a handler for the HTTP API.
 pub fn build_handler<T: BeaconChainTypes + 'static>(
     beacon_chain: Arc<BeaconChain<T>>,
+    historical_proofs: Arc<HistoricalProofService<T>>,
 ) -> impl Handler {
     let mut router = Router::new();
 
-    router.get("/node/fork", handle_fork::<T>, "fork");
+    for &endpoint in Endpoint::ALL {
+        let method = match endpoint.method() {
+            Method::Get => iron::method::Get,
+            Method::Post => iron::method::Post,
+        };
+        router.route(
+            method,
+            endpoint.path(),
+            handler::<T>(endpoint),
+            endpoint.id(),
+        );
+    }
 
     let mut chain = Chain::new(router);
 
     // Insert `BeaconChain` so it may be accessed in a request.
     chain.link(Read::<BeaconChainKey<T>>::both(beacon_chain.clone()));
+    chain.link(Read::<HistoricalProofsKey<T>>::both(historical_proofs));
     // Set the content-type headers.
     chain.link_after(SetJsonContentType);
     // Set the cache headers.
@@ -31,6 +69,26 @@ pub fn build_handler<T: BeaconChainTypes + 'static>(
     chain
 }
 
+/// Returns the handler of `endpoint`.
+fn handler<T: BeaconChainTypes + 'static>(
+    endpoint: Endpoint,
+) -> fn(&mut Request) -> IronResult<Response> {
+    match endpoint {
+        Endpoint::NodeFork => handle_fork::<T>,
+        Endpoint::ValidatorDuties => handle_validator_duties::<T>,
+        Endpoint::ValidatorMonitor => handle_validator_monitor::<T>,
+        Endpoint::FinalityStream => handle_finality_stream::<T>,
+        Endpoint::ProofStream => handle_proof_stream::<T>,
+        Endpoint::VerifyPartial => handle_verify_partial,
+        Endpoint::Proof => handle_proof::<T>,
+        Endpoint::HistoricalProof => handle_historical_proof::<T>,
+        Endpoint::ValidatorStatus => handle_validator_status::<T>,
+        Endpoint::Header => handle_header::<T>,
+        Endpoint::Archive => handle_archive::<T>,
+        Endpoint::Spec => handle_spec,
+    }
+}
+
 /// Sets the `cache-control` headers on _all_ responses, unless they are already set.
 struct SetCacheDirectives;
 impl AfterMiddleware for SetCacheDirectives {
@@ -57,6 +115,14 @@ impl AfterMiddleware for SetJsonContentType {
     }
 }
 
+/// Returns the OpenAPI document describing this API.
+fn handle_spec(_req: &mut Request) -> IronResult<Response> {
+    Ok(Response::with((
+        Status::Ok,
+        openapi::document().to_string(),
+    )))
+}
+
 fn handle_fork<T: BeaconChainTypes + 'static>(req: &mut Request) -> IronResult<Response> {
     let beacon_chain = req
         .get::<Read<BeaconChainKey<T>>>()
@@ -69,3 +135,501 @@ fn handle_fork<T: BeaconChainTypes + 'static>(req: &mut Request) -> IronResult<R
 
     Ok(Response::with((Status::Ok, response.to_string())))
 }
+
+/// Returns the per-epoch performance of each validator tracked by the validator monitor.
+fn handle_validator_monitor<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let response = json!({
+        "validators": beacon_chain.validator_monitor.performance(),
+    });
+
+    Ok(Response::with((Status::Ok, response.to_string())))
+}
+
+/// The body of a `POST /validator/duties` request.
+#[derive(Deserialize)]
+struct ValidatorDutiesRequest {
+    epoch: Epoch,
+    pubkeys: Vec<PublicKey>,
+}
+
+/// The duties of a single validator, as returned from `POST /validator/duties`.
+///
+/// All duty fields are empty if the validator is unknown or inactive at the requested epoch.
+#[derive(Serialize)]
+struct ValidatorDuty {
+    validator_pubkey: PublicKey,
+    validator_index: Option<usize>,
+    block_proposal_slots: Vec<Slot>,
+    attestation_duty: Option<AttestationDuty>,
+}
+
+/// Returns the block proposal slots and attestation committee assignments for each of the given
+/// public keys.
+///
+/// Duties are read from the committee caches of the current state, so only the previous, current
+/// and next epochs may be requested.
+fn handle_validator_duties<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let body = match read_body(req) {
+        Ok(body) => body,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let request: ValidatorDutiesRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+
+    let slots_per_epoch = T::EthSpec::slots_per_epoch();
+
+    // Duties are read from the head, so are unreliable if the head is more than an epoch old.
+    let head_slot = beacon_chain.head().beacon_block.slot;
+    if let Some(present_slot) = beacon_chain.read_slot_clock() {
+        if present_slot > head_slot + slots_per_epoch {
+            return Ok(ApiError::NotSynced {
+                head_slot,
+                present_slot,
+            }
+            .into());
+        }
+    }
+
+    let state = beacon_chain.current_state();
+
+    let relative_epoch =
+        match RelativeEpoch::from_epoch(state.slot.epoch(slots_per_epoch), request.epoch) {
+            Ok(relative_epoch) => relative_epoch,
+            Err(e) => return Ok(bad_request(format!("Invalid epoch: {:?}", e))),
+        };
+
+    let proposers: Result<Vec<usize>, _> = request
+        .epoch
+        .slot_iter(slots_per_epoch)
+        .map(|slot| state.get_beacon_proposer_index(slot, relative_epoch, &beacon_chain.spec))
+        .collect();
+    let proposers = match proposers {
+        Ok(proposers) => proposers,
+        Err(e) => return Ok(server_error(format!("Unable to find proposers: {:?}", e))),
+    };
+
+    let mut duties = Vec::with_capacity(request.pubkeys.len());
+
+    for pubkey in request.pubkeys {
+        let validator_index = match state.get_validator_index(&pubkey) {
+            Ok(index) => index,
+            Err(e) => return Ok(server_error(format!("Beacon state error: {:?}", e))),
+        };
+
+        let (block_proposal_slots, attestation_duty) = match validator_index {
+            Some(index) => {
+                let block_proposal_slots = proposers
+                    .iter()
+                    .zip(request.epoch.slot_iter(slots_per_epoch))
+                    .filter(|(proposer, _)| **proposer == index)
+                    .map(|(_, slot)| slot)
+                    .collect();
+
+                let attestation_duty = match state.get_attestation_duties(index, relative_epoch) {
+                    Ok(duty) => duty,
+                    Err(e) => return Ok(server_error(format!("Beacon state error: {:?}", e))),
+                };
+
+                (block_proposal_slots, attestation_duty)
+            }
+            None => (vec![], None),
+        };
+
+        duties.push(ValidatorDuty {
+            validator_pubkey: pubkey,
+            validator_index,
+            block_proposal_slots,
+            attestation_duty,
+        });
+    }
+
+    match serde_json::to_string(&duties) {
+        Ok(body) => Ok(Response::with((Status::Ok, body))),
+        Err(e) => Ok(server_error(format!("Unable to serialize duties: {:?}", e))),
+    }
+}
+
+/// The body of a `POST /lightclient/verify` request.
+#[derive(Deserialize)]
+struct VerifyPartialRequest {
+    partial: SerializedPartial,
+    root: Hash256,
+}
+
+/// Checks a third-party proof against an expected root, returning whether it is valid, the
+/// deepest node which failed to verify and the leaves whose paths were covered.
+///
+/// The proof is not checked against any state known to this node.
+fn handle_verify_partial(req: &mut Request) -> IronResult<Response> {
+    let body = match read_body(req) {
+        Ok(body) => body,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let request: VerifyPartialRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+
+    let verification = match verify_partial(&request.partial, request.root) {
+        Ok(verification) => verification,
+        Err(e) => return Ok(bad_request(format!("Malformed partial: {:?}", e))),
+    };
+
+    match serde_json::to_string(&verification) {
+        Ok(body) => Ok(Response::with((Status::Ok, body))),
+        Err(e) => Ok(server_error(format!(
+            "Unable to serialize verification: {:?}",
+            e
+        ))),
+    }
+}
+
+/// Returns the header of the block at `:root`, which may be any block this node has imported.
+///
+/// A light client checks the header against the root, e.g., to bootstrap from a trusted
+/// checkpoint.
+fn handle_header<T: BeaconChainTypes + 'static>(req: &mut Request) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let param = req
+        .extensions
+        .get::<Router>()
+        .and_then(|params| params.find("root"))
+        .unwrap_or("");
+    let root: Hash256 = match serde_json::from_value(json!(param)) {
+        Ok(root) => root,
+        Err(_) => return Ok(bad_request(format!("Invalid block root: {}", param))),
+    };
+
+    let block = match beacon_chain.get_block(&root) {
+        Ok(Some(block)) => block,
+        Ok(None) => return Ok(ApiError::UnknownBlockRoot(root).into()),
+        Err(e) => return Ok(server_error(format!("Unable to read block: {:?}", e))),
+    };
+
+    let update = HeaderUpdate {
+        header: block.block_header(),
+    };
+    match serde_json::to_string(&update) {
+        Ok(body) => Ok(Response::with((Status::Ok, body))),
+        Err(e) => Ok(server_error(format!("Unable to serialize header: {:?}", e))),
+    }
+}
+
+/// Returns the roots of the block and state at the finalized slot `:slot`, which must have been
+/// archived, i.e., this must be an archive node.
+fn handle_archive<T: BeaconChainTypes + 'static>(req: &mut Request) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let param = req
+        .extensions
+        .get::<Router>()
+        .and_then(|params| params.find("slot"))
+        .unwrap_or("");
+    let slot = match param.parse::<u64>() {
+        Ok(slot) => Slot::new(slot),
+        Err(_) => return Ok(bad_request(format!("Invalid slot: {}", param))),
+    };
+
+    let archived = match beacon_chain.archived_slot(slot) {
+        Ok(Some(archived)) => archived,
+        Ok(None) => return Ok(ApiError::UnknownSlot(slot).into()),
+        Err(e) => return Ok(server_error(format!("Unable to read archive: {:?}", e))),
+    };
+
+    let body = json!({
+        "slot": slot,
+        "block_root": archived.block_root,
+        "state_root": archived.state_root,
+    });
+    Ok(Response::with((Status::Ok, body.to_string())))
+}
+
+/// Returns a proof of the nodes at the requested generalized indices of a state known to this
+/// node, which may be the state of any block it has imported.
+fn handle_proof<T: BeaconChainTypes + 'static>(req: &mut Request) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let body = match read_body(req) {
+        Ok(body) => body,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let request: ProofRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+    match request.validate() {
+        Ok(()) => {}
+        Err(ErrorCode::TooManyIndices) => {
+            return Ok(ApiError::TooManyIndices {
+                count: request.indices.len(),
+                max: MAX_PROOF_INDICES,
+            }
+            .into())
+        }
+        Err(code) => return Ok(bad_request(format!("Invalid request: {:?}", code))),
+    }
+
+    let state = match beacon_chain.get_state(&request.state_root) {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            let is_recent = beacon_chain
+                .head()
+                .beacon_state
+                .latest_state_roots
+                .contains(&request.state_root);
+            let error = if is_recent {
+                ApiError::PrunedState(request.state_root)
+            } else {
+                ApiError::UnknownStateRoot(request.state_root)
+            };
+            return Ok(error.into());
+        }
+        Err(e) => return Ok(server_error(format!("Unable to read state: {:?}", e))),
+    };
+
+    let proof = match state.prove(&request.indices) {
+        Ok(proof) => proof,
+        Err(BeaconStateError::UnsupportedProofIndex(index)) => {
+            return Ok(ApiError::UnsupportedIndex(index).into())
+        }
+        Err(e) => return Ok(server_error(format!("Unable to build proof: {:?}", e))),
+    };
+
+    proof_response(ProofResponse {
+        state_root: request.state_root,
+        proof,
+    })
+}
+
+/// Returns a proof of the nodes at the requested generalized indices of the state at a finalized
+/// slot, rebuilding the state if the slot was skipped.
+fn handle_historical_proof<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let historical_proofs = req
+        .get::<Read<HistoricalProofsKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let body = match read_body(req) {
+        Ok(body) => body,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let request: HistoricalProofRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+    match request.validate() {
+        Ok(()) => {}
+        Err(ErrorCode::TooManyIndices) => {
+            return Ok(ApiError::TooManyIndices {
+                count: request.indices.len(),
+                max: MAX_PROOF_INDICES,
+            }
+            .into())
+        }
+        Err(code) => return Ok(bad_request(format!("Invalid request: {:?}", code))),
+    }
+
+    let proof = match historical_proofs.prove(request.slot, &request.indices) {
+        Ok(proof) => proof,
+        Err(HistoricalProofError::NotFinalized {
+            slot,
+            finalized_slot,
+        }) => {
+            return Ok(ApiError::NotFinalized {
+                slot,
+                finalized_slot,
+            }
+            .into())
+        }
+        Err(HistoricalProofError::UnknownSlot(slot)) => {
+            return Ok(ApiError::UnknownSlot(slot).into())
+        }
+        Err(HistoricalProofError::ReplayTooLong {
+            slot,
+            replay_slots,
+            max,
+        }) => {
+            return Ok(ApiError::ReplayTooLong {
+                slot,
+                replay_slots,
+                max,
+            }
+            .into())
+        }
+        Err(HistoricalProofError::MissingState(root)) => {
+            return Ok(ApiError::PrunedState(root).into())
+        }
+        Err(HistoricalProofError::UnsupportedIndex(index)) => {
+            return Ok(ApiError::UnsupportedIndex(index).into())
+        }
+        Err(e) => return Ok(server_error(format!("Unable to build proof: {:?}", e))),
+    };
+
+    proof_response(ProofResponse {
+        state_root: proof.state_root,
+        proof: proof.proof,
+    })
+}
+
+/// Returns a proof of the status and balance of the validator with public key `:pubkey` against
+/// the latest finalized state, as a minimal multiproof.
+fn handle_validator_status<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let param = req
+        .extensions
+        .get::<Router>()
+        .and_then(|params| params.find("pubkey"))
+        .unwrap_or("");
+    let pubkey: PublicKey = match serde_json::from_value(json!(param)) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return Ok(bad_request(format!("Invalid public key: {}", param))),
+    };
+
+    let (finalized_epoch, finalized_root, state_root, state) = match beacon_chain.finalized_state()
+    {
+        Ok(finalized) => finalized,
+        Err(e) => {
+            return Ok(server_error(format!(
+                "Unable to read finalized state: {:?}",
+                e
+            )))
+        }
+    };
+
+    // The pubkey cache of a stored state may be incomplete, so the registry is scanned instead.
+    let index = match state
+        .validator_registry
+        .iter()
+        .position(|validator| validator.pubkey == pubkey)
+    {
+        Some(index) => index,
+        None => return Ok(ApiError::UnknownValidator(pubkey).into()),
+    };
+
+    let partial = match state.validator_status_proof(index) {
+        Ok(partial) => partial,
+        Err(BeaconStateError::UnknownValidator) => {
+            return Ok(ApiError::UnknownValidator(pubkey).into())
+        }
+        Err(e) => return Ok(server_error(format!("Unable to build proof: {:?}", e))),
+    };
+    let indices =
+        validator_status_indices(state.validator_registry.len(), state.balances.len(), index);
+    let proof = match Multiproof::from_partial(&partial, &indices) {
+        Ok(proof) => proof,
+        Err(e) => return Ok(server_error(format!("Unable to build proof: {:?}", e))),
+    };
+
+    proof_response(ValidatorStatusProof {
+        finalized_epoch,
+        finalized_root,
+        state_root,
+        validator_index: index as u64,
+        proof,
+    })
+}
+
+/// Serializes `response`, unless it is too large for a light client to accept.
+fn proof_response<M: Encode + serde::Serialize>(response: M) -> IronResult<Response> {
+    // A light client refuses any message larger than `MAX_MESSAGE_BYTES`.
+    let bytes = response.as_ssz_bytes().len();
+    if bytes > MAX_MESSAGE_BYTES {
+        return Ok(ApiError::ProofTooLarge {
+            bytes,
+            max: MAX_MESSAGE_BYTES,
+        }
+        .into());
+    }
+
+    match serde_json::to_string(&response) {
+        Ok(body) => Ok(Response::with((Status::Ok, body))),
+        Err(e) => Ok(server_error(format!("Unable to serialize proof: {:?}", e))),
+    }
+}
+
+/// Builds a `400 Bad Request` response carrying an `ApiError::InvalidRequest`.
+fn bad_request(message: String) -> Response {
+    ApiError::InvalidRequest(message).into()
+}
+
+/// Reads the body of `req` as a string, refusing any longer than `MAX_REQUEST_BODY_BYTES`.
+pub(crate) fn read_body(req: &mut Request) -> Result<String, ApiError> {
+    read_limited(&mut req.body, MAX_REQUEST_BODY_BYTES)
+}
+
+/// Reads `reader` to its end as a string, refusing it if it is longer than `max` bytes.
+///
+/// At most `max + 1` bytes are read, so an oversized body is not buffered.
+fn read_limited<R: IoRead>(reader: R, max: usize) -> Result<String, ApiError> {
+    let mut body = String::new();
+    reader
+        .take(max as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| ApiError::InvalidRequest(format!("Unable to read request body: {:?}", e)))?;
+
+    if body.len() > max {
+        Err(ApiError::RequestTooLarge { max })
+    } else {
+        Ok(body)
+    }
+}
+
+/// Builds a `500 Internal Server Error` response carrying an `ApiError::ServerError`.
+fn server_error(message: String) -> Response {
+    ApiError::ServerError(message).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_limited_refuses_long_bodies() {
+        let body = "a".repeat(8);
+
+        assert_eq!(read_limited(body.as_bytes(), 8), Ok(body.clone()));
+        assert_eq!(read_limited(body.as_bytes(), 9), Ok(body.clone()));
+        assert_eq!(
+            read_limited(body.as_bytes(), 7),
+            Err(ApiError::RequestTooLarge { max: 7 })
+        );
+    }
+
+    #[test]
+    fn request_too_large_is_413() {
+        let response: Response = ApiError::RequestTooLarge { max: 7 }.into();
+
+        assert_eq!(response.status, Some(Status::PayloadTooLarge));
+    }
+}
@@ -0,0 +1,11 @@
+//! Prints the OpenAPI document of the HTTP API, from which client SDKs are generated.
+//!
+//! The document is identical to that served at `/spec`.
+fn main() {
+    let document = http_server::openapi::document();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&document).expect("a JSON value is serializable")
+    );
+}
@@ -0,0 +1,418 @@
+//! The endpoints of the HTTP API, each declaring its method, path, parameters and body types.
+//!
+//! The router is built from these declarations, as is the OpenAPI document served at `/spec`, so
+//! the document cannot omit or misplace a route. A `Schema` mirrors the JSON serialization of the
+//! Rust type it is named after, and must be updated along with that type.
+
+/// An HTTP method of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    /// The lowercase name of the method, as used by OpenAPI.
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::Get => "get",
+            Method::Post => "post",
+        }
+    }
+}
+
+/// The JSON shape of a request or response body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schema {
+    Integer,
+    Boolean,
+    String,
+    /// A `0x`-prefixed hex string, e.g., a root or a public key.
+    Hex,
+    /// Any JSON value.
+    Any,
+    Nullable(&'static Schema),
+    Array(&'static Schema),
+    /// An object, named after its Rust type, with each of its fields.
+    Object(&'static str, &'static [(&'static str, Schema)]),
+}
+
+/// The body of a successful response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Body {
+    Json(Schema),
+    /// A server-sent events stream, in which each `event` carries a JSON value as its data.
+    EventStream {
+        event: &'static str,
+        data: Schema,
+    },
+}
+
+/// A parameter within the path of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathParam {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub schema: Schema,
+}
+
+const SERIALIZED_PARTIAL: Schema = Schema::Object(
+    "SerializedPartial",
+    &[
+        ("indices", Schema::Array(&Schema::Integer)),
+        ("chunks", Schema::Array(&Schema::Hex)),
+    ],
+);
+
+const BEACON_BLOCK_HEADER: Schema = Schema::Object(
+    "BeaconBlockHeader",
+    &[
+        ("slot", Schema::Integer),
+        ("previous_block_root", Schema::Hex),
+        ("state_root", Schema::Hex),
+        ("block_body_root", Schema::Hex),
+        ("signature", Schema::Hex),
+    ],
+);
+
+const NODE_FORK: Schema = Schema::Object(
+    "NodeFork",
+    &[
+        (
+            "fork",
+            Schema::Object(
+                "Fork",
+                &[
+                    ("previous_version", Schema::Hex),
+                    ("current_version", Schema::Hex),
+                    ("epoch", Schema::Integer),
+                ],
+            ),
+        ),
+        ("chain_id", Schema::Integer),
+    ],
+);
+
+const VALIDATOR_DUTIES_REQUEST: Schema = Schema::Object(
+    "ValidatorDutiesRequest",
+    &[
+        ("epoch", Schema::Integer),
+        ("pubkeys", Schema::Array(&Schema::Hex)),
+    ],
+);
+
+const VALIDATOR_DUTY: Schema = Schema::Object(
+    "ValidatorDuty",
+    &[
+        ("validator_pubkey", Schema::Hex),
+        ("validator_index", Schema::Nullable(&Schema::Integer)),
+        ("block_proposal_slots", Schema::Array(&Schema::Integer)),
+        (
+            "attestation_duty",
+            Schema::Nullable(&Schema::Object(
+                "AttestationDuty",
+                &[
+                    ("slot", Schema::Integer),
+                    ("shard", Schema::Integer),
+                    ("committee_index", Schema::Integer),
+                    ("committee_len", Schema::Integer),
+                ],
+            )),
+        ),
+    ],
+);
+
+const EPOCH_SUMMARY: Schema = Schema::Object(
+    "EpochSummary",
+    &[
+        ("epoch", Schema::Integer),
+        ("attested", Schema::Boolean),
+        ("inclusion_distance", Schema::Nullable(&Schema::Integer)),
+        ("balance", Schema::Integer),
+        ("balance_delta", Schema::Nullable(&Schema::Integer)),
+    ],
+);
+
+const VALIDATOR_MONITOR: Schema = Schema::Object(
+    "ValidatorMonitor",
+    &[(
+        "validators",
+        Schema::Array(&Schema::Object(
+            "ValidatorPerformance",
+            &[
+                ("pubkey", Schema::Hex),
+                ("index", Schema::Nullable(&Schema::Integer)),
+                ("missed_attestations", Schema::Integer),
+                ("epochs", Schema::Array(&EPOCH_SUMMARY)),
+            ],
+        )),
+    )],
+);
+
+const FINALITY_UPDATE: Schema = Schema::Object(
+    "FinalityUpdate",
+    &[
+        ("head_header", BEACON_BLOCK_HEADER),
+        ("head_state_root", Schema::Hex),
+        ("finalized_epoch", Schema::Integer),
+        ("finalized_root", Schema::Hex),
+        ("finality_proof", SERIALIZED_PARTIAL),
+        ("finalized_header", Schema::Nullable(&BEACON_BLOCK_HEADER)),
+    ],
+);
+
+const PROOF_SUBSCRIPTION: Schema = Schema::Object(
+    "ProofSubscription",
+    &[("indices", Schema::Array(&Schema::Integer))],
+);
+
+const PROOF_PUSH: Schema = Schema::Object(
+    "ProofPush",
+    &[
+        ("finalized_epoch", Schema::Integer),
+        ("finalized_root", Schema::Hex),
+        ("state_root", Schema::Hex),
+        ("proof", SERIALIZED_PARTIAL),
+    ],
+);
+
+const VERIFY_PARTIAL_REQUEST: Schema = Schema::Object(
+    "VerifyPartialRequest",
+    &[("partial", SERIALIZED_PARTIAL), ("root", Schema::Hex)],
+);
+
+const PARTIAL_VERIFICATION: Schema = Schema::Object(
+    "PartialVerification",
+    &[
+        ("valid", Schema::Boolean),
+        ("first_bad_node", Schema::Nullable(&Schema::Integer)),
+        ("covered_paths", Schema::Array(&Schema::Integer)),
+    ],
+);
+
+const PROOF_REQUEST: Schema = Schema::Object(
+    "ProofRequest",
+    &[
+        ("state_root", Schema::Hex),
+        ("indices", Schema::Array(&Schema::Integer)),
+    ],
+);
+
+const HISTORICAL_PROOF_REQUEST: Schema = Schema::Object(
+    "HistoricalProofRequest",
+    &[
+        ("slot", Schema::Integer),
+        ("indices", Schema::Array(&Schema::Integer)),
+    ],
+);
+
+const PROOF_RESPONSE: Schema = Schema::Object(
+    "ProofResponse",
+    &[("state_root", Schema::Hex), ("proof", SERIALIZED_PARTIAL)],
+);
+
+const VALIDATOR_STATUS_PROOF: Schema = Schema::Object(
+    "ValidatorStatusProof",
+    &[
+        ("finalized_epoch", Schema::Integer),
+        ("finalized_root", Schema::Hex),
+        ("state_root", Schema::Hex),
+        ("validator_index", Schema::Integer),
+        (
+            "proof",
+            Schema::Object(
+                "Multiproof",
+                &[
+                    ("indices", Schema::Array(&Schema::Integer)),
+                    ("leaves", Schema::Array(&Schema::Hex)),
+                    ("proof", Schema::Array(&Schema::Hex)),
+                ],
+            ),
+        ),
+    ],
+);
+
+const HEADER_UPDATE: Schema = Schema::Object("HeaderUpdate", &[("header", BEACON_BLOCK_HEADER)]);
+
+const ARCHIVED_SLOT: Schema = Schema::Object(
+    "ArchivedSlot",
+    &[
+        ("slot", Schema::Integer),
+        ("block_root", Schema::Hex),
+        ("state_root", Schema::Hex),
+    ],
+);
+
+/// The body of every refused request, i.e., a `lightclient_protocol::HttpError`.
+pub const HTTP_ERROR: Schema = Schema::Object(
+    "HttpError",
+    &[
+        ("code", Schema::Integer),
+        ("message", Schema::String),
+        ("details", Schema::Any),
+    ],
+);
+
+/// An endpoint of the HTTP API, other than `/metrics`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endpoint {
+    NodeFork,
+    ValidatorDuties,
+    ValidatorMonitor,
+    FinalityStream,
+    ProofStream,
+    VerifyPartial,
+    Proof,
+    HistoricalProof,
+    ValidatorStatus,
+    Header,
+    Archive,
+    Spec,
+}
+
+impl Endpoint {
+    /// Every endpoint, in the order they are routed and documented.
+    pub const ALL: &'static [Endpoint] = &[
+        Endpoint::NodeFork,
+        Endpoint::ValidatorDuties,
+        Endpoint::ValidatorMonitor,
+        Endpoint::FinalityStream,
+        Endpoint::ProofStream,
+        Endpoint::VerifyPartial,
+        Endpoint::Proof,
+        Endpoint::HistoricalProof,
+        Endpoint::ValidatorStatus,
+        Endpoint::Header,
+        Endpoint::Archive,
+        Endpoint::Spec,
+    ];
+
+    pub fn method(self) -> Method {
+        match self {
+            Endpoint::ValidatorDuties
+            | Endpoint::ProofStream
+            | Endpoint::VerifyPartial
+            | Endpoint::Proof
+            | Endpoint::HistoricalProof => Method::Post,
+            _ => Method::Get,
+        }
+    }
+
+    /// The path of the endpoint, in which each parameter is written as `:name`.
+    pub fn path(self) -> &'static str {
+        match self {
+            Endpoint::NodeFork => "/node/fork",
+            Endpoint::ValidatorDuties => "/validator/duties",
+            Endpoint::ValidatorMonitor => "/validator/monitor",
+            Endpoint::FinalityStream => "/lightclient/finality_stream",
+            Endpoint::ProofStream => "/lightclient/proof_stream",
+            Endpoint::VerifyPartial => "/lightclient/verify",
+            Endpoint::Proof => "/lightclient/proof",
+            Endpoint::HistoricalProof => "/lightclient/historical_proof",
+            Endpoint::ValidatorStatus => "/lightclient/validator/:pubkey",
+            Endpoint::Header => "/lightclient/header/:root",
+            Endpoint::Archive => "/beacon/archive/:slot",
+            Endpoint::Spec => "/spec",
+        }
+    }
+
+    /// A unique name of the endpoint, which is the route id and the OpenAPI operation id.
+    pub fn id(self) -> &'static str {
+        match self {
+            Endpoint::NodeFork => "fork",
+            Endpoint::ValidatorDuties => "validator_duties",
+            Endpoint::ValidatorMonitor => "validator_monitor",
+            Endpoint::FinalityStream => "finality_stream",
+            Endpoint::ProofStream => "proof_stream",
+            Endpoint::VerifyPartial => "verify",
+            Endpoint::Proof => "proof",
+            Endpoint::HistoricalProof => "historical_proof",
+            Endpoint::ValidatorStatus => "validator_status",
+            Endpoint::Header => "header",
+            Endpoint::Archive => "archive",
+            Endpoint::Spec => "spec",
+        }
+    }
+
+    pub fn summary(self) -> &'static str {
+        match self {
+            Endpoint::NodeFork => "The fork of the head state, and the chain id.",
+            Endpoint::ValidatorDuties => {
+                "The block proposal slots and attestation duties of validators at an epoch."
+            }
+            Endpoint::ValidatorMonitor => {
+                "The per-epoch performance of each validator tracked by the validator monitor."
+            }
+            Endpoint::FinalityStream => "Streams an update each time the finalized checkpoint changes.",
+            Endpoint::ProofStream => {
+                "Streams a proof of the subscribed indices each time the finalized checkpoint changes."
+            }
+            Endpoint::VerifyPartial => "Checks a proof against an expected root.",
+            Endpoint::Proof => "A proof of the requested indices of a known state.",
+            Endpoint::HistoricalProof => {
+                "A proof of the requested indices of the state at a finalized slot."
+            }
+            Endpoint::ValidatorStatus => {
+                "A proof of the status and balance of a validator in the latest finalized state."
+            }
+            Endpoint::Header => "The header of a known block.",
+            Endpoint::Archive => "The block and state roots of an archived slot.",
+            Endpoint::Spec => "This OpenAPI document.",
+        }
+    }
+
+    pub fn params(self) -> &'static [PathParam] {
+        match self {
+            Endpoint::ValidatorStatus => &[PathParam {
+                name: "pubkey",
+                description: "The public key of the validator.",
+                schema: Schema::Hex,
+            }],
+            Endpoint::Header => &[PathParam {
+                name: "root",
+                description: "The root of the block.",
+                schema: Schema::Hex,
+            }],
+            Endpoint::Archive => &[PathParam {
+                name: "slot",
+                description: "A finalized slot.",
+                schema: Schema::Integer,
+            }],
+            _ => &[],
+        }
+    }
+
+    /// The JSON body of a request, if the endpoint takes one.
+    pub fn request(self) -> Option<Schema> {
+        match self {
+            Endpoint::ValidatorDuties => Some(VALIDATOR_DUTIES_REQUEST),
+            Endpoint::ProofStream => Some(PROOF_SUBSCRIPTION),
+            Endpoint::VerifyPartial => Some(VERIFY_PARTIAL_REQUEST),
+            Endpoint::Proof => Some(PROOF_REQUEST),
+            Endpoint::HistoricalProof => Some(HISTORICAL_PROOF_REQUEST),
+            _ => None,
+        }
+    }
+
+    pub fn response(self) -> Body {
+        match self {
+            Endpoint::NodeFork => Body::Json(NODE_FORK),
+            Endpoint::ValidatorDuties => Body::Json(Schema::Array(&VALIDATOR_DUTY)),
+            Endpoint::ValidatorMonitor => Body::Json(VALIDATOR_MONITOR),
+            Endpoint::FinalityStream => Body::EventStream {
+                event: "finality_update",
+                data: FINALITY_UPDATE,
+            },
+            Endpoint::ProofStream => Body::EventStream {
+                event: "proof_push",
+                data: PROOF_PUSH,
+            },
+            Endpoint::VerifyPartial => Body::Json(PARTIAL_VERIFICATION),
+            Endpoint::Proof | Endpoint::HistoricalProof => Body::Json(PROOF_RESPONSE),
+            Endpoint::ValidatorStatus => Body::Json(VALIDATOR_STATUS_PROOF),
+            Endpoint::Header => Body::Json(HEADER_UPDATE),
+            Endpoint::Archive => Body::Json(ARCHIVED_SLOT),
+            Endpoint::Spec => Body::Json(Schema::Any),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use iron::{status::Status, Response};
+use lightclient_protocol::{ErrorCode, HttpError};
+use serde_json::json;
+use types::{Hash256, PublicKey, Slot};
+
+/// The reasons the HTTP API refuses a request.
+///
+/// Each is returned as an `HttpError`, i.e., as `{code, message, details}`, where `code` is the
+/// stable `ErrorCode` of the failure.
+#[derive(Debug, PartialEq)]
+pub enum ApiError {
+    /// The request is malformed, or could not be decoded.
+    InvalidRequest(String),
+    /// The body of the request is longer than `max` bytes.
+    RequestTooLarge {
+        max: usize,
+    },
+    TooManyIndices {
+        count: usize,
+        max: usize,
+    },
+    UnknownStateRoot(Hash256),
+    UnknownBlockRoot(Hash256),
+    /// No validator in the finalized state has the public key.
+    UnknownValidator(PublicKey),
+    /// The state is in the recent history of the head, but is no longer stored.
+    PrunedState(Hash256),
+    UnsupportedIndex(u64),
+    ProofTooLarge {
+        bytes: usize,
+        max: usize,
+    },
+    NotSynced {
+        head_slot: Slot,
+        present_slot: Slot,
+    },
+    /// A historical proof was requested for a slot which is not yet finalized.
+    NotFinalized {
+        slot: Slot,
+        finalized_slot: Slot,
+    },
+    /// No block is known at or before the slot.
+    UnknownSlot(Slot),
+    /// Rebuilding the state of the slot would replay more slots than allowed.
+    ReplayTooLong {
+        slot: Slot,
+        replay_slots: u64,
+        max: u64,
+    },
+    ServerError(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            ApiError::RequestTooLarge { .. } => ErrorCode::RequestTooLarge,
+            ApiError::TooManyIndices { .. } => ErrorCode::TooManyIndices,
+            ApiError::UnknownStateRoot(_) => ErrorCode::UnknownStateRoot,
+            ApiError::UnknownBlockRoot(_) => ErrorCode::UnknownBlockRoot,
+            ApiError::UnknownValidator(_) => ErrorCode::UnknownValidator,
+            ApiError::PrunedState(_) => ErrorCode::PrunedState,
+            ApiError::UnsupportedIndex(_) => ErrorCode::UnsupportedIndex,
+            ApiError::ProofTooLarge { .. } => ErrorCode::ProofTooLarge,
+            ApiError::NotSynced { .. } => ErrorCode::NotSynced,
+            ApiError::NotFinalized { .. } => ErrorCode::InvalidRequest,
+            ApiError::UnknownSlot(_) => ErrorCode::UnknownStateRoot,
+            ApiError::ReplayTooLong { .. } => ErrorCode::PrunedState,
+            ApiError::ServerError(_) => ErrorCode::ServerError,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::InvalidRequest(message) | ApiError::ServerError(message) => message.clone(),
+            ApiError::RequestTooLarge { max } => {
+                format!("Request body exceeds the limit of {} bytes", max)
+            }
+            ApiError::TooManyIndices { count, max } => {
+                format!("Requested {} indices, at most {} are allowed", count, max)
+            }
+            ApiError::UnknownStateRoot(_) => "Unknown state root".to_string(),
+            ApiError::UnknownBlockRoot(_) => "Unknown block root".to_string(),
+            ApiError::UnknownValidator(_) => "Unknown validator".to_string(),
+            ApiError::PrunedState(_) => "State is no longer stored".to_string(),
+            ApiError::UnsupportedIndex(index) => format!("Unable to prove index {}", index),
+            ApiError::ProofTooLarge { bytes, max } => {
+                format!("Proof is {} bytes, at most {} are allowed", bytes, max)
+            }
+            ApiError::NotSynced {
+                head_slot,
+                present_slot,
+            } => format!(
+                "Head is at slot {}, the present slot is {}",
+                head_slot, present_slot
+            ),
+            ApiError::NotFinalized {
+                slot,
+                finalized_slot,
+            } => format!(
+                "Slot {} is not finalized, the finalized slot is {}",
+                slot, finalized_slot
+            ),
+            ApiError::UnknownSlot(slot) => format!("No block is known at or before slot {}", slot),
+            ApiError::ReplayTooLong {
+                replay_slots, max, ..
+            } => format!(
+                "Rebuilding the state requires {} slots, at most {} are allowed",
+                replay_slots, max
+            ),
+        }
+    }
+
+    /// Returns the values describing the failure, if any.
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            ApiError::InvalidRequest(_) | ApiError::ServerError(_) => serde_json::Value::Null,
+            ApiError::RequestTooLarge { max } => json!({ "max": max }),
+            ApiError::TooManyIndices { count, max } => json!({ "count": count, "max": max }),
+            ApiError::UnknownStateRoot(root) | ApiError::PrunedState(root) => {
+                json!({ "state_root": root })
+            }
+            ApiError::UnknownBlockRoot(root) => json!({ "block_root": root }),
+            ApiError::UnknownValidator(pubkey) => json!({ "pubkey": pubkey }),
+            ApiError::UnsupportedIndex(index) => json!({ "index": index }),
+            ApiError::ProofTooLarge { bytes, max } => json!({ "bytes": bytes, "max": max }),
+            ApiError::NotSynced {
+                head_slot,
+                present_slot,
+            } => json!({ "head_slot": head_slot, "present_slot": present_slot }),
+            ApiError::NotFinalized {
+                slot,
+                finalized_slot,
+            } => json!({ "slot": slot, "finalized_slot": finalized_slot }),
+            ApiError::UnknownSlot(slot) => json!({ "slot": slot }),
+            ApiError::ReplayTooLong {
+                slot,
+                replay_slots,
+                max,
+            } => json!({ "slot": slot, "replay_slots": replay_slots, "max": max }),
+        }
+    }
+}
+
+impl From<ApiError> for Response {
+    fn from(error: ApiError) -> Response {
+        let code = error.code();
+        let status = Status::from_u16(code.http_status());
+        let body = HttpError::new(code, error.message(), error.details());
+
+        let body = serde_json::to_string(&body)
+            .unwrap_or_else(|_| json!({ "code": code.as_u64() }).to_string());
+
+        Response::with((status, body))
+    }
+}
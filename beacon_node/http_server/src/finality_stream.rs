@@ -0,0 +1,117 @@
+use crate::{key::BeaconChainKey, map_persistent_err_to_500};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use iron::headers::ContentType;
+use iron::mime::Mime;
+use iron::response::WriteBody;
+use iron::{status::Status, IronResult, Request, Response};
+use lightclient_protocol::FinalityUpdate;
+use persistent::Read;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use types::{Epoch, Hash256};
+
+/// How often each open stream checks the chain for a new finalized checkpoint.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The longest a stream may go without writing, after which a comment is written so that a
+/// disconnected client is detected.
+pub(crate) const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Opens a server-sent events stream which emits a `finality_update` event with the current
+/// finalized checkpoint, then another each time the finalized checkpoint changes.
+///
+/// Each open stream occupies one of the server's worker threads until the client disconnects.
+pub fn handle_finality_stream<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let mut response = Response::with(Status::Ok);
+    response.headers.set(ContentType(event_stream_mime()));
+    response.body = Some(Box::new(FinalityStream {
+        beacon_chain,
+        last_sent: None,
+    }));
+
+    Ok(response)
+}
+
+pub(crate) fn event_stream_mime() -> Mime {
+    "text/event-stream"
+        .parse()
+        .expect("text/event-stream is a valid mime type")
+}
+
+/// A response body which writes finality updates until the client disconnects.
+struct FinalityStream<T: BeaconChainTypes> {
+    beacon_chain: Arc<BeaconChain<T>>,
+    /// The finalized checkpoint of the last update written to the client.
+    last_sent: Option<(Epoch, Hash256)>,
+}
+
+impl<T: BeaconChainTypes> FinalityStream<T> {
+    /// Returns an update if the finalized checkpoint has changed since the last update was sent.
+    fn next_update(&mut self) -> io::Result<Option<FinalityUpdate>> {
+        let update = {
+            let head = self.beacon_chain.head();
+            let checkpoint = (
+                head.beacon_state.finalized_epoch,
+                head.beacon_state.finalized_root,
+            );
+
+            if self.last_sent == Some(checkpoint) {
+                return Ok(None);
+            }
+            self.last_sent = Some(checkpoint);
+
+            let (_, finality_proof) = head.beacon_state.finality_proof();
+
+            FinalityUpdate {
+                head_header: head.beacon_block.block_header(),
+                head_state_root: head.beacon_state_root,
+                finalized_epoch: checkpoint.0,
+                finalized_root: checkpoint.1,
+                finality_proof,
+                finalized_header: None,
+            }
+        };
+
+        let finalized_block = self
+            .beacon_chain
+            .get_block(&update.finalized_root)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))?;
+
+        Ok(Some(FinalityUpdate {
+            finalized_header: finalized_block.map(|block| block.block_header()),
+            ..update
+        }))
+    }
+}
+
+impl<T: BeaconChainTypes> WriteBody for FinalityStream<T> {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        let mut last_write = Instant::now();
+
+        // Returns once a write fails, i.e., when the client has disconnected.
+        loop {
+            if let Some(update) = self.next_update()? {
+                let data = serde_json::to_string(&update)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                write!(res, "event: finality_update\ndata: {}\n\n", data)?;
+                res.flush()?;
+                last_write = Instant::now();
+            } else if last_write.elapsed() >= KEEP_ALIVE_INTERVAL {
+                write!(res, ": keep-alive\n\n")?;
+                res.flush()?;
+                last_write = Instant::now();
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::metrics::LocalMetrics;
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{BeaconChain, BeaconChainTypes, HistoricalProofService};
 use iron::typemap::Key;
 use prometheus::Registry;
 use std::marker::PhantomData;
@@ -14,6 +14,14 @@ impl<T: BeaconChainTypes + 'static> Key for BeaconChainKey<T> {
     type Value = Arc<BeaconChain<T>>;
 }
 
+pub struct HistoricalProofsKey<T> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: BeaconChainTypes + 'static> Key for HistoricalProofsKey<T> {
+    type Value = Arc<HistoricalProofService<T>>;
+}
+
 pub struct MetricsRegistryKey;
 
 impl Key for MetricsRegistryKey {
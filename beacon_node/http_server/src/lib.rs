@@ -1,8 +1,15 @@
 mod api;
+pub mod endpoint;
+mod error;
+mod finality_stream;
 mod key;
 mod metrics;
+pub mod openapi;
+mod proof_stream;
 
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{
+    BeaconChain, BeaconChainTypes, HistoricalProofService, DEFAULT_MAX_REPLAY_SLOTS,
+};
 use clap::ArgMatches;
 use futures::Future;
 use iron::prelude::*;
@@ -20,6 +27,8 @@ pub struct HttpServerConfig {
     pub enabled: bool,
     pub listen_address: String,
     pub listen_port: String,
+    /// The most slots a single historical proof request may replay.
+    pub max_replay_slots: u64,
 }
 
 impl Default for HttpServerConfig {
@@ -28,6 +37,7 @@ impl Default for HttpServerConfig {
             enabled: false,
             listen_address: "127.0.0.1".to_string(),
             listen_port: "5052".to_string(),
+            max_replay_slots: DEFAULT_MAX_REPLAY_SLOTS,
         }
     }
 }
@@ -46,6 +56,12 @@ impl HttpServerConfig {
             self.listen_port = listen_port.to_string();
         }
 
+        if let Some(max_replay_slots) = args.value_of("http-max-replay-slots") {
+            self.max_replay_slots = max_replay_slots
+                .parse()
+                .map_err(|_| "http-max-replay-slots is not u64")?;
+        }
+
         Ok(())
     }
 }
@@ -55,8 +71,13 @@ pub fn create_iron_http_server<T: BeaconChainTypes + 'static>(
     beacon_chain: Arc<BeaconChain<T>>,
     db_path: PathBuf,
     metrics_registry: Registry,
+    max_replay_slots: u64,
 ) -> Iron<Router> {
     let mut router = Router::new();
+    let historical_proofs = Arc::new(HistoricalProofService::new(
+        beacon_chain.clone(),
+        max_replay_slots,
+    ));
 
     // A `GET` request to `/metrics` is handled by the `metrics` module.
     router.get(
@@ -66,7 +87,11 @@ pub fn create_iron_http_server<T: BeaconChainTypes + 'static>(
     );
 
     // Any request to all other endpoints is handled by the `api` module.
-    router.any("/*", api::build_handler(beacon_chain.clone()), "api");
+    router.any(
+        "/*",
+        api::build_handler(beacon_chain.clone(), historical_proofs),
+        "api",
+    );
 
     Iron::new(router)
 }
@@ -89,7 +114,12 @@ pub fn start_service<T: BeaconChainTypes + 'static>(
     let (shutdown_trigger, wait_for_shutdown) = exit_future::signal();
 
     // Create an `iron` http, without starting it yet.
-    let iron = create_iron_http_server(beacon_chain, db_path, metrics_registry);
+    let iron = create_iron_http_server(
+        beacon_chain,
+        db_path,
+        metrics_registry,
+        config.max_replay_slots,
+    );
 
     // Create a HTTP server future.
     //
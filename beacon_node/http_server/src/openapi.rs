@@ -0,0 +1,177 @@
+//! Generates the OpenAPI document of the HTTP API from the declarations of its endpoints.
+//!
+//! Client SDKs are generated from this document, either as served at `/spec` or as printed by the
+//! `openapi` binary.
+use crate::endpoint::{Body, Endpoint, Schema, HTTP_ERROR};
+use serde_json::{json, Map, Value};
+
+/// The version of the OpenAPI specification the document conforms to.
+pub const OPENAPI_VERSION: &str = "3.0.2";
+
+/// Returns the OpenAPI document describing every endpoint.
+pub fn document() -> Value {
+    let mut schemas = Map::new();
+    let mut paths = Map::new();
+
+    let error = schema(&HTTP_ERROR, &mut schemas);
+
+    for &endpoint in Endpoint::ALL {
+        let mut operation = json!({
+            "operationId": endpoint.id(),
+            "summary": endpoint.summary(),
+            "parameters": endpoint
+                .params()
+                .iter()
+                .map(|param| json!({
+                    "name": param.name,
+                    "in": "path",
+                    "required": true,
+                    "description": param.description,
+                    "schema": schema(&param.schema, &mut schemas),
+                }))
+                .collect::<Vec<_>>(),
+            "responses": {
+                "200": response(endpoint.response(), &mut schemas),
+                "default": {
+                    "description": "The request was refused.",
+                    "content": { "application/json": { "schema": error.clone() } },
+                },
+            },
+        });
+
+        if let Some(request) = endpoint.request() {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": schema(&request, &mut schemas) } },
+            });
+        }
+
+        paths
+            .entry(openapi_path(endpoint.path()))
+            .or_insert_with(|| json!({}))[endpoint.method().name()] = operation;
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "Lighthouse HTTP API",
+            "version": version::version(),
+        },
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+/// Converts a path with `:name` parameters to one with `{name}` parameters.
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                format!("{{{}}}", &segment[1..])
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn response(body: Body, schemas: &mut Map<String, Value>) -> Value {
+    match body {
+        Body::Json(data) => json!({
+            "description": "Success.",
+            "content": { "application/json": { "schema": schema(&data, schemas) } },
+        }),
+        Body::EventStream { event, data } => json!({
+            "description": format!(
+                "A server-sent events stream of `{}` events, each with JSON data.",
+                event
+            ),
+            "content": { "text/event-stream": { "schema": { "type": "string" } } },
+            "x-events": { event: schema(&data, schemas) },
+        }),
+    }
+}
+
+/// Returns the OpenAPI schema of `schema`, adding each object it contains to `schemas` and
+/// referring to it by name.
+fn schema(schema: &Schema, schemas: &mut Map<String, Value>) -> Value {
+    match schema {
+        Schema::Integer => json!({ "type": "integer" }),
+        Schema::Boolean => json!({ "type": "boolean" }),
+        Schema::String => json!({ "type": "string" }),
+        Schema::Hex => json!({ "type": "string", "pattern": "^0x[0-9a-fA-F]*$" }),
+        Schema::Any => json!({}),
+        // A reference may not have siblings, so a nullable object is wrapped in `allOf`.
+        Schema::Nullable(inner @ Schema::Object(..)) => {
+            json!({ "allOf": [self::schema(inner, schemas)], "nullable": true })
+        }
+        Schema::Nullable(inner) => {
+            let mut inner = self::schema(inner, schemas);
+            inner["nullable"] = json!(true);
+            inner
+        }
+        Schema::Array(item) => json!({ "type": "array", "items": self::schema(item, schemas) }),
+        Schema::Object(name, fields) => {
+            if !schemas.contains_key(*name) {
+                let mut properties = Map::new();
+                let mut required = vec![];
+                for (field, field_schema) in fields.iter() {
+                    properties.insert(field.to_string(), self::schema(field_schema, schemas));
+                    match field_schema {
+                        Schema::Nullable(_) | Schema::Any => {}
+                        _ => required.push(field.to_string()),
+                    }
+                }
+                schemas.insert(
+                    name.to_string(),
+                    json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    }),
+                );
+            }
+            json!({ "$ref": format!("#/components/schemas/{}", name) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_paths() {
+        assert_eq!(
+            openapi_path("/lightclient/header/:root"),
+            "/lightclient/header/{root}"
+        );
+        assert_eq!(openapi_path("/spec"), "/spec");
+    }
+
+    #[test]
+    fn documents_every_endpoint() {
+        let document = document();
+
+        for &endpoint in Endpoint::ALL {
+            let operation =
+                &document["paths"][openapi_path(endpoint.path())][endpoint.method().name()];
+            assert_eq!(operation["operationId"], json!(endpoint.id()));
+            assert_eq!(
+                operation["parameters"].as_array().map(Vec::len),
+                Some(endpoint.params().len())
+            );
+        }
+
+        let schemas = &document["components"]["schemas"];
+        assert_eq!(
+            schemas["ProofResponse"]["properties"]["proof"],
+            json!({ "$ref": "#/components/schemas/SerializedPartial" })
+        );
+        assert_eq!(
+            schemas["ValidatorDuty"]["required"],
+            json!(["validator_pubkey", "block_proposal_slots"])
+        );
+    }
+}
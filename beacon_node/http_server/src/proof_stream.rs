@@ -0,0 +1,141 @@
+use crate::api::read_body;
+use crate::error::ApiError;
+use crate::finality_stream::{event_stream_mime, KEEP_ALIVE_INTERVAL, POLL_INTERVAL};
+use crate::{key::BeaconChainKey, map_persistent_err_to_500};
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use iron::headers::ContentType;
+use iron::response::WriteBody;
+use iron::{status::Status, IronResult, Request, Response};
+use lightclient_protocol::{ErrorCode, ProofPush, ProofSubscription, MAX_PROOF_INDICES};
+use merkle_proof::MerkleTreeOverlay;
+use persistent::Read;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use types::{BeaconState, Epoch, Hash256};
+
+/// Opens a server-sent events stream which emits a `proof_push` event with a proof of the
+/// subscribed indices against the state of the finalized block, then another each time the
+/// finalized checkpoint changes.
+///
+/// The body of the request is a `ProofSubscription`, which may not be empty. As with the finality
+/// stream, each open stream occupies one of the server's worker threads.
+pub fn handle_proof_stream<T: BeaconChainTypes + 'static>(
+    req: &mut Request,
+) -> IronResult<Response> {
+    let beacon_chain = req
+        .get::<Read<BeaconChainKey<T>>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let body = match read_body(req) {
+        Ok(body) => body,
+        Err(e) => return Ok(e.into()),
+    };
+
+    let subscription: ProofSubscription = match serde_json::from_str(&body) {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            return Ok(ApiError::InvalidRequest(format!("Invalid request body: {:?}", e)).into())
+        }
+    };
+    match subscription.validate() {
+        Ok(()) if !subscription.indices.is_empty() => {}
+        Err(ErrorCode::TooManyIndices) => {
+            return Ok(ApiError::TooManyIndices {
+                count: subscription.indices.len(),
+                max: MAX_PROOF_INDICES,
+            }
+            .into())
+        }
+        _ => {
+            return Ok(
+                ApiError::InvalidRequest("Subscription must be non-empty and valid".into()).into(),
+            )
+        }
+    }
+
+    let unattached = subscription
+        .indices
+        .iter()
+        .find(|&&index| !BeaconState::<T::EthSpec>::is_attached(index));
+    if let Some(&index) = unattached {
+        return Ok(ApiError::UnsupportedIndex(index).into());
+    }
+
+    let mut response = Response::with(Status::Ok);
+    response.headers.set(ContentType(event_stream_mime()));
+    response.body = Some(Box::new(ProofStream {
+        beacon_chain,
+        indices: subscription.indices,
+        last_sent: None,
+    }));
+
+    Ok(response)
+}
+
+/// A response body which writes proof pushes until the client disconnects.
+struct ProofStream<T: BeaconChainTypes> {
+    beacon_chain: Arc<BeaconChain<T>>,
+    indices: Vec<u64>,
+    /// The finalized checkpoint of the last push written to the client.
+    last_sent: Option<(Epoch, Hash256)>,
+}
+
+impl<T: BeaconChainTypes> ProofStream<T> {
+    /// Returns a push if the finalized checkpoint has changed since the last push was sent.
+    fn next_push(&mut self) -> io::Result<Option<ProofPush>> {
+        let checkpoint = {
+            let head = self.beacon_chain.head();
+            (
+                head.beacon_state.finalized_epoch,
+                head.beacon_state.finalized_root,
+            )
+        };
+        if self.last_sent == Some(checkpoint) {
+            return Ok(None);
+        }
+
+        let (finalized_epoch, finalized_root, state_root, state) =
+            match self.beacon_chain.finalized_state() {
+                Ok(finalized) => finalized,
+                // E.g., no block has been finalized yet.
+                Err(_) => return Ok(None),
+            };
+        let proof = state
+            .prove(&self.indices)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))?;
+        self.last_sent = Some((finalized_epoch, finalized_root));
+
+        Ok(Some(ProofPush {
+            finalized_epoch,
+            finalized_root,
+            state_root,
+            proof,
+        }))
+    }
+}
+
+impl<T: BeaconChainTypes> WriteBody for ProofStream<T> {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        let mut last_write = Instant::now();
+
+        // Returns once a write fails, i.e., when the client has disconnected.
+        loop {
+            if let Some(push) = self.next_push()? {
+                let data = serde_json::to_string(&push)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                write!(res, "event: proof_push\ndata: {}\n\n", data)?;
+                res.flush()?;
+                last_write = Instant::now();
+            } else if last_write.elapsed() >= KEEP_ALIVE_INTERVAL {
+                write!(res, ": keep-alive\n\n")?;
+                res.flush()?;
+                last_write = Instant::now();
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
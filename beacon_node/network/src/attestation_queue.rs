@@ -0,0 +1,99 @@
+use eth2_libp2p::PeerId;
+use std::time::{Duration, Instant};
+use types::Attestation;
+
+/// The longest an attestation may wait in the queue before the batch containing it is processed.
+pub const BATCH_WINDOW: Duration = Duration::from_millis(50);
+
+/// The largest number of attestations processed in a single batch.
+pub const MAX_BATCH_SIZE: usize = 128;
+
+/// Collects attestations received via gossip so that their signatures may be verified together.
+///
+/// A batch is due once `MAX_BATCH_SIZE` attestations are queued, or `BATCH_WINDOW` after the first
+/// attestation of the batch arrived.
+#[derive(Default)]
+pub struct AttestationQueue {
+    pending: Vec<(PeerId, Attestation)>,
+    /// When the oldest attestation in `pending` was queued.
+    oldest: Option<Instant>,
+}
+
+impl AttestationQueue {
+    /// Adds an attestation received from `peer_id` to the queue.
+    pub fn push(&mut self, peer_id: PeerId, attestation: Attestation) {
+        if self.pending.is_empty() {
+            self.oldest = Some(Instant::now());
+        }
+        self.pending.push((peer_id, attestation));
+    }
+
+    /// Returns the time remaining until the current batch is due, or `None` if the queue is empty.
+    pub fn time_until_due(&self, now: Instant) -> Option<Duration> {
+        if self.pending.len() >= MAX_BATCH_SIZE {
+            return Some(Duration::from_secs(0));
+        }
+
+        self.oldest.map(|oldest| {
+            let deadline = oldest + BATCH_WINDOW;
+            if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            }
+        })
+    }
+
+    /// Returns `true` if the current batch should be processed.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.time_until_due(now) == Some(Duration::from_secs(0))
+    }
+
+    /// Removes and returns all queued attestations.
+    pub fn drain(&mut self) -> Vec<(PeerId, Attestation)> {
+        self.oldest = None;
+        std::mem::replace(&mut self.pending, vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{AggregateSignature, AttestationData, Bitfield};
+
+    fn attestation() -> Attestation {
+        Attestation {
+            aggregation_bitfield: Bitfield::new(),
+            data: AttestationData::default(),
+            custody_bitfield: Bitfield::new(),
+            signature: AggregateSignature::new(),
+        }
+    }
+
+    #[test]
+    fn batch_is_due_after_window() {
+        let mut queue = AttestationQueue::default();
+        let now = Instant::now();
+
+        assert_eq!(queue.time_until_due(now), None);
+        assert!(!queue.is_due(now));
+
+        queue.push(PeerId::random(), attestation());
+        assert!(!queue.is_due(Instant::now()));
+        assert!(queue.is_due(Instant::now() + BATCH_WINDOW));
+
+        assert_eq!(queue.drain().len(), 1);
+        assert_eq!(queue.time_until_due(Instant::now()), None);
+    }
+
+    #[test]
+    fn batch_is_due_when_full() {
+        let mut queue = AttestationQueue::default();
+
+        for _ in 0..MAX_BATCH_SIZE {
+            queue.push(PeerId::random(), attestation());
+        }
+
+        assert!(queue.is_due(Instant::now()));
+    }
+}
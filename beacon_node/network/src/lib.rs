@@ -1,6 +1,9 @@
 /// This crate provides the network server for Lighthouse.
+mod attestation_queue;
 pub mod error;
 pub mod message_handler;
+mod nat;
+mod proof_subscriptions;
 pub mod service;
 pub mod sync;
 
@@ -1,12 +1,16 @@
+use crate::attestation_queue::AttestationQueue;
 use crate::error;
+use crate::proof_subscriptions::ProofSubscriptions;
 use crate::service::{NetworkMessage, OutgoingMessage};
-use crate::sync::SimpleSync;
+use crate::sync::{BackfillSync, SimpleSync};
 use beacon_chain::{BeaconChain, BeaconChainTypes};
-use crossbeam_channel::{unbounded as channel, Sender};
+use crossbeam_channel::{unbounded as channel, RecvTimeoutError, Sender};
 use eth2_libp2p::{
-    behaviour::PubsubMessage,
-    rpc::{methods::GoodbyeReason, RPCRequest, RPCResponse, RequestId},
-    PeerId, RPCEvent,
+    rpc::{
+        methods::{GoodbyeReason, MetaData, Ping, ProofPush, ProofSubscription},
+        ErrorResponse, RPCErrorCode, RPCRequest, RPCResponse, RequestId,
+    },
+    PeerId, PubsubMessage, RPCEvent,
 };
 use futures::future;
 use slog::{debug, warn};
@@ -22,11 +26,21 @@ use std::time::Instant;
 /// Handles messages received from the network and client and organises syncing.
 pub struct MessageHandler<T: BeaconChainTypes> {
     /// Currently loaded and initialised beacon chain.
-    _chain: Arc<BeaconChain<T>>,
+    chain: Arc<BeaconChain<T>>,
     /// The syncing framework.
     sync: SimpleSync<T>,
+    /// Downloads blocks prior to the checkpoint the chain was started from.
+    backfill: BackfillSync<T>,
     /// The context required to send messages to, and process messages from peers.
     network_context: NetworkContext,
+    /// Gossip attestations awaiting batch verification.
+    attestation_queue: AttestationQueue,
+    /// The `MetaData` we advertise to peers.
+    meta_data: MetaData,
+    /// The most recent `MetaData` received from each connected peer.
+    peer_meta_data: HashMap<PeerId, MetaData>,
+    /// The peers to push proofs to when finality advances.
+    proof_subscriptions: ProofSubscriptions,
     /// The `MessageHandler` logger.
     log: slog::Logger,
 }
@@ -59,11 +73,17 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
         // Initialise sync and begin processing in thread
         // generate the Message handler
         let sync = SimpleSync::new(beacon_chain.clone(), &log);
+        let backfill = BackfillSync::new(beacon_chain.clone(), &log);
 
         let mut handler = MessageHandler {
-            _chain: beacon_chain.clone(),
+            chain: beacon_chain.clone(),
             sync,
+            backfill,
             network_context: NetworkContext::new(network_send, log.clone()),
+            attestation_queue: AttestationQueue::default(),
+            meta_data: MetaData::default(),
+            peer_meta_data: HashMap::new(),
+            proof_subscriptions: ProofSubscriptions::default(),
             log: log.clone(),
         };
 
@@ -71,9 +91,30 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
         // TODO: Handle manual termination of thread
         executor.spawn(future::poll_fn(move || -> Result<_, _> {
             loop {
-                handler.handle_message(handler_recv.recv().map_err(|_| {
-                    debug!(log, "Network message handler terminated.");
-                })?);
+                // Wait for the next message, or until the queued attestations are due.
+                let message = match handler.attestation_queue.time_until_due(Instant::now()) {
+                    Some(timeout) => handler_recv.recv_timeout(timeout),
+                    None => handler_recv
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                };
+
+                match message {
+                    Ok(message) => {
+                        handler.handle_message(message);
+                        // Finality only advances when a message brings a new block.
+                        handler.push_finalized_proofs();
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        debug!(log, "Network message handler terminated.");
+                        return Err(());
+                    }
+                }
+
+                if handler.attestation_queue.is_due(Instant::now()) {
+                    handler.process_attestation_queue();
+                }
             }
         }));
 
@@ -85,7 +126,15 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
         match message {
             // we have initiated a connection to a peer
             HandlerMessage::PeerDialed(peer_id) => {
-                self.sync.on_connect(peer_id, &mut self.network_context);
+                self.sync
+                    .on_connect(peer_id.clone(), &mut self.network_context);
+                self.network_context
+                    .send_rpc_request(peer_id, RPCRequest::MetaData);
+            }
+            // a peer has disconnected
+            HandlerMessage::PeerDisconnected(peer_id) => {
+                self.peer_meta_data.remove(&peer_id);
+                self.proof_subscriptions.remove_peer(&peer_id);
             }
             // we have received an RPC message request/response
             HandlerMessage::RPC(peer_id, rpc_event) => {
@@ -95,8 +144,6 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
             HandlerMessage::PubsubMessage(peer_id, gossip) => {
                 self.handle_gossip(peer_id, *gossip);
             }
-            //TODO: Handle all messages
-            _ => {}
         }
     }
 
@@ -115,13 +162,36 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
     fn handle_rpc_request(&mut self, peer_id: PeerId, request_id: RequestId, request: RPCRequest) {
         // TODO: process the `id`.
         match request {
-            RPCRequest::Hello(hello_message) => self.sync.on_hello_request(
+            RPCRequest::Hello(hello_message) => {
+                self.sync.on_hello_request(
+                    peer_id.clone(),
+                    request_id,
+                    hello_message,
+                    &mut self.network_context,
+                );
+                self.backfill.add_peer(peer_id, &mut self.network_context);
+            }
+            RPCRequest::Goodbye(goodbye_reason) => {
+                self.backfill.remove_peer(&peer_id);
+                self.peer_meta_data.remove(&peer_id);
+                self.proof_subscriptions.remove_peer(&peer_id);
+                self.sync.on_goodbye(peer_id, goodbye_reason)
+            }
+            RPCRequest::Ping(ping) => {
+                self.network_context.send_rpc_response(
+                    peer_id.clone(),
+                    request_id,
+                    RPCResponse::Pong(Ping {
+                        data: self.meta_data.seq_number,
+                    }),
+                );
+                self.on_ping(peer_id, ping);
+            }
+            RPCRequest::MetaData => self.network_context.send_rpc_response(
                 peer_id,
                 request_id,
-                hello_message,
-                &mut self.network_context,
+                RPCResponse::MetaData(self.meta_data.clone()),
             ),
-            RPCRequest::Goodbye(goodbye_reason) => self.sync.on_goodbye(peer_id, goodbye_reason),
             RPCRequest::BeaconBlockRoots(request) => self.sync.on_beacon_block_roots_request(
                 peer_id,
                 request_id,
@@ -145,6 +215,17 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
                 // useful for light-client support in later phases.
                 warn!(self.log, "BeaconChainState RPC call is not supported.");
             }
+            RPCRequest::ProofSubscription(subscription) => {
+                self.on_proof_subscription(peer_id, request_id, subscription)
+            }
+            RPCRequest::ProofPush(_) => {
+                // We never subscribe to proofs, so any push is unsolicited.
+                debug!(
+                    self.log,
+                    "Ignoring unsolicited proof push";
+                    "peer" => format!("{:?}", peer_id),
+                );
+            }
         }
     }
 
@@ -167,10 +248,30 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
             return;
         }
 
+        if self.backfill.is_awaiting(&peer_id, id) {
+            self.backfill
+                .on_response(peer_id, response, &mut self.network_context);
+            return;
+        }
+
         match response {
             RPCResponse::Hello(hello_message) => {
-                self.sync
-                    .on_hello_response(peer_id, hello_message, &mut self.network_context);
+                self.sync.on_hello_response(
+                    peer_id.clone(),
+                    hello_message,
+                    &mut self.network_context,
+                );
+                self.backfill.add_peer(peer_id, &mut self.network_context);
+            }
+            RPCResponse::Pong(ping) => self.on_ping(peer_id, ping),
+            RPCResponse::MetaData(meta_data) => {
+                debug!(
+                    self.log,
+                    "Received peer MetaData";
+                    "peer" => format!("{:?}", peer_id),
+                    "seq_number" => meta_data.seq_number,
+                );
+                self.peer_meta_data.insert(peer_id, meta_data);
             }
             RPCResponse::BeaconBlockRoots(response) => {
                 self.sync.on_beacon_block_roots_response(
@@ -201,7 +302,130 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
                 // beacon state RPC request.
                 warn!(self.log, "BeaconChainState RPC call is not supported.");
             }
+            RPCResponse::ProofSubscription(_) => {
+                // We never subscribe to proofs, so we should never reach this code.
+                warn!(
+                    self.log,
+                    "Received an unexpected ProofSubscription response."
+                );
+            }
+            RPCResponse::Error(response) => {
+                debug!(
+                    self.log,
+                    "Peer returned RPC error";
+                    "peer" => format!("{:?}", peer_id),
+                    "code" => format!("{:?}", response.code),
+                    "message" => String::from_utf8_lossy(&response.message).to_string()
+                );
+            }
+        };
+    }
+
+    /// Requests the `MetaData` of `peer_id` if `ping` shows it has changed since we last received
+    /// it.
+    fn on_ping(&mut self, peer_id: PeerId, ping: Ping) {
+        let is_stale = self
+            .peer_meta_data
+            .get(&peer_id)
+            .map_or(true, |meta_data| meta_data.seq_number < ping.data);
+
+        if is_stale {
+            self.network_context
+                .send_rpc_request(peer_id, RPCRequest::MetaData);
+        }
+    }
+
+    /// Records the proof subscription of `peer_id`, echoing it back if accepted, then pushes a
+    /// proof against the current finalized state.
+    fn on_proof_subscription(
+        &mut self,
+        peer_id: PeerId,
+        request_id: RequestId,
+        subscription: ProofSubscription,
+    ) {
+        let result = subscription
+            .validate()
+            .map_err(|code| format!("Invalid subscription: {:?}", code))
+            .and_then(|()| {
+                self.proof_subscriptions
+                    .subscribe(peer_id.clone(), subscription.indices.clone())
+            });
+
+        match result {
+            Ok(()) => {
+                self.network_context.send_rpc_response(
+                    peer_id.clone(),
+                    request_id,
+                    RPCResponse::ProofSubscription(subscription.clone()),
+                );
+                if !subscription.indices.is_empty() {
+                    self.push_proofs(vec![(subscription.indices, vec![peer_id])]);
+                }
+            }
+            Err(message) => self.network_context.send_rpc_response(
+                peer_id,
+                request_id,
+                RPCResponse::Error(ErrorResponse::new(RPCErrorCode::InvalidRequest, &message)),
+            ),
+        }
+    }
+
+    /// Pushes proofs to every subscriber if finality has advanced since the last pushes.
+    fn push_finalized_proofs(&mut self) {
+        let finalized = {
+            let head = self.chain.head();
+            (
+                head.beacon_state.finalized_epoch,
+                head.beacon_state.finalized_root,
+            )
         };
+
+        let due = self.proof_subscriptions.due(finalized);
+        if !due.is_empty() {
+            self.push_proofs(due);
+        }
+    }
+
+    /// Pushes a proof of each set of indices against the finalized state to the peers which
+    /// subscribed to it.
+    fn push_proofs(&mut self, subscriptions: impl IntoIterator<Item = (Vec<u64>, Vec<PeerId>)>) {
+        let (finalized_epoch, finalized_root, state_root, state) =
+            match self.chain.finalized_state() {
+                Ok(finalized) => finalized,
+                Err(e) => {
+                    debug!(
+                        self.log,
+                        "Unable to load finalized state for proof pushes";
+                        "error" => format!("{:?}", e),
+                    );
+                    return;
+                }
+            };
+
+        for (indices, peer_ids) in subscriptions {
+            let proof = match state.prove(&indices) {
+                Ok(proof) => proof,
+                Err(e) => {
+                    debug!(
+                        self.log,
+                        "Unable to prove subscribed indices";
+                        "error" => format!("{:?}", e),
+                    );
+                    continue;
+                }
+            };
+
+            let push = ProofPush {
+                finalized_epoch,
+                finalized_root,
+                state_root,
+                proof,
+            };
+            for peer_id in peer_ids {
+                self.network_context
+                    .send_rpc_notification(peer_id, RPCRequest::ProofPush(push.clone()));
+            }
+        }
     }
 
     /// Handle RPC messages
@@ -213,11 +437,17 @@ impl<T: BeaconChainTypes + 'static> MessageHandler<T> {
                         .on_block_gossip(peer_id, message, &mut self.network_context);
             }
             PubsubMessage::Attestation(message) => {
-                self.sync
-                    .on_attestation_gossip(peer_id, message, &mut self.network_context)
+                self.attestation_queue.push(peer_id, message);
             }
         }
     }
+
+    /// Verifies and imports all queued gossip attestations as a single batch.
+    fn process_attestation_queue(&mut self) {
+        let batch = self.attestation_queue.drain();
+        self.sync
+            .on_attestation_gossip_batch(batch, &mut self.network_context);
+    }
 }
 
 pub struct NetworkContext {
@@ -242,11 +472,12 @@ impl NetworkContext {
     }
 
     pub fn disconnect(&mut self, peer_id: PeerId, reason: GoodbyeReason) {
-        self.send_rpc_request(peer_id, RPCRequest::Goodbye(reason))
+        self.send_rpc_request(peer_id, RPCRequest::Goodbye(reason));
         // TODO: disconnect peers.
     }
 
-    pub fn send_rpc_request(&mut self, peer_id: PeerId, rpc_request: RPCRequest) {
+    /// Sends `rpc_request` to `peer_id`, returning the `RequestId` which its response will carry.
+    pub fn send_rpc_request(&mut self, peer_id: PeerId, rpc_request: RPCRequest) -> RequestId {
         let id = self.generate_request_id(&peer_id);
 
         self.outstanding_outgoing_request_ids
@@ -260,6 +491,22 @@ impl NetworkContext {
                 body: rpc_request,
             },
         );
+
+        id
+    }
+
+    /// Sends `rpc_request` to `peer_id` without expecting a response, e.g., a `ProofPush`.
+    pub fn send_rpc_notification(&mut self, peer_id: PeerId, rpc_request: RPCRequest) {
+        let id = self.generate_request_id(&peer_id);
+
+        self.send_rpc_event(
+            peer_id,
+            RPCEvent::Request {
+                id,
+                method_id: rpc_request.method_id(),
+                body: rpc_request,
+            },
+        );
     }
 
     pub fn send_rpc_response(
@@ -0,0 +1,307 @@
+//! Port mapping on the local router, so that a node behind NAT is dialable without manual
+//! router configuration.
+//!
+//! UPnP is tried first, as it also discovers the router. Otherwise NAT-PMP (RFC 6886) is used,
+//! with the configured router. Mappings are leased, so they are renewed for as long as the
+//! network service runs.
+use crate::service::NetworkMessage;
+use crossbeam_channel::Sender;
+use eth2_libp2p::multiaddr::Protocol;
+use eth2_libp2p::{transport, Multiaddr};
+use igd::{PortMappingProtocol, SearchOptions};
+use slog::{debug, info, warn};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+/// The lifetime requested for each mapping.
+pub const LEASE_DURATION: Duration = Duration::from_secs(3600);
+
+/// The port on which a router serves NAT-PMP.
+pub const NAT_PMP_PORT: u16 = 5351;
+
+/// The number of times a NAT-PMP request is sent before giving up, doubling the timeout each time.
+const NAT_PMP_ATTEMPTS: u32 = 4;
+
+/// The timeout of the first NAT-PMP request.
+const NAT_PMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Description of the mappings, as shown by the router.
+const MAPPING_DESCRIPTION: &str = "lighthouse";
+
+/// A NAT-PMP response opcode is the request opcode plus 128.
+const NAT_PMP_RESPONSE: u8 = 128;
+const NAT_PMP_OP_EXTERNAL_ADDRESS: u8 = 0;
+const NAT_PMP_OP_MAP_TCP: u8 = 2;
+
+/// Returns the TCP ports of the IPv4 listen addresses, which may be mapped.
+pub fn mappable_ports(listen_addresses: &[Multiaddr]) -> Vec<u16> {
+    let mut ports = vec![];
+    for address in listen_addresses {
+        if let Some(Protocol::Ip4(_)) = address.iter().next() {
+            if let Some(port) = transport::tcp_port(address) {
+                if !ports.contains(&port) {
+                    ports.push(port);
+                }
+            }
+        }
+    }
+    ports
+}
+
+/// Spawns a thread which maps each of `ports` on the router, and renews the mappings before they
+/// expire. Each mapped address is sent to the network service to be advertised.
+///
+/// The thread exits once the network service stops.
+pub fn spawn_port_mapping(
+    ports: Vec<u16>,
+    nat_pmp_gateway: Option<Ipv4Addr>,
+    network_send: Sender<NetworkMessage>,
+    log: slog::Logger,
+) -> Result<(), String> {
+    thread::Builder::new()
+        .name("port_mapping".into())
+        .spawn(move || loop {
+            for &port in &ports {
+                let external = match map_port(port, nat_pmp_gateway, &log) {
+                    Some(external) => external,
+                    None => continue,
+                };
+
+                let mut address = Multiaddr::from(Protocol::Ip4(*external.ip()));
+                address.append(Protocol::Tcp(external.port()));
+                if network_send
+                    .send(NetworkMessage::AddExternalAddress(address))
+                    .is_err()
+                {
+                    debug!(log, "Port mapping stopped");
+                    return;
+                }
+            }
+
+            // Renew well before the lease expires.
+            thread::sleep(LEASE_DURATION / 2);
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Unable to spawn port mapping thread: {:?}", e))
+}
+
+/// Maps `port` via UPnP, or NAT-PMP if UPnP is unavailable, returning the external address.
+fn map_port(
+    port: u16,
+    nat_pmp_gateway: Option<Ipv4Addr>,
+    log: &slog::Logger,
+) -> Option<SocketAddrV4> {
+    let upnp_error = match map_port_upnp(port) {
+        Ok(external) => {
+            info!(log, "Mapped port via UPnP"; "port" => port, "external" => format!("{}", external));
+            return Some(external);
+        }
+        Err(e) => e,
+    };
+
+    let gateway = match nat_pmp_gateway {
+        Some(gateway) => gateway,
+        None => {
+            warn!(log, "Unable to map port"; "port" => port, "upnp_error" => upnp_error);
+            return None;
+        }
+    };
+
+    match map_port_nat_pmp(port, gateway) {
+        Ok(external) => {
+            info!(log, "Mapped port via NAT-PMP"; "port" => port, "external" => format!("{}", external));
+            Some(external)
+        }
+        Err(e) => {
+            warn!(
+                log,
+                "Unable to map port";
+                "port" => port,
+                "upnp_error" => upnp_error,
+                "nat_pmp_error" => e
+            );
+            None
+        }
+    }
+}
+
+/// Maps `port` to the same external port via UPnP.
+fn map_port_upnp(port: u16) -> Result<SocketAddrV4, String> {
+    let gateway = igd::search_gateway(SearchOptions::default())
+        .map_err(|e| format!("No UPnP gateway: {}", e))?;
+    let local_ip = local_ipv4(*gateway.addr.ip())?;
+
+    gateway
+        .add_port(
+            PortMappingProtocol::TCP,
+            port,
+            SocketAddrV4::new(local_ip, port),
+            LEASE_DURATION.as_secs() as u32,
+            MAPPING_DESCRIPTION,
+        )
+        .map_err(|e| format!("UPnP mapping failed: {}", e))?;
+    let external_ip = gateway
+        .get_external_ip()
+        .map_err(|e| format!("UPnP external address unknown: {}", e))?;
+
+    Ok(SocketAddrV4::new(external_ip, port))
+}
+
+/// Maps `port` via NAT-PMP on `gateway`. The router may choose a different external port.
+fn map_port_nat_pmp(port: u16, gateway: Ipv4Addr) -> Result<SocketAddrV4, String> {
+    let response = nat_pmp_request(
+        gateway,
+        &encode_map_request(port, port, LEASE_DURATION.as_secs() as u32),
+    )?;
+    let (external_port, _lifetime) = decode_map_response(&response)?;
+
+    let response = nat_pmp_request(gateway, &encode_external_address_request())?;
+    let external_ip = decode_external_address_response(&response)?;
+
+    Ok(SocketAddrV4::new(external_ip, external_port))
+}
+
+/// Returns the local IPv4 address from which `gateway` is reached.
+fn local_ipv4(gateway: Ipv4Addr) -> Result<Ipv4Addr, String> {
+    // Connecting a UDP socket sends nothing, but selects the local address of the route.
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("{:?}", e))?;
+    socket
+        .connect(SocketAddrV4::new(gateway, NAT_PMP_PORT))
+        .map_err(|e| format!("No route to gateway {}: {:?}", gateway, e))?;
+
+    match socket.local_addr().map_err(|e| format!("{:?}", e))? {
+        SocketAddr::V4(addr) => Ok(*addr.ip()),
+        SocketAddr::V6(addr) => Err(format!("Local address {} is not IPv4", addr)),
+    }
+}
+
+/// Sends `request` to the NAT-PMP server of `gateway`, returning its response.
+fn nat_pmp_request(gateway: Ipv4Addr, request: &[u8]) -> Result<Vec<u8>, String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("{:?}", e))?;
+    socket
+        .connect(SocketAddrV4::new(gateway, NAT_PMP_PORT))
+        .map_err(|e| format!("No route to gateway {}: {:?}", gateway, e))?;
+
+    let mut timeout = NAT_PMP_INITIAL_TIMEOUT;
+    let mut buf = [0; 16];
+    for _ in 0..NAT_PMP_ATTEMPTS {
+        socket.send(request).map_err(|e| format!("{:?}", e))?;
+        socket
+            .set_read_timeout(Some(timeout))
+            .map_err(|e| format!("{:?}", e))?;
+        if let Ok(len) = socket.recv(&mut buf) {
+            return Ok(buf[..len].to_vec());
+        }
+        timeout *= 2;
+    }
+
+    Err(format!("No NAT-PMP response from {}", gateway))
+}
+
+/// Encodes a request to map TCP `internal_port` to `external_port` for `lifetime` seconds.
+pub fn encode_map_request(internal_port: u16, external_port: u16, lifetime: u32) -> [u8; 12] {
+    let mut request = [0; 12];
+    request[1] = NAT_PMP_OP_MAP_TCP;
+    request[4..6].copy_from_slice(&internal_port.to_be_bytes());
+    request[6..8].copy_from_slice(&external_port.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime.to_be_bytes());
+    request
+}
+
+/// Decodes the response to a TCP mapping request, returning the mapped external port and the
+/// lifetime of the mapping in seconds.
+pub fn decode_map_response(response: &[u8]) -> Result<(u16, u32), String> {
+    check_response(response, NAT_PMP_OP_MAP_TCP, 16)?;
+
+    let external_port = u16::from_be_bytes([response[10], response[11]]);
+    let mut lifetime = [0; 4];
+    lifetime.copy_from_slice(&response[12..16]);
+
+    Ok((external_port, u32::from_be_bytes(lifetime)))
+}
+
+/// Encodes a request for the external address of the router.
+pub fn encode_external_address_request() -> [u8; 2] {
+    [0, NAT_PMP_OP_EXTERNAL_ADDRESS]
+}
+
+/// Decodes the response to an external address request.
+pub fn decode_external_address_response(response: &[u8]) -> Result<Ipv4Addr, String> {
+    check_response(response, NAT_PMP_OP_EXTERNAL_ADDRESS, 12)?;
+
+    Ok(Ipv4Addr::new(
+        response[8],
+        response[9],
+        response[10],
+        response[11],
+    ))
+}
+
+/// Checks the length, version, opcode and result code of a response to a request with `opcode`.
+fn check_response(response: &[u8], opcode: u8, len: usize) -> Result<(), String> {
+    if response.len() < len {
+        return Err(format!(
+            "NAT-PMP response of {} bytes is too short",
+            response.len()
+        ));
+    }
+    if response[0] != 0 || response[1] != NAT_PMP_RESPONSE + opcode {
+        return Err(format!(
+            "Unexpected NAT-PMP response version {} opcode {}",
+            response[0], response[1]
+        ));
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(()),
+        code => Err(format!("NAT-PMP result code {}", code)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_requests() {
+        assert_eq!(
+            encode_map_request(9000, 9001, 3600),
+            [0, 2, 0, 0, 0x23, 0x28, 0x23, 0x29, 0, 0, 0x0e, 0x10]
+        );
+        assert_eq!(encode_external_address_request(), [0, 0]);
+    }
+
+    #[test]
+    fn decodes_responses() {
+        let map_response = [
+            0, 130, 0, 0, 0, 0, 0, 1, 0x23, 0x28, 0x23, 0x29, 0, 0, 0x0e, 0x10,
+        ];
+        assert_eq!(decode_map_response(&map_response), Ok((9001, 3600)));
+
+        let address_response = [0, 128, 0, 0, 0, 0, 0, 1, 203, 0, 113, 7];
+        assert_eq!(
+            decode_external_address_response(&address_response),
+            Ok(Ipv4Addr::new(203, 0, 113, 7))
+        );
+
+        // Not authorized.
+        let mut refused = map_response;
+        refused[3] = 2;
+        assert!(decode_map_response(&refused).is_err());
+        // A response to a different request.
+        assert!(decode_map_response(&address_response).is_err());
+        assert!(decode_external_address_response(&map_response[..8]).is_err());
+    }
+
+    #[test]
+    fn maps_ipv4_tcp_ports() {
+        let addresses: Vec<Multiaddr> = vec![
+            "/ip4/0.0.0.0/tcp/9000".parse().unwrap(),
+            "/ip6/::/tcp/9001".parse().unwrap(),
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            "/ip4/0.0.0.0/udp/9002/quic".parse().unwrap(),
+        ];
+
+        assert_eq!(mappable_ports(&addresses), vec![9000]);
+    }
+}
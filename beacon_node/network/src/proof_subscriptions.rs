@@ -0,0 +1,95 @@
+use eth2_libp2p::PeerId;
+use std::collections::{BTreeMap, HashMap};
+use types::{Epoch, Hash256};
+
+/// The most peers which may hold a proof subscription at once.
+pub const MAX_SUBSCRIBERS: usize = 64;
+
+/// The generalized indices each peer has subscribed to, which are proven against the state of
+/// each newly finalized block.
+#[derive(Default)]
+pub struct ProofSubscriptions {
+    subscriptions: HashMap<PeerId, Vec<u64>>,
+    /// The finalized checkpoint most recently given to `Self::due`.
+    last_finalized: Option<(Epoch, Hash256)>,
+}
+
+impl ProofSubscriptions {
+    /// Replaces the subscription of `peer_id` with `indices`, or cancels it if `indices` is empty.
+    pub fn subscribe(&mut self, peer_id: PeerId, indices: Vec<u64>) -> Result<(), String> {
+        if indices.is_empty() {
+            self.subscriptions.remove(&peer_id);
+            return Ok(());
+        }
+        if !self.subscriptions.contains_key(&peer_id) && self.subscriptions.len() >= MAX_SUBSCRIBERS
+        {
+            return Err(format!("At most {} peers may subscribe", MAX_SUBSCRIBERS));
+        }
+
+        self.subscriptions.insert(peer_id, indices);
+        Ok(())
+    }
+
+    /// Cancels the subscription of `peer_id`, e.g., once it disconnects.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.subscriptions.remove(peer_id);
+    }
+
+    /// Returns the subscribers to push proofs to, grouped by the indices they subscribed to, if
+    /// `finalized` differs from the checkpoint last given.
+    pub fn due(&mut self, finalized: (Epoch, Hash256)) -> BTreeMap<Vec<u64>, Vec<PeerId>> {
+        let mut due = BTreeMap::new();
+        if self.last_finalized == Some(finalized) {
+            return due;
+        }
+        self.last_finalized = Some(finalized);
+
+        for (peer_id, indices) in &self.subscriptions {
+            due.entry(indices.clone())
+                .or_insert_with(Vec::new)
+                .push(peer_id.clone());
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushes_once_per_checkpoint() {
+        let mut subscriptions = ProofSubscriptions::default();
+        let (a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random());
+        subscriptions.subscribe(a.clone(), vec![36, 37]).unwrap();
+        subscriptions.subscribe(b.clone(), vec![36, 37]).unwrap();
+        subscriptions.subscribe(c.clone(), vec![47]).unwrap();
+
+        let checkpoint = (Epoch::new(2), Hash256::from_low_u64_le(2));
+        let due = subscriptions.due(checkpoint);
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[&vec![36, 37]].len(), 2);
+        assert_eq!(due[&vec![47]], vec![c.clone()]);
+        assert!(subscriptions.due(checkpoint).is_empty());
+
+        subscriptions.subscribe(a, vec![]).unwrap();
+        subscriptions.remove_peer(&c);
+        let due = subscriptions.due((Epoch::new(3), Hash256::from_low_u64_le(3)));
+        assert_eq!(
+            due.into_iter().collect::<Vec<_>>(),
+            vec![(vec![36, 37], vec![b])]
+        );
+    }
+
+    #[test]
+    fn limits_subscribers() {
+        let mut subscriptions = ProofSubscriptions::default();
+        let peers: Vec<PeerId> = (0..MAX_SUBSCRIBERS).map(|_| PeerId::random()).collect();
+        for peer_id in &peers {
+            subscriptions.subscribe(peer_id.clone(), vec![1]).unwrap();
+        }
+
+        assert!(subscriptions.subscribe(PeerId::random(), vec![1]).is_err());
+        assert_eq!(subscriptions.subscribe(peers[0].clone(), vec![2]), Ok(()));
+    }
+}
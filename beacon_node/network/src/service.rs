@@ -1,24 +1,26 @@
 use crate::error;
 use crate::message_handler::{HandlerMessage, MessageHandler};
+use crate::nat;
 use crate::NetworkConfig;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use crossbeam_channel::{unbounded as channel, Sender, TryRecvError};
 use eth2_libp2p::Service as LibP2PService;
-use eth2_libp2p::{Libp2pEvent, PeerId};
+use eth2_libp2p::{Libp2pEvent, Multiaddr, PeerId};
 use eth2_libp2p::{PubsubMessage, RPCEvent};
 use futures::prelude::*;
 use futures::sync::oneshot;
 use futures::Stream;
+use parking_lot::Mutex;
 use slog::{debug, info, o, trace};
 use std::marker::PhantomData;
 use std::sync::Arc;
 use tokio::runtime::TaskExecutor;
-use types::Topic;
 
 /// Service that handles communication between internal services and the eth2_libp2p network service.
 pub struct Service<T: BeaconChainTypes> {
     //libp2p_service: Arc<Mutex<LibP2PService>>,
-    _libp2p_exit: oneshot::Sender<()>,
+    /// Signal to terminate the libp2p service, taken on shutdown.
+    libp2p_exit: Mutex<Option<oneshot::Sender<()>>>,
     network_send: crossbeam_channel::Sender<NetworkMessage>,
     _phantom: PhantomData<T>, //message_handler: MessageHandler,
                               //message_handler_send: Sender<HandlerMessage>
@@ -31,6 +33,8 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
         executor: &TaskExecutor,
         log: slog::Logger,
     ) -> error::Result<(Arc<Self>, Sender<NetworkMessage>)> {
+        let fork_digest = beacon_chain.fork_digest();
+
         // build the network channel
         let (network_send, network_recv) = channel::<NetworkMessage>();
         // launch message handler thread
@@ -44,7 +48,19 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
 
         // launch libp2p service
         let libp2p_log = log.new(o!("Service" => "Libp2p"));
-        let libp2p_service = LibP2PService::new(config.clone(), libp2p_log)?;
+        let libp2p_service = LibP2PService::new(config.clone(), fork_digest, libp2p_log)?;
+
+        if config.port_mapping {
+            let listen_addresses = config
+                .listen_multiaddrs()
+                .map_err(|e| format!("Invalid listen multiaddr: {}", e))?;
+            nat::spawn_port_mapping(
+                nat::mappable_ports(&listen_addresses),
+                config.nat_pmp_gateway,
+                network_send.clone(),
+                log.new(o!("Service" => "PortMapping")),
+            )?;
+        }
 
         // TODO: Spawn thread to handle libp2p messages and pass to message handler thread.
         let libp2p_exit = spawn_service(
@@ -55,7 +71,7 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
             log,
         )?;
         let network_service = Service {
-            _libp2p_exit: libp2p_exit,
+            libp2p_exit: Mutex::new(Some(libp2p_exit)),
             network_send: network_send.clone(),
             _phantom: PhantomData,
         };
@@ -63,6 +79,26 @@ impl<T: BeaconChainTypes + 'static> Service<T> {
         Ok((Arc::new(network_service), network_send))
     }
 
+    /// Stops the libp2p service. The message handler (and with it, sync) exits once the libp2p
+    /// service has dropped its channel.
+    ///
+    /// Subsequent calls have no effect.
+    pub fn shutdown(&self) {
+        if let Some(libp2p_exit) = self.libp2p_exit.lock().take() {
+            // The service may have already stopped, in which case there is nothing to signal.
+            let _ = libp2p_exit.send(());
+        }
+    }
+
+    /// Publishes `message` on the gossipsub topic of its kind.
+    pub fn publish(&self, message: PubsubMessage) -> error::Result<()> {
+        self.network_send
+            .send(NetworkMessage::Publish {
+                message: Box::new(message),
+            })
+            .map_err(|e| format!("Unable to publish to network: {:?}", e).into())
+    }
+
     // TODO: Testing only
     pub fn send_message(&self) {
         self.network_send
@@ -165,9 +201,12 @@ fn network_service(
                         }
                     };
                 }
-                Ok(NetworkMessage::Publish { topics, message }) => {
-                    debug!(log, "Sending pubsub message on topics {:?}", topics);
-                    libp2p_service.swarm.publish(topics, *message);
+                Ok(NetworkMessage::Publish { message }) => {
+                    debug!(log, "Sending pubsub message"; "kind" => format!("{:?}", message.kind()));
+                    libp2p_service.swarm.publish(*message);
+                }
+                Ok(NetworkMessage::AddExternalAddress(address)) => {
+                    libp2p_service.add_external_address(address);
                 }
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => {
@@ -187,11 +226,10 @@ pub enum NetworkMessage {
     /// Send a message to libp2p service.
     //TODO: Define typing for messages across the wire
     Send(PeerId, OutgoingMessage),
-    /// Publish a message to pubsub mechanism.
-    Publish {
-        topics: Vec<Topic>,
-        message: Box<PubsubMessage>,
-    },
+    /// Publish a message to pubsub mechanism, on the topic of its kind.
+    Publish { message: Box<PubsubMessage> },
+    /// Advertise an address on which this node is reachable, e.g. a port mapped on the router.
+    AddExternalAddress(Multiaddr),
 }
 
 /// Type of outgoing messages that can be sent through the network service.
@@ -0,0 +1,272 @@
+use crate::message_handler::NetworkContext;
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use eth2_libp2p::rpc::methods::*;
+use eth2_libp2p::rpc::{RPCRequest, RPCResponse, RequestId};
+use eth2_libp2p::PeerId;
+use slog::{debug, info, o, warn};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tree_hash::TreeHash;
+use types::{BeaconBlockBody, BeaconBlockHeader, Hash256, Slot};
+
+/// The number of slots requested from a peer in a single batch.
+const BACKFILL_BATCH_SIZE: u64 = 64;
+
+/// The number of seconds to wait for a response before giving up on a batch.
+const BACKFILL_TIMEOUT_SECS: u64 = 30;
+
+/// The stage of the batch currently being downloaded.
+enum BatchState {
+    /// No batch is being downloaded.
+    Idle,
+    /// Waiting for the headers of a batch.
+    AwaitingHeaders {
+        peer_id: PeerId,
+        request_id: RequestId,
+        sent: Instant,
+    },
+    /// Headers have been received, waiting for their bodies.
+    AwaitingBodies {
+        peer_id: PeerId,
+        request_id: RequestId,
+        sent: Instant,
+        headers: Vec<BeaconBlockHeader>,
+    },
+}
+
+/// Downloads historical blocks backwards from the oldest known block (e.g., the checkpoint the
+/// chain was started from) to genesis, one batch at a time.
+///
+/// Each batch is verified against the chain of parent roots ending at the oldest known block and
+/// is stored without replaying any state transitions.
+pub struct BackfillSync<T: BeaconChainTypes> {
+    /// A reference to the underlying beacon chain.
+    chain: Arc<BeaconChain<T>>,
+    /// Peers which have completed a handshake and may be asked for blocks.
+    peers: HashSet<PeerId>,
+    /// The batch presently being downloaded.
+    state: BatchState,
+    /// Backfill logger.
+    log: slog::Logger,
+}
+
+impl<T: BeaconChainTypes> BackfillSync<T> {
+    pub fn new(beacon_chain: Arc<BeaconChain<T>>, log: &slog::Logger) -> Self {
+        BackfillSync {
+            chain: beacon_chain,
+            peers: HashSet::new(),
+            state: BatchState::Idle,
+            log: log.new(o!("Service" => "Backfill")),
+        }
+    }
+
+    /// A peer has said hello; it may be used to download history.
+    pub fn add_peer(&mut self, peer_id: PeerId, network: &mut NetworkContext) {
+        self.peers.insert(peer_id);
+        self.maybe_request_batch(network);
+    }
+
+    /// A peer has left; it may no longer be used.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// Returns `true` if the response `request_id` from `peer_id` belongs to backfill.
+    pub fn is_awaiting(&self, peer_id: &PeerId, request_id: RequestId) -> bool {
+        match &self.state {
+            BatchState::AwaitingHeaders {
+                peer_id: p,
+                request_id: id,
+                ..
+            }
+            | BatchState::AwaitingBodies {
+                peer_id: p,
+                request_id: id,
+                ..
+            } => p == peer_id && *id == request_id,
+            BatchState::Idle => false,
+        }
+    }
+
+    /// Handle a response to one of our requests, as identified by `self.is_awaiting`.
+    pub fn on_response(
+        &mut self,
+        peer_id: PeerId,
+        response: RPCResponse,
+        network: &mut NetworkContext,
+    ) {
+        let state = std::mem::replace(&mut self.state, BatchState::Idle);
+
+        match (state, response) {
+            (BatchState::AwaitingHeaders { .. }, RPCResponse::BeaconBlockHeaders(res)) => {
+                self.on_headers(peer_id, res.headers, network)
+            }
+            (BatchState::AwaitingBodies { headers, .. }, RPCResponse::BeaconBlockBodies(res)) => {
+                self.on_bodies(peer_id, headers, res.block_bodies, network)
+            }
+            (_, response) => {
+                warn!(
+                    self.log,
+                    "Unexpected backfill response";
+                    "peer" => format!("{:?}", peer_id),
+                    "method_id" => response.method_id(),
+                );
+                self.peers.remove(&peer_id);
+                self.maybe_request_batch(network);
+            }
+        }
+    }
+
+    fn on_headers(
+        &mut self,
+        peer_id: PeerId,
+        headers: Vec<BeaconBlockHeader>,
+        network: &mut NetworkContext,
+    ) {
+        let (oldest_slot, _) = self.chain.oldest_block();
+        let headers: Vec<BeaconBlockHeader> = headers
+            .into_iter()
+            .filter(|header| header.slot < oldest_slot)
+            .collect();
+
+        if headers.is_empty() {
+            debug!(
+                self.log,
+                "Peer has no historical headers";
+                "peer" => format!("{:?}", peer_id),
+            );
+            self.peers.remove(&peer_id);
+            self.maybe_request_batch(network);
+            return;
+        }
+
+        let block_roots = headers
+            .iter()
+            .map(BeaconBlockHeader::canonical_root)
+            .collect();
+        let request_id = network.send_rpc_request(
+            peer_id.clone(),
+            RPCRequest::BeaconBlockBodies(BeaconBlockBodiesRequest { block_roots }),
+        );
+
+        self.state = BatchState::AwaitingBodies {
+            peer_id,
+            request_id,
+            sent: Instant::now(),
+            headers,
+        };
+    }
+
+    fn on_bodies(
+        &mut self,
+        peer_id: PeerId,
+        headers: Vec<BeaconBlockHeader>,
+        bodies: Vec<BeaconBlockBody>,
+        network: &mut NetworkContext,
+    ) {
+        let mut bodies: Vec<(Hash256, BeaconBlockBody)> = bodies
+            .into_iter()
+            .map(|body| (Hash256::from_slice(&body.tree_hash_root()), body))
+            .collect();
+
+        let blocks = headers
+            .into_iter()
+            .filter_map(|header| {
+                let i = bodies
+                    .iter()
+                    .position(|(root, _)| *root == header.block_body_root)?;
+                Some(header.into_block(bodies.swap_remove(i).1))
+            })
+            .collect();
+
+        match self.chain.import_historical_blocks(blocks) {
+            Ok(count) => {
+                let (oldest_slot, _) = self.chain.oldest_block();
+                info!(
+                    self.log,
+                    "Imported historical blocks";
+                    "count" => count,
+                    "oldest_slot" => oldest_slot,
+                    "peer" => format!("{:?}", peer_id),
+                );
+
+                if self.chain.is_backfill_complete() {
+                    info!(self.log, "Historical block backfill complete");
+                }
+            }
+            Err(e) => {
+                warn!(
+                    self.log,
+                    "Peer sent invalid historical blocks";
+                    "peer" => format!("{:?}", peer_id),
+                    "error" => format!("{:?}", e),
+                );
+                self.peers.remove(&peer_id);
+                network.disconnect(peer_id, GoodbyeReason::Fault);
+            }
+        }
+
+        self.maybe_request_batch(network);
+    }
+
+    /// Request the batch of headers preceding the oldest known block, if backfill is incomplete
+    /// and no other batch is in flight.
+    fn maybe_request_batch(&mut self, network: &mut NetworkContext) {
+        if self.chain.is_backfill_complete() {
+            return;
+        }
+
+        let timeout = Duration::from_secs(BACKFILL_TIMEOUT_SECS);
+        match &self.state {
+            BatchState::AwaitingHeaders { peer_id, sent, .. }
+            | BatchState::AwaitingBodies { peer_id, sent, .. } => {
+                if sent.elapsed() < timeout {
+                    return;
+                }
+                warn!(
+                    self.log,
+                    "Backfill request timed out";
+                    "peer" => format!("{:?}", peer_id),
+                );
+                let peer_id = peer_id.clone();
+                self.peers.remove(&peer_id);
+            }
+            BatchState::Idle => {}
+        }
+        self.state = BatchState::Idle;
+
+        let peer_id = match self.peers.iter().next() {
+            Some(peer_id) => peer_id.clone(),
+            None => return,
+        };
+
+        let (oldest_slot, parent_root) = self.chain.oldest_block();
+        let start_slot = Slot::from(oldest_slot.as_u64().saturating_sub(BACKFILL_BATCH_SIZE));
+        let max_headers = (oldest_slot - start_slot).as_u64();
+
+        debug!(
+            self.log,
+            "Requesting historical headers";
+            "start_slot" => start_slot,
+            "count" => max_headers,
+            "peer" => format!("{:?}", peer_id),
+        );
+
+        let request_id = network.send_rpc_request(
+            peer_id.clone(),
+            RPCRequest::BeaconBlockHeaders(BeaconBlockHeadersRequest {
+                start_root: parent_root,
+                start_slot,
+                max_headers,
+                skip_slots: 0,
+            }),
+        );
+
+        self.state = BatchState::AwaitingHeaders {
+            peer_id,
+            request_id,
+            sent: Instant::now(),
+        };
+    }
+}
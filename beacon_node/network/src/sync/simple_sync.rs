@@ -10,7 +10,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use store::Store;
 use types::{
-    Attestation, BeaconBlock, BeaconBlockBody, BeaconBlockHeader, Epoch, EthSpec, Hash256, Slot,
+    Attestation, BeaconBlock, BeaconBlockBody, BeaconBlockHeader, Epoch, EthSpec, ForkDigest,
+    Hash256, Slot,
 };
 
 /// The number of slots that we can import blocks ahead of us, before going into full Sync mode.
@@ -29,6 +30,7 @@ const SHOULD_NOT_FORWARD_GOSSIP_BLOCK: bool = false;
 /// Keeps track of syncing information for known connected peers.
 #[derive(Clone, Copy, Debug)]
 pub struct PeerSyncInfo {
+    fork_digest: ForkDigest,
     network_id: u8,
     latest_finalized_root: Hash256,
     latest_finalized_epoch: Epoch,
@@ -39,6 +41,7 @@ pub struct PeerSyncInfo {
 impl From<HelloMessage> for PeerSyncInfo {
     fn from(hello: HelloMessage) -> PeerSyncInfo {
         PeerSyncInfo {
+            fork_digest: hello.fork_digest,
             network_id: hello.network_id,
             latest_finalized_root: hello.latest_finalized_root,
             latest_finalized_epoch: hello.latest_finalized_epoch,
@@ -165,8 +168,16 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
         let remote = PeerSyncInfo::from(hello);
         let local = PeerSyncInfo::from(&self.chain);
 
+        // Disconnect nodes who are on a different fork or chain, before any blocks are exchanged.
+        if local.fork_digest != remote.fork_digest {
+            info!(
+                self.log, "HandshakeFailure";
+                "peer" => format!("{:?}", peer_id),
+                "reason" => "fork_digest"
+            );
+            network.disconnect(peer_id.clone(), GoodbyeReason::IrreleventNetwork);
         // Disconnect nodes who are on a different network.
-        if local.network_id != remote.network_id {
+        } else if local.network_id != remote.network_id {
             info!(
                 self.log, "HandshakeFailure";
                 "peer" => format!("{:?}", peer_id),
@@ -553,21 +564,42 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
         }
     }
 
-    /// Process a gossip message declaring a new attestation.
+    /// Process a batch of gossip messages declaring new attestations.
     ///
-    /// Not currently implemented.
-    pub fn on_attestation_gossip(
+    /// Signatures across the batch are verified together by the `BeaconChain`.
+    pub fn on_attestation_gossip_batch(
         &mut self,
-        _peer_id: PeerId,
-        msg: Attestation,
+        batch: Vec<(PeerId, Attestation)>,
         _network: &mut NetworkContext,
     ) {
-        match self.chain.process_attestation(msg) {
-            Ok(()) => info!(self.log, "ImportedAttestation"; "source" => "gossip"),
-            Err(e) => {
-                warn!(self.log, "InvalidAttestation"; "source" => "gossip", "error" => format!("{:?}", e))
+        if batch.is_empty() {
+            return;
+        }
+
+        let (peers, attestations): (Vec<PeerId>, Vec<Attestation>) = batch.into_iter().unzip();
+        let results = self.chain.process_attestation_batch(attestations);
+
+        let mut imported = 0;
+        for (peer_id, result) in peers.iter().zip(results) {
+            match result {
+                Ok(()) => imported += 1,
+                Err(e) => warn!(
+                    self.log,
+                    "InvalidAttestation";
+                    "source" => "gossip",
+                    "peer" => format!("{:?}", peer_id),
+                    "error" => format!("{:?}", e)
+                ),
             }
         }
+
+        info!(
+            self.log,
+            "ImportedAttestations";
+            "source" => "gossip",
+            "count" => imported,
+            "batch_size" => peers.len()
+        );
     }
 
     /// Iterate through the `import_queue` and process any complete blocks.
@@ -772,6 +804,18 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
                         );
                     }
                 }
+                BlockProcessingOutcome::WeakSubjectivityConflict { checkpoint, found } => {
+                    error!(
+                        self.log, "WeakSubjectivityConflict";
+                        "source" => source,
+                        "msg" => "block conflicts with the weak subjectivity checkpoint",
+                        "checkpoint_root" => format!("{}", checkpoint.root),
+                        "checkpoint_epoch" => checkpoint.epoch,
+                        "found_root" => format!("{}", found),
+                        "peer" => format!("{:?}", peer_id),
+                    );
+                    network.disconnect(peer_id, GoodbyeReason::Fault);
+                }
                 _ => {
                     debug!(
                         self.log, "InvalidBlock";
@@ -800,9 +844,11 @@ impl<T: BeaconChainTypes> SimpleSync<T> {
 /// Build a `HelloMessage` representing the state of the given `beacon_chain`.
 fn hello_message<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) -> HelloMessage {
     let spec = &beacon_chain.spec;
+    let fork_digest = beacon_chain.fork_digest();
     let state = &beacon_chain.head().beacon_state;
 
     HelloMessage {
+        fork_digest,
         network_id: spec.chain_id,
         latest_finalized_root: state.finalized_root,
         latest_finalized_epoch: state.finalized_epoch,
@@ -136,14 +136,11 @@ impl<T: BeaconChainTypes> AttestationService for AttestationServiceInstance<T> {
                     "type" => "valid_attestation",
                 );
 
-                // TODO: Obtain topics from the network service properly.
-                let topic = types::TopicBuilder::new("beacon_chain".to_string()).build();
                 let message = PubsubMessage::Attestation(attestation);
 
                 // Publish the attestation to the p2p network via gossipsub.
                 self.network_chan
                     .send(NetworkMessage::Publish {
-                        topics: vec![topic],
                         message: Box::new(message),
                     })
                     .unwrap_or_else(|e| {
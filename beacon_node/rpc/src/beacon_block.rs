@@ -50,7 +50,21 @@ impl<T: BeaconChainTypes> BeaconBlockService for BeaconBlockServiceInstance<T> {
             }
         };
 
-        let produced_block = match self.chain.produce_block(randao_reveal) {
+        let graffiti = match graffiti_from_bytes(req.get_graffiti()) {
+            Some(graffiti) => graffiti,
+            None => {
+                let log_clone = self.log.clone();
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::InvalidArgument,
+                        Some("Graffiti exceeds 32 bytes".to_string()),
+                    ))
+                    .map_err(move |e| warn!(log_clone, "failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        let produced_block = match self.chain.produce_block(randao_reveal, graffiti) {
             Ok((block, _state)) => block,
             Err(e) => {
                 // could not produce a block
@@ -103,15 +117,11 @@ impl<T: BeaconChainTypes> BeaconBlockService for BeaconBlockServiceInstance<T> {
                                 "block_slot" => block.slot,
                             );
 
-                            // TODO: Obtain topics from the network service properly.
-                            let topic =
-                                types::TopicBuilder::new("beacon_chain".to_string()).build();
                             let message = PubsubMessage::Block(block);
 
                             // Publish the block to the p2p network via gossipsub.
                             self.network_chan
                                 .send(NetworkMessage::Publish {
-                                    topics: vec![topic],
                                     message: Box::new(message),
                                 })
                                 .unwrap_or_else(|e| {
@@ -166,3 +176,13 @@ impl<T: BeaconChainTypes> BeaconBlockService for BeaconBlockServiceInstance<T> {
         ctx.spawn(f)
     }
 }
+
+/// Pads `bytes` with zeros to fill a block's graffiti field, returning `None` if it is too long.
+fn graffiti_from_bytes(bytes: &[u8]) -> Option<[u8; 32]> {
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut graffiti = [0; 32];
+    graffiti[..bytes.len()].copy_from_slice(bytes);
+    Some(graffiti)
+}
@@ -5,6 +5,7 @@ use protos::services::{Empty, Fork, NodeInfoResponse};
 use protos::services_grpc::BeaconNodeService;
 use slog::{trace, warn};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone)]
 pub struct BeaconNodeServiceInstance<T: BeaconChainTypes> {
@@ -38,6 +39,10 @@ impl<T: BeaconChainTypes> BeaconNodeService for BeaconNodeServiceInstance<T> {
         node_info.set_genesis_time(genesis_time);
         node_info.set_genesis_slot(spec.genesis_slot.as_u64());
         node_info.set_chain_id(u32::from(spec.chain_id));
+        // Allows the validator client to detect skew between its clock and ours.
+        if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+            node_info.set_time_ms(now.as_millis() as u64);
+        }
 
         // send the node_info the requester
         let error_log = self.log.clone();
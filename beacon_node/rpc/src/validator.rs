@@ -2,7 +2,10 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use bls::PublicKey;
 use futures::Future;
 use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
-use protos::services::{ActiveValidator, GetDutiesRequest, GetDutiesResponse, ValidatorDuty};
+use protos::services::{
+    ActiveValidator, GetDutiesRequest, GetDutiesResponse, GetLivenessRequest, GetLivenessResponse,
+    ValidatorDuty,
+};
 use protos::services_grpc::ValidatorService;
 use slog::{trace, warn};
 use ssz::Decode;
@@ -70,6 +73,9 @@ impl<T: BeaconChainTypes> ValidatorService for ValidatorServiceInstance<T> {
             }
         };
 
+        // the indices of the active validators, whose registry entries are proven on request
+        let mut active_indices = vec![];
+
         // get the duties for each validator
         for validator_pk in validators.get_public_keys() {
             let mut active_validator = ActiveValidator::new();
@@ -159,14 +165,112 @@ impl<T: BeaconChainTypes> ValidatorService for ValidatorServiceInstance<T> {
             duty.set_attestation_slot(attestation_duties.slot.as_u64());
             duty.set_attestation_shard(attestation_duties.shard);
             duty.set_committee_len(attestation_duties.committee_len as u64);
+            duty.set_validator_index(val_index as u64);
+            active_indices.push(val_index);
 
             active_validator.set_duty(duty);
             resp_validators.push(active_validator);
         }
 
+        if req.get_include_proof() {
+            match state.duties_proof(&active_indices, epoch, spec) {
+                Ok((state_root, partial)) => {
+                    resp.set_state_root(state_root.as_bytes().to_vec());
+                    resp.set_proof_indices(partial.indices);
+                    resp.set_proof_chunks(
+                        partial
+                            .chunks
+                            .iter()
+                            .map(|chunk| chunk.as_bytes().to_vec())
+                            .collect(),
+                    );
+                }
+                Err(e) => {
+                    let log_clone = self.log.clone();
+                    let f = sink
+                        .fail(RpcStatus::new(
+                            RpcStatusCode::FailedPrecondition,
+                            Some(format!("Unable to prove duties: {:?}", e)),
+                        ))
+                        .map_err(move |e| warn!(log_clone, "Failed to reply {:?}: {:?}", req, e));
+                    return ctx.spawn(f);
+                }
+            }
+        }
+
         let f = sink
             .success(resp)
             .map_err(move |e| println!("failed to reply {:?}: {:?}", req, e));
         ctx.spawn(f)
     }
+    /// For a list of validator public keys, returns whether each validator attested or proposed a
+    /// block during the requested epoch, according to the canonical chain.
+    ///
+    /// Validators which are not in the registry are reported as not live.
+    fn get_validator_liveness(
+        &mut self,
+        ctx: RpcContext,
+        req: GetLivenessRequest,
+        sink: UnarySink<GetLivenessResponse>,
+    ) {
+        trace!(self.log, "RPC request"; "endpoint" => "GetValidatorLiveness", "epoch" => req.get_epoch());
+
+        let epoch = Epoch::from(req.get_epoch());
+
+        let mut validator_indices = Vec::new();
+        let mut is_known = Vec::new();
+        for validator_pk in req.get_validators().get_public_keys() {
+            let public_key = match PublicKey::from_ssz_bytes(validator_pk) {
+                Ok(v) => v,
+                Err(_) => {
+                    let log_clone = self.log.clone();
+                    let f = sink
+                        .fail(RpcStatus::new(
+                            RpcStatusCode::InvalidArgument,
+                            Some("Invalid public_key".to_string()),
+                        ))
+                        .map_err(move |_| warn!(log_clone, "failed to reply {:?}", req));
+                    return ctx.spawn(f);
+                }
+            };
+
+            match self.chain.validator_index(&public_key) {
+                Some(index) => {
+                    validator_indices.push(index);
+                    is_known.push(true);
+                }
+                None => is_known.push(false),
+            }
+        }
+
+        let liveness = match self.chain.validator_liveness(epoch, &validator_indices) {
+            Ok(v) => v,
+            Err(e) => {
+                let log_clone = self.log.clone();
+                let f = sink
+                    .fail(RpcStatus::new(
+                        RpcStatusCode::FailedPrecondition,
+                        Some(format!("Unable to determine liveness: {:?}", e)),
+                    ))
+                    .map_err(move |e| warn!(log_clone, "failed to reply {:?}: {:?}", req, e));
+                return ctx.spawn(f);
+            }
+        };
+
+        // Interleave the results for known validators with `false` for unknown ones.
+        let mut liveness = liveness.into_iter();
+        let mut resp = GetLivenessResponse::new();
+        resp.set_is_live(
+            is_known
+                .into_iter()
+                .map(|known| known && liveness.next().unwrap_or(false))
+                .collect(),
+        );
+
+        let log_clone = self.log.clone();
+        let f = sink
+            .success(resp)
+            .map_err(move |e| warn!(log_clone, "failed to reply {:?}: {:?}", req, e));
+        ctx.spawn(f)
+    }
 }
@@ -0,0 +1,592 @@
+//! Detects slashable attestations and blocks amongst those seen by the beacon node.
+//!
+//! Attestations and block headers are queued as they are verified, then checked in batches by
+//! `Slasher::process_queued`, against every attestation and block recorded in the slasher's own
+//! store:
+//!
+//! - an attestation for the same target as another by the same validator is a double vote.
+//! - an attestation which surrounds, or is surrounded by, another by the same validator is a
+//!   surround vote, detected with the spans of `spans`.
+//! - a block header for the same slot as another by the same proposer is a double proposal.
+//!
+//! Surround votes are checked against the attestations of the last `history_length` epochs only,
+//! and the records of earlier epochs are deleted by `prune`.
+mod prune;
+mod spans;
+
+use self::prune::{prune, PruneIndex};
+use self::spans::{SpanKind, Spans};
+use parking_lot::Mutex;
+use ssz::{Decode, Encode};
+use std::collections::HashSet;
+use std::mem;
+use store::{DBColumn, Store};
+use tree_hash::TreeHash;
+use types::{
+    AttesterSlashing, BeaconBlockHeader, Epoch, Hash256, IndexedAttestation, ProposerSlashing,
+};
+
+/// The number of epochs of attestations checked for surround votes, unless configured otherwise.
+pub const DEFAULT_HISTORY_LENGTH: u64 = 4096;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    DBError(store::Error),
+    /// A stored span chunk has the wrong number of bytes.
+    InvalidSpanChunk {
+        length: usize,
+    },
+    /// An attestation is recorded under this root, but is not stored.
+    MissingAttestation(Hash256),
+    /// The roots recorded of a validator at a target have the wrong number of bytes.
+    InvalidAttesterRecord {
+        length: usize,
+    },
+    /// The stored index of keys to prune is malformed.
+    InvalidPruneIndex,
+}
+
+impl From<store::Error> for Error {
+    fn from(e: store::Error) -> Error {
+        Error::DBError(e)
+    }
+}
+
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Error {
+        Error::DBError(e.into())
+    }
+}
+
+/// The slashings found by `Slasher::process_queued`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Slashings {
+    pub attester_slashings: Vec<AttesterSlashing>,
+    pub proposer_slashings: Vec<ProposerSlashing>,
+}
+
+impl Slashings {
+    pub fn is_empty(&self) -> bool {
+        self.attester_slashings.is_empty() && self.proposer_slashings.is_empty()
+    }
+}
+
+/// A surround vote of a validator, found by its spans.
+enum Surround {
+    /// The new attestation surrounds the validator's attestation with this target.
+    Surrounds(u64),
+    /// The new attestation is surrounded by the validator's attestation with this target.
+    SurroundedBy(u64),
+}
+
+pub struct Slasher<S: Store> {
+    store: S,
+    history_length: u64,
+    slots_per_epoch: u64,
+    attestation_queue: Mutex<Vec<IndexedAttestation>>,
+    block_queue: Mutex<Vec<(u64, BeaconBlockHeader)>>,
+    /// Held whilst processing, so that concurrent batches do not overwrite each other's spans.
+    process_lock: Mutex<()>,
+}
+
+impl<S: Store> Slasher<S> {
+    /// Instantiates a slasher which records to `store`, its own database, which is not shared with
+    /// the beacon chain.
+    pub fn new(store: S, history_length: u64, slots_per_epoch: u64) -> Self {
+        Self {
+            store,
+            history_length,
+            slots_per_epoch,
+            attestation_queue: Mutex::new(vec![]),
+            block_queue: Mutex::new(vec![]),
+            process_lock: Mutex::new(()),
+        }
+    }
+
+    /// Queues a verified attestation to be checked.
+    pub fn accept_attestation(&self, attestation: IndexedAttestation) {
+        self.attestation_queue.lock().push(attestation);
+    }
+
+    /// Queues the header of a verified block, proposed by the validator at `proposer_index`, to be
+    /// checked.
+    pub fn accept_block_header(&self, proposer_index: u64, header: BeaconBlockHeader) {
+        self.block_queue.lock().push((proposer_index, header));
+    }
+
+    /// Checks and records every queued attestation and block header, returning the slashings of
+    /// those which conflict with another.
+    ///
+    /// Attestations with a source, and block headers with a slot, more than `history_length`
+    /// epochs before `current_epoch` are dropped, and the records of those epochs are deleted.
+    pub fn process_queued(&self, current_epoch: Epoch) -> Result<Slashings, Error> {
+        let _lock = self.process_lock.lock();
+
+        let attestations = mem::replace(&mut *self.attestation_queue.lock(), vec![]);
+        let blocks = mem::replace(&mut *self.block_queue.lock(), vec![]);
+
+        let lowest_epoch = current_epoch.as_u64().saturating_sub(self.history_length);
+        prune(&self.store, lowest_epoch)?;
+
+        let mut index = PruneIndex::default();
+        let slashings = Slashings {
+            attester_slashings: self.process_attestations(
+                attestations,
+                lowest_epoch,
+                &mut index,
+            )?,
+            proposer_slashings: self.process_block_headers(blocks, lowest_epoch, &mut index)?,
+        };
+        index.flush(&self.store)?;
+
+        Ok(slashings)
+    }
+
+    fn process_attestations(
+        &self,
+        attestations: Vec<IndexedAttestation>,
+        lowest_epoch: u64,
+        index: &mut PruneIndex,
+    ) -> Result<Vec<AttesterSlashing>, Error> {
+        let store = &self.store;
+
+        let mut min_targets = Spans::new(SpanKind::Min);
+        let mut max_targets = Spans::new(SpanKind::Max);
+        let mut found = HashSet::new();
+        let mut slashings = vec![];
+
+        for attestation in attestations {
+            let source = attestation.data.source_epoch.as_u64();
+            let target = attestation.data.target_epoch.as_u64();
+            if source < lowest_epoch {
+                continue;
+            }
+
+            let root = Hash256::from_slice(&attestation.tree_hash_root());
+            let column = DBColumn::SlasherIndexedAttestations.into();
+            if !store.key_exists(column, root.as_bytes())? {
+                store.put_bytes(column, root.as_bytes(), &attestation.as_ssz_bytes())?;
+                index.insert(column, root.as_bytes().to_vec(), target);
+            }
+
+            let validators = attestation
+                .custody_bit_0_indices
+                .iter()
+                .chain(&attestation.custody_bit_1_indices);
+
+            for &validator in validators {
+                let mut conflicts = vec![];
+
+                // The same vote, aggregated differently, is neither slashable nor recorded again.
+                let recorded = self.recorded_attestations(validator, target)?;
+                if recorded
+                    .iter()
+                    .all(|(_, other)| other.data != attestation.data)
+                {
+                    for (other_root, other) in &recorded {
+                        if other.is_double_vote(&attestation) {
+                            conflicts
+                                .push(((*other_root, other.clone()), (root, attestation.clone())));
+                        }
+                    }
+                    self.record_attestation(validator, target, root, recorded.is_empty(), index)?;
+                }
+
+                let surround = check_and_update_spans(
+                    store,
+                    &mut min_targets,
+                    &mut max_targets,
+                    validator,
+                    (source, target),
+                    lowest_epoch,
+                )?;
+                // Of the attestations at the target of the span, not every one need be surrounding
+                // or surrounded.
+                match surround {
+                    Some(Surround::Surrounds(other_target)) => {
+                        if let Some(other) = self
+                            .recorded_attestations(validator, other_target)?
+                            .into_iter()
+                            .find(|other| attestation.is_surround_vote(&other.1))
+                        {
+                            conflicts.push(((root, attestation.clone()), other));
+                        }
+                    }
+                    Some(Surround::SurroundedBy(other_target)) => {
+                        if let Some(other) = self
+                            .recorded_attestations(validator, other_target)?
+                            .into_iter()
+                            .find(|other| other.1.is_surround_vote(&attestation))
+                        {
+                            conflicts.push((other, (root, attestation.clone())));
+                        }
+                    }
+                    None => {}
+                }
+
+                // One slashing covers every validator which made both attestations.
+                for ((root_1, attestation_1), (root_2, attestation_2)) in conflicts {
+                    if found.insert((root_1, root_2)) {
+                        slashings.push(AttesterSlashing {
+                            attestation_1,
+                            attestation_2,
+                        });
+                    }
+                }
+            }
+        }
+
+        min_targets.flush(store, index)?;
+        max_targets.flush(store, index)?;
+
+        Ok(slashings)
+    }
+
+    fn process_block_headers(
+        &self,
+        headers: Vec<(u64, BeaconBlockHeader)>,
+        lowest_epoch: u64,
+        index: &mut PruneIndex,
+    ) -> Result<Vec<ProposerSlashing>, Error> {
+        let column = DBColumn::SlasherProposals.into();
+        let mut slashings = vec![];
+
+        for (proposer_index, header) in headers {
+            let epoch = header.slot.epoch(self.slots_per_epoch).as_u64();
+            if epoch < lowest_epoch {
+                continue;
+            }
+
+            let key = record_key(proposer_index, header.slot.as_u64());
+
+            match self.store.get_bytes(column, &key)? {
+                Some(bytes) => {
+                    let recorded = BeaconBlockHeader::from_ssz_bytes(&bytes)?;
+                    if recorded != header {
+                        slashings.push(ProposerSlashing {
+                            proposer_index,
+                            header_1: recorded,
+                            header_2: header,
+                        });
+                    }
+                }
+                None => {
+                    self.store.put_bytes(column, &key, &header.as_ssz_bytes())?;
+                    index.insert(column, key, epoch);
+                }
+            }
+        }
+
+        Ok(slashings)
+    }
+
+    /// Returns every attestation recorded of `validator` for `target`, with its root, in the order
+    /// they were recorded.
+    fn recorded_attestations(
+        &self,
+        validator: u64,
+        target: u64,
+    ) -> Result<Vec<(Hash256, IndexedAttestation)>, Error> {
+        let bytes = match self.store.get_bytes(
+            DBColumn::SlasherAttesterRecords.into(),
+            &record_key(validator, target),
+        )? {
+            Some(bytes) => bytes,
+            None => return Ok(vec![]),
+        };
+        if bytes.len() % 32 != 0 {
+            return Err(Error::InvalidAttesterRecord {
+                length: bytes.len(),
+            });
+        }
+
+        bytes
+            .chunks(32)
+            .map(|root| {
+                let root = Hash256::from_slice(root);
+                let bytes = self
+                    .store
+                    .get_bytes(DBColumn::SlasherIndexedAttestations.into(), root.as_bytes())?
+                    .ok_or(Error::MissingAttestation(root))?;
+
+                Ok((root, IndexedAttestation::from_ssz_bytes(&bytes)?))
+            })
+            .collect()
+    }
+
+    /// Adds `root` to the attestations recorded of `validator` for `target`, indexing the record
+    /// for pruning if it is the first.
+    fn record_attestation(
+        &self,
+        validator: u64,
+        target: u64,
+        root: Hash256,
+        first: bool,
+        index: &mut PruneIndex,
+    ) -> Result<(), Error> {
+        let column = DBColumn::SlasherAttesterRecords.into();
+        let key = record_key(validator, target);
+
+        let mut roots = self.store.get_bytes(column, &key)?.unwrap_or_else(Vec::new);
+        roots.extend_from_slice(root.as_bytes());
+        self.store.put_bytes(column, &key, &roots)?;
+        if first {
+            index.insert(column, key, target);
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the attestation of `validator` from `source` to `target` against the validator's spans,
+/// then adds it to them.
+///
+/// The min targets are updated from the epoch before `source` down to `lowest_epoch`, and the
+/// max targets from the epoch after `source` up to `target`. Each update stops at the first span
+/// which already covers the attestation, since every span beyond it does too.
+fn check_and_update_spans<S: Store>(
+    store: &S,
+    min_targets: &mut Spans,
+    max_targets: &mut Spans,
+    validator: u64,
+    (source, target): (u64, u64),
+    lowest_epoch: u64,
+) -> Result<Option<Surround>, Error> {
+    let surround = match min_targets.get(store, validator, source)? {
+        Some(min_target) if min_target < target => Some(Surround::Surrounds(min_target)),
+        _ => match max_targets.get(store, validator, source)? {
+            Some(max_target) if max_target > target => Some(Surround::SurroundedBy(max_target)),
+            _ => None,
+        },
+    };
+
+    let mut epoch = source;
+    while epoch > lowest_epoch {
+        epoch -= 1;
+        if min_targets
+            .get(store, validator, epoch)?
+            .map_or(false, |min_target| min_target <= target)
+        {
+            break;
+        }
+        min_targets.set(store, validator, epoch, target)?;
+    }
+
+    for epoch in source + 1..target {
+        if max_targets
+            .get(store, validator, epoch)?
+            .map_or(false, |max_target| max_target >= target)
+        {
+            break;
+        }
+        max_targets.set(store, validator, epoch, target)?;
+    }
+
+    Ok(surround)
+}
+
+/// Returns the key of the record of a validator at an epoch or slot.
+fn record_key(validator: u64, at: u64) -> Vec<u8> {
+    [validator.to_be_bytes(), at.to_be_bytes()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+    use types::{AggregateSignature, AttestationData, Signature, Slot};
+
+    const SLOTS_PER_EPOCH: u64 = 8;
+
+    fn slasher() -> Slasher<MemoryStore> {
+        Slasher::new(MemoryStore::open(), DEFAULT_HISTORY_LENGTH, SLOTS_PER_EPOCH)
+    }
+
+    fn attestation(validators: &[u64], source: u64, target: u64, root: u8) -> IndexedAttestation {
+        IndexedAttestation {
+            custody_bit_0_indices: validators.to_vec(),
+            custody_bit_1_indices: vec![],
+            data: AttestationData {
+                beacon_block_root: Hash256::from([root; 32]),
+                source_epoch: Epoch::new(source),
+                source_root: Hash256::zero(),
+                target_epoch: Epoch::new(target),
+                target_root: Hash256::zero(),
+                shard: 0,
+                previous_crosslink_root: Hash256::zero(),
+                crosslink_data_root: Hash256::zero(),
+            },
+            signature: AggregateSignature::new(),
+        }
+    }
+
+    fn process(slasher: &Slasher<MemoryStore>, attestations: &[IndexedAttestation]) -> Slashings {
+        for attestation in attestations {
+            slasher.accept_attestation(attestation.clone());
+        }
+        slasher.process_queued(Epoch::new(10)).unwrap()
+    }
+
+    fn slashing(
+        attestation_1: &IndexedAttestation,
+        attestation_2: &IndexedAttestation,
+    ) -> AttesterSlashing {
+        AttesterSlashing {
+            attestation_1: attestation_1.clone(),
+            attestation_2: attestation_2.clone(),
+        }
+    }
+
+    #[test]
+    fn detects_double_votes() {
+        let slasher = slasher();
+        let first = attestation(&[1, 2, 3], 3, 4, 1);
+        let second = attestation(&[2, 3], 3, 4, 2);
+
+        assert!(process(&slasher, &[first.clone()]).is_empty());
+        // The same vote, aggregated differently, is not slashable.
+        assert!(process(&slasher, &[attestation(&[1], 3, 4, 1)]).is_empty());
+        // Validators 2 and 3 are slashed by the same slashing.
+        assert_eq!(
+            process(&slasher, &[second.clone()]).attester_slashings,
+            vec![slashing(&first, &second)]
+        );
+    }
+
+    #[test]
+    fn detects_surround_votes() {
+        let slasher = slasher();
+        let inner = attestation(&[1], 4, 5, 1);
+        let outer = attestation(&[1], 2, 7, 2);
+        let later = attestation(&[1], 5, 6, 3);
+
+        assert!(process(&slasher, &[inner.clone(), later]).is_empty());
+        assert_eq!(
+            process(&slasher, &[outer.clone()]).attester_slashings,
+            vec![slashing(&outer, &inner)]
+        );
+
+        let slasher = self::slasher();
+        assert!(process(&slasher, &[outer.clone()]).is_empty());
+        assert_eq!(
+            process(&slasher, &[inner.clone()]).attester_slashings,
+            vec![slashing(&outer, &inner)]
+        );
+        // Validator 2 made neither.
+        assert!(process(&slasher, &[attestation(&[2], 3, 6, 1)]).is_empty());
+    }
+
+    #[test]
+    fn checks_every_attestation_at_a_target() {
+        let slasher = slasher();
+        let later_source = attestation(&[1], 6, 8, 1);
+        let outer = attestation(&[1], 4, 8, 2);
+        let inner = attestation(&[1], 5, 7, 3);
+
+        assert!(process(&slasher, &[later_source.clone()]).is_empty());
+        assert_eq!(
+            process(&slasher, &[outer.clone()]).attester_slashings,
+            vec![slashing(&later_source, &outer)]
+        );
+        // Only the second attestation recorded at target 8 surrounds the new one.
+        assert_eq!(
+            process(&slasher, &[inner.clone()]).attester_slashings,
+            vec![slashing(&outer, &inner)]
+        );
+    }
+
+    #[test]
+    fn prunes_records_before_the_history() {
+        let slasher = Slasher::new(MemoryStore::open(), 4, SLOTS_PER_EPOCH);
+        let first = attestation(&[1], 6, 8, 1);
+        let root = Hash256::from_slice(&first.tree_hash_root());
+        let stored = |column: DBColumn, key: &[u8]| slasher.store.key_exists(column.into(), key);
+
+        slasher.accept_attestation(first.clone());
+        assert!(slasher.process_queued(Epoch::new(10)).unwrap().is_empty());
+        assert_eq!(
+            stored(DBColumn::SlasherAttesterRecords, &record_key(1, 8)),
+            Ok(true)
+        );
+        assert_eq!(
+            stored(DBColumn::SlasherIndexedAttestations, root.as_bytes()),
+            Ok(true)
+        );
+
+        // The target is still within the history, so a double vote is found.
+        slasher.accept_attestation(attestation(&[1], 7, 8, 2));
+        assert_eq!(
+            slasher
+                .process_queued(Epoch::new(11))
+                .unwrap()
+                .attester_slashings
+                .len(),
+            1
+        );
+
+        assert!(slasher.process_queued(Epoch::new(13)).unwrap().is_empty());
+        assert_eq!(
+            stored(DBColumn::SlasherAttesterRecords, &record_key(1, 8)),
+            Ok(false)
+        );
+        assert_eq!(
+            stored(DBColumn::SlasherIndexedAttestations, root.as_bytes()),
+            Ok(false)
+        );
+        assert_eq!(
+            Spans::new(SpanKind::Max).get(&slasher.store, 1, 7),
+            Ok(Some(8))
+        );
+
+        // The spans of epochs 0 to 15 are kept until epoch 15 is before the history.
+        assert!(slasher.process_queued(Epoch::new(20)).unwrap().is_empty());
+        assert_eq!(
+            Spans::new(SpanKind::Max).get(&slasher.store, 1, 7),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn ignores_attestations_before_the_history() {
+        let slasher = Slasher::new(MemoryStore::open(), 4, SLOTS_PER_EPOCH);
+
+        assert!(process(&slasher, &[attestation(&[1], 5, 8, 1)]).is_empty());
+        assert!(process(&slasher, &[attestation(&[1], 5, 8, 2)]).is_empty());
+        assert!(process(&slasher, &[attestation(&[1], 6, 9, 1)]).is_empty());
+        assert_eq!(
+            process(&slasher, &[attestation(&[1], 6, 9, 2)])
+                .attester_slashings
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn detects_double_proposals() {
+        let slasher = slasher();
+        let header = |state_root: u8| BeaconBlockHeader {
+            slot: Slot::new(8),
+            previous_block_root: Hash256::zero(),
+            state_root: Hash256::from([state_root; 32]),
+            block_body_root: Hash256::zero(),
+            signature: Signature::empty_signature(),
+        };
+
+        slasher.accept_block_header(3, header(1));
+        slasher.accept_block_header(3, header(1));
+        slasher.accept_block_header(4, header(2));
+        slasher.accept_block_header(3, header(2));
+
+        assert_eq!(
+            slasher.process_queued(Epoch::new(1)).unwrap(),
+            Slashings {
+                attester_slashings: vec![],
+                proposer_slashings: vec![ProposerSlashing {
+                    proposer_index: 3,
+                    header_1: header(1),
+                    header_2: header(2),
+                }],
+            }
+        );
+    }
+}
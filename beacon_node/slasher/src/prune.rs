@@ -0,0 +1,168 @@
+//! Deletes the records of epochs before the slasher's history, so that its store does not grow
+//! without bound.
+//!
+//! Each key written by the slasher is indexed by the last epoch for which it is needed. The keys
+//! of an epoch indexed by each batch are stored under the epoch and the number of the batch, and
+//! the number of batches under the epoch alone. `prune` deletes the keys of every epoch before the
+//! history, then their index.
+use crate::Error;
+use std::collections::BTreeMap;
+use store::{DBColumn, Store};
+
+/// The key under which the first epoch not yet pruned is stored.
+const PRUNED_EPOCH_KEY: &[u8] = b"pruned_epoch";
+
+/// The keys indexed whilst processing a batch, by the last epoch for which they are needed. They
+/// are added to the stored index by `Self::flush`.
+#[derive(Default)]
+pub struct PruneIndex {
+    keys: BTreeMap<u64, Vec<(&'static str, Vec<u8>)>>,
+}
+
+impl PruneIndex {
+    /// Indexes `key` of `column` to be deleted once `epoch` is before the history.
+    pub fn insert(&mut self, column: &'static str, key: Vec<u8>, epoch: u64) {
+        self.keys
+            .entry(epoch)
+            .or_insert_with(Vec::new)
+            .push((column, key));
+    }
+
+    /// Writes the keys indexed to `store`.
+    pub fn flush<S: Store>(self, store: &S) -> Result<(), Error> {
+        let column = DBColumn::SlasherPruneIndex.into();
+
+        for (epoch, keys) in self.keys {
+            let batches = read_u64(store, &batches_key(epoch))?.unwrap_or(0);
+            store.put_bytes(column, &keys_key(epoch, batches), &encode_keys(&keys))?;
+            store.put_bytes(column, &batches_key(epoch), &(batches + 1).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Deletes every key indexed by an epoch before `lowest_epoch`.
+///
+/// Keys of such epochs must not be indexed afterwards, as they would never be deleted.
+pub fn prune<S: Store>(store: &S, lowest_epoch: u64) -> Result<(), Error> {
+    let column = DBColumn::SlasherPruneIndex.into();
+
+    // Nothing is stored before the first batch, which prunes first.
+    let pruned_epoch = read_u64(store, PRUNED_EPOCH_KEY)?.unwrap_or(lowest_epoch);
+    if pruned_epoch > lowest_epoch {
+        return Ok(());
+    }
+
+    for epoch in pruned_epoch..lowest_epoch {
+        let batches = match read_u64(store, &batches_key(epoch))? {
+            Some(batches) => batches,
+            None => continue,
+        };
+        for batch in 0..batches {
+            if let Some(bytes) = store.get_bytes(column, &keys_key(epoch, batch))? {
+                for (key_column, key) in decode_keys(&bytes)? {
+                    store.key_delete(&key_column, &key)?;
+                }
+            }
+            store.key_delete(column, &keys_key(epoch, batch))?;
+        }
+        store.key_delete(column, &batches_key(epoch))?;
+    }
+    store.put_bytes(column, PRUNED_EPOCH_KEY, &lowest_epoch.to_le_bytes())?;
+
+    Ok(())
+}
+
+fn batches_key(epoch: u64) -> Vec<u8> {
+    epoch.to_be_bytes().to_vec()
+}
+
+fn keys_key(epoch: u64, batch: u64) -> Vec<u8> {
+    [epoch.to_be_bytes(), batch.to_be_bytes()].concat()
+}
+
+fn read_u64<S: Store>(store: &S, key: &[u8]) -> Result<Option<u64>, Error> {
+    match store.get_bytes(DBColumn::SlasherPruneIndex.into(), key)? {
+        Some(bytes) => {
+            if bytes.len() != 8 {
+                return Err(Error::InvalidPruneIndex);
+            }
+            let mut le_bytes = [0; 8];
+            le_bytes.copy_from_slice(&bytes);
+            Ok(Some(u64::from_le_bytes(le_bytes)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Encodes each column and key, each preceded by its length in one byte.
+fn encode_keys(keys: &[(&'static str, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (column, key) in keys {
+        for part in &[column.as_bytes(), &key[..]] {
+            bytes.push(part.len() as u8);
+            bytes.extend_from_slice(part);
+        }
+    }
+    bytes
+}
+
+fn decode_keys(mut bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut keys = vec![];
+    while !bytes.is_empty() {
+        let column =
+            String::from_utf8(split_part(&mut bytes)?).map_err(|_| Error::InvalidPruneIndex)?;
+        keys.push((column, split_part(&mut bytes)?));
+    }
+    Ok(keys)
+}
+
+/// Removes the first part, preceded by its length, from `bytes`.
+fn split_part(bytes: &mut &[u8]) -> Result<Vec<u8>, Error> {
+    let (&len, rest) = bytes.split_first().ok_or(Error::InvalidPruneIndex)?;
+    if rest.len() < len as usize {
+        return Err(Error::InvalidPruneIndex);
+    }
+    let (part, rest) = rest.split_at(len as usize);
+    *bytes = rest;
+    Ok(part.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+
+    #[test]
+    fn prunes_epochs_before_the_history() {
+        let store = MemoryStore::open();
+        let column = DBColumn::SlasherProposals.into();
+        let put = |key: &[u8], epoch: u64, index: &mut PruneIndex| {
+            store.put_bytes(column, key, &[1]).unwrap();
+            index.insert(column, key.to_vec(), epoch);
+        };
+
+        prune(&store, 2).unwrap();
+        let mut index = PruneIndex::default();
+        put(b"a", 2, &mut index);
+        put(b"b", 3, &mut index);
+        index.flush(&store).unwrap();
+        let mut index = PruneIndex::default();
+        put(b"c", 2, &mut index);
+        index.flush(&store).unwrap();
+
+        prune(&store, 3).unwrap();
+        assert!(!store.key_exists(column, b"a").unwrap());
+        assert!(store.key_exists(column, b"b").unwrap());
+        assert!(!store.key_exists(column, b"c").unwrap());
+        assert_eq!(read_u64(&store, &batches_key(2)), Ok(None));
+        assert_eq!(read_u64(&store, &batches_key(3)), Ok(Some(1)));
+
+        // Pruning an earlier epoch again does nothing.
+        prune(&store, 1).unwrap();
+        prune(&store, 4).unwrap();
+        assert!(!store.key_exists(column, b"b").unwrap());
+        assert_eq!(read_u64(&store, &batches_key(3)), Ok(None));
+    }
+}
@@ -0,0 +1,189 @@
+//! The min and max target spans of each validator, from which surround votes are detected
+//! without comparing an attestation with every other of its validator.
+//!
+//! For a validator and an epoch `e`:
+//!
+//! - the min target is the least target of its attestations with a source later than `e`, so
+//!   that a new attestation with source `e` surrounds one of them iff its target is greater.
+//! - the max target is the greatest target of its attestations with a source earlier than `e` and
+//!   a target later than `e`, so that one of them surrounds a new attestation with source `e` iff
+//!   its target is less.
+//!
+//! The spans of `CHUNK_EPOCHS` consecutive epochs of a validator are stored under one key.
+use crate::prune::PruneIndex;
+use crate::Error;
+use std::collections::{HashMap, HashSet};
+use store::{DBColumn, Store};
+
+/// The number of epochs of a validator's span stored under one key.
+pub const CHUNK_EPOCHS: u64 = 16;
+
+/// The number of bytes of a target epoch in a stored chunk.
+const TARGET_BYTES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+    Min,
+    Max,
+}
+
+impl SpanKind {
+    fn column(self) -> &'static str {
+        match self {
+            SpanKind::Min => DBColumn::SlasherMinTargets.into(),
+            SpanKind::Max => DBColumn::SlasherMaxTargets.into(),
+        }
+    }
+
+    /// The value of an epoch which no attestation spans.
+    ///
+    /// A target is always later than the epoch it is the max target of, so no max target is `0`.
+    fn empty(self) -> u64 {
+        match self {
+            SpanKind::Min => u64::max_value(),
+            SpanKind::Max => 0,
+        }
+    }
+}
+
+/// The span chunks of one kind read or written whilst processing a batch of attestations.
+/// Modified chunks are written to the store by `Self::flush`.
+pub struct Spans {
+    kind: SpanKind,
+    chunks: HashMap<(u64, u64), Vec<u64>>,
+    dirty: HashSet<(u64, u64)>,
+    /// The chunks which were not in the store.
+    new: HashSet<(u64, u64)>,
+}
+
+impl Spans {
+    pub fn new(kind: SpanKind) -> Self {
+        Self {
+            kind,
+            chunks: HashMap::new(),
+            dirty: HashSet::new(),
+            new: HashSet::new(),
+        }
+    }
+
+    /// Returns the span of `validator` at `epoch`, if any attestation spans it.
+    pub fn get<S: Store>(
+        &mut self,
+        store: &S,
+        validator: u64,
+        epoch: u64,
+    ) -> Result<Option<u64>, Error> {
+        let empty = self.kind.empty();
+        let target = self.chunk_mut(store, validator, epoch)?[(epoch % CHUNK_EPOCHS) as usize];
+
+        Ok(Some(target).filter(|&target| target != empty))
+    }
+
+    /// Sets the span of `validator` at `epoch` to `target`.
+    pub fn set<S: Store>(
+        &mut self,
+        store: &S,
+        validator: u64,
+        epoch: u64,
+        target: u64,
+    ) -> Result<(), Error> {
+        self.chunk_mut(store, validator, epoch)?[(epoch % CHUNK_EPOCHS) as usize] = target;
+        self.dirty.insert((validator, epoch / CHUNK_EPOCHS));
+
+        Ok(())
+    }
+
+    /// Writes every modified chunk to `store`, indexing each new one to be pruned after its last
+    /// epoch.
+    pub fn flush<S: Store>(self, store: &S, index: &mut PruneIndex) -> Result<(), Error> {
+        for key in &self.dirty {
+            let bytes: Vec<u8> = self.chunks[key]
+                .iter()
+                .flat_map(|target| target.to_le_bytes().to_vec())
+                .collect();
+            store.put_bytes(self.kind.column(), &chunk_key(*key), &bytes)?;
+
+            if self.new.contains(key) {
+                let (_, chunk) = *key;
+                let last_epoch = (chunk + 1) * CHUNK_EPOCHS - 1;
+                index.insert(self.kind.column(), chunk_key(*key), last_epoch);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn chunk_mut<S: Store>(
+        &mut self,
+        store: &S,
+        validator: u64,
+        epoch: u64,
+    ) -> Result<&mut Vec<u64>, Error> {
+        let key = (validator, epoch / CHUNK_EPOCHS);
+
+        if !self.chunks.contains_key(&key) {
+            let chunk = match store.get_bytes(self.kind.column(), &chunk_key(key))? {
+                Some(bytes) => decode_chunk(&bytes)?,
+                None => {
+                    self.new.insert(key);
+                    vec![self.kind.empty(); CHUNK_EPOCHS as usize]
+                }
+            };
+            self.chunks.insert(key, chunk);
+        }
+
+        Ok(self.chunks.get_mut(&key).expect("chunk was inserted"))
+    }
+}
+
+/// Returns the key of the chunk of a validator, which sorts by validator then by epoch.
+fn chunk_key((validator, chunk): (u64, u64)) -> Vec<u8> {
+    [validator.to_be_bytes(), chunk.to_be_bytes()].concat()
+}
+
+fn decode_chunk(bytes: &[u8]) -> Result<Vec<u64>, Error> {
+    if bytes.len() != CHUNK_EPOCHS as usize * TARGET_BYTES {
+        return Err(Error::InvalidSpanChunk {
+            length: bytes.len(),
+        });
+    }
+
+    Ok(bytes
+        .chunks(TARGET_BYTES)
+        .map(|target| {
+            let mut le_bytes = [0; TARGET_BYTES];
+            le_bytes.copy_from_slice(target);
+            u64::from_le_bytes(le_bytes)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::MemoryStore;
+
+    #[test]
+    fn spans_are_flushed_to_the_store() {
+        let store = MemoryStore::open();
+
+        let mut spans = Spans::new(SpanKind::Min);
+        assert_eq!(spans.get(&store, 3, 20), Ok(None));
+        spans.set(&store, 3, 20, 25).unwrap();
+        spans.set(&store, 3, 40, 41).unwrap();
+        assert_eq!(spans.get(&store, 3, 20), Ok(Some(25)));
+
+        // Unflushed spans are not stored.
+        assert_eq!(Spans::new(SpanKind::Min).get(&store, 3, 20), Ok(None));
+
+        spans.flush(&store, &mut PruneIndex::default()).unwrap();
+        let mut spans = Spans::new(SpanKind::Min);
+        assert_eq!(spans.get(&store, 3, 20), Ok(Some(25)));
+        assert_eq!(spans.get(&store, 3, 21), Ok(None));
+        assert_eq!(spans.get(&store, 3, 40), Ok(Some(41)));
+        assert_eq!(spans.get(&store, 4, 20), Ok(None));
+
+        // The spans of each kind are kept apart.
+        assert_eq!(Spans::new(SpanKind::Max).get(&store, 3, 20), Ok(None));
+    }
+}
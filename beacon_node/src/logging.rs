@@ -0,0 +1,355 @@
+//! Builds the root logger from the logging CLI flags.
+//!
+//! Records may be filtered per crate or module and, optionally, also written to a log file which
+//! is rotated once it reaches a maximum size or age.
+use clap::ArgMatches;
+use slog::{o, Drain, FnValue, Level, OwnedKVList, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// The default size, in MB, at which the log file is rotated.
+pub const DEFAULT_LOGFILE_MAX_SIZE: u64 = 200;
+/// The default number of rotated log files which are kept.
+pub const DEFAULT_LOGFILE_MAX_NUMBER: usize = 5;
+
+/// Configuration of the logger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    /// Either `term` or `json`.
+    pub format: String,
+    /// The level of records from modules without a filter.
+    pub level: Level,
+    /// Levels for specific crates or modules, e.g., `network` or `network::service`.
+    pub filters: Vec<(String, Level)>,
+    pub logfile: Option<LogFileConfig>,
+}
+
+/// Configuration of the log file and its rotation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    /// The size in bytes at which the file is rotated.
+    pub max_size: u64,
+    /// The age at which the file is rotated, regardless of its size.
+    pub max_age: Option<Duration>,
+    /// The number of rotated files which are kept, i.e., `beacon.log.1` to `beacon.log.N`.
+    pub max_number: usize,
+}
+
+impl LogConfig {
+    /// Reads the config from the logging CLI flags.
+    pub fn from_cli_args(args: &ArgMatches) -> Result<Self, String> {
+        let level = parse_level(args.value_of("log-level").unwrap_or("info"))?;
+        let filters = match args.value_of("log-filter") {
+            Some(filters) => parse_filters(filters)?,
+            None => vec![],
+        };
+
+        let logfile = match args.value_of("logfile") {
+            Some(path) => {
+                let max_size = match args.value_of("logfile-max-size") {
+                    Some(size) => size
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid logfile-max-size: {}", size))?,
+                    None => DEFAULT_LOGFILE_MAX_SIZE,
+                };
+                let max_age = match args.value_of("logfile-max-age") {
+                    Some(hours) => Some(Duration::from_secs(
+                        hours
+                            .parse::<u64>()
+                            .map_err(|_| format!("Invalid logfile-max-age: {}", hours))?
+                            * 3600,
+                    )),
+                    None => None,
+                };
+                let max_number = match args.value_of("logfile-max-number") {
+                    Some(number) => number
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid logfile-max-number: {}", number))?,
+                    None => DEFAULT_LOGFILE_MAX_NUMBER,
+                };
+
+                Some(LogFileConfig {
+                    path: PathBuf::from(path),
+                    max_size: max_size * 1024 * 1024,
+                    max_age,
+                    max_number,
+                })
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            format: args.value_of("log-format").unwrap_or("term").to_string(),
+            level,
+            filters,
+            logfile,
+        })
+    }
+
+    /// Builds the root logger, writing to the terminal and to the log file, if any.
+    pub fn build_logger(&self) -> Result<slog::Logger, String> {
+        let mut drain: Box<dyn Drain<Ok = (), Err = slog::Never> + Send> =
+            match self.format.as_str() {
+                "json" => Box::new(json_drain(io::stdout()).fuse()),
+                _ => {
+                    let decorator = slog_term::TermDecorator::new().build();
+                    Box::new(slog_term::CompactFormat::new(decorator).build().fuse())
+                }
+            };
+
+        if let Some(logfile) = &self.logfile {
+            let file = RotatingFile::open(logfile.clone())
+                .map_err(|e| format!("Unable to open log file {:?}: {:?}", logfile.path, e))?;
+
+            let file_drain: Box<dyn Drain<Ok = (), Err = slog::Never> + Send> =
+                match self.format.as_str() {
+                    "json" => Box::new(json_drain(file).fuse()),
+                    _ => {
+                        let decorator = slog_term::PlainSyncDecorator::new(file);
+                        Box::new(slog_term::FullFormat::new(decorator).build().fuse())
+                    }
+                };
+
+            drain = Box::new(slog::Duplicate::new(drain, file_drain).ignore_res());
+        }
+
+        let drain = slog_async::Async::new(drain).build().fuse();
+        let drain = ModuleFilter {
+            drain,
+            level: self.level,
+            filters: self.filters.clone(),
+        };
+
+        Ok(slog::Logger::root(drain, o!()))
+    }
+}
+
+/// Returns a drain which writes each record as a single-line JSON object with the `ts`, `level`,
+/// `msg` and `module` of the record, along with its fields.
+fn json_drain<W: Write>(io: W) -> slog_json::Json<W> {
+    slog_json::Json::new(io)
+        .add_default_keys()
+        .add_key_value(o!("module" => FnValue(|record: &Record| record.module())))
+        .build()
+}
+
+/// Parses a level, e.g., `debug`.
+fn parse_level(level: &str) -> Result<Level, String> {
+    match level.to_lowercase().as_str() {
+        "crit" | "critical" => Ok(Level::Critical),
+        "error" => Ok(Level::Error),
+        "warn" | "warning" => Ok(Level::Warning),
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        _ => Err(format!("Invalid log level: {}", level)),
+    }
+}
+
+/// Parses comma-separated module levels, e.g., `network=debug,store=info`.
+fn parse_filters(filters: &str) -> Result<Vec<(String, Level)>, String> {
+    filters
+        .split(',')
+        .filter(|filter| !filter.trim().is_empty())
+        .map(|filter| {
+            let mut parts = filter.trim().splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(module), Some(level)) if !module.is_empty() => {
+                    Ok((module.to_string(), parse_level(level)?))
+                }
+                _ => Err(format!(
+                    "Invalid log filter: {}, expected MODULE=LEVEL",
+                    filter
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Drops records below the level of their module.
+///
+/// The level of a module is given by the longest filter which names it or one of its parents,
+/// or else the default level.
+struct ModuleFilter<D> {
+    drain: D,
+    level: Level,
+    filters: Vec<(String, Level)>,
+}
+
+impl<D> ModuleFilter<D> {
+    fn level_for(&self, module: &str) -> Level {
+        self.filters
+            .iter()
+            .filter(|(name, _)| {
+                module == name
+                    || (module.starts_with(name.as_str()) && module[name.len()..].starts_with("::"))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.level)
+    }
+}
+
+impl<D: Drain> Drain for ModuleFilter<D> {
+    type Ok = ();
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), D::Err> {
+        if record.level().is_at_least(self.level_for(record.module())) {
+            self.drain.log(record, values).map(|_| ())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A log file which, once it reaches its maximum size or age, is renamed to `<path>.1` and
+/// replaced by an empty file.
+///
+/// Previously rotated files are shifted to `<path>.2` and so on, and those beyond the retention
+/// limit are deleted.
+struct RotatingFile {
+    config: LogFileConfig,
+    file: File,
+    size: u64,
+    opened: Instant,
+}
+
+impl RotatingFile {
+    fn open(config: LogFileConfig) -> io::Result<Self> {
+        if let Some(parent) = config.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            config,
+            file,
+            size,
+            opened: Instant::now(),
+        })
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.config.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn needs_rotation(&self, len: usize) -> bool {
+        let too_large = self.size > 0 && self.size + len as u64 > self.config.max_size;
+        let too_old = self
+            .config
+            .max_age
+            .map_or(false, |max_age| self.opened.elapsed() >= max_age);
+
+        too_large || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.config.max_number == 0 {
+            fs::remove_file(&self.config.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.config.max_number));
+            for n in (1..self.config.max_number).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.config.path, self.rotated_path(1))?;
+        }
+
+        *self = Self::open(self.config.clone())?;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(
+            parse_filters("network=debug, store=INFO").unwrap(),
+            vec![
+                ("network".to_string(), Level::Debug),
+                ("store".to_string(), Level::Info)
+            ]
+        );
+        assert_eq!(parse_filters("").unwrap(), vec![]);
+        assert!(parse_filters("network").is_err());
+        assert!(parse_filters("=debug").is_err());
+        assert!(parse_filters("network=loud").is_err());
+    }
+
+    #[test]
+    fn uses_most_specific_filter() {
+        let filter = ModuleFilter {
+            drain: slog::Discard,
+            level: Level::Info,
+            filters: parse_filters("network=debug,network::rpc=error").unwrap(),
+        };
+
+        assert_eq!(filter.level_for("store"), Level::Info);
+        assert_eq!(filter.level_for("network"), Level::Debug);
+        assert_eq!(filter.level_for("network::service"), Level::Debug);
+        assert_eq!(filter.level_for("network::rpc::handler"), Level::Error);
+        assert_eq!(filter.level_for("network_config"), Level::Info);
+    }
+
+    #[test]
+    fn rotates_and_retains() {
+        let dir = std::env::temp_dir().join(format!("lighthouse-log-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("beacon.log");
+
+        let mut file = RotatingFile::open(LogFileConfig {
+            path: path.clone(),
+            max_size: 10,
+            max_age: None,
+            max_number: 2,
+        })
+        .unwrap();
+
+        for line in &["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+        assert_eq!(read("beacon.log"), Some("fourth\n".to_string()));
+        assert_eq!(read("beacon.log.1"), Some("third\n".to_string()));
+        assert_eq!(read("beacon.log.2"), Some("second\n".to_string()));
+        assert_eq!(read("beacon.log.3"), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
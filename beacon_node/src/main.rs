@@ -1,12 +1,17 @@
 extern crate slog;
 
+mod logging;
 mod run;
 
 use clap::{App, Arg};
 use client::{ClientConfig, Eth2Config};
-use eth2_config::{get_data_dir, read_from_file, write_to_file};
-use slog::{crit, o, Drain};
-use std::path::PathBuf;
+use eth2_config::{
+    config_file_args, get_data_dir, get_matches_with_config_file, read_from_file,
+    write_effective_config, write_to_file, DUMP_CONFIG_FLAG,
+};
+use logging::LogConfig;
+use slog::crit;
+use std::path::{Path, PathBuf};
 
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse";
 
@@ -14,13 +19,9 @@ pub const CLIENT_CONFIG_FILENAME: &str = "beacon-node.toml";
 pub const ETH2_CONFIG_FILENAME: &str = "eth2-spec.toml";
 
 fn main() {
-    let decorator = slog_term::TermDecorator::new().build();
-    let drain = slog_term::CompactFormat::new(decorator).build().fuse();
-    let drain = slog_async::Async::new(drain).build().fuse();
-    let logger = slog::Logger::root(drain, o!());
-
-    let matches = App::new("Lighthouse")
-        .version(version::version().as_str())
+    let version = version::version();
+    let app = App::new("Lighthouse")
+        .version(version.as_str())
         .author("Sigma Prime <contact@sigmaprime.io>")
         .about("Eth 2.0 Client")
         // file system related arguments
@@ -47,6 +48,54 @@ fn main() {
                 .help("One or more comma-delimited multi-addresses to bootstrap the p2p network.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("libp2p-addresses")
+                .long("libp2p-addresses")
+                .value_name("MULTIADDRS")
+                .help("One or more comma-delimited multi-addresses of static peers, which are redialed whenever they disconnect.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("trusted-peers")
+                .long("trusted-peers")
+                .value_name("PEER_IDS")
+                .help("One or more comma-delimited peer ids which are exempt from rate limiting.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("transport-preference")
+                .long("transport-preference")
+                .value_name("TRANSPORTS")
+                .help("Comma-delimited transports in order of preference (tcp, quic), deciding which address of a peer with several is dialed. Only tcp is supported until libp2p provides QUIC.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dual-stack")
+                .long("dual-stack")
+                .help("Also listen on IPv6 (::) wherever listening on IPv4 0.0.0.0.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("port-mapping")
+                .long("port-mapping")
+                .help("Map the TCP listen ports on the local router via UPnP, or NAT-PMP if UPnP is unavailable.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("nat-pmp-gateway")
+                .long("nat-pmp-gateway")
+                .value_name("IP_ADDRESS")
+                .help("The IPv4 address of the router to request NAT-PMP mappings from.")
+                .requires("port-mapping")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("validator-monitor-pubkeys")
+                .long("validator-monitor-pubkeys")
+                .value_name("PUBKEYS")
+                .help("One or more comma-delimited validator public keys whose performance is tracked via metrics and the HTTP API.")
+                .takes_value(true),
+        )
         // rpc related arguments
         .arg(
             Arg::with_name("rpc")
@@ -91,6 +140,42 @@ fn main() {
                 .help("Listen port for the HTTP server.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("http-max-replay-slots")
+                .long("http-max-replay-slots")
+                .value_name("SLOTS")
+                .help("The most slots a historical proof request may replay to rebuild a state.")
+                .takes_value(true),
+        )
+        // WebSocket related arguments
+        .arg(
+            Arg::with_name("ws")
+                .long("ws")
+                .value_name("WS")
+                .help("Enable the WebSocket event server.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("ws-address")
+                .long("ws-address")
+                .value_name("WSADDRESS")
+                .help("Listen address for the WebSocket server.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ws-port")
+                .long("ws-port")
+                .value_name("WSPORT")
+                .help("Listen port for the WebSocket server.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ws-proof-indices")
+                .long("ws-proof-indices")
+                .value_name("INDICES")
+                .help("Comma-separated generalized indices of the finalized state, a proof of which is pushed to WebSocket clients each time the finalized checkpoint changes.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("db")
                 .long("db")
@@ -100,6 +185,14 @@ fn main() {
                 .possible_values(&["disk", "memory"])
                 .default_value("memory"),
         )
+        .arg(
+            Arg::with_name("db-compression")
+                .long("db-compression")
+                .value_name("CODEC")
+                .help("The codec with which an on-disk database compresses blocks and states. Values written with either codec remain readable.")
+                .takes_value(true)
+                .possible_values(&["snappy", "zstd"]),
+        )
         .arg(
             Arg::with_name("spec-constants")
                 .long("spec-constants")
@@ -110,13 +203,142 @@ fn main() {
                 .possible_values(&["mainnet", "minimal"])
                 .default_value("minimal"),
         )
+        .arg(
+            Arg::with_name("checkpoint-state")
+                .long("checkpoint-state")
+                .value_name("PATH_OR_URL")
+                .help("A trusted, finalized SSZ BeaconState from which to start syncing instead of genesis.")
+                .requires("checkpoint-block")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("checkpoint-block")
+                .long("checkpoint-block")
+                .value_name("PATH_OR_URL")
+                .help("The SSZ BeaconBlock corresponding to the checkpoint state.")
+                .requires("checkpoint-state")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("wss-checkpoint")
+                .long("wss-checkpoint")
+                .value_name("BLOCK_ROOT:EPOCH")
+                .help("A trusted block root at the start of an epoch, through which the canonical chain must pass. Blocks of any other chain are rejected, and the node refuses to start if it already follows one.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("archive")
+                .long("archive")
+                .help("Retain the state of every finalized slot and index blocks and states by slot, serving historical queries without replay at the cost of disk.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("archive-diffs")
+                .long("archive-diffs")
+                .help("Store the archived states of skipped slots as diffs against the previous slot, rather than in full.")
+                .requires("archive")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("slasher")
+                .long("slasher")
+                .help("Check all verified attestations and blocks for double votes, surround votes and double proposals, and include the resulting slashings in produced blocks.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("slasher-history-length")
+                .long("slasher-history-length")
+                .value_name("EPOCHS")
+                .help("The number of epochs of attestations checked for surround votes by the slasher.")
+                .requires("slasher")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("The format of log output. The json format emits one object per record, for log aggregation systems.")
+                .takes_value(true)
+                .possible_values(&["term", "json"])
+                .default_value("term"),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .help("The level of log records to output, for modules without a filter.")
+                .takes_value(true)
+                .possible_values(&["crit", "error", "warn", "info", "debug", "trace"])
+                .default_value("info"),
+        )
+        .arg(
+            Arg::with_name("log-filter")
+                .long("log-filter")
+                .value_name("MODULE=LEVEL")
+                .help("One or more comma-delimited levels for specific crates or modules, e.g., network=debug,store=info.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logfile")
+                .long("logfile")
+                .value_name("PATH")
+                .help("A file to which logs are also written, in the format given by --log-format.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logfile-max-size")
+                .long("logfile-max-size")
+                .value_name("MB")
+                .help("The size at which the log file is rotated.")
+                .requires("logfile")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logfile-max-age")
+                .long("logfile-max-age")
+                .value_name("HOURS")
+                .help("The age at which the log file is rotated, regardless of its size.")
+                .requires("logfile")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logfile-max-number")
+                .long("logfile-max-number")
+                .value_name("COUNT")
+                .help("The number of rotated log files to keep.")
+                .requires("logfile")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("recent-genesis")
                 .long("recent-genesis")
                 .short("r")
                 .help("When present, genesis will be within 30 minutes prior. Only for testing"),
         )
-        .get_matches();
+        .args(&config_file_args());
+
+    let matches = match get_matches_with_config_file(app, std::env::args_os()) {
+        Ok(matches) => matches,
+        Err(e) => {
+            eprintln!("Failed to load config file: {}", e);
+            return;
+        }
+    };
+
+    let logger = match LogConfig::from_cli_args(&matches).and_then(|c| c.build_logger()) {
+        Ok(logger) => logger,
+        Err(e) => {
+            eprintln!("Failed to initialize logging: {}", e);
+            return;
+        }
+    };
+
+    if let Some(path) = matches.value_of(DUMP_CONFIG_FLAG) {
+        if let Err(e) = write_effective_config(&matches, Path::new(path)) {
+            crit!(logger, "Failed to dump config"; "error" => e);
+            return;
+        }
+    }
 
     let data_dir = match get_data_dir(&matches, PathBuf::from(DEFAULT_DATA_DIR)) {
         Ok(dir) => dir,
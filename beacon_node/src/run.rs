@@ -1,6 +1,6 @@
 use client::{
     error, notifier, BeaconChainTypes, Client, ClientConfig, ClientType, Eth2Config,
-    InitialiseBeaconChain,
+    InitialiseBeaconChain, WebSocketSender,
 };
 use futures::sync::oneshot;
 use futures::Future;
@@ -8,6 +8,7 @@ use slog::{error, info, warn};
 use std::cell::RefCell;
 use std::path::Path;
 use std::path::PathBuf;
+use store::compression::Compression;
 use store::{DiskStore, MemoryStore};
 use tokio::runtime::Builder;
 use tokio::runtime::Runtime;
@@ -103,14 +104,40 @@ pub fn run<T>(
     log: &slog::Logger,
 ) -> error::Result<()>
 where
-    T: BeaconChainTypes + InitialiseBeaconChain<T> + Clone + Send + Sync + 'static,
+    T: BeaconChainTypes<EventHandler = WebSocketSender<<T as BeaconChainTypes>::EthSpec>>
+        + InitialiseBeaconChain<T>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
     T::Store: OpenDatabase,
 {
-    let store = T::Store::open_database(&db_path)?;
+    let compression: Compression = client_config.db_compression.parse()?;
+    let store = T::Store::open_database(&db_path, compression)?;
+
+    // Upgrade databases written by previous releases before anything reads from them.
+    store::migrate_schema(&store)
+        .map_err(|e| format!("Unable to migrate database schema: {:?}", e))?;
+
+    let slasher_store = if client_config.slasher {
+        let slasher_db_path = client_config
+            .slasher_db_path()
+            .ok_or_else::<error::Error, _>(|| "Unable to access slasher database path".into())?;
+        Some(T::Store::open_database(&slasher_db_path, compression)?)
+    } else {
+        None
+    };
 
-    let client: Client<T> = Client::new(client_config, eth2_config, store, log.clone(), &executor)?;
+    let client: Client<T> = Client::new(
+        client_config,
+        eth2_config,
+        store,
+        slasher_store,
+        log.clone(),
+        &executor,
+    )?;
 
-    // run service until ctrl-c
+    // run service until ctrl-c (SIGINT) or SIGTERM
     let (ctrlc_send, ctrlc_oneshot) = oneshot::channel();
     let ctrlc_send_c = RefCell::new(Some(ctrlc_send));
     ctrlc::set_handler(move || {
@@ -131,8 +158,7 @@ where
     // perform global shutdown operations.
     info!(log, "Shutting down..");
     exit_signal.fire();
-    // shutdown the client
-    //    client.exit_signal.fire();
+    // Dropping the client stops its services and persists the chain to disk.
     drop(client);
     runtime.shutdown_on_idle().wait().unwrap();
     Ok(())
@@ -142,17 +168,18 @@ where
 ///
 /// Panics if unable to open the database.
 pub trait OpenDatabase: Sized {
-    fn open_database(path: &Path) -> error::Result<Self>;
+    fn open_database(path: &Path, compression: Compression) -> error::Result<Self>;
 }
 
 impl OpenDatabase for MemoryStore {
-    fn open_database(_path: &Path) -> error::Result<Self> {
+    fn open_database(_path: &Path, _compression: Compression) -> error::Result<Self> {
         Ok(MemoryStore::open())
     }
 }
 
 impl OpenDatabase for DiskStore {
-    fn open_database(path: &Path) -> error::Result<Self> {
-        DiskStore::open(path).map_err(|e| format!("Unable to open database: {:?}", e).into())
+    fn open_database(path: &Path, compression: Compression) -> error::Result<Self> {
+        DiskStore::open_with_compression(path, compression)
+            .map_err(|e| format!("Unable to open database: {:?}", e).into())
     }
 }
@@ -0,0 +1,301 @@
+//! Storage for the archive of finalized slots.
+//!
+//! An archive node indexes the block and state roots of every finalized slot by slot number, and
+//! retains the state of every such slot, including those which were skipped. The state of a
+//! block is always stored in full, under its root in the `BeaconState` column. The state of a
+//! skipped slot may instead be stored as a `StateDiff` against the state of the slot before it,
+//! trading the cost of resolving a chain of diffs for disk.
+use crate::{DBColumn, Error, Store, StoreItem};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::{BeaconState, EthSpec, Hash256, Slot};
+
+/// 32-byte key for accessing the `ArchiveTip`.
+pub const ARCHIVE_TIP_KEY: &str = "ARCHIVETIPARCHIVETIPARCHIVETIPAR";
+
+/// The number of bytes of a stored state compared at once when building a `StateDiff`.
+pub const DIFF_CHUNK_SIZE: usize = 64;
+
+/// The most diffs which may be chained before a state is stored in full again, which bounds the
+/// work of resolving any archived state.
+pub const MAX_DIFF_DEPTH: u64 = 32;
+
+/// How the states of skipped slots are retained by an archive node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StateStorage {
+    /// Every state is stored in full.
+    Full,
+    /// The states of skipped slots are stored as diffs against the state of the previous slot.
+    Diffs,
+}
+
+/// The roots of the block and state at a finalized slot.
+///
+/// The block root is that of the latest block at or before the slot, so it repeats across
+/// skipped slots.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct ArchivedSlot {
+    pub block_root: Hash256,
+    pub state_root: Hash256,
+}
+
+/// The latest slot which has been archived.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct ArchiveTip(pub Slot);
+
+impl StoreItem for ArchiveTip {
+    fn db_column() -> DBColumn {
+        DBColumn::Metadata
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &mut [u8]) -> Result<Self, Error> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// A run of bytes of a stored state, starting at `index * DIFF_CHUNK_SIZE`.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct DiffChunk {
+    pub index: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// The stored bytes of a state, expressed as the chunks in which they differ from the stored
+/// bytes of the state at `base`.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct StateDiff {
+    /// The root of the state this diff applies to.
+    pub base: Hash256,
+    /// The number of diffs between this state and a state stored in full, including this one.
+    pub depth: u64,
+    /// The length of the stored bytes of the state.
+    pub len: u64,
+    pub chunks: Vec<DiffChunk>,
+}
+
+impl StateDiff {
+    /// Returns the diff from `base_bytes`, the stored bytes of the state at `base` which is
+    /// `base_depth` diffs from a full state, to `bytes`.
+    pub fn new(base: Hash256, base_depth: u64, base_bytes: &[u8], bytes: &[u8]) -> Self {
+        let chunks = bytes
+            .chunks(DIFF_CHUNK_SIZE)
+            .enumerate()
+            .filter(|(i, chunk)| {
+                let start = i * DIFF_CHUNK_SIZE;
+                let end = std::cmp::min(start + chunk.len(), base_bytes.len());
+                start >= end || base_bytes[start..end] != **chunk
+            })
+            .map(|(i, chunk)| DiffChunk {
+                index: i as u64,
+                bytes: chunk.to_vec(),
+            })
+            .collect();
+
+        Self {
+            base,
+            depth: base_depth + 1,
+            len: bytes.len() as u64,
+            chunks,
+        }
+    }
+
+    /// Applies `self` to `base_bytes`, the stored bytes of the state at `self.base`.
+    pub fn apply(&self, base_bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut bytes = base_bytes.to_vec();
+        bytes.resize(self.len as usize, 0);
+
+        for chunk in &self.chunks {
+            let start = chunk.index as usize * DIFF_CHUNK_SIZE;
+            let end = start + chunk.bytes.len();
+            if end > bytes.len() {
+                return Err(Error::InvalidStateDiff(self.base));
+            }
+            bytes[start..end].copy_from_slice(&chunk.bytes);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl StoreItem for StateDiff {
+    fn db_column() -> DBColumn {
+        DBColumn::ArchiveStateDiff
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &mut [u8]) -> Result<Self, Error> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// Returns the key of `slot` in the `ArchiveSlot` column, which sorts in slot order.
+fn slot_key(slot: Slot) -> [u8; 8] {
+    slot.as_u64().to_be_bytes()
+}
+
+/// Returns the archived roots of `slot`, if it has been archived.
+pub fn get_archived_slot<S: Store>(store: &S, slot: Slot) -> Result<Option<ArchivedSlot>, Error> {
+    match store.get_bytes(DBColumn::ArchiveSlot.into(), &slot_key(slot))? {
+        Some(bytes) => Ok(Some(ArchivedSlot::from_ssz_bytes(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Indexes the roots of `slot`.
+pub fn put_archived_slot<S: Store>(
+    store: &S,
+    slot: Slot,
+    archived: &ArchivedSlot,
+) -> Result<(), Error> {
+    store.put_bytes(
+        DBColumn::ArchiveSlot.into(),
+        &slot_key(slot),
+        &archived.as_ssz_bytes(),
+    )
+}
+
+/// Returns the latest archived slot, if any slot has been archived.
+pub fn get_archive_tip<S: Store>(store: &S) -> Result<Option<Slot>, Error> {
+    let key = Hash256::from_slice(ARCHIVE_TIP_KEY.as_bytes());
+
+    Ok(store.get::<ArchiveTip>(&key)?.map(|tip| tip.0))
+}
+
+/// Sets the latest archived slot. All slots before it must already be archived.
+pub fn put_archive_tip<S: Store>(store: &S, slot: Slot) -> Result<(), Error> {
+    let key = Hash256::from_slice(ARCHIVE_TIP_KEY.as_bytes());
+
+    store.put(&key, &ArchiveTip(slot))
+}
+
+/// Returns the stored bytes of the state at `state_root`, resolving any chain of diffs, and the
+/// number of diffs resolved.
+pub fn get_state_bytes<S: Store>(
+    store: &S,
+    state_root: &Hash256,
+) -> Result<Option<(Vec<u8>, u64)>, Error> {
+    let state_column: &str = DBColumn::BeaconState.into();
+
+    let mut diffs = vec![];
+    let mut root = *state_root;
+    let base_bytes = loop {
+        if let Some(bytes) = store.get_bytes(state_column, root.as_bytes())? {
+            break bytes;
+        }
+        match store.get::<StateDiff>(&root)? {
+            // A longer chain was not written by `StateDiff::new`, and may be a cycle.
+            Some(diff) if (diffs.len() as u64) < MAX_DIFF_DEPTH => {
+                root = diff.base;
+                diffs.push(diff);
+            }
+            Some(_) => return Err(Error::InvalidStateDiff(root)),
+            None if diffs.is_empty() => return Ok(None),
+            None => return Err(Error::MissingDiffBase(root)),
+        }
+    };
+
+    let depth = diffs.len() as u64;
+    let bytes = diffs
+        .iter()
+        .rev()
+        .try_fold(base_bytes, |bytes, diff| diff.apply(&bytes))?;
+
+    Ok(Some((bytes, depth)))
+}
+
+/// Returns the state at `state_root`, whether it is stored in full or as a diff.
+pub fn get_archived_state<S: Store, E: EthSpec>(
+    store: &S,
+    state_root: &Hash256,
+) -> Result<Option<BeaconState<E>>, Error> {
+    match get_state_bytes(store, state_root)? {
+        Some((mut bytes, _)) => Ok(Some(BeaconState::from_store_bytes(&mut bytes)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+
+    #[test]
+    fn diff_round_trip() {
+        let base: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+        let mut changed = base.clone();
+        changed[70] = 0;
+        let diff = StateDiff::new(Hash256::zero(), 0, &base, &changed);
+        assert_eq!(diff.chunks.len(), 1);
+        assert_eq!(diff.chunks[0].index, 1);
+        assert_eq!(diff.apply(&base), Ok(changed));
+
+        let longer: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let diff = StateDiff::new(Hash256::zero(), 0, &base, &longer);
+        assert_eq!(diff.apply(&base), Ok(longer));
+
+        let shorter = base[..100].to_vec();
+        let diff = StateDiff::new(Hash256::zero(), 0, &base, &shorter);
+        assert!(diff.chunks.is_empty());
+        assert_eq!(diff.apply(&base), Ok(shorter));
+    }
+
+    #[test]
+    fn resolves_diff_chains() {
+        let store = MemoryStore::open();
+        let roots: Vec<Hash256> = (0..3).map(Hash256::from_low_u64_be).collect();
+        let states: Vec<Vec<u8>> = (0..3).map(|i| vec![i as u8; 100 + i]).collect();
+
+        store
+            .put_bytes(
+                DBColumn::BeaconState.into(),
+                roots[0].as_bytes(),
+                &states[0],
+            )
+            .unwrap();
+        for i in 1..3 {
+            let diff = StateDiff::new(roots[i - 1], i as u64 - 1, &states[i - 1], &states[i]);
+            store.put(&roots[i], &diff).unwrap();
+        }
+
+        for i in 0..3 {
+            assert_eq!(
+                get_state_bytes(&store, &roots[i]),
+                Ok(Some((states[i].clone(), i as u64)))
+            );
+        }
+        assert_eq!(get_state_bytes(&store, &Hash256::random()), Ok(None));
+
+        store
+            .key_delete(DBColumn::BeaconState.into(), roots[0].as_bytes())
+            .unwrap();
+        assert_eq!(
+            get_state_bytes(&store, &roots[2]),
+            Err(Error::MissingDiffBase(roots[0]))
+        );
+    }
+
+    #[test]
+    fn indexes_slots() {
+        let store = MemoryStore::open();
+        let archived = ArchivedSlot {
+            block_root: Hash256::from_low_u64_be(1),
+            state_root: Hash256::from_low_u64_be(2),
+        };
+
+        assert_eq!(get_archive_tip(&store), Ok(None));
+        put_archived_slot(&store, Slot::new(3), &archived).unwrap();
+        put_archive_tip(&store, Slot::new(3)).unwrap();
+
+        assert_eq!(get_archived_slot(&store, Slot::new(3)), Ok(Some(archived)));
+        assert_eq!(get_archived_slot(&store, Slot::new(4)), Ok(None));
+        assert_eq!(get_archive_tip(&store), Ok(Some(Slot::new(3))));
+    }
+}
@@ -0,0 +1,191 @@
+//! Transparent compression of the values of blocks and states.
+//!
+//! SSZ blocks and states are highly compressible, and reading them from disk dominates the cost
+//! of loading a state which is not cached. An on-disk store compresses values in the columns
+//! given by `is_compressed_column` with its configured `Compression`, marking each with the
+//! prefix of its codec. Values written with either codec, or before compression was introduced,
+//! remain readable, the latter until they are rewritten by the schema migration.
+use crate::{DBColumn, Error};
+use std::str::FromStr;
+
+/// Marks a value compressed with snappy.
+///
+/// An uncompressed value never starts with these bytes in practice. Blocks and states start with
+/// their slot, which these bytes would make implausibly large, and state diffs with the root of
+/// their base state, which matches with negligible probability.
+pub const SNAPPY_PREFIX: &[u8] = b"\xffsnappy\x00";
+
+/// Marks a value compressed with zstd, like `SNAPPY_PREFIX`.
+pub const ZSTD_PREFIX: &[u8] = b"\xffzstd\x00\x00\x00";
+
+/// The level at which values are compressed with zstd, which is its default.
+const ZSTD_LEVEL: i32 = 3;
+
+/// The codec with which an on-disk store compresses values.
+///
+/// Snappy is faster, whereas zstd produces smaller values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Snappy,
+    Zstd,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::Snappy
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snappy" => Ok(Compression::Snappy),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(format!("Unknown compression: {}", other)),
+        }
+    }
+}
+
+impl Compression {
+    fn prefix(self) -> &'static [u8] {
+        match self {
+            Compression::Snappy => SNAPPY_PREFIX,
+            Compression::Zstd => ZSTD_PREFIX,
+        }
+    }
+}
+
+/// Returns `true` if values in `column` are compressed by an on-disk store.
+pub fn is_compressed_column(column: &str) -> bool {
+    let compressed: [&str; 3] = [
+        DBColumn::BeaconBlock.into(),
+        DBColumn::BeaconState.into(),
+        DBColumn::ArchiveStateDiff.into(),
+    ];
+
+    compressed.contains(&column)
+}
+
+/// Returns the codec with which `value` was compressed by `compress`, if it was.
+pub fn compression_of(value: &[u8]) -> Option<Compression> {
+    [Compression::Snappy, Compression::Zstd]
+        .iter()
+        .cloned()
+        .find(|compression| value.starts_with(compression.prefix()))
+}
+
+/// Returns `true` if `value` was compressed by `compress`.
+pub fn is_compressed(value: &[u8]) -> bool {
+    compression_of(value).is_some()
+}
+
+/// Compresses `value` with `compression`, prefixing it with the prefix of the codec.
+pub fn compress(value: &[u8], compression: Compression) -> Result<Vec<u8>, Error> {
+    let compressed = match compression {
+        Compression::Snappy => snap::Encoder::new()
+            .compress_vec(value)
+            .map_err(|e| Error::CompressionError(format!("{:?}", e)))?,
+        Compression::Zstd => zstd::encode_all(value, ZSTD_LEVEL)
+            .map_err(|e| Error::CompressionError(format!("{:?}", e)))?,
+    };
+
+    let prefix = compression.prefix();
+    let mut bytes = Vec::with_capacity(prefix.len() + compressed.len());
+    bytes.extend_from_slice(prefix);
+    bytes.extend_from_slice(&compressed);
+    Ok(bytes)
+}
+
+/// Decompresses `value` if it was compressed by `compress`, with either codec, otherwise returns
+/// it unchanged.
+pub fn decompress(value: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let compression = match compression_of(&value) {
+        Some(compression) => compression,
+        None => return Ok(value),
+    };
+
+    let compressed = &value[compression.prefix().len()..];
+    match compression {
+        Compression::Snappy => snap::Decoder::new()
+            .decompress_vec(compressed)
+            .map_err(|e| Error::CompressionError(format!("{:?}", e))),
+        Compression::Zstd => {
+            zstd::decode_all(compressed).map_err(|e| Error::CompressionError(format!("{:?}", e)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiskStore, Store};
+    use tempfile::tempdir;
+
+    #[test]
+    fn round_trip() {
+        let value: Vec<u8> = (0..1024).map(|i| (i % 7) as u8).collect();
+
+        for &compression in &[Compression::Snappy, Compression::Zstd] {
+            let compressed = compress(&value, compression).unwrap();
+            assert_eq!(compression_of(&compressed), Some(compression));
+            assert!(compressed.len() < value.len());
+            assert_eq!(decompress(compressed), Ok(value.clone()));
+        }
+    }
+
+    #[test]
+    fn parses_compression() {
+        assert_eq!("snappy".parse(), Ok(Compression::Snappy));
+        assert_eq!("zstd".parse(), Ok(Compression::Zstd));
+        assert!("gzip".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    fn passes_through_uncompressed_values() {
+        let value = vec![8, 0, 0, 0, 42];
+
+        assert!(!is_compressed(&value));
+        assert_eq!(decompress(value.clone()), Ok(value));
+    }
+
+    #[test]
+    fn compressed_columns() {
+        assert!(is_compressed_column(DBColumn::BeaconState.into()));
+        assert!(is_compressed_column(DBColumn::BeaconBlock.into()));
+        assert!(!is_compressed_column(DBColumn::Metadata.into()));
+    }
+
+    #[test]
+    fn disk_store_compresses_values() {
+        let dir = tempdir().unwrap();
+        let store = DiskStore::open(dir.path()).unwrap();
+        let column = DBColumn::BeaconState.into();
+        let value = vec![3; 4096];
+
+        store.put_bytes(column, b"key", &value).unwrap();
+        assert_eq!(store.get_bytes(column, b"key"), Ok(Some(value.clone())));
+
+        // Compressing a store whose values are already compressed is a no-op.
+        store.compress_values().unwrap();
+        assert_eq!(store.get_bytes(column, b"key"), Ok(Some(value.clone())));
+    }
+
+    #[test]
+    fn disk_store_reads_either_codec() {
+        let dir = tempdir().unwrap();
+        let column = DBColumn::BeaconBlock.into();
+        let value = vec![5; 4096];
+
+        {
+            let store = DiskStore::open(dir.path()).unwrap();
+            store.put_bytes(column, b"snappy", &value).unwrap();
+        }
+
+        let store = DiskStore::open_with_compression(dir.path(), Compression::Zstd).unwrap();
+        store.put_bytes(column, b"zstd", &value).unwrap();
+        assert_eq!(store.get_bytes(column, b"snappy"), Ok(Some(value.clone())));
+        assert_eq!(store.get_bytes(column, b"zstd"), Ok(Some(value)));
+    }
+}
@@ -1,9 +1,40 @@
 use ssz::DecodeError;
+use state_processing::BlockReplayError;
+use types::{Hash256, Slot};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     SszDecodeError(DecodeError),
-    DBError { message: String },
+    DBError {
+        message: String,
+    },
+    /// The database was written by a newer release with an unknown schema.
+    SchemaVersionTooNew {
+        found: u64,
+        supported: u64,
+    },
+    /// There is no migration from the given schema version.
+    NoSchemaMigration {
+        from: u64,
+    },
+    /// The block at this root is needed to migrate the database, but is not stored.
+    MissingBlock(Hash256),
+    /// The archived diff against the state at this root does not apply to it.
+    InvalidStateDiff(Hash256),
+    /// The state at this root is the base of an archived diff, but is not stored.
+    MissingDiffBase(Hash256),
+    /// Reconstructing the state of the slot would replay more than `max` slots.
+    ReplayTooLong {
+        slot: Slot,
+        replay_slots: u64,
+        max: u64,
+    },
+    /// The block at this root is an ancestor of the requested slot, but neither it nor any
+    /// later ancestor has a stored state to replay from.
+    NoRestorePoint(Hash256),
+    BlockReplayError(BlockReplayError),
+    /// A value could not be compressed or decompressed.
+    CompressionError(String),
 }
 
 impl From<DecodeError> for Error {
@@ -12,6 +43,12 @@ impl From<DecodeError> for Error {
     }
 }
 
+impl From<BlockReplayError> for Error {
+    fn from(e: BlockReplayError) -> Error {
+        Error::BlockReplayError(e)
+    }
+}
+
 impl From<DBError> for Error {
     fn from(e: DBError) -> Error {
         Error::DBError { message: e.message }
@@ -1,30 +1,45 @@
 use super::*;
+use crate::compression::{compress, decompress, is_compressed, is_compressed_column, Compression};
 use db_key::Key;
 use leveldb::database::kv::KV;
 use leveldb::database::Database;
 use leveldb::error::Error as LevelDBError;
+use leveldb::iterator::Iterable;
 use leveldb::options::{Options, ReadOptions, WriteOptions};
 use std::path::Path;
 use std::sync::Arc;
 
 /// A wrapped leveldb database.
+///
+/// Values in the columns of blocks and states are compressed (see `crate::compression`).
 #[derive(Clone)]
 pub struct LevelDB {
     // Note: this `Arc` is only included because of an artificial constraint by gRPC. Hopefully we
     // can remove this one day.
     db: Arc<Database<BytesKey>>,
+    /// The codec with which values are compressed when written.
+    compression: Compression,
 }
 
 impl LevelDB {
     /// Open a database at `path`, creating a new database if one does not already exist.
+    ///
+    /// Values are compressed with the default `Compression`.
     pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::open_with_compression(path, Compression::default())
+    }
+
+    /// Open a database at `path` as `open` does, compressing values written with `compression`.
+    ///
+    /// Values already written with another codec are still read, but are not recompressed.
+    pub fn open_with_compression(path: &Path, compression: Compression) -> Result<Self, Error> {
         let mut options = Options::new();
 
         options.create_if_missing = true;
 
         let db = Arc::new(Database::open(path, options)?);
 
-        Ok(Self { db })
+        Ok(Self { db, compression })
     }
 
     fn read_options(&self) -> ReadOptions<BytesKey> {
@@ -62,18 +77,29 @@ impl Store for LevelDB {
     fn get_bytes(&self, col: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
         let column_key = Self::get_key_for_col(col, key);
 
-        self.db
-            .get(self.read_options(), column_key)
-            .map_err(Into::into)
+        match self.db.get(self.read_options(), column_key)? {
+            Some(val) if is_compressed_column(col) => Ok(Some(decompress(val)?)),
+            val => Ok(val),
+        }
     }
 
     /// Store some `value` in `column`, indexed with `key`.
     fn put_bytes(&self, col: &str, key: &[u8], val: &[u8]) -> Result<(), Error> {
         let column_key = Self::get_key_for_col(col, key);
 
-        self.db
-            .put(self.write_options(), column_key, val)
-            .map_err(Into::into)
+        if is_compressed_column(col) {
+            self.db
+                .put(
+                    self.write_options(),
+                    column_key,
+                    &compress(val, self.compression)?,
+                )
+                .map_err(Into::into)
+        } else {
+            self.db
+                .put(self.write_options(), column_key, val)
+                .map_err(Into::into)
+        }
     }
 
     /// Return `true` if `key` exists in `column`.
@@ -93,6 +119,39 @@ impl Store for LevelDB {
             .delete(self.write_options(), column_key)
             .map_err(Into::into)
     }
+
+    /// Compresses each value in a compressed column which was written before compression was
+    /// introduced.
+    fn compress_values(&self) -> Result<(), Error> {
+        // Each column is identified by a three byte prefix of its keys.
+        let keys: Vec<BytesKey> = self
+            .db
+            .keys_iter(self.read_options())
+            .filter(|key| {
+                key.key.len() >= 3
+                    && std::str::from_utf8(&key.key[..3]).map_or(false, is_compressed_column)
+            })
+            .collect();
+
+        for key in keys {
+            let val = match self
+                .db
+                .get(self.read_options(), BytesKey::from_u8(&key.key))?
+            {
+                Some(val) => val,
+                None => continue,
+            };
+            if !is_compressed(&val) {
+                self.db.put(
+                    self.write_options(),
+                    key,
+                    &compress(&val, self.compression)?,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl From<LevelDBError> for Error {
@@ -2,21 +2,31 @@
 //!
 //! Provides the following stores:
 //!
-//! - `DiskStore`: an on-disk store backed by leveldb. Used in production.
+//! - `DiskStore`: an on-disk store backed by leveldb, which compresses blocks and states. Used in
+//!   production.
 //! - `MemoryStore`: an in-memory store backed by a hash-map. Used for testing.
 //!
 //! Provides a simple API for storing/retrieving all types that sometimes needs type-hints. See
 //! tests for implementation examples.
 
+pub mod archive;
 mod block_at_slot;
+pub mod compression;
 mod errors;
 mod impls;
 mod leveldb_store;
 mod memory_store;
+mod schema;
+mod shutdown;
+mod state_at_slot;
 
 pub use self::leveldb_store::LevelDB as DiskStore;
 pub use self::memory_store::MemoryStore;
 pub use errors::Error;
+pub use schema::{
+    get_schema_version, migrate_schema, SchemaVersion, BEACON_CHAIN_DB_KEY, CURRENT_SCHEMA_VERSION,
+};
+pub use shutdown::{get_clean_shutdown, set_clean_shutdown};
 pub use types::*;
 
 /// An object capable of storing and retrieving objects implementing `StoreItem`.
@@ -58,6 +68,21 @@ pub trait Store: Sync + Send + Sized {
         block_at_slot::get_block_at_preceeding_slot(self, slot, start_block_root)
     }
 
+    /// Returns the state at `slot` on the chain of the block at `head_block_root`, replaying the
+    /// blocks after the nearest stored state before it.
+    ///
+    /// Returns `None` if no block at or before `slot` is known. Returns an error if more than
+    /// `max_replay_slots` slots would be replayed.
+    fn get_state_at_slot<E: EthSpec>(
+        &self,
+        head_block_root: Hash256,
+        slot: Slot,
+        max_replay_slots: u64,
+        spec: &ChainSpec,
+    ) -> Result<Option<BeaconState<E>>, Error> {
+        state_at_slot::get_state_at_slot(self, head_block_root, slot, max_replay_slots, spec)
+    }
+
     /// Retrieve some bytes in `column` with `key`.
     fn get_bytes(&self, column: &str, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
 
@@ -69,6 +94,13 @@ pub trait Store: Sync + Send + Sized {
 
     /// Removes `key` from `column`.
     fn key_delete(&self, column: &str, key: &[u8]) -> Result<(), Error>;
+
+    /// Rewrites any uncompressed values in the columns which `Self` compresses.
+    ///
+    /// Stores which do not compress values, such as `MemoryStore`, need not implement this.
+    fn compress_values(&self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 /// A unique column identifier.
@@ -76,6 +108,16 @@ pub enum DBColumn {
     BeaconBlock,
     BeaconState,
     BeaconChain,
+    Metadata,
+    LightClient,
+    ArchiveSlot,
+    ArchiveStateDiff,
+    SlasherMinTargets,
+    SlasherMaxTargets,
+    SlasherAttesterRecords,
+    SlasherIndexedAttestations,
+    SlasherProposals,
+    SlasherPruneIndex,
 }
 
 impl<'a> Into<&'a str> for DBColumn {
@@ -85,6 +127,16 @@ impl<'a> Into<&'a str> for DBColumn {
             DBColumn::BeaconBlock => &"blk",
             DBColumn::BeaconState => &"ste",
             DBColumn::BeaconChain => &"bch",
+            DBColumn::Metadata => &"met",
+            DBColumn::LightClient => &"lcl",
+            DBColumn::ArchiveSlot => &"arc",
+            DBColumn::ArchiveStateDiff => &"asd",
+            DBColumn::SlasherMinTargets => &"smn",
+            DBColumn::SlasherMaxTargets => &"smx",
+            DBColumn::SlasherAttesterRecords => &"sar",
+            DBColumn::SlasherIndexedAttestations => &"sia",
+            DBColumn::SlasherProposals => &"spr",
+            DBColumn::SlasherPruneIndex => &"spi",
         }
     }
 }
@@ -0,0 +1,465 @@
+//! Versioning of the on-disk database schema.
+//!
+//! The schema version is stored under a fixed key in the `Metadata` column. On startup,
+//! `migrate_schema` upgrades a database from the version it was written with to
+//! `CURRENT_SCHEMA_VERSION` by applying each migration in turn, avoiding the need to re-sync when
+//! the layout of the database changes between releases.
+use crate::{DBColumn, Error, Store, StoreItem};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::{BeaconBlock, Hash256, Slot};
+
+/// 32-byte key for accessing the persisted beacon chain.
+pub const BEACON_CHAIN_DB_KEY: &str = "PERSISTEDBEACONCHAINPERSISTEDBEA";
+
+/// 32-byte key for accessing the `SchemaVersion`.
+pub const SCHEMA_VERSION_KEY: &str = "SCHEMAVERSIONSCHEMAVERSIONSCHEMA";
+
+/// The schema version written by this release.
+pub const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(3);
+
+/// The version of the database schema.
+///
+/// A database without a stored version predates versioning and is considered to be version `0`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Encode, Decode)]
+pub struct SchemaVersion(pub u64);
+
+impl StoreItem for SchemaVersion {
+    fn db_column() -> DBColumn {
+        DBColumn::Metadata
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &mut [u8]) -> Result<Self, Error> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// Upgrades the database from version `n` to version `n + 1`.
+type Migration<S> = fn(&S) -> Result<(), Error>;
+
+/// Returns the migration which upgrades a database from `version`, if one exists.
+fn migration_from<S: Store>(version: SchemaVersion) -> Option<Migration<S>> {
+    match version {
+        SchemaVersion(0) => Some(migrate_0_to_1::<S>),
+        SchemaVersion(1) => Some(migrate_1_to_2::<S>),
+        SchemaVersion(2) => Some(migrate_2_to_3::<S>),
+        _ => None,
+    }
+}
+
+/// Version 1 introduces the schema version itself.
+///
+/// Unversioned databases may have been written by releases which persisted the beacon chain
+/// without the root of its anchor block, or without the oldest block stored, so these are added.
+fn migrate_0_to_1<S: Store>(store: &S) -> Result<(), Error> {
+    let column: &str = DBColumn::BeaconChain.into();
+    let key = BEACON_CHAIN_DB_KEY.as_bytes();
+
+    let bytes = match store.get_bytes(column, key)? {
+        Some(bytes) => bytes,
+        None => return Ok(()),
+    };
+
+    if PersistedBeaconChainV1::from_ssz_bytes(&bytes).is_ok() {
+        return Ok(());
+    }
+
+    let (canonical_head, genesis_block_root, anchor_block_root, state) =
+        if let Ok(chain) = PersistedBeaconChainWithAnchor::from_ssz_bytes(&bytes) {
+            (
+                chain.canonical_head,
+                chain.genesis_block_root,
+                chain.anchor_block_root,
+                chain.state,
+            )
+        } else {
+            // Only a chain started from genesis could be persisted without its anchor.
+            let chain = PersistedBeaconChainV0::from_ssz_bytes(&bytes)?;
+            (
+                chain.canonical_head,
+                chain.genesis_block_root,
+                chain.genesis_block_root,
+                chain.state,
+            )
+        };
+
+    // No history before the anchor was stored by releases which did not record the oldest block.
+    let anchor_block: BeaconBlock = store
+        .get(&anchor_block_root)?
+        .ok_or_else(|| Error::MissingBlock(anchor_block_root))?;
+
+    let chain = PersistedBeaconChainV1 {
+        canonical_head,
+        genesis_block_root,
+        anchor_block_root,
+        oldest_block_slot: anchor_block.slot,
+        oldest_block_parent: anchor_block.previous_block_root,
+        state,
+    };
+
+    store.put_bytes(column, key, &chain.as_ssz_bytes())
+}
+
+/// Version 2 compresses the values of blocks and states, so a release which cannot decompress
+/// them refuses to open the database.
+fn migrate_1_to_2<S: Store>(store: &S) -> Result<(), Error> {
+    store.compress_values()
+}
+
+/// The persisted beacon chain as written by releases which started from genesis only.
+///
+/// The fields of variable length are left encoded, as only the fixed fields change.
+#[derive(Encode, Decode)]
+struct PersistedBeaconChainV0 {
+    canonical_head: Vec<u8>,
+    genesis_block_root: Hash256,
+    state: Vec<u8>,
+}
+
+/// The persisted beacon chain as written by releases which started from a checkpoint, but did not
+/// back-fill history.
+#[derive(Encode, Decode)]
+struct PersistedBeaconChainWithAnchor {
+    canonical_head: Vec<u8>,
+    genesis_block_root: Hash256,
+    anchor_block_root: Hash256,
+    state: Vec<u8>,
+}
+
+/// The persisted beacon chain of schema version 1.
+#[derive(Encode, Decode)]
+struct PersistedBeaconChainV1 {
+    canonical_head: Vec<u8>,
+    genesis_block_root: Hash256,
+    anchor_block_root: Hash256,
+    oldest_block_slot: Slot,
+    oldest_block_parent: Hash256,
+    state: Vec<u8>,
+}
+
+/// Version 3 persists the operation pool and fork choice with the beacon chain.
+///
+/// Both are added empty, unless the operation pool is already present, as it is when written by
+/// releases which persisted it without changing the schema version. Fork choice is rebuilt from
+/// the canonical chain when loaded empty.
+fn migrate_2_to_3<S: Store>(store: &S) -> Result<(), Error> {
+    let column: &str = DBColumn::BeaconChain.into();
+    let key = BEACON_CHAIN_DB_KEY.as_bytes();
+
+    let bytes = match store.get_bytes(column, key)? {
+        Some(bytes) => bytes,
+        None => return Ok(()),
+    };
+
+    let chain = if let Ok(chain) = PersistedBeaconChainWithOpPool::from_ssz_bytes(&bytes) {
+        chain
+    } else {
+        let chain = PersistedBeaconChainV1::from_ssz_bytes(&bytes)?;
+        PersistedBeaconChainWithOpPool {
+            canonical_head: chain.canonical_head,
+            op_pool: EmptyOperationPool::default().as_ssz_bytes(),
+            genesis_block_root: chain.genesis_block_root,
+            anchor_block_root: chain.anchor_block_root,
+            oldest_block_slot: chain.oldest_block_slot,
+            oldest_block_parent: chain.oldest_block_parent,
+            state: chain.state,
+        }
+    };
+
+    let chain = PersistedBeaconChainV3 {
+        canonical_head: chain.canonical_head,
+        op_pool: chain.op_pool,
+        fork_choice: EmptyForkChoice::default().as_ssz_bytes(),
+        genesis_block_root: chain.genesis_block_root,
+        anchor_block_root: chain.anchor_block_root,
+        oldest_block_slot: chain.oldest_block_slot,
+        oldest_block_parent: chain.oldest_block_parent,
+        state: chain.state,
+    };
+
+    store.put_bytes(column, key, &chain.as_ssz_bytes())
+}
+
+/// The persisted beacon chain as written by releases which persisted the operation pool, but
+/// not fork choice.
+#[derive(Encode, Decode)]
+struct PersistedBeaconChainWithOpPool {
+    canonical_head: Vec<u8>,
+    op_pool: Vec<u8>,
+    genesis_block_root: Hash256,
+    anchor_block_root: Hash256,
+    oldest_block_slot: Slot,
+    oldest_block_parent: Hash256,
+    state: Vec<u8>,
+}
+
+/// The persisted beacon chain of schema version 3.
+#[derive(Encode, Decode)]
+struct PersistedBeaconChainV3 {
+    canonical_head: Vec<u8>,
+    op_pool: Vec<u8>,
+    fork_choice: Vec<u8>,
+    genesis_block_root: Hash256,
+    anchor_block_root: Hash256,
+    oldest_block_slot: Slot,
+    oldest_block_parent: Hash256,
+    state: Vec<u8>,
+}
+
+/// An empty persisted operation pool. Empty lists are encoded alike whatever their elements, so
+/// this is encoded as any pool without operations.
+#[derive(Default, Encode)]
+struct EmptyOperationPool {
+    attestations: Vec<u8>,
+    deposits: Vec<u8>,
+    attester_slashings: Vec<u8>,
+    proposer_slashings: Vec<u8>,
+    voluntary_exits: Vec<u8>,
+    transfers: Vec<u8>,
+}
+
+/// An empty persisted fork choice, encoded as `EmptyOperationPool` is.
+#[derive(Default, Encode)]
+struct EmptyForkChoice {
+    block_roots: Vec<u8>,
+    latest_messages: Vec<u8>,
+}
+
+/// Returns the schema version of `store`.
+pub fn get_schema_version<S: Store>(store: &S) -> Result<SchemaVersion, Error> {
+    let key = Hash256::from_slice(SCHEMA_VERSION_KEY.as_bytes());
+
+    Ok(store.get(&key)?.unwrap_or(SchemaVersion(0)))
+}
+
+/// Sets the schema version of `store`.
+fn put_schema_version<S: Store>(store: &S, version: SchemaVersion) -> Result<(), Error> {
+    let key = Hash256::from_slice(SCHEMA_VERSION_KEY.as_bytes());
+
+    store.put(&key, &version)
+}
+
+/// Upgrades `store` to `CURRENT_SCHEMA_VERSION`, applying each intermediate migration in order.
+///
+/// The version is written after each successful migration, so an interrupted upgrade resumes
+/// from the last completed step. Returns an error if `store` was written by a newer release.
+pub fn migrate_schema<S: Store>(store: &S) -> Result<(), Error> {
+    let mut version = get_schema_version(store)?;
+
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(Error::SchemaVersionTooNew {
+            found: version.0,
+            supported: CURRENT_SCHEMA_VERSION.0,
+        });
+    }
+
+    while version < CURRENT_SCHEMA_VERSION {
+        let migration = migration_from::<S>(version)
+            .ok_or_else(|| Error::NoSchemaMigration { from: version.0 })?;
+
+        migration(store)?;
+
+        version = SchemaVersion(version.0 + 1);
+        put_schema_version(store, version)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+    use types::{EthSpec, MinimalEthSpec};
+
+    #[test]
+    fn migrates_unversioned_store() {
+        let store = MemoryStore::open();
+
+        assert_eq!(get_schema_version(&store), Ok(SchemaVersion(0)));
+
+        migrate_schema(&store).unwrap();
+
+        assert_eq!(get_schema_version(&store), Ok(CURRENT_SCHEMA_VERSION));
+
+        // Migrating an up-to-date store is a no-op.
+        migrate_schema(&store).unwrap();
+
+        assert_eq!(get_schema_version(&store), Ok(CURRENT_SCHEMA_VERSION));
+    }
+
+    fn put_block<S: Store>(store: &S, slot: u64, previous_block_root: Hash256) -> Hash256 {
+        let mut block = BeaconBlock::empty(&MinimalEthSpec::default_spec());
+        block.slot = Slot::new(slot);
+        block.previous_block_root = previous_block_root;
+
+        let root = block.canonical_root();
+        store.put(&root, &block).unwrap();
+        root
+    }
+
+    fn put_persisted_chain<S: Store>(store: &S, chain: &impl Encode) {
+        let column: &str = DBColumn::BeaconChain.into();
+        store
+            .put_bytes(
+                column,
+                BEACON_CHAIN_DB_KEY.as_bytes(),
+                &chain.as_ssz_bytes(),
+            )
+            .unwrap();
+    }
+
+    fn get_persisted_chain<S: Store>(store: &S) -> PersistedBeaconChainV1 {
+        let column: &str = DBColumn::BeaconChain.into();
+        let bytes = store
+            .get_bytes(column, BEACON_CHAIN_DB_KEY.as_bytes())
+            .unwrap()
+            .unwrap();
+        PersistedBeaconChainV1::from_ssz_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn adds_anchor_and_oldest_block_to_chain_from_genesis() {
+        let store = MemoryStore::open();
+        let genesis_block_root = put_block(&store, 0, Hash256::zero());
+        put_persisted_chain(
+            &store,
+            &PersistedBeaconChainV0 {
+                canonical_head: vec![1, 2, 3],
+                genesis_block_root,
+                state: vec![4, 5],
+            },
+        );
+
+        migrate_0_to_1(&store).unwrap();
+
+        let chain = get_persisted_chain(&store);
+        assert_eq!(chain.canonical_head, vec![1, 2, 3]);
+        assert_eq!(chain.genesis_block_root, genesis_block_root);
+        assert_eq!(chain.anchor_block_root, genesis_block_root);
+        assert_eq!(chain.oldest_block_slot, Slot::new(0));
+        assert_eq!(chain.oldest_block_parent, Hash256::zero());
+        assert_eq!(chain.state, vec![4, 5]);
+    }
+
+    #[test]
+    fn adds_oldest_block_to_chain_from_checkpoint() {
+        let store = MemoryStore::open();
+        let parent = Hash256::from_slice(&[7; 32]);
+        let anchor_block_root = put_block(&store, 64, parent);
+        let genesis_block_root = Hash256::from_slice(&[9; 32]);
+        put_persisted_chain(
+            &store,
+            &PersistedBeaconChainWithAnchor {
+                canonical_head: vec![1, 2, 3],
+                genesis_block_root,
+                anchor_block_root,
+                state: vec![4, 5],
+            },
+        );
+
+        migrate_0_to_1(&store).unwrap();
+
+        let chain = get_persisted_chain(&store);
+        assert_eq!(chain.canonical_head, vec![1, 2, 3]);
+        assert_eq!(chain.genesis_block_root, genesis_block_root);
+        assert_eq!(chain.anchor_block_root, anchor_block_root);
+        assert_eq!(chain.oldest_block_slot, Slot::new(64));
+        assert_eq!(chain.oldest_block_parent, parent);
+        assert_eq!(chain.state, vec![4, 5]);
+
+        // A chain in the layout of version 1 is left as it is.
+        migrate_0_to_1(&store).unwrap();
+        assert_eq!(get_persisted_chain(&store).oldest_block_parent, parent);
+    }
+
+    #[test]
+    fn adds_op_pool_and_fork_choice_to_chain() {
+        let v1 = PersistedBeaconChainV1 {
+            canonical_head: vec![1, 2, 3],
+            genesis_block_root: Hash256::from_slice(&[9; 32]),
+            anchor_block_root: Hash256::from_slice(&[8; 32]),
+            oldest_block_slot: Slot::new(64),
+            oldest_block_parent: Hash256::from_slice(&[7; 32]),
+            state: vec![4, 5],
+        };
+        let with_op_pool = PersistedBeaconChainWithOpPool {
+            canonical_head: v1.canonical_head.clone(),
+            op_pool: vec![6],
+            genesis_block_root: v1.genesis_block_root,
+            anchor_block_root: v1.anchor_block_root,
+            oldest_block_slot: v1.oldest_block_slot,
+            oldest_block_parent: v1.oldest_block_parent,
+            state: v1.state.clone(),
+        };
+
+        for (chain, op_pool) in vec![
+            (
+                v1.as_ssz_bytes(),
+                EmptyOperationPool::default().as_ssz_bytes(),
+            ),
+            (with_op_pool.as_ssz_bytes(), vec![6]),
+        ] {
+            let store = MemoryStore::open();
+            let column: &str = DBColumn::BeaconChain.into();
+            store
+                .put_bytes(column, BEACON_CHAIN_DB_KEY.as_bytes(), &chain)
+                .unwrap();
+
+            migrate_2_to_3(&store).unwrap();
+
+            let bytes = store
+                .get_bytes(column, BEACON_CHAIN_DB_KEY.as_bytes())
+                .unwrap()
+                .unwrap();
+            let chain = PersistedBeaconChainV3::from_ssz_bytes(&bytes).unwrap();
+            assert_eq!(chain.canonical_head, v1.canonical_head);
+            assert_eq!(chain.op_pool, op_pool);
+            assert_eq!(chain.fork_choice, vec![8, 0, 0, 0, 8, 0, 0, 0]);
+            assert_eq!(chain.genesis_block_root, v1.genesis_block_root);
+            assert_eq!(chain.anchor_block_root, v1.anchor_block_root);
+            assert_eq!(chain.oldest_block_slot, v1.oldest_block_slot);
+            assert_eq!(chain.oldest_block_parent, v1.oldest_block_parent);
+            assert_eq!(chain.state, v1.state);
+        }
+    }
+
+    #[test]
+    fn refuses_chain_without_anchor_block() {
+        let store = MemoryStore::open();
+        let genesis_block_root = Hash256::from_slice(&[9; 32]);
+        put_persisted_chain(
+            &store,
+            &PersistedBeaconChainV0 {
+                canonical_head: vec![1, 2, 3],
+                genesis_block_root,
+                state: vec![4, 5],
+            },
+        );
+
+        assert_eq!(
+            migrate_0_to_1(&store),
+            Err(Error::MissingBlock(genesis_block_root))
+        );
+    }
+
+    #[test]
+    fn rejects_newer_store() {
+        let store = MemoryStore::open();
+        let newer = SchemaVersion(CURRENT_SCHEMA_VERSION.0 + 1);
+
+        put_schema_version(&store, newer).unwrap();
+
+        assert_eq!(
+            migrate_schema(&store),
+            Err(Error::SchemaVersionTooNew {
+                found: newer.0,
+                supported: CURRENT_SCHEMA_VERSION.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Tracking of whether the database was closed cleanly.
+//!
+//! The flag is cleared when a node starts and set again once it has flushed its in-memory state
+//! during shutdown. Finding it unset on startup indicates that the previous process crashed or
+//! was killed, and that the persisted chain should be checked before it is trusted.
+use crate::{DBColumn, Error, Store, StoreItem};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::Hash256;
+
+/// 32-byte key for accessing the `CleanShutdown` flag.
+pub const CLEAN_SHUTDOWN_KEY: &str = "CLEANSHUTDOWNCLEANSHUTDOWNCLEANS";
+
+/// Indicates whether the last process to use the database shut down cleanly.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct CleanShutdown(pub bool);
+
+impl StoreItem for CleanShutdown {
+    fn db_column() -> DBColumn {
+        DBColumn::Metadata
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &mut [u8]) -> Result<Self, Error> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// Returns `true` if the last process to use `store` shut down cleanly.
+///
+/// A database without the flag has never been shut down cleanly (or is new).
+pub fn get_clean_shutdown<S: Store>(store: &S) -> Result<bool, Error> {
+    let key = Hash256::from_slice(CLEAN_SHUTDOWN_KEY.as_bytes());
+
+    Ok(store
+        .get::<CleanShutdown>(&key)?
+        .map_or(false, |flag| flag.0))
+}
+
+/// Sets the clean shutdown flag of `store`.
+pub fn set_clean_shutdown<S: Store>(store: &S, clean: bool) -> Result<(), Error> {
+    let key = Hash256::from_slice(CLEAN_SHUTDOWN_KEY.as_bytes());
+
+    store.put(&key, &CleanShutdown(clean))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+
+    #[test]
+    fn clean_shutdown_flag() {
+        let store = MemoryStore::open();
+
+        assert_eq!(get_clean_shutdown(&store), Ok(false));
+
+        set_clean_shutdown(&store, true).unwrap();
+        assert_eq!(get_clean_shutdown(&store), Ok(true));
+
+        set_clean_shutdown(&store, false).unwrap();
+        assert_eq!(get_clean_shutdown(&store), Ok(false));
+    }
+}
@@ -0,0 +1,143 @@
+//! Reconstruction of the state at any slot from the nearest stored state before it.
+//!
+//! A node which is not an archive node stores the post-state of each block it imports, but not
+//! the states of skipped slots, and some block states may be missing, e.g. if they were never
+//! written before an unclean shutdown. The nearest stored state (the "restore point") is loaded
+//! and the blocks after it are replayed to materialize the requested state.
+use crate::archive;
+use crate::{Error, Store};
+use state_processing::BlockReplayer;
+use types::{BeaconBlock, BeaconState, ChainSpec, EthSpec, Hash256, Slot};
+
+/// Returns the latest block at or before `slot` which is an ancestor of (or is) the block at
+/// `root`.
+fn get_block_at_or_before_slot<S: Store>(
+    store: &S,
+    slot: Slot,
+    mut root: Hash256,
+) -> Result<Option<(Hash256, BeaconBlock)>, Error> {
+    loop {
+        match store.get::<BeaconBlock>(&root)? {
+            Some(block) if block.slot <= slot => break Ok(Some((root, block))),
+            Some(block) => root = block.previous_block_root,
+            None => break Ok(None),
+        }
+    }
+}
+
+pub fn get_state_at_slot<S: Store, E: EthSpec>(
+    store: &S,
+    head_block_root: Hash256,
+    slot: Slot,
+    max_replay_slots: u64,
+    spec: &ChainSpec,
+) -> Result<Option<BeaconState<E>>, Error> {
+    let (block_root, mut block) = match get_block_at_or_before_slot(store, slot, head_block_root)? {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+
+    // An archive node retains the state of each finalized slot, so nothing need be replayed.
+    if let Some(archived) = archive::get_archived_slot(store, slot)? {
+        if archived.block_root == block_root {
+            if let Some(state) = archive::get_archived_state(store, &archived.state_root)? {
+                return Ok(Some(state));
+            }
+        }
+    }
+
+    // Walk back through the ancestors of the block until one has a stored state, checking the
+    // budget before reading each further block.
+    let mut blocks = vec![];
+    let restore_state = loop {
+        let replay_slots = (slot - block.slot).as_u64();
+        if replay_slots > max_replay_slots {
+            return Err(Error::ReplayTooLong {
+                slot,
+                replay_slots,
+                max: max_replay_slots,
+            });
+        }
+
+        if let Some(state) = store.get::<BeaconState<E>>(&block.state_root)? {
+            break state;
+        }
+
+        let parent_root = block.previous_block_root;
+        blocks.push(block);
+        block = store
+            .get(&parent_root)?
+            .ok_or_else(|| Error::NoRestorePoint(parent_root))?;
+    };
+
+    blocks.reverse();
+    let mut replayer = BlockReplayer::new(restore_state, spec).apply_blocks(&blocks)?;
+    replayer.advance_to(slot)?;
+
+    Ok(Some(replayer.into_state()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+    use types::test_utils::TestingBeaconStateBuilder;
+    use types::{Keypair, MinimalEthSpec};
+
+    /// Stores a state at slot `0` and an empty block with that state, returning the block root.
+    fn store_restore_point(store: &MemoryStore, spec: &ChainSpec) -> Hash256 {
+        let (state, _) = TestingBeaconStateBuilder::<MinimalEthSpec>::from_single_keypair(
+            4,
+            &Keypair::random(),
+            spec,
+        )
+        .build();
+        let state_root = state.canonical_root();
+        store.put(&state_root, &state).unwrap();
+
+        let mut block = BeaconBlock::empty(spec);
+        block.state_root = state_root;
+        let block_root = block.canonical_root();
+        store.put(&block_root, &block).unwrap();
+
+        block_root
+    }
+
+    #[test]
+    fn replays_skipped_slots() {
+        let spec = MinimalEthSpec::default_spec();
+        let store = MemoryStore::open();
+        let block_root = store_restore_point(&store, &spec);
+
+        let state: BeaconState<MinimalEthSpec> =
+            get_state_at_slot(&store, block_root, Slot::new(3), 4, &spec)
+                .unwrap()
+                .unwrap();
+        assert_eq!(state.slot, Slot::new(3));
+
+        assert_eq!(
+            get_state_at_slot::<_, MinimalEthSpec>(&store, block_root, Slot::new(5), 4, &spec)
+                .err(),
+            Some(Error::ReplayTooLong {
+                slot: Slot::new(5),
+                replay_slots: 5,
+                max: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_block() {
+        let spec = MinimalEthSpec::default_spec();
+        let store = MemoryStore::open();
+
+        let state = get_state_at_slot::<_, MinimalEthSpec>(
+            &store,
+            Hash256::random(),
+            Slot::new(1),
+            4,
+            &spec,
+        );
+        assert!(state.unwrap().is_none());
+    }
+}
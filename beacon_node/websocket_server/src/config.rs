@@ -0,0 +1,56 @@
+use clap::ArgMatches;
+use serde_derive::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+/// WebSocket server configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Enable the WebSocket server.
+    pub enabled: bool,
+    /// The IPv4 address the WebSocket server will listen on.
+    pub listen_address: Ipv4Addr,
+    /// The port the WebSocket server will listen on.
+    pub port: u16,
+    /// The generalized indices of the finalized state to push a proof bundle of each time the
+    /// finalized checkpoint changes. No proofs are pushed if empty.
+    pub proof_indices: Vec<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            enabled: false, // websocket server disabled by default
+            listen_address: Ipv4Addr::new(127, 0, 0, 1),
+            port: 5053,
+            proof_indices: vec![],
+        }
+    }
+}
+
+impl Config {
+    pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), &'static str> {
+        if args.is_present("ws") {
+            self.enabled = true;
+        }
+
+        if let Some(ws_address) = args.value_of("ws-address") {
+            self.listen_address = ws_address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| "ws-address is not IPv4 address")?;
+        }
+
+        if let Some(ws_port) = args.value_of("ws-port") {
+            self.port = ws_port.parse::<u16>().map_err(|_| "ws-port is not u16")?;
+        }
+
+        if let Some(indices) = args.value_of("ws-proof-indices") {
+            self.proof_indices = indices
+                .split(',')
+                .map(|index| index.trim().parse::<u64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| "ws-proof-indices is not a list of integers")?;
+        }
+
+        Ok(())
+    }
+}
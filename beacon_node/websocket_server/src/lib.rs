@@ -0,0 +1,132 @@
+//! A WebSocket server which pushes `BeaconChain` events (e.g., new heads, finalization) to all
+//! connected clients as JSON, so that external tooling need not poll the HTTP API.
+mod config;
+
+use beacon_chain::events::{EventHandler, EventKind};
+use futures::Future;
+use slog::{debug, error, info, Logger};
+use std::marker::PhantomData;
+use std::thread;
+use tokio::runtime::TaskExecutor;
+use types::EthSpec;
+use ws::{Sender, WebSocket};
+
+pub use config::Config as WebSocketConfig;
+
+/// Broadcasts `BeaconChain` events to all clients connected to the WebSocket server.
+pub struct WebSocketSender<T: EthSpec> {
+    sender: Option<Sender>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: EthSpec> WebSocketSender<T> {
+    /// Creates a dummy websocket server that never starts and where all future calls are no-ops.
+    pub fn dummy() -> Self {
+        Self {
+            sender: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Send a string to all connected clients.
+    pub fn send_string(&self, string: String) -> Result<(), String> {
+        if let Some(sender) = &self.sender {
+            sender
+                .send(string)
+                .map_err(|e| format!("Unable to broadcast to websocket clients: {:?}", e))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T: EthSpec> Clone for WebSocketSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: EthSpec> EventHandler<T> for WebSocketSender<T> {
+    fn register(&self, kind: EventKind<T>) -> Result<(), String> {
+        self.send_string(
+            serde_json::to_string(&kind)
+                .map_err(|e| format!("Unable to serialize event: {:?}", e))?,
+        )
+    }
+}
+
+/// Start the WebSocket server on its own thread.
+///
+/// Returns a `WebSocketSender` for broadcasting events and a `Signal` which shuts the server
+/// down when fired or dropped.
+pub fn start_server<T: EthSpec>(
+    config: &WebSocketConfig,
+    executor: &TaskExecutor,
+    log: &Logger,
+) -> Result<(WebSocketSender<T>, exit_future::Signal), String> {
+    let server_string = format!("{}:{}", config.listen_address, config.port);
+
+    // Create a server that simply ignores any incoming messages.
+    let server = WebSocket::new(|_| |_| Ok(()))
+        .map_err(|e| format!("Failed to initialize websocket server: {:?}", e))?;
+
+    let broadcaster = server.broadcaster();
+
+    // Produce a signal/channel that can gracefully shutdown the websocket server.
+    let exit_signal = {
+        let (exit_signal, exit) = exit_future::signal();
+
+        let log_inner = log.clone();
+        let broadcaster_inner = server.broadcaster();
+        let exit_future = exit.and_then(move |_| {
+            if let Err(e) = broadcaster_inner.shutdown() {
+                error!(
+                    log_inner,
+                    "Websocket server failed to shutdown";
+                    "error" => format!("{:?}", e)
+                );
+            } else {
+                debug!(log_inner, "Websocket server shutdown");
+            }
+            Ok(())
+        });
+
+        // Place a future on the executor that will shutdown the websocket server when the
+        // application exits.
+        executor.spawn(exit_future);
+
+        exit_signal
+    };
+
+    let log_inner = log.clone();
+    let _handle = thread::spawn(move || match server.listen(server_string) {
+        Ok(_) => {
+            debug!(log_inner, "Websocket server thread stopped");
+        }
+        Err(e) => {
+            error!(
+                log_inner,
+                "Websocket server failed to start";
+                "error" => format!("{:?}", e)
+            );
+        }
+    });
+
+    info!(
+        log,
+        "WebSocket server started";
+        "address" => format!("{}", config.listen_address),
+        "port" => config.port,
+    );
+
+    Ok((
+        WebSocketSender {
+            sender: Some(broadcaster),
+            _phantom: PhantomData,
+        },
+        exit_signal,
+    ))
+}
@@ -1,5 +1,5 @@
 //! The optimised bitwise LMD-GHOST fork choice rule.
-use crate::{ForkChoice, ForkChoiceError};
+use crate::{add_anchor_to_ancestors, blocks_in_insertion_order, ForkChoice, ForkChoiceError};
 use bit_vec::BitVec;
 use log::{debug, trace};
 use std::collections::HashMap;
@@ -266,6 +266,22 @@ impl<T: Store, E: EthSpec> ForkChoice<T> for BitwiseLMDGhost<T, E> {
         Ok(())
     }
 
+    fn add_anchor_block(
+        &mut self,
+        block: &BeaconBlock,
+        block_hash: &Hash256,
+        spec: &ChainSpec,
+    ) -> Result<(), ForkChoiceError> {
+        add_anchor_to_ancestors(
+            &mut self.ancestors,
+            &mut self.max_known_height,
+            block,
+            block_hash,
+            spec,
+        );
+        Ok(())
+    }
+
     fn add_attestation(
         &mut self,
         validator_index: u64,
@@ -310,7 +326,21 @@ impl<T: Store, E: EthSpec> ForkChoice<T> for BitwiseLMDGhost<T, E> {
         Ok(())
     }
 
+    fn set_latest_vote(&mut self, validator_index: u64, block_hash: &Hash256) {
+        trace!(
+            "Setting vote of validator: {:?} to block: {}",
+            validator_index,
+            block_hash
+        );
+        self.latest_attestation_targets
+            .insert(validator_index, *block_hash);
+    }
+
     /// Perform lmd_ghost on the current chain to find the head.
+    fn block_roots(&self) -> Vec<Hash256> {
+        blocks_in_insertion_order(&self.children)
+    }
+
     fn find_head(
         &mut self,
         justified_block_start: &Hash256,
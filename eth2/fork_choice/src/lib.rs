@@ -22,9 +22,10 @@ pub mod optimized_lmd_ghost;
 pub mod slow_lmd_ghost;
 pub mod test_utils;
 
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use store::Error as DBError;
-use types::{BeaconBlock, ChainSpec, Hash256};
+use types::{BeaconBlock, ChainSpec, Hash256, SlotHeight};
 
 pub use bitwise_lmd_ghost::BitwiseLMDGhost;
 pub use longest_chain::LongestChain;
@@ -46,6 +47,18 @@ pub trait ForkChoice<T>: Send + Sync {
         block_hash: &Hash256,
         spec: &ChainSpec,
     ) -> Result<(), ForkChoiceError>;
+    /// Called once when the chain is started from a trusted checkpoint instead of genesis.
+    ///
+    /// The given block becomes the root of the block tree; none of its ancestors are known to the
+    /// fork choice. Implementations which do not track ancestry may ignore this.
+    fn add_anchor_block(
+        &mut self,
+        _block: &BeaconBlock,
+        _block_hash: &Hash256,
+        _spec: &ChainSpec,
+    ) -> Result<(), ForkChoiceError> {
+        Ok(())
+    }
     /// Called when an attestation has been added. Allows generic attestation-level data structures to be built for a given fork choice.
     // This can be generalised to a full attestation if required later.
     fn add_attestation(
@@ -54,6 +67,16 @@ pub trait ForkChoice<T>: Send + Sync {
         target_block_hash: &Hash256,
         spec: &ChainSpec,
     ) -> Result<(), ForkChoiceError>;
+    /// Sets the vote of a validator to `block_hash`, replacing any earlier vote.
+    ///
+    /// Unlike `add_attestation`, the votes are not compared, so the caller must know this is the
+    /// validator's latest message, e.g. by the target epoch of its attestations.
+    fn set_latest_vote(&mut self, validator_index: u64, block_hash: &Hash256);
+    /// Returns the roots of the blocks given to `add_block`, each after its parent.
+    ///
+    /// A fork choice equal to this one, but for its attestations, is rebuilt by giving these blocks
+    /// to `add_block` of a new fork choice with the same anchor.
+    fn block_roots(&self) -> Vec<Hash256>;
     /// The fork-choice algorithm to find the current canonical head of the chain.
     // TODO: Remove the justified_start_block parameter and make it internal
     fn find_head(
@@ -63,6 +86,48 @@ pub trait ForkChoice<T>: Send + Sync {
     ) -> Result<Hash256, ForkChoiceError>;
 }
 
+/// Records `block` as the anchor in the log lookup table of ancestors, `ancestors`, used by the
+/// optimised LMD-GHOST implementations.
+///
+/// The anchor has no known ancestors, so it acts as its own ancestor at every level of the table.
+/// Ancestor searches never descend below the justified block, which is never older than the
+/// anchor.
+fn add_anchor_to_ancestors(
+    ancestors: &mut [HashMap<Hash256, Hash256>],
+    max_known_height: &mut SlotHeight,
+    block: &BeaconBlock,
+    block_hash: &Hash256,
+    spec: &ChainSpec,
+) {
+    for level in ancestors.iter_mut() {
+        level.insert(*block_hash, *block_hash);
+    }
+    *max_known_height = std::cmp::max(*max_known_height, block.slot.height(spec.genesis_slot));
+}
+
+/// Returns the blocks of the tree `children`, which maps each block to its children, such that
+/// each block follows its parent.
+///
+/// The roots of the tree, e.g., the anchor block, are parents only, so are not returned.
+fn blocks_in_insertion_order(children: &HashMap<Hash256, Vec<Hash256>>) -> Vec<Hash256> {
+    let all_children: HashSet<&Hash256> = children.values().flatten().collect();
+
+    let mut queue: VecDeque<&Hash256> = children
+        .keys()
+        .filter(|parent| !all_children.contains(parent))
+        .collect();
+    let mut blocks = Vec::with_capacity(all_children.len());
+
+    while let Some(parent) = queue.pop_front() {
+        for child in children.get(parent).into_iter().flatten() {
+            blocks.push(*child);
+            queue.push_back(child);
+        }
+    }
+
+    blocks
+}
+
 /// Possible fork choice errors that can occur.
 #[derive(Debug, PartialEq)]
 pub enum ForkChoiceError {
@@ -93,3 +158,25 @@ pub enum ForkChoiceAlgorithm {
     /// An optimised implementation of LMD ghost.
     OptimizedLMDGhost,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(i: u8) -> Hash256 {
+        Hash256::from_slice(&[i; 32])
+    }
+
+    #[test]
+    fn blocks_follow_their_parents() {
+        // 1 is the anchor, with children 2 and 3. 4 is a child of 3 and 5 a child of 4.
+        let mut children = HashMap::new();
+        children.insert(root(4), vec![root(5)]);
+        children.insert(root(1), vec![root(2), root(3)]);
+        children.insert(root(3), vec![root(4)]);
+
+        let blocks = blocks_in_insertion_order(&children);
+
+        assert_eq!(blocks, vec![root(2), root(3), root(4), root(5)]);
+    }
+}
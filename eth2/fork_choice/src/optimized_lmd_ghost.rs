@@ -1,5 +1,5 @@
 //! The optimised bitwise LMD-GHOST fork choice rule.
-use crate::{ForkChoice, ForkChoiceError};
+use crate::{add_anchor_to_ancestors, blocks_in_insertion_order, ForkChoice, ForkChoiceError};
 use log::{debug, trace};
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -237,6 +237,22 @@ impl<T: Store, E: EthSpec> ForkChoice<T> for OptimizedLMDGhost<T, E> {
         Ok(())
     }
 
+    fn add_anchor_block(
+        &mut self,
+        block: &BeaconBlock,
+        block_hash: &Hash256,
+        spec: &ChainSpec,
+    ) -> Result<(), ForkChoiceError> {
+        add_anchor_to_ancestors(
+            &mut self.ancestors,
+            &mut self.max_known_height,
+            block,
+            block_hash,
+            spec,
+        );
+        Ok(())
+    }
+
     fn add_attestation(
         &mut self,
         validator_index: u64,
@@ -281,7 +297,21 @@ impl<T: Store, E: EthSpec> ForkChoice<T> for OptimizedLMDGhost<T, E> {
         Ok(())
     }
 
+    fn set_latest_vote(&mut self, validator_index: u64, block_hash: &Hash256) {
+        trace!(
+            "Setting vote of validator: {:?} to block: {}",
+            validator_index,
+            block_hash
+        );
+        self.latest_attestation_targets
+            .insert(validator_index, *block_hash);
+    }
+
     /// Perform lmd_ghost on the current chain to find the head.
+    fn block_roots(&self) -> Vec<Hash256> {
+        blocks_in_insertion_order(&self.children)
+    }
+
     fn find_head(
         &mut self,
         justified_block_start: &Hash256,
@@ -425,6 +455,9 @@ impl<T> CacheKey<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::TestingForkChoiceBuilder;
+    use store::MemoryStore;
+    use types::MainnetEthSpec;
 
     #[test]
     pub fn test_power_of_2_below() {
@@ -444,4 +477,25 @@ mod tests {
             assert!(power_of_2_below(x) <= x, "{}", x);
         }
     }
+
+    #[test]
+    pub fn latest_vote_replaces_any_earlier() {
+        let spec = MainnetEthSpec::default_spec();
+        let builder: TestingForkChoiceBuilder<MemoryStore, MainnetEthSpec> =
+            TestingForkChoiceBuilder::new(4, 3, Arc::new(MemoryStore::open()));
+        let mut fork_choice: OptimizedLMDGhost<MemoryStore, MainnetEthSpec> = builder.build();
+        let lower = builder.chain[1].0;
+        let higher = builder.chain[2].0;
+
+        // `add_attestation` keeps the vote for the higher block.
+        fork_choice.add_attestation(0, &higher, &spec).unwrap();
+        fork_choice.add_attestation(0, &lower, &spec).unwrap();
+        assert_eq!(
+            fork_choice.latest_attestation_targets.get(&0),
+            Some(&higher)
+        );
+
+        fork_choice.set_latest_vote(0, &lower);
+        assert_eq!(fork_choice.latest_attestation_targets.get(&0), Some(&lower));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{ForkChoice, ForkChoiceError};
+use crate::{blocks_in_insertion_order, ForkChoice, ForkChoiceError};
 use log::{debug, trace};
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -156,7 +156,21 @@ impl<T: Store, E: EthSpec> ForkChoice<T> for SlowLMDGhost<T, E> {
         Ok(())
     }
 
+    fn set_latest_vote(&mut self, validator_index: u64, block_hash: &Hash256) {
+        trace!(
+            "Setting vote of validator: {:?} to block: {}",
+            validator_index,
+            block_hash
+        );
+        self.latest_attestation_targets
+            .insert(validator_index, *block_hash);
+    }
+
     /// A very inefficient implementation of LMD ghost.
+    fn block_roots(&self) -> Vec<Hash256> {
+        blocks_in_insertion_order(&self.children)
+    }
+
     fn find_head(
         &mut self,
         justified_block_start: &Hash256,
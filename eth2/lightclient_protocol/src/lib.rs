@@ -0,0 +1,397 @@
+//! The messages exchanged between a beacon node serving light clients and the light client.
+//!
+//! Every message may be encoded as SSZ or, over the HTTP API, as JSON. SSZ messages must be
+//! decoded with `decode`, which enforces `MAX_MESSAGE_BYTES`.
+use merkle_proof::{Multiproof, SerializedPartial};
+use serde_derive::{Deserialize, Serialize};
+use ssz::Decode;
+use ssz_derive::{Decode, Encode};
+use types::{BeaconBlockHeader, Epoch, Hash256, Slot};
+
+/// The largest encoded message either side will decode.
+pub const MAX_MESSAGE_BYTES: usize = 1 << 20;
+/// The most generalized indices a single `ProofRequest` may ask for.
+pub const MAX_PROOF_INDICES: usize = 1_024;
+/// The longest message an `ErrorResponse` may carry.
+pub const MAX_ERROR_MESSAGE_BYTES: usize = 256;
+
+/// A request for a proof of the nodes at `indices` of the state with root `state_root`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofRequest {
+    pub state_root: Hash256,
+    /// Generalized indices within the state tree.
+    pub indices: Vec<u64>,
+}
+
+impl ProofRequest {
+    /// Checks the request is within the limits of the protocol.
+    pub fn validate(&self) -> Result<(), ErrorCode> {
+        validate_indices(&self.indices)
+    }
+}
+
+/// A request for a proof of the nodes at `indices` of the state at `slot`, which must be
+/// finalized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct HistoricalProofRequest {
+    pub slot: Slot,
+    /// Generalized indices within the state tree.
+    pub indices: Vec<u64>,
+}
+
+impl HistoricalProofRequest {
+    /// Checks the request is within the limits of the protocol.
+    pub fn validate(&self) -> Result<(), ErrorCode> {
+        validate_indices(&self.indices)
+    }
+}
+
+/// Subscribes to proofs of the nodes at `indices` of the state of each newly finalized block.
+///
+/// An empty `indices` cancels the subscription.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofSubscription {
+    /// Generalized indices within the state tree.
+    pub indices: Vec<u64>,
+}
+
+impl ProofSubscription {
+    /// Checks the subscription is within the limits of the protocol.
+    pub fn validate(&self) -> Result<(), ErrorCode> {
+        if self.indices.is_empty() {
+            return Ok(());
+        }
+        validate_indices(&self.indices)
+    }
+}
+
+/// Pushed to a subscriber when finality advances: a proof of the nodes of its `ProofSubscription`
+/// against the state of the finalized block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofPush {
+    pub finalized_epoch: Epoch,
+    pub finalized_root: Hash256,
+    /// The state root of the finalized block.
+    pub state_root: Hash256,
+    pub proof: SerializedPartial,
+}
+
+fn validate_indices(indices: &[u64]) -> Result<(), ErrorCode> {
+    if indices.is_empty() || indices.contains(&0) {
+        return Err(ErrorCode::InvalidRequest);
+    }
+    if indices.len() > MAX_PROOF_INDICES {
+        return Err(ErrorCode::TooManyIndices);
+    }
+    Ok(())
+}
+
+/// A proof against `state_root` of the nodes asked for by a `ProofRequest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofResponse {
+    pub state_root: Hash256,
+    pub proof: SerializedPartial,
+}
+
+/// Announces a new head block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct HeaderUpdate {
+    pub header: BeaconBlockHeader,
+}
+
+/// Announces a new finalized checkpoint, with a proof of it against the head state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct FinalityUpdate {
+    /// The signed header of the head block.
+    pub head_header: BeaconBlockHeader,
+    /// The root of the head state, which commits to the finalized checkpoint.
+    pub head_state_root: Hash256,
+    pub finalized_epoch: Epoch,
+    pub finalized_root: Hash256,
+    /// A proof of `finalized_epoch` and `finalized_root` against `head_state_root`.
+    pub finality_proof: SerializedPartial,
+    /// The signed header of the finalized block, if the beacon node has it.
+    pub finalized_header: Option<BeaconBlockHeader>,
+}
+
+/// A proof of the status of a validator against the latest finalized state.
+///
+/// `proof` covers the lengths of the registry and the balances, and the public key, activation
+/// and exit epochs, slashed flag and effective balance of the registry entry, and the balance of
+/// the validator, as from `validator_status_indices`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ValidatorStatusProof {
+    pub finalized_epoch: Epoch,
+    pub finalized_root: Hash256,
+    /// The root of the state of the finalized block.
+    pub state_root: Hash256,
+    pub validator_index: u64,
+    pub proof: Multiproof,
+}
+
+/// The reasons a request may be refused.
+///
+/// The numeric code of each reason is stable, so clients may branch on it. New reasons are only
+/// ever given new codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    /// The request is malformed, or could not be decoded.
+    InvalidRequest,
+    /// The request exceeds `MAX_PROOF_INDICES`.
+    TooManyIndices,
+    /// The beacon node does not have the requested state.
+    UnknownStateRoot,
+    /// The beacon node cannot prove a requested index.
+    UnsupportedIndex,
+    /// The beacon node failed to handle a valid request.
+    ServerError,
+    /// The beacon node does not have the requested block.
+    UnknownBlockRoot,
+    /// The beacon node knows of the requested state, but no longer stores it, or can no longer
+    /// rebuild it within its limits.
+    PrunedState,
+    /// The response would exceed `MAX_MESSAGE_BYTES`.
+    ProofTooLarge,
+    /// The beacon node is too far behind the present slot to answer reliably.
+    NotSynced,
+    /// The requested validator is not in the registry.
+    UnknownValidator,
+    /// The body of the request is larger than the beacon node accepts.
+    RequestTooLarge,
+}
+
+impl ErrorCode {
+    pub fn as_u64(self) -> u64 {
+        match self {
+            ErrorCode::InvalidRequest => 1,
+            ErrorCode::TooManyIndices => 2,
+            ErrorCode::UnknownStateRoot => 3,
+            ErrorCode::UnsupportedIndex => 4,
+            ErrorCode::ServerError => 5,
+            ErrorCode::UnknownBlockRoot => 6,
+            ErrorCode::PrunedState => 7,
+            ErrorCode::ProofTooLarge => 8,
+            ErrorCode::NotSynced => 9,
+            ErrorCode::UnknownValidator => 10,
+            ErrorCode::RequestTooLarge => 11,
+        }
+    }
+
+    pub fn from_u64(code: u64) -> Option<Self> {
+        match code {
+            1 => Some(ErrorCode::InvalidRequest),
+            2 => Some(ErrorCode::TooManyIndices),
+            3 => Some(ErrorCode::UnknownStateRoot),
+            4 => Some(ErrorCode::UnsupportedIndex),
+            5 => Some(ErrorCode::ServerError),
+            6 => Some(ErrorCode::UnknownBlockRoot),
+            7 => Some(ErrorCode::PrunedState),
+            8 => Some(ErrorCode::ProofTooLarge),
+            9 => Some(ErrorCode::NotSynced),
+            10 => Some(ErrorCode::UnknownValidator),
+            11 => Some(ErrorCode::RequestTooLarge),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status with which the HTTP API refuses a request.
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::TooManyIndices
+            | ErrorCode::UnsupportedIndex
+            | ErrorCode::ProofTooLarge => 400,
+            ErrorCode::UnknownStateRoot
+            | ErrorCode::UnknownBlockRoot
+            | ErrorCode::UnknownValidator => 404,
+            ErrorCode::PrunedState => 410,
+            ErrorCode::RequestTooLarge => 413,
+            ErrorCode::ServerError => 500,
+            ErrorCode::NotSynced => 503,
+        }
+    }
+}
+
+/// The JSON body with which the HTTP API refuses a request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpError {
+    /// An `ErrorCode`, as a `u64`.
+    pub code: u64,
+    pub message: String,
+    /// Any values describing the failure, e.g., the requested root.
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+impl HttpError {
+    pub fn new(code: ErrorCode, message: String, details: serde_json::Value) -> Self {
+        Self {
+            code: code.as_u64(),
+            message,
+            details,
+        }
+    }
+
+    /// Returns the error code, if it is known.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_u64(self.code)
+    }
+}
+
+/// The response to a refused request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ErrorResponse {
+    /// An `ErrorCode`, as a `u64`.
+    pub code: u64,
+    /// A UTF-8 description, of at most `MAX_ERROR_MESSAGE_BYTES`.
+    pub message: Vec<u8>,
+}
+
+impl ErrorResponse {
+    /// Builds a response, truncating `message` to `MAX_ERROR_MESSAGE_BYTES`.
+    pub fn new(code: ErrorCode, message: &str) -> Self {
+        let mut end = message.len().min(MAX_ERROR_MESSAGE_BYTES);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        Self {
+            code: code.as_u64(),
+            message: message[..end].as_bytes().to_vec(),
+        }
+    }
+
+    /// Returns the error code, if it is known.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_u64(self.code)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    /// The message exceeds `MAX_MESSAGE_BYTES`.
+    TooLarge(usize),
+    Ssz(ssz::DecodeError),
+}
+
+/// Decodes an SSZ message, refusing any larger than `MAX_MESSAGE_BYTES`.
+pub fn decode<T: Decode>(bytes: &[u8]) -> Result<T, DecodeError> {
+    if bytes.len() > MAX_MESSAGE_BYTES {
+        return Err(DecodeError::TooLarge(bytes.len()));
+    }
+    T::from_ssz_bytes(bytes).map_err(DecodeError::Ssz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::Encode;
+
+    #[test]
+    fn proof_request_limits() {
+        let request = |indices: Vec<u64>| ProofRequest {
+            state_root: Hash256::zero(),
+            indices,
+        };
+
+        assert_eq!(request(vec![1, 32]).validate(), Ok(()));
+        assert_eq!(request(vec![]).validate(), Err(ErrorCode::InvalidRequest));
+        assert_eq!(request(vec![0]).validate(), Err(ErrorCode::InvalidRequest));
+        assert_eq!(
+            request(vec![1; MAX_PROOF_INDICES + 1]).validate(),
+            Err(ErrorCode::TooManyIndices)
+        );
+    }
+
+    #[test]
+    fn historical_proof_request_limits() {
+        let request = |indices: Vec<u64>| HistoricalProofRequest {
+            slot: Slot::new(64),
+            indices,
+        };
+
+        assert_eq!(request(vec![1, 32]).validate(), Ok(()));
+        assert_eq!(request(vec![0]).validate(), Err(ErrorCode::InvalidRequest));
+        assert_eq!(
+            request(vec![1; MAX_PROOF_INDICES + 1]).validate(),
+            Err(ErrorCode::TooManyIndices)
+        );
+    }
+
+    #[test]
+    fn empty_subscription_cancels() {
+        let subscription = |indices: Vec<u64>| ProofSubscription { indices };
+
+        assert_eq!(subscription(vec![]).validate(), Ok(()));
+        assert_eq!(subscription(vec![1, 32]).validate(), Ok(()));
+        assert_eq!(
+            subscription(vec![0]).validate(),
+            Err(ErrorCode::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let response = ProofResponse {
+            state_root: Hash256::from_low_u64_le(7),
+            proof: SerializedPartial {
+                indices: vec![2, 3],
+                chunks: vec![Hash256::zero(), Hash256::from_low_u64_le(1)],
+            },
+        };
+        assert_eq!(decode(&response.as_ssz_bytes()), Ok(response));
+
+        let error = ErrorResponse::new(ErrorCode::UnknownStateRoot, "unknown");
+        assert_eq!(decode(&error.as_ssz_bytes()), Ok(error.clone()));
+        assert_eq!(error.error_code(), Some(ErrorCode::UnknownStateRoot));
+    }
+
+    #[test]
+    fn error_codes_are_stable() {
+        for code in 1..=11 {
+            let error_code = ErrorCode::from_u64(code).unwrap();
+            assert_eq!(error_code.as_u64(), code);
+        }
+        assert_eq!(ErrorCode::from_u64(0), None);
+        assert_eq!(ErrorCode::from_u64(12), None);
+
+        assert_eq!(ErrorCode::UnknownStateRoot.as_u64(), 3);
+        assert_eq!(ErrorCode::PrunedState.as_u64(), 7);
+        assert_eq!(ErrorCode::ProofTooLarge.as_u64(), 8);
+        assert_eq!(ErrorCode::NotSynced.as_u64(), 9);
+    }
+
+    #[test]
+    fn http_error_json() {
+        let error = HttpError::new(
+            ErrorCode::NotSynced,
+            "Not synced".to_string(),
+            serde_json::json!({ "head_slot": 1 }),
+        );
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            json,
+            r#"{"code":9,"message":"Not synced","details":{"head_slot":1}}"#
+        );
+
+        let error: HttpError = serde_json::from_str(r#"{"code":3,"message":"Unknown"}"#).unwrap();
+        assert_eq!(error.error_code(), Some(ErrorCode::UnknownStateRoot));
+        assert_eq!(error.details, serde_json::Value::Null);
+    }
+
+    #[test]
+    fn refuses_large_messages() {
+        assert_eq!(
+            decode::<ErrorResponse>(&vec![0; MAX_MESSAGE_BYTES + 1]),
+            Err(DecodeError::TooLarge(MAX_MESSAGE_BYTES + 1))
+        );
+    }
+
+    #[test]
+    fn truncates_error_messages() {
+        let message = "é".repeat(MAX_ERROR_MESSAGE_BYTES);
+        let error = ErrorResponse::new(ErrorCode::ServerError, &message);
+        assert!(error.message.len() <= MAX_ERROR_MESSAGE_BYTES);
+        assert!(String::from_utf8(error.message).is_ok());
+    }
+}
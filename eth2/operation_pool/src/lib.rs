@@ -1,3 +1,7 @@
+mod persistence;
+
+pub use persistence::PersistedOperationPool;
+
 use int_to_bytes::int_to_bytes8;
 use itertools::Itertools;
 use parking_lot::RwLock;
@@ -134,6 +138,21 @@ impl<T: EthSpec> OperationPool<T> {
         // Check that attestation signatures are valid.
         validate_attestation_time_independent_only(state, &attestation, spec)?;
 
+        self.insert_validated_attestation(attestation, state, spec);
+
+        Ok(())
+    }
+
+    /// Insert an attestation which has already been validated against `state`, signature
+    /// included (e.g., as part of a batch), aggregating it with existing attestations if possible.
+    ///
+    /// No checks are made, so an invalid attestation may be aggregated into valid ones.
+    pub fn insert_validated_attestation(
+        &self,
+        attestation: Attestation,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) {
         let id = AttestationId::from_data(&attestation.data, state, spec);
 
         // Take a write lock on the attestations map.
@@ -142,7 +161,7 @@ impl<T: EthSpec> OperationPool<T> {
         let existing_attestations = match attestations.entry(id) {
             hash_map::Entry::Vacant(entry) => {
                 entry.insert(vec![attestation]);
-                return Ok(());
+                return;
             }
             hash_map::Entry::Occupied(entry) => entry.into_mut(),
         };
@@ -160,8 +179,6 @@ impl<T: EthSpec> OperationPool<T> {
         if !aggregated {
             existing_attestations.push(attestation);
         }
-
-        Ok(())
     }
 
     /// Total number of attestations in the pool, including attestations for the same data.
@@ -506,9 +523,7 @@ fn superior_attestation_exists_in_state<T: EthSpec>(
             let bitfield = &attestation.aggregation_bitfield;
             let existing_bitfield = &existing_attestation.aggregation_bitfield;
 
-            existing_attestation.data == attestation.data
-                && bitfield.intersection(existing_bitfield).num_set_bits()
-                    == bitfield.num_set_bits()
+            existing_attestation.data == attestation.data && bitfield.is_subset(existing_bitfield)
         })
 }
 
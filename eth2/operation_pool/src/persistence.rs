@@ -0,0 +1,97 @@
+use crate::{AttestationId, OperationPool};
+use parking_lot::RwLock;
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use types::*;
+
+/// SSZ-serializable version of `OperationPool`.
+///
+/// Operations are stored without the keys used to index them in memory; these are re-computed
+/// from the operations themselves (and the fork of `state`) when the pool is restored.
+#[derive(Encode, Decode)]
+pub struct PersistedOperationPool {
+    attestations: Vec<Attestation>,
+    deposits: Vec<Deposit>,
+    attester_slashings: Vec<AttesterSlashing>,
+    proposer_slashings: Vec<ProposerSlashing>,
+    voluntary_exits: Vec<VoluntaryExit>,
+    transfers: Vec<Transfer>,
+}
+
+impl PersistedOperationPool {
+    /// Convert an `OperationPool` into serializable form.
+    pub fn from_operation_pool<T: EthSpec>(operation_pool: &OperationPool<T>) -> Self {
+        Self {
+            attestations: operation_pool
+                .attestations
+                .read()
+                .values()
+                .flatten()
+                .cloned()
+                .collect(),
+            deposits: operation_pool.deposits.read().values().cloned().collect(),
+            attester_slashings: operation_pool
+                .attester_slashings
+                .read()
+                .values()
+                .cloned()
+                .collect(),
+            proposer_slashings: operation_pool
+                .proposer_slashings
+                .read()
+                .values()
+                .cloned()
+                .collect(),
+            voluntary_exits: operation_pool
+                .voluntary_exits
+                .read()
+                .values()
+                .cloned()
+                .collect(),
+            transfers: operation_pool.transfers.read().iter().cloned().collect(),
+        }
+    }
+
+    /// Reconstruct an `OperationPool`, re-computing attestation IDs using the fork of `state`.
+    ///
+    /// Operations are not re-validated; stale operations will be pruned or filtered as usual.
+    pub fn into_operation_pool<T: EthSpec>(
+        self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> OperationPool<T> {
+        let mut attestations = HashMap::new();
+        for attestation in self.attestations {
+            attestations
+                .entry(AttestationId::from_data(&attestation.data, state, spec))
+                .or_insert_with(Vec::new)
+                .push(attestation);
+        }
+
+        OperationPool {
+            attestations: RwLock::new(attestations),
+            deposits: RwLock::new(self.deposits.into_iter().map(|d| (d.index, d)).collect()),
+            attester_slashings: RwLock::new(
+                self.attester_slashings
+                    .into_iter()
+                    .map(|s| (OperationPool::<T>::attester_slashing_id(&s, state, spec), s))
+                    .collect(),
+            ),
+            proposer_slashings: RwLock::new(
+                self.proposer_slashings
+                    .into_iter()
+                    .map(|s| (s.proposer_index, s))
+                    .collect(),
+            ),
+            voluntary_exits: RwLock::new(
+                self.voluntary_exits
+                    .into_iter()
+                    .map(|e| (e.validator_index, e))
+                    .collect(),
+            ),
+            transfers: RwLock::new(self.transfers.into_iter().collect()),
+            _phantom: PhantomData,
+        }
+    }
+}
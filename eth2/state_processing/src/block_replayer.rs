@@ -0,0 +1,164 @@
+use crate::{
+    per_block_processing_without_verifying_block_signature, per_slot_processing,
+    BlockProcessingError, SlotProcessingError,
+};
+use types::*;
+
+#[derive(Debug, PartialEq)]
+pub enum BlockReplayError {
+    /// A block precedes the state it would be applied to, e.g., as blocks were given out of order.
+    BlockPrecedesState {
+        block_slot: Slot,
+        state_slot: Slot,
+    },
+    /// The target slot precedes the state.
+    TargetPrecedesState {
+        target_slot: Slot,
+        state_slot: Slot,
+    },
+    SlotProcessingError(SlotProcessingError),
+    BlockProcessingError(BlockProcessingError),
+    BeaconStateError(BeaconStateError),
+}
+
+impl From<SlotProcessingError> for BlockReplayError {
+    fn from(e: SlotProcessingError) -> BlockReplayError {
+        BlockReplayError::SlotProcessingError(e)
+    }
+}
+
+impl From<BlockProcessingError> for BlockReplayError {
+    fn from(e: BlockProcessingError) -> BlockReplayError {
+        BlockReplayError::BlockProcessingError(e)
+    }
+}
+
+impl From<BeaconStateError> for BlockReplayError {
+    fn from(e: BeaconStateError) -> BlockReplayError {
+        BlockReplayError::BeaconStateError(e)
+    }
+}
+
+/// Materializes a state by applying already-imported blocks, in ascending slot order, to an
+/// earlier state, and then advancing through any empty slots.
+///
+/// The blocks were verified when they were imported, so their proposer signatures are not
+/// checked again.
+pub struct BlockReplayer<'a, T: EthSpec> {
+    state: BeaconState<T>,
+    spec: &'a ChainSpec,
+}
+
+impl<'a, T: EthSpec> BlockReplayer<'a, T> {
+    pub fn new(state: BeaconState<T>, spec: &'a ChainSpec) -> Self {
+        Self { state, spec }
+    }
+
+    /// Applies each of `blocks`, advancing through the empty slots before each.
+    pub fn apply_blocks(mut self, blocks: &[BeaconBlock]) -> Result<Self, BlockReplayError> {
+        for block in blocks {
+            if block.slot < self.state.slot {
+                return Err(BlockReplayError::BlockPrecedesState {
+                    block_slot: block.slot,
+                    state_slot: self.state.slot,
+                });
+            }
+
+            self.advance_to(block.slot)?;
+            per_block_processing_without_verifying_block_signature(
+                &mut self.state,
+                block,
+                self.spec,
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    /// Advances the state through empty slots until it is at `target_slot`.
+    pub fn advance_to(&mut self, target_slot: Slot) -> Result<(), BlockReplayError> {
+        if target_slot < self.state.slot {
+            return Err(BlockReplayError::TargetPrecedesState {
+                target_slot,
+                state_slot: self.state.slot,
+            });
+        }
+
+        while self.state.slot < target_slot {
+            // Ensure the next epoch state caches are built in case of an epoch transition.
+            self.state
+                .build_committee_cache(RelativeEpoch::Next, self.spec)?;
+
+            per_slot_processing(&mut self.state, self.spec)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the replayed state.
+    pub fn into_state(self) -> BeaconState<T> {
+        self.state
+    }
+}
+
+#[cfg(all(test, not(feature = "fake_crypto")))]
+mod tests {
+    use super::*;
+    use crate::per_block_processing::block_processing_builder::BlockProcessingBuilder;
+
+    const VALIDATOR_COUNT: usize = 10;
+
+    fn block_and_state(spec: &ChainSpec) -> (BeaconBlock, BeaconState<MainnetEthSpec>) {
+        let mut builder = BlockProcessingBuilder::new(VALIDATOR_COUNT, spec);
+        // Place the block in the last slot of an epoch, so replay crosses an epoch transition.
+        builder.set_slot(
+            (MainnetEthSpec::genesis_epoch() + 4).end_slot(MainnetEthSpec::slots_per_epoch()),
+        );
+        builder.build_caches(spec);
+
+        builder.build(None, None, spec)
+    }
+
+    #[test]
+    fn replays_blocks_and_empty_slots() {
+        let spec = MainnetEthSpec::default_spec();
+        let (block, state) = block_and_state(&spec);
+        let target_slot = block.slot + 3;
+
+        let mut expected = state.clone();
+        per_block_processing_without_verifying_block_signature(&mut expected, &block, &spec)
+            .unwrap();
+        for _ in 0..3 {
+            expected
+                .build_committee_cache(RelativeEpoch::Next, &spec)
+                .unwrap();
+            per_slot_processing(&mut expected, &spec).unwrap();
+        }
+
+        let mut replayer = BlockReplayer::new(state, &spec)
+            .apply_blocks(&[block])
+            .unwrap();
+        replayer.advance_to(target_slot).unwrap();
+        let replayed = replayer.into_state();
+
+        assert_eq!(replayed.slot, target_slot);
+        assert_eq!(replayed.canonical_root(), expected.canonical_root());
+    }
+
+    #[test]
+    fn rejects_blocks_before_state() {
+        let spec = MainnetEthSpec::default_spec();
+        let (block, mut state) = block_and_state(&spec);
+        state.slot = block.slot + 1;
+
+        let result = BlockReplayer::new(state, &spec).apply_blocks(&[block.clone()]);
+
+        assert_eq!(
+            result.err(),
+            Some(BlockReplayError::BlockPrecedesState {
+                block_slot: block.slot,
+                state_slot: block.slot + 1,
+            })
+        );
+    }
+}
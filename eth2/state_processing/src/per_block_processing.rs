@@ -9,8 +9,9 @@ pub use self::verify_attester_slashing::{
 };
 pub use self::verify_proposer_slashing::verify_proposer_slashing;
 pub use validate_attestation::{
-    validate_attestation, validate_attestation_time_independent_only,
-    validate_attestation_without_signature,
+    attestation_signature_set, validate_attestation, validate_attestation_time_independent_only,
+    validate_attestation_time_independent_only_without_signature,
+    validate_attestation_without_signature, verify_attestation_signature,
 };
 pub use verify_deposit::{
     get_existing_validator_index, verify_deposit_index, verify_deposit_merkle_proof,
@@ -18,7 +19,8 @@ pub use verify_deposit::{
 };
 pub use verify_exit::{verify_exit, verify_exit_time_independent_only};
 pub use verify_indexed_attestation::{
-    verify_indexed_attestation, verify_indexed_attestation_without_signature,
+    indexed_attestation_signature_set, verify_indexed_attestation,
+    verify_indexed_attestation_signature, verify_indexed_attestation_without_signature,
 };
 pub use verify_transfer::{
     execute_transfer, verify_transfer, verify_transfer_time_independent_only,
@@ -95,6 +97,38 @@ fn per_block_processing_signature_optional<T: EthSpec>(
     Ok(())
 }
 
+/// Verifies the signatures of a block against `state`, without executing the state transition:
+/// the proposer signature, the RANDAO reveal and the aggregate signature of each attestation.
+///
+/// Intended for checks which only need to know that a block was signed by the expected
+/// validators, e.g., gossip validation. Nothing else about the block is validated; in particular,
+/// the signatures of slashings, deposits, exits and transfers are not verified.
+///
+/// `state` must be in the epoch of the block, with its previous and current epoch caches built.
+pub fn verify_block_signatures_only<T: EthSpec>(
+    block: &BeaconBlock,
+    state: &BeaconState<T>,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    verify!(
+        block.slot.epoch(T::slots_per_epoch()) == state.current_epoch(),
+        Invalid::StateSlotMismatch
+    );
+
+    verify_block_signature(state, block, spec)?;
+    verify_randao(state, block, spec)?;
+
+    // Verify attestations in parallel.
+    block
+        .body
+        .attestations
+        .par_iter()
+        .enumerate()
+        .try_for_each(|(i, attestation)| {
+            verify_attestation_signature(state, attestation, spec).map_err(|e| e.into_with_index(i))
+        })
+}
+
 /// Processes the block header.
 ///
 /// Spec v0.6.3
@@ -165,6 +199,22 @@ pub fn process_randao<T: EthSpec>(
     state: &mut BeaconState<T>,
     block: &BeaconBlock,
     spec: &ChainSpec,
+) -> Result<(), Error> {
+    verify_randao(state, block, spec)?;
+
+    // Update the current epoch RANDAO mix.
+    state.update_randao_mix(state.current_epoch(), &block.body.randao_reveal)?;
+
+    Ok(())
+}
+
+/// Verifies the `randao_reveal` against the block's proposer pubkey.
+///
+/// Spec v0.6.3
+pub fn verify_randao<T: EthSpec>(
+    state: &BeaconState<T>,
+    block: &BeaconBlock,
+    spec: &ChainSpec,
 ) -> Result<(), Error> {
     let block_proposer = &state.validator_registry
         [state.get_beacon_proposer_index(block.slot, RelativeEpoch::Current, spec)?];
@@ -183,9 +233,6 @@ pub fn process_randao<T: EthSpec>(
         Invalid::BadRandaoSignature
     );
 
-    // Update the current epoch RANDAO mix.
-    state.update_randao_mix(state.current_epoch(), &block.body.randao_reveal)?;
-
     Ok(())
 }
 
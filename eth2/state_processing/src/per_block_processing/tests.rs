@@ -1,8 +1,11 @@
 #![cfg(all(test, not(feature = "fake_crypto")))]
 use super::block_processing_builder::BlockProcessingBuilder;
 use super::errors::*;
-use crate::per_block_processing;
+use crate::per_block_processing::attestation_signature_set;
+use crate::{per_block_processing, verify_block_signatures_only};
+use bls::verify_signature_sets;
 use tree_hash::SignedRoot;
+use types::test_utils::{TestingAttestationBuilder, TestingBeaconStateBuilder};
 use types::*;
 
 pub const VALIDATOR_COUNT: usize = 10;
@@ -100,6 +103,137 @@ fn invalid_randao_reveal_signature() {
     );
 }
 
+#[test]
+fn block_signatures_only() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec);
+    let (block, state) = builder.build(None, None, &spec);
+
+    assert_eq!(verify_block_signatures_only(&block, &state, &spec), Ok(()));
+}
+
+#[test]
+fn block_signatures_only_ignores_parent_root() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec);
+    let invalid_parent_root = Hash256::from([0xAA; 32]);
+    let (block, state) = builder.build(None, Some(invalid_parent_root), &spec);
+
+    assert_eq!(verify_block_signatures_only(&block, &state, &spec), Ok(()));
+}
+
+#[test]
+fn block_signatures_only_invalid_block_signature() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec);
+    let (mut block, state) = builder.build(None, None, &spec);
+
+    block.signature = Signature::new(
+        &block.signed_root(),
+        spec.get_domain(
+            block.slot.epoch(MainnetEthSpec::slots_per_epoch()),
+            Domain::BeaconProposer,
+            &state.fork,
+        ),
+        &Keypair::random().sk,
+    );
+
+    assert_eq!(
+        verify_block_signatures_only(&block, &state, &spec),
+        Err(BlockProcessingError::Invalid(BlockInvalid::BadSignature))
+    );
+}
+
+#[test]
+fn block_signatures_only_invalid_randao() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec);
+    let (block, state) = builder.build(Some(Keypair::random().sk), None, &spec);
+
+    assert_eq!(
+        verify_block_signatures_only(&block, &state, &spec),
+        Err(BlockProcessingError::Invalid(
+            BlockInvalid::BadRandaoSignature
+        ))
+    );
+}
+
+#[test]
+fn block_signatures_only_other_epoch() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec);
+    let (mut block, state) = builder.build(None, None, &spec);
+
+    block.slot += 1;
+
+    assert_eq!(
+        verify_block_signatures_only(&block, &state, &spec),
+        Err(BlockProcessingError::Invalid(
+            BlockInvalid::StateSlotMismatch
+        ))
+    );
+}
+
+#[test]
+fn attestation_signature_set_verifies_signature() {
+    let (state, attestation, spec) = signed_attestation();
+
+    let set = attestation_signature_set(&state, &attestation, &spec)
+        .unwrap()
+        .expect("attestation without custody bits has a set");
+    assert!(verify_signature_sets(&[set]));
+
+    let mut invalid = attestation.clone();
+    invalid.data.crosslink_data_root = Hash256::from([0xAA; 32]);
+    let set = attestation_signature_set(&state, &invalid, &spec)
+        .unwrap()
+        .expect("attestation without custody bits has a set");
+    assert!(!verify_signature_sets(&[set]));
+}
+
+#[test]
+fn attestation_signature_set_excludes_custody_bits() {
+    let (state, mut attestation, spec) = signed_attestation();
+
+    attestation.custody_bitfield.set(0, true);
+
+    assert_eq!(
+        attestation_signature_set(&state, &attestation, &spec).map(|set| set.is_none()),
+        Ok(true)
+    );
+}
+
+/// Returns an attestation signed by its whole committee, with the state and spec it is valid for.
+fn signed_attestation() -> (BeaconState<MinimalEthSpec>, Attestation, ChainSpec) {
+    let spec = MinimalEthSpec::default_spec();
+
+    let mut state_builder =
+        TestingBeaconStateBuilder::from_default_keypairs_file_if_exists(64, &spec);
+    state_builder.teleport_to_slot(spec.genesis_slot + MinimalEthSpec::slots_per_epoch() * 4);
+    state_builder.build_caches(&spec).unwrap();
+    let (state, keypairs) = state_builder.build();
+
+    let slot = state.slot - 1;
+    let committee = state
+        .get_crosslink_committees_at_slot(slot)
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap()
+        .into_owned();
+
+    let mut builder =
+        TestingAttestationBuilder::new(&state, &committee.committee, slot, committee.shard, &spec);
+    let secret_keys: Vec<&SecretKey> = committee
+        .committee
+        .iter()
+        .map(|&i| &keypairs[i].sk)
+        .collect();
+    builder.sign(&committee.committee, &secret_keys, &state.fork, &spec);
+
+    (state, builder.build(), spec)
+}
+
 fn get_builder(spec: &ChainSpec) -> (BlockProcessingBuilder<MainnetEthSpec>) {
     let mut builder = BlockProcessingBuilder::new(VALIDATOR_COUNT, &spec);
 
@@ -1,8 +1,10 @@
 use super::errors::{AttestationInvalid as Invalid, AttestationValidationError as Error};
 use crate::common::convert_to_indexed;
 use crate::per_block_processing::{
-    verify_indexed_attestation, verify_indexed_attestation_without_signature,
+    indexed_attestation_signature_set, verify_indexed_attestation,
+    verify_indexed_attestation_signature, verify_indexed_attestation_without_signature,
 };
+use bls::SignatureSet;
 use tree_hash::TreeHash;
 use types::*;
 
@@ -43,6 +45,51 @@ pub fn validate_attestation_without_signature<T: EthSpec>(
     validate_attestation_parametric(state, attestation, spec, false, false)
 }
 
+/// Like `validate_attestation_time_independent_only` but doesn't check the signature.
+///
+/// Intended for attestations whose signature has already been verified, e.g., as part of a batch.
+pub fn validate_attestation_time_independent_only_without_signature<T: EthSpec>(
+    state: &BeaconState<T>,
+    attestation: &Attestation,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    validate_attestation_parametric(state, attestation, spec, false, true)
+}
+
+/// Verifies only the aggregate signature of an `Attestation`, not whether it may be included in a
+/// block.
+///
+/// Spec v0.6.3
+pub fn verify_attestation_signature<T: EthSpec>(
+    state: &BeaconState<T>,
+    attestation: &Attestation,
+    spec: &ChainSpec,
+) -> Result<(), Error> {
+    let indexed_attestation = convert_to_indexed(state, attestation)?;
+    verify_indexed_attestation_signature(state, &indexed_attestation, spec)?;
+
+    Ok(())
+}
+
+/// Returns the aggregate signature of an `Attestation` as a `SignatureSet`, so it may be verified
+/// in a batch.
+///
+/// Returns `None` if the attestation can't be expressed as a single set, in which case it must be
+/// verified with `verify_attestation_signature`.
+pub fn attestation_signature_set<'a, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    attestation: &Attestation,
+    spec: &ChainSpec,
+) -> Result<Option<SignatureSet<'a>>, Error> {
+    let indexed_attestation = convert_to_indexed(state, attestation)?;
+
+    Ok(indexed_attestation_signature_set(
+        state,
+        &indexed_attestation,
+        spec,
+    )?)
+}
+
 /// Indicates if an `Attestation` is valid to be included in a block in the current epoch of the
 /// given state, optionally validating the aggregate signature.
 ///
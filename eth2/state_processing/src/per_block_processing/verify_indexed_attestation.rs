@@ -1,6 +1,8 @@
 use super::errors::{
     IndexedAttestationInvalid as Invalid, IndexedAttestationValidationError as Error,
 };
+use bls::SignatureSet;
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::iter::FromIterator;
 use tree_hash::TreeHash;
@@ -82,33 +84,44 @@ fn verify_indexed_attestation_parametric<T: EthSpec>(
 }
 
 /// Create an aggregate public key for a list of validators, failing if any key can't be found.
-fn create_aggregate_pubkey<'a, T, I>(
+///
+/// Aggregates are looked up in, and added to, the state's `aggregate_pubkey_cache`.
+fn create_aggregate_pubkey<T: EthSpec>(
     state: &BeaconState<T>,
-    validator_indices: I,
-) -> Result<AggregatePublicKey, Error>
-where
-    I: IntoIterator<Item = &'a u64>,
-    T: EthSpec,
-{
-    validator_indices.into_iter().try_fold(
-        AggregatePublicKey::new(),
-        |mut aggregate_pubkey, &validator_idx| {
-            state
-                .validator_registry
-                .get(validator_idx as usize)
-                .ok_or_else(|| Error::Invalid(Invalid::UnknownValidator(validator_idx)))
-                .map(|validator| {
-                    aggregate_pubkey.add(&validator.pubkey);
-                    aggregate_pubkey
-                })
-        },
-    )
+    validator_indices: &[u64],
+) -> Result<AggregatePublicKey, Error> {
+    // The cache is shared with other forks, so it may hold aggregates of validators which are
+    // unknown to this state.
+    if let Some(&validator_idx) = validator_indices
+        .iter()
+        .find(|&&i| i as usize >= state.validator_registry.len())
+    {
+        invalid!(Invalid::UnknownValidator(validator_idx));
+    }
+
+    if let Some(aggregate_pubkey) = state.aggregate_pubkey_cache.get(validator_indices) {
+        return Ok(aggregate_pubkey);
+    }
+
+    let pubkeys: Vec<&PublicKey> = validator_indices
+        .iter()
+        .map(|&validator_idx| &state.validator_registry[validator_idx as usize].pubkey)
+        .collect();
+    let aggregate_pubkey = AggregatePublicKey::aggregate(&pubkeys);
+
+    if !validator_indices.is_empty() {
+        state
+            .aggregate_pubkey_cache
+            .insert(validator_indices, aggregate_pubkey.clone());
+    }
+
+    Ok(aggregate_pubkey)
 }
 
 /// Verify the signature of an IndexedAttestation.
 ///
 /// Spec v0.6.3
-fn verify_indexed_attestation_signature<T: EthSpec>(
+pub fn verify_indexed_attestation_signature<T: EthSpec>(
     state: &BeaconState<T>,
     indexed_attestation: &IndexedAttestation,
     spec: &ChainSpec,
@@ -154,3 +167,49 @@ fn verify_indexed_attestation_signature<T: EthSpec>(
 
     Ok(())
 }
+
+/// Returns the signature of an `IndexedAttestation` as a `SignatureSet`, so it may be verified in
+/// a batch with `bls::verify_signature_sets`.
+///
+/// A set covers a single message, so `None` is returned if any validator signed with custody bit
+/// `1`. Such attestations must be verified with `verify_indexed_attestation_signature`.
+pub fn indexed_attestation_signature_set<'a, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    indexed_attestation: &IndexedAttestation,
+    spec: &ChainSpec,
+) -> Result<Option<SignatureSet<'a>>, Error> {
+    if !indexed_attestation.custody_bit_1_indices.is_empty() {
+        return Ok(None);
+    }
+
+    let signing_keys = indexed_attestation
+        .custody_bit_0_indices
+        .iter()
+        .map(|&validator_idx| {
+            state
+                .validator_registry
+                .get(validator_idx as usize)
+                .map(|validator| &validator.pubkey)
+                .ok_or_else(|| Error::Invalid(Invalid::UnknownValidator(validator_idx)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let message = AttestationDataAndCustodyBit {
+        data: indexed_attestation.data.clone(),
+        custody_bit: false,
+    }
+    .tree_hash_root();
+
+    let domain = spec.get_domain(
+        indexed_attestation.data.target_epoch,
+        Domain::Attestation,
+        &state.fork,
+    );
+
+    Ok(Some(SignatureSet {
+        signature: Cow::Owned(indexed_attestation.signature.clone()),
+        signing_keys,
+        message,
+        domain,
+    }))
+}
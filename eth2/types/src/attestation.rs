@@ -34,9 +34,9 @@ pub struct Attestation {
 impl Attestation {
     /// Are the aggregation bitfields of these attestations disjoint?
     pub fn signers_disjoint_from(&self, other: &Attestation) -> bool {
-        self.aggregation_bitfield
-            .intersection(&other.aggregation_bitfield)
-            .is_zero()
+        !self
+            .aggregation_bitfield
+            .intersects(&other.aggregation_bitfield)
     }
 
     /// Aggregate another Attestation into this one.
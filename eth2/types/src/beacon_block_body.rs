@@ -1,4 +1,4 @@
-use crate::test_utils::{graffiti_from_hex_str, TestRandom};
+use crate::test_utils::TestRandom;
 use crate::*;
 
 use serde_derive::{Deserialize, Serialize};
@@ -24,7 +24,7 @@ use tree_hash_derive::{CachedTreeHash, TreeHash};
 pub struct BeaconBlockBody {
     pub randao_reveal: Signature,
     pub eth1_data: Eth1Data,
-    #[serde(deserialize_with = "graffiti_from_hex_str")]
+    #[serde(with = "serde_hex::fixed_bytes_hex")]
     pub graffiti: [u8; 32],
     pub proposer_slashings: Vec<ProposerSlashing>,
     pub attester_slashings: Vec<AttesterSlashing>,
@@ -2,6 +2,7 @@ use crate::test_utils::TestRandom;
 use crate::*;
 use bls::Signature;
 
+use compare_fields_derive::CompareFields;
 use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use test_random_derive::TestRandom;
@@ -23,6 +24,7 @@ use tree_hash_derive::{CachedTreeHash, SignedRoot, TreeHash};
     CachedTreeHash,
     TestRandom,
     SignedRoot,
+    CompareFields,
 )]
 pub struct BeaconBlockHeader {
     pub slot: Slot,
@@ -15,13 +15,28 @@ use test_random_derive::TestRandom;
 use tree_hash::TreeHash;
 use tree_hash_derive::{CachedTreeHash, TreeHash};
 
+pub use self::aggregate_pubkey_cache::AggregatePubkeyCache;
 pub use self::committee_cache::CommitteeCache;
+pub use self::duties_proof::{verify_duties_proof, DutiesProofError};
+pub use self::finality_proof::{
+    ancestry_indices, block_root_of_header, verify_ancestry_proof, verify_finality_proof,
+    FinalityProofError,
+};
+pub use self::state_proof::{
+    balances_length_index, latest_block_root_index, latest_state_root_index, state_length_indices,
+    unverified_length, validator_balance_indices, validator_status_indices, verify_balance_proof,
+    verify_validator_status_proof, ProvenBalance, ProvenValidatorStatus, StateProofError,
+};
 pub use beacon_state_types::*;
 
+mod aggregate_pubkey_cache;
 mod beacon_state_types;
 mod committee_cache;
+mod duties_proof;
 mod exit_cache;
+mod finality_proof;
 mod pubkey_cache;
+mod state_proof;
 mod tests;
 
 pub const CACHED_EPOCHS: usize = 3;
@@ -59,6 +74,8 @@ pub enum Error {
     RelativeEpochError(RelativeEpochError),
     CommitteeCacheUninitialized(RelativeEpoch),
     TreeHashCacheError(TreeHashCacheError),
+    /// The node at this generalized index may not be proven.
+    UnsupportedProofIndex(u64),
 }
 
 /// The state of the `BeaconChain` at some slot.
@@ -84,15 +101,17 @@ where
     // Misc
     pub slot: Slot,
     pub genesis_time: u64,
+    #[compare_fields(nested)]
     pub fork: Fork,
 
     // Validator registry
-    #[compare_fields(as_slice)]
+    #[compare_fields(as_slice, nested)]
     pub validator_registry: Vec<Validator>,
     #[compare_fields(as_slice)]
     pub balances: Vec<u64>,
 
     // Randomness and committees
+    #[compare_fields(as_slice)]
     pub latest_randao_mixes: FixedLenVec<Hash256, T::LatestRandaoMixesLength>,
     pub latest_start_shard: u64,
 
@@ -108,19 +127,27 @@ where
     pub finalized_root: Hash256,
 
     // Recent state
+    #[compare_fields(as_slice, nested)]
     pub current_crosslinks: FixedLenVec<Crosslink, T::ShardCount>,
+    #[compare_fields(as_slice, nested)]
     pub previous_crosslinks: FixedLenVec<Crosslink, T::ShardCount>,
+    #[compare_fields(as_slice)]
     pub latest_block_roots: FixedLenVec<Hash256, T::SlotsPerHistoricalRoot>,
     #[compare_fields(as_slice)]
     pub latest_state_roots: FixedLenVec<Hash256, T::SlotsPerHistoricalRoot>,
     #[compare_fields(as_slice)]
     latest_active_index_roots: FixedLenVec<Hash256, T::LatestActiveIndexRootsLength>,
+    #[compare_fields(as_slice)]
     latest_slashed_balances: FixedLenVec<u64, T::LatestSlashedExitLength>,
+    #[compare_fields(nested)]
     pub latest_block_header: BeaconBlockHeader,
+    #[compare_fields(as_slice)]
     pub historical_roots: Vec<Hash256>,
 
     // Ethereum 1.0 chain data
+    #[compare_fields(nested)]
     pub latest_eth1_data: Eth1Data,
+    #[compare_fields(as_slice, nested)]
     pub eth1_data_votes: Vec<Eth1Data>,
     pub deposit_index: u64,
 
@@ -149,6 +176,12 @@ where
     #[tree_hash(skip_hashing)]
     #[test_random(default)]
     pub exit_cache: ExitCache,
+    #[serde(skip_serializing, skip_deserializing)]
+    #[ssz(skip_serializing)]
+    #[ssz(skip_deserializing)]
+    #[tree_hash(skip_hashing)]
+    #[test_random(default)]
+    pub aggregate_pubkey_cache: AggregatePubkeyCache,
 }
 
 impl<T: EthSpec> BeaconState<T> {
@@ -229,6 +262,7 @@ impl<T: EthSpec> BeaconState<T> {
             pubkey_cache: PubkeyCache::default(),
             tree_hash_cache: TreeHashCache::default(),
             exit_cache: ExitCache::default(),
+            aggregate_pubkey_cache: AggregatePubkeyCache::default(),
         }
     }
 
@@ -784,6 +818,7 @@ impl<T: EthSpec> BeaconState<T> {
         self.drop_pubkey_cache();
         self.drop_tree_hash_cache();
         self.exit_cache = ExitCache::default();
+        self.aggregate_pubkey_cache = AggregatePubkeyCache::default();
     }
 
     /// Build an epoch cache, unless it is has already been built.
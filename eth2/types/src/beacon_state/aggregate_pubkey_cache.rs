@@ -0,0 +1,123 @@
+use crate::*;
+use hashing::hash;
+use parking_lot::RwLock;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+/// The maximum number of aggregates held before the oldest are evicted.
+pub const AGGREGATE_PUBKEY_CACHE_SIZE: usize = 4_096;
+
+/// Maps sets of validator indices to the aggregate of their public keys.
+///
+/// The attesting indices of an attestation are determined by its committee (i.e., the shuffling
+/// and the committee within it) and its aggregation bitfield, so the same patterns are seen many
+/// times (e.g., full committees) across gossip and block processing. Keying by the indices
+/// themselves is valid across forks, since every validator registry is a prefix of any registry
+/// which has processed more deposits. Callers must still check the indices against their own
+/// registry.
+///
+/// Clones share the same underlying cache, so a state and the states derived from it all benefit
+/// from each other's aggregations.
+#[derive(Clone, Default)]
+pub struct AggregatePubkeyCache(Arc<RwLock<Inner>>);
+
+#[derive(Default)]
+struct Inner {
+    aggregates: HashMap<Hash256, AggregatePublicKey>,
+    /// Keys in order of insertion, for eviction.
+    order: VecDeque<Hash256>,
+}
+
+impl AggregatePubkeyCache {
+    /// Returns the aggregate public key of `indices`, if known.
+    pub fn get(&self, indices: &[u64]) -> Option<AggregatePublicKey> {
+        self.0.read().aggregates.get(&Self::key(indices)).cloned()
+    }
+
+    /// Stores the aggregate public key of `indices`, evicting the oldest entry if the cache is
+    /// full.
+    pub fn insert(&self, indices: &[u64], aggregate: AggregatePublicKey) {
+        let key = Self::key(indices);
+        let mut inner = self.0.write();
+
+        if inner.aggregates.insert(key, aggregate).is_none() {
+            inner.order.push_back(key);
+        }
+
+        while inner.order.len() > AGGREGATE_PUBKEY_CACHE_SIZE {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.aggregates.remove(&oldest);
+            }
+        }
+    }
+
+    /// Returns the number of aggregates in the cache.
+    pub fn len(&self) -> usize {
+        self.0.read().aggregates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key(indices: &[u64]) -> Hash256 {
+        let bytes: Vec<u8> = indices
+            .iter()
+            .flat_map(|i| i.to_le_bytes().to_vec())
+            .collect();
+        Hash256::from_slice(&hash(&bytes))
+    }
+}
+
+impl fmt::Debug for AggregatePubkeyCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AggregatePubkeyCache {{ len: {} }}", self.len())
+    }
+}
+
+/// The contents of a cache have no bearing on the equality of the states holding it.
+impl PartialEq for AggregatePubkeyCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(keypairs: &[Keypair]) -> AggregatePublicKey {
+        let pubkeys: Vec<&PublicKey> = keypairs.iter().map(|keypair| &keypair.pk).collect();
+        AggregatePublicKey::aggregate(&pubkeys)
+    }
+
+    #[test]
+    fn shared_between_clones() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+        let cache = AggregatePubkeyCache::default();
+        let clone = cache.clone();
+
+        cache.insert(&[0, 1, 2], aggregate(&keypairs));
+
+        assert_eq!(
+            clone.get(&[0, 1, 2]).map(|key| key.as_raw().as_bytes()),
+            Some(aggregate(&keypairs).as_raw().as_bytes())
+        );
+        assert!(clone.get(&[0, 1]).is_none());
+    }
+
+    #[test]
+    fn evicts_oldest() {
+        let cache = AggregatePubkeyCache::default();
+        let key = aggregate(&[Keypair::random()]);
+
+        for i in 0..=AGGREGATE_PUBKEY_CACHE_SIZE as u64 {
+            cache.insert(&[i], key.clone());
+        }
+
+        assert_eq!(cache.len(), AGGREGATE_PUBKEY_CACHE_SIZE);
+        assert!(cache.get(&[0]).is_none());
+        assert!(cache.get(&[1]).is_some());
+    }
+}
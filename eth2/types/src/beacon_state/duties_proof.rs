@@ -0,0 +1,391 @@
+//! Merkle proofs of the parts of a `BeaconState` which determine the duties of its validators.
+//!
+//! A proof covers, for each validator, the public key and activation and exit epochs of its
+//! registry entry, as well as the length of the registry and the two state fields from which the
+//! shuffling seed of an epoch is generated. It allows a validator client to check the duties
+//! reported by a beacon node against a state root, rather than trusting them outright.
+use super::{BeaconState, Error};
+use crate::*;
+use fixed_len_vec::typenum::Unsigned;
+use int_to_bytes::int_to_bytes32;
+use merkle_proof::{
+    concat_generalized_indices, verify_partial, MerkleTree, PartialError, SerializedPartial,
+};
+use tree_hash::{TreeHash, TreeHashType, BYTES_PER_CHUNK};
+
+/// The number of fields of `BeaconState` which are hashed.
+const STATE_FIELDS: usize = 27;
+/// The generalized index of the first field of `BeaconState`, its fields padded to 32 leaves.
+const STATE_FIELDS_START: u64 = 32;
+pub(super) const VALIDATOR_REGISTRY_FIELD: u64 = 3;
+const LATEST_RANDAO_MIXES_FIELD: u64 = 5;
+const LATEST_ACTIVE_INDEX_ROOTS_FIELD: u64 = 20;
+
+/// The generalized index of the first field of `Validator`, its 8 fields forming 8 leaves.
+const VALIDATOR_FIELDS_START: u64 = 8;
+pub(super) const PUBKEY_FIELD: u64 = 0;
+pub(super) const ACTIVATION_EPOCH_FIELD: u64 = 3;
+pub(super) const EXIT_EPOCH_FIELD: u64 = 4;
+const PROVEN_VALIDATOR_FIELDS: [u64; 3] = [PUBKEY_FIELD, ACTIVATION_EPOCH_FIELD, EXIT_EPOCH_FIELD];
+
+#[derive(Debug, PartialEq)]
+pub enum DutiesProofError {
+    MalformedProof(PartialError),
+    /// The proof does not hash to the state root.
+    InvalidProof,
+    /// The node at this generalized index is absent from the proof, or does not contribute to
+    /// the root.
+    Unproven(u64),
+    /// The validator index is beyond the end of the proven registry.
+    UnknownValidator(usize),
+    /// The registry entry at the index has a different public key.
+    PubkeyMismatch(usize),
+    /// The validator at the index is not active in the epoch.
+    InactiveValidator(usize),
+}
+
+/// Returns the generalized index of the state field numbered `field`.
+pub(super) fn state_field_index(field: u64) -> u64 {
+    STATE_FIELDS_START + field
+}
+
+/// Returns the generalized index of the length of the validator registry.
+pub(super) fn registry_length_index() -> u64 {
+    // A list's root is the hash of the root of its elements and its length.
+    2 * state_field_index(VALIDATOR_REGISTRY_FIELD) + 1
+}
+
+/// Returns the generalized index of the root of the validator registry's elements.
+fn registry_elements_index() -> u64 {
+    2 * state_field_index(VALIDATOR_REGISTRY_FIELD)
+}
+
+/// Returns the generalized index of `field` of validator `index`, in a registry of `registry_len`.
+pub(super) fn validator_field_index(registry_len: usize, index: usize, field: u64) -> u64 {
+    let validator = concat_generalized_indices(
+        registry_elements_index(),
+        registry_len.next_power_of_two() as u64 + index as u64,
+    );
+    concat_generalized_indices(validator, VALIDATOR_FIELDS_START + field)
+}
+
+/// Returns the positions in `latest_randao_mixes` and `latest_active_index_roots` of the fields
+/// from which the seed of `epoch` is generated.
+///
+/// Spec v0.6.3
+fn seed_positions<T: EthSpec>(epoch: Epoch, spec: &ChainSpec) -> (usize, usize) {
+    let randao_len = T::LatestRandaoMixesLength::to_u64();
+    let randao = (epoch + randao_len - spec.min_seed_lookahead).as_usize() % randao_len as usize;
+    let index_root = epoch.as_usize() % T::LatestActiveIndexRootsLength::to_usize();
+
+    (randao, index_root)
+}
+
+/// Returns the generalized indices of the fields from which the seed of `epoch` is generated.
+fn seed_indices<T: EthSpec>(epoch: Epoch, spec: &ChainSpec) -> [u64; 2] {
+    let (randao, index_root) = seed_positions::<T>(epoch, spec);
+    [
+        concat_generalized_indices(
+            state_field_index(LATEST_RANDAO_MIXES_FIELD),
+            T::LatestRandaoMixesLength::to_u64().next_power_of_two() + randao as u64,
+        ),
+        concat_generalized_indices(
+            state_field_index(LATEST_ACTIVE_INDEX_ROOTS_FIELD),
+            T::LatestActiveIndexRootsLength::to_u64().next_power_of_two() + index_root as u64,
+        ),
+    ]
+}
+
+pub(super) fn root(item: &impl TreeHash) -> Hash256 {
+    Hash256::from_slice(&item.tree_hash_root())
+}
+
+/// Returns the size of the value of the field read by `_field` within its chunk.
+fn leaf_size<S, F: TreeHash>(_field: impl Fn(&S) -> &F) -> usize {
+    match F::tree_hash_type() {
+        TreeHashType::Basic => BYTES_PER_CHUNK / F::tree_hash_packing_factor(),
+        _ => BYTES_PER_CHUNK,
+    }
+}
+
+pub(super) fn validator_field_roots(validator: &Validator) -> Vec<Hash256> {
+    vec![
+        root(&validator.pubkey),
+        root(&validator.withdrawal_credentials),
+        root(&validator.activation_eligibility_epoch),
+        root(&validator.activation_epoch),
+        root(&validator.exit_epoch),
+        root(&validator.withdrawable_epoch),
+        root(&validator.slashed),
+        root(&validator.effective_balance),
+    ]
+}
+
+/// Implements `field_roots` and `field_layout` from a single list of the hashed fields of
+/// `BeaconState`, in order, so that the two cannot disagree.
+macro_rules! state_fields {
+    ($($field: ident),*) => {
+        impl<T: EthSpec> BeaconState<T> {
+            /// Returns the roots of the hashed fields, in order.
+            pub(super) fn field_roots(&self) -> Vec<Hash256> {
+                let roots = vec![$(root(&self.$field)),*];
+                debug_assert_eq!(roots.len(), STATE_FIELDS);
+                roots
+            }
+
+            /// Returns the name of each hashed field, in order, and the size of its value within
+            /// its chunk: that of a basic type, or that of the root of any other.
+            pub(super) fn field_layout() -> Vec<(&'static str, usize)> {
+                vec![$((stringify!($field), leaf_size(|state: &Self| &state.$field))),*]
+            }
+        }
+    };
+}
+
+state_fields!(
+    slot,
+    genesis_time,
+    fork,
+    validator_registry,
+    balances,
+    latest_randao_mixes,
+    latest_start_shard,
+    previous_epoch_attestations,
+    current_epoch_attestations,
+    previous_justified_epoch,
+    current_justified_epoch,
+    previous_justified_root,
+    current_justified_root,
+    justification_bitfield,
+    finalized_epoch,
+    finalized_root,
+    current_crosslinks,
+    previous_crosslinks,
+    latest_block_roots,
+    latest_state_roots,
+    latest_active_index_roots,
+    latest_slashed_balances,
+    latest_block_header,
+    historical_roots,
+    latest_eth1_data,
+    eth1_data_votes,
+    deposit_index
+);
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Returns the root of the state, together with a proof against it of the registry entries
+    /// of `validator_indices` and of the inputs to the seed of `epoch`.
+    ///
+    /// The whole state is hashed, so this is about as expensive as `canonical_root`.
+    pub fn duties_proof(
+        &self,
+        validator_indices: &[usize],
+        epoch: Epoch,
+        spec: &ChainSpec,
+    ) -> Result<(Hash256, SerializedPartial), Error> {
+        // Ensures the index root, and so the seed, of `epoch` is within the state.
+        self.get_active_index_root(epoch, spec)?;
+
+        let mut validator_indices = validator_indices.to_vec();
+        validator_indices.sort_unstable();
+        validator_indices.dedup();
+        if validator_indices
+            .last()
+            .map_or(false, |&i| i >= self.validator_registry.len())
+        {
+            return Err(Error::UnknownValidator);
+        }
+
+        let fields = MerkleTree::new(self.field_roots());
+        let registry = MerkleTree::new(self.validator_registry.iter().map(root).collect());
+        let randao_mixes = MerkleTree::new(self.latest_randao_mixes.to_vec());
+        let index_roots = MerkleTree::new(self.latest_active_index_roots.to_vec());
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+
+        fields.append_helpers(
+            1,
+            &[
+                state_field_index(VALIDATOR_REGISTRY_FIELD),
+                state_field_index(LATEST_RANDAO_MIXES_FIELD),
+                state_field_index(LATEST_ACTIVE_INDEX_ROOTS_FIELD),
+            ],
+            &mut partial,
+        );
+
+        partial.indices.push(registry_length_index());
+        partial.chunks.push(Hash256::from_slice(&int_to_bytes32(
+            self.validator_registry.len() as u64,
+        )));
+
+        let registry_leaves: Vec<u64> = validator_indices
+            .iter()
+            .map(|&i| registry.leaf_index(i))
+            .collect();
+        registry.append_helpers(registry_elements_index(), &registry_leaves, &mut partial);
+
+        for (&i, &leaf) in validator_indices.iter().zip(&registry_leaves) {
+            let validator = &self.validator_registry[i];
+            let validator_fields: Vec<usize> = PROVEN_VALIDATOR_FIELDS
+                .iter()
+                .map(|&f| f as usize)
+                .collect();
+
+            MerkleTree::new(validator_field_roots(validator)).append_proof(
+                concat_generalized_indices(registry_elements_index(), leaf),
+                &validator_fields,
+                &mut partial,
+            );
+        }
+
+        let (randao, index_root) = seed_positions::<T>(epoch, spec);
+        randao_mixes.append_proof(
+            state_field_index(LATEST_RANDAO_MIXES_FIELD),
+            &[randao],
+            &mut partial,
+        );
+        index_roots.append_proof(
+            state_field_index(LATEST_ACTIVE_INDEX_ROOTS_FIELD),
+            &[index_root],
+            &mut partial,
+        );
+
+        Ok((fields.root(), partial))
+    }
+}
+
+/// Verifies that `partial` proves, against `state_root`, that each validator in `validators` is
+/// at its claimed index in the registry and is active in `epoch`, and that the inputs to the seed
+/// of `epoch` are proven.
+///
+/// This does not recompute the shuffling, which requires the whole registry, so the committee
+/// positions claimed alongside the proof are not checked.
+pub fn verify_duties_proof<T: EthSpec>(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+    validators: &[(usize, &PublicKey)],
+    epoch: Epoch,
+    spec: &ChainSpec,
+) -> Result<(), DutiesProofError> {
+    let verification =
+        verify_partial(partial, state_root).map_err(DutiesProofError::MalformedProof)?;
+    if !verification.valid {
+        return Err(DutiesProofError::InvalidProof);
+    }
+
+    let proven = |index: u64| -> Result<Hash256, DutiesProofError> {
+        if verification.covered_paths.binary_search(&index).is_err() {
+            return Err(DutiesProofError::Unproven(index));
+        }
+        partial
+            .indices
+            .iter()
+            .position(|&i| i == index)
+            .map(|position| partial.chunks[position])
+            .ok_or(DutiesProofError::Unproven(index))
+    };
+    let as_u64 = |chunk: Hash256| {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&chunk.as_bytes()[0..8]);
+        u64::from_le_bytes(bytes)
+    };
+
+    for &index in &seed_indices::<T>(epoch, spec) {
+        proven(index)?;
+    }
+
+    let registry_len = as_u64(proven(registry_length_index())?) as usize;
+
+    for &(index, pubkey) in validators {
+        if index >= registry_len {
+            return Err(DutiesProofError::UnknownValidator(index));
+        }
+        let field = |f| proven(validator_field_index(registry_len, index, f));
+
+        if field(PUBKEY_FIELD)? != root(pubkey) {
+            return Err(DutiesProofError::PubkeyMismatch(index));
+        }
+
+        let activation_epoch = Epoch::new(as_u64(field(ACTIVATION_EPOCH_FIELD)?));
+        let exit_epoch = Epoch::new(as_u64(field(EXIT_EPOCH_FIELD)?));
+        if !(activation_epoch <= epoch && epoch < exit_epoch) {
+            return Err(DutiesProofError::InactiveValidator(index));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+
+    fn state() -> (BeaconState<MinimalEthSpec>, Vec<Keypair>, ChainSpec) {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(16, &spec);
+        let (state, keypairs) = builder.build();
+        (state, keypairs, spec)
+    }
+
+    #[test]
+    fn field_roots_match_tree_hash() {
+        let (state, _, _) = state();
+        assert_eq!(
+            MerkleTree::new(state.field_roots()).root(),
+            Hash256::from_slice(&state.tree_hash_root())
+        );
+
+        let layout = BeaconState::<MinimalEthSpec>::field_layout();
+        assert_eq!(layout.len(), STATE_FIELDS);
+        assert_eq!(layout[0], ("slot", 8));
+        assert_eq!(layout[2], ("fork", 32));
+        assert_eq!(layout[26], ("deposit_index", 8));
+    }
+
+    #[test]
+    fn proof_verifies() {
+        let (state, keypairs, spec) = state();
+        let epoch = state.current_epoch();
+
+        let (state_root, partial) = state.duties_proof(&[3, 11], epoch, &spec).unwrap();
+        let validators = [(3, &keypairs[3].pk), (11, &keypairs[11].pk)];
+
+        assert_eq!(state_root, state.canonical_root());
+        assert_eq!(
+            verify_duties_proof::<MinimalEthSpec>(&partial, state_root, &validators, epoch, &spec),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_claims() {
+        let (state, keypairs, spec) = state();
+        let epoch = state.current_epoch();
+        let (state_root, partial) = state.duties_proof(&[3], epoch, &spec).unwrap();
+
+        let verify = |validators: &[(usize, &PublicKey)], state_root| {
+            verify_duties_proof::<MinimalEthSpec>(&partial, state_root, validators, epoch, &spec)
+        };
+
+        assert_eq!(
+            verify(&[(3, &keypairs[4].pk)], state_root),
+            Err(DutiesProofError::PubkeyMismatch(3))
+        );
+        assert_eq!(
+            verify(&[(4, &keypairs[4].pk)], state_root),
+            Err(DutiesProofError::Unproven(validator_field_index(
+                16,
+                4,
+                PUBKEY_FIELD
+            )))
+        );
+        assert_eq!(
+            verify(&[(3, &keypairs[3].pk)], Hash256::zero()),
+            Err(DutiesProofError::InvalidProof)
+        );
+    }
+}
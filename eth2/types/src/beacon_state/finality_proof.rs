@@ -0,0 +1,250 @@
+//! Merkle proofs of the finalized checkpoint of a `BeaconState`, which allow a light client to
+//! learn the finalized block from a state root without downloading the state, and of the recent
+//! ancestors of the block of a state, which allow it to check that the finalized block descends
+//! from one it already trusts.
+use super::duties_proof::{root, state_field_index};
+use super::state_proof::latest_block_root_index;
+use super::BeaconState;
+use crate::*;
+use fixed_len_vec::typenum::Unsigned;
+use merkle_proof::{
+    verify_partial, MerkleTree, PartialError, PartialVerification, SerializedPartial,
+};
+
+const SLOT_FIELD: u64 = 0;
+const FINALIZED_EPOCH_FIELD: u64 = 14;
+const FINALIZED_ROOT_FIELD: u64 = 15;
+
+#[derive(Debug, PartialEq)]
+pub enum FinalityProofError {
+    MalformedProof(PartialError),
+    /// The proof does not hash to the state root.
+    InvalidProof,
+    /// The node at this generalized index is absent from the proof, or does not contribute to
+    /// the root.
+    Unproven(u64),
+    /// The ancestor is not within the `SlotsPerHistoricalRoot` slots before the state, so its
+    /// root is not in `latest_block_roots`.
+    AncestorOutOfRange {
+        state_slot: Slot,
+        ancestor_slot: Slot,
+    },
+    /// The state has a different block root at the slot of the ancestor.
+    NotAnAncestor,
+}
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Returns the root of the state, together with a proof against it of `finalized_epoch` and
+    /// `finalized_root`.
+    ///
+    /// The whole state is hashed, so this is about as expensive as `canonical_root`.
+    pub fn finality_proof(&self) -> (Hash256, SerializedPartial) {
+        let fields = MerkleTree::new(self.field_roots());
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        fields.append_proof(
+            1,
+            &[
+                FINALIZED_EPOCH_FIELD as usize,
+                FINALIZED_ROOT_FIELD as usize,
+            ],
+            &mut partial,
+        );
+
+        (fields.root(), partial)
+    }
+}
+
+/// Verifies that `partial` proves a finalized checkpoint against `state_root`, returning the
+/// finalized epoch and root.
+pub fn verify_finality_proof(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+) -> Result<(Epoch, Hash256), FinalityProofError> {
+    let verification = verify(partial, state_root)?;
+    let epoch = proven_chunk(
+        partial,
+        &verification,
+        state_field_index(FINALIZED_EPOCH_FIELD),
+    )?;
+
+    Ok((
+        Epoch::new(chunk_to_u64(epoch)),
+        proven_chunk(
+            partial,
+            &verification,
+            state_field_index(FINALIZED_ROOT_FIELD),
+        )?,
+    ))
+}
+
+/// Returns the generalized indices needed to prove that the block at `ancestor_slot` is an
+/// ancestor of the block of a state: those of the slot of the state and of the entry for
+/// `ancestor_slot` in its `latest_block_roots`.
+pub fn ancestry_indices<T: EthSpec>(ancestor_slot: Slot) -> Vec<u64> {
+    vec![
+        state_field_index(SLOT_FIELD),
+        latest_block_root_index::<T>(ancestor_slot),
+    ]
+}
+
+/// Verifies that `partial` proves, against `state_root`, that the block with `ancestor_root` at
+/// `ancestor_slot` is an ancestor of the block of the state.
+///
+/// A state only holds the block roots of the `SlotsPerHistoricalRoot` slots before its own, so
+/// older ancestors cannot be proven.
+pub fn verify_ancestry_proof<T: EthSpec>(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+    ancestor_slot: Slot,
+    ancestor_root: Hash256,
+) -> Result<(), FinalityProofError> {
+    let verification = verify(partial, state_root)?;
+    let state_slot = Slot::new(chunk_to_u64(proven_chunk(
+        partial,
+        &verification,
+        state_field_index(SLOT_FIELD),
+    )?));
+
+    let history = T::SlotsPerHistoricalRoot::to_u64();
+    if ancestor_slot >= state_slot
+        || state_slot.as_u64() > ancestor_slot.as_u64().saturating_add(history)
+    {
+        return Err(FinalityProofError::AncestorOutOfRange {
+            state_slot,
+            ancestor_slot,
+        });
+    }
+
+    let root = proven_chunk(
+        partial,
+        &verification,
+        latest_block_root_index::<T>(ancestor_slot),
+    )?;
+    if root != ancestor_root {
+        return Err(FinalityProofError::NotAnAncestor);
+    }
+
+    Ok(())
+}
+
+fn verify(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+) -> Result<PartialVerification, FinalityProofError> {
+    let verification =
+        verify_partial(partial, state_root).map_err(FinalityProofError::MalformedProof)?;
+    if !verification.valid {
+        return Err(FinalityProofError::InvalidProof);
+    }
+    Ok(verification)
+}
+
+/// Returns the chunk at `index`, if `verification` of `partial` found it on a path to the root.
+fn proven_chunk(
+    partial: &SerializedPartial,
+    verification: &PartialVerification,
+    index: u64,
+) -> Result<Hash256, FinalityProofError> {
+    if verification.covered_paths.binary_search(&index).is_err() {
+        return Err(FinalityProofError::Unproven(index));
+    }
+    partial
+        .indices
+        .iter()
+        .position(|&i| i == index)
+        .map(|position| partial.chunks[position])
+        .ok_or(FinalityProofError::Unproven(index))
+}
+
+/// Returns the `u64` packed at the start of `chunk`.
+fn chunk_to_u64(chunk: Hash256) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&chunk.as_bytes()[0..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Returns the root of the block with header `header`.
+///
+/// This is the `tree_hash_root` of the header, which matches that of the full block, rather than
+/// `BeaconBlockHeader::canonical_root`, which omits the signature.
+pub fn block_root_of_header(header: &BeaconBlockHeader) -> Hash256 {
+    root(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+
+    #[test]
+    fn proof_verifies() {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _) = builder.build();
+        state.finalized_epoch = Epoch::new(3);
+        state.finalized_root = Hash256::from_low_u64_le(42);
+
+        let (state_root, partial) = state.finality_proof();
+
+        assert_eq!(state_root, state.canonical_root());
+        assert_eq!(
+            verify_finality_proof(&partial, state_root),
+            Ok((state.finalized_epoch, state.finalized_root))
+        );
+        assert_eq!(
+            verify_finality_proof(&partial, Hash256::zero()),
+            Err(FinalityProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn ancestry_proof_verifies() {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _): (BeaconState<MinimalEthSpec>, _) = builder.build();
+        state.slot = Slot::new(70);
+        let ancestor_root = Hash256::from_low_u64_le(42);
+        state.set_block_root(Slot::new(8), ancestor_root).unwrap();
+
+        let state_root = state.canonical_root();
+        let verify = |slot: u64, root: Hash256| {
+            let partial = state
+                .prove(&ancestry_indices::<MinimalEthSpec>(Slot::new(slot)))
+                .unwrap();
+            verify_ancestry_proof::<MinimalEthSpec>(&partial, state_root, Slot::new(slot), root)
+        };
+
+        assert_eq!(verify(8, ancestor_root), Ok(()));
+        assert_eq!(
+            verify(8, Hash256::zero()),
+            Err(FinalityProofError::NotAnAncestor)
+        );
+        assert_eq!(
+            verify(5, ancestor_root),
+            Err(FinalityProofError::AncestorOutOfRange {
+                state_slot: Slot::new(70),
+                ancestor_slot: Slot::new(5)
+            })
+        );
+        assert_eq!(
+            verify(70, ancestor_root),
+            Err(FinalityProofError::AncestorOutOfRange {
+                state_slot: Slot::new(70),
+                ancestor_slot: Slot::new(70)
+            })
+        );
+    }
+
+    #[test]
+    fn header_root_matches_block_root() {
+        let block = BeaconBlock::empty(&MinimalEthSpec::default_spec());
+        assert_eq!(
+            block_root_of_header(&block.block_header()),
+            block.canonical_root()
+        );
+    }
+}
@@ -0,0 +1,691 @@
+//! Merkle proofs of arbitrary nodes of a `BeaconState`, by generalized index, and of the balances
+//! and status of individual validators.
+//!
+//! Only the fields of the state, the validator registry, the balances and the latest block and
+//! state roots may be proven into; nodes within any other field are refused.
+use super::duties_proof::{
+    registry_length_index, root, state_field_index, validator_field_index, validator_field_roots,
+    ACTIVATION_EPOCH_FIELD, EXIT_EPOCH_FIELD, PUBKEY_FIELD, VALIDATOR_REGISTRY_FIELD,
+};
+use super::{BeaconState, Error};
+use crate::*;
+use hashing::hash;
+use int_to_bytes::int_to_bytes32;
+use merkle_proof::tree_arithmetic::split_generalized_index;
+use merkle_proof::{
+    concat_generalized_indices, generalized_index_depth, helper_indices, metrics, verify_partial,
+    MerkleTree, MerkleTreeOverlay, PartialError, SchemaEntry, SerializedPartial,
+};
+use ssz::Encode;
+
+/// The depth of the fields of `BeaconState`, its 27 fields padded to 32 leaves.
+const STATE_FIELDS_DEPTH: u32 = 5;
+const BALANCES_FIELD: u64 = 4;
+const LATEST_BLOCK_ROOTS_FIELD: u64 = 18;
+const LATEST_STATE_ROOTS_FIELD: u64 = 19;
+/// The depth of the fields of `Validator`, its 8 fields forming 8 leaves.
+const VALIDATOR_FIELDS_DEPTH: u32 = 3;
+const SLASHED_FIELD: u64 = 6;
+const EFFECTIVE_BALANCE_FIELD: u64 = 7;
+/// The number of balances packed into each chunk of the balances tree.
+const BALANCES_PER_CHUNK: usize = 4;
+
+#[derive(Debug, PartialEq)]
+pub enum StateProofError {
+    MalformedProof(PartialError),
+    /// The proof does not hash to the state root.
+    InvalidProof,
+    /// The node at this generalized index is absent from the proof, or does not contribute to
+    /// the root.
+    Unproven(u64),
+    /// The validator index is beyond the end of the proven registry or balances.
+    UnknownValidator(usize),
+    /// The registry entry at the index has a different public key.
+    PubkeyMismatch(usize),
+}
+
+/// The balances of a validator, as proven against a state root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProvenBalance {
+    pub effective_balance: u64,
+    pub balance: u64,
+}
+
+/// The status of a validator, as proven against a state root.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProvenValidatorStatus {
+    pub activation_epoch: Epoch,
+    pub exit_epoch: Epoch,
+    pub slashed: bool,
+    pub effective_balance: u64,
+    pub balance: u64,
+}
+
+/// Returns the generalized index of the length of the balances.
+pub fn balances_length_index() -> u64 {
+    2 * state_field_index(BALANCES_FIELD) + 1
+}
+
+fn balances_elements_index() -> u64 {
+    2 * state_field_index(BALANCES_FIELD)
+}
+
+/// Returns the generalized indices of the lengths of the registry and the balances, which must be
+/// known to find the generalized indices of a validator's entries.
+pub fn state_length_indices() -> [u64; 2] {
+    [registry_length_index(), balances_length_index()]
+}
+
+/// Returns the generalized index of the chunk holding the balance of validator `index`, among
+/// `balances_len` balances.
+fn balance_chunk_index(balances_len: usize, index: usize) -> u64 {
+    let chunks = (balances_len + BALANCES_PER_CHUNK - 1) / BALANCES_PER_CHUNK;
+    concat_generalized_indices(
+        balances_elements_index(),
+        chunks.next_power_of_two() as u64 + (index / BALANCES_PER_CHUNK) as u64,
+    )
+}
+
+/// Returns the generalized indices of the nodes needed to prove the public key, effective balance
+/// and balance of validator `index`, given the lengths of the registry and the balances.
+pub fn validator_balance_indices(
+    registry_len: usize,
+    balances_len: usize,
+    index: usize,
+) -> Vec<u64> {
+    vec![
+        registry_length_index(),
+        balances_length_index(),
+        validator_field_index(registry_len, index, PUBKEY_FIELD),
+        validator_field_index(registry_len, index, EFFECTIVE_BALANCE_FIELD),
+        balance_chunk_index(balances_len, index),
+    ]
+}
+
+/// Returns the generalized indices of the nodes needed to prove the public key, activation and
+/// exit epochs, slashed flag, effective balance and balance of validator `index`, given the lengths
+/// of the registry and the balances.
+pub fn validator_status_indices(
+    registry_len: usize,
+    balances_len: usize,
+    index: usize,
+) -> Vec<u64> {
+    let mut indices = validator_balance_indices(registry_len, balances_len, index);
+    for &field in &[ACTIVATION_EPOCH_FIELD, EXIT_EPOCH_FIELD, SLASHED_FIELD] {
+        indices.push(validator_field_index(registry_len, index, field));
+    }
+    indices
+}
+
+/// Returns the generalized index of the entry for `slot` in `latest_block_roots`.
+pub fn latest_block_root_index<T: EthSpec>(slot: Slot) -> u64 {
+    history_entry_index::<T>(LATEST_BLOCK_ROOTS_FIELD, slot)
+}
+
+/// Returns the generalized index of the entry for `slot` in `latest_state_roots`.
+///
+/// A partial proving this entry links the state root of `slot` to that of any state within
+/// `SlotsPerHistoricalRoot` slots after it, e.g. as the first partial of a `ProofBundle` whose
+/// link is a proof against the earlier state.
+pub fn latest_state_root_index<T: EthSpec>(slot: Slot) -> u64 {
+    history_entry_index::<T>(LATEST_STATE_ROOTS_FIELD, slot)
+}
+
+fn history_entry_index<T: EthSpec>(field: u64, slot: Slot) -> u64 {
+    let len = T::SlotsPerHistoricalRoot::to_u64();
+    concat_generalized_indices(
+        state_field_index(field),
+        len.next_power_of_two() + slot.as_u64() % len,
+    )
+}
+
+/// Returns the depth of the trees of `latest_block_roots` and `latest_state_roots`.
+fn history_depth<T: EthSpec>() -> u32 {
+    T::SlotsPerHistoricalRoot::to_u64()
+        .next_power_of_two()
+        .trailing_zeros()
+}
+
+/// Returns the node at `index` within the tree of a list whose elements tree is `elements` and
+/// whose length is `len`. Nodes below the leaves of `elements` are resolved by `leaf_node`.
+fn list_node(
+    index: u64,
+    elements: &MerkleTree,
+    len: usize,
+    leaf_node: impl Fn(usize, u64) -> Option<Hash256>,
+) -> Option<Hash256> {
+    match index {
+        1 => {
+            let mut preimage = elements.root().as_bytes().to_vec();
+            preimage.extend_from_slice(&int_to_bytes32(len as u64));
+            Some(Hash256::from_slice(&hash(&preimage)))
+        }
+        3 => Some(Hash256::from_slice(&int_to_bytes32(len as u64))),
+        _ => {
+            let (child, within) = split_generalized_index(index, 1);
+            if child != 2 {
+                // Nothing lies below the length.
+                None
+            } else if generalized_index_depth(within) <= elements.depth() {
+                elements.node(within)
+            } else {
+                let (leaf, below) = split_generalized_index(within, elements.depth());
+                leaf_node((leaf - (1 << elements.depth())) as usize, below)
+            }
+        }
+    }
+}
+
+/// Overlays the nodes which `BeaconState::prove` may prove: the fields of the state and, within
+/// the validator registry and the balances, the root of the elements, of any depth, and the length,
+/// and the nodes of the latest block and state roots.
+///
+/// Only the depth of the latest block and state roots depends on `T`.
+impl<T: EthSpec> MerkleTreeOverlay for BeaconState<T> {
+    fn is_attached(index: u64) -> bool {
+        if index == 0 {
+            return false;
+        }
+        if generalized_index_depth(index) <= STATE_FIELDS_DEPTH {
+            return true;
+        }
+
+        let (field, within) = split_generalized_index(index, STATE_FIELDS_DEPTH);
+        if field == state_field_index(VALIDATOR_REGISTRY_FIELD)
+            || field == state_field_index(BALANCES_FIELD)
+        {
+            within == 3 || split_generalized_index(within, 1).0 == 2
+        } else if field == state_field_index(LATEST_BLOCK_ROOTS_FIELD)
+            || field == state_field_index(LATEST_STATE_ROOTS_FIELD)
+        {
+            generalized_index_depth(within) <= history_depth::<T>()
+        } else {
+            false
+        }
+    }
+
+    /// Lists the fields of the state, besides those with attached nodes below them: the lengths
+    /// of the validator registry and the balances, and each of the latest block and state roots.
+    ///
+    /// The elements of the registry and the balances are left out, as their generalized indices
+    /// depend on the lengths of the lists. Those of a validator are given by
+    /// `validator_balance_indices` and `validator_status_indices` instead.
+    fn schema() -> Vec<SchemaEntry> {
+        let entry = |path: String, generalized_index: u64, size: usize| SchemaEntry {
+            path,
+            generalized_index,
+            size,
+            offset: 0,
+        };
+
+        let mut schema = vec![];
+        for (field, (name, size)) in Self::field_layout().into_iter().enumerate() {
+            let field = field as u64;
+            if field == VALIDATOR_REGISTRY_FIELD {
+                schema.push(entry(format!("{}.len", name), registry_length_index(), 8));
+            } else if field == BALANCES_FIELD {
+                schema.push(entry(format!("{}.len", name), balances_length_index(), 8));
+            } else if field == LATEST_BLOCK_ROOTS_FIELD || field == LATEST_STATE_ROOTS_FIELD {
+                for slot in 0..T::SlotsPerHistoricalRoot::to_u64() {
+                    schema.push(entry(
+                        format!("{}.{}", name, slot),
+                        history_entry_index::<T>(field, Slot::new(slot)),
+                        32,
+                    ));
+                }
+            } else {
+                schema.push(entry(name.to_string(), state_field_index(field), size));
+            }
+        }
+        schema.sort_by_key(|entry| entry.generalized_index);
+
+        schema
+    }
+}
+
+impl<T: EthSpec> BeaconState<T> {
+    /// Returns a proof of the nodes at `indices` against the root of the state.
+    ///
+    /// The whole state is hashed, so this is about as expensive as `canonical_root`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Error::UnsupportedProofIndex` for a node within any field besides the validator
+    /// registry, the balances and the latest block and state roots, or beyond the end of any.
+    pub fn prove(&self, indices: &[u64]) -> Result<SerializedPartial, Error> {
+        let _timer = metrics::start_proof_timer();
+
+        let fields = MerkleTree::new(self.field_roots());
+        let registry = MerkleTree::new(self.validator_registry.iter().map(root).collect());
+        let balances = MerkleTree::new(
+            self.balances
+                .as_ssz_bytes()
+                .chunks(32)
+                .map(|chunk| {
+                    let mut bytes = [0; 32];
+                    bytes[0..chunk.len()].copy_from_slice(chunk);
+                    Hash256::from(bytes)
+                })
+                .collect(),
+        );
+        let block_roots = MerkleTree::new(self.latest_block_roots.to_vec());
+        let state_roots = MerkleTree::new(self.latest_state_roots.to_vec());
+
+        let node = |index: u64| -> Option<Hash256> {
+            if index == 0 {
+                return None;
+            }
+            if generalized_index_depth(index) <= STATE_FIELDS_DEPTH {
+                return fields.node(index);
+            }
+
+            let (field, within) = split_generalized_index(index, STATE_FIELDS_DEPTH);
+            if field == state_field_index(VALIDATOR_REGISTRY_FIELD) {
+                list_node(
+                    within,
+                    &registry,
+                    self.validator_registry.len(),
+                    |i, below| {
+                        let validator = self.validator_registry.get(i)?;
+                        if generalized_index_depth(below) > VALIDATOR_FIELDS_DEPTH {
+                            return None;
+                        }
+                        MerkleTree::new(validator_field_roots(validator)).node(below)
+                    },
+                )
+            } else if field == state_field_index(BALANCES_FIELD) {
+                // Balances are packed, so there is nothing below the leaves.
+                list_node(within, &balances, self.balances.len(), |_, _| None)
+            } else if field == state_field_index(LATEST_BLOCK_ROOTS_FIELD) {
+                block_roots.node(within)
+            } else if field == state_field_index(LATEST_STATE_ROOTS_FIELD) {
+                state_roots.node(within)
+            } else {
+                None
+            }
+        };
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        for index in indices.iter().cloned().chain(helper_indices(indices)) {
+            let chunk = node(index).ok_or(Error::UnsupportedProofIndex(index))?;
+            partial.indices.push(index);
+            partial.chunks.push(chunk);
+        }
+        metrics::observe_proof(&partial);
+
+        Ok(partial)
+    }
+
+    /// Returns a proof of the public key, effective balance and balance of validator `index`.
+    pub fn balance_proof(&self, index: usize) -> Result<SerializedPartial, Error> {
+        if index >= self.validator_registry.len() || index >= self.balances.len() {
+            return Err(Error::UnknownValidator);
+        }
+        self.prove(&validator_balance_indices(
+            self.validator_registry.len(),
+            self.balances.len(),
+            index,
+        ))
+    }
+
+    /// Returns a proof of the public key, activation and exit epochs, slashed flag, effective
+    /// balance and balance of validator `index`.
+    pub fn validator_status_proof(&self, index: usize) -> Result<SerializedPartial, Error> {
+        if index >= self.validator_registry.len() || index >= self.balances.len() {
+            return Err(Error::UnknownValidator);
+        }
+        self.prove(&validator_status_indices(
+            self.validator_registry.len(),
+            self.balances.len(),
+            index,
+        ))
+    }
+}
+
+/// Verifies that `partial` proves, against `state_root`, that the validator at `index` has the
+/// public key `pubkey`, returning its effective balance and balance.
+///
+/// The partial must include the lengths of the registry and balances, as from
+/// `validator_balance_indices`.
+pub fn verify_balance_proof(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+    index: usize,
+    pubkey: &PublicKey,
+) -> Result<ProvenBalance, StateProofError> {
+    let entries = ProvenEntries::verify(partial, state_root, index, pubkey)?;
+
+    Ok(ProvenBalance {
+        effective_balance: entries.field_u64(EFFECTIVE_BALANCE_FIELD)?,
+        balance: entries.balance()?,
+    })
+}
+
+/// Verifies that `partial` proves, against `state_root`, that the validator at `index` has the
+/// public key `pubkey`, returning its status.
+///
+/// The partial must include the lengths of the registry and balances, as from
+/// `validator_status_indices`.
+pub fn verify_validator_status_proof(
+    partial: &SerializedPartial,
+    state_root: Hash256,
+    index: usize,
+    pubkey: &PublicKey,
+) -> Result<ProvenValidatorStatus, StateProofError> {
+    let entries = ProvenEntries::verify(partial, state_root, index, pubkey)?;
+
+    Ok(ProvenValidatorStatus {
+        activation_epoch: Epoch::new(entries.field_u64(ACTIVATION_EPOCH_FIELD)?),
+        exit_epoch: Epoch::new(entries.field_u64(EXIT_EPOCH_FIELD)?),
+        slashed: entries.field(SLASHED_FIELD)?.as_bytes()[0] != 0,
+        effective_balance: entries.field_u64(EFFECTIVE_BALANCE_FIELD)?,
+        balance: entries.balance()?,
+    })
+}
+
+/// The registry entry and balance of a validator in a verified proof, whose public key has been
+/// checked.
+struct ProvenEntries<'a> {
+    partial: &'a SerializedPartial,
+    covered_paths: Vec<u64>,
+    registry_len: usize,
+    balances_len: usize,
+    index: usize,
+}
+
+impl<'a> ProvenEntries<'a> {
+    fn verify(
+        partial: &'a SerializedPartial,
+        state_root: Hash256,
+        index: usize,
+        pubkey: &PublicKey,
+    ) -> Result<Self, StateProofError> {
+        let verification =
+            verify_partial(partial, state_root).map_err(StateProofError::MalformedProof)?;
+        if !verification.valid {
+            return Err(StateProofError::InvalidProof);
+        }
+
+        let mut entries = ProvenEntries {
+            partial,
+            covered_paths: verification.covered_paths,
+            registry_len: 0,
+            balances_len: 0,
+            index,
+        };
+        entries.registry_len = as_u64(entries.proven(registry_length_index())?, 0) as usize;
+        entries.balances_len = as_u64(entries.proven(balances_length_index())?, 0) as usize;
+        if index >= entries.registry_len || index >= entries.balances_len {
+            return Err(StateProofError::UnknownValidator(index));
+        }
+
+        if entries.field(PUBKEY_FIELD)? != root(pubkey) {
+            return Err(StateProofError::PubkeyMismatch(index));
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the node at `index`, if it is proven.
+    fn proven(&self, index: u64) -> Result<Hash256, StateProofError> {
+        if self.covered_paths.binary_search(&index).is_err() {
+            return Err(StateProofError::Unproven(index));
+        }
+        self.partial
+            .indices
+            .iter()
+            .position(|&i| i == index)
+            .map(|position| self.partial.chunks[position])
+            .ok_or(StateProofError::Unproven(index))
+    }
+
+    /// Returns the root of `field` of the registry entry.
+    fn field(&self, field: u64) -> Result<Hash256, StateProofError> {
+        self.proven(validator_field_index(self.registry_len, self.index, field))
+    }
+
+    /// Returns `field` of the registry entry, which must be a `u64` or an epoch.
+    fn field_u64(&self, field: u64) -> Result<u64, StateProofError> {
+        Ok(as_u64(self.field(field)?, 0))
+    }
+
+    fn balance(&self) -> Result<u64, StateProofError> {
+        let chunk = self.proven(balance_chunk_index(self.balances_len, self.index))?;
+        Ok(as_u64(chunk, (self.index % BALANCES_PER_CHUNK) * 8))
+    }
+}
+
+/// Reads the little-endian `u64` at `offset` in `chunk`.
+fn as_u64(chunk: Hash256, offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&chunk.as_bytes()[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Returns the length proven at `index` by `partial`, without checking the proof.
+///
+/// This is only for choosing which nodes to request next; the lengths must be proven again by
+/// `verify_balance_proof`.
+pub fn unverified_length(partial: &SerializedPartial, index: u64) -> Option<usize> {
+    let position = partial.indices.iter().position(|&i| i == index)?;
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&partial.chunks.get(position)?.as_bytes()[0..8]);
+    Some(u64::from_le_bytes(bytes) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestingBeaconStateBuilder;
+    use merkle_proof::{BundleError, ProofBundle, ProofLink};
+
+    fn state() -> (BeaconState<MinimalEthSpec>, Vec<Keypair>) {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(10, &spec);
+        let (mut state, keypairs) = builder.build();
+        for (i, balance) in state.balances.iter_mut().enumerate() {
+            *balance += i as u64;
+        }
+        (state, keypairs)
+    }
+
+    #[test]
+    fn nodes_match_tree_hash() {
+        let (state, _) = state();
+        let registry = state_field_index(VALIDATOR_REGISTRY_FIELD);
+        let balances = state_field_index(BALANCES_FIELD);
+        let partial = state.prove(&[1, registry, balances]).unwrap();
+
+        assert_eq!(partial.chunks[0], state.canonical_root());
+        assert_eq!(partial.chunks[1], root(&state.validator_registry));
+        assert_eq!(partial.chunks[2], root(&state.balances));
+    }
+
+    #[test]
+    fn balance_proof_verifies() {
+        let (state, keypairs) = state();
+        let state_root = state.canonical_root();
+
+        for &index in &[0, 5, 9] {
+            let partial = state.balance_proof(index).unwrap();
+            assert_eq!(
+                verify_balance_proof(&partial, state_root, index, &keypairs[index].pk),
+                Ok(ProvenBalance {
+                    effective_balance: state.validator_registry[index].effective_balance,
+                    balance: state.balances[index],
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn validator_status_proof_verifies() {
+        let (mut state, keypairs) = state();
+        state.validator_registry[5].slashed = true;
+        state.validator_registry[5].exit_epoch = Epoch::new(42);
+        let state_root = state.canonical_root();
+
+        for &index in &[0, 5] {
+            let validator = &state.validator_registry[index];
+            let partial = state.validator_status_proof(index).unwrap();
+            assert_eq!(
+                verify_validator_status_proof(&partial, state_root, index, &keypairs[index].pk),
+                Ok(ProvenValidatorStatus {
+                    activation_epoch: validator.activation_epoch,
+                    exit_epoch: validator.exit_epoch,
+                    slashed: validator.slashed,
+                    effective_balance: validator.effective_balance,
+                    balance: state.balances[index],
+                })
+            );
+        }
+
+        // A balance proof does not cover the epochs or the slashed flag.
+        let partial = state.balance_proof(5).unwrap();
+        match verify_validator_status_proof(&partial, state_root, 5, &keypairs[5].pk) {
+            Err(StateProofError::Unproven(_)) => {}
+            other => panic!("expected Unproven, got {:?}", other),
+        }
+        assert_eq!(
+            state.validator_status_proof(10),
+            Err(Error::UnknownValidator)
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_claims() {
+        let (state, keypairs) = state();
+        let state_root = state.canonical_root();
+        let partial = state.balance_proof(5).unwrap();
+
+        assert_eq!(
+            verify_balance_proof(&partial, state_root, 5, &keypairs[4].pk),
+            Err(StateProofError::PubkeyMismatch(5))
+        );
+        assert_eq!(
+            verify_balance_proof(&partial, state_root, 10, &keypairs[4].pk),
+            Err(StateProofError::UnknownValidator(10))
+        );
+        assert_eq!(
+            verify_balance_proof(&partial, Hash256::zero(), 5, &keypairs[5].pk),
+            Err(StateProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn overlay_attaches_provable_nodes() {
+        let (state, _) = state();
+        let partial = state.balance_proof(5).unwrap();
+        assert_eq!(
+            partial.validate_structure::<BeaconState<MinimalEthSpec>>(),
+            Ok(())
+        );
+
+        let attached = BeaconState::<MinimalEthSpec>::is_attached;
+        let genesis_time = state_field_index(1);
+        let randao_mixes = state_field_index(5);
+        assert!(attached(1));
+        assert!(attached(genesis_time));
+        assert!(attached(balances_length_index()));
+        assert!(attached(balance_chunk_index(1_000, 999)));
+        assert!(attached(latest_block_root_index::<MinimalEthSpec>(
+            Slot::new(70)
+        )));
+        assert!(!attached(0));
+        assert!(!attached(2 * genesis_time));
+        assert!(!attached(2 * randao_mixes));
+        assert!(!attached(2 * balances_length_index()));
+        assert!(!attached(
+            2 * latest_state_root_index::<MinimalEthSpec>(Slot::new(70))
+        ));
+    }
+
+    #[test]
+    fn schema_lists_provable_leaves() {
+        let (state, _) = state();
+        let schema = BeaconState::<MinimalEthSpec>::schema();
+        let indices: Vec<u64> = schema.iter().map(|entry| entry.generalized_index).collect();
+
+        let history_len = <MinimalEthSpec as EthSpec>::SlotsPerHistoricalRoot::to_usize();
+        assert_eq!(schema.len(), 23 + 2 + 2 * history_len);
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let partial = state.prove(&indices).unwrap();
+        assert_eq!(
+            partial.validate_structure::<BeaconState<MinimalEthSpec>>(),
+            Ok(())
+        );
+
+        let value = |path: &str| {
+            let position = schema.iter().position(|entry| entry.path == path).unwrap();
+            let entry = &schema[position];
+            partial.chunks[position].as_bytes()[entry.offset..entry.offset + entry.size].to_vec()
+        };
+        assert_eq!(value("genesis_time"), state.genesis_time.to_le_bytes());
+        assert_eq!(
+            value("balances.len"),
+            (state.balances.len() as u64).to_le_bytes()
+        );
+        assert_eq!(
+            value("latest_block_roots.3"),
+            state.latest_block_roots[3].as_bytes()
+        );
+        assert_eq!(value("finalized_root"), state.finalized_root.as_bytes());
+    }
+
+    #[test]
+    fn bundle_proves_past_balances() {
+        let (past, keypairs) = state();
+        let past_root = past.canonical_root();
+        let (mut state, _) = state();
+        state.slot = past.slot + 3;
+        state.set_state_root(past.slot, past_root).unwrap();
+
+        let index = latest_state_root_index::<MinimalEthSpec>(past.slot);
+        let partial = state.prove(&[index]).unwrap();
+        assert_eq!(
+            partial.validate_structure::<BeaconState<MinimalEthSpec>>(),
+            Ok(())
+        );
+        let bundle = ProofBundle {
+            partial,
+            links: vec![ProofLink {
+                index,
+                root: past_root,
+                partial: past.balance_proof(5).unwrap(),
+            }],
+        };
+
+        let verifications = bundle.verify(state.canonical_root()).unwrap();
+        assert_eq!(
+            verify_balance_proof(&bundle.links[0].partial, past_root, 5, &keypairs[5].pk),
+            Ok(ProvenBalance {
+                effective_balance: past.validator_registry[5].effective_balance,
+                balance: past.balances[5],
+            })
+        );
+        assert_eq!(verifications.len(), 2);
+
+        let mut wrong_slot = bundle;
+        wrong_slot.links[0].index = latest_state_root_index::<MinimalEthSpec>(past.slot + 1);
+        assert_eq!(
+            wrong_slot.verify(state.canonical_root()),
+            Err(BundleError::UnprovenRoot { partial: 1 })
+        );
+    }
+
+    #[test]
+    fn refuses_unsupported_indices() {
+        let (state, _) = state();
+        let randao_mixes = state_field_index(5);
+
+        assert_eq!(
+            state.prove(&[2 * randao_mixes]),
+            Err(Error::UnsupportedProofIndex(2 * randao_mixes))
+        );
+        assert_eq!(state.prove(&[0]), Err(Error::UnsupportedProofIndex(0)));
+    }
+}
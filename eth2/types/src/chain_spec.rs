@@ -1,7 +1,6 @@
 use crate::*;
 use int_to_bytes::int_to_bytes4;
 use serde_derive::{Deserialize, Serialize};
-use test_utils::{u8_from_hex_str, u8_to_hex_str};
 
 /// Each of the BLS signature domains.
 ///
@@ -52,7 +51,7 @@ pub struct ChainSpec {
     #[serde(skip_serializing)]
     pub far_future_epoch: Epoch,
     pub zero_hash: Hash256,
-    #[serde(deserialize_with = "u8_from_hex_str", serialize_with = "u8_to_hex_str")]
+    #[serde(with = "serde_hex::u8_hex")]
     pub bls_withdrawal_prefix_byte: u8,
 
     /*
@@ -1,6 +1,7 @@
 use crate::test_utils::TestRandom;
 use crate::{Epoch, Hash256};
 
+use compare_fields_derive::CompareFields;
 use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use test_random_derive::TestRandom;
@@ -23,6 +24,7 @@ use tree_hash_derive::{CachedTreeHash, TreeHash};
     TreeHash,
     CachedTreeHash,
     TestRandom,
+    CompareFields,
 )]
 pub struct Crosslink {
     pub epoch: Epoch,
@@ -1,6 +1,7 @@
 use super::Hash256;
 use crate::test_utils::TestRandom;
 
+use compare_fields_derive::CompareFields;
 use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use test_random_derive::TestRandom;
@@ -21,6 +22,7 @@ use tree_hash_derive::{CachedTreeHash, TreeHash};
     TreeHash,
     CachedTreeHash,
     TestRandom,
+    CompareFields,
 )]
 pub struct Eth1Data {
     pub deposit_root: Hash256,
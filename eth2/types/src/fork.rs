@@ -1,8 +1,6 @@
-use crate::{
-    test_utils::{fork_from_hex_str, TestRandom},
-    Epoch,
-};
+use crate::{test_utils::TestRandom, Epoch};
 
+use compare_fields_derive::CompareFields;
 use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use test_random_derive::TestRandom;
@@ -23,11 +21,12 @@ use tree_hash_derive::{CachedTreeHash, TreeHash};
     TreeHash,
     CachedTreeHash,
     TestRandom,
+    CompareFields,
 )]
 pub struct Fork {
-    #[serde(deserialize_with = "fork_from_hex_str")]
+    #[serde(with = "serde_hex::fixed_bytes_hex")]
     pub previous_version: [u8; 4],
-    #[serde(deserialize_with = "fork_from_hex_str")]
+    #[serde(with = "serde_hex::fixed_bytes_hex")]
     pub current_version: [u8; 4],
     pub epoch: Epoch,
 }
@@ -96,4 +95,18 @@ mod tests {
         assert_eq!(fork.get_fork_version(epoch), current_version);
         assert_eq!(fork.get_fork_version(epoch + 1), current_version);
     }
+
+    #[test]
+    fn serde_hex_versions() {
+        let fork = Fork {
+            previous_version: [0, 0, 0, 1],
+            current_version: [0, 0, 0, 2],
+            epoch: Epoch::new(3),
+        };
+
+        let json = serde_json::to_value(&fork).unwrap();
+        assert_eq!(json["previous_version"], "0x00000001");
+        assert_eq!(json["current_version"], "0x00000002");
+        assert_eq!(serde_json::from_value::<Fork>(json).unwrap(), fork);
+    }
 }
@@ -0,0 +1,64 @@
+use crate::{test_utils::TestRandom, Hash256};
+
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use test_random_derive::TestRandom;
+use tree_hash::TreeHash;
+use tree_hash_derive::TreeHash;
+
+/// The first four bytes of the root of a `ForkData`, identifying both a chain and a fork of it.
+pub type ForkDigest = [u8; 4];
+
+/// The fork and chain identity that peers must share in order to communicate.
+///
+/// Spec v0.11.1, with the genesis block root standing in for `genesis_validators_root`.
+#[derive(
+    Debug, Clone, PartialEq, Default, Serialize, Deserialize, Encode, Decode, TreeHash, TestRandom,
+)]
+pub struct ForkData {
+    #[serde(with = "serde_hex::fixed_bytes_hex")]
+    pub current_version: [u8; 4],
+    pub genesis_root: Hash256,
+}
+
+impl ForkData {
+    /// Returns the digest used to separate gossip topics and to reject peers on other forks.
+    ///
+    /// Spec v0.11.1
+    pub fn fork_digest(&self) -> ForkDigest {
+        let mut digest = [0; 4];
+        digest.copy_from_slice(&self.tree_hash_root()[0..4]);
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ssz_tests!(ForkData);
+
+    #[test]
+    fn fork_digest_depends_on_version_and_genesis() {
+        let fork_data = ForkData {
+            current_version: [0; 4],
+            genesis_root: Hash256::from_low_u64_le(1),
+        };
+
+        let other_version = ForkData {
+            current_version: [1, 0, 0, 0],
+            ..fork_data.clone()
+        };
+        let other_genesis = ForkData {
+            genesis_root: Hash256::from_low_u64_le(2),
+            ..fork_data.clone()
+        };
+
+        assert_ne!(fork_data.fork_digest(), other_version.fork_digest());
+        assert_ne!(fork_data.fork_digest(), other_genesis.fork_digest());
+        assert_eq!(
+            fork_data.fork_digest()[..],
+            fork_data.tree_hash_root()[0..4]
+        );
+    }
+}
@@ -19,6 +19,7 @@ pub mod deposit;
 pub mod deposit_data;
 pub mod eth1_data;
 pub mod fork;
+pub mod fork_data;
 pub mod free_attestation;
 pub mod historical_batch;
 pub mod indexed_attestation;
@@ -52,6 +53,7 @@ pub use crate::deposit::Deposit;
 pub use crate::deposit_data::DepositData;
 pub use crate::eth1_data::Eth1Data;
 pub use crate::fork::Fork;
+pub use crate::fork_data::{ForkData, ForkDigest};
 pub use crate::free_attestation::FreeAttestation;
 pub use crate::historical_batch::HistoricalBatch;
 pub use crate::indexed_attestation::IndexedAttestation;
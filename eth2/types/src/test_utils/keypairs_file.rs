@@ -30,7 +30,7 @@ impl KeypairsFile for Vec<Keypair> {
             let mut buf = Vec::with_capacity(BATCH_BYTE_LEN);
 
             for keypair in keypair_batch {
-                buf.append(&mut keypair.sk.as_raw().as_bytes());
+                buf.extend_from_slice(&keypair.sk.as_bytes());
                 buf.append(&mut keypair.pk.clone().as_uncompressed_bytes());
             }
 
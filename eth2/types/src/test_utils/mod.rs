@@ -3,7 +3,6 @@ mod macros;
 mod builders;
 mod generate_deterministic_keypairs;
 mod keypairs_file;
-mod serde_utils;
 mod test_random;
 
 pub use builders::*;
@@ -14,5 +13,4 @@ pub use rand::{
     RngCore,
     {prng::XorShiftRng, SeedableRng},
 };
-pub use serde_utils::{fork_from_hex_str, graffiti_from_hex_str, u8_from_hex_str, u8_to_hex_str};
 pub use test_random::TestRandom;
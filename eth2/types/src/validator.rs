@@ -1,5 +1,6 @@
 use crate::{test_utils::TestRandom, Epoch, Hash256, PublicKey};
 
+use compare_fields_derive::CompareFields;
 use serde_derive::{Deserialize, Serialize};
 use ssz_derive::{Decode, Encode};
 use test_random_derive::TestRandom;
@@ -19,6 +20,7 @@ use tree_hash_derive::{CachedTreeHash, TreeHash};
     TestRandom,
     TreeHash,
     CachedTreeHash,
+    CompareFields,
 )]
 pub struct Validator {
     pub pubkey: PublicKey,
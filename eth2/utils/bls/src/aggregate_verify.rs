@@ -0,0 +1,122 @@
+use super::{AggregatePublicKey, AggregateSignature, PublicKey};
+use std::collections::HashSet;
+
+impl AggregatePublicKey {
+    /// Returns the aggregate of `public_keys`.
+    pub fn aggregate(public_keys: &[&PublicKey]) -> Self {
+        let mut aggregate = Self::new();
+        for public_key in public_keys {
+            aggregate.add(public_key);
+        }
+        aggregate
+    }
+}
+
+impl AggregateSignature {
+    /// Verifies this signature as the aggregate of the signatures of every key in `public_keys`
+    /// over the same `msg`.
+    ///
+    /// Returns `false` if `public_keys` is empty.
+    ///
+    /// `FastAggregateVerify` of the IETF BLS signature draft.
+    pub fn fast_aggregate_verify(
+        &self,
+        msg: &[u8],
+        domain: u64,
+        public_keys: &[&PublicKey],
+    ) -> bool {
+        if public_keys.is_empty() {
+            return false;
+        }
+
+        self.verify(msg, domain, &AggregatePublicKey::aggregate(public_keys))
+    }
+
+    /// Verifies this signature as the aggregate of the signature of each key in `public_keys` over
+    /// the 32 byte message at the same index in `messages`.
+    ///
+    /// Returns `false` if there are no messages, if the number of messages and keys differ, or if
+    /// any message is repeated, since a repeated message allows rogue key attacks.
+    ///
+    /// `AggregateVerify` of the IETF BLS signature draft.
+    pub fn aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        domain: u64,
+        public_keys: &[&PublicKey],
+    ) -> bool {
+        if messages.is_empty() || messages.len() != public_keys.len() {
+            return false;
+        }
+
+        let mut distinct = HashSet::with_capacity(messages.len());
+        if !messages.iter().all(|message| distinct.insert(message)) {
+            return false;
+        }
+
+        let public_keys: Vec<AggregatePublicKey> = public_keys
+            .iter()
+            .map(|public_key| AggregatePublicKey::aggregate(&[*public_key]))
+            .collect();
+        let public_keys: Vec<&AggregatePublicKey> = public_keys.iter().collect();
+
+        self.verify_multiple(messages, domain, &public_keys)
+    }
+}
+
+#[cfg(all(test, not(feature = "fake_crypto")))]
+mod tests {
+    use super::*;
+    use crate::{Keypair, Signature};
+
+    #[test]
+    fn fast_aggregate_verify_checks_all_keys() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+        let message = [1; 32];
+
+        let mut signature = AggregateSignature::new();
+        for keypair in &keypairs {
+            signature.add(&Signature::new(&message, 42, &keypair.sk));
+        }
+        let keys: Vec<&PublicKey> = keypairs.iter().map(|keypair| &keypair.pk).collect();
+
+        assert!(signature.fast_aggregate_verify(&message, 42, &keys));
+        assert!(!signature.fast_aggregate_verify(&message, 42, &keys[..2]));
+        assert!(!signature.fast_aggregate_verify(&message, 42, &[]));
+        assert!(!signature.fast_aggregate_verify(&[2; 32], 42, &keys));
+    }
+
+    #[test]
+    fn aggregate_verify_pairs_keys_with_messages() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+        let messages: Vec<Vec<u8>> = (0..3).map(|i| vec![i; 32]).collect();
+
+        let mut signature = AggregateSignature::new();
+        for (keypair, message) in keypairs.iter().zip(&messages) {
+            signature.add(&Signature::new(message, 42, &keypair.sk));
+        }
+        let keys: Vec<&PublicKey> = keypairs.iter().map(|keypair| &keypair.pk).collect();
+        let messages: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+
+        assert!(signature.aggregate_verify(&messages, 42, &keys));
+        assert!(!signature.aggregate_verify(&messages, 42, &[keys[1], keys[0], keys[2]]));
+        assert!(!signature.aggregate_verify(&messages[..2], 42, &keys));
+        assert!(!signature.aggregate_verify(&[], 42, &[]));
+    }
+
+    #[test]
+    fn aggregate_verify_rejects_repeated_messages() {
+        let keypairs: Vec<Keypair> = (0..2).map(|_| Keypair::random()).collect();
+        let message = [1; 32];
+
+        let mut signature = AggregateSignature::new();
+        for keypair in &keypairs {
+            signature.add(&Signature::new(&message, 42, &keypair.sk));
+        }
+        let keys: Vec<&PublicKey> = keypairs.iter().map(|keypair| &keypair.pk).collect();
+
+        // Valid as a `fast_aggregate_verify`, but not as an `aggregate_verify`.
+        assert!(signature.fast_aggregate_verify(&message, 42, &keys));
+        assert!(!signature.aggregate_verify(&[&message[..], &message[..]], 42, &keys));
+    }
+}
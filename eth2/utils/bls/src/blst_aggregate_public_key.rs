@@ -0,0 +1,61 @@
+use super::{PublicKey, BLS_PUBLIC_KEY_BYTE_SIZE};
+use blst::{
+    blst_p1, blst_p1_add_or_double_affine, blst_p1_affine, blst_p1_affine_compress, blst_p1_mult,
+    blst_p1_to_affine,
+};
+
+/// A BLS aggregate public key, backed by `blst`.
+///
+/// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
+/// serialization).
+#[derive(Debug, Clone, Default)]
+pub struct BlstAggregatePublicKey(blst_p1);
+
+impl BlstAggregatePublicKey {
+    /// Instantiate a new aggregate public key at the point at infinity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, public_key: &PublicKey) {
+        let mut sum = blst_p1::default();
+        unsafe { blst_p1_add_or_double_affine(&mut sum, &self.0, public_key.point()) };
+        self.0 = sum;
+    }
+
+    /// Returns the aggregate as an affine point.
+    pub fn point(&self) -> blst_p1_affine {
+        let mut affine = blst_p1_affine::default();
+        unsafe { blst_p1_to_affine(&mut affine, &self.0) };
+        affine
+    }
+
+    /// Returns the aggregate multiplied by the little-endian `scalar`, as an affine point.
+    pub(crate) fn scaled(&self, scalar: &[u8]) -> blst_p1_affine {
+        let mut product = blst_p1::default();
+        let mut affine = blst_p1_affine::default();
+        unsafe {
+            blst_p1_mult(&mut product, &self.0, scalar.as_ptr(), scalar.len() * 8);
+            blst_p1_to_affine(&mut affine, &product);
+        }
+        affine
+    }
+
+    /// Returns itself, since the point has no byte-oriented API of its own.
+    pub fn as_raw(&self) -> &Self {
+        self
+    }
+
+    /// Returns the aggregate as compressed bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; BLS_PUBLIC_KEY_BYTE_SIZE];
+        unsafe { blst_p1_affine_compress(bytes.as_mut_ptr(), &self.point()) };
+        bytes
+    }
+
+    /// Return a hex string representation of this key's bytes.
+    #[cfg(test)]
+    pub fn as_hex_string(&self) -> String {
+        serde_hex::encode(self.as_bytes())
+    }
+}
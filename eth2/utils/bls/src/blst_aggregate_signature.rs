@@ -0,0 +1,303 @@
+use super::*;
+use crate::blst_signature::{
+    hash_to_g2, p2_from_bytes, p2_to_affine, p2_to_bytes, verify_pairings,
+};
+use crate::signature_set::SignatureSet;
+use blst::{
+    blst_p2, blst_p2_add_or_double, blst_p2_add_or_double_affine, blst_p2_from_affine, blst_p2_mult,
+};
+use cached_tree_hash::cached_tree_hash_ssz_encoding_as_vector;
+use rand::Rng;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_hex::{encode as hex_encode, HexVisitor};
+use ssz::{Decode, DecodeError};
+use tree_hash::tree_hash_ssz_encoding_as_vector;
+
+/// A BLS aggregate signature, backed by `blst`.
+///
+/// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
+/// serialization).
+#[derive(Debug, Clone, Default)]
+pub struct BlstAggregateSignature {
+    aggregate_signature: blst_p2,
+    is_empty: bool,
+}
+
+impl BlstAggregateSignature {
+    /// Instantiate a new AggregateSignature.
+    ///
+    /// is_empty is false
+    /// AggregateSiganture is point at infinity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (aggregate) a signature to the `AggregateSignature`.
+    pub fn add(&mut self, signature: &Signature) {
+        if !self.is_empty {
+            let mut sum = blst_p2::default();
+            unsafe {
+                blst_p2_add_or_double_affine(&mut sum, &self.aggregate_signature, signature.point())
+            };
+            self.aggregate_signature = sum;
+        }
+    }
+
+    /// Add (aggregate) another `AggregateSignature`.
+    pub fn add_aggregate(&mut self, agg_signature: &BlstAggregateSignature) {
+        let mut sum = blst_p2::default();
+        unsafe {
+            blst_p2_add_or_double(
+                &mut sum,
+                &self.aggregate_signature,
+                &agg_signature.aggregate_signature,
+            )
+        };
+        self.aggregate_signature = sum;
+    }
+
+    /// Verify the `AggregateSignature` against an `AggregatePublicKey`.
+    ///
+    /// Only returns `true` if the set of keys in the `AggregatePublicKey` match the set of keys
+    /// that signed the `AggregateSignature`.
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        domain: u64,
+        aggregate_public_key: &AggregatePublicKey,
+    ) -> bool {
+        if self.is_empty {
+            return false;
+        }
+        verify_pairings(
+            &[(aggregate_public_key.point(), hash_to_g2(msg, domain))],
+            &p2_to_affine(&self.aggregate_signature),
+        )
+    }
+
+    /// Verify this AggregateSignature against multiple AggregatePublickeys with multiple Messages.
+    ///
+    ///  All PublicKeys related to a Message should be aggregated into one AggregatePublicKey.
+    ///  Each AggregatePublicKey has a 1:1 ratio with a 32 byte Message.
+    pub fn verify_multiple(
+        &self,
+        messages: &[&[u8]],
+        domain: u64,
+        aggregate_public_keys: &[&AggregatePublicKey],
+    ) -> bool {
+        if self.is_empty || messages.len() != aggregate_public_keys.len() {
+            return false;
+        }
+
+        let pairs: Vec<_> = aggregate_public_keys
+            .iter()
+            .zip(messages)
+            .map(|(public_key, message)| (public_key.point(), hash_to_g2(message, domain)))
+            .collect();
+
+        verify_pairings(&pairs, &p2_to_affine(&self.aggregate_signature))
+    }
+
+    /// Return AggregateSiganture as bytes
+    pub fn as_bytes(&self) -> Vec<u8> {
+        if self.is_empty {
+            return vec![0; BLS_AGG_SIG_BYTE_SIZE];
+        }
+        p2_to_bytes(&p2_to_affine(&self.aggregate_signature))
+    }
+
+    /// Convert bytes to AggregateSiganture
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(Self::empty_signature());
+        }
+
+        let affine = p2_from_bytes(bytes).ok_or_else(|| {
+            DecodeError::BytesInvalid(format!("Invalid AggregateSignature bytes: {:?}", bytes))
+        })?;
+        let mut aggregate_signature = blst_p2::default();
+        unsafe { blst_p2_from_affine(&mut aggregate_signature, &affine) };
+
+        Ok(Self {
+            aggregate_signature,
+            is_empty: false,
+        })
+    }
+
+    /// Returns if the AggregateSiganture `is_empty`
+    pub fn is_empty(&self) -> bool {
+        self.is_empty
+    }
+
+    /// Creates a new AggregateSignature
+    ///
+    /// aggregate_signature set to the point infinity
+    /// is_empty set to true
+    pub fn empty_signature() -> Self {
+        Self {
+            aggregate_signature: blst_p2::default(),
+            is_empty: true,
+        }
+    }
+
+    /// Return a hex string representation of the bytes of this signature.
+    #[cfg(test)]
+    pub fn as_hex_string(&self) -> String {
+        hex_encode(self.as_bytes())
+    }
+}
+
+/// Verifies every set at once by checking a random linear combination of them.
+///
+/// Each set is weighted by a random, non-zero 64-bit scalar so that invalid signatures cannot
+/// cancel each other out.
+pub(crate) fn verify_signature_sets(sets: &[SignatureSet]) -> bool {
+    let mut rng = rand::thread_rng();
+    let mut signature_sum = blst_p2::default();
+    let mut pairs = Vec::with_capacity(sets.len());
+
+    for set in sets {
+        if set.signature.is_empty || set.signing_keys.is_empty() {
+            return false;
+        }
+
+        let mut scalar: u64 = 0;
+        while scalar == 0 {
+            scalar = rng.gen();
+        }
+        let scalar = scalar.to_le_bytes();
+
+        let public_key = AggregatePublicKey::aggregate(&set.signing_keys);
+
+        let mut scaled = blst_p2::default();
+        let mut sum = blst_p2::default();
+        unsafe {
+            blst_p2_mult(
+                &mut scaled,
+                &set.signature.aggregate_signature,
+                scalar.as_ptr(),
+                scalar.len() * 8,
+            );
+            blst_p2_add_or_double(&mut sum, &signature_sum, &scaled);
+        }
+        signature_sum = sum;
+
+        pairs.push((
+            public_key.scaled(&scalar),
+            hash_to_g2(&set.message, set.domain),
+        ));
+    }
+
+    verify_pairings(&pairs, &p2_to_affine(&signature_sum))
+}
+
+impl PartialEq for BlstAggregateSignature {
+    /// Compares the encoded points, since equal points may have different projective coordinates.
+    fn eq(&self, other: &BlstAggregateSignature) -> bool {
+        self.is_empty == other.is_empty && self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for BlstAggregateSignature {}
+
+impl_ssz!(
+    BlstAggregateSignature,
+    BLS_AGG_SIG_BYTE_SIZE,
+    "AggregateSignature"
+);
+
+impl Serialize for BlstAggregateSignature {
+    /// Serde serialization is compliant the Ethereum YAML test format.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(self.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for BlstAggregateSignature {
+    /// Serde serialization is compliant the Ethereum YAML test format.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_str(HexVisitor)?;
+        let agg_sig = BlstAggregateSignature::from_ssz_bytes(&bytes)
+            .map_err(|e| serde::de::Error::custom(format!("invalid ssz ({:?})", e)))?;
+
+        Ok(agg_sig)
+    }
+}
+
+tree_hash_ssz_encoding_as_vector!(BlstAggregateSignature);
+cached_tree_hash_ssz_encoding_as_vector!(BlstAggregateSignature, 96);
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Keypair, Signature};
+    use super::*;
+    use milagro_bls::{
+        AggregatePublicKey as MilagroAggregatePublicKey,
+        AggregateSignature as MilagroAggregateSignature, Signature as MilagroSignature,
+    };
+    use ssz::Encode;
+
+    #[test]
+    pub fn test_ssz_round_trip() {
+        let keypair = Keypair::random();
+
+        let mut original = BlstAggregateSignature::new();
+        original.add(&Signature::new(&[42, 42], 0, &keypair.sk));
+
+        let bytes = original.as_ssz_bytes();
+        let decoded = BlstAggregateSignature::from_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    pub fn test_aggregate_matches_milagro() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+
+        let mut blst_sig = BlstAggregateSignature::new();
+        let mut blst_pk = AggregatePublicKey::new();
+        let mut milagro_sig = MilagroAggregateSignature::new();
+        let mut milagro_pk = MilagroAggregatePublicKey::new();
+        for keypair in &keypairs {
+            blst_sig.add(&Signature::new(&[42, 42], 5, &keypair.sk));
+            blst_pk.add(&keypair.pk);
+            milagro_sig.add(&MilagroSignature::new(&[42, 42], 5, &keypair.sk.to_raw()));
+            milagro_pk.add(&milagro_bls::PublicKey::from_secret_key(
+                &keypair.sk.to_raw(),
+            ));
+        }
+
+        assert_eq!(blst_sig.as_bytes(), milagro_sig.as_bytes());
+        assert_eq!(blst_pk.as_bytes(), milagro_pk.as_bytes());
+        assert!(blst_sig.verify(&[42, 42], 5, &blst_pk));
+        assert!(!blst_sig.verify(&[42, 42], 6, &blst_pk));
+
+        let from_milagro = BlstAggregateSignature::from_bytes(&milagro_sig.as_bytes()).unwrap();
+        assert!(from_milagro.verify(&[42, 42], 5, &blst_pk));
+    }
+
+    #[test]
+    pub fn test_verify_multiple() {
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::random()).collect();
+        let messages: Vec<&[u8]> = vec![&[1; 32], &[2; 32]];
+
+        let mut signature = BlstAggregateSignature::new();
+        let mut public_keys = vec![AggregatePublicKey::new(), AggregatePublicKey::new()];
+        for (i, keypair) in keypairs.iter().enumerate() {
+            signature.add(&Signature::new(messages[i % 2], 9, &keypair.sk));
+            public_keys[i % 2].add(&keypair.pk);
+        }
+        let public_keys: Vec<&AggregatePublicKey> = public_keys.iter().collect();
+
+        assert!(signature.verify_multiple(&messages, 9, &public_keys));
+        assert!(!signature.verify_multiple(&[messages[1], messages[0]], 9, &public_keys));
+        assert!(!signature.verify_multiple(&messages[..1], 9, &public_keys[..1]));
+    }
+}
@@ -0,0 +1,240 @@
+use super::{SecretKey, BLS_PUBLIC_KEY_BYTE_SIZE};
+use crate::blst_signature::secret_key_to_scalar;
+use blst::{
+    blst_p1, blst_p1_affine, blst_p1_affine_compress, blst_p1_affine_in_g1, blst_p1_affine_is_inf,
+    blst_p1_affine_serialize, blst_p1_deserialize, blst_p1_to_affine, blst_p1_uncompress,
+    blst_sk_to_pk_in_g1, BLST_ERROR,
+};
+use cached_tree_hash::cached_tree_hash_ssz_encoding_as_vector;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_hex::{encode as hex_encode, HexVisitor};
+use ssz::{Decode, DecodeError, Encode};
+use std::default;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use tree_hash::tree_hash_ssz_encoding_as_vector;
+
+/// The length of a public key as uncompressed (x, y) bytes.
+const UNCOMPRESSED_BYTE_SIZE: usize = 96;
+
+/// A single BLS public key, backed by `blst`.
+///
+/// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
+/// serialization).
+#[derive(Clone, Eq)]
+pub struct BlstPublicKey(blst_p1_affine);
+
+impl BlstPublicKey {
+    pub fn from_secret_key(secret_key: &SecretKey) -> Self {
+        let scalar = secret_key_to_scalar(secret_key);
+        let mut point = blst_p1::default();
+        let mut affine = blst_p1_affine::default();
+        unsafe {
+            blst_sk_to_pk_in_g1(&mut point, &scalar);
+            blst_p1_to_affine(&mut affine, &point);
+        }
+        BlstPublicKey(affine)
+    }
+
+    /// Returns the underlying point.
+    pub fn point(&self) -> &blst_p1_affine {
+        &self.0
+    }
+
+    /// Returns itself, since the point has no byte-oriented API of its own.
+    pub fn as_raw(&self) -> &Self {
+        self
+    }
+
+    /// Returns the underlying point as compressed bytes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; BLS_PUBLIC_KEY_BYTE_SIZE];
+        unsafe { blst_p1_affine_compress(bytes.as_mut_ptr(), &self.0) };
+        bytes
+    }
+
+    /// Converts compressed bytes to BlstPublicKey
+    ///
+    /// The point at infinity and points outside of G1 are rejected, since `blst` does not check
+    /// either when uncompressing.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut affine = blst_p1_affine::default();
+        let result = if bytes.len() == BLS_PUBLIC_KEY_BYTE_SIZE {
+            unsafe { blst_p1_uncompress(&mut affine, bytes.as_ptr()) }
+        } else {
+            BLST_ERROR::BLST_BAD_ENCODING
+        };
+
+        if result == BLST_ERROR::BLST_SUCCESS
+            && unsafe { !blst_p1_affine_is_inf(&affine) && blst_p1_affine_in_g1(&affine) }
+        {
+            Ok(BlstPublicKey(affine))
+        } else {
+            Err(DecodeError::BytesInvalid(format!(
+                "Invalid PublicKey bytes: {:?}",
+                bytes
+            )))
+        }
+    }
+
+    /// Returns the BlstPublicKey as (x, y) bytes
+    pub fn as_uncompressed_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; UNCOMPRESSED_BYTE_SIZE];
+        unsafe { blst_p1_affine_serialize(bytes.as_mut_ptr(), &self.0) };
+        bytes
+    }
+
+    /// Converts (x, y) bytes to BlstPublicKey
+    ///
+    /// Unlike `from_bytes`, the point is not checked to be in G1, so the bytes must be trusted,
+    /// e.g., as produced by `as_uncompressed_bytes`.
+    pub fn from_uncompressed_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut affine = blst_p1_affine::default();
+        let result = if bytes.len() == UNCOMPRESSED_BYTE_SIZE {
+            unsafe { blst_p1_deserialize(&mut affine, bytes.as_ptr()) }
+        } else {
+            BLST_ERROR::BLST_BAD_ENCODING
+        };
+
+        if result == BLST_ERROR::BLST_SUCCESS {
+            Ok(BlstPublicKey(affine))
+        } else {
+            Err(DecodeError::BytesInvalid(
+                "Invalid PublicKey uncompressed bytes.".to_string(),
+            ))
+        }
+    }
+
+    /// Returns the last 6 bytes of the SSZ encoding of the public key, as a hex string.
+    ///
+    /// Useful for providing a short identifier to the user.
+    pub fn concatenated_hex_id(&self) -> String {
+        self.as_hex_string()[0..6].to_string()
+    }
+
+    /// Returns the point as a hex string of the SSZ encoding.
+    ///
+    /// Note: the string is prefixed with `0x`.
+    pub fn as_hex_string(&self) -> String {
+        hex_encode(self.as_ssz_bytes())
+    }
+}
+
+impl fmt::Display for BlstPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.concatenated_hex_id())
+    }
+}
+
+impl fmt::Debug for BlstPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_hex_string())
+    }
+}
+
+impl default::Default for BlstPublicKey {
+    fn default() -> Self {
+        let secret_key = SecretKey::random();
+        BlstPublicKey::from_secret_key(&secret_key)
+    }
+}
+
+impl_ssz!(BlstPublicKey, BLS_PUBLIC_KEY_BYTE_SIZE, "PublicKey");
+
+impl Serialize for BlstPublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(self.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for BlstPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_str(HexVisitor)?;
+        let pubkey = Self::from_ssz_bytes(&bytes[..])
+            .map_err(|e| serde::de::Error::custom(format!("invalid pubkey ({:?})", e)))?;
+        Ok(pubkey)
+    }
+}
+
+tree_hash_ssz_encoding_as_vector!(BlstPublicKey);
+cached_tree_hash_ssz_encoding_as_vector!(BlstPublicKey, 48);
+
+impl PartialEq for BlstPublicKey {
+    fn eq(&self, other: &BlstPublicKey) -> bool {
+        self.as_ssz_bytes() == other.as_ssz_bytes()
+    }
+}
+
+impl Hash for BlstPublicKey {
+    /// Note: this is distinct from consensus serialization, it will produce a different hash.
+    ///
+    /// This method uses the uncompressed bytes, which are much faster to obtain than the
+    /// compressed bytes required for consensus serialization.
+    ///
+    /// Use `ssz::Encode` to obtain the bytes required for consensus hashing.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_uncompressed_bytes().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use milagro_bls::PublicKey as MilagroPublicKey;
+    use ssz::ssz_encode;
+
+    #[test]
+    pub fn test_ssz_round_trip() {
+        let sk = SecretKey::random();
+        let original = BlstPublicKey::from_secret_key(&sk);
+
+        let bytes = ssz_encode(&original);
+        let decoded = BlstPublicKey::from_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    pub fn test_uncompressed_round_trip() {
+        let original = BlstPublicKey::from_secret_key(&SecretKey::random());
+
+        let bytes = original.as_uncompressed_bytes();
+        let decoded = BlstPublicKey::from_uncompressed_bytes(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    pub fn test_rejects_infinity_and_points_outside_g1() {
+        let mut infinity = vec![0; BLS_PUBLIC_KEY_BYTE_SIZE];
+        infinity[0] = 0xc0;
+        assert!(BlstPublicKey::from_bytes(&infinity).is_err());
+
+        // On the curve, but not in the subgroup of order r.
+        let outside_g1 = hex::decode(
+            "8123456789abcdef0123456789abcdef0123456789abcdef\
+             0123456789abcdef0123456789abcdef0123456789abcdef",
+        )
+        .unwrap();
+        assert!(BlstPublicKey::from_bytes(&outside_g1).is_err());
+    }
+
+    #[test]
+    pub fn test_matches_milagro() {
+        let sk = SecretKey::random();
+        let milagro = MilagroPublicKey::from_secret_key(&sk.to_raw()).as_bytes();
+
+        assert_eq!(BlstPublicKey::from_secret_key(&sk).as_bytes(), milagro);
+        assert_eq!(
+            BlstPublicKey::from_bytes(&milagro).unwrap().as_bytes(),
+            milagro
+        );
+    }
+}
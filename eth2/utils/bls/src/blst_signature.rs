@@ -0,0 +1,333 @@
+use super::{PublicKey, SecretKey, BLS_SIG_BYTE_SIZE};
+use blst::{
+    blst_fp12, blst_fp12_finalverify, blst_fp12_mul, blst_fp12_one, blst_miller_loop,
+    blst_p1_affine, blst_p1_affine_generator, blst_p1_affine_is_inf, blst_p2, blst_p2_affine,
+    blst_p2_affine_compress, blst_p2_affine_in_g2, blst_p2_affine_is_inf, blst_p2_from_affine,
+    blst_p2_to_affine, blst_p2_uncompress, blst_scalar, blst_scalar_from_bendian,
+    blst_sign_pk_in_g1, BLST_ERROR,
+};
+use cached_tree_hash::cached_tree_hash_ssz_encoding_as_vector;
+use hex::encode as hex_encode;
+use milagro_bls::{
+    compress_g2, hash_on_g2, PublicKey as MilagroPublicKey, Signature as MilagroSignature,
+};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_hex::HexVisitor;
+use ssz::{ssz_encode, Decode, DecodeError};
+use tree_hash::tree_hash_ssz_encoding_as_vector;
+
+/// A single BLS signature, backed by `blst`.
+///
+/// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
+/// serialization).
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct BlstSignature {
+    signature: blst_p2_affine,
+    is_empty: bool,
+}
+
+impl BlstSignature {
+    /// Instantiate a new Signature from a message and a SecretKey.
+    pub fn new(msg: &[u8], domain: u64, sk: &SecretKey) -> Self {
+        let mut hash = blst_p2::default();
+        let mut signature = blst_p2::default();
+        unsafe {
+            blst_p2_from_affine(&mut hash, &hash_to_g2(msg, domain));
+            blst_sign_pk_in_g1(&mut signature, &hash, &secret_key_to_scalar(sk));
+        }
+        Self {
+            signature: p2_to_affine(&signature),
+            is_empty: false,
+        }
+    }
+
+    /// Instantiate a new Signature from a message and a SecretKey, where the message has already
+    /// been hashed.
+    ///
+    /// Signs with `milagro_bls`, which provides the mapping from hashed coordinates to a point.
+    pub fn new_hashed(x_real_hashed: &[u8], x_imaginary_hashed: &[u8], sk: &SecretKey) -> Self {
+        let signature =
+            MilagroSignature::new_hashed(x_real_hashed, x_imaginary_hashed, &sk.to_raw());
+        Self::from_bytes(&signature.as_bytes()).expect("milagro_bls produces valid signature bytes")
+    }
+
+    /// Verify the Signature against a PublicKey.
+    pub fn verify(&self, msg: &[u8], domain: u64, pk: &PublicKey) -> bool {
+        if self.is_empty {
+            return false;
+        }
+        verify_pairings(&[(*pk.point(), hash_to_g2(msg, domain))], &self.signature)
+    }
+
+    /// Verify the Signature against a PublicKey, where the message has already been hashed.
+    ///
+    /// Verifies with `milagro_bls`, which provides the mapping from hashed coordinates to a point.
+    pub fn verify_hashed(
+        &self,
+        x_real_hashed: &[u8],
+        x_imaginary_hashed: &[u8],
+        pk: &PublicKey,
+    ) -> bool {
+        match (
+            MilagroSignature::from_bytes(&self.as_bytes()),
+            MilagroPublicKey::from_bytes(&pk.as_bytes()),
+        ) {
+            (Ok(signature), Ok(pk)) => {
+                signature.verify_hashed(x_real_hashed, x_imaginary_hashed, &pk)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns itself, since the point has no byte-oriented API of its own.
+    pub fn as_raw(&self) -> &Self {
+        self
+    }
+
+    /// Returns the underlying point.
+    pub fn point(&self) -> &blst_p2_affine {
+        &self.signature
+    }
+
+    /// Returns a new empty signature.
+    pub fn empty_signature() -> Self {
+        // Set the signature to the point at infinity.
+        let mut empty: Vec<u8> = vec![0; BLS_SIG_BYTE_SIZE];
+        empty[0] += u8::pow(2, 6) + u8::pow(2, 7);
+        Self {
+            signature: p2_uncompress(&empty).expect("infinity is a valid point"),
+            is_empty: true,
+        }
+    }
+
+    // Converts a BLS Signature to bytes
+    pub fn as_bytes(&self) -> Vec<u8> {
+        if self.is_empty {
+            return vec![0; BLS_SIG_BYTE_SIZE];
+        }
+        p2_to_bytes(&self.signature)
+    }
+
+    // Convert bytes to BLS Signature
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(Self::empty_signature());
+        }
+
+        let signature = p2_from_bytes(bytes).ok_or_else(|| {
+            DecodeError::BytesInvalid(format!("Invalid Signature bytes: {:?}", bytes))
+        })?;
+        Ok(Self {
+            signature,
+            is_empty: false,
+        })
+    }
+
+    // Check for empty Signature
+    pub fn is_empty(&self) -> bool {
+        self.is_empty
+    }
+
+    /// Display a signature as a hex string of its bytes.
+    #[cfg(test)]
+    pub fn as_hex_string(&self) -> String {
+        hex_encode(self.as_bytes())
+    }
+}
+
+impl_ssz!(BlstSignature, BLS_SIG_BYTE_SIZE, "Signature");
+
+tree_hash_ssz_encoding_as_vector!(BlstSignature);
+cached_tree_hash_ssz_encoding_as_vector!(BlstSignature, 96);
+
+impl Serialize for BlstSignature {
+    /// Serde serialization is compliant the Ethereum YAML test format.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(ssz_encode(self)))
+    }
+}
+
+impl<'de> Deserialize<'de> for BlstSignature {
+    /// Serde serialization is compliant the Ethereum YAML test format.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_str(HexVisitor)?;
+        let signature = Self::from_ssz_bytes(&bytes[..])
+            .map_err(|e| serde::de::Error::custom(format!("invalid ssz ({:?})", e)))?;
+        Ok(signature)
+    }
+}
+
+/// Hashes `msg` and `domain` to a point on G2.
+///
+/// The hash is computed by `milagro_bls`, since `blst` only implements the later hash-to-curve
+/// standard. This keeps signatures identical between the two backends.
+pub(crate) fn hash_to_g2(msg: &[u8], domain: u64) -> blst_p2_affine {
+    let bytes = compress_g2(&mut hash_on_g2(msg, domain));
+    p2_uncompress(&bytes).expect("milagro_bls produces valid G2 points")
+}
+
+/// Returns `true` if the product of the pairings of each `(public_key, message)` pair is equal to
+/// the pairing of the generator of G1 with `signature`.
+///
+/// Public keys and signatures at infinity contribute the identity.
+pub(crate) fn verify_pairings(
+    pairs: &[(blst_p1_affine, blst_p2_affine)],
+    signature: &blst_p2_affine,
+) -> bool {
+    unsafe {
+        let mut lhs = *blst_fp12_one();
+        for (public_key, message) in pairs {
+            if blst_p1_affine_is_inf(public_key) {
+                continue;
+            }
+            let mut pairing = blst_fp12::default();
+            let mut product = blst_fp12::default();
+            blst_miller_loop(&mut pairing, message, public_key);
+            blst_fp12_mul(&mut product, &lhs, &pairing);
+            lhs = product;
+        }
+
+        let mut rhs = *blst_fp12_one();
+        if !blst_p2_affine_is_inf(signature) {
+            blst_miller_loop(&mut rhs, signature, blst_p1_affine_generator());
+        }
+
+        blst_fp12_finalverify(&lhs, &rhs)
+    }
+}
+
+/// Converts a secret key to a `blst` scalar.
+pub(crate) fn secret_key_to_scalar(sk: &SecretKey) -> blst_scalar {
+    // `milagro_bls` encodes the key as a 48 byte big-endian integer, of which only the last 32
+    // bytes may be non-zero.
+    let bytes = sk.as_bytes();
+    let mut scalar = blst_scalar::default();
+    unsafe { blst_scalar_from_bendian(&mut scalar, bytes[bytes.len() - 32..].as_ptr()) };
+    scalar
+}
+
+pub(crate) fn p2_to_affine(point: &blst_p2) -> blst_p2_affine {
+    let mut affine = blst_p2_affine::default();
+    unsafe { blst_p2_to_affine(&mut affine, point) };
+    affine
+}
+
+pub(crate) fn p2_to_bytes(point: &blst_p2_affine) -> Vec<u8> {
+    let mut bytes = vec![0; BLS_SIG_BYTE_SIZE];
+    unsafe { blst_p2_affine_compress(bytes.as_mut_ptr(), point) };
+    bytes
+}
+
+/// Decodes a compressed signature, rejecting the point at infinity and points outside of G2.
+pub(crate) fn p2_from_bytes(bytes: &[u8]) -> Option<blst_p2_affine> {
+    p2_uncompress(bytes)
+        .filter(|affine| unsafe { !blst_p2_affine_is_inf(affine) && blst_p2_affine_in_g2(affine) })
+}
+
+/// Decodes a compressed point on the curve, without checking that it is in G2. Only for points
+/// which are known to be valid.
+fn p2_uncompress(bytes: &[u8]) -> Option<blst_p2_affine> {
+    if bytes.len() != BLS_SIG_BYTE_SIZE {
+        return None;
+    }
+    let mut affine = blst_p2_affine::default();
+    match unsafe { blst_p2_uncompress(&mut affine, bytes.as_ptr()) } {
+        BLST_ERROR::BLST_SUCCESS => Some(affine),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Keypair;
+    use super::*;
+    use ssz::ssz_encode;
+
+    #[test]
+    pub fn test_ssz_round_trip() {
+        let keypair = Keypair::random();
+
+        let original = BlstSignature::new(&[42, 42], 0, &keypair.sk);
+
+        let bytes = ssz_encode(&original);
+        let decoded = BlstSignature::from_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    pub fn test_empty_signature() {
+        let sig = BlstSignature::empty_signature();
+
+        assert!(sig.is_empty());
+        assert_eq!(sig.as_bytes(), vec![0; BLS_SIG_BYTE_SIZE]);
+        assert!(!sig.verify(&[42, 42], 0, &Keypair::random().pk));
+    }
+
+    #[test]
+    pub fn test_rejects_infinity_and_points_outside_g2() {
+        let mut infinity = vec![0; BLS_SIG_BYTE_SIZE];
+        infinity[0] = 0xc0;
+        assert!(BlstSignature::from_bytes(&infinity).is_err());
+
+        // The point with x = i, which is on the curve but not in the subgroup of order r.
+        let mut outside_g2 = vec![0; BLS_SIG_BYTE_SIZE];
+        outside_g2[0] = 0x80;
+        outside_g2[47] = 0x01;
+        assert!(BlstSignature::from_bytes(&outside_g2).is_err());
+        assert!(p2_uncompress(&outside_g2).is_some());
+    }
+
+    #[test]
+    pub fn test_verify() {
+        let keypair = Keypair::random();
+        let sig = BlstSignature::new(&[42, 42], 7, &keypair.sk);
+
+        assert!(sig.verify(&[42, 42], 7, &keypair.pk));
+        assert!(!sig.verify(&[42, 43], 7, &keypair.pk));
+        assert!(!sig.verify(&[42, 42], 8, &keypair.pk));
+        assert!(!sig.verify(&[42, 42], 7, &Keypair::random().pk));
+    }
+
+    #[test]
+    pub fn test_matches_milagro() {
+        let keypair = Keypair::random();
+        let pk = MilagroPublicKey::from_secret_key(&keypair.sk.to_raw());
+
+        for (msg, domain) in &[
+            (vec![42, 42], 0),
+            (vec![1; 32], 3),
+            (vec![], u64::max_value()),
+        ] {
+            let blst = BlstSignature::new(msg, *domain, &keypair.sk);
+            let milagro = MilagroSignature::new(msg, *domain, &keypair.sk.to_raw());
+
+            assert_eq!(blst.as_bytes(), milagro.as_bytes());
+
+            // Each backend verifies the other's signatures.
+            let from_milagro = BlstSignature::from_bytes(&milagro.as_bytes()).unwrap();
+            assert!(from_milagro.verify(msg, *domain, &keypair.pk));
+            let from_blst = MilagroSignature::from_bytes(&blst.as_bytes()).unwrap();
+            assert!(from_blst.verify(msg, *domain, &pk));
+        }
+    }
+
+    #[test]
+    pub fn test_hashed_matches_milagro() {
+        let keypair = Keypair::random();
+        let (x_real, x_imaginary) = (vec![1; 32], vec![2; 32]);
+
+        let blst = BlstSignature::new_hashed(&x_real, &x_imaginary, &keypair.sk);
+        let milagro = MilagroSignature::new_hashed(&x_real, &x_imaginary, &keypair.sk.to_raw());
+
+        assert_eq!(blst.as_bytes(), milagro.as_bytes());
+        assert!(blst.verify_hashed(&x_real, &x_imaginary, &keypair.pk));
+    }
+}
@@ -1,9 +1,11 @@
 use super::{PublicKey, SecretKey};
-use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
+/// A secret key and its public key.
+///
+/// As with `SecretKey`, a keypair cannot be serialized; its `Debug` output omits the secret key.
+#[derive(Debug, Clone, Eq)]
 pub struct Keypair {
     pub sk: SecretKey,
     pub pk: PublicKey,
@@ -3,11 +3,14 @@ extern crate ssz;
 
 #[macro_use]
 mod macros;
+mod aggregate_verify;
 mod keypair;
 mod secret_key;
+mod signature_set;
 
 pub use crate::keypair::Keypair;
 pub use crate::secret_key::SecretKey;
+pub use crate::signature_set::{verify_signature_sets, SignatureSet};
 pub use milagro_bls::{compress_g2, hash_on_g2};
 
 #[cfg(feature = "fake_crypto")]
@@ -19,13 +22,22 @@ mod fake_public_key;
 #[cfg(feature = "fake_crypto")]
 mod fake_signature;
 
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+mod blst_aggregate_public_key;
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+mod blst_aggregate_signature;
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+mod blst_public_key;
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+mod blst_signature;
+
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 mod aggregate_public_key;
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 mod aggregate_signature;
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 mod public_key;
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 mod signature;
 
 #[cfg(feature = "fake_crypto")]
@@ -38,9 +50,19 @@ mod fakes {
     pub use crate::fake_signature::FakeSignature as Signature;
 }
 
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+pub use blsts::*;
+#[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+mod blsts {
+    pub use crate::blst_aggregate_public_key::BlstAggregatePublicKey as AggregatePublicKey;
+    pub use crate::blst_aggregate_signature::BlstAggregateSignature as AggregateSignature;
+    pub use crate::blst_public_key::BlstPublicKey as PublicKey;
+    pub use crate::blst_signature::BlstSignature as Signature;
+}
+
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 pub use reals::*;
-#[cfg(not(feature = "fake_crypto"))]
+#[cfg(not(any(feature = "fake_crypto", feature = "blst")))]
 mod reals {
     pub use crate::aggregate_public_key::AggregatePublicKey;
     pub use crate::aggregate_signature::AggregateSignature;
@@ -19,7 +19,7 @@ pub struct PublicKey(RawPublicKey);
 
 impl PublicKey {
     pub fn from_secret_key(secret_key: &SecretKey) -> Self {
-        PublicKey(RawPublicKey::from_secret_key(secret_key.as_raw()))
+        PublicKey(RawPublicKey::from_secret_key(&secret_key.to_raw()))
     }
 
     /// Returns the underlying signature.
@@ -1,88 +1,108 @@
 extern crate rand;
 
 use super::BLS_SECRET_KEY_BYTE_SIZE;
-use hex::encode as hex_encode;
 use milagro_bls::SecretKey as RawSecretKey;
-use serde::de::{Deserialize, Deserializer};
-use serde::ser::{Serialize, Serializer};
-use serde_hex::HexVisitor;
-use ssz::{ssz_encode, Decode, DecodeError};
-use tree_hash::tree_hash_ssz_encoding_as_vector;
+use ssz::DecodeError;
+use std::fmt;
+use zeroize::Zeroizing;
 
-/// A single BLS signature.
+/// A single BLS secret key.
 ///
-/// This struct is a wrapper upon a base type and provides helper functions (e.g., SSZ
-/// serialization).
-#[derive(Debug, PartialEq, Clone, Eq)]
-pub struct SecretKey(RawSecretKey);
+/// The key is held as bytes which are zeroed when dropped. It has no `Debug` output, SSZ or serde
+/// encoding; its bytes may only be obtained through `SecretKey::as_bytes`, which is for storing
+/// the key in a keystore.
+///
+/// `milagro_bls` has no way to clear its own representation of a key, so one is only created for
+/// the duration of each operation (see `SecretKey::to_raw`).
+#[derive(Clone)]
+pub struct SecretKey(Zeroizing<Vec<u8>>);
 
 impl SecretKey {
     pub fn random() -> Self {
-        SecretKey(RawSecretKey::random(&mut rand::thread_rng()))
+        SecretKey(Zeroizing::new(
+            RawSecretKey::random(&mut rand::thread_rng()).as_bytes(),
+        ))
     }
 
-    /// Returns the underlying point as compressed bytes.
-    fn as_bytes(&self) -> Vec<u8> {
-        self.as_raw().as_bytes()
+    /// Returns the secret key as bytes, which are zeroed when dropped.
+    ///
+    /// These bytes are the secret itself. Only use them to write the key to a keystore.
+    pub fn as_bytes(&self) -> Zeroizing<Vec<u8>> {
+        self.0.clone()
     }
 
     /// Instantiate a SecretKey from existing bytes.
     ///
     /// Note: this is _not_ SSZ decoding.
     pub fn from_bytes(bytes: &[u8]) -> Result<SecretKey, DecodeError> {
-        Ok(SecretKey(RawSecretKey::from_bytes(bytes).map_err(|e| {
-            DecodeError::BytesInvalid(format!(
-                "Invalid SecretKey bytes: {:?} Error: {:?}",
-                bytes, e
-            ))
-        })?))
+        if bytes.len() != BLS_SECRET_KEY_BYTE_SIZE {
+            return Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: BLS_SECRET_KEY_BYTE_SIZE,
+            });
+        }
+
+        // The error is not included, nor are the bytes, since either may reveal the key.
+        RawSecretKey::from_bytes(bytes)
+            .map_err(|_| DecodeError::BytesInvalid("Invalid SecretKey bytes".to_string()))?;
+
+        Ok(SecretKey(Zeroizing::new(bytes.to_vec())))
     }
 
-    /// Returns the underlying secret key.
-    pub fn as_raw(&self) -> &RawSecretKey {
-        &self.0
+    /// Returns the key as a `milagro_bls` secret key, which should be dropped as soon as it is no
+    /// longer required.
+    pub fn to_raw(&self) -> RawSecretKey {
+        RawSecretKey::from_bytes(&self.0).expect("bytes are checked when the key is created")
     }
 }
 
-impl_ssz!(SecretKey, BLS_SECRET_KEY_BYTE_SIZE, "SecretKey");
-
-impl Serialize for SecretKey {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&hex_encode(ssz_encode(self)))
+/// Keys are compared in constant time, so that the comparison does not reveal how many leading
+/// bytes are equal.
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
     }
 }
 
-impl<'de> Deserialize<'de> for SecretKey {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let bytes = deserializer.deserialize_str(HexVisitor)?;
-        let secret_key = SecretKey::from_ssz_bytes(&bytes[..])
-            .map_err(|e| serde::de::Error::custom(format!("invalid ssz ({:?})", e)))?;
-        Ok(secret_key)
+impl Eq for SecretKey {}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey(<redacted>)")
     }
 }
 
-tree_hash_ssz_encoding_as_vector!(SecretKey);
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ssz::ssz_encode;
 
     #[test]
-    pub fn test_ssz_round_trip() {
+    pub fn test_bytes_round_trip() {
         let original =
             SecretKey::from_bytes("jzjxxgjajfjrmgodszzsgqccmhnyvetcuxobhtynojtpdtbj".as_bytes())
                 .unwrap();
 
-        let bytes = ssz_encode(&original);
-        let decoded = SecretKey::from_ssz_bytes(&bytes).unwrap();
+        let decoded = SecretKey::from_bytes(&original.as_bytes()).unwrap();
 
         assert_eq!(original, decoded);
+        assert_ne!(original, SecretKey::random());
+    }
+
+    #[test]
+    pub fn test_debug_is_redacted() {
+        let sk = SecretKey::random();
+        let hex = hex::encode(&*sk.as_bytes());
+
+        assert!(!format!("{:?}", sk).contains(&hex));
+
+        let pk = crate::PublicKey::from_secret_key(&sk);
+        let keypair = crate::Keypair { sk, pk };
+        assert!(!format!("{:?}", keypair).contains(&hex));
     }
 }
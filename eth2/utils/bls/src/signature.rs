@@ -22,7 +22,7 @@ impl Signature {
     /// Instantiate a new Signature from a message and a SecretKey.
     pub fn new(msg: &[u8], domain: u64, sk: &SecretKey) -> Self {
         Signature {
-            signature: RawSignature::new(msg, domain, sk.as_raw()),
+            signature: RawSignature::new(msg, domain, &sk.to_raw()),
             is_empty: false,
         }
     }
@@ -31,7 +31,7 @@ impl Signature {
     /// been hashed.
     pub fn new_hashed(x_real_hashed: &[u8], x_imaginary_hashed: &[u8], sk: &SecretKey) -> Self {
         Signature {
-            signature: RawSignature::new_hashed(x_real_hashed, x_imaginary_hashed, sk.as_raw()),
+            signature: RawSignature::new_hashed(x_real_hashed, x_imaginary_hashed, &sk.to_raw()),
             is_empty: false,
         }
     }
@@ -0,0 +1,161 @@
+use super::{AggregateSignature, PublicKey, Signature};
+use std::borrow::Cow;
+
+/// A signature, the keys which signed it and the message they signed.
+///
+/// Many sets may be verified together with `verify_signature_sets`, which is cheaper than
+/// verifying each set in turn.
+#[derive(Clone, Debug)]
+pub struct SignatureSet<'a> {
+    pub signature: Cow<'a, AggregateSignature>,
+    pub signing_keys: Vec<&'a PublicKey>,
+    pub message: Vec<u8>,
+    pub domain: u64,
+}
+
+impl<'a> SignatureSet<'a> {
+    /// A set of a single signature by a single key.
+    pub fn single(
+        signature: &Signature,
+        signing_key: &'a PublicKey,
+        message: Vec<u8>,
+        domain: u64,
+    ) -> Self {
+        let mut aggregate = AggregateSignature::new();
+        aggregate.add(signature);
+
+        Self {
+            signature: Cow::Owned(aggregate),
+            signing_keys: vec![signing_key],
+            message,
+            domain,
+        }
+    }
+
+    /// A set of an aggregate signature by many keys, over the same message.
+    pub fn new(
+        signature: &'a AggregateSignature,
+        signing_keys: Vec<&'a PublicKey>,
+        message: Vec<u8>,
+        domain: u64,
+    ) -> Self {
+        Self {
+            signature: Cow::Borrowed(signature),
+            signing_keys,
+            message,
+            domain,
+        }
+    }
+
+    /// Verifies this set alone.
+    pub fn verify(&self) -> bool {
+        self.signature
+            .fast_aggregate_verify(&self.message, self.domain, &self.signing_keys)
+    }
+}
+
+/// Returns `true` if every set is valid.
+///
+/// With the `blst` backend the sets are checked together, as a random linear combination, at the
+/// cost of one pairing per set plus one. Otherwise each set is verified in turn.
+///
+/// A set without signing keys is never valid. An empty list of sets is valid.
+pub fn verify_signature_sets(sets: &[SignatureSet]) -> bool {
+    #[cfg(all(feature = "blst", not(feature = "fake_crypto")))]
+    {
+        crate::blst_aggregate_signature::verify_signature_sets(sets)
+    }
+    #[cfg(not(all(feature = "blst", not(feature = "fake_crypto"))))]
+    {
+        sets.iter().all(SignatureSet::verify)
+    }
+}
+
+#[cfg(all(test, not(feature = "fake_crypto")))]
+mod tests {
+    use super::*;
+    use crate::Keypair;
+
+    struct Signed {
+        keypairs: Vec<Keypair>,
+        signature: AggregateSignature,
+        message: Vec<u8>,
+        domain: u64,
+    }
+
+    fn signed(signers: usize, message: u8) -> Signed {
+        let keypairs: Vec<Keypair> = (0..signers).map(|_| Keypair::random()).collect();
+        let message = vec![message; 32];
+        let domain = 42;
+
+        let mut signature = AggregateSignature::new();
+        for keypair in &keypairs {
+            signature.add(&Signature::new(&message, domain, &keypair.sk));
+        }
+
+        Signed {
+            keypairs,
+            signature,
+            message,
+            domain,
+        }
+    }
+
+    fn set(signed: &Signed) -> SignatureSet {
+        SignatureSet::new(
+            &signed.signature,
+            signed.keypairs.iter().map(|keypair| &keypair.pk).collect(),
+            signed.message.clone(),
+            signed.domain,
+        )
+    }
+
+    #[test]
+    fn verifies_valid_sets() {
+        let aggregates: Vec<Signed> = (1..4).map(|i| signed(i, i as u8)).collect();
+        let keypair = Keypair::random();
+        let signature = Signature::new(&[7; 32], 3, &keypair.sk);
+
+        let mut sets: Vec<SignatureSet> = aggregates.iter().map(set).collect();
+        sets.push(SignatureSet::single(
+            &signature,
+            &keypair.pk,
+            vec![7; 32],
+            3,
+        ));
+
+        assert!(sets.iter().all(SignatureSet::verify));
+        assert!(verify_signature_sets(&sets));
+        assert!(verify_signature_sets(&[]));
+    }
+
+    #[test]
+    fn rejects_any_invalid_set() {
+        let aggregates: Vec<Signed> = (1..4).map(|i| signed(i, i as u8)).collect();
+
+        let mut sets: Vec<SignatureSet> = aggregates.iter().map(set).collect();
+        sets[1].message = vec![99; 32];
+        assert!(!verify_signature_sets(&sets));
+
+        let mut sets: Vec<SignatureSet> = aggregates.iter().map(set).collect();
+        sets[2].signing_keys.pop();
+        assert!(!verify_signature_sets(&sets));
+
+        let mut sets: Vec<SignatureSet> = aggregates.iter().map(set).collect();
+        sets[0].signing_keys.clear();
+        assert!(!verify_signature_sets(&sets));
+    }
+
+    #[test]
+    fn rejects_swapped_signatures() {
+        // Each signature is invalid alone, though their sum is valid for the sum of the sets.
+        let a = signed(1, 1);
+        let b = signed(1, 2);
+
+        let mut sets = vec![set(&a), set(&b)];
+        sets[0].signature = Cow::Borrowed(&b.signature);
+        sets[1].signature = Cow::Borrowed(&a.signature);
+
+        assert!(!verify_signature_sets(&sets));
+    }
+}
@@ -111,12 +111,37 @@ impl BooleanBitfield {
         self.to_bytes().len()
     }
 
-    /// Returns the number of `1` bits in the bitfield
+    /// Returns the number of `1` bits in the bitfield, counting a whole word at a time.
     pub fn num_set_bits(&self) -> usize {
-        self.0.iter().filter(|&bit| bit).count()
+        self.0
+            .blocks()
+            .map(|block| block.count_ones() as usize)
+            .sum()
     }
 
-    /// Compute the intersection (binary-and) of this bitfield with another. Lengths must match.
+    /// Returns true if every bit set in this bitfield is also set in `other`.
+    ///
+    /// Compares a whole word at a time. Bits beyond the length of `other` are treated as `0`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        let mut other_blocks = other.0.blocks();
+        self.0
+            .blocks()
+            .all(|block| block & !other_blocks.next().unwrap_or(0) == 0)
+    }
+
+    /// Returns true if some bit is set in both this bitfield and `other`, i.e., if their
+    /// intersection is not zero, without computing it.
+    ///
+    /// Compares a whole word at a time. Lengths need not match.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.0
+            .blocks()
+            .zip(other.0.blocks())
+            .any(|(block, other_block)| block & other_block != 0)
+    }
+
+    /// Compute the intersection (binary-and) of this bitfield with another, a whole word at a
+    /// time. Lengths must match.
     pub fn intersection(&self, other: &Self) -> Self {
         let mut res = self.clone();
         res.intersection_inplace(other);
@@ -128,7 +153,8 @@ impl BooleanBitfield {
         self.0.intersect(&other.0);
     }
 
-    /// Compute the union (binary-or) of this bitfield with another. Lengths must match.
+    /// Compute the union (binary-or) of this bitfield with another, a whole word at a time. Lengths
+    /// must match.
     pub fn union(&self, other: &Self) -> Self {
         let mut res = self.clone();
         res.union_inplace(other);
@@ -568,4 +594,35 @@ mod tests {
         assert_eq!(b.difference(&a), b_a);
         assert!(a.difference(&a).is_zero());
     }
+
+    #[test]
+    fn test_is_subset() {
+        let a = BooleanBitfield::from_bytes(&[0b1100, 0b0001]);
+        let b = BooleanBitfield::from_bytes(&[0b1101, 0b1001]);
+        let c = BooleanBitfield::from_bytes(&[0b1011, 0b1001]);
+        assert!(a.is_subset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(!a.is_subset(&c));
+        assert!(a.is_subset(&a));
+        assert!(BooleanBitfield::new().is_subset(&a));
+
+        // Missing bits are `0`.
+        let short = BooleanBitfield::from_bytes(&[0b1100]);
+        assert!(!a.is_subset(&short));
+        assert!(BooleanBitfield::from_bytes(&[0b1100, 0]).is_subset(&short));
+        let long = BooleanBitfield::from_bytes(&[0b1100, 0b0001, 0, 0, 0b1]);
+        assert!(a.is_subset(&long));
+        assert!(!long.is_subset(&a));
+    }
+
+    #[test]
+    fn test_intersects() {
+        let a = BooleanBitfield::from_bytes(&[0b1100, 0b0001]);
+        let b = BooleanBitfield::from_bytes(&[0b0011, 0b1001]);
+        let c = BooleanBitfield::from_bytes(&[0b0011, 0b1000]);
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&c));
+        assert!(!a.intersects(&BooleanBitfield::new()));
+        assert!(a.intersects(&BooleanBitfield::from_bytes(&[0, 0b0001, 0, 0, 0xff])));
+    }
 }
@@ -3,12 +3,16 @@
 //! Returns comparisons as data, without making assumptions about the desired equality (e.g.,
 //! does not `panic!` on inequality).
 //!
+//! `CompareFields::diff` instead returns only the differences, each addressed by a path to the
+//! differing leaf. Fields marked `#[compare_fields(nested)]` are recursed into, as are the elements
+//! of fields marked `#[compare_fields(as_slice, nested)]`.
+//!
 //! Note: `compare_fields_derive` requires `PartialEq` and `Debug` implementations.
 //!
 //! ## Example
 //!
 //! ```rust
-//! use compare_fields::{CompareFields, Comparison, FieldComparison};
+//! use compare_fields::{CompareFields, Comparison, FieldComparison, FieldDiff};
 //! use compare_fields_derive::CompareFields;
 //!
 //! #[derive(PartialEq, Debug, CompareFields)]
@@ -82,9 +86,21 @@
 //! ];
 //! assert_eq!(bar_a.compare_fields(&bar_b), bar_a_b);
 //!
+//! #[derive(PartialEq, Debug, CompareFields)]
+//! pub struct Baz {
+//!     #[compare_fields(nested)]
+//!     bar: Bar,
+//! }
 //!
+//! let baz_a = Baz { bar: bar_a };
+//! let baz_b = Baz { bar: bar_b };
 //!
-//! // TODO:
+//! let diffs: Vec<String> = baz_a
+//!     .diff(&baz_b)
+//!     .iter()
+//!     .map(FieldDiff::path_string)
+//!     .collect();
+//! assert_eq!(diffs, vec!["bar.b", "bar.c[0]"]);
 //! ```
 use std::fmt::Debug;
 
@@ -157,6 +173,45 @@ pub struct FieldComparison {
 
 pub trait CompareFields {
     fn compare_fields(&self, b: &Self) -> Vec<Comparison>;
+
+    /// Returns each differing leaf between `self` and `b`, addressed by its path.
+    ///
+    /// The derived implementation recurses into nested fields. This default only descends into
+    /// the children of a `Comparison::Parent`.
+    fn diff(&self, b: &Self) -> Vec<FieldDiff> {
+        let mut diffs = vec![];
+
+        for comparison in self.compare_fields(b) {
+            match comparison {
+                Comparison::Child(fc) => {
+                    if fc.not_equal() {
+                        diffs.push(FieldDiff {
+                            path: vec![Path::Ident(fc.field_name)],
+                            a: fc.a,
+                            b: fc.b,
+                        });
+                    }
+                }
+                Comparison::Parent {
+                    field_name,
+                    children,
+                    ..
+                } => {
+                    for (i, fc) in children.into_iter().enumerate() {
+                        if fc.not_equal() {
+                            diffs.push(FieldDiff {
+                                path: vec![Path::Ident(field_name.clone()), Path::Index(i)],
+                                a: fc.a,
+                                b: fc.b,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        diffs
+    }
 }
 
 impl FieldComparison {
@@ -177,3 +232,115 @@ impl FieldComparison {
         !self.equal()
     }
 }
+
+/// An element of the path to a field, as used by `merkle_partial`: either the name of a field of
+/// a container or an index into a list.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Path {
+    Ident(String),
+    Index(usize),
+}
+
+/// A leaf which differs between two values, e.g., `validator_registry[3].effective_balance`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldDiff {
+    pub path: Vec<Path>,
+    pub a: String,
+    pub b: String,
+}
+
+impl FieldDiff {
+    /// Returns the path as a string, e.g., `validator_registry[3].effective_balance`.
+    pub fn path_string(&self) -> String {
+        let mut string = String::new();
+
+        for element in &self.path {
+            match element {
+                Path::Ident(name) => {
+                    if !string.is_empty() {
+                        string.push('.');
+                    }
+                    string.push_str(name);
+                }
+                Path::Index(i) => string.push_str(&format!("[{}]", i)),
+            }
+        }
+
+        string
+    }
+
+    /// Prepends `element` to the path.
+    fn prefixed(mut self, element: Path) -> Self {
+        self.path.insert(0, element);
+        self
+    }
+}
+
+/// Returns a difference at `field_name` if `a != b`.
+pub fn diff_field<T: Debug + PartialEq<T>>(field_name: &str, a: &T, b: &T) -> Vec<FieldDiff> {
+    if a == b {
+        vec![]
+    } else {
+        vec![FieldDiff {
+            path: vec![Path::Ident(field_name.to_string())],
+            a: format!("{:?}", a),
+            b: format!("{:?}", b),
+        }]
+    }
+}
+
+/// Returns a difference at `field_name[i]` for each differing element, including those present in
+/// only one of `a` and `b`.
+pub fn diff_slice<T: Debug + PartialEq<T>>(field_name: &str, a: &[T], b: &[T]) -> Vec<FieldDiff> {
+    (0..std::cmp::max(a.len(), b.len()))
+        .filter_map(|i| {
+            let (a, b) = (a.get(i), b.get(i));
+            if a == b {
+                None
+            } else {
+                Some(FieldDiff {
+                    path: vec![Path::Ident(field_name.to_string()), Path::Index(i)],
+                    a: format!("{:?}", a),
+                    b: format!("{:?}", b),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Returns the differences within `a` and `b`, prefixed by `field_name`.
+pub fn diff_nested<T: CompareFields>(field_name: &str, a: &T, b: &T) -> Vec<FieldDiff> {
+    a.diff(b)
+        .into_iter()
+        .map(|diff| diff.prefixed(Path::Ident(field_name.to_string())))
+        .collect()
+}
+
+/// Returns the differences within each pair of elements of `a` and `b`, prefixed by
+/// `field_name[i]`.
+///
+/// Elements present in only one of `a` and `b` are returned whole.
+pub fn diff_nested_slice<T>(field_name: &str, a: &[T], b: &[T]) -> Vec<FieldDiff>
+where
+    T: CompareFields + Debug + PartialEq<T>,
+{
+    let mut diffs = vec![];
+
+    for i in 0..std::cmp::max(a.len(), b.len()) {
+        let prefix = |diff: FieldDiff| {
+            diff.prefixed(Path::Index(i))
+                .prefixed(Path::Ident(field_name.to_string()))
+        };
+
+        match (a.get(i), b.get(i)) {
+            (Some(a), Some(b)) => diffs.extend(a.diff(b).into_iter().map(prefix)),
+            (a, b) => diffs.push(prefix(FieldDiff {
+                path: vec![],
+                a: format!("{:?}", a),
+                b: format!("{:?}", b),
+            })),
+        }
+    }
+
+    diffs
+}
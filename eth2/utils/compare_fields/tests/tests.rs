@@ -0,0 +1,68 @@
+use compare_fields::{CompareFields, FieldDiff};
+use compare_fields_derive::CompareFields;
+
+#[derive(Clone, PartialEq, Debug, CompareFields)]
+struct Leaf {
+    x: u64,
+    y: u64,
+}
+
+#[derive(Clone, PartialEq, Debug, CompareFields)]
+struct Node {
+    #[compare_fields(nested)]
+    leaf: Leaf,
+    #[compare_fields(as_slice, nested)]
+    leaves: Vec<Leaf>,
+    #[compare_fields(as_slice)]
+    values: Vec<u64>,
+    other: u8,
+}
+
+fn node() -> Node {
+    Node {
+        leaf: Leaf { x: 1, y: 2 },
+        leaves: vec![Leaf { x: 3, y: 4 }, Leaf { x: 5, y: 6 }],
+        values: vec![7, 8],
+        other: 9,
+    }
+}
+
+fn paths(diffs: &[FieldDiff]) -> Vec<String> {
+    diffs.iter().map(FieldDiff::path_string).collect()
+}
+
+#[test]
+fn equal_values_have_no_diffs() {
+    assert!(node().diff(&node()).is_empty());
+}
+
+#[test]
+fn diffs_leaves() {
+    let a = node();
+    let mut b = node();
+    b.leaf.y = 20;
+    b.leaves[1].x = 50;
+    b.values[0] = 70;
+    b.other = 90;
+
+    let diffs = a.diff(&b);
+    assert_eq!(
+        paths(&diffs),
+        vec!["leaf.y", "leaves[1].x", "values[0]", "other"]
+    );
+    assert_eq!(diffs[1].a, "5");
+    assert_eq!(diffs[1].b, "50");
+}
+
+#[test]
+fn diffs_missing_elements_whole() {
+    let a = node();
+    let mut b = node();
+    b.leaves.pop();
+    b.values.push(10);
+
+    let diffs = a.diff(&b);
+    assert_eq!(paths(&diffs), vec!["leaves[1]", "values[2]"]);
+    assert_eq!(diffs[0].a, "Some(Leaf { x: 5, y: 6 })");
+    assert_eq!(diffs[0].b, "None");
+}
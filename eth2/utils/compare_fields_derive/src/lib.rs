@@ -5,13 +5,24 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-fn is_slice(field: &syn::Field) -> bool {
-    for attr in &field.attrs {
-        if attr.tts.to_string() == "( as_slice )" {
-            return true;
-        }
-    }
-    false
+/// Returns the options given in `#[compare_fields(...)]` attributes on `field`, e.g., `as_slice`.
+fn field_options(field: &syn::Field) -> Vec<String> {
+    field
+        .attrs
+        .iter()
+        .filter(|attr| {
+            attr.path.segments.len() == 1 && attr.path.segments[0].ident == "compare_fields"
+        })
+        .flat_map(|attr| {
+            attr.tts
+                .to_string()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split(',')
+                .map(|option| option.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
 }
 
 #[proc_macro_derive(CompareFields, attributes(compare_fields))]
@@ -27,6 +38,7 @@ pub fn compare_fields_derive(input: TokenStream) -> TokenStream {
     };
 
     let mut quotes = vec![];
+    let mut diff_quotes = vec![];
 
     for field in struct_data.fields.iter() {
         let ident_a = match &field.ident {
@@ -37,7 +49,11 @@ pub fn compare_fields_derive(input: TokenStream) -> TokenStream {
         let field_name = format!("{:}", ident_a);
         let ident_b = ident_a.clone();
 
-        let quote = if is_slice(field) {
+        let options = field_options(field);
+        let is_slice = options.iter().any(|option| option == "as_slice");
+        let is_nested = options.iter().any(|option| option == "nested");
+
+        let quote = if is_slice {
             quote! {
                 comparisons.push(compare_fields::Comparison::from_slice(
                         #field_name.to_string(),
@@ -58,6 +74,16 @@ pub fn compare_fields_derive(input: TokenStream) -> TokenStream {
         };
 
         quotes.push(quote);
+
+        let diff_fn = match (is_slice, is_nested) {
+            (false, false) => quote! { compare_fields::diff_field },
+            (true, false) => quote! { compare_fields::diff_slice },
+            (false, true) => quote! { compare_fields::diff_nested },
+            (true, true) => quote! { compare_fields::diff_nested_slice },
+        };
+        diff_quotes.push(quote! {
+            diffs.append(&mut #diff_fn(#field_name, &self.#ident_a, &b.#ident_b));
+        });
     }
 
     let output = quote! {
@@ -71,6 +97,16 @@ pub fn compare_fields_derive(input: TokenStream) -> TokenStream {
 
                 comparisons
             }
+
+            fn diff(&self, b: &Self) -> Vec<compare_fields::FieldDiff> {
+                let mut diffs = vec![];
+
+                #(
+                    #diff_quotes
+                )*
+
+                diffs
+            }
         }
     };
     output.into()
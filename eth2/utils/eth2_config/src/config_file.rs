@@ -0,0 +1,310 @@
+//! Loads CLI flags from a TOML or YAML config file, and writes the effective flags back out.
+//!
+//! The file maps the long name of each flag to its value, e.g., `http-port = 5052` or
+//! `http: true`. Lists are joined with commas. Flags given on the command line override those in
+//! the file.
+use clap::{App, Arg, ArgMatches};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+pub const CONFIG_FILE_FLAG: &str = "config-file";
+pub const DUMP_CONFIG_FLAG: &str = "dump-config";
+
+/// The value of a flag in a config file.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlagValue {
+    /// A flag which takes no value, present if `true`.
+    Switch(bool),
+    Value(String),
+}
+
+/// Returns the `--config-file` and `--dump-config` arguments.
+pub fn config_file_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name(CONFIG_FILE_FLAG)
+            .long(CONFIG_FILE_FLAG)
+            .value_name("PATH")
+            .help("A TOML or YAML file of flags, by their long names. Flags on the command line take precedence.")
+            .takes_value(true),
+        Arg::with_name(DUMP_CONFIG_FLAG)
+            .long(DUMP_CONFIG_FLAG)
+            .value_name("PATH")
+            .help("Write the effective flags to a TOML or YAML file, which may be given to --config-file.")
+            .takes_value(true),
+    ]
+}
+
+/// Parses `args` with `app`, adding any flag in the `--config-file` which is not given in `args`.
+///
+/// Exits the process if `args` are invalid, as `App::get_matches_from` does.
+pub fn get_matches_with_config_file<'a, I, T>(
+    app: App<'a, 'a>,
+    args: I,
+) -> Result<ArgMatches<'a>, String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let matches = app.clone().get_matches_from(args.clone());
+
+    let path = match matches.value_of(CONFIG_FILE_FLAG) {
+        Some(path) => path,
+        None => return Ok(matches),
+    };
+    let flags = read_flags(Path::new(path))?;
+
+    let mut file_args = vec![];
+    for (name, value) in flags {
+        if name == CONFIG_FILE_FLAG || name == DUMP_CONFIG_FLAG {
+            return Err(format!("{} may not be set in a config file", name));
+        }
+        if matches.occurrences_of(&name) > 0 {
+            continue;
+        }
+
+        match value {
+            FlagValue::Switch(true) => file_args.push(OsString::from(format!("--{}", name))),
+            FlagValue::Switch(false) => {}
+            FlagValue::Value(value) => {
+                file_args.push(OsString::from(format!("--{}={}", name, value)))
+            }
+        }
+    }
+
+    // Flags of the app must precede any subcommand, so the file's flags are placed first.
+    let mut args = args.into_iter();
+    let merged: Vec<OsString> = args
+        .next()
+        .into_iter()
+        .chain(file_args)
+        .chain(args)
+        .collect();
+
+    Ok(app.get_matches_from(merged))
+}
+
+/// Writes the flags in `matches`, including defaults, to `path` in the format of a config file.
+pub fn write_effective_config(matches: &ArgMatches, path: &Path) -> Result<(), String> {
+    let flags: BTreeMap<String, FlagValue> = matches
+        .args
+        .iter()
+        .filter(|(name, _)| **name != CONFIG_FILE_FLAG && **name != DUMP_CONFIG_FLAG)
+        .map(|(name, arg)| {
+            let value = if arg.vals.is_empty() {
+                FlagValue::Switch(true)
+            } else {
+                let vals: Vec<String> = arg
+                    .vals
+                    .iter()
+                    .map(|val| val.to_string_lossy().into_owned())
+                    .collect();
+                FlagValue::Value(vals.join(","))
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+
+    let contents = match Format::of(path)? {
+        Format::Toml => toml::to_string(&flags).map_err(|e| format!("{:?}", e))?,
+        Format::Yaml => serde_yaml::to_string(&flags).map_err(|e| format!("{:?}", e))?,
+    };
+
+    fs::write(path, contents).map_err(|e| format!("Unable to write {:?}: {:?}", path, e))
+}
+
+enum Format {
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(Format::Toml),
+            Some("yaml") | Some("yml") => Ok(Format::Yaml),
+            _ => Err(format!(
+                "Config file {:?} must have a .toml, .yaml or .yml extension",
+                path
+            )),
+        }
+    }
+}
+
+/// Reads the flags in the config file at `path`.
+fn read_flags(path: &Path) -> Result<BTreeMap<String, FlagValue>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Unable to read {:?}: {:?}", path, e))?;
+
+    parse_flags(&contents, Format::of(path)?)
+        .map_err(|e| format!("Unable to parse {:?}: {}", path, e))
+}
+
+fn parse_flags(contents: &str, format: Format) -> Result<BTreeMap<String, FlagValue>, String> {
+    match format {
+        Format::Toml => {
+            let table: BTreeMap<String, toml::Value> =
+                toml::from_str(contents).map_err(|e| format!("{:?}", e))?;
+            table
+                .into_iter()
+                .map(|(name, value)| Ok((name.clone(), toml_flag(&name, value)?)))
+                .collect()
+        }
+        Format::Yaml => {
+            let mapping: BTreeMap<String, serde_yaml::Value> =
+                serde_yaml::from_str(contents).map_err(|e| format!("{:?}", e))?;
+            mapping
+                .into_iter()
+                .map(|(name, value)| Ok((name.clone(), yaml_flag(&name, value)?)))
+                .collect()
+        }
+    }
+}
+
+fn toml_flag(name: &str, value: toml::Value) -> Result<FlagValue, String> {
+    match value {
+        toml::Value::Boolean(switch) => Ok(FlagValue::Switch(switch)),
+        toml::Value::String(value) => Ok(FlagValue::Value(value)),
+        toml::Value::Integer(value) => Ok(FlagValue::Value(value.to_string())),
+        toml::Value::Float(value) => Ok(FlagValue::Value(value.to_string())),
+        toml::Value::Array(values) => values
+            .into_iter()
+            .map(|value| match toml_flag(name, value)? {
+                FlagValue::Value(value) => Ok(value),
+                FlagValue::Switch(_) => Err(format!("{} may not contain booleans", name)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|values| FlagValue::Value(values.join(","))),
+        _ => Err(format!(
+            "{} must be a boolean, string, number or list",
+            name
+        )),
+    }
+}
+
+fn yaml_flag(name: &str, value: serde_yaml::Value) -> Result<FlagValue, String> {
+    match value {
+        serde_yaml::Value::Bool(switch) => Ok(FlagValue::Switch(switch)),
+        serde_yaml::Value::String(value) => Ok(FlagValue::Value(value)),
+        serde_yaml::Value::Number(value) => Ok(FlagValue::Value(value.to_string())),
+        serde_yaml::Value::Sequence(values) => values
+            .into_iter()
+            .map(|value| match yaml_flag(name, value)? {
+                FlagValue::Value(value) => Ok(value),
+                FlagValue::Switch(_) => Err(format!("{} may not contain booleans", name)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|values| FlagValue::Value(values.join(","))),
+        _ => Err(format!(
+            "{} must be a boolean, string, number or list",
+            name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App<'static, 'static> {
+        App::new("test")
+            .args(&config_file_args())
+            .arg(Arg::with_name("http").long("http"))
+            .arg(
+                Arg::with_name("http-port")
+                    .long("http-port")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("boot-nodes")
+                    .long("boot-nodes")
+                    .takes_value(true),
+            )
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn parses_toml_and_yaml() {
+        let expected: BTreeMap<String, FlagValue> = vec![
+            (
+                "boot-nodes".to_string(),
+                FlagValue::Value("a,b".to_string()),
+            ),
+            ("http".to_string(), FlagValue::Switch(true)),
+            (
+                "http-port".to_string(),
+                FlagValue::Value("5052".to_string()),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let toml = "http = true\nhttp-port = 5052\nboot-nodes = [\"a\", \"b\"]\n";
+        assert_eq!(parse_flags(toml, Format::Toml), Ok(expected.clone()));
+
+        let yaml = "http: true\nhttp-port: 5052\nboot-nodes:\n  - a\n  - b\n";
+        assert_eq!(parse_flags(yaml, Format::Yaml), Ok(expected));
+
+        assert!(parse_flags("http = { a = 1 }", Format::Toml).is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_file() {
+        let path = temp_path("config.yaml");
+        fs::write(&path, "http: true\nhttp-port: 5052\nboot-nodes: a\n").unwrap();
+
+        let matches = get_matches_with_config_file(
+            app(),
+            vec![
+                "test",
+                "--config-file",
+                path.to_str().unwrap(),
+                "--http-port",
+                "6000",
+            ],
+        )
+        .unwrap();
+
+        assert!(matches.is_present("http"));
+        assert_eq!(matches.value_of("http-port"), Some("6000"));
+        assert_eq!(matches.value_of("boot-nodes"), Some("a"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn dumped_config_round_trips() {
+        let dump = temp_path("dump.toml");
+        let matches = get_matches_with_config_file(
+            app(),
+            vec![
+                "test",
+                "--http",
+                "--http-port",
+                "6000",
+                "--dump-config",
+                "x",
+            ],
+        )
+        .unwrap();
+        write_effective_config(&matches, &dump).unwrap();
+
+        let matches = get_matches_with_config_file(
+            app(),
+            vec!["test", "--config-file", dump.to_str().unwrap()],
+        )
+        .unwrap();
+        assert!(matches.is_present("http"));
+        assert_eq!(matches.value_of("http-port"), Some("6000"));
+        assert!(!matches.is_present("boot-nodes"));
+
+        fs::remove_file(dump).unwrap();
+    }
+}
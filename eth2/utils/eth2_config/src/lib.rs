@@ -1,3 +1,5 @@
+mod config_file;
+
 use clap::ArgMatches;
 use serde_derive::{Deserialize, Serialize};
 use std::fs;
@@ -7,6 +9,11 @@ use std::path::PathBuf;
 use std::time::SystemTime;
 use types::ChainSpec;
 
+pub use config_file::{
+    config_file_args, get_matches_with_config_file, write_effective_config, FlagValue,
+    CONFIG_FILE_FLAG, DUMP_CONFIG_FLAG,
+};
+
 /// The core configuration of a Lighthouse beacon node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -1,9 +1,19 @@
+#[cfg(not(target_arch = "wasm32"))]
 use ring::digest::{digest, SHA256};
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn hash(input: &[u8]) -> Vec<u8> {
     digest(&SHA256, input).as_ref().into()
 }
 
+/// `ring` does not build for WebAssembly, so a pure Rust SHA-256 is used instead.
+#[cfg(target_arch = "wasm32")]
+pub fn hash(input: &[u8]) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    Sha256::digest(input).to_vec()
+}
+
 /// Get merkle root of some hashed values - the input leaf nodes is expected to already be hashed
 /// Outputs a `Vec<u8>` byte array of the merkle root given a set of leaf node values.
 pub fn merkle_root(values: &[Vec<u8>]) -> Option<Vec<u8>> {
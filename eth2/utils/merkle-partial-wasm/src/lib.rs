@@ -0,0 +1,92 @@
+//! WebAssembly bindings for verifying proofs of beacon state nodes.
+//!
+//! Proofs are SSZ-encoded `SerializedPartial`s and paths are generalized indices.
+use ethereum_types::H256;
+use merkle_proof::{verify_partial, SerializedPartial};
+use ssz::Decode;
+use wasm_bindgen::prelude::*;
+
+/// Returns `true` if `proof_bytes` hashes to `root` and proves the node at each of `paths`.
+///
+/// Returns an error if the proof cannot be decoded or is malformed, or if `root` is not 32 bytes.
+#[wasm_bindgen]
+pub fn verify(proof_bytes: &[u8], root: &[u8], paths: &[u64]) -> Result<bool, JsValue> {
+    verify_proof(proof_bytes, root, paths).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Returns the 32-byte chunk at `path` in `proof_bytes`, if present.
+///
+/// The proof is not checked, so it must first be verified with `verify`.
+#[wasm_bindgen]
+pub fn get(proof_bytes: &[u8], path: u64) -> Result<Option<Vec<u8>>, JsValue> {
+    get_chunk(proof_bytes, path).map_err(|e| JsValue::from_str(&e))
+}
+
+fn decode_proof(proof_bytes: &[u8]) -> Result<SerializedPartial, String> {
+    SerializedPartial::from_ssz_bytes(proof_bytes).map_err(|e| format!("Invalid proof: {:?}", e))
+}
+
+fn verify_proof(proof_bytes: &[u8], root: &[u8], paths: &[u64]) -> Result<bool, String> {
+    let partial = decode_proof(proof_bytes)?;
+    if root.len() != 32 {
+        return Err(format!("Root must be 32 bytes, not {}", root.len()));
+    }
+
+    let verification = verify_partial(&partial, H256::from_slice(root))
+        .map_err(|e| format!("Malformed proof: {:?}", e))?;
+
+    Ok(verification.valid
+        && paths
+            .iter()
+            .all(|path| verification.covered_paths.binary_search(path).is_ok()))
+}
+
+fn get_chunk(proof_bytes: &[u8], path: u64) -> Result<Option<Vec<u8>>, String> {
+    let partial = decode_proof(proof_bytes)?;
+
+    Ok(partial
+        .indices
+        .iter()
+        .zip(&partial.chunks)
+        .find(|(&index, _)| index == path)
+        .map(|(_, chunk)| chunk.as_bytes().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_proof::MerkleTree;
+    use ssz::Encode;
+
+    /// Returns a tree over 8 leaves and an encoded proof of leaves `2` and `5`.
+    fn proof() -> (MerkleTree, Vec<u8>) {
+        let tree = MerkleTree::new((0..8).map(|i| H256::from([i + 1; 32])).collect());
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        tree.append_proof(1, &[2, 5], &mut partial);
+
+        (tree, partial.as_ssz_bytes())
+    }
+
+    #[test]
+    fn verifies_proven_paths() {
+        let (tree, bytes) = proof();
+        let root = tree.root();
+
+        assert_eq!(verify_proof(&bytes, root.as_bytes(), &[10, 13]), Ok(true));
+        assert_eq!(verify_proof(&bytes, root.as_bytes(), &[14]), Ok(false));
+        assert_eq!(verify_proof(&bytes, &[0; 32], &[10]), Ok(false));
+        assert!(verify_proof(&bytes, &[0; 31], &[10]).is_err());
+        assert!(verify_proof(&[1, 2, 3], root.as_bytes(), &[10]).is_err());
+    }
+
+    #[test]
+    fn gets_chunks() {
+        let (_, bytes) = proof();
+
+        assert_eq!(get_chunk(&bytes, 10), Ok(Some(vec![3; 32])));
+        assert_eq!(get_chunk(&bytes, 15), Ok(None));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::partial::{verify_partial, PartialError, PartialVerification, SerializedPartial};
+use ethereum_types::H256;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+
+/// A partial against a root which is proven by the partial before it in a `ProofBundle`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofLink {
+    /// The generalized index of `root` within the partial before this one.
+    pub index: u64,
+    pub root: H256,
+    pub partial: SerializedPartial,
+}
+
+/// Partials against several roots, each of which, besides the first, is proven by the partial
+/// before it.
+///
+/// E.g., a proof of a field of a past state may be a proof against the present state root of an
+/// entry of its `latest_state_roots`, followed by a proof against that entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct ProofBundle {
+    /// A partial against the trusted root.
+    pub partial: SerializedPartial,
+    pub links: Vec<ProofLink>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BundleError {
+    /// A partial is malformed. Partials are numbered from `0`, the first partial of the bundle,
+    /// to `links.len()`.
+    MalformedProof { partial: usize, error: PartialError },
+    /// The partial does not hash to its root.
+    InvalidProof { partial: usize },
+    /// The root of the partial is not proven at its index by the partial before it.
+    UnprovenRoot { partial: usize },
+}
+
+impl ProofBundle {
+    /// Verifies the first partial against `root`, and each link against its root, which must be
+    /// proven by the partial before it.
+    ///
+    /// Returns the verification of each partial, starting with the first.
+    pub fn verify(&self, root: H256) -> Result<Vec<PartialVerification>, BundleError> {
+        let mut verifications = Vec::with_capacity(self.links.len() + 1);
+        verifications.push(verify(0, &self.partial, root)?);
+
+        for (i, link) in self.links.iter().enumerate() {
+            let previous = if i == 0 {
+                &self.partial
+            } else {
+                &self.links[i - 1].partial
+            };
+            let is_proven = verifications[i]
+                .covered_paths
+                .binary_search(&link.index)
+                .is_ok()
+                && previous
+                    .indices
+                    .iter()
+                    .zip(&previous.chunks)
+                    .any(|(&index, &chunk)| index == link.index && chunk == link.root);
+            if !is_proven {
+                return Err(BundleError::UnprovenRoot { partial: i + 1 });
+            }
+
+            verifications.push(verify(i + 1, &link.partial, link.root)?);
+        }
+
+        Ok(verifications)
+    }
+}
+
+fn verify(
+    partial_number: usize,
+    partial: &SerializedPartial,
+    root: H256,
+) -> Result<PartialVerification, BundleError> {
+    let verification =
+        verify_partial(partial, root).map_err(|error| BundleError::MalformedProof {
+            partial: partial_number,
+            error,
+        })?;
+    if !verification.valid {
+        return Err(BundleError::InvalidProof {
+            partial: partial_number,
+        });
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::MerkleTree;
+
+    fn leaves(n: u64) -> Vec<H256> {
+        (0..n).map(|i| H256::from_low_u64_le(i + 1)).collect()
+    }
+
+    /// Returns a bundle proving the leaf 2 of `inner`, whose root is leaf 5 of `outer`, and the
+    /// root of `outer`.
+    fn bundle() -> (ProofBundle, H256) {
+        let inner = MerkleTree::new(leaves(4));
+        let mut outer_leaves = leaves(8);
+        outer_leaves[5] = inner.root();
+        let outer = MerkleTree::new(outer_leaves);
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        outer.append_proof(1, &[5], &mut partial);
+
+        let mut link = ProofLink {
+            index: outer.leaf_index(5),
+            root: inner.root(),
+            partial: SerializedPartial {
+                indices: vec![],
+                chunks: vec![],
+            },
+        };
+        inner.append_proof(1, &[2], &mut link.partial);
+
+        (
+            ProofBundle {
+                partial,
+                links: vec![link],
+            },
+            outer.root(),
+        )
+    }
+
+    #[test]
+    fn verifies_links() {
+        let (bundle, root) = bundle();
+        let verifications = bundle.verify(root).unwrap();
+
+        assert_eq!(verifications.len(), 2);
+        assert!(verifications[1].covered_paths.contains(&6));
+    }
+
+    #[test]
+    fn rejects_broken_links() {
+        let (bundle, root) = bundle();
+
+        assert_eq!(
+            bundle.verify(H256::zero()),
+            Err(BundleError::InvalidProof { partial: 0 })
+        );
+
+        let mut wrong_index = bundle.clone();
+        wrong_index.links[0].index = 12;
+        assert_eq!(
+            wrong_index.verify(root),
+            Err(BundleError::UnprovenRoot { partial: 1 })
+        );
+
+        let mut wrong_root = bundle.clone();
+        wrong_root.links[0].root = H256::zero();
+        assert_eq!(
+            wrong_root.verify(root),
+            Err(BundleError::UnprovenRoot { partial: 1 })
+        );
+
+        let mut wrong_chunk = bundle;
+        wrong_chunk.links[0].partial.chunks[0] = H256::zero();
+        assert_eq!(
+            wrong_chunk.verify(root),
+            Err(BundleError::InvalidProof { partial: 1 })
+        );
+    }
+}
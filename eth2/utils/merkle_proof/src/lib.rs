@@ -1,6 +1,25 @@
+mod bundle;
+pub mod metrics;
+mod multiproof;
+mod overlay;
+mod partial;
+mod tree;
+pub mod tree_arithmetic;
+mod wire;
+
 use ethereum_types::H256;
 use hashing::hash;
 
+pub use bundle::{BundleError, ProofBundle, ProofLink};
+pub use multiproof::{Multiproof, MultiproofError};
+pub use overlay::{byte_list_chunk_index, byte_list_schema, MerkleTreeOverlay, SchemaEntry};
+pub use partial::{verify_partial, PartialError, PartialVerification, SerializedPartial};
+pub use tree::MerkleTree;
+pub use tree_arithmetic::{
+    concat_generalized_indices, generalized_index_depth, helper_indices, redundant_index,
+};
+pub use wire::{Compression, WireError};
+
 /// Verify a proof that `leaf` exists at `index` in a Merkle tree rooted at `root`.
 ///
 /// The `branch` argument is the main component of the proof: it should be a list of internal
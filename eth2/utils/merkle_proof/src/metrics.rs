@@ -0,0 +1,105 @@
+//! Metrics of proof generation and verification.
+//!
+//! With the `metrics` feature, these are registered with the default `prometheus` registry, which
+//! the beacon node's metrics endpoint gathers. Without it, every function here is a no-op.
+use crate::partial::{PartialError, PartialVerification, SerializedPartial};
+
+#[cfg(feature = "metrics")]
+use lazy_static::lazy_static;
+#[cfg(feature = "metrics")]
+use prometheus::{Histogram, HistogramOpts, HistogramTimer, IntCounter, Opts};
+
+#[cfg(feature = "metrics")]
+lazy_static! {
+    static ref PROOF_GENERATION_TIMES: Histogram = histogram(HistogramOpts::new(
+        "merkle_proof_generation_seconds",
+        "Time taken to generate a proof"
+    ));
+    static ref PROOF_CHUNKS: Histogram = histogram(
+        HistogramOpts::new(
+            "merkle_proof_chunks",
+            "Number of chunks in each generated proof"
+        )
+        .buckets(vec![1.0, 4.0, 16.0, 64.0, 256.0, 1024.0, 4096.0])
+    );
+    static ref PROOF_CACHE_HITS: IntCounter = int_counter(Opts::new(
+        "merkle_proof_cache_hits_total",
+        "Proofs served from a cache"
+    ));
+    static ref PROOF_CACHE_MISSES: IntCounter = int_counter(Opts::new(
+        "merkle_proof_cache_misses_total",
+        "Proofs which were not found in a cache"
+    ));
+    static ref PROOF_VERIFICATIONS: IntCounter = int_counter(Opts::new(
+        "merkle_proof_verifications_total",
+        "Proofs verified"
+    ));
+    static ref PROOF_VERIFICATION_FAILURES: IntCounter = int_counter(Opts::new(
+        "merkle_proof_verification_failures_total",
+        "Proofs which were malformed or did not match the expected root"
+    ));
+}
+
+#[cfg(feature = "metrics")]
+fn histogram(opts: HistogramOpts) -> Histogram {
+    let histogram = Histogram::with_opts(opts).expect("histogram options are valid");
+    prometheus::register(Box::new(histogram.clone())).expect("metric is registered once");
+    histogram
+}
+
+#[cfg(feature = "metrics")]
+fn int_counter(opts: Opts) -> IntCounter {
+    let counter = IntCounter::with_opts(opts).expect("counter options are valid");
+    prometheus::register(Box::new(counter.clone())).expect("metric is registered once");
+    counter
+}
+
+/// Records the time taken to generate a proof when dropped.
+pub struct ProofTimer {
+    #[cfg(feature = "metrics")]
+    _timer: HistogramTimer,
+}
+
+/// Starts timing the generation of a proof.
+pub fn start_proof_timer() -> ProofTimer {
+    ProofTimer {
+        #[cfg(feature = "metrics")]
+        _timer: PROOF_GENERATION_TIMES.start_timer(),
+    }
+}
+
+/// Records the size of a generated proof.
+pub fn observe_proof(partial: &SerializedPartial) {
+    #[cfg(feature = "metrics")]
+    PROOF_CHUNKS.observe(partial.chunks.len() as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = partial;
+}
+
+/// Records that a proof was served from a cache.
+pub fn record_cache_hit() {
+    #[cfg(feature = "metrics")]
+    PROOF_CACHE_HITS.inc();
+}
+
+/// Records that a proof was not found in a cache.
+pub fn record_cache_miss() {
+    #[cfg(feature = "metrics")]
+    PROOF_CACHE_MISSES.inc();
+}
+
+/// Records the outcome of verifying a proof.
+pub(crate) fn observe_verification(result: &Result<PartialVerification, PartialError>) {
+    #[cfg(feature = "metrics")]
+    {
+        PROOF_VERIFICATIONS.inc();
+        if result
+            .as_ref()
+            .map_or(true, |verification| !verification.valid)
+        {
+            PROOF_VERIFICATION_FAILURES.inc();
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = result;
+}
@@ -0,0 +1,217 @@
+//! Proofs in the canonical multiproof form of the eth2 spec, as taken by its
+//! `verify_merkle_multiproof`, for exchange with other clients.
+//!
+//! Unlike a `SerializedPartial`, which may list its nodes in any order, a multiproof separates the
+//! proven leaves, in ascending order of generalized index, from the helper nodes, which are given
+//! in descending order and whose indices are implied by those of the leaves.
+use crate::partial::SerializedPartial;
+use crate::tree_arithmetic::helper_indices;
+use ethereum_types::H256;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+
+/// A proof of `leaves` at `indices`, with the helper nodes of `get_helper_indices(indices)` in
+/// `proof`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct Multiproof {
+    /// The generalized indices of the proven leaves, ascending.
+    pub indices: Vec<u64>,
+    /// The value of the leaf at each of `indices`.
+    pub leaves: Vec<H256>,
+    /// The values of the helper nodes, in descending order of generalized index.
+    pub proof: Vec<H256>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MultiproofError {
+    /// There must be exactly one leaf per index.
+    LengthMismatch { indices: usize, leaves: usize },
+    /// The proof must have exactly one node per helper index.
+    ProofLengthMismatch { expected: usize, found: usize },
+    /// `0` is not a generalized index.
+    ZeroIndex,
+    /// The indices are not strictly ascending at this index.
+    UnorderedIndex(u64),
+    /// The node at this index is needed, but absent from the partial.
+    MissingIndex(u64),
+}
+
+impl Multiproof {
+    /// Extracts the multiproof of the nodes at `leaves` from `partial`, which must hold those
+    /// nodes and every helper node. Any other nodes of `partial` are dropped.
+    pub fn from_partial(
+        partial: &SerializedPartial,
+        leaves: &[u64],
+    ) -> Result<Self, MultiproofError> {
+        if partial.indices.len() != partial.chunks.len() {
+            return Err(MultiproofError::LengthMismatch {
+                indices: partial.indices.len(),
+                leaves: partial.chunks.len(),
+            });
+        }
+        let nodes: HashMap<u64, H256> = partial
+            .indices
+            .iter()
+            .cloned()
+            .zip(partial.chunks.iter().cloned())
+            .collect();
+        let node = |index: u64| {
+            nodes
+                .get(&index)
+                .cloned()
+                .ok_or(MultiproofError::MissingIndex(index))
+        };
+
+        let mut indices = leaves.to_vec();
+        indices.sort();
+        check_indices(&indices)?;
+
+        Ok(Self {
+            leaves: indices
+                .iter()
+                .map(|&index| node(index))
+                .collect::<Result<_, _>>()?,
+            proof: helper_indices(&indices)
+                .into_iter()
+                .rev()
+                .map(node)
+                .collect::<Result<_, _>>()?,
+            indices,
+        })
+    }
+
+    /// Returns the partial holding the leaves followed by the helper nodes, which may be verified
+    /// with `verify_partial`.
+    pub fn to_partial(&self) -> Result<SerializedPartial, MultiproofError> {
+        if self.indices.len() != self.leaves.len() {
+            return Err(MultiproofError::LengthMismatch {
+                indices: self.indices.len(),
+                leaves: self.leaves.len(),
+            });
+        }
+        check_indices(&self.indices)?;
+
+        let helpers: Vec<u64> = helper_indices(&self.indices).into_iter().rev().collect();
+        if helpers.len() != self.proof.len() {
+            return Err(MultiproofError::ProofLengthMismatch {
+                expected: helpers.len(),
+                found: self.proof.len(),
+            });
+        }
+
+        Ok(SerializedPartial {
+            indices: [&self.indices[..], &helpers[..]].concat(),
+            chunks: [&self.leaves[..], &self.proof[..]].concat(),
+        })
+    }
+}
+
+/// Checks that `indices` are non-zero and strictly ascending.
+fn check_indices(indices: &[u64]) -> Result<(), MultiproofError> {
+    if indices.first() == Some(&0) {
+        return Err(MultiproofError::ZeroIndex);
+    }
+    match indices.windows(2).find(|pair| pair[0] >= pair[1]) {
+        Some(pair) => Err(MultiproofError::UnorderedIndex(pair[1])),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::verify_partial;
+    use crate::tree::MerkleTree;
+
+    fn tree() -> MerkleTree {
+        MerkleTree::new((0..8).map(|i| H256::from([i + 1; 32])).collect())
+    }
+
+    fn proof_of(tree: &MerkleTree, leaves: &[usize]) -> SerializedPartial {
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        tree.append_proof(1, leaves, &mut partial);
+        partial
+    }
+
+    #[test]
+    fn orders_as_the_spec() {
+        let tree = tree();
+        let partial = proof_of(&tree, &[6, 1]);
+
+        let multiproof = Multiproof::from_partial(&partial, &[14, 9]).unwrap();
+
+        assert_eq!(multiproof.indices, vec![9, 14]);
+        assert_eq!(
+            multiproof.leaves,
+            vec![tree.node(9).unwrap(), tree.node(14).unwrap()]
+        );
+        assert_eq!(
+            multiproof.proof,
+            [15, 8, 6, 5]
+                .iter()
+                .map(|&index| tree.node(index).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn converts_to_a_valid_partial() {
+        let tree = tree();
+        let multiproof =
+            Multiproof::from_partial(&proof_of(&tree, &[0, 3, 4]), &[8, 11, 12]).unwrap();
+
+        let partial = multiproof.to_partial().unwrap();
+        let verification = verify_partial(&partial, tree.root()).unwrap();
+
+        assert!(verification.valid);
+        for index in &multiproof.indices {
+            assert!(verification.covered_paths.contains(index));
+        }
+        assert_eq!(
+            Multiproof::from_partial(&partial, &multiproof.indices),
+            Ok(multiproof)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_multiproofs() {
+        let tree = tree();
+        let partial = proof_of(&tree, &[0]);
+        let multiproof = Multiproof::from_partial(&partial, &[8]).unwrap();
+
+        assert_eq!(
+            Multiproof::from_partial(&partial, &[8, 12]),
+            Err(MultiproofError::MissingIndex(12))
+        );
+        assert_eq!(
+            Multiproof::from_partial(&partial, &[8, 8]),
+            Err(MultiproofError::UnorderedIndex(8))
+        );
+
+        let mut short = multiproof.clone();
+        short.proof.pop();
+        assert_eq!(
+            short.to_partial(),
+            Err(MultiproofError::ProofLengthMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+
+        let mut zero = multiproof.clone();
+        zero.indices[0] = 0;
+        assert_eq!(zero.to_partial(), Err(MultiproofError::ZeroIndex));
+
+        let mut unordered = multiproof;
+        unordered.indices.push(4);
+        unordered.leaves.push(H256::zero());
+        assert_eq!(
+            unordered.to_partial(),
+            Err(MultiproofError::UnorderedIndex(4))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::tree_arithmetic::{concat_generalized_indices, split_generalized_index};
+use ethereum_types::H256;
+use serde_derive::{Deserialize, Serialize};
+
+/// The number of bytes of a byte list packed into each chunk.
+const BYTES_PER_CHUNK: usize = 32;
+
+/// A leaf of the Merkle tree of a type, as listed by `MerkleTreeOverlay::schema`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaEntry {
+    /// The fields from the root to the leaf, separated by `.`, e.g., `latest_block_roots.3`.
+    pub path: String,
+    pub generalized_index: u64,
+    /// The number of bytes of the chunk which hold the value, e.g., `8` for a `u64`.
+    pub size: usize,
+    /// The position in the chunk of the first byte of the value.
+    pub offset: usize,
+}
+
+/// The shape of the Merkle tree of a type, i.e., which generalized indices are its nodes.
+pub trait MerkleTreeOverlay {
+    /// Returns `true` if the tree of the type has a node at `index`.
+    ///
+    /// Where the shape depends on the value, e.g., on the length of a list, a node of the tree of
+    /// any value is attached.
+    fn is_attached(index: u64) -> bool;
+
+    /// Returns the leaves of the tree which are attached whatever the value, in ascending order of
+    /// generalized index.
+    ///
+    /// The elements of a list are omitted, as their generalized indices depend on its length.
+    /// The length itself is a leaf.
+    fn schema() -> Vec<SchemaEntry>;
+}
+
+/// Implements `MerkleTreeOverlay` for a basic type of `$size` bytes, whose tree is a single chunk.
+macro_rules! impl_for_basic {
+    ($type: ty, $size: expr) => {
+        impl MerkleTreeOverlay for $type {
+            fn is_attached(index: u64) -> bool {
+                index == 1
+            }
+
+            fn schema() -> Vec<SchemaEntry> {
+                vec![SchemaEntry {
+                    path: String::new(),
+                    generalized_index: 1,
+                    size: $size,
+                    offset: 0,
+                }]
+            }
+        }
+    };
+}
+
+impl_for_basic!(u8, 1);
+impl_for_basic!(u16, 2);
+impl_for_basic!(u32, 4);
+impl_for_basic!(u64, 8);
+impl_for_basic!(usize, 8);
+impl_for_basic!(bool, 1);
+impl_for_basic!(H256, 32);
+impl_for_basic!([u8; 4], 4);
+impl_for_basic!([u8; 32], 32);
+
+/// A list of bytes is packed 32 bytes to a chunk, and its root is that of the chunks, padded to a
+/// power of two, hashed with the length. The root of the chunks is `2` and the length is `3`.
+///
+/// The number of chunks depends on the length, so every node below `2` is attached. See
+/// `byte_list_schema` for the chunks of a list of known length.
+impl MerkleTreeOverlay for Vec<u8> {
+    fn is_attached(index: u64) -> bool {
+        match index {
+            0 => false,
+            1 | 3 => true,
+            _ => split_generalized_index(index, 1).0 == 2,
+        }
+    }
+
+    fn schema() -> Vec<SchemaEntry> {
+        vec![SchemaEntry {
+            path: "len".to_string(),
+            generalized_index: 3,
+            size: 8,
+            offset: 0,
+        }]
+    }
+}
+
+/// Returns the generalized index of the chunk holding byte `byte` of a byte list of `len` bytes,
+/// or `None` if the list is no longer than `byte`.
+pub fn byte_list_chunk_index(len: usize, byte: usize) -> Option<u64> {
+    if byte >= len {
+        return None;
+    }
+    let chunks = (len + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
+
+    Some(concat_generalized_indices(
+        2,
+        (chunks.next_power_of_two() + byte / BYTES_PER_CHUNK) as u64,
+    ))
+}
+
+/// Returns the leaves of the tree of a byte list of `len` bytes, in ascending order of generalized
+/// index: the length and each chunk holding bytes of the list, as `0`, `1` and so on. The size of
+/// the last chunk is the number of bytes left over, and padding is not listed.
+pub fn byte_list_schema(len: usize) -> Vec<SchemaEntry> {
+    let mut schema = <Vec<u8>>::schema();
+    for byte in (0..len).step_by(BYTES_PER_CHUNK) {
+        schema.push(SchemaEntry {
+            path: (byte / BYTES_PER_CHUNK).to_string(),
+            generalized_index: byte_list_chunk_index(len, byte).expect("byte is within the list"),
+            size: std::cmp::min(BYTES_PER_CHUNK, len - byte),
+            offset: 0,
+        });
+    }
+    schema.sort_by_key(|entry| entry.generalized_index);
+
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{hash_concat, MerkleTree};
+    use tree_hash::TreeHash;
+
+    /// Returns the tree of the chunks of `bytes`, and its root hashed with the length.
+    fn byte_list_tree(bytes: &[u8]) -> (MerkleTree, H256) {
+        let chunks = bytes
+            .chunks(BYTES_PER_CHUNK)
+            .map(|chunk| {
+                let mut padded = [0; BYTES_PER_CHUNK];
+                padded[0..chunk.len()].copy_from_slice(chunk);
+                H256::from(padded)
+            })
+            .collect();
+        let tree = MerkleTree::new(chunks);
+
+        let mut len = [0; 32];
+        len[0..8].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        let root = hash_concat(tree.root(), H256::from(len));
+
+        (tree, root)
+    }
+
+    #[test]
+    fn byte_list_matches_tree_hash() {
+        for &len in &[0, 1, 32, 33, 70, 128, 129] {
+            let bytes: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let (tree, root) = byte_list_tree(&bytes);
+            assert_eq!(
+                root,
+                H256::from_slice(&bytes.tree_hash_root()),
+                "len {}",
+                len
+            );
+
+            let schema = byte_list_schema(len);
+            assert_eq!(schema.len(), 1 + (len + 31) / 32);
+            assert!(schema
+                .windows(2)
+                .all(|pair| pair[0].generalized_index < pair[1].generalized_index));
+
+            for entry in schema.iter().filter(|entry| entry.path != "len") {
+                assert!(<Vec<u8>>::is_attached(entry.generalized_index));
+                let (elements, within) = split_generalized_index(entry.generalized_index, 1);
+                assert_eq!(elements, 2);
+
+                let start = entry.path.parse::<usize>().unwrap() * BYTES_PER_CHUNK;
+                assert_eq!(
+                    &tree.node(within).unwrap().as_bytes()[0..entry.size],
+                    &bytes[start..start + entry.size]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn byte_list_chunk_indices() {
+        // 70 bytes fill 3 chunks, padded to 4 below the root of the chunks, `2`.
+        assert_eq!(byte_list_chunk_index(70, 0), Some(8));
+        assert_eq!(byte_list_chunk_index(70, 31), Some(8));
+        assert_eq!(byte_list_chunk_index(70, 32), Some(9));
+        assert_eq!(byte_list_chunk_index(70, 69), Some(10));
+        assert_eq!(byte_list_chunk_index(70, 70), None);
+        assert_eq!(byte_list_chunk_index(1, 0), Some(2));
+    }
+
+    #[test]
+    fn byte_list_attaches_chunks_and_length() {
+        let attached = <Vec<u8>>::is_attached;
+        assert!(attached(1));
+        assert!(attached(2));
+        assert!(attached(3));
+        assert!(attached(8));
+        assert!(attached(2 << 20));
+        assert!(!attached(0));
+        assert!(!attached(6));
+        assert!(!attached(7));
+    }
+}
@@ -0,0 +1,400 @@
+use crate::metrics;
+use crate::overlay::MerkleTreeOverlay;
+use crate::tree::hash_concat;
+use crate::tree_arithmetic::{
+    checked_generalized_index_depth, checked_left_child, parent, redundant_index, sibling,
+};
+use ethereum_types::H256;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A subset of the nodes of a Merkle tree, sufficient to prove some of its leaves.
+///
+/// Nodes are identified by generalized index: the root is `1` and the children of node `i` are
+/// `2i` and `2i + 1`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct SerializedPartial {
+    pub indices: Vec<u64>,
+    /// The value of the node at each of `indices`.
+    pub chunks: Vec<H256>,
+}
+
+/// The outcome of verifying a `SerializedPartial` against a root.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PartialVerification {
+    /// `true` if the partial hashes up to the expected root without contradiction.
+    pub valid: bool,
+    /// The deepest node whose supplied or computed value differs from the hash of its children.
+    ///
+    /// This is `1` if the partial is internally consistent but yields a different root.
+    pub first_bad_node: Option<u64>,
+    /// The leaves of the partial whose every ancestor was reconstructed, in ascending order.
+    pub covered_paths: Vec<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PartialError {
+    /// There must be exactly one chunk per index.
+    LengthMismatch { indices: usize, chunks: usize },
+    /// `0` is not a generalized index.
+    ZeroIndex,
+    /// The same index was given two different values.
+    ConflictingIndex(u64),
+    /// The same index was given more than once.
+    DuplicateIndex(u64),
+    /// The index is not a node of the tree of the type being proven.
+    UnattachedIndex(u64),
+    /// The index is an ancestor of another, so an internal node is presented as a leaf.
+    RedundantIndex(u64),
+    /// The children of the index would not fit in a `u64`.
+    IndexOverflow(u64),
+}
+
+impl SerializedPartial {
+    /// Checks that the partial is a well-formed proof into the tree of `T`, before it is
+    /// verified, merged or cached.
+    ///
+    /// There must be one chunk per index, and every index must be a node of the tree of `T`.
+    /// Indices may not repeat, nor may any be an ancestor of another: a node supplied along with
+    /// its descendants is both a leaf and an internal node of the partial, which invites
+    /// second-preimage tricks.
+    pub fn validate_structure<T: MerkleTreeOverlay>(&self) -> Result<(), PartialError> {
+        if self.indices.len() != self.chunks.len() {
+            return Err(PartialError::LengthMismatch {
+                indices: self.indices.len(),
+                chunks: self.chunks.len(),
+            });
+        }
+
+        let mut seen = BTreeSet::new();
+        for &index in &self.indices {
+            if index == 0 {
+                return Err(PartialError::ZeroIndex);
+            }
+            if !seen.insert(index) {
+                return Err(PartialError::DuplicateIndex(index));
+            }
+            if !T::is_attached(index) {
+                return Err(PartialError::UnattachedIndex(index));
+            }
+        }
+
+        match redundant_index(&self.indices) {
+            Some(index) => Err(PartialError::RedundantIndex(index)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Verifies that `partial` is a valid proof of its leaves against `root`.
+///
+/// Sibling nodes are hashed together, deepest first, until no more parents can be computed. A
+/// parent which was also supplied in `partial` must match the computed value.
+pub fn verify_partial(
+    partial: &SerializedPartial,
+    root: H256,
+) -> Result<PartialVerification, PartialError> {
+    let result = verify(partial, root);
+    metrics::observe_verification(&result);
+    result
+}
+
+fn verify(partial: &SerializedPartial, root: H256) -> Result<PartialVerification, PartialError> {
+    if partial.indices.len() != partial.chunks.len() {
+        return Err(PartialError::LengthMismatch {
+            indices: partial.indices.len(),
+            chunks: partial.chunks.len(),
+        });
+    }
+
+    let mut nodes: BTreeMap<u64, H256> = BTreeMap::new();
+    for (&index, &chunk) in partial.indices.iter().zip(&partial.chunks) {
+        checked_generalized_index_depth(index).map_err(|_| PartialError::ZeroIndex)?;
+        if nodes
+            .insert(index, chunk)
+            .map_or(false, |prev| prev != chunk)
+        {
+            return Err(PartialError::ConflictingIndex(index));
+        }
+    }
+
+    let mut leaves = vec![];
+    for &index in nodes.keys() {
+        let left = checked_left_child(index).map_err(|_| PartialError::IndexOverflow(index))?;
+        if !nodes.contains_key(&left) && !nodes.contains_key(&sibling(left)) {
+            leaves.push(index);
+        }
+    }
+
+    let mut first_bad_node = None;
+    // Nodes whose value was computed from their children, whether or not it was also supplied.
+    let mut computed: BTreeSet<u64> = BTreeSet::new();
+    let mut pending: BTreeSet<u64> = nodes.keys().cloned().collect();
+
+    while let Some(index) = pending.iter().next_back().cloned() {
+        pending.remove(&index);
+        if index == 1 {
+            continue;
+        }
+
+        let sibling = sibling(index);
+        let (left, right) = match (nodes.get(&(index & !1)), nodes.get(&(index | 1))) {
+            (Some(left), Some(right)) => (*left, *right),
+            _ => continue,
+        };
+        pending.remove(&sibling);
+
+        let parent = parent(index);
+        let value = hash_concat(left, right);
+        computed.insert(parent);
+
+        match nodes.get(&parent) {
+            Some(supplied) if *supplied != value => {
+                first_bad_node.get_or_insert(parent);
+            }
+            Some(_) => {}
+            None => {
+                nodes.insert(parent, value);
+                pending.insert(parent);
+            }
+        }
+    }
+
+    if first_bad_node.is_none() && nodes.get(&1).map_or(false, |node| *node != root) {
+        first_bad_node = Some(1);
+    }
+
+    let covered_paths = leaves
+        .into_iter()
+        .filter(|&leaf| {
+            let mut i = leaf;
+            while i > 1 {
+                i = parent(i);
+                if !computed.contains(&i) {
+                    return false;
+                }
+            }
+            true
+        })
+        .collect();
+
+    Ok(PartialVerification {
+        valid: first_bad_node.is_none() && nodes.get(&1) == Some(&root),
+        first_bad_node,
+        covered_paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overlay::SchemaEntry;
+    use hashing::hash;
+
+    fn hash_concat(h1: H256, h2: H256) -> H256 {
+        let mut preimage = h1.as_bytes().to_vec();
+        preimage.extend_from_slice(h2.as_bytes());
+        H256::from_slice(&hash(&preimage))
+    }
+
+    /// Returns the leaves `4..8` and root of a depth-2 tree.
+    fn tree() -> (Vec<H256>, H256) {
+        let leaves: Vec<H256> = (0..4).map(|i| H256::from([i as u8 + 1; 32])).collect();
+        let root = hash_concat(
+            hash_concat(leaves[0], leaves[1]),
+            hash_concat(leaves[2], leaves[3]),
+        );
+        (leaves, root)
+    }
+
+    #[test]
+    fn verifies_multiproof() {
+        let (leaves, root) = tree();
+        let partial = SerializedPartial {
+            indices: vec![4, 5, 3],
+            chunks: vec![leaves[0], leaves[1], hash_concat(leaves[2], leaves[3])],
+        };
+
+        let verification = verify_partial(&partial, root).unwrap();
+
+        assert!(verification.valid);
+        assert_eq!(verification.first_bad_node, None);
+        assert_eq!(verification.covered_paths, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn reports_deepest_bad_node() {
+        let (leaves, root) = tree();
+        let partial = SerializedPartial {
+            indices: vec![2, 3, 4, 5],
+            chunks: vec![
+                H256::zero(),
+                hash_concat(leaves[2], leaves[3]),
+                leaves[0],
+                leaves[1],
+            ],
+        };
+
+        let verification = verify_partial(&partial, root).unwrap();
+
+        assert!(!verification.valid);
+        assert_eq!(verification.first_bad_node, Some(2));
+    }
+
+    #[test]
+    fn reports_wrong_root() {
+        let (leaves, _) = tree();
+        let partial = SerializedPartial {
+            indices: vec![2, 3],
+            chunks: vec![leaves[0], leaves[1]],
+        };
+
+        let verification = verify_partial(&partial, H256::zero()).unwrap();
+
+        assert!(!verification.valid);
+        assert_eq!(verification.first_bad_node, Some(1));
+    }
+
+    #[test]
+    fn incomplete_partial_is_invalid() {
+        let (leaves, root) = tree();
+        let partial = SerializedPartial {
+            indices: vec![4, 5],
+            chunks: vec![leaves[0], leaves[1]],
+        };
+
+        let verification = verify_partial(&partial, root).unwrap();
+
+        assert!(!verification.valid);
+        assert_eq!(verification.first_bad_node, None);
+        assert!(verification.covered_paths.is_empty());
+    }
+
+    #[test]
+    fn supplied_ancestors_do_not_cover_leaves() {
+        let (leaves, root) = tree();
+        // Leaf 4 has no sibling, so nothing attests to its value.
+        let partial = SerializedPartial {
+            indices: vec![2, 3, 4],
+            chunks: vec![
+                hash_concat(leaves[0], leaves[1]),
+                hash_concat(leaves[2], leaves[3]),
+                H256::zero(),
+            ],
+        };
+
+        let verification = verify_partial(&partial, root).unwrap();
+
+        assert!(verification.valid);
+        assert_eq!(verification.covered_paths, vec![3]);
+    }
+
+    #[test]
+    fn rejects_malformed_partials() {
+        let chunk = H256::zero();
+
+        assert_eq!(
+            verify_partial(
+                &SerializedPartial {
+                    indices: vec![1, 2],
+                    chunks: vec![chunk],
+                },
+                chunk
+            ),
+            Err(PartialError::LengthMismatch {
+                indices: 2,
+                chunks: 1
+            })
+        );
+        assert_eq!(
+            verify_partial(
+                &SerializedPartial {
+                    indices: vec![0],
+                    chunks: vec![chunk],
+                },
+                chunk
+            ),
+            Err(PartialError::ZeroIndex)
+        );
+        assert_eq!(
+            verify_partial(
+                &SerializedPartial {
+                    indices: vec![2, 2],
+                    chunks: vec![chunk, H256::from([1; 32])],
+                },
+                chunk
+            ),
+            Err(PartialError::ConflictingIndex(2))
+        );
+        assert_eq!(
+            verify_partial(
+                &SerializedPartial {
+                    indices: vec![1 << 63, (1 << 63) + 1],
+                    chunks: vec![chunk, chunk],
+                },
+                chunk
+            ),
+            Err(PartialError::IndexOverflow(1 << 63))
+        );
+    }
+
+    /// The tree of four leaves built by `tree`.
+    struct FourLeaves;
+
+    impl MerkleTreeOverlay for FourLeaves {
+        fn is_attached(index: u64) -> bool {
+            index > 0 && index < 8
+        }
+
+        fn schema() -> Vec<SchemaEntry> {
+            (0..4)
+                .map(|leaf| SchemaEntry {
+                    path: leaf.to_string(),
+                    generalized_index: 4 + leaf,
+                    size: 32,
+                    offset: 0,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn validates_structure() {
+        let partial = |indices: Vec<u64>| SerializedPartial {
+            chunks: vec![H256::zero(); indices.len()],
+            indices,
+        };
+
+        assert_eq!(
+            partial(vec![4, 5, 3]).validate_structure::<FourLeaves>(),
+            Ok(())
+        );
+        assert_eq!(
+            partial(vec![4, 5, 4]).validate_structure::<FourLeaves>(),
+            Err(PartialError::DuplicateIndex(4))
+        );
+        assert_eq!(
+            partial(vec![8, 9, 5, 3]).validate_structure::<FourLeaves>(),
+            Err(PartialError::UnattachedIndex(8))
+        );
+        assert_eq!(
+            partial(vec![2, 3, 4]).validate_structure::<FourLeaves>(),
+            Err(PartialError::RedundantIndex(2))
+        );
+        assert_eq!(
+            partial(vec![0]).validate_structure::<FourLeaves>(),
+            Err(PartialError::ZeroIndex)
+        );
+
+        let mut mismatched = partial(vec![4, 5, 3]);
+        mismatched.chunks.pop();
+        assert_eq!(
+            mismatched.validate_structure::<FourLeaves>(),
+            Err(PartialError::LengthMismatch {
+                indices: 3,
+                chunks: 2
+            })
+        );
+    }
+}
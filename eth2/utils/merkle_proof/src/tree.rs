@@ -0,0 +1,149 @@
+use crate::partial::SerializedPartial;
+use crate::tree_arithmetic::{concat_generalized_indices, helper_indices};
+use ethereum_types::H256;
+use hashing::hash;
+use tree_hash::{pool, MERKLE_HASH_CHUNK};
+
+/// A complete binary Merkle tree, with every node kept in memory so that proofs may be produced.
+///
+/// The leaves are padded with zero chunks to the next power of two, matching `tree_hash`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleTree {
+    /// Nodes by generalized index. The element at `0` is unused.
+    nodes: Vec<H256>,
+    depth: u32,
+}
+
+impl MerkleTree {
+    /// Builds the tree over `leaves`. An empty list of leaves yields a single zero chunk.
+    pub fn new(mut leaves: Vec<H256>) -> Self {
+        let width = leaves.len().next_power_of_two();
+        leaves.resize(width, H256::zero());
+
+        let mut nodes = vec![H256::zero(); width];
+        nodes.append(&mut leaves);
+        for i in (1..width).rev() {
+            nodes[i] = hash_concat(nodes[2 * i], nodes[2 * i + 1]);
+        }
+
+        Self {
+            nodes,
+            depth: width.trailing_zeros(),
+        }
+    }
+
+    pub fn root(&self) -> H256 {
+        self.nodes[1]
+    }
+
+    /// The number of levels below the root.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns the node at generalized index `index`, if it is within the tree.
+    pub fn node(&self, index: u64) -> Option<H256> {
+        self.nodes
+            .get(index as usize)
+            .cloned()
+            .filter(|_| index > 0)
+    }
+
+    /// Returns the generalized index of leaf `i`.
+    pub fn leaf_index(&self, i: usize) -> u64 {
+        (1 << self.depth) + i as u64
+    }
+
+    /// Appends the leaves at positions `leaves` to `partial`, together with the nodes required to
+    /// hash them up to the root. All indices are relative to `root_index`, the generalized index
+    /// of this tree's root within some larger tree.
+    pub fn append_proof(&self, root_index: u64, leaves: &[usize], partial: &mut SerializedPartial) {
+        let leaves: Vec<u64> = leaves.iter().map(|&i| self.leaf_index(i)).collect();
+        self.append_helpers(root_index, &leaves, partial);
+        for index in leaves {
+            partial
+                .indices
+                .push(concat_generalized_indices(root_index, index));
+            partial.chunks.push(self.nodes[index as usize]);
+        }
+    }
+
+    /// Appends only the nodes required to hash `nodes` up to the root, omitting `nodes`
+    /// themselves. This is for nodes whose values are proven by a deeper tree.
+    pub fn append_helpers(&self, root_index: u64, nodes: &[u64], partial: &mut SerializedPartial) {
+        for index in helper_indices(nodes) {
+            partial
+                .indices
+                .push(concat_generalized_indices(root_index, index));
+            partial.chunks.push(self.nodes[index as usize]);
+        }
+    }
+}
+
+/// Hashes `left` followed by `right`, in a buffer from the `tree_hash` pool.
+pub(crate) fn hash_concat(left: H256, right: H256) -> H256 {
+    let mut preimage = pool::take(MERKLE_HASH_CHUNK);
+    preimage.extend_from_slice(left.as_bytes());
+    preimage.extend_from_slice(right.as_bytes());
+
+    let node = H256::from_slice(&hash(&preimage));
+    pool::give(preimage);
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::verify_partial;
+
+    fn leaves(n: u8) -> Vec<H256> {
+        (0..n).map(|i| H256::from([i + 1; 32])).collect()
+    }
+
+    #[test]
+    fn pads_to_power_of_two() {
+        let tree = MerkleTree::new(leaves(3));
+        let padded = MerkleTree::new(vec![leaves(3), vec![H256::zero()]].concat());
+
+        assert_eq!(tree.depth(), 2);
+        assert_eq!(tree.root(), padded.root());
+        assert_eq!(MerkleTree::new(leaves(1)).root(), leaves(1)[0]);
+        assert_eq!(MerkleTree::new(vec![]).root(), H256::zero());
+    }
+
+    #[test]
+    fn proofs_verify() {
+        let tree = MerkleTree::new(leaves(5));
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        tree.append_proof(1, &[0, 4], &mut partial);
+
+        let verification = verify_partial(&partial, tree.root()).unwrap();
+
+        assert!(verification.valid);
+        assert!(verification.covered_paths.contains(&tree.leaf_index(0)));
+        assert!(verification.covered_paths.contains(&tree.leaf_index(4)));
+    }
+
+    #[test]
+    fn nested_proofs_verify() {
+        let inner = MerkleTree::new(leaves(4));
+        let mut outer_leaves = leaves(2);
+        outer_leaves[1] = inner.root();
+        let outer = MerkleTree::new(outer_leaves);
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        outer.append_helpers(1, &[3], &mut partial);
+        inner.append_proof(3, &[2], &mut partial);
+
+        assert!(verify_partial(&partial, outer.root()).unwrap().valid);
+        // The leaf follows its helpers, the deepest of which is its sibling at `15`.
+        assert_eq!(concat_generalized_indices(3, inner.leaf_index(2)), 14);
+        assert_eq!(partial.indices, vec![2, 6, 15, 14]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Arithmetic on generalized indices.
+//!
+//! A generalized index identifies a node of a binary Merkle tree: the root is `1` and the children
+//! of node `i` are `2i` and `2i + 1`, so the depth of a node is the position of the highest set
+//! bit of its index. `0` is not a generalized index.
+//!
+//! The plain functions assume valid arguments, as when the indices are constants of a known
+//! tree. Each `checked_` variant instead returns an error for an argument which is not a
+//! generalized index, or for a result which would not fit in a `u64`, i.e., which would be deeper
+//! than depth `63`. These are for indices received from elsewhere, e.g., in a proof request.
+use std::collections::BTreeSet;
+
+/// The greatest depth of a node whose generalized index fits in a `u64`.
+pub const MAX_DEPTH: u32 = 63;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeArithmeticError {
+    /// `0` is not a generalized index.
+    ZeroIndex,
+    /// The resulting generalized index would be deeper than `MAX_DEPTH`.
+    Overflow,
+    /// The node at `ancestor` is not an ancestor of, nor the same node as, that at `descendant`.
+    NotAnAncestor { ancestor: u64, descendant: u64 },
+    /// The node at `index` is shallower than `depth`, so has no ancestor at that depth.
+    DepthBeyondIndex { index: u64, depth: u32 },
+}
+
+/// Returns the depth of the node at generalized index `index`, the root being at depth `0`.
+pub fn generalized_index_depth(index: u64) -> u32 {
+    MAX_DEPTH - index.leading_zeros()
+}
+
+/// As `generalized_index_depth`, returning an error for the index `0`.
+pub fn checked_generalized_index_depth(index: u64) -> Result<u32, TreeArithmeticError> {
+    if index == 0 {
+        Err(TreeArithmeticError::ZeroIndex)
+    } else {
+        Ok(generalized_index_depth(index))
+    }
+}
+
+/// Returns the generalized index, within the outer tree, of the node at `inner` within the
+/// subtree rooted at `outer`.
+pub fn concat_generalized_indices(outer: u64, inner: u64) -> u64 {
+    let depth = generalized_index_depth(inner);
+    (outer << depth) | (inner ^ (1 << depth))
+}
+
+/// As `concat_generalized_indices`, returning an error if the result would be deeper than
+/// `MAX_DEPTH`.
+pub fn checked_concat_generalized_indices(
+    outer: u64,
+    inner: u64,
+) -> Result<u64, TreeArithmeticError> {
+    let outer_depth = checked_generalized_index_depth(outer)?;
+    let inner_depth = checked_generalized_index_depth(inner)?;
+    if outer_depth + inner_depth > MAX_DEPTH {
+        return Err(TreeArithmeticError::Overflow);
+    }
+
+    Ok(concat_generalized_indices(outer, inner))
+}
+
+/// Splits `index` at `depth`, returning its ancestor at that depth and its generalized index
+/// within the subtree rooted at that ancestor. This is the inverse of `concat_generalized_indices`.
+///
+/// `depth` must not exceed the depth of `index`.
+pub fn split_generalized_index(index: u64, depth: u32) -> (u64, u64) {
+    let below = generalized_index_depth(index) - depth;
+    let ancestor = index >> below;
+    let relative = (1 << below) | (index & ((1 << below) - 1));
+    (ancestor, relative)
+}
+
+/// As `split_generalized_index`, returning an error if `depth` exceeds the depth of `index`.
+pub fn checked_split_generalized_index(
+    index: u64,
+    depth: u32,
+) -> Result<(u64, u64), TreeArithmeticError> {
+    if depth > checked_generalized_index_depth(index)? {
+        return Err(TreeArithmeticError::DepthBeyondIndex { index, depth });
+    }
+
+    Ok(split_generalized_index(index, depth))
+}
+
+/// Returns the number of levels from `ancestor` down to `descendant`, which is `0` if they are
+/// the same node.
+pub fn relative_depth(ancestor: u64, descendant: u64) -> Result<u32, TreeArithmeticError> {
+    let ancestor_depth = checked_generalized_index_depth(ancestor)?;
+    let descendant_depth = checked_generalized_index_depth(descendant)?;
+
+    if descendant_depth < ancestor_depth
+        || descendant >> (descendant_depth - ancestor_depth) != ancestor
+    {
+        return Err(TreeArithmeticError::NotAnAncestor {
+            ancestor,
+            descendant,
+        });
+    }
+
+    Ok(descendant_depth - ancestor_depth)
+}
+
+/// Returns `true` if the node at `ancestor` is a strict ancestor of that at `descendant`.
+pub fn is_ancestor(ancestor: u64, descendant: u64) -> bool {
+    relative_depth(ancestor, descendant).map_or(false, |depth| depth > 0)
+}
+
+/// Returns the generalized index of the parent of `index`, which must not be the root.
+pub fn parent(index: u64) -> u64 {
+    index / 2
+}
+
+/// Returns the generalized index of the other child of the parent of `index`, which must not be
+/// the root.
+pub fn sibling(index: u64) -> u64 {
+    index ^ 1
+}
+
+/// Returns the generalized index of the left child of `index`.
+pub fn left_child(index: u64) -> u64 {
+    2 * index
+}
+
+/// Returns the generalized index of the right child of `index`.
+pub fn right_child(index: u64) -> u64 {
+    2 * index + 1
+}
+
+/// As `left_child`, returning an error if the child would be deeper than `MAX_DEPTH`.
+pub fn checked_left_child(index: u64) -> Result<u64, TreeArithmeticError> {
+    if index == 0 {
+        return Err(TreeArithmeticError::ZeroIndex);
+    }
+    index.checked_mul(2).ok_or(TreeArithmeticError::Overflow)
+}
+
+/// As `right_child`, returning an error if the child would be deeper than `MAX_DEPTH`.
+pub fn checked_right_child(index: u64) -> Result<u64, TreeArithmeticError> {
+    // The left child is even, so one more cannot overflow.
+    checked_left_child(index).map(|left| left + 1)
+}
+
+/// Returns, in ascending order, the indices of the nodes besides `leaves` needed to compute the
+/// root from `leaves`.
+pub fn helper_indices(leaves: &[u64]) -> Vec<u64> {
+    let mut path = BTreeSet::new();
+    let mut siblings = BTreeSet::new();
+
+    for &leaf in leaves {
+        let mut index = leaf;
+        while index > 1 {
+            path.insert(index);
+            siblings.insert(sibling(index));
+            index = parent(index);
+        }
+    }
+
+    siblings.difference(&path).cloned().collect()
+}
+
+/// Returns any of `indices` which is a strict ancestor of another of `indices`.
+pub fn redundant_index(indices: &[u64]) -> Option<u64> {
+    let indices: BTreeSet<u64> = indices.iter().cloned().collect();
+
+    indices.iter().find_map(|&index| {
+        let mut ancestor = parent(index);
+        while ancestor > 0 {
+            if indices.contains(&ancestor) {
+                return Some(ancestor);
+            }
+            ancestor = parent(ancestor);
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every generalized index of depth at most 10.
+    fn indices() -> std::ops::Range<u64> {
+        1..(1 << 11)
+    }
+
+    #[test]
+    fn depth_and_children() {
+        assert_eq!(generalized_index_depth(1), 0);
+        assert_eq!(generalized_index_depth(u64::max_value()), MAX_DEPTH);
+        assert_eq!(
+            checked_generalized_index_depth(0),
+            Err(TreeArithmeticError::ZeroIndex)
+        );
+
+        for index in indices() {
+            let depth = generalized_index_depth(index);
+            assert!(index >= 1 << depth && index < 2 << depth);
+
+            let (left, right) = (left_child(index), right_child(index));
+            assert_eq!(checked_left_child(index), Ok(left));
+            assert_eq!(checked_right_child(index), Ok(right));
+            assert_eq!(generalized_index_depth(left), depth + 1);
+            assert_eq!(sibling(left), right);
+            assert_eq!(sibling(right), left);
+            assert_eq!(parent(left), index);
+            assert_eq!(parent(right), index);
+        }
+
+        let deepest = 1 << MAX_DEPTH;
+        assert_eq!(checked_left_child(deepest - 1), Ok(u64::max_value() - 1));
+        assert_eq!(checked_right_child(deepest - 1), Ok(u64::max_value()));
+        assert_eq!(
+            checked_left_child(deepest),
+            Err(TreeArithmeticError::Overflow)
+        );
+        assert_eq!(
+            checked_right_child(u64::max_value()),
+            Err(TreeArithmeticError::Overflow)
+        );
+        assert_eq!(checked_left_child(0), Err(TreeArithmeticError::ZeroIndex));
+    }
+
+    #[test]
+    fn concatenates_indices() {
+        assert_eq!(concat_generalized_indices(1, 5), 5);
+        assert_eq!(concat_generalized_indices(5, 1), 5);
+        assert_eq!(concat_generalized_indices(3, 4), 12);
+        assert_eq!(concat_generalized_indices(3, 5), 13);
+    }
+
+    #[test]
+    fn concat_and_split_are_inverse() {
+        for outer in 1..(1 << 6) {
+            for inner in 1..(1 << 6) {
+                let index = concat_generalized_indices(outer, inner);
+                let outer_depth = generalized_index_depth(outer);
+
+                assert_eq!(checked_concat_generalized_indices(outer, inner), Ok(index));
+                assert_eq!(
+                    generalized_index_depth(index),
+                    outer_depth + generalized_index_depth(inner)
+                );
+                assert_eq!(split_generalized_index(index, outer_depth), (outer, inner));
+                assert_eq!(
+                    relative_depth(outer, index),
+                    Ok(generalized_index_depth(inner))
+                );
+                assert_eq!(is_ancestor(outer, index), inner > 1);
+            }
+        }
+
+        for index in indices() {
+            for depth in 0..=generalized_index_depth(index) {
+                let (ancestor, relative) = split_generalized_index(index, depth);
+                assert_eq!(generalized_index_depth(ancestor), depth);
+                assert_eq!(concat_generalized_indices(ancestor, relative), index);
+                assert_eq!(
+                    checked_split_generalized_index(index, depth),
+                    Ok((ancestor, relative))
+                );
+            }
+            let depth = generalized_index_depth(index) + 1;
+            assert_eq!(
+                checked_split_generalized_index(index, depth),
+                Err(TreeArithmeticError::DepthBeyondIndex { index, depth })
+            );
+        }
+    }
+
+    #[test]
+    fn checked_concat_rejects_overflow() {
+        let deepest = 1 << MAX_DEPTH;
+        assert_eq!(
+            checked_concat_generalized_indices(2, deepest >> 1),
+            Ok(deepest)
+        );
+        assert_eq!(
+            checked_concat_generalized_indices(3, u64::max_value() >> 1),
+            Ok(u64::max_value())
+        );
+        assert_eq!(
+            checked_concat_generalized_indices(2, deepest),
+            Err(TreeArithmeticError::Overflow)
+        );
+        assert_eq!(
+            checked_concat_generalized_indices(deepest, 2),
+            Err(TreeArithmeticError::Overflow)
+        );
+        assert_eq!(
+            checked_concat_generalized_indices(0, 2),
+            Err(TreeArithmeticError::ZeroIndex)
+        );
+        assert_eq!(
+            checked_concat_generalized_indices(2, 0),
+            Err(TreeArithmeticError::ZeroIndex)
+        );
+    }
+
+    #[test]
+    fn relative_depth_requires_an_ancestor() {
+        for ancestor in 1..(1 << 9) {
+            for descendant in 1..(1 << 9) {
+                // Walk up from the descendant, one parent at a time.
+                let mut node = descendant;
+                let mut depth = 0;
+                let expected = loop {
+                    if node == ancestor {
+                        break Some(depth);
+                    }
+                    if node == 1 {
+                        break None;
+                    }
+                    node = parent(node);
+                    depth += 1;
+                };
+
+                match relative_depth(ancestor, descendant) {
+                    Ok(depth) => assert_eq!(Some(depth), expected),
+                    Err(e) => {
+                        assert_eq!(expected, None);
+                        assert_eq!(
+                            e,
+                            TreeArithmeticError::NotAnAncestor {
+                                ancestor,
+                                descendant
+                            }
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(relative_depth(0, 1), Err(TreeArithmeticError::ZeroIndex));
+    }
+
+    #[test]
+    fn helpers_and_redundancy() {
+        assert_eq!(helper_indices(&[4, 5]), vec![3]);
+        assert_eq!(helper_indices(&[4, 7]), vec![5, 6]);
+        assert_eq!(helper_indices(&[1]), Vec::<u64>::new());
+        assert_eq!(redundant_index(&[4, 5, 3]), None);
+        assert_eq!(redundant_index(&[2, 3, 4]), Some(2));
+        assert_eq!(redundant_index(&[9, 1]), Some(1));
+
+        // Every leaf of a tree, together with its helpers, has no redundant index.
+        for leaf in 8..16 {
+            let mut proof = helper_indices(&[leaf]);
+            assert_eq!(proof.len(), 3);
+            assert_eq!(redundant_index(&proof), None);
+            proof.push(leaf);
+            assert_eq!(redundant_index(&proof), None);
+            assert!(proof.iter().all(|&index| !is_ancestor(index, leaf)));
+        }
+    }
+}
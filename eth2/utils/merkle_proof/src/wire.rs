@@ -0,0 +1,533 @@
+//! A compact encoding of a `SerializedPartial`, in which the chunks may be compressed.
+//!
+//! The encoding is a one-byte flag, the number of chunks as a little-endian `u32`, the indices of
+//! the chunks and then the chunks, compressed as flagged. Proofs of zero-heavy regions of a state
+//! (e.g., empty histories) compress very well.
+//!
+//! The indices are given in one of two ways:
+//!
+//! - If the nodes are exactly the leaves of a tree descending from the root, which is the case for
+//!   any proof without redundant nodes, as a bitfield with a bit for each node of that tree in
+//!   descent (i.e., pre-) order, set if the node is a branch. The chunks are in the same order.
+//!   This costs about two bits per chunk.
+//! - Otherwise, as a list of little-endian `u64`.
+//!
+//! The low bits of the flag give the `Compression` and its high bit is set for the bitfield.
+//!
+//! Each codec is only available if its optional dependency, `snap` or `zstd`, is enabled.
+use crate::partial::SerializedPartial;
+use crate::tree_arithmetic::{
+    checked_generalized_index_depth, checked_left_child, checked_right_child,
+    checked_split_generalized_index,
+};
+use ethereum_types::H256;
+use std::convert::TryInto;
+
+const CHUNK_BYTES: usize = 32;
+const HEADER_BYTES: usize = 5;
+const BITFIELD_FLAG: u8 = 0x80;
+
+/// The compression of the chunks of an encoded partial.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Snappy,
+    Zstd,
+}
+
+impl Compression {
+    fn flag(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Snappy => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    fn from_flag(flag: u8) -> Option<Self> {
+        match flag {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Snappy),
+            2 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WireError {
+    /// The encoding ends within its header or indices.
+    TooShort,
+    UnknownFlag(u8),
+    /// The codec was not enabled when this crate was built.
+    UnsupportedCompression(Compression),
+    /// The partial has more chunks than the decoder accepts.
+    TooManyChunks {
+        chunks: usize,
+        max: usize,
+    },
+    /// There must be exactly one chunk per index.
+    LengthMismatch {
+        indices: usize,
+        chunks: usize,
+    },
+    /// `0` is not a generalized index.
+    ZeroIndex,
+    /// The bitfield does not describe a tree with one leaf per chunk, or is not zero-padded.
+    InvalidBitfield,
+    /// The chunks are not exactly 32 bytes per index, once decompressed.
+    InvalidPayloadLength {
+        expected: usize,
+        found: usize,
+    },
+    CompressionFailed(String),
+    DecompressionFailed(String),
+}
+
+impl SerializedPartial {
+    /// Encodes the partial, compressing its chunks with `compression`.
+    ///
+    /// If the indices are given as a bitfield, the chunks of the decoded partial will be in
+    /// descent order rather than the order of `self`.
+    pub fn to_wire_bytes(&self, compression: Compression) -> Result<Vec<u8>, WireError> {
+        if self.indices.len() != self.chunks.len() {
+            return Err(WireError::LengthMismatch {
+                indices: self.indices.len(),
+                chunks: self.chunks.len(),
+            });
+        }
+        let count: u32 = self
+            .indices
+            .len()
+            .try_into()
+            .map_err(|_| WireError::TooManyChunks {
+                chunks: self.indices.len(),
+                max: u32::max_value() as usize,
+            })?;
+        if self.indices.contains(&0) {
+            return Err(WireError::ZeroIndex);
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.indices.len() * 8);
+        let mut payload = Vec::with_capacity(self.chunks.len() * CHUNK_BYTES);
+
+        match descent_order(&self.indices) {
+            Some((branches, order)) => {
+                bytes.push(compression.flag() | BITFIELD_FLAG);
+                bytes.extend_from_slice(&count.to_le_bytes());
+
+                let mut bitfield = vec![0; bitfield_bytes(self.indices.len())];
+                for (i, _) in branches.iter().enumerate().filter(|(_, branch)| **branch) {
+                    bitfield[i / 8] |= 1 << (i % 8);
+                }
+                bytes.extend_from_slice(&bitfield);
+
+                for i in order {
+                    payload.extend_from_slice(self.chunks[i].as_bytes());
+                }
+            }
+            None => {
+                bytes.push(compression.flag());
+                bytes.extend_from_slice(&count.to_le_bytes());
+                for index in &self.indices {
+                    bytes.extend_from_slice(&index.to_le_bytes());
+                }
+
+                for chunk in &self.chunks {
+                    payload.extend_from_slice(chunk.as_bytes());
+                }
+            }
+        }
+        bytes.extend_from_slice(&compress(compression, &payload)?);
+
+        Ok(bytes)
+    }
+
+    /// Decodes a partial, refusing any with more than `max_chunks` chunks.
+    ///
+    /// The size of the decompressed chunks is known from the number of indices, so no more is
+    /// ever decompressed.
+    pub fn from_wire_bytes(bytes: &[u8], max_chunks: usize) -> Result<Self, WireError> {
+        if bytes.len() < HEADER_BYTES {
+            return Err(WireError::TooShort);
+        }
+        let flag = bytes[0];
+        let compression =
+            Compression::from_flag(flag & !BITFIELD_FLAG).ok_or(WireError::UnknownFlag(flag))?;
+        let count = read_u32(&bytes[1..HEADER_BYTES]) as usize;
+        if count > max_chunks {
+            return Err(WireError::TooManyChunks {
+                chunks: count,
+                max: max_chunks,
+            });
+        }
+
+        let is_bitfield = flag & BITFIELD_FLAG != 0;
+        let indices_end = if is_bitfield {
+            HEADER_BYTES + bitfield_bytes(count)
+        } else {
+            HEADER_BYTES + count * 8
+        };
+        if bytes.len() < indices_end {
+            return Err(WireError::TooShort);
+        }
+        let indices = if is_bitfield {
+            descent_indices(&bytes[HEADER_BYTES..indices_end], count)?
+        } else {
+            bytes[HEADER_BYTES..indices_end]
+                .chunks(8)
+                .map(read_u64)
+                .collect()
+        };
+        if indices.contains(&0) {
+            return Err(WireError::ZeroIndex);
+        }
+
+        let payload = decompress(compression, &bytes[indices_end..], count * CHUNK_BYTES)?;
+        if payload.len() != count * CHUNK_BYTES {
+            return Err(WireError::InvalidPayloadLength {
+                expected: count * CHUNK_BYTES,
+                found: payload.len(),
+            });
+        }
+        let chunks = payload.chunks(CHUNK_BYTES).map(H256::from_slice).collect();
+
+        Ok(SerializedPartial { indices, chunks })
+    }
+}
+
+/// Returns the length of the bitfield of a tree with `leaves` leaves, i.e., with `2 * leaves - 1`
+/// nodes.
+fn bitfield_bytes(leaves: usize) -> usize {
+    (2 * leaves + 6) / 8
+}
+
+/// If `indices` are exactly the leaves of a tree descending from the root, returns whether each
+/// node of the tree is a branch and the position in `indices` of each leaf, both in descent
+/// order.
+fn descent_order(indices: &[u64]) -> Option<(Vec<bool>, Vec<usize>)> {
+    let mut branches = Vec::with_capacity(indices.len() * 2);
+    let mut order = Vec::with_capacity(indices.len());
+    descend(
+        1,
+        (0..indices.len()).collect(),
+        indices,
+        &mut branches,
+        &mut order,
+    )?;
+
+    Some((branches, order))
+}
+
+/// Visits the subtree at `node`, of which `members` are the positions of the indices within it.
+fn descend(
+    node: u64,
+    members: Vec<usize>,
+    indices: &[u64],
+    branches: &mut Vec<bool>,
+    order: &mut Vec<usize>,
+) -> Option<()> {
+    match members.as_slice() {
+        [] => None,
+        [member] if indices[*member] == node => {
+            branches.push(false);
+            order.push(*member);
+            Some(())
+        }
+        _ if members.iter().any(|member| indices[*member] == node) => None,
+        _ => {
+            let depth = checked_generalized_index_depth(node).ok()?;
+            let left_child = checked_left_child(node).ok()?;
+            let mut left = vec![];
+            let mut right = vec![];
+            for member in members {
+                // Fails for the index `0`, which lies in no subtree.
+                let (child, _) =
+                    checked_split_generalized_index(indices[member], depth + 1).ok()?;
+                if child == left_child {
+                    left.push(member);
+                } else {
+                    right.push(member);
+                }
+            }
+
+            branches.push(true);
+            descend(left_child, left, indices, branches, order)?;
+            descend(
+                checked_right_child(node).ok()?,
+                right,
+                indices,
+                branches,
+                order,
+            )
+        }
+    }
+}
+
+/// Returns the leaves, in descent order, of the tree with `count` leaves described by `bitfield`.
+fn descent_indices(bitfield: &[u8], count: usize) -> Result<Vec<u64>, WireError> {
+    let mut indices = Vec::with_capacity(count);
+    let mut pending = vec![1_u64];
+    let mut position = 0;
+
+    while let Some(node) = pending.pop() {
+        let byte = bitfield
+            .get(position / 8)
+            .ok_or(WireError::InvalidBitfield)?;
+        let is_branch = (byte >> (position % 8)) & 1 == 1;
+        position += 1;
+
+        if is_branch {
+            let left = checked_left_child(node).map_err(|_| WireError::InvalidBitfield)?;
+            pending.push(checked_right_child(node).map_err(|_| WireError::InvalidBitfield)?);
+            pending.push(left);
+        } else if indices.len() < count {
+            indices.push(node);
+        } else {
+            return Err(WireError::InvalidBitfield);
+        }
+    }
+
+    let is_padded = (position..bitfield.len() * 8).all(|i| (bitfield[i / 8] >> (i % 8)) & 1 == 0);
+    if indices.len() != count || !is_padded {
+        return Err(WireError::InvalidBitfield);
+    }
+
+    Ok(indices)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut array = [0; 4];
+    array.copy_from_slice(bytes);
+    u32::from_le_bytes(array)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut array = [0; 8];
+    array.copy_from_slice(bytes);
+    u64::from_le_bytes(array)
+}
+
+fn compress(compression: Compression, payload: &[u8]) -> Result<Vec<u8>, WireError> {
+    match compression {
+        Compression::None => Ok(payload.to_vec()),
+        #[cfg(feature = "snap")]
+        Compression::Snappy => snap::Encoder::new()
+            .compress_vec(payload)
+            .map_err(|e| WireError::CompressionFailed(format!("{:?}", e))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => zstd::block::compress(payload, 0)
+            .map_err(|e| WireError::CompressionFailed(format!("{:?}", e))),
+        #[allow(unreachable_patterns)]
+        _ => Err(WireError::UnsupportedCompression(compression)),
+    }
+}
+
+/// Decompresses `payload`, failing if it would exceed `expected` bytes.
+fn decompress(
+    compression: Compression,
+    payload: &[u8],
+    expected: usize,
+) -> Result<Vec<u8>, WireError> {
+    match compression {
+        Compression::None if payload.len() != expected => Err(WireError::InvalidPayloadLength {
+            expected,
+            found: payload.len(),
+        }),
+        Compression::None => Ok(payload.to_vec()),
+        #[cfg(feature = "snap")]
+        Compression::Snappy => {
+            let found = snap::decompress_len(payload)
+                .map_err(|e| WireError::DecompressionFailed(format!("{:?}", e)))?;
+            if found != expected {
+                return Err(WireError::InvalidPayloadLength { expected, found });
+            }
+            snap::Decoder::new()
+                .decompress_vec(payload)
+                .map_err(|e| WireError::DecompressionFailed(format!("{:?}", e)))
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => zstd::block::decompress(payload, expected)
+            .map_err(|e| WireError::DecompressionFailed(format!("{:?}", e))),
+        #[allow(unreachable_patterns)]
+        _ => Err(WireError::UnsupportedCompression(compression)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A partial of every node at depth 6, which are the leaves of a full tree.
+    fn partial() -> SerializedPartial {
+        let mut chunks = vec![H256::zero(); 64];
+        chunks[3] = H256::from_low_u64_le(42);
+        SerializedPartial {
+            indices: (64..128).collect(),
+            chunks,
+        }
+    }
+
+    fn chunks(n: u64) -> Vec<H256> {
+        (0..n).map(H256::from_low_u64_le).collect()
+    }
+
+    fn round_trip(compression: Compression) -> usize {
+        let partial = partial();
+        let bytes = partial.to_wire_bytes(compression).unwrap();
+        assert_eq!(SerializedPartial::from_wire_bytes(&bytes, 64), Ok(partial));
+        bytes.len()
+    }
+
+    #[test]
+    fn uncompressed_round_trip() {
+        assert_eq!(round_trip(Compression::None), HEADER_BYTES + 16 + 64 * 32);
+    }
+
+    #[test]
+    #[cfg(feature = "snap")]
+    fn snappy_round_trip() {
+        assert!(round_trip(Compression::Snappy) < round_trip(Compression::None));
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd_round_trip() {
+        assert!(round_trip(Compression::Zstd) < round_trip(Compression::None));
+    }
+
+    #[test]
+    fn bitfield_is_in_descent_order() {
+        let partial = SerializedPartial {
+            indices: vec![3, 5, 4],
+            chunks: chunks(3),
+        };
+        let bytes = partial.to_wire_bytes(Compression::None).unwrap();
+
+        // Nodes 1, 2, 4, 5, 3, of which 1 and 2 are branches.
+        assert_eq!(bytes[0], BITFIELD_FLAG);
+        assert_eq!(bytes[HEADER_BYTES], 0b00011);
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes, 3),
+            Ok(SerializedPartial {
+                indices: vec![4, 5, 3],
+                chunks: vec![partial.chunks[2], partial.chunks[1], partial.chunks[0]],
+            })
+        );
+    }
+
+    #[test]
+    fn lists_indices_which_are_not_a_tree() {
+        // Node 3 is missing, node 2 is redundant and node 1 is the whole tree.
+        for indices in &[vec![4, 5], vec![2, 4, 5, 3], vec![1, 2]] {
+            let partial = SerializedPartial {
+                indices: indices.clone(),
+                chunks: chunks(indices.len() as u64),
+            };
+            let bytes = partial.to_wire_bytes(Compression::None).unwrap();
+
+            assert_eq!(bytes[0], Compression::None.flag());
+            assert_eq!(
+                SerializedPartial::from_wire_bytes(&bytes, indices.len()),
+                Ok(partial)
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_zero_index() {
+        let partial = SerializedPartial {
+            indices: vec![0, 2, 3],
+            chunks: chunks(3),
+        };
+        assert_eq!(
+            partial.to_wire_bytes(Compression::None),
+            Err(WireError::ZeroIndex)
+        );
+
+        let mut bytes = SerializedPartial {
+            indices: vec![2, 4, 5],
+            chunks: chunks(3),
+        }
+        .to_wire_bytes(Compression::None)
+        .unwrap();
+        bytes[HEADER_BYTES..HEADER_BYTES + 8].copy_from_slice(&0_u64.to_le_bytes());
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes, 3),
+            Err(WireError::ZeroIndex)
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_encodings() {
+        let bytes = partial().to_wire_bytes(Compression::None).unwrap();
+
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes, 63),
+            Err(WireError::TooManyChunks {
+                chunks: 64,
+                max: 63
+            })
+        );
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes[..HEADER_BYTES + 15], 64),
+            Err(WireError::TooShort)
+        );
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes[..bytes.len() - 1], 64),
+            Err(WireError::InvalidPayloadLength {
+                expected: 64 * 32,
+                found: 64 * 32 - 1
+            })
+        );
+
+        let mut unknown = bytes.clone();
+        unknown[0] = 9;
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&unknown, 64),
+            Err(WireError::UnknownFlag(9))
+        );
+
+        // The padding of the bitfield must be zero.
+        let mut padded = bytes.clone();
+        padded[HEADER_BYTES + 15] |= 0x80;
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&padded, 64),
+            Err(WireError::InvalidBitfield)
+        );
+
+        // A tree with fewer leaves than chunks.
+        let mut short_tree = bytes.clone();
+        short_tree[HEADER_BYTES] = 0;
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&short_tree, 64),
+            Err(WireError::InvalidBitfield)
+        );
+
+        // A tree with more leaves than chunks.
+        let mut long_tree = bytes;
+        long_tree[HEADER_BYTES + 8] = 0xff;
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&long_tree, 64),
+            Err(WireError::InvalidBitfield)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "snap")]
+    fn refuses_oversized_decompression() {
+        let mut bytes = partial().to_wire_bytes(Compression::None).unwrap();
+        let payload = vec![0; 64 * 32 + 1];
+        bytes.truncate(HEADER_BYTES + 16);
+        bytes[0] = Compression::Snappy.flag() | BITFIELD_FLAG;
+        bytes.extend(snap::Encoder::new().compress_vec(&payload).unwrap());
+
+        assert_eq!(
+            SerializedPartial::from_wire_bytes(&bytes, 64),
+            Err(WireError::InvalidPayloadLength {
+                expected: 64 * 32,
+                found: 64 * 32 + 1
+            })
+        );
+    }
+}
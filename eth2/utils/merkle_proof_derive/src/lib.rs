@@ -0,0 +1,120 @@
+#![recursion_limit = "256"]
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, DeriveInput};
+
+/// Returns the ident and type of each named field in the struct, whilst filtering out fields
+/// that are not part of the tree.
+///
+/// # Panics
+/// Any unnamed struct field (like in a tuple struct) will raise a panic at compile time.
+fn get_overlaid_named_fields(struct_data: &syn::DataStruct) -> Vec<(&syn::Ident, &syn::Type)> {
+    struct_data
+        .fields
+        .iter()
+        .filter_map(|f| {
+            if should_skip_overlay(f) {
+                None
+            } else {
+                Some(match &f.ident {
+                    Some(ref ident) => (ident, &f.ty),
+                    _ => panic!("merkle_proof_derive only supports named struct fields."),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Returns true if some field has an attribute declaring it is not part of the tree.
+///
+/// The field attribute is: `#[merkle_partial(skip)]`
+fn should_skip_overlay(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        attr.into_token_stream().to_string().replace(' ', "") == "#[merkle_partial(skip)]"
+    })
+}
+
+/// Implements `merkle_proof::MerkleTreeOverlay` for some `struct`.
+///
+/// Each field is a leaf, in the order they are defined, and the leaves are padded to a power of
+/// two, as when tree hashing. The type of each field must implement `MerkleTreeOverlay`, unless
+/// the field is marked `#[merkle_partial(skip)]`, which leaves it out of the tree; mark any field
+/// marked `#[tree_hash(skip_hashing)]` likewise.
+#[proc_macro_derive(MerkleTreeOverlay, attributes(merkle_partial))]
+pub fn merkle_tree_overlay_derive(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let struct_data = match &item.data {
+        syn::Data::Struct(s) => s,
+        _ => panic!("merkle_proof_derive only supports structs."),
+    };
+
+    let fields = get_overlaid_named_fields(struct_data);
+    if fields.is_empty() {
+        panic!("merkle_proof_derive requires at least one field in the tree.");
+    }
+
+    let depth = fields.len().next_power_of_two().trailing_zeros();
+    let leaves_a: Vec<u64> = (0..fields.len() as u64).map(|i| (1 << depth) + i).collect();
+    let leaves_b = leaves_a.clone();
+    let types_a: Vec<&syn::Type> = fields.iter().map(|(_, ty)| *ty).collect();
+    let types_b = types_a.clone();
+    let names: Vec<String> = fields.iter().map(|(ident, _)| ident.to_string()).collect();
+
+    let output = quote! {
+        impl #impl_generics merkle_proof::MerkleTreeOverlay for #name #ty_generics #where_clause {
+            fn is_attached(index: u64) -> bool {
+                if index == 0 {
+                    return false;
+                }
+                if merkle_proof::generalized_index_depth(index) <= #depth {
+                    return true;
+                }
+
+                let (leaf, within) =
+                    merkle_proof::tree_arithmetic::split_generalized_index(index, #depth);
+                #(
+                    if leaf == #leaves_a {
+                        return <#types_a as merkle_proof::MerkleTreeOverlay>::is_attached(within);
+                    }
+                )*
+
+                // Nothing lies below the padding.
+                false
+            }
+
+            fn schema() -> Vec<merkle_proof::SchemaEntry> {
+                let mut schema = vec![];
+
+                #(
+                    let name = #names;
+                    for entry in <#types_b as merkle_proof::MerkleTreeOverlay>::schema() {
+                        let path = if entry.path.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{}.{}", name, entry.path)
+                        };
+                        schema.push(merkle_proof::SchemaEntry {
+                            path,
+                            generalized_index: merkle_proof::concat_generalized_indices(
+                                #leaves_b,
+                                entry.generalized_index,
+                            ),
+                            size: entry.size,
+                            offset: entry.offset,
+                        });
+                    }
+                )*
+
+                schema.sort_by_key(|entry| entry.generalized_index);
+                schema
+            }
+        }
+    };
+    output.into()
+}
@@ -0,0 +1,69 @@
+use ethereum_types::H256;
+use merkle_proof::{MerkleTreeOverlay, SchemaEntry};
+use merkle_proof_derive::MerkleTreeOverlay;
+
+#[derive(MerkleTreeOverlay)]
+pub struct Inner {
+    pub a: u64,
+    pub b: H256,
+}
+
+#[derive(MerkleTreeOverlay)]
+pub struct Outer {
+    pub x: u64,
+    #[merkle_partial(skip)]
+    pub cache: Vec<String>,
+    pub inner: Inner,
+    pub y: bool,
+}
+
+/// `Outer` without its cache.
+#[derive(MerkleTreeOverlay)]
+pub struct Uncached {
+    pub x: u64,
+    pub inner: Inner,
+    pub y: bool,
+}
+
+fn entry(path: &str, generalized_index: u64, size: usize) -> SchemaEntry {
+    SchemaEntry {
+        path: path.to_string(),
+        generalized_index,
+        size,
+        offset: 0,
+    }
+}
+
+#[test]
+fn schema_descends_into_fields() {
+    // The three fields of `Outer` are the leaves 4, 5 and 6, with 7 as padding; those of `Inner`
+    // are 10 and 11, the children of 5.
+    assert_eq!(
+        Outer::schema(),
+        vec![
+            entry("x", 4, 8),
+            entry("y", 6, 1),
+            entry("inner.a", 10, 8),
+            entry("inner.b", 11, 32),
+        ]
+    );
+}
+
+#[test]
+fn skipped_fields_are_excluded() {
+    assert_eq!(Outer::schema(), Uncached::schema());
+    for index in 0..64 {
+        assert_eq!(Outer::is_attached(index), Uncached::is_attached(index));
+    }
+}
+
+#[test]
+fn attaches_nodes_of_fields() {
+    for &index in &[1, 2, 3, 4, 5, 6, 7, 10, 11] {
+        assert!(Outer::is_attached(index), "{} is attached", index);
+    }
+    // Below a basic field, below the padding, below the leaves of `Inner`, and the zero index.
+    for &index in &[0, 8, 12, 14, 20, 23] {
+        assert!(!Outer::is_attached(index), "{} is not attached", index);
+    }
+}
@@ -0,0 +1,19 @@
+//! Serializes a `Vec<u8>` as a 0x-prefixed hex string.
+//!
+//! Use with `#[serde(with = "serde_hex::bytes_hex")]`.
+use crate::{encode, PrefixedHexVisitor};
+use serde::{Deserializer, Serializer};
+
+pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&encode(bytes))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(PrefixedHexVisitor)
+}
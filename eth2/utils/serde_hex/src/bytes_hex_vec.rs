@@ -0,0 +1,34 @@
+//! Serializes a `Vec<Vec<u8>>` as a list of 0x-prefixed hex strings.
+//!
+//! Use with `#[serde(with = "serde_hex::bytes_hex_vec")]`.
+use crate::bytes_hex;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(transparent)]
+struct HexRef<'a>(#[serde(with = "bytes_hex")] &'a [u8]);
+
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct Hex(#[serde(with = "bytes_hex")] Vec<u8>);
+
+pub fn serialize<S>(list: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(list.len()))?;
+    for bytes in list {
+        seq.serialize_element(&HexRef(bytes))?;
+    }
+    seq.end()
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let list: Vec<Hex> = Deserialize::deserialize(deserializer)?;
+    Ok(list.into_iter().map(|Hex(bytes)| bytes).collect())
+}
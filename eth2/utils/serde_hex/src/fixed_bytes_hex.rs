@@ -0,0 +1,37 @@
+//! Serializes a fixed-length byte array (e.g., `[u8; 4]`) as a 0x-prefixed hex string.
+//!
+//! Deserialization fails unless the string decodes to exactly the length of the array.
+//!
+//! Use with `#[serde(with = "serde_hex::fixed_bytes_hex")]`.
+use crate::{encode, PrefixedHexVisitor};
+use serde::de::Error;
+use serde::{Deserializer, Serializer};
+
+pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<[u8]>,
+    S: Serializer,
+{
+    serializer.serialize_str(&encode(bytes))
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Default + AsMut<[u8]>,
+    D: Deserializer<'de>,
+{
+    let decoded = deserializer.deserialize_str(PrefixedHexVisitor)?;
+
+    let mut array = T::default();
+    let expected = array.as_mut().len();
+    if decoded.len() != expected {
+        return Err(D::Error::custom(format!(
+            "expected {} bytes, got {}",
+            expected,
+            decoded.len()
+        )));
+    }
+    array.as_mut().copy_from_slice(&decoded);
+
+    Ok(array)
+}
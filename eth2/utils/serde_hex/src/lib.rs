@@ -1,8 +1,19 @@
+//! Utilities for (de)serializing bytes as hex strings.
+//!
+//! Besides the visitors, this crate provides modules for use with `#[serde(with = "...")]`, so
+//! that fields are serialized consistently without per-field functions.
 use hex;
 use hex::ToHex;
 use serde::de::{self, Visitor};
 use std::fmt;
 
+pub mod bytes_hex;
+pub mod bytes_hex_vec;
+pub mod fixed_bytes_hex;
+pub mod quoted_u64;
+pub mod quoted_u64_vec;
+pub mod u8_hex;
+
 pub fn encode<T: AsRef<[u8]>>(data: T) -> String {
     let mut hex = String::with_capacity(data.as_ref().len() * 2);
 
@@ -74,4 +85,80 @@ mod test {
         let hex = encode(&bytes);
         assert_eq!(hex.as_str(), "0x010203");
     }
+
+    #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+    struct Adapted {
+        #[serde(with = "fixed_bytes_hex")]
+        fixed: [u8; 4],
+        #[serde(with = "bytes_hex")]
+        bytes: Vec<u8>,
+        #[serde(with = "u8_hex")]
+        byte: u8,
+        #[serde(with = "quoted_u64")]
+        quoted: u64,
+        #[serde(with = "bytes_hex_vec")]
+        bytes_list: Vec<Vec<u8>>,
+        #[serde(with = "quoted_u64_vec")]
+        quoted_list: Vec<u64>,
+    }
+
+    fn adapted() -> Adapted {
+        Adapted {
+            fixed: [0, 1, 2, 255],
+            bytes: vec![10, 11],
+            byte: 16,
+            quoted: u64::max_value(),
+            bytes_list: vec![vec![], vec![1]],
+            quoted_list: vec![0, 42],
+        }
+    }
+
+    #[test]
+    fn adapters_round_trip() {
+        let json = serde_json::to_value(adapted()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "fixed": "0x000102ff",
+                "bytes": "0x0a0b",
+                "byte": "0x10",
+                "quoted": "18446744073709551615",
+                "bytes_list": ["0x", "0x01"],
+                "quoted_list": ["0", "42"],
+            })
+        );
+
+        assert_eq!(serde_json::from_value::<Adapted>(json).unwrap(), adapted());
+    }
+
+    #[test]
+    fn quoted_u64_accepts_numbers() {
+        let json = serde_json::json!({
+            "fixed": "0x000102ff",
+            "bytes": "0x0a0b",
+            "byte": "0x10",
+            "quoted": 18_446_744_073_709_551_615u64,
+            "bytes_list": ["0x", "0x01"],
+            "quoted_list": [0, "42"],
+        });
+
+        assert_eq!(serde_json::from_value::<Adapted>(json).unwrap(), adapted());
+    }
+
+    #[test]
+    fn adapters_reject_bad_input() {
+        let valid = serde_json::to_value(adapted()).unwrap();
+        let invalid = |field: &str, value: serde_json::Value| {
+            let mut json = valid.clone();
+            json[field] = value;
+            serde_json::from_value::<Adapted>(json).is_err()
+        };
+
+        assert!(invalid("fixed", serde_json::json!("0x000102")));
+        assert!(invalid("fixed", serde_json::json!("0x00010203ff")));
+        assert!(invalid("bytes", serde_json::json!("0a0b")));
+        assert!(invalid("byte", serde_json::json!("0x1011")));
+        assert!(invalid("quoted", serde_json::json!("-1")));
+        assert!(invalid("bytes_list", serde_json::json!(["0xzz"])));
+    }
 }
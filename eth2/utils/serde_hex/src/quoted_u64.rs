@@ -0,0 +1,50 @@
+//! Serializes a `u64` as a decimal string, e.g., `"32000000000"`, so that it survives JSON
+//! parsers which read numbers as doubles.
+//!
+//! Both strings and plain numbers are accepted when deserializing.
+//!
+//! Use with `#[serde(with = "serde_hex::quoted_u64")]`.
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt;
+
+pub struct QuotedU64Visitor;
+
+impl<'de> Visitor<'de> for QuotedU64Visitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a u64, optionally quoted")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        value
+            .parse()
+            .map_err(|e| de::Error::custom(format!("invalid u64 ({:?})", e)))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(value)
+    }
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // Serde requires the `value` to be a ref.
+pub fn serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_string())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(QuotedU64Visitor)
+}
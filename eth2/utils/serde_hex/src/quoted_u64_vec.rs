@@ -0,0 +1,30 @@
+//! Serializes a `Vec<u64>` as a list of decimal strings.
+//!
+//! Use with `#[serde(with = "serde_hex::quoted_u64_vec")]`.
+use crate::quoted_u64;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct Quoted(#[serde(with = "quoted_u64")] u64);
+
+pub fn serialize<S>(list: &[u64], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(list.len()))?;
+    for &value in list {
+        seq.serialize_element(&Quoted(value))?;
+    }
+    seq.end()
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let list: Vec<Quoted> = Deserialize::deserialize(deserializer)?;
+    Ok(list.into_iter().map(|Quoted(value)| value).collect())
+}
@@ -0,0 +1,29 @@
+//! Serializes a `u8` as a 0x-prefixed hex string of one byte, e.g., `0x0a`.
+//!
+//! Use with `#[serde(with = "serde_hex::u8_hex")]`.
+use crate::{encode, PrefixedHexVisitor};
+use serde::de::Error;
+use serde::{Deserializer, Serializer};
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // Serde requires the `byte` to be a ref.
+pub fn serialize<S>(byte: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&encode([*byte]))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let decoded = deserializer.deserialize_str(PrefixedHexVisitor)?;
+
+    match decoded.as_slice() {
+        [byte] => Ok(*byte),
+        _ => Err(D::Error::custom(format!(
+            "expected 1 byte, got {}",
+            decoded.len()
+        ))),
+    }
+}
@@ -3,11 +3,12 @@ mod testing_slot_clock;
 
 pub use crate::system_time_slot_clock::{Error as SystemTimeSlotClockError, SystemTimeSlotClock};
 pub use crate::testing_slot_clock::{Error as TestingSlotClockError, TestingSlotClock};
+use std::fmt::Debug;
 use std::time::Duration;
 pub use types::Slot;
 
 pub trait SlotClock: Send + Sync + Sized {
-    type Error;
+    type Error: Debug;
 
     /// Create a new `SlotClock`.
     ///
@@ -17,4 +18,13 @@ pub trait SlotClock: Send + Sync + Sized {
     fn present_slot(&self) -> Result<Option<Slot>, Self::Error>;
 
     fn duration_to_next_slot(&self) -> Result<Option<Duration>, Self::Error>;
+
+    /// Returns the duration from now until `offset` after the start of `slot`.
+    ///
+    /// Returns `None` if that time has already passed.
+    fn duration_to_slot_offset(
+        &self,
+        slot: Slot,
+        offset: Duration,
+    ) -> Result<Option<Duration>, Self::Error>;
 }
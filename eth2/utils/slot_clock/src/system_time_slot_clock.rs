@@ -52,6 +52,36 @@ impl SlotClock for SystemTimeSlotClock {
     fn duration_to_next_slot(&self) -> Result<Option<Duration>, Error> {
         duration_to_next_slot(self.genesis_seconds, self.slot_duration_seconds)
     }
+
+    fn duration_to_slot_offset(
+        &self,
+        slot: Slot,
+        offset: Duration,
+    ) -> Result<Option<Duration>, Error> {
+        if self.slot_duration_seconds == 0 {
+            return Err(Error::SlotDurationIsZero);
+        }
+
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+
+        Ok(self
+            .slot_start(slot)
+            .map(|start| start + offset)
+            .and_then(|time| time.checked_sub(now)))
+    }
+}
+
+impl SystemTimeSlotClock {
+    /// Returns the start of `slot` as a duration since the UNIX epoch, or `None` if `slot` is
+    /// prior to genesis.
+    fn slot_start(&self, slot: Slot) -> Option<Duration> {
+        let slots_since_genesis = slot.as_u64().checked_sub(self.genesis_slot.as_u64())?;
+        let seconds = slots_since_genesis
+            .checked_mul(self.slot_duration_seconds)?
+            .checked_add(self.genesis_seconds)?;
+
+        Some(Duration::from_secs(seconds))
+    }
 }
 
 impl From<SystemTimeError> for Error {
@@ -130,6 +160,53 @@ mod tests {
         assert_eq!(clock.present_slot().unwrap(), Some(Slot::new(42)));
     }
 
+    #[test]
+    fn test_slot_start() {
+        let clock = SystemTimeSlotClock {
+            genesis_slot: Slot::new(10),
+            genesis_seconds: 1_000,
+            slot_duration_seconds: 6,
+        };
+
+        assert_eq!(clock.slot_start(Slot::new(9)), None);
+        assert_eq!(
+            clock.slot_start(Slot::new(10)),
+            Some(Duration::from_secs(1_000))
+        );
+        assert_eq!(
+            clock.slot_start(Slot::new(13)),
+            Some(Duration::from_secs(1_018))
+        );
+    }
+
+    #[test]
+    fn test_duration_to_slot_offset() {
+        let slot_time = 100;
+        let since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        // The present slot began 5 seconds ago.
+        let clock = SystemTimeSlotClock {
+            genesis_slot: Slot::new(0),
+            genesis_seconds: since_epoch.as_secs() - slot_time * 42 - 5,
+            slot_duration_seconds: slot_time,
+        };
+
+        assert_eq!(
+            clock
+                .duration_to_slot_offset(Slot::new(42), Duration::from_secs(1))
+                .unwrap(),
+            None
+        );
+
+        let remaining = clock
+            .duration_to_slot_offset(Slot::new(42), Duration::from_secs(50))
+            .unwrap()
+            .expect("offset should be in the future");
+        assert!(remaining > Duration::from_secs(43) && remaining <= Duration::from_secs(45));
+    }
+
     #[test]
     fn test_slot_from_duration() {
         let slot_time = 100;
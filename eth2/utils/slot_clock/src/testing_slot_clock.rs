@@ -6,35 +6,95 @@ use types::Slot;
 #[derive(Debug, PartialEq)]
 pub enum Error {}
 
-/// Determines the present slot based upon the present system time.
+/// A slot clock which only moves when told to, so that timing-dependent behaviour can be tested
+/// deterministically.
+///
+/// The present time is held as a duration since genesis, which may be advanced within a slot or
+/// set to the start of any slot. If `slot_duration_seconds == 0`, each slot lasts one second.
 pub struct TestingSlotClock {
-    slot: RwLock<Slot>,
+    genesis_slot: Slot,
+    slot_duration_seconds: u64,
+    /// The present time, as a duration since genesis.
+    now: RwLock<Duration>,
 }
 
 impl TestingSlotClock {
+    /// Moves the clock to the start of `slot`.
+    ///
+    /// Moving to a slot prior to genesis moves the clock to genesis.
     pub fn set_slot(&self, slot: u64) {
-        *self.slot.write().expect("TestingSlotClock poisoned.") = Slot::from(slot);
+        let slots_since_genesis = slot.saturating_sub(self.genesis_slot.as_u64());
+        self.set_time_since_genesis(Duration::from_secs(
+            slots_since_genesis * self.slot_duration_seconds,
+        ));
+    }
+
+    /// Moves the clock to `time` after genesis.
+    pub fn set_time_since_genesis(&self, time: Duration) {
+        *self.now.write().expect("TestingSlotClock poisoned.") = time;
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.write().expect("TestingSlotClock poisoned.") += duration;
+    }
+
+    /// Moves the clock to the start of the next slot.
+    pub fn advance_slot(&self) {
+        let next_slot = self.slot() + 1;
+        self.set_slot(next_slot.as_u64());
+    }
+
+    /// Returns the present time, as a duration since genesis.
+    pub fn time_since_genesis(&self) -> Duration {
+        *self.now.read().expect("TestingSlotClock poisoned.")
+    }
+
+    fn slot(&self) -> Slot {
+        self.genesis_slot + self.time_since_genesis().as_secs() / self.slot_duration_seconds
+    }
+
+    /// Returns the start of `slot` as a duration since genesis, or `None` if `slot` is prior to
+    /// genesis.
+    fn slot_start(&self, slot: Slot) -> Option<Duration> {
+        let slots_since_genesis = slot.as_u64().checked_sub(self.genesis_slot.as_u64())?;
+        let seconds = slots_since_genesis.checked_mul(self.slot_duration_seconds)?;
+        Some(Duration::from_secs(seconds))
     }
 }
 
 impl SlotClock for TestingSlotClock {
     type Error = Error;
 
-    /// Create a new `TestingSlotClock` at `genesis_slot`.
-    fn new(genesis_slot: Slot, _genesis_seconds: u64, _slot_duration_seconds: u64) -> Self {
+    /// Create a new `TestingSlotClock` at the start of `genesis_slot`.
+    fn new(genesis_slot: Slot, _genesis_seconds: u64, slot_duration_seconds: u64) -> Self {
         TestingSlotClock {
-            slot: RwLock::new(genesis_slot),
+            genesis_slot,
+            slot_duration_seconds: std::cmp::max(slot_duration_seconds, 1),
+            now: RwLock::new(Duration::from_secs(0)),
         }
     }
 
     fn present_slot(&self) -> Result<Option<Slot>, Error> {
-        let slot = *self.slot.read().expect("TestingSlotClock poisoned.");
-        Ok(Some(slot))
+        Ok(Some(self.slot()))
     }
 
-    /// Always returns a duration of 1 second.
     fn duration_to_next_slot(&self) -> Result<Option<Duration>, Error> {
-        Ok(Some(Duration::from_secs(1)))
+        let next_slot = self.slot() + 1;
+        Ok(self
+            .slot_start(next_slot)
+            .and_then(|start| start.checked_sub(self.time_since_genesis())))
+    }
+
+    fn duration_to_slot_offset(
+        &self,
+        slot: Slot,
+        offset: Duration,
+    ) -> Result<Option<Duration>, Error> {
+        Ok(self
+            .slot_start(slot)
+            .map(|start| start + offset)
+            .and_then(|time| time.checked_sub(self.time_since_genesis())))
     }
 }
 
@@ -51,4 +111,48 @@ mod tests {
         clock.set_slot(123);
         assert_eq!(clock.present_slot(), Ok(Some(Slot::new(123))));
     }
+
+    #[test]
+    fn advances_manually() {
+        let clock = TestingSlotClock::new(Slot::new(10), 0, 6);
+        assert_eq!(
+            clock.duration_to_next_slot(),
+            Ok(Some(Duration::from_secs(6)))
+        );
+
+        clock.advance(Duration::from_millis(5_500));
+        assert_eq!(clock.present_slot(), Ok(Some(Slot::new(10))));
+        assert_eq!(
+            clock.duration_to_next_slot(),
+            Ok(Some(Duration::from_millis(500)))
+        );
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(clock.present_slot(), Ok(Some(Slot::new(11))));
+
+        clock.advance_slot();
+        assert_eq!(clock.present_slot(), Ok(Some(Slot::new(12))));
+        assert_eq!(clock.time_since_genesis(), Duration::from_secs(12));
+    }
+
+    #[test]
+    fn duration_to_slot_offset() {
+        let clock = TestingSlotClock::new(Slot::new(0), 0, 6);
+        clock.set_slot(3);
+        clock.advance(Duration::from_secs(2));
+
+        let offset = Duration::from_secs(3);
+        assert_eq!(
+            clock.duration_to_slot_offset(Slot::new(3), offset),
+            Ok(Some(Duration::from_secs(1)))
+        );
+        assert_eq!(
+            clock.duration_to_slot_offset(Slot::new(4), offset),
+            Ok(Some(Duration::from_secs(7)))
+        );
+        assert_eq!(
+            clock.duration_to_slot_offset(Slot::new(2), offset),
+            Ok(None)
+        );
+    }
 }
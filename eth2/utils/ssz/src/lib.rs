@@ -39,9 +39,10 @@ mod encode;
 mod macros;
 
 pub use decode::{
-    impls::decode_list_of_variable_length_items, Decode, DecodeError, SszDecoder, SszDecoderBuilder,
+    impls::decode_list_of_variable_length_items, read_union_index, Decode, DecodeError, SszDecoder,
+    SszDecoderBuilder,
 };
-pub use encode::{Encode, SszEncoder};
+pub use encode::{encode_union_index, Encode, SszEncoder};
 
 /// The number of bytes used to represent an offset.
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
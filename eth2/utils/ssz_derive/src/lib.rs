@@ -58,21 +58,83 @@ fn should_skip_serializing(field: &syn::Field) -> bool {
     false
 }
 
-/// Implements `ssz::Encode` for some `struct`.
+/// How an enum is encoded, as declared by the attribute `#[ssz(enum_behaviour = "...")]`.
+#[derive(Clone, Copy, PartialEq)]
+enum EnumBehaviour {
+    /// `"union"`: the value is prefixed with the index of its variant, as for an SSZ union.
+    Union,
+    /// `"transparent"`: only the value is encoded, so the variant must be given when decoding.
+    Transparent,
+}
+
+/// Returns the behaviour declared by the `#[ssz(enum_behaviour = "...")]` attribute of an enum.
+///
+/// # Panics
+/// If the attribute is missing or names an unknown behaviour.
+fn get_enum_behaviour(item: &DeriveInput) -> EnumBehaviour {
+    for attr in &item.attrs {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) if list.ident == "ssz" => list,
+            _ => continue,
+        };
+
+        for nested in &list.nested {
+            if let syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) = nested {
+                if name_value.ident != "enum_behaviour" {
+                    continue;
+                }
+                return match &name_value.lit {
+                    syn::Lit::Str(behaviour) if behaviour.value() == "union" => {
+                        EnumBehaviour::Union
+                    }
+                    syn::Lit::Str(behaviour) if behaviour.value() == "transparent" => {
+                        EnumBehaviour::Transparent
+                    }
+                    _ => panic!("ssz_derive enum_behaviour must be \"union\" or \"transparent\"."),
+                };
+            }
+        }
+    }
+    panic!("ssz_derive requires enums to declare #[ssz(enum_behaviour = \"...\")].");
+}
+
+/// Returns the `syn::Ident` and the `syn::Type` of the value of each variant of the enum.
+///
+/// # Panics
+/// Any variant which does not hold exactly one unnamed field will raise a panic at compile time.
+fn get_enum_variants<'a>(enum_data: &'a syn::DataEnum) -> Vec<(&'a syn::Ident, &'a syn::Type)> {
+    enum_data
+        .variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                (&variant.ident, &fields.unnamed[0].ty)
+            }
+            _ => panic!("ssz_derive only supports enum variants with a single unnamed field."),
+        })
+        .collect()
+}
+
+/// Implements `ssz::Encode` for some `struct` or `enum`.
 ///
 /// Fields are encoded in the order they are defined.
+///
+/// An enum must declare its behaviour with `#[ssz(enum_behaviour = "...")]`. The value of a
+/// `"union"` enum is prefixed with the index of its variant, in the order the variants are
+/// defined, whilst only the value of a `"transparent"` enum is encoded.
 #[proc_macro_derive(Encode, attributes(ssz))]
 pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
 
-    let name = &item.ident;
-    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
-
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_encode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
     let field_idents = get_serializable_named_field_idents(&struct_data);
     let field_types_a = get_serializable_field_types(&struct_data);
     let field_types_b = field_types_a.clone();
@@ -117,6 +179,46 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
     output.into()
 }
 
+/// Implements `ssz::Encode` for an enum whose variants each hold a single value.
+fn ssz_encode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let behaviour = get_enum_behaviour(item);
+    let variants = get_enum_variants(enum_data);
+    let appends = variants.iter().enumerate().map(|(i, (ident, _))| {
+        let prefix = match behaviour {
+            EnumBehaviour::Union => quote! {
+                buf.append(&mut ssz::encode_union_index(#i));
+            },
+            EnumBehaviour::Transparent => quote! {},
+        };
+        quote! {
+            #name::#ident(value) => {
+                #prefix
+                ssz::Encode::ssz_append(value, buf);
+            }
+        }
+    });
+
+    let output = quote! {
+        impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                match self {
+                    #(
+                        #appends
+                    )*
+                }
+            }
+        }
+    };
+    output.into()
+}
+
 /// Returns true if some field has an attribute declaring it should not be deserialized.
 ///
 /// The field attribute is: `#[ssz(skip_deserializing)]`
@@ -129,21 +231,27 @@ fn should_skip_deserializing(field: &syn::Field) -> bool {
     false
 }
 
-/// Implements `ssz::Decode` for some `struct`.
+/// Implements `ssz::Decode` for some `struct`, or decoding for some `enum`.
 ///
 /// Fields are decoded in the order they are defined.
-#[proc_macro_derive(Decode)]
+///
+/// For an enum, implements `from_ssz_bytes_with_variant(bytes, variant)`, which decodes `bytes`
+/// as the value of the variant at index `variant`, in the order the variants are defined. This
+/// suits a `"transparent"` enum, whose variant is known from elsewhere, e.g., a slot. A `"union"`
+/// enum also implements `ssz::Decode`, reading the variant from its prefix.
+#[proc_macro_derive(Decode, attributes(ssz))]
 pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
 
-    let name = &item.ident;
-    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
-
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_decode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
     let mut register_types = vec![];
     let mut decodes = vec![];
     let mut is_fixed_lens = vec![];
@@ -222,3 +330,67 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
     };
     output.into()
 }
+
+/// Implements decoding for an enum whose variants each hold a single value.
+fn ssz_decode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let name_string = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let variants = get_enum_variants(enum_data);
+    let decodes = variants.iter().enumerate().map(|(i, (ident, ty))| {
+        quote! {
+            #i => Ok(#name::#ident(<#ty as ssz::Decode>::from_ssz_bytes(bytes)?)),
+        }
+    });
+
+    let union_impl = match get_enum_behaviour(item) {
+        EnumBehaviour::Union => quote! {
+            impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
+                fn is_ssz_fixed_len() -> bool {
+                    false
+                }
+
+                fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
+                    if bytes.len() < ssz::BYTES_PER_LENGTH_OFFSET {
+                        return Err(ssz::DecodeError::InvalidByteLength {
+                            len: bytes.len(),
+                            expected: ssz::BYTES_PER_LENGTH_OFFSET,
+                        });
+                    }
+
+                    let (index_bytes, value_bytes) = bytes.split_at(ssz::BYTES_PER_LENGTH_OFFSET);
+                    let variant = ssz::read_union_index(index_bytes)?;
+
+                    Self::from_ssz_bytes_with_variant(value_bytes, variant)
+                }
+            }
+        },
+        EnumBehaviour::Transparent => quote! {},
+    };
+
+    let output = quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Decodes `bytes` as the value of the variant at index `variant`, in the order the
+            /// variants are defined.
+            pub fn from_ssz_bytes_with_variant(
+                bytes: &[u8],
+                variant: usize,
+            ) -> Result<Self, ssz::DecodeError> {
+                match variant {
+                    #(
+                        #decodes
+                    )*
+                    _ => Err(ssz::DecodeError::BytesInvalid(format!(
+                        "{} is not a valid variant of {}",
+                        variant,
+                        #name_string
+                    ))),
+                }
+            }
+        }
+
+        #union_impl
+    };
+    output.into()
+}
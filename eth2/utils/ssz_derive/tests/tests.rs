@@ -1,7 +1,7 @@
-use ssz::Encode;
-use ssz_derive::Encode;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
 
-#[derive(Debug, PartialEq, Encode)]
+#[derive(Debug, PartialEq, Encode, Decode)]
 pub struct Foo {
     a: u16,
     b: Vec<u8>,
@@ -20,3 +20,56 @@ fn encode() {
 
     assert_eq!(foo.as_ssz_bytes(), bytes);
 }
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[ssz(enum_behaviour = "union")]
+pub enum Union {
+    A(u16),
+    B(Vec<u8>),
+}
+
+#[test]
+fn union() {
+    let a = Union::A(42);
+    let b = Union::B(vec![1, 2]);
+
+    assert_eq!(a.as_ssz_bytes(), vec![0, 0, 0, 0, 42, 0]);
+    assert_eq!(b.as_ssz_bytes(), vec![1, 0, 0, 0, 1, 2]);
+    assert_eq!(Union::from_ssz_bytes(&a.as_ssz_bytes()), Ok(a));
+    assert_eq!(Union::from_ssz_bytes(&b.as_ssz_bytes()), Ok(b));
+    assert!(Union::from_ssz_bytes(&[2, 0, 0, 0]).is_err());
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[ssz(enum_behaviour = "transparent")]
+pub enum Transparent {
+    Foo(Foo),
+    Wide(u32),
+}
+
+#[test]
+fn transparent() {
+    let wide = Transparent::Wide(7);
+    let bytes = wide.as_ssz_bytes();
+
+    assert_eq!(bytes, vec![7, 0, 0, 0]);
+    assert_eq!(
+        Transparent::from_ssz_bytes_with_variant(&bytes, 1),
+        Ok(wide)
+    );
+    assert!(Transparent::from_ssz_bytes_with_variant(&bytes, 2).is_err());
+
+    let foo = Transparent::Foo(Foo {
+        a: 42,
+        b: vec![0, 1, 2, 3],
+        c: 11,
+    });
+    assert_eq!(
+        foo.as_ssz_bytes(),
+        vec![42, 0, 8, 0, 0, 0, 11, 0, 0, 1, 2, 3]
+    );
+    assert_eq!(
+        Transparent::from_ssz_bytes_with_variant(&foo.as_ssz_bytes(), 0),
+        Ok(foo)
+    );
+}
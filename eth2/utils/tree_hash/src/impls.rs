@@ -113,11 +113,13 @@ macro_rules! impl_for_list {
             }
 
             fn tree_hash_root(&self) -> Vec<u8> {
-                let mut root_and_len = Vec::with_capacity(HASHSIZE * 2);
+                let mut root_and_len = pool::take(HASHSIZE * 2);
                 root_and_len.append(&mut vec_tree_hash_root(self));
                 root_and_len.append(&mut int_to_bytes32(self.len() as u64));
 
-                hash(&root_and_len)
+                let root = hash(&root_and_len);
+                pool::give(root_and_len);
+                root
             }
         }
     };
@@ -132,8 +134,7 @@ where
 {
     let leaves = match T::tree_hash_type() {
         TreeHashType::Basic => {
-            let mut leaves =
-                Vec::with_capacity((HASHSIZE / T::tree_hash_packing_factor()) * vec.len());
+            let mut leaves = pool::take((HASHSIZE / T::tree_hash_packing_factor()) * vec.len());
 
             for item in vec {
                 leaves.append(&mut item.tree_hash_packed_encoding());
@@ -142,7 +143,7 @@ where
             leaves
         }
         TreeHashType::Container | TreeHashType::List | TreeHashType::Vector => {
-            let mut leaves = Vec::with_capacity(vec.len() * HASHSIZE);
+            let mut leaves = pool::take(vec.len() * HASHSIZE);
 
             for item in vec {
                 leaves.append(&mut item.tree_hash_root())
@@ -152,7 +153,9 @@ where
         }
     };
 
-    merkle_root(&leaves)
+    let root = merkle_root(&leaves);
+    pool::give(leaves);
+    root
 }
 
 #[cfg(test)]
@@ -169,5 +172,4 @@ mod test {
         assert_eq!(true.tree_hash_root(), true_bytes);
         assert_eq!(false.tree_hash_root(), false_bytes);
     }
-
 }
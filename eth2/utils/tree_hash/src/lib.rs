@@ -1,5 +1,6 @@
 pub mod impls;
 pub mod merkleize;
+pub mod pool;
 
 pub const BYTES_PER_CHUNK: usize = 32;
 pub const HASHSIZE: usize = 32;
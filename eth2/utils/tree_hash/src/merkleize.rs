@@ -2,29 +2,38 @@ use super::*;
 use hashing::hash;
 
 pub fn merkle_root(bytes: &[u8]) -> Vec<u8> {
-    // TODO: replace this with a more memory efficient method.
-    efficient_merkleize(&bytes)[0..32].to_vec()
+    let mut o = pool::take(num_merkleized_bytes(bytes.len()));
+    merkleize_into(bytes, &mut o);
+
+    let root = o[0..HASHSIZE].to_vec();
+    pool::give(o);
+    root
 }
 
 pub fn efficient_merkleize(bytes: &[u8]) -> Vec<u8> {
+    let mut o = Vec::with_capacity(num_merkleized_bytes(bytes.len()));
+    merkleize_into(bytes, &mut o);
+    o
+}
+
+/// Writes the tree over `bytes` to the empty `o`: the internal nodes, root first, followed by
+/// `bytes` itself.
+fn merkleize_into(bytes: &[u8], o: &mut Vec<u8>) {
     // If the bytes are just one chunk (or less than one chunk) just return them.
     if bytes.len() <= HASHSIZE {
-        let mut o = bytes.to_vec();
+        o.extend_from_slice(bytes);
         o.resize(HASHSIZE, 0);
-        return o;
+        return;
     }
 
     let leaves = num_sanitized_leaves(bytes.len());
     let nodes = num_nodes(leaves);
     let internal_nodes = nodes - leaves;
 
-    let num_bytes = std::cmp::max(internal_nodes, 1) * HASHSIZE + bytes.len();
+    o.resize(internal_nodes * HASHSIZE, 0);
+    o.extend_from_slice(bytes);
 
-    let mut o: Vec<u8> = vec![0; internal_nodes * HASHSIZE];
-
-    o.append(&mut bytes.to_vec());
-
-    assert_eq!(o.len(), num_bytes);
+    assert_eq!(o.len(), num_merkleized_bytes(bytes.len()));
 
     let empty_chunk_hash = hash(&[0; MERKLE_HASH_CHUNK]);
 
@@ -43,8 +52,8 @@ pub fn efficient_merkleize(bytes: &[u8]) -> Vec<u8> {
                 match o.get(i..) {
                     // Able to get some of the bytes, pad them out.
                     Some(slice) => {
-                        let mut bytes = slice.to_vec();
-                        bytes.resize(MERKLE_HASH_CHUNK, 0);
+                        let mut bytes = [0; MERKLE_HASH_CHUNK];
+                        bytes[0..slice.len()].copy_from_slice(slice);
                         hash(&bytes)
                     }
                     // Unable to get any bytes, use the empty-chunk hash.
@@ -55,8 +64,16 @@ pub fn efficient_merkleize(bytes: &[u8]) -> Vec<u8> {
 
         o[j..j + HASHSIZE].copy_from_slice(&hash);
     }
+}
 
-    o
+/// The length of the output of `efficient_merkleize` for `num_bytes` of input.
+fn num_merkleized_bytes(num_bytes: usize) -> usize {
+    if num_bytes <= HASHSIZE {
+        return HASHSIZE;
+    }
+    let leaves = num_sanitized_leaves(num_bytes);
+    let internal_nodes = num_nodes(leaves) - leaves;
+    std::cmp::max(internal_nodes, 1) * HASHSIZE + num_bytes
 }
 
 fn num_sanitized_leaves(num_bytes: usize) -> usize {
@@ -67,3 +84,19 @@ fn num_sanitized_leaves(num_bytes: usize) -> usize {
 fn num_nodes(num_leaves: usize) -> usize {
     2 * num_leaves - 1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merkle_root_matches_efficient_merkleize() {
+        for len in &[0, 1, 32, 33, 64, 96, 200, 1024] {
+            let bytes: Vec<u8> = (0..*len).map(|i| i as u8).collect();
+            let tree = efficient_merkleize(&bytes);
+
+            assert_eq!(tree.len(), num_merkleized_bytes(bytes.len()));
+            assert_eq!(merkle_root(&bytes), tree[0..HASHSIZE].to_vec());
+        }
+    }
+}
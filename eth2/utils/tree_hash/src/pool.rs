@@ -0,0 +1,81 @@
+//! A pool of byte buffers for the intermediate chunks of merkleization.
+//!
+//! Hashing a container allocates a buffer for the leaves of each of its fields which is a
+//! container, list or vector, and another for the tree over them, all of which are freed as soon
+//! as the root is known. Taking these buffers from the pool of the current thread, and giving them
+//! back once the root is known, lets hashing e.g. a block body reuse a handful of buffers rather
+//! than allocate thousands.
+//!
+//! Buffers are taken and given back individually, never borrowed from the pool for the length of
+//! a closure, so that hashing may recurse into the fields of a container whilst holding a buffer.
+use std::cell::RefCell;
+
+/// The most buffers kept by the pool of each thread.
+pub const MAX_POOLED_BUFFERS: usize = 64;
+/// The capacity, in bytes, above which a buffer is freed rather than pooled, so that hashing a
+/// large list does not pin its memory.
+pub const MAX_POOLED_CAPACITY: usize = 1 << 20;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = RefCell::new(vec![]);
+}
+
+/// Takes an empty buffer with room for at least `capacity` bytes from the pool of the current
+/// thread, allocating one if the pool is empty.
+pub fn take(capacity: usize) -> Vec<u8> {
+    let mut buf = POOL
+        .with(|pool| pool.borrow_mut().pop())
+        .unwrap_or_default();
+    buf.reserve(capacity);
+    buf
+}
+
+/// Gives `buf` back to the pool of the current thread, to be returned by a later `take`.
+pub fn give(mut buf: Vec<u8>) {
+    if buf.capacity() > MAX_POOLED_CAPACITY {
+        return;
+    }
+    buf.clear();
+
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        if pool.len() < MAX_POOLED_BUFFERS {
+            pool.push(buf);
+        }
+    });
+}
+
+/// The number of buffers held by the pool of the current thread.
+pub fn pooled() -> usize {
+    POOL.with(|pool| pool.borrow().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_buffers() {
+        let mut buf = take(64);
+        buf.extend_from_slice(&[1; 64]);
+        let ptr = buf.as_ptr();
+        give(buf);
+        assert_eq!(pooled(), 1);
+
+        let buf = take(32);
+        assert!(buf.is_empty());
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(pooled(), 0);
+    }
+
+    #[test]
+    fn limits_pool() {
+        give(Vec::with_capacity(MAX_POOLED_CAPACITY + 1));
+        assert_eq!(pooled(), 0);
+
+        for _ in 0..MAX_POOLED_BUFFERS + 1 {
+            give(Vec::with_capacity(32));
+        }
+        assert_eq!(pooled(), MAX_POOLED_BUFFERS);
+    }
+}
@@ -144,13 +144,15 @@ pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
             }
 
             fn tree_hash_root(&self) -> Vec<u8> {
-                let mut leaves = Vec::with_capacity(4 * tree_hash::HASHSIZE);
+                let mut leaves = tree_hash::pool::take(4 * tree_hash::HASHSIZE);
 
                 #(
                     leaves.append(&mut self.#idents.tree_hash_root());
                 )*
 
-                tree_hash::merkleize::merkle_root(&leaves)
+                let root = tree_hash::merkleize::merkle_root(&leaves);
+                tree_hash::pool::give(leaves);
+                root
             }
         }
     };
@@ -174,13 +176,15 @@ pub fn tree_hash_signed_root_derive(input: TokenStream) -> TokenStream {
     let output = quote! {
         impl tree_hash::SignedRoot for #name {
             fn signed_root(&self) -> Vec<u8> {
-                let mut leaves = Vec::with_capacity(#num_elems * tree_hash::HASHSIZE);
+                let mut leaves = tree_hash::pool::take(#num_elems * tree_hash::HASHSIZE);
 
                 #(
                     leaves.append(&mut self.#idents.tree_hash_root());
                 )*
 
-                tree_hash::merkleize::merkle_root(&leaves)
+                let root = tree_hash::merkleize::merkle_root(&leaves);
+                tree_hash::pool::give(leaves);
+                root
             }
         }
     };
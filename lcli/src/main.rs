@@ -0,0 +1,164 @@
+mod parse_ssz;
+mod prove;
+mod transition_blocks;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use ssz::Decode;
+use std::fs;
+use std::process;
+use types::{EthSpec, MainnetEthSpec, MinimalEthSpec};
+
+fn main() {
+    let matches = App::new("Lighthouse CLI Tool")
+        .version("0.0.1")
+        .author("Sigma Prime <contact@sigmaprime.io>")
+        .about("Performs offline debugging tasks on SSZ files, such as replaying blocks, decoding objects and proving the nodes of states.")
+        .arg(
+            Arg::with_name("spec")
+                .long("spec")
+                .short("s")
+                .value_name("TITLE")
+                .help("The spec constants of the objects, which determine the shape of states.")
+                .takes_value(true)
+                .possible_values(&["minimal", "mainnet"])
+                .default_value("minimal")
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("transition-blocks")
+                .about("Applies blocks to a state, writing the post-state.")
+                .arg(
+                    Arg::with_name("pre-state")
+                        .long("pre-state")
+                        .value_name("PATH")
+                        .help("The SSZ BeaconState to which the blocks are applied.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("block")
+                        .long("block")
+                        .value_name("PATH")
+                        .help("An SSZ BeaconBlock to apply. May be repeated, in slot order.")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("PATH")
+                        .help("The path to which the SSZ post-state is written.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("no-signature-verification")
+                        .long("no-signature-verification")
+                        .help("Do not verify the signatures of the blocks or their operations.")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("no-state-root-verification")
+                        .long("no-state-root-verification")
+                        .help("Do not check the state root of each block against the state it produces.")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pretty-ssz")
+                .about("Prints an SSZ object as JSON.")
+                .arg(
+                    Arg::with_name("type")
+                        .value_name("TYPE")
+                        .help("The type of the object, e.g., BeaconState.")
+                        .required(true)
+                        .possible_values(parse_ssz::TYPES),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .help("The SSZ file.")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("hash-tree-root")
+                .about("Prints the hash tree root of an SSZ object.")
+                .arg(
+                    Arg::with_name("type")
+                        .value_name("TYPE")
+                        .help("The type of the object, e.g., BeaconState.")
+                        .required(true)
+                        .possible_values(parse_ssz::TYPES),
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .help("The SSZ file.")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("prove")
+                .about("Prints a proof of nodes of a state, by generalized index, as JSON.")
+                .arg(
+                    Arg::with_name("state")
+                        .long("state")
+                        .value_name("PATH")
+                        .help("The SSZ BeaconState to prove against.")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("index")
+                        .long("index")
+                        .value_name("GENERALIZED_INDEX")
+                        .help("The generalized index of a node to prove. May be repeated.")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("multiproof")
+                        .long("multiproof")
+                        .help("Print the proof in the canonical multiproof form of the spec, rather than as a serialized partial.")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("schema")
+                .about("Prints the path, generalized index, size and offset of each provable leaf of a state, as JSON."),
+        )
+        .get_matches();
+
+    let result = match matches.value_of("spec") {
+        Some("mainnet") => run::<MainnetEthSpec>(&matches),
+        _ => run::<MinimalEthSpec>(&matches),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    match matches.subcommand() {
+        ("transition-blocks", Some(matches)) => transition_blocks::run::<T>(matches),
+        ("pretty-ssz", Some(matches)) => parse_ssz::run_pretty_ssz::<T>(matches),
+        ("hash-tree-root", Some(matches)) => parse_ssz::run_hash_tree_root::<T>(matches),
+        ("prove", Some(matches)) => prove::run::<T>(matches),
+        ("schema", Some(_)) => prove::run_schema::<T>(),
+        _ => Err("No subcommand given, see --help".to_string()),
+    }
+}
+
+/// Reads and decodes the SSZ object at `path`.
+fn read_ssz<D: Decode>(path: &str) -> Result<D, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read {}: {:?}", path, e))?;
+
+    D::from_ssz_bytes(&bytes).map_err(|e| format!("Unable to decode {}: {:?}", path, e))
+}
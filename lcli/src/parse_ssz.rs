@@ -0,0 +1,120 @@
+use clap::ArgMatches;
+use serde::Serialize;
+use ssz::Decode;
+use std::fs;
+use tree_hash::TreeHash;
+use types::*;
+
+/// The types which may be decoded.
+pub const TYPES: &[&str] = &[
+    "Attestation",
+    "AttestationData",
+    "AttesterSlashing",
+    "BeaconBlock",
+    "BeaconBlockBody",
+    "BeaconBlockHeader",
+    "BeaconState",
+    "Deposit",
+    "IndexedAttestation",
+    "ProposerSlashing",
+    "Transfer",
+    "Validator",
+    "VoluntaryExit",
+];
+
+/// Calls `$function::<Type>($bytes)`, where `Type` is named by `$type_name`.
+macro_rules! with_type {
+    ($type_name: expr, $spec: ty, $function: ident, $bytes: expr) => {
+        match $type_name {
+            "Attestation" => $function::<Attestation>($bytes),
+            "AttestationData" => $function::<AttestationData>($bytes),
+            "AttesterSlashing" => $function::<AttesterSlashing>($bytes),
+            "BeaconBlock" => $function::<BeaconBlock>($bytes),
+            "BeaconBlockBody" => $function::<BeaconBlockBody>($bytes),
+            "BeaconBlockHeader" => $function::<BeaconBlockHeader>($bytes),
+            "BeaconState" => $function::<BeaconState<$spec>>($bytes),
+            "Deposit" => $function::<Deposit>($bytes),
+            "IndexedAttestation" => $function::<IndexedAttestation>($bytes),
+            "ProposerSlashing" => $function::<ProposerSlashing>($bytes),
+            "Transfer" => $function::<Transfer>($bytes),
+            "Validator" => $function::<Validator>($bytes),
+            "VoluntaryExit" => $function::<VoluntaryExit>($bytes),
+            other => Err(format!(
+                "Unknown type {}, expected one of {:?}",
+                other, TYPES
+            )),
+        }
+    };
+}
+
+pub fn run_pretty_ssz<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let (type_name, bytes) = type_and_bytes(matches)?;
+
+    println!("{}", pretty_ssz::<T>(type_name, &bytes)?);
+
+    Ok(())
+}
+
+pub fn run_hash_tree_root<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let (type_name, bytes) = type_and_bytes(matches)?;
+
+    println!("{:?}", hash_tree_root::<T>(type_name, &bytes)?);
+
+    Ok(())
+}
+
+fn type_and_bytes<'a>(matches: &'a ArgMatches) -> Result<(&'a str, Vec<u8>), String> {
+    let type_name = matches.value_of("type").ok_or("No type given")?;
+    let path = matches.value_of("path").ok_or("No path given")?;
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read {}: {:?}", path, e))?;
+
+    Ok((type_name, bytes))
+}
+
+/// Decodes `bytes` as the type named `type_name`, returning it as pretty-printed JSON.
+pub fn pretty_ssz<T: EthSpec>(type_name: &str, bytes: &[u8]) -> Result<String, String> {
+    with_type!(type_name, T, to_json, bytes)
+}
+
+/// Decodes `bytes` as the type named `type_name`, returning its hash tree root.
+pub fn hash_tree_root<T: EthSpec>(type_name: &str, bytes: &[u8]) -> Result<Hash256, String> {
+    with_type!(type_name, T, to_root, bytes)
+}
+
+fn decode<D: Decode>(bytes: &[u8]) -> Result<D, String> {
+    D::from_ssz_bytes(bytes).map_err(|e| format!("Unable to decode SSZ: {:?}", e))
+}
+
+fn to_json<D: Decode + Serialize>(bytes: &[u8]) -> Result<String, String> {
+    serde_json::to_string_pretty(&decode::<D>(bytes)?)
+        .map_err(|e| format!("Unable to encode JSON: {:?}", e))
+}
+
+fn to_root<D: Decode + TreeHash>(bytes: &[u8]) -> Result<Hash256, String> {
+    Ok(Hash256::from_slice(&decode::<D>(bytes)?.tree_hash_root()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssz::Encode;
+
+    #[test]
+    fn decodes_named_types() {
+        let spec = MinimalEthSpec::default_spec();
+        let block = BeaconBlock::empty(&spec);
+        let bytes = block.as_ssz_bytes();
+
+        assert_eq!(
+            hash_tree_root::<MinimalEthSpec>("BeaconBlock", &bytes),
+            Ok(Hash256::from_slice(&block.tree_hash_root()))
+        );
+        assert_eq!(
+            pretty_ssz::<MinimalEthSpec>("BeaconBlock", &bytes),
+            Ok(serde_json::to_string_pretty(&block).unwrap())
+        );
+
+        assert!(hash_tree_root::<MinimalEthSpec>("BeaconState", &bytes).is_err());
+        assert!(pretty_ssz::<MinimalEthSpec>("Block", &bytes).is_err());
+    }
+}
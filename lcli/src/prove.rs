@@ -0,0 +1,106 @@
+use crate::read_ssz;
+use clap::ArgMatches;
+use merkle_proof::{MerkleTreeOverlay, Multiproof};
+use serde_derive::Serialize;
+use types::{BeaconState, EthSpec, Hash256};
+
+/// A proof of nodes of a state, and the root of that state.
+#[derive(Debug, Serialize)]
+struct StateProof<P> {
+    state_root: Hash256,
+    proof: P,
+}
+
+pub fn run<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let state: BeaconState<T> = read_ssz(matches.value_of("state").ok_or("No state given")?)?;
+    let indices = matches
+        .values_of("index")
+        .ok_or("No index given")?
+        .map(|index| {
+            index
+                .parse()
+                .map_err(|_| format!("{} is not a generalized index", index))
+        })
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    println!(
+        "{}",
+        prove(&state, &indices, matches.is_present("multiproof"))?
+    );
+
+    Ok(())
+}
+
+/// Prints the provable leaves of a state, with their generalized indices, as JSON.
+pub fn run_schema<T: EthSpec>() -> Result<(), String> {
+    let schema = serde_json::to_string_pretty(&BeaconState::<T>::schema())
+        .map_err(|e| format!("Unable to encode JSON: {:?}", e))?;
+    println!("{}", schema);
+
+    Ok(())
+}
+
+/// Returns a proof of the nodes of `state` at `indices` as JSON, in the multiproof form if
+/// `multiproof` is `true`.
+pub fn prove<T: EthSpec>(
+    state: &BeaconState<T>,
+    indices: &[u64],
+    multiproof: bool,
+) -> Result<String, String> {
+    let partial = state
+        .prove(indices)
+        .map_err(|e| format!("Unable to prove indices: {:?}", e))?;
+    let state_root = state.canonical_root();
+
+    let json = if multiproof {
+        let proof = Multiproof::from_partial(&partial, indices)
+            .map_err(|e| format!("Unable to build multiproof: {:?}", e))?;
+        serde_json::to_string_pretty(&StateProof { state_root, proof })
+    } else {
+        serde_json::to_string_pretty(&StateProof {
+            state_root,
+            proof: partial,
+        })
+    };
+
+    json.map_err(|e| format!("Unable to encode JSON: {:?}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use merkle_proof::{verify_partial, SerializedPartial};
+    use serde_derive::Deserialize;
+    use types::test_utils::TestingBeaconStateBuilder;
+    use types::MinimalEthSpec;
+
+    #[derive(Deserialize)]
+    struct ParsedProof<P> {
+        state_root: Hash256,
+        proof: P,
+    }
+
+    #[test]
+    fn proves_against_the_state_root() {
+        let spec = MinimalEthSpec::default_spec();
+        let (state, _) =
+            TestingBeaconStateBuilder::<MinimalEthSpec>::from_deterministic_keypairs(4, &spec)
+                .build();
+
+        let json = prove(&state, &[33, 40], false).unwrap();
+        let parsed: ParsedProof<SerializedPartial> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.state_root, state.canonical_root());
+        assert!(
+            verify_partial(&parsed.proof, parsed.state_root)
+                .unwrap()
+                .valid
+        );
+
+        let json = prove(&state, &[40, 33], true).unwrap();
+        let parsed: ParsedProof<Multiproof> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.proof.indices, vec![33, 40]);
+
+        // Nodes within the slot cannot be proven.
+        assert!(prove(&state, &[64], false).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::read_ssz;
+use clap::ArgMatches;
+use ssz::Encode;
+use state_processing::{
+    per_block_processing, per_block_processing_without_verifying_block_signature,
+    per_slot_processing, BlockProcessingError, SlotProcessingError,
+};
+use std::fs;
+use types::*;
+
+#[derive(Debug, PartialEq)]
+pub enum TransitionError {
+    /// A block precedes the state it would be applied to, e.g., as blocks were given out of order.
+    BlockPrecedesState {
+        block_slot: Slot,
+        state_slot: Slot,
+    },
+    /// The state root of the block at `slot` is not that of the state it produces.
+    StateRootMismatch {
+        slot: Slot,
+        block: Hash256,
+        computed: Hash256,
+    },
+    SlotProcessingError(SlotProcessingError),
+    BlockProcessingError(Slot, BlockProcessingError),
+    BeaconStateError(BeaconStateError),
+}
+
+impl From<SlotProcessingError> for TransitionError {
+    fn from(e: SlotProcessingError) -> TransitionError {
+        TransitionError::SlotProcessingError(e)
+    }
+}
+
+impl From<BeaconStateError> for TransitionError {
+    fn from(e: BeaconStateError) -> TransitionError {
+        TransitionError::BeaconStateError(e)
+    }
+}
+
+pub fn run<T: EthSpec>(matches: &ArgMatches) -> Result<(), String> {
+    let pre_state: BeaconState<T> =
+        read_ssz(matches.value_of("pre-state").ok_or("No pre-state given")?)?;
+    let blocks = matches
+        .values_of("block")
+        .ok_or("No block given")?
+        .map(read_ssz)
+        .collect::<Result<Vec<BeaconBlock>, _>>()?;
+    let output = matches.value_of("output").ok_or("No output given")?;
+
+    let post_state = transition_blocks(
+        pre_state,
+        &blocks,
+        !matches.is_present("no-signature-verification"),
+        !matches.is_present("no-state-root-verification"),
+        &T::default_spec(),
+    )
+    .map_err(|e| format!("Transition failed: {:?}", e))?;
+
+    fs::write(output, post_state.as_ssz_bytes())
+        .map_err(|e| format!("Unable to write {}: {:?}", output, e))?;
+    println!("{:?}", post_state.canonical_root());
+
+    Ok(())
+}
+
+/// Applies each of `blocks`, in order, to `state`, advancing through the empty slots before each.
+///
+/// Unlike replaying imported blocks, this checks everything that importing them would, unless
+/// told otherwise, so that the first invalid block is found.
+pub fn transition_blocks<T: EthSpec>(
+    mut state: BeaconState<T>,
+    blocks: &[BeaconBlock],
+    verify_signatures: bool,
+    verify_state_roots: bool,
+    spec: &ChainSpec,
+) -> Result<BeaconState<T>, TransitionError> {
+    for block in blocks {
+        if block.slot < state.slot {
+            return Err(TransitionError::BlockPrecedesState {
+                block_slot: block.slot,
+                state_slot: state.slot,
+            });
+        }
+
+        while state.slot < block.slot {
+            // Ensure the next epoch state caches are built in case of an epoch transition.
+            state.build_committee_cache(RelativeEpoch::Next, spec)?;
+            per_slot_processing(&mut state, spec)?;
+        }
+        state.build_committee_cache(RelativeEpoch::Current, spec)?;
+
+        if verify_signatures {
+            per_block_processing(&mut state, block, spec)
+        } else {
+            per_block_processing_without_verifying_block_signature(&mut state, block, spec)
+        }
+        .map_err(|e| TransitionError::BlockProcessingError(block.slot, e))?;
+
+        if verify_state_roots {
+            let computed = state.canonical_root();
+            if block.state_root != computed {
+                return Err(TransitionError::StateRootMismatch {
+                    slot: block.slot,
+                    block: block.state_root,
+                    computed,
+                });
+            }
+        }
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use state_processing::per_block_processing::block_processing_builder::BlockProcessingBuilder;
+
+    fn block_and_state(spec: &ChainSpec) -> (BeaconBlock, BeaconState<MinimalEthSpec>) {
+        let mut builder = BlockProcessingBuilder::new(8, spec);
+        builder.set_slot(Slot::new(3));
+        builder.build_caches(spec);
+
+        builder.build(None, None, spec)
+    }
+
+    #[test]
+    fn applies_blocks() {
+        let spec = MinimalEthSpec::default_spec();
+        let (mut block, state) = block_and_state(&spec);
+
+        // The builder does not compute the state root.
+        assert_eq!(
+            transition_blocks(state.clone(), &[block.clone()], true, true, &spec),
+            Err(TransitionError::StateRootMismatch {
+                slot: block.slot,
+                block: block.state_root,
+                computed: transition_blocks(state.clone(), &[block.clone()], true, false, &spec)
+                    .unwrap()
+                    .canonical_root(),
+            })
+        );
+
+        let post_state =
+            transition_blocks(state.clone(), &[block.clone()], true, false, &spec).unwrap();
+        assert_eq!(post_state.slot, block.slot);
+        assert_eq!(
+            post_state.latest_block_header.block_body_root,
+            block.block_header().block_body_root
+        );
+
+        // A block with an invalid signature is applied only without verification.
+        block.signature = Signature::empty_signature();
+        assert!(transition_blocks(state.clone(), &[block.clone()], true, false, &spec).is_err());
+        assert!(transition_blocks(state, &[block], false, false, &spec).is_ok());
+    }
+
+    #[test]
+    fn rejects_blocks_before_the_state() {
+        let spec = MinimalEthSpec::default_spec();
+        let (mut block, state) = block_and_state(&spec);
+        block.slot = Slot::new(2);
+
+        assert_eq!(
+            transition_blocks(state, &[block], true, true, &spec),
+            Err(TransitionError::BlockPrecedesState {
+                block_slot: Slot::new(2),
+                state_slot: Slot::new(3),
+            })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! A local HTTP API for querying the headers and state chunks the light client has verified.
+use crate::beacon_node::BeaconNodeClient;
+use crate::relay;
+use crate::store::HeaderStore;
+use iron::headers::ContentType;
+use iron::prelude::*;
+use iron::typemap::Key;
+use iron::{status::Status, AfterMiddleware, Listening};
+use persistent::Read;
+use router::Router;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use types::{Hash256, Slot};
+
+pub struct StoreKey;
+
+impl Key for StoreKey {
+    type Value = Arc<RwLock<HeaderStore>>;
+}
+
+/// Starts the query API on `listen_address`, e.g., `127.0.0.1:5053`.
+///
+/// If `upstream` is given, the light client endpoints of the beacon node HTTP API are also
+/// served, relaying verified data so that other clients may follow this one.
+///
+/// The server runs on its own threads until the returned `Listening` is closed.
+pub fn start_server(
+    store: Arc<RwLock<HeaderStore>>,
+    upstream: Option<Arc<BeaconNodeClient>>,
+    listen_address: &str,
+) -> Result<Listening, String> {
+    let mut router = Router::new();
+    router.get("/finalized", handle_finalized, "finalized");
+    router.get("/headers/:root", handle_header, "header");
+    router.get("/slots/:slot", handle_slot, "slot");
+    router.get("/reorgs", handle_reorgs, "reorgs");
+    router.get("/partials/:state_root", handle_partial, "partial");
+
+    if upstream.is_some() {
+        router.get(
+            "/lightclient/finality_stream",
+            relay::handle_finality_stream,
+            "finality_stream",
+        );
+        router.get(
+            "/lightclient/header/:root",
+            relay::handle_header,
+            "relay_header",
+        );
+        router.post("/lightclient/proof", relay::handle_proof, "relay_proof");
+    }
+
+    let mut chain = Chain::new(router);
+    chain.link(Read::<StoreKey>::both(store));
+    if let Some(upstream) = upstream {
+        chain.link(Read::<relay::UpstreamKey>::both(upstream));
+    }
+    chain.link_after(SetJsonContentType);
+
+    Iron::new(chain)
+        .http(listen_address)
+        .map_err(|e| format!("Unable to start query API: {:?}", e))
+}
+
+/// Sets the `content-type` headers on _all_ responses, unless they are already set.
+struct SetJsonContentType;
+impl AfterMiddleware for SetJsonContentType {
+    fn after(&self, _req: &mut Request, mut resp: Response) -> IronResult<Response> {
+        if resp.headers.get::<ContentType>() == None {
+            resp.headers.set(ContentType::json());
+        }
+        Ok(resp)
+    }
+}
+
+/// Returns the latest verified finalized checkpoint, with its header and the head it was proven
+/// from.
+fn handle_finalized(req: &mut Request) -> IronResult<Response> {
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+    Ok(json_or_not_found(
+        store.finalized(),
+        "No finalized checkpoint verified",
+    ))
+}
+
+/// Returns the verified header of the block at `:root`.
+fn handle_header(req: &mut Request) -> IronResult<Response> {
+    let root = match root_param(req, "root") {
+        Ok(root) => root,
+        Err(response) => return Ok(response),
+    };
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+    Ok(json_or_not_found(store.header(&root), "Unknown block root"))
+}
+
+/// Returns the most recent reorgs, oldest first, with the roots of the headers each abandoned.
+fn handle_reorgs(req: &mut Request) -> IronResult<Response> {
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+
+    let reorgs: Vec<_> = store.reorgs().collect();
+    Ok(json_or_not_found(Some(&reorgs), ""))
+}
+
+/// Returns the verified headers of the blocks at `:slot`, by block root.
+fn handle_slot(req: &mut Request) -> IronResult<Response> {
+    let param = req
+        .extensions
+        .get::<Router>()
+        .and_then(|params| params.find("slot"))
+        .unwrap_or("");
+    let slot = match param.parse::<u64>() {
+        Ok(slot) => Slot::new(slot),
+        Err(_) => {
+            return Ok(error_response(
+                Status::BadRequest,
+                format!("Invalid slot: {}", param),
+            ))
+        }
+    };
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+
+    let headers: BTreeMap<String, _> = store
+        .headers_at_slot(slot)
+        .into_iter()
+        .map(|(root, header)| (format!("{:?}", root), header))
+        .collect();
+    Ok(json_or_not_found(Some(&headers), ""))
+}
+
+/// Returns the chunks proven against `:state_root`, by generalized index.
+fn handle_partial(req: &mut Request) -> IronResult<Response> {
+    let state_root = match root_param(req, "state_root") {
+        Ok(root) => root,
+        Err(response) => return Ok(response),
+    };
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+
+    let chunks: Option<BTreeMap<String, Hash256>> = store.partial(&state_root).map(|chunks| {
+        chunks
+            .iter()
+            .map(|(index, chunk)| (index.to_string(), *chunk))
+            .collect()
+    });
+    Ok(json_or_not_found(chunks.as_ref(), "Unknown state root"))
+}
+
+pub fn get_store(req: &mut Request) -> IronResult<Arc<Arc<RwLock<HeaderStore>>>> {
+    req.get::<Read<StoreKey>>()
+        .map_err(|e| IronError::new(e, Status::InternalServerError))
+}
+
+/// Parses the route parameter `name` as a `0x`-prefixed root.
+pub fn root_param(req: &Request, name: &str) -> Result<Hash256, Response> {
+    let param = req
+        .extensions
+        .get::<Router>()
+        .and_then(|params| params.find(name))
+        .unwrap_or("");
+
+    serde_json::from_value(json!(param))
+        .map_err(|_| error_response(Status::BadRequest, format!("Invalid {}: {}", name, param)))
+}
+
+pub fn json_or_not_found<T: Serialize>(item: Option<&T>, message: &str) -> Response {
+    match item {
+        Some(item) => match serde_json::to_string(item) {
+            Ok(body) => Response::with((Status::Ok, body)),
+            Err(e) => error_response(
+                Status::InternalServerError,
+                format!("Unable to serialize response: {:?}", e),
+            ),
+        },
+        None => error_response(Status::NotFound, message.to_string()),
+    }
+}
+
+fn error_response(status: Status, message: String) -> Response {
+    Response::with((status, json!({ "error": message }).to_string()))
+}
@@ -0,0 +1,79 @@
+//! Queries the balance of a validator, verifying it against the latest finalized state.
+use crate::beacon_node::BeaconNodeClient;
+use crate::store::HeaderStore;
+use types::{
+    state_length_indices, unverified_length, validator_balance_indices, verify_balance_proof,
+    Epoch, Hash256, MainnetEthSpec, PublicKey, Slot,
+};
+
+/// The balance of a validator, and the finalized state against which it was proven.
+#[derive(Debug, PartialEq)]
+pub struct VerifiedBalance {
+    pub validator_index: usize,
+    pub effective_balance: u64,
+    pub balance: u64,
+    pub state_root: Hash256,
+    pub finalized_epoch: Epoch,
+    pub finalized_root: Hash256,
+    pub finalized_slot: Slot,
+}
+
+/// Finds the balance of the validator with `pubkey` in the state of the latest finalized block,
+/// as reported by the beacon node and verified against Merkle proofs.
+///
+/// The finalized block is taken from a finality update, which `store` must already trust a
+/// finalized block to anchor: the update is only accepted with a proof that its head descends
+/// from that block. The validator index reported by the beacon node is not trusted; the proof
+/// must show `pubkey` at that index.
+pub fn query_balance(
+    client: &BeaconNodeClient,
+    store: &mut HeaderStore,
+    pubkey: &PublicKey,
+    slots_per_epoch: u64,
+) -> Result<VerifiedBalance, String> {
+    let indices = store.ancestry_indices::<MainnetEthSpec>().ok_or_else(|| {
+        "No finalized block is trusted; bootstrap from a checkpoint first".to_string()
+    })?;
+
+    let update = client
+        .finality_updates()?
+        .next()
+        .ok_or_else(|| "Finality stream closed without an update".to_string())??;
+    let ancestry = client.ancestry_proof(&update, Some(indices))?;
+    store
+        .import::<MainnetEthSpec>(&update, &ancestry)
+        .map_err(|e| format!("Invalid finality update: {:?}", e))?;
+    let finality = store
+        .finalized()
+        .cloned()
+        .ok_or_else(|| "No finalized block was verified".to_string())?;
+    let state_root = finality.finalized_header.state_root;
+
+    let epoch = finality.head_header.slot.epoch(slots_per_epoch);
+    let validator_index = client.validator_index(pubkey, epoch)?;
+
+    // The lengths are needed to find the validator's entries, and are proven again below.
+    let lengths = client.proof(state_root, state_length_indices().to_vec())?;
+    let [registry_length, balances_length] = state_length_indices();
+    let registry_len = unverified_length(&lengths, registry_length)
+        .ok_or_else(|| "Proof omits the registry length".to_string())?;
+    let balances_len = unverified_length(&lengths, balances_length)
+        .ok_or_else(|| "Proof omits the balances length".to_string())?;
+
+    let proof = client.proof(
+        state_root,
+        validator_balance_indices(registry_len, balances_len, validator_index),
+    )?;
+    let proven = verify_balance_proof(&proof, state_root, validator_index, pubkey)
+        .map_err(|e| format!("Invalid balance proof: {:?}", e))?;
+
+    Ok(VerifiedBalance {
+        validator_index,
+        effective_balance: proven.effective_balance,
+        balance: proven.balance,
+        state_root,
+        finalized_epoch: finality.finalized_epoch,
+        finalized_root: finality.finalized_root,
+        finalized_slot: finality.finalized_header.slot,
+    })
+}
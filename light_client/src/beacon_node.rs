@@ -0,0 +1,441 @@
+//! A client for the light client endpoints of a beacon node's HTTP API.
+use lightclient_protocol::{FinalityUpdate, HeaderUpdate, HttpError, ProofRequest, ProofResponse};
+use merkle_proof::SerializedPartial;
+use serde_derive::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use types::{block_root_of_header, BeaconBlockHeader, Epoch, Hash256, PublicKey};
+
+/// The name of the server-sent event which carries a `FinalityUpdate`.
+const FINALITY_UPDATE_EVENT: &str = "finality_update";
+
+/// The body of a `POST /validator/duties` request.
+#[derive(Serialize)]
+struct DutiesRequest<'a> {
+    epoch: Epoch,
+    pubkeys: &'a [PublicKey],
+}
+
+/// The part of a `POST /validator/duties` response used to find a validator's index.
+#[derive(Deserialize)]
+struct Duty {
+    validator_index: Option<usize>,
+}
+
+/// How long a request, other than for the finality stream, may take before the gateway is
+/// considered unavailable.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// The longest delay between two rounds of attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How requests are retried when no gateway answers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of rounds of attempts after the first, each trying every gateway.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubling with each retry up to `MAX_BACKOFF`.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// The delay before the retry numbered `retry`, from `0`.
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::max_value());
+        self.backoff
+            .checked_mul(factor)
+            .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+    }
+}
+
+/// Why a request to a single gateway failed.
+#[derive(Debug, PartialEq)]
+enum GatewayError {
+    /// The gateway could not be reached, failed or gave an invalid response. The request may
+    /// succeed at another gateway, or later.
+    Unavailable(String),
+    /// The gateway refused the request, which is not retried.
+    Refused(String),
+}
+
+impl From<reqwest::Error> for GatewayError {
+    fn from(e: reqwest::Error) -> GatewayError {
+        match e.status() {
+            Some(status) if status.is_client_error() => GatewayError::Refused(format!("{:?}", e)),
+            _ => GatewayError::Unavailable(format!("{:?}", e)),
+        }
+    }
+}
+
+/// A client for the HTTP API of one or more beacon nodes, or of light clients relaying one, which
+/// serve as interchangeable gateways.
+///
+/// All requests are made over HTTP(S), so no connection to the peer-to-peer network is needed.
+/// Each request is sent to the gateway which last answered, falling back to the others in order,
+/// and is retried with exponential backoff while none of them answers.
+pub struct BeaconNodeClient {
+    gateways: Vec<String>,
+    retry: RetryPolicy,
+    /// The index in `gateways` of the gateway which last answered.
+    preferred: AtomicUsize,
+    client: reqwest::Client,
+    /// A client without a timeout, for the long-lived finality stream.
+    stream_client: reqwest::Client,
+}
+
+impl BeaconNodeClient {
+    /// Creates a client for the HTTP APIs at `urls`, e.g., `https://localhost:5052`, which are
+    /// tried in order.
+    pub fn new(urls: &[String], retry: RetryPolicy) -> Result<Self, String> {
+        if urls.is_empty() {
+            return Err("No beacon node given".to_string());
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+        let stream_client = reqwest::Client::builder()
+            .timeout(None)
+            .build()
+            .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+
+        Ok(Self {
+            gateways: urls
+                .iter()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect(),
+            retry,
+            preferred: AtomicUsize::new(0),
+            client,
+            stream_client,
+        })
+    }
+
+    /// Opens the finality stream of the first gateway which answers, returning an iterator over
+    /// its updates.
+    ///
+    /// The iterator ends when the stream is closed.
+    pub fn finality_updates(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<FinalityUpdate, String>>, String> {
+        let response = self.request("open finality stream", |url| {
+            Ok(self
+                .stream_client
+                .get(&format!("{}/lightclient/finality_stream", url))
+                .send()
+                .and_then(|response| response.error_for_status())?)
+        })?;
+
+        let events = EventReader {
+            lines: BufReader::new(response).lines(),
+        };
+
+        Ok(events
+            .filter(|event| match event {
+                Ok((name, _)) => name == FINALITY_UPDATE_EVENT,
+                Err(_) => true,
+            })
+            .map(|event| {
+                event.and_then(|(_, data)| {
+                    serde_json::from_str(&data)
+                        .map_err(|e| format!("Invalid finality update: {:?}", e))
+                })
+            }))
+    }
+
+    /// Requests the header of the block at `root`, checking that it is the block at `root`.
+    pub fn header(&self, root: Hash256) -> Result<BeaconBlockHeader, String> {
+        self.request("request header", |url| {
+            let update: HeaderUpdate = self
+                .client
+                .get(&format!(
+                    "{}/lightclient/header/{}",
+                    url,
+                    root_to_string(root)
+                ))
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json())?;
+
+            // Another gateway may be honest.
+            if block_root_of_header(&update.header) != root {
+                return Err(GatewayError::Unavailable(format!(
+                    "Header is not the block at {}",
+                    root_to_string(root)
+                )));
+            }
+
+            Ok(update.header)
+        })
+    }
+
+    /// Asks the beacon node for the index of the validator with `pubkey`, as of `epoch`.
+    ///
+    /// The index is not verified; it must be checked against a proof of the registry.
+    pub fn validator_index(&self, pubkey: &PublicKey, epoch: Epoch) -> Result<usize, String> {
+        let duties: Vec<Duty> = self.request("request validator duties", |url| {
+            Ok(self
+                .client
+                .post(&format!("{}/validator/duties", url))
+                .json(&DutiesRequest {
+                    epoch,
+                    pubkeys: &[pubkey.clone()],
+                })
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json())?)
+        })?;
+
+        duties
+            .first()
+            .and_then(|duty| duty.validator_index)
+            .ok_or_else(|| "Unknown validator".to_string())
+    }
+
+    /// Requests a proof of the nodes at `indices`, as from `HeaderStore::ancestry_indices`, of
+    /// the head state of `update`. None is needed, so none is requested, if `indices` is `None`.
+    ///
+    /// The proof is not verified.
+    pub fn ancestry_proof(
+        &self,
+        update: &FinalityUpdate,
+        indices: Option<Vec<u64>>,
+    ) -> Result<SerializedPartial, String> {
+        match indices {
+            Some(indices) => self.proof(update.head_state_root, indices),
+            None => Ok(SerializedPartial {
+                indices: vec![],
+                chunks: vec![],
+            }),
+        }
+    }
+
+    /// Requests a proof of the nodes at `indices` of the state with root `state_root`.
+    ///
+    /// The proof is not verified.
+    pub fn proof(
+        &self,
+        state_root: Hash256,
+        indices: Vec<u64>,
+    ) -> Result<SerializedPartial, String> {
+        self.request("request proof", |url| {
+            let mut response = self
+                .client
+                .post(&format!("{}/lightclient/proof", url))
+                .json(&ProofRequest {
+                    state_root,
+                    indices: indices.clone(),
+                })
+                .send()?;
+
+            if response.status().is_server_error() {
+                return Err(GatewayError::Unavailable(format!(
+                    "Gateway failed: {}",
+                    response.status()
+                )));
+            }
+            if !response.status().is_success() {
+                let error: HttpError = response.json().map_err(|e| {
+                    GatewayError::Refused(format!("Invalid error response: {:?}", e))
+                })?;
+                return Err(GatewayError::Refused(format!(
+                    "Proof refused ({:?}): {}",
+                    error.error_code(),
+                    error.message
+                )));
+            }
+
+            let response: ProofResponse = response.json().map_err(|e| {
+                GatewayError::Unavailable(format!("Invalid proof response: {:?}", e))
+            })?;
+            if response.state_root != state_root {
+                return Err(GatewayError::Unavailable(format!(
+                    "Proof is of another state: {:?}",
+                    response.state_root
+                )));
+            }
+
+            Ok(response.proof)
+        })
+    }
+
+    /// Sends a request with `send`, given the URL of a gateway, to each gateway in turn, starting
+    /// with the one which last answered, until one answers or refuses it. If none does, waits and
+    /// tries them all again, up to `RetryPolicy::max_retries` times.
+    ///
+    /// `action` describes the request in errors.
+    fn request<T, F>(&self, action: &str, send: F) -> Result<T, String>
+    where
+        F: Fn(&str) -> Result<T, GatewayError>,
+    {
+        let mut last_error = String::new();
+
+        for attempt in 0..=self.retry.max_retries {
+            if attempt > 0 {
+                thread::sleep(self.retry.delay(attempt - 1));
+            }
+
+            let preferred = self.preferred.load(Ordering::Relaxed);
+            for i in gateway_order(self.gateways.len(), preferred) {
+                match send(&self.gateways[i]) {
+                    Ok(value) => {
+                        self.preferred.store(i, Ordering::Relaxed);
+                        return Ok(value);
+                    }
+                    Err(GatewayError::Refused(e)) => {
+                        return Err(format!("Unable to {}: {}", action, e));
+                    }
+                    Err(GatewayError::Unavailable(e)) => {
+                        last_error = format!("{}: {}", self.gateways[i], e);
+                    }
+                }
+            }
+        }
+
+        Err(format!(
+            "Unable to {} after {} attempts: {}",
+            action,
+            self.retry.max_retries + 1,
+            last_error
+        ))
+    }
+
+    /// The gateways, separated by commas, e.g., for logging.
+    pub fn gateways(&self) -> String {
+        self.gateways.join(",")
+    }
+}
+
+/// The indices of `n` gateways in the order they are tried, starting with `preferred`.
+fn gateway_order(n: usize, preferred: usize) -> impl Iterator<Item = usize> {
+    (0..n).map(move |i| (preferred + i) % n)
+}
+
+/// Formats `root` as `0x`-prefixed hex, as the HTTP API expects.
+fn root_to_string(root: Hash256) -> String {
+    serde_json::to_value(root)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Parses server-sent events into `(event, data)` pairs.
+struct EventReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+}
+
+impl<R: BufRead> Iterator for EventReader<R> {
+    type Item = Result<(String, String), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut event = String::new();
+        let mut data: Vec<String> = vec![];
+
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(format!("Unable to read event stream: {:?}", e))),
+            };
+
+            if line.is_empty() {
+                // An event ends with a blank line. A blank line after only comments is ignored.
+                if !data.is_empty() {
+                    return Some(Ok((event, data.join("\n"))));
+                }
+            } else if line.starts_with(':') {
+                // A comment, e.g., a keep-alive.
+            } else if line.starts_with("event:") {
+                event = line["event:".len()..].trim().to_string();
+            } else if line.starts_with("data:") {
+                data.push(line["data:".len()..].trim_start().to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn parses_events() {
+        let stream =
+            ": keep-alive\n\nevent: finality_update\ndata: {\"a\": 1}\n\ndata: x\ndata: y\n\n";
+        let events: Vec<_> = EventReader {
+            lines: stream.as_bytes().lines(),
+        }
+        .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                Ok(("finality_update".to_string(), "{\"a\": 1}".to_string())),
+                Ok((String::new(), "x\ny".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        let retry = RetryPolicy {
+            max_retries: 40,
+            backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(retry.delay(0), Duration::from_millis(500));
+        assert_eq!(retry.delay(3), Duration::from_secs(4));
+        assert_eq!(retry.delay(7), MAX_BACKOFF);
+        assert_eq!(retry.delay(39), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn falls_back_to_other_gateways() {
+        let urls = vec!["http://a".to_string(), "http://b".to_string()];
+        let client = BeaconNodeClient::new(
+            &urls,
+            RetryPolicy {
+                max_retries: 1,
+                backoff: Duration::from_millis(0),
+            },
+        )
+        .unwrap();
+        let tried = RefCell::new(vec![]);
+        let send = |url: &str| {
+            tried.borrow_mut().push(url.to_string());
+            match url {
+                "http://b" => Ok(()),
+                _ => Err(GatewayError::Unavailable("down".to_string())),
+            }
+        };
+
+        assert_eq!(client.request("test", send), Ok(()));
+        assert_eq!(client.request("test", send), Ok(()));
+        // The gateway which answered is tried first.
+        assert_eq!(*tried.borrow(), vec!["http://a", "http://b", "http://b"]);
+
+        tried.borrow_mut().clear();
+        let unavailable = client.request("test", |url| {
+            tried.borrow_mut().push(url.to_string());
+            Err::<(), _>(GatewayError::Unavailable("down".to_string()))
+        });
+        assert!(unavailable.is_err());
+        assert_eq!(tried.borrow().len(), 4);
+
+        tried.borrow_mut().clear();
+        let refused = client.request("test", |url| {
+            tried.borrow_mut().push(url.to_string());
+            Err::<(), _>(GatewayError::Refused("unknown".to_string()))
+        });
+        assert!(refused.is_err());
+        assert_eq!(tried.borrow().len(), 1);
+    }
+
+    #[test]
+    fn orders_gateways() {
+        assert_eq!(gateway_order(3, 0).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(gateway_order(3, 2).collect::<Vec<_>>(), vec![2, 0, 1]);
+    }
+}
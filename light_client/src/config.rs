@@ -0,0 +1,142 @@
+use crate::beacon_node::RetryPolicy;
+use crate::store::Checkpoint;
+use clap::ArgMatches;
+use std::path::PathBuf;
+use std::time::Duration;
+use types::{Epoch, EthSpec, Hash256, MainnetEthSpec};
+
+/// The default weak subjectivity period, in epochs.
+///
+/// This is a conservative choice of `MIN_VALIDATOR_WITHDRAWABILITY_DELAY`, within which a
+/// validator set which finalized a checkpoint cannot have withdrawn.
+pub const DEFAULT_WEAK_SUBJECTIVITY_PERIOD: u64 = 256;
+
+/// Stores the configuration of the light client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// The data directory, which stores the database of verified headers.
+    pub data_dir: PathBuf,
+    /// The HTTP(S) APIs from which updates are requested, tried in order. Each may be a beacon
+    /// node or a light client relaying one.
+    pub beacon_nodes: Vec<String>,
+    /// How requests are retried when no beacon node answers.
+    pub retry: RetryPolicy,
+    /// The address on which to serve the query API.
+    pub listen_address: String,
+    /// The port on which to serve the query API.
+    pub listen_port: String,
+    /// The number of slots per epoch of the beacon chain.
+    pub slots_per_epoch: u64,
+    /// A trusted finalized checkpoint from which to bootstrap, if any.
+    pub checkpoint: Option<Checkpoint>,
+    /// The most epochs by which a single update may advance finality, when bootstrapped from a
+    /// checkpoint.
+    pub weak_subjectivity_period: u64,
+    /// If `true`, relay verified updates and proofs to other clients over the query API.
+    pub serve: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from(".lighthouse-lc"),
+            beacon_nodes: vec!["http://localhost:5052".to_string()],
+            retry: RetryPolicy {
+                max_retries: 3,
+                backoff: Duration::from_millis(500),
+            },
+            listen_address: "127.0.0.1".to_string(),
+            listen_port: "5053".to_string(),
+            slots_per_epoch: MainnetEthSpec::slots_per_epoch(),
+            checkpoint: None,
+            weak_subjectivity_period: DEFAULT_WEAK_SUBJECTIVITY_PERIOD,
+            serve: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn apply_cli_args(&mut self, args: &ArgMatches) -> Result<(), String> {
+        if let Some(datadir) = args.value_of("datadir") {
+            self.data_dir = PathBuf::from(datadir);
+        }
+
+        if let Some(beacon_nodes) = args.value_of("beacon-node") {
+            self.beacon_nodes = beacon_nodes
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect();
+            if self.beacon_nodes.is_empty() {
+                return Err(format!("Invalid beacon node {}", beacon_nodes));
+            }
+        }
+
+        if let Some(max_retries) = args.value_of("max-retries") {
+            self.retry.max_retries = max_retries
+                .parse::<u32>()
+                .map_err(|e| format!("Invalid max retries {}: {:?}", max_retries, e))?;
+        }
+
+        if let Some(backoff) = args.value_of("retry-backoff") {
+            let millis = backoff
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid retry backoff {}: {:?}", backoff, e))?;
+            self.retry.backoff = Duration::from_millis(millis);
+        }
+
+        if let Some(listen_address) = args.value_of("listen-address") {
+            self.listen_address = listen_address.to_string();
+        }
+
+        if let Some(port) = args.value_of("port") {
+            port.parse::<u16>()
+                .map_err(|e| format!("Invalid port {}: {:?}", port, e))?;
+            self.listen_port = port.to_string();
+        }
+
+        if let Some(slots_per_epoch) = args.value_of("slots-per-epoch") {
+            self.slots_per_epoch = slots_per_epoch
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid slots per epoch {}: {:?}", slots_per_epoch, e))?;
+        }
+
+        if let (Some(root), Some(epoch)) = (
+            args.value_of("checkpoint-root"),
+            args.value_of("checkpoint-epoch"),
+        ) {
+            let root: Hash256 = serde_json::from_value(serde_json::json!(root))
+                .map_err(|e| format!("Invalid checkpoint root {}: {:?}", root, e))?;
+            let epoch = epoch
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid checkpoint epoch {}: {:?}", epoch, e))?;
+            self.checkpoint = Some(Checkpoint {
+                epoch: Epoch::new(epoch),
+                root,
+            });
+        }
+
+        if let Some(period) = args.value_of("weak-subjectivity-period") {
+            self.weak_subjectivity_period = period
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid weak subjectivity period {}: {:?}", period, e))?;
+        }
+
+        if args.is_present("serve") {
+            self.serve = true;
+        }
+
+        Ok(())
+    }
+
+    /// The directory of the database of verified headers.
+    pub fn db_path(&self) -> PathBuf {
+        self.data_dir.join("headers_db")
+    }
+
+    /// The address and port of the query API, e.g., `127.0.0.1:5053`.
+    pub fn listen_socket(&self) -> String {
+        format!("{}:{}", self.listen_address, self.listen_port)
+    }
+}
@@ -0,0 +1,340 @@
+mod api;
+mod balance;
+mod beacon_node;
+mod config;
+mod persisted_store;
+mod relay;
+mod store;
+
+use crate::beacon_node::BeaconNodeClient;
+use crate::config::Config;
+use crate::persisted_store::{PersistedHeaderStore, HEADER_STORE_DB_KEY};
+use crate::store::HeaderStore;
+use ::store::{DiskStore, Store};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use eth2_config::get_data_dir;
+use slog::{crit, info, o, warn, Drain};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+use types::{Hash256, MainnetEthSpec, PublicKey};
+
+pub const DEFAULT_DATA_DIR: &str = ".lighthouse-lc";
+
+/// How long to wait before reconnecting to the beacon node after the finality stream ends.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+fn main() {
+    // Logging
+    let decorator = slog_term::TermDecorator::new().build();
+    let drain = slog_term::CompactFormat::new(decorator).build().fuse();
+    let drain = slog_async::Async::new(drain).build().fuse();
+    let log = slog::Logger::root(drain, o!());
+
+    // CLI
+    let matches = App::new("Lighthouse Light Client")
+        .version("0.0.1")
+        .author("Sigma Prime <contact@sigmaprime.io>")
+        .about("Follows finality of an Eth 2.0 beacon chain, verifying each update against Merkle proofs")
+        .arg(
+            Arg::with_name("datadir")
+                .long("datadir")
+                .value_name("DIR")
+                .help("Data directory for the database of verified headers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("beacon-node")
+                .long("beacon-node")
+                .value_name("URL[,URL...]")
+                .help("The HTTP(S) APIs of the beacon nodes to follow, separated by commas. Each is tried in turn until one answers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-retries")
+                .long("max-retries")
+                .value_name("RETRIES")
+                .help("The number of times to retry a request when no beacon node answers.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("retry-backoff")
+                .long("retry-backoff")
+                .value_name("MILLIS")
+                .help("The delay before the first retry, in milliseconds, which doubles with each retry.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("listen-address")
+                .long("listen-address")
+                .value_name("ADDRESS")
+                .help("The address on which to serve the query API.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("port")
+                .long("port")
+                .value_name("PORT")
+                .help("The port on which to serve the query API.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("slots-per-epoch")
+                .long("slots-per-epoch")
+                .value_name("SLOTS")
+                .help("The number of slots per epoch of the beacon chain.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("checkpoint-root")
+                .long("checkpoint-root")
+                .value_name("ROOT")
+                .help("The block root of a trusted finalized checkpoint from which to bootstrap.")
+                .requires("checkpoint-epoch")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("checkpoint-epoch")
+                .long("checkpoint-epoch")
+                .value_name("EPOCH")
+                .help("The epoch of the trusted finalized checkpoint.")
+                .requires("checkpoint-root")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("weak-subjectivity-period")
+                .long("weak-subjectivity-period")
+                .value_name("EPOCHS")
+                .help("The most epochs by which a single update may advance finality, when bootstrapped from a checkpoint.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("serve")
+                .long("serve")
+                .help("Relay verified finality updates, headers and proofs to other clients over the query API.")
+                .takes_value(false),
+        )
+        .subcommand(
+            SubCommand::with_name("balance")
+                .about("Prints the balance of a validator, proven against the latest finalized state, which must descend from a trusted checkpoint")
+                .arg(
+                    Arg::with_name("pubkey")
+                        .value_name("PUBKEY")
+                        .help("The 0x-prefixed public key of the validator.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .get_matches();
+
+    let mut config = Config::default();
+    match get_data_dir(&matches, PathBuf::from(DEFAULT_DATA_DIR)) {
+        Ok(data_dir) => config.data_dir = data_dir,
+        Err(e) => {
+            crit!(log, "Failed to initialize data dir"; "error" => e);
+            return;
+        }
+    }
+    if let Err(e) = config.apply_cli_args(&matches) {
+        crit!(log, "Failed to parse CLI arguments"; "error" => e);
+        return;
+    }
+
+    let client = match BeaconNodeClient::new(&config.beacon_nodes, config.retry) {
+        Ok(client) => Arc::new(client),
+        Err(e) => {
+            crit!(log, "Failed to create beacon node client"; "error" => e);
+            return;
+        }
+    };
+
+    let db = match fs::create_dir_all(&config.data_dir)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|_| DiskStore::open(&config.db_path()).map_err(|e| format!("{:?}", e)))
+    {
+        Ok(db) => db,
+        Err(e) => {
+            crit!(log, "Failed to open database"; "error" => e, "path" => format!("{:?}", config.db_path()));
+            return;
+        }
+    };
+
+    let mut store = match load_or_bootstrap(&db, &client, &config, &log) {
+        Ok(store) => store,
+        Err(e) => {
+            crit!(log, "Failed to initialize header store"; "error" => e);
+            return;
+        }
+    };
+
+    if let Some(matches) = matches.subcommand_matches("balance") {
+        print_balance(&client, &mut store, matches, &config, &log);
+        return;
+    }
+    let store = Arc::new(RwLock::new(store));
+
+    let upstream = if config.serve {
+        Some(client.clone())
+    } else {
+        None
+    };
+    let _listening = match api::start_server(store.clone(), upstream, &config.listen_socket()) {
+        Ok(listening) => listening,
+        Err(e) => {
+            crit!(log, "Failed to start query API"; "error" => e);
+            return;
+        }
+    };
+    info!(log, "Query API running"; "address" => config.listen_socket());
+
+    follow_finality(&client, &store, &db, &log);
+}
+
+/// Resumes the header store from the database if it was persisted, otherwise creates it,
+/// bootstrapping from the trusted checkpoint if one is configured.
+fn load_or_bootstrap(
+    db: &DiskStore,
+    client: &BeaconNodeClient,
+    config: &Config,
+    log: &slog::Logger,
+) -> Result<HeaderStore, String> {
+    let key = Hash256::from_slice(HEADER_STORE_DB_KEY.as_bytes());
+    let persisted: Option<PersistedHeaderStore> = db
+        .get(&key)
+        .map_err(|e| format!("Unable to read database: {:?}", e))?;
+
+    if let Some(persisted) = persisted {
+        let store = HeaderStore::from_persisted(persisted);
+        if config.checkpoint.is_some() {
+            warn!(log, "Ignoring checkpoint, resuming from database");
+        }
+        info!(
+            log,
+            "Resumed from database";
+            "finalized_epoch" => store.finalized().map(|finalized| finalized.finalized_epoch.as_u64()),
+        );
+        return Ok(store);
+    }
+
+    match config.checkpoint {
+        Some(checkpoint) => {
+            let header = client.header(checkpoint.root)?;
+            let store =
+                HeaderStore::from_checkpoint(checkpoint, header, config.weak_subjectivity_period)
+                    .map_err(|e| format!("Invalid checkpoint: {:?}", e))?;
+            info!(
+                log,
+                "Bootstrapped from checkpoint";
+                "epoch" => checkpoint.epoch.as_u64(),
+                "root" => format!("{:?}", checkpoint.root),
+            );
+            Ok(store)
+        }
+        None => Ok(HeaderStore::default()),
+    }
+}
+
+/// Queries and prints the verified balance of the validator given on the command line.
+fn print_balance(
+    client: &BeaconNodeClient,
+    store: &mut HeaderStore,
+    matches: &ArgMatches,
+    config: &Config,
+    log: &slog::Logger,
+) {
+    let pubkey = matches.value_of("pubkey").unwrap_or("");
+    let pubkey: PublicKey = match serde_json::from_value(serde_json::json!(pubkey)) {
+        Ok(pubkey) => pubkey,
+        Err(e) => {
+            crit!(log, "Invalid public key"; "error" => format!("{:?}", e));
+            return;
+        }
+    };
+
+    match balance::query_balance(client, store, &pubkey, config.slots_per_epoch) {
+        Ok(balance) => {
+            println!("validator_index:   {}", balance.validator_index);
+            println!("balance:           {}", balance.balance);
+            println!("effective_balance: {}", balance.effective_balance);
+            println!("state_root:        {:?}", balance.state_root);
+            println!("finalized_root:    {:?}", balance.finalized_root);
+            println!("finalized_epoch:   {}", balance.finalized_epoch.as_u64());
+            println!("finalized_slot:    {}", balance.finalized_slot.as_u64());
+        }
+        Err(e) => crit!(log, "Unable to verify balance"; "error" => e),
+    }
+}
+
+/// Imports finality updates from the beacon nodes until the process is stopped, reconnecting,
+/// possibly to another beacon node, whenever the stream ends.
+fn follow_finality(
+    client: &BeaconNodeClient,
+    store: &RwLock<HeaderStore>,
+    db: &DiskStore,
+    log: &slog::Logger,
+) {
+    loop {
+        info!(log, "Following finality"; "beacon_nodes" => client.gateways());
+
+        match client.finality_updates() {
+            Ok(updates) => {
+                for update in updates {
+                    let update = match update {
+                        Ok(update) => update,
+                        Err(e) => {
+                            warn!(log, "Unable to read finality update"; "error" => e);
+                            break;
+                        }
+                    };
+
+                    let indices = store
+                        .read()
+                        .expect("store lock is not poisoned")
+                        .ancestry_indices::<MainnetEthSpec>();
+                    let ancestry = match client.ancestry_proof(&update, indices) {
+                        Ok(ancestry) => ancestry,
+                        Err(e) => {
+                            warn!(log, "Unable to fetch proof of ancestry"; "error" => e);
+                            continue;
+                        }
+                    };
+
+                    let result = {
+                        let mut store = store.write().expect("store lock is not poisoned");
+                        store
+                            .import::<MainnetEthSpec>(&update, &ancestry)
+                            .map(|advanced| (advanced, store.as_persisted()))
+                    };
+
+                    if let Ok((_, persisted)) = &result {
+                        let key = Hash256::from_slice(HEADER_STORE_DB_KEY.as_bytes());
+                        if let Err(e) = db.put(&key, persisted) {
+                            warn!(log, "Unable to persist header store"; "error" => format!("{:?}", e));
+                        }
+                    }
+
+                    match result {
+                        Ok((true, _)) => info!(
+                            log,
+                            "Verified finalized checkpoint";
+                            "epoch" => update.finalized_epoch.as_u64(),
+                            "root" => format!("{}", update.finalized_root),
+                        ),
+                        Ok((false, _)) => {}
+                        Err(e) => warn!(
+                            log,
+                            "Rejected finality update";
+                            "error" => format!("{:?}", e),
+                            "epoch" => update.finalized_epoch.as_u64(),
+                        ),
+                    }
+                }
+            }
+            Err(e) => warn!(log, "Unable to reach beacon node"; "error" => e),
+        }
+
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
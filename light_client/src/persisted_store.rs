@@ -0,0 +1,39 @@
+use crate::store::{Checkpoint, VerifiedFinality};
+use ::store::{DBColumn, Error as StoreError, StoreItem};
+use merkle_proof::SerializedPartial;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use types::{BeaconBlockHeader, Epoch, Hash256};
+
+/// 32-byte key for accessing the `PersistedHeaderStore`.
+pub const HEADER_STORE_DB_KEY: &str = "PERSISTEDHEADERSTOREPERSISTEDHEA";
+
+/// The contents of a `HeaderStore`, as written to disk so that the light client may resume from
+/// the last verified update after a restart.
+#[derive(Encode, Decode)]
+pub struct PersistedHeaderStore {
+    pub checkpoint: Option<Checkpoint>,
+    pub weak_subjectivity_period: Option<u64>,
+    pub finalized: Option<VerifiedFinality>,
+    /// Finalized block roots, with the epoch of each in `finalized_epochs`.
+    pub finalized_epochs: Vec<Epoch>,
+    pub finalized_roots: Vec<Hash256>,
+    pub headers: Vec<BeaconBlockHeader>,
+    /// Proven chunks, with the state root against which each was proven in `partial_roots`.
+    pub partial_roots: Vec<Hash256>,
+    pub partials: Vec<SerializedPartial>,
+}
+
+impl StoreItem for PersistedHeaderStore {
+    fn db_column() -> DBColumn {
+        DBColumn::LightClient
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &mut [u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
@@ -0,0 +1,202 @@
+//! Serves the light client endpoints of the beacon node HTTP API from verified data, so that other
+//! clients, including other light clients, may follow this one rather than the beacon node.
+use crate::api::{get_store, json_or_not_found, root_param};
+use crate::beacon_node::BeaconNodeClient;
+use crate::store::{HeaderStore, ProofError};
+use iron::headers::ContentType;
+use iron::mime::Mime;
+use iron::prelude::*;
+use iron::response::WriteBody;
+use iron::status::Status;
+use iron::typemap::Key;
+use lightclient_protocol::{ErrorCode, HeaderUpdate, HttpError, ProofRequest, ProofResponse};
+use merkle_proof::{metrics, redundant_index, SerializedPartial};
+use persistent::Read;
+use serde_json::json;
+use std::io::{self, Read as IoRead, Write};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use types::{Epoch, Hash256};
+
+/// How often each open stream checks the store for a new finalized checkpoint.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The longest a stream may go without writing, after which a comment is written so that a
+/// disconnected client is detected.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The beacon node from which proofs missing from the store are fetched.
+pub struct UpstreamKey;
+
+impl Key for UpstreamKey {
+    type Value = Arc<BeaconNodeClient>;
+}
+
+/// Opens a server-sent events stream which emits a `finality_update` event with the latest
+/// verified update, then another each time finality advances.
+///
+/// Each open stream occupies one of the server's worker threads until the client disconnects.
+pub fn handle_finality_stream(req: &mut Request) -> IronResult<Response> {
+    let store = get_store(req)?;
+
+    let mut response = Response::with(Status::Ok);
+    response.headers.set(ContentType(event_stream_mime()));
+    response.body = Some(Box::new(FinalityStream {
+        store: (*store).clone(),
+        last_sent: None,
+    }));
+
+    Ok(response)
+}
+
+fn event_stream_mime() -> Mime {
+    "text/event-stream"
+        .parse()
+        .expect("text/event-stream is a valid mime type")
+}
+
+/// A response body which writes verified finality updates until the client disconnects.
+struct FinalityStream {
+    store: Arc<RwLock<HeaderStore>>,
+    /// The finalized checkpoint of the last update written to the client.
+    last_sent: Option<(Epoch, Hash256)>,
+}
+
+impl WriteBody for FinalityStream {
+    fn write_body(&mut self, res: &mut dyn Write) -> io::Result<()> {
+        let mut last_write = Instant::now();
+
+        // Returns once a write fails, i.e., when the client has disconnected.
+        loop {
+            let update = {
+                let store = self.store.read().expect("store lock is not poisoned");
+                store
+                    .latest_update()
+                    .filter(|update| {
+                        self.last_sent != Some((update.finalized_epoch, update.finalized_root))
+                    })
+                    .cloned()
+            };
+
+            if let Some(update) = update {
+                let data = serde_json::to_string(&update)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                write!(res, "event: finality_update\ndata: {}\n\n", data)?;
+                res.flush()?;
+                self.last_sent = Some((update.finalized_epoch, update.finalized_root));
+                last_write = Instant::now();
+            } else if last_write.elapsed() >= KEEP_ALIVE_INTERVAL {
+                write!(res, ": keep-alive\n\n")?;
+                res.flush()?;
+                last_write = Instant::now();
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Returns the verified header of the block at `:root`.
+pub fn handle_header(req: &mut Request) -> IronResult<Response> {
+    let root = match root_param(req, "root") {
+        Ok(root) => root,
+        Err(response) => return Ok(response),
+    };
+    let store = get_store(req)?;
+    let store = store.read().expect("store lock is not poisoned");
+
+    let update = store.header(&root).map(|header| HeaderUpdate {
+        header: header.clone(),
+    });
+    Ok(json_or_not_found(update.as_ref(), "Unknown block root"))
+}
+
+/// Returns a proof of the nodes at the requested generalized indices of the state of a verified
+/// header.
+///
+/// The proof is built from the chunks already proven if possible. Otherwise it is fetched from
+/// the beacon node and verified, and its chunks are kept for later requests.
+pub fn handle_proof(req: &mut Request) -> IronResult<Response> {
+    let mut body = String::new();
+    if let Err(e) = req.body.read_to_string(&mut body) {
+        return Ok(proof_error(
+            ErrorCode::InvalidRequest,
+            format!("Unable to read request body: {:?}", e),
+        ));
+    }
+
+    let request: ProofRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            return Ok(proof_error(
+                ErrorCode::InvalidRequest,
+                format!("Invalid request body: {:?}", e),
+            ))
+        }
+    };
+    if let Err(code) = request.validate() {
+        return Ok(proof_error(code, format!("Invalid request: {:?}", code)));
+    }
+    // Such a proof would be refused by `HeaderStore::import_proof`, so is not requested.
+    if let Some(index) = redundant_index(&request.indices) {
+        return Ok(proof_error(
+            ErrorCode::InvalidRequest,
+            format!("Index {} is an ancestor of another index", index),
+        ));
+    }
+
+    let store = get_store(req)?;
+    {
+        let store = store.read().expect("store lock is not poisoned");
+        if !store.is_known_state_root(&request.state_root) {
+            return Ok(proof_error(
+                ErrorCode::UnknownStateRoot,
+                format!("Unknown state root: {:?}", request.state_root),
+            ));
+        }
+        if let Some(proof) = store.cached_proof(&request.state_root, &request.indices) {
+            metrics::record_cache_hit();
+            return Ok(proof_response(request.state_root, proof));
+        }
+    }
+    metrics::record_cache_miss();
+
+    let upstream = req
+        .get::<Read<UpstreamKey>>()
+        .map_err(|e| IronError::new(e, Status::InternalServerError))?;
+    let proof = match upstream.proof(request.state_root, request.indices.clone()) {
+        Ok(proof) => proof,
+        Err(e) => return Ok(proof_error(ErrorCode::ServerError, e)),
+    };
+
+    let result = store
+        .write()
+        .expect("store lock is not poisoned")
+        .import_proof(request.state_root, &proof, &request.indices);
+    match result {
+        Ok(()) => Ok(proof_response(request.state_root, proof)),
+        Err(ProofError::UnknownStateRoot) => Ok(proof_error(
+            ErrorCode::UnknownStateRoot,
+            format!("Unknown state root: {:?}", request.state_root),
+        )),
+        Err(e) => Ok(proof_error(
+            ErrorCode::ServerError,
+            format!("Beacon node served an invalid proof: {:?}", e),
+        )),
+    }
+}
+
+fn proof_response(state_root: Hash256, proof: SerializedPartial) -> Response {
+    json_or_not_found(Some(&ProofResponse { state_root, proof }), "")
+}
+
+/// Builds a response carrying an `HttpError`, as the beacon node does.
+fn proof_error(code: ErrorCode, message: String) -> Response {
+    let status = Status::from_u16(code.http_status());
+    let body = serde_json::to_string(&HttpError::new(code, message, serde_json::Value::Null))
+        .unwrap_or_else(|_| json!({ "code": code.as_u64() }).to_string());
+
+    Response::with((status, body))
+}
@@ -0,0 +1,942 @@
+//! The headers and state chunks the light client has verified.
+use crate::persisted_store::PersistedHeaderStore;
+use lightclient_protocol::FinalityUpdate;
+use merkle_proof::{helper_indices, verify_partial, PartialError, SerializedPartial};
+use serde_derive::Serialize;
+use ssz_derive::{Decode, Encode};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use types::{
+    ancestry_indices, block_root_of_header, verify_ancestry_proof, verify_finality_proof,
+    BeaconBlockHeader, BeaconState, Epoch, EthSpec, FinalityProofError, Hash256, MainnetEthSpec,
+    Slot,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum UpdateError {
+    /// The head header does not commit to the head state root.
+    HeadStateRootMismatch,
+    InvalidFinalityProof(FinalityProofError),
+    /// The proven checkpoint differs from the one claimed by the update.
+    CheckpointMismatch,
+    /// The update did not include the finalized header.
+    MissingFinalizedHeader,
+    /// The finalized header is not the block at the finalized root.
+    FinalizedHeaderMismatch,
+    /// The update finalizes an earlier epoch than one already verified.
+    FinalityReverted {
+        known: Epoch,
+        update: Epoch,
+    },
+    /// The update finalizes a different block in an epoch for which another was verified.
+    ConflictingFinality(Epoch),
+    /// The update finalizes an epoch before the trusted checkpoint.
+    BeforeCheckpoint {
+        checkpoint: Epoch,
+        update: Epoch,
+    },
+    /// The update finalizes a different block in the epoch of the trusted checkpoint.
+    ConflictsWithCheckpoint,
+    /// The update finalizes an epoch more than the weak subjectivity period after the latest
+    /// known finalized epoch, so the light client must be bootstrapped from a newer checkpoint.
+    BeyondWeakSubjectivityPeriod {
+        known: Epoch,
+        update: Epoch,
+    },
+    /// The update does not prove that its head descends from the latest known finalized block.
+    UnprovenAncestry(FinalityProofError),
+}
+
+/// The most reorg events kept for the query API.
+pub const MAX_REORG_EVENTS: usize = 64;
+
+/// The reasons a proof fetched on behalf of another client may be refused.
+#[derive(Debug, PartialEq)]
+pub enum ProofError {
+    /// The state root is not that of any verified header.
+    UnknownStateRoot,
+    MalformedProof(PartialError),
+    /// The proof does not hash to the state root.
+    InvalidProof,
+    /// The node at this generalized index is absent from the proof, or does not contribute to
+    /// the root.
+    Unproven(u64),
+}
+
+/// A finalized block root, trusted by the user, from which the light client is bootstrapped.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct Checkpoint {
+    pub epoch: Epoch,
+    pub root: Hash256,
+}
+
+/// A finalized checkpoint and the head it was proven from.
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, Decode)]
+pub struct VerifiedFinality {
+    pub finalized_epoch: Epoch,
+    pub finalized_root: Hash256,
+    pub finalized_header: BeaconBlockHeader,
+    pub head_header: BeaconBlockHeader,
+    pub head_state_root: Hash256,
+}
+
+/// A change of head to a block which does not descend from the previous head.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReorgEvent {
+    /// The slot of the new head.
+    pub slot: Slot,
+    pub old_head_root: Hash256,
+    pub new_head_root: Hash256,
+    /// The roots of the verified headers dropped with the old head, whose proven chunks were
+    /// also dropped.
+    pub abandoned_roots: Vec<Hash256>,
+}
+
+/// Checks that `update` is internally consistent: that its finality proof is valid against the
+/// state root of its head header, and that its finalized header is the proven block.
+///
+/// Block signatures are not checked, since that requires the shuffling and so the whole validator
+/// registry, nor is the finalized block checked to descend from any block already trusted. Only
+/// `HeaderStore::import`, given a proof of ancestry, anchors an update to a trusted block.
+pub fn verify_update(update: &FinalityUpdate) -> Result<VerifiedFinality, UpdateError> {
+    if update.head_header.state_root != update.head_state_root {
+        return Err(UpdateError::HeadStateRootMismatch);
+    }
+
+    validate_state_partial(&update.finality_proof)
+        .map_err(|e| UpdateError::InvalidFinalityProof(FinalityProofError::MalformedProof(e)))?;
+    let (finalized_epoch, finalized_root) =
+        verify_finality_proof(&update.finality_proof, update.head_state_root)
+            .map_err(UpdateError::InvalidFinalityProof)?;
+    if (finalized_epoch, finalized_root) != (update.finalized_epoch, update.finalized_root) {
+        return Err(UpdateError::CheckpointMismatch);
+    }
+
+    let finalized_header = update
+        .finalized_header
+        .clone()
+        .ok_or(UpdateError::MissingFinalizedHeader)?;
+    if block_root_of_header(&finalized_header) != finalized_root {
+        return Err(UpdateError::FinalizedHeaderMismatch);
+    }
+
+    Ok(VerifiedFinality {
+        finalized_epoch,
+        finalized_root,
+        finalized_header,
+        head_header: update.head_header.clone(),
+        head_state_root: update.head_state_root,
+    })
+}
+
+/// Checks that `partial` is well-formed as a proof into a state, before it is verified or cached.
+fn validate_state_partial(partial: &SerializedPartial) -> Result<(), PartialError> {
+    // Only the depth of the latest block and state roots depends on the spec, and that of the
+    // mainnet spec admits those of the smaller specs.
+    partial.validate_structure::<BeaconState<MainnetEthSpec>>()
+}
+
+/// Verified headers, by block root, and the state chunks proven against each state root.
+#[derive(Default)]
+pub struct HeaderStore {
+    /// The trusted checkpoint, which no update may revert or conflict with.
+    checkpoint: Option<Checkpoint>,
+    /// The most epochs by which an update may advance the finalized epoch, if limited.
+    weak_subjectivity_period: Option<u64>,
+    finalized: Option<VerifiedFinality>,
+    /// Finalized block roots by epoch, to detect conflicting updates.
+    finalized_roots: BTreeMap<Epoch, Hash256>,
+    headers: HashMap<Hash256, BeaconBlockHeader>,
+    /// The roots of `headers`, by slot.
+    slots: BTreeMap<Slot, Vec<Hash256>>,
+    /// Proven chunks by generalized index, for each state root.
+    partials: HashMap<Hash256, BTreeMap<u64, Hash256>>,
+    /// The latest update to advance finality, to be relayed to other clients.
+    latest_update: Option<FinalityUpdate>,
+    /// The most recent reorgs, oldest first.
+    reorgs: VecDeque<ReorgEvent>,
+}
+
+impl HeaderStore {
+    /// Creates a store bootstrapped from a trusted `checkpoint`, whose block has `header`.
+    ///
+    /// Updates which revert or conflict with the checkpoint, or which advance the finalized epoch
+    /// by more than `weak_subjectivity_period` epochs at once, are refused.
+    pub fn from_checkpoint(
+        checkpoint: Checkpoint,
+        header: BeaconBlockHeader,
+        weak_subjectivity_period: u64,
+    ) -> Result<Self, UpdateError> {
+        if block_root_of_header(&header) != checkpoint.root {
+            return Err(UpdateError::FinalizedHeaderMismatch);
+        }
+
+        let mut store = Self {
+            checkpoint: Some(checkpoint),
+            weak_subjectivity_period: Some(weak_subjectivity_period),
+            ..Self::default()
+        };
+        store.insert_header(checkpoint.root, header);
+        store
+            .finalized_roots
+            .insert(checkpoint.epoch, checkpoint.root);
+
+        Ok(store)
+    }
+
+    /// Verifies `update` and, if it is consistent with the updates already imported, stores its
+    /// headers and proven chunks.
+    ///
+    /// Unless it finalizes the latest known finalized block, `ancestry` must prove that block an
+    /// ancestor of the head of `update`, against the nodes at `ancestry_indices`.
+    ///
+    /// Returns `true` if the update advanced the finalized checkpoint.
+    pub fn import<T: EthSpec>(
+        &mut self,
+        update: &FinalityUpdate,
+        ancestry: &SerializedPartial,
+    ) -> Result<bool, UpdateError> {
+        let verified = verify_update(update)?;
+
+        if let Some(checkpoint) = &self.checkpoint {
+            if verified.finalized_epoch < checkpoint.epoch {
+                return Err(UpdateError::BeforeCheckpoint {
+                    checkpoint: checkpoint.epoch,
+                    update: verified.finalized_epoch,
+                });
+            }
+            if verified.finalized_epoch == checkpoint.epoch
+                && verified.finalized_root != checkpoint.root
+            {
+                return Err(UpdateError::ConflictsWithCheckpoint);
+            }
+        }
+        if let (Some(period), Some(known)) = (self.weak_subjectivity_period, self.known_epoch()) {
+            if verified.finalized_epoch > known + period {
+                return Err(UpdateError::BeyondWeakSubjectivityPeriod {
+                    known,
+                    update: verified.finalized_epoch,
+                });
+            }
+        }
+
+        if let Some(known) = &self.finalized {
+            if verified.finalized_epoch < known.finalized_epoch {
+                return Err(UpdateError::FinalityReverted {
+                    known: known.finalized_epoch,
+                    update: verified.finalized_epoch,
+                });
+            }
+        }
+        if let Some(root) = self.finalized_roots.get(&verified.finalized_epoch) {
+            if *root != verified.finalized_root {
+                return Err(UpdateError::ConflictingFinality(verified.finalized_epoch));
+            }
+        }
+
+        // Both blocks are ancestors of the head, so the later finalized block descends from the
+        // known one.
+        if let Some((slot, root)) = self.anchor() {
+            if verified.finalized_root != root {
+                verify_ancestry_proof::<T>(ancestry, verified.head_state_root, slot, root)
+                    .map_err(UpdateError::UnprovenAncestry)?;
+            }
+        }
+
+        let head_root = block_root_of_header(&verified.head_header);
+        if let Some(reorg) = self.detect_reorg(head_root, &verified) {
+            for root in &reorg.abandoned_roots {
+                self.remove_header(root);
+            }
+            if self.reorgs.len() >= MAX_REORG_EVENTS {
+                self.reorgs.pop_front();
+            }
+            self.reorgs.push_back(reorg);
+        }
+
+        self.insert_header(head_root, verified.head_header.clone());
+        self.insert_header(verified.finalized_root, verified.finalized_header.clone());
+        self.import_partial(verified.head_state_root, &update.finality_proof);
+        self.import_partial(verified.head_state_root, ancestry);
+
+        let advanced = self.finalized.as_ref().map_or(true, |known| {
+            known.finalized_epoch < verified.finalized_epoch
+        });
+
+        self.finalized_roots
+            .insert(verified.finalized_epoch, verified.finalized_root);
+        self.finalized = Some(verified);
+
+        if advanced {
+            self.latest_update = Some(update.clone());
+            self.prune();
+        }
+
+        Ok(advanced)
+    }
+
+    /// Returns a reorg event if the head of `verified`, with root `head_root`, does not descend
+    /// from the previous head.
+    ///
+    /// Only verified headers are known, so ancestry is followed through them alone. If the chain
+    /// of known ancestors ends before the slot of the previous head, the new head is assumed to
+    /// descend from it.
+    fn detect_reorg(&self, head_root: Hash256, verified: &VerifiedFinality) -> Option<ReorgEvent> {
+        let old_head = &self.finalized.as_ref()?.head_header;
+        let old_head_root = block_root_of_header(old_head);
+
+        let mut new_chain = HashSet::new();
+        let mut root = head_root;
+        let mut header = &verified.head_header;
+        loop {
+            if root == old_head_root {
+                return None;
+            }
+            new_chain.insert(root);
+            if header.slot <= old_head.slot {
+                break;
+            }
+            root = header.previous_block_root;
+            header = self.headers.get(&root)?;
+        }
+
+        // Drop the old head and its known ancestors back to the new chain or to a finalized
+        // block, neither of which are abandoned.
+        let finalized: HashSet<Hash256> = self
+            .finalized_roots
+            .values()
+            .cloned()
+            .chain(std::iter::once(verified.finalized_root))
+            .collect();
+        let mut abandoned_roots = vec![];
+        let mut root = old_head_root;
+        while !new_chain.contains(&root) && !finalized.contains(&root) {
+            let header = match self.headers.get(&root) {
+                Some(header) => header,
+                None => break,
+            };
+            abandoned_roots.push(root);
+            root = header.previous_block_root;
+        }
+
+        Some(ReorgEvent {
+            slot: verified.head_header.slot,
+            old_head_root,
+            new_head_root: head_root,
+            abandoned_roots,
+        })
+    }
+
+    /// Drops the header of the block at `root` and the chunks proven against its state.
+    fn remove_header(&mut self, root: &Hash256) {
+        if let Some(header) = self.headers.remove(root) {
+            self.partials.remove(&header.state_root);
+            if let Some(roots) = self.slots.get_mut(&header.slot) {
+                roots.retain(|r| r != root);
+                if roots.is_empty() {
+                    self.slots.remove(&header.slot);
+                }
+            }
+        }
+    }
+
+    fn insert_header(&mut self, root: Hash256, header: BeaconBlockHeader) {
+        let roots = self.slots.entry(header.slot).or_insert_with(Vec::new);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+        self.headers.insert(root, header);
+    }
+
+    /// Drops the headers of blocks before the finalized block, the chunks proven against their
+    /// states and the finalized roots of earlier epochs, none of which may change.
+    fn prune(&mut self) {
+        let (finalized_epoch, finalized_slot) = match &self.finalized {
+            Some(finalized) => (finalized.finalized_epoch, finalized.finalized_header.slot),
+            None => return,
+        };
+
+        let pruned = {
+            let kept = self.slots.split_off(&finalized_slot);
+            std::mem::replace(&mut self.slots, kept)
+        };
+        for root in pruned.values().flatten() {
+            if let Some(header) = self.headers.remove(root) {
+                self.partials.remove(&header.state_root);
+            }
+        }
+
+        self.finalized_roots = self.finalized_roots.split_off(&finalized_epoch);
+    }
+
+    /// Verifies that `partial` proves the nodes at `indices` against `state_root`, which must be
+    /// the state of a verified header, then stores the chunks it proves.
+    pub fn import_proof(
+        &mut self,
+        state_root: Hash256,
+        partial: &SerializedPartial,
+        indices: &[u64],
+    ) -> Result<(), ProofError> {
+        if !self.is_known_state_root(&state_root) {
+            return Err(ProofError::UnknownStateRoot);
+        }
+
+        validate_state_partial(partial).map_err(ProofError::MalformedProof)?;
+        let verification =
+            verify_partial(partial, state_root).map_err(ProofError::MalformedProof)?;
+        if !verification.valid {
+            return Err(ProofError::InvalidProof);
+        }
+        if let Some(index) = indices
+            .iter()
+            .find(|index| verification.covered_paths.binary_search(index).is_err())
+        {
+            return Err(ProofError::Unproven(*index));
+        }
+
+        self.import_partial(state_root, partial);
+        Ok(())
+    }
+
+    /// Returns a proof of the nodes at `indices` against `state_root`, if every node it requires
+    /// has already been proven.
+    pub fn cached_proof(&self, state_root: &Hash256, indices: &[u64]) -> Option<SerializedPartial> {
+        let chunks = self.partials.get(state_root)?;
+
+        let mut partial = SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        };
+        for index in indices.iter().cloned().chain(helper_indices(indices)) {
+            partial.indices.push(index);
+            partial.chunks.push(*chunks.get(&index)?);
+        }
+
+        Some(partial)
+    }
+
+    /// Returns `true` if `state_root` is the state of a verified header.
+    pub fn is_known_state_root(&self, state_root: &Hash256) -> bool {
+        self.headers
+            .values()
+            .any(|header| header.state_root == *state_root)
+    }
+
+    /// Stores the chunks which `partial` proves against `state_root`: each leaf whose path to the
+    /// root was reconstructed, and the helpers hashed along that path.
+    ///
+    /// Any other chunk does not contribute to the root, so could hold any value, and is dropped.
+    /// Nothing is stored if `partial` is not a valid proof. Stored chunks are never replaced.
+    fn import_partial(&mut self, state_root: Hash256, partial: &SerializedPartial) {
+        let covered_paths = match verify_partial(partial, state_root) {
+            Ok(verification) if verification.valid => verification.covered_paths,
+            _ => return,
+        };
+        let supplied: HashMap<u64, Hash256> = partial
+            .indices
+            .iter()
+            .cloned()
+            .zip(partial.chunks.iter().cloned())
+            .collect();
+
+        let chunks = self
+            .partials
+            .entry(state_root)
+            .or_insert_with(BTreeMap::new);
+        for leaf in covered_paths {
+            let mut index = leaf;
+            let mut chunk_index = leaf;
+            loop {
+                if let Some(chunk) = supplied.get(&chunk_index) {
+                    chunks.entry(chunk_index).or_insert(*chunk);
+                }
+                if index <= 1 {
+                    break;
+                }
+                chunk_index = index ^ 1;
+                index /= 2;
+            }
+        }
+    }
+
+    /// The latest finalized epoch, whether from a verified update or the trusted checkpoint.
+    fn known_epoch(&self) -> Option<Epoch> {
+        self.finalized
+            .as_ref()
+            .map(|finalized| finalized.finalized_epoch)
+            .or_else(|| self.checkpoint.map(|checkpoint| checkpoint.epoch))
+    }
+
+    /// The slot and root of the latest known finalized block, from which the head of every update
+    /// must be proven to descend.
+    fn anchor(&self) -> Option<(Slot, Hash256)> {
+        match (&self.finalized, &self.checkpoint) {
+            (Some(finalized), _) => {
+                Some((finalized.finalized_header.slot, finalized.finalized_root))
+            }
+            (None, Some(checkpoint)) => self
+                .headers
+                .get(&checkpoint.root)
+                .map(|header| (header.slot, checkpoint.root)),
+            (None, None) => None,
+        }
+    }
+
+    /// Returns the generalized indices of the nodes of the head state of an update which prove
+    /// that the latest known finalized block is an ancestor of its head, or `None` if no block is
+    /// trusted yet.
+    pub fn ancestry_indices<T: EthSpec>(&self) -> Option<Vec<u64>> {
+        self.anchor().map(|(slot, _)| ancestry_indices::<T>(slot))
+    }
+
+    /// The trusted checkpoint, if the store was bootstrapped from one.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// The most recent reorgs, oldest first.
+    pub fn reorgs(&self) -> impl Iterator<Item = &ReorgEvent> {
+        self.reorgs.iter()
+    }
+
+    /// The latest update to advance finality.
+    pub fn latest_update(&self) -> Option<&FinalityUpdate> {
+        self.latest_update.as_ref()
+    }
+
+    /// The latest verified finalized checkpoint.
+    pub fn finalized(&self) -> Option<&VerifiedFinality> {
+        self.finalized.as_ref()
+    }
+
+    /// Returns the verified header of the block at `root`.
+    pub fn header(&self, root: &Hash256) -> Option<&BeaconBlockHeader> {
+        self.headers.get(root)
+    }
+
+    /// Returns the verified headers of the blocks at `slot`, with their roots.
+    pub fn headers_at_slot(&self, slot: Slot) -> Vec<(Hash256, &BeaconBlockHeader)> {
+        self.slots
+            .get(&slot)
+            .map(|roots| {
+                roots
+                    .iter()
+                    .filter_map(|root| Some((*root, self.headers.get(root)?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the chunks proven against `state_root`, by generalized index.
+    pub fn partial(&self, state_root: &Hash256) -> Option<&BTreeMap<u64, Hash256>> {
+        self.partials.get(state_root)
+    }
+
+    /// Returns the contents of the store, to be written to disk.
+    pub fn as_persisted(&self) -> PersistedHeaderStore {
+        let (partial_roots, partials) = self
+            .partials
+            .iter()
+            .map(|(state_root, chunks)| {
+                let partial = SerializedPartial {
+                    indices: chunks.keys().cloned().collect(),
+                    chunks: chunks.values().cloned().collect(),
+                };
+                (*state_root, partial)
+            })
+            .unzip();
+
+        PersistedHeaderStore {
+            checkpoint: self.checkpoint,
+            weak_subjectivity_period: self.weak_subjectivity_period,
+            finalized: self.finalized.clone(),
+            finalized_epochs: self.finalized_roots.keys().cloned().collect(),
+            finalized_roots: self.finalized_roots.values().cloned().collect(),
+            headers: self.headers.values().cloned().collect(),
+            partial_roots,
+            partials,
+        }
+    }
+
+    /// Restores a store written to disk by `as_persisted`.
+    pub fn from_persisted(persisted: PersistedHeaderStore) -> Self {
+        let mut store = Self {
+            checkpoint: persisted.checkpoint,
+            weak_subjectivity_period: persisted.weak_subjectivity_period,
+            finalized: persisted.finalized,
+            finalized_roots: persisted
+                .finalized_epochs
+                .into_iter()
+                .zip(persisted.finalized_roots)
+                .collect(),
+            ..Self::default()
+        };
+        for header in persisted.headers {
+            store.insert_header(block_root_of_header(&header), header);
+        }
+        for (state_root, partial) in persisted.partial_roots.iter().zip(&persisted.partials) {
+            store.import_partial(*state_root, partial);
+        }
+
+        store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::TestingBeaconStateBuilder;
+    use types::{BeaconBlock, MinimalEthSpec};
+
+    /// Returns the head state of a chain of `block`s, one at each slot, which ends with
+    /// `finalized`, finalized in `epoch`. The head is at the slot after the finalized block.
+    fn head_state(epoch: u64, finalized: &BeaconBlockHeader) -> BeaconState<MinimalEthSpec> {
+        let spec = MinimalEthSpec::default_spec();
+        let builder = TestingBeaconStateBuilder::from_deterministic_keypairs(8, &spec);
+        let (mut state, _): (BeaconState<MinimalEthSpec>, _) = builder.build();
+        state.slot = finalized.slot + 1;
+
+        let history = MinimalEthSpec::slots_per_historical_root() as u64;
+        for slot in state.slot.as_u64().saturating_sub(history)..finalized.slot.as_u64() {
+            let root = block(slot).canonical_root();
+            state.set_block_root(Slot::new(slot), root).unwrap();
+        }
+        let finalized_root = block_root_of_header(finalized);
+        state
+            .set_block_root(finalized.slot, finalized_root)
+            .unwrap();
+
+        state.finalized_epoch = Epoch::new(epoch);
+        state.finalized_root = finalized_root;
+        state
+    }
+
+    /// Returns an update whose head state has finalized `finalized_block` in `epoch`.
+    fn update(epoch: u64, finalized_block: &BeaconBlock) -> FinalityUpdate {
+        update_from(
+            &head_state(epoch, &finalized_block.block_header()),
+            finalized_block,
+        )
+    }
+
+    /// Returns an update with head state `state`, which has finalized `finalized_block`.
+    fn update_from(
+        state: &BeaconState<MinimalEthSpec>,
+        finalized_block: &BeaconBlock,
+    ) -> FinalityUpdate {
+        let (head_state_root, finality_proof) = state.finality_proof();
+        let mut head = BeaconBlock::empty(&MinimalEthSpec::default_spec());
+        head.slot = state.slot;
+        head.state_root = head_state_root;
+
+        FinalityUpdate {
+            head_header: head.block_header(),
+            head_state_root,
+            finalized_epoch: state.finalized_epoch,
+            finalized_root: state.finalized_root,
+            finality_proof,
+            finalized_header: Some(finalized_block.block_header()),
+        }
+    }
+
+    /// Imports `update`, built by `update`, with the proof of ancestry the store asks for.
+    fn import(store: &mut HeaderStore, update: &FinalityUpdate) -> Result<bool, UpdateError> {
+        let ancestry = match store.ancestry_indices::<MinimalEthSpec>() {
+            Some(indices) => head_state(
+                update.finalized_epoch.as_u64(),
+                update.finalized_header.as_ref().unwrap(),
+            )
+            .prove(&indices)
+            .unwrap(),
+            None => empty_partial(),
+        };
+        store.import::<MinimalEthSpec>(update, &ancestry)
+    }
+
+    fn empty_partial() -> SerializedPartial {
+        SerializedPartial {
+            indices: vec![],
+            chunks: vec![],
+        }
+    }
+
+    fn block(slot: u64) -> BeaconBlock {
+        let mut block = BeaconBlock::empty(&MinimalEthSpec::default_spec());
+        block.slot = Slot::new(slot);
+        block
+    }
+
+    #[test]
+    fn imports_valid_updates() {
+        let mut store = HeaderStore::default();
+        let first = update(1, &block(8));
+        let second = update(2, &block(16));
+
+        assert_eq!(import(&mut store, &first), Ok(true));
+        assert_eq!(import(&mut store, &first), Ok(false));
+        assert_eq!(import(&mut store, &second), Ok(true));
+
+        let finalized = store.finalized().unwrap();
+        assert_eq!(finalized.finalized_epoch, Epoch::new(2));
+        assert_eq!(
+            store.header(&second.finalized_root),
+            second.finalized_header.as_ref()
+        );
+        assert!(store.partial(&second.head_state_root).is_some());
+    }
+
+    #[test]
+    fn rejects_inconsistent_updates() {
+        let valid = update(1, &block(8));
+
+        let mut wrong_header = valid.clone();
+        wrong_header.finalized_header = Some(block(9).block_header());
+        assert_eq!(
+            verify_update(&wrong_header),
+            Err(UpdateError::FinalizedHeaderMismatch)
+        );
+
+        let mut wrong_claim = valid.clone();
+        wrong_claim.finalized_epoch = Epoch::new(2);
+        assert_eq!(
+            verify_update(&wrong_claim),
+            Err(UpdateError::CheckpointMismatch)
+        );
+
+        let mut wrong_state = valid.clone();
+        wrong_state.head_state_root = Hash256::zero();
+        assert_eq!(
+            verify_update(&wrong_state),
+            Err(UpdateError::HeadStateRootMismatch)
+        );
+    }
+
+    #[test]
+    fn enforces_checkpoint() {
+        let finalized_block = block(16);
+        let checkpoint = Checkpoint {
+            epoch: Epoch::new(2),
+            root: block_root_of_header(&finalized_block.block_header()),
+        };
+
+        assert_eq!(
+            HeaderStore::from_checkpoint(checkpoint, block(8).block_header(), 4).err(),
+            Some(UpdateError::FinalizedHeaderMismatch)
+        );
+
+        let mut store =
+            HeaderStore::from_checkpoint(checkpoint, finalized_block.block_header(), 4).unwrap();
+        assert_eq!(
+            import(&mut store, &update(1, &block(8))),
+            Err(UpdateError::BeforeCheckpoint {
+                checkpoint: Epoch::new(2),
+                update: Epoch::new(1)
+            })
+        );
+        assert_eq!(
+            import(&mut store, &update(2, &block(17))),
+            Err(UpdateError::ConflictsWithCheckpoint)
+        );
+        assert_eq!(
+            import(&mut store, &update(7, &block(56))),
+            Err(UpdateError::BeyondWeakSubjectivityPeriod {
+                known: Epoch::new(2),
+                update: Epoch::new(7)
+            })
+        );
+        assert_eq!(import(&mut store, &update(2, &finalized_block)), Ok(true));
+        assert_eq!(import(&mut store, &update(6, &block(48))), Ok(true));
+    }
+
+    #[test]
+    fn requires_proof_of_ancestry() {
+        let mut store = HeaderStore::default();
+        import(&mut store, &update(1, &block(8))).unwrap();
+        let indices = store.ancestry_indices::<MinimalEthSpec>().unwrap();
+
+        let descendant = update(2, &block(16));
+        assert_eq!(
+            store.import::<MinimalEthSpec>(&descendant, &empty_partial()),
+            Err(UpdateError::UnprovenAncestry(
+                FinalityProofError::InvalidProof
+            ))
+        );
+
+        // A chain which has a different block at the slot of the known finalized block.
+        let mut state = head_state(2, &block(16).block_header());
+        state.set_block_root(Slot::new(8), Hash256::zero()).unwrap();
+        let fork = update_from(&state, &block(16));
+        assert_eq!(
+            store.import::<MinimalEthSpec>(&fork, &state.prove(&indices).unwrap()),
+            Err(UpdateError::UnprovenAncestry(
+                FinalityProofError::NotAnAncestor
+            ))
+        );
+
+        // The known finalized block is too old to be in the latest block roots of the head.
+        assert_eq!(
+            import(&mut store, &update(10, &block(80))),
+            Err(UpdateError::UnprovenAncestry(
+                FinalityProofError::AncestorOutOfRange {
+                    state_slot: Slot::new(81),
+                    ancestor_slot: Slot::new(8),
+                }
+            ))
+        );
+
+        assert_eq!(import(&mut store, &descendant), Ok(true));
+        assert_eq!(
+            store.ancestry_indices::<MinimalEthSpec>(),
+            Some(ancestry_indices::<MinimalEthSpec>(Slot::new(16)))
+        );
+    }
+
+    #[test]
+    fn prunes_before_finalized_block() {
+        let mut store = HeaderStore::default();
+        let first = update(1, &block(8));
+        let second = update(2, &block(16));
+        import(&mut store, &first).unwrap();
+        import(&mut store, &second).unwrap();
+
+        assert_eq!(store.header(&first.finalized_root), None);
+        assert!(store.headers_at_slot(Slot::new(8)).is_empty());
+        assert_eq!(
+            store.headers_at_slot(Slot::new(16)),
+            vec![(
+                second.finalized_root,
+                second.finalized_header.as_ref().unwrap()
+            )]
+        );
+        assert_eq!(
+            import(&mut store, &update(1, &block(8))),
+            Err(UpdateError::FinalityReverted {
+                known: Epoch::new(2),
+                update: Epoch::new(1)
+            })
+        );
+    }
+
+    #[test]
+    fn serves_verified_proofs() {
+        let mut store = HeaderStore::default();
+        let imported = update(1, &block(8));
+        import(&mut store, &imported).unwrap();
+        assert_eq!(store.latest_update(), Some(&imported));
+
+        let state_root = imported.head_state_root;
+        let indices = [46, 47];
+        assert!(store.cached_proof(&state_root, &indices).is_some());
+        assert_eq!(store.cached_proof(&state_root, &[40]), None);
+        assert_eq!(store.cached_proof(&Hash256::zero(), &indices), None);
+
+        let proof = store.cached_proof(&state_root, &indices).unwrap();
+        assert_eq!(
+            store.import_proof(Hash256::zero(), &proof, &indices),
+            Err(ProofError::UnknownStateRoot)
+        );
+        assert_eq!(
+            store.import_proof(state_root, &proof, &[40]),
+            Err(ProofError::Unproven(40))
+        );
+        assert_eq!(store.import_proof(state_root, &proof, &indices), Ok(()));
+    }
+
+    #[test]
+    fn stores_only_proven_chunks() {
+        let mut store = HeaderStore::default();
+        let imported = update(1, &block(8));
+        import(&mut store, &imported).unwrap();
+        let state_root = imported.head_state_root;
+        let proven = store.partial(&state_root).unwrap().clone();
+
+        // A chunk below a supplied node is not hashed into the root, so is not proven.
+        let mut partial = imported.finality_proof.clone();
+        let unconnected = 2 * partial.indices[0];
+        partial.indices.push(unconnected);
+        partial.chunks.push(Hash256::from_low_u64_le(42));
+        assert!(verify_partial(&partial, state_root).unwrap().valid);
+
+        store.import_partial(state_root, &partial);
+        assert_eq!(store.partial(&state_root), Some(&proven));
+        assert_eq!(store.cached_proof(&state_root, &[unconnected]), None);
+
+        // An invalid proof stores nothing.
+        let mut forged = imported.finality_proof.clone();
+        forged.chunks[0] = Hash256::from_low_u64_le(42);
+        store.import_partial(Hash256::zero(), &forged);
+        assert_eq!(store.partial(&Hash256::zero()), None);
+    }
+
+    #[test]
+    fn rolls_back_abandoned_heads() {
+        let mut store = HeaderStore::default();
+        let first = update(1, &block(8));
+        import(&mut store, &first).unwrap();
+        let old_head_root = block_root_of_header(&first.head_header);
+
+        // A competing head at the same slot, with the same state.
+        let mut second = first.clone();
+        second.head_header.block_body_root = Hash256::from_low_u64_le(1);
+        let new_head_root = block_root_of_header(&second.head_header);
+        assert_eq!(import(&mut store, &second), Ok(false));
+
+        assert_eq!(
+            store.reorgs().collect::<Vec<_>>(),
+            vec![&ReorgEvent {
+                slot: second.head_header.slot,
+                old_head_root,
+                new_head_root,
+                abandoned_roots: vec![old_head_root],
+            }]
+        );
+        assert_eq!(store.header(&old_head_root), None);
+        assert_eq!(
+            store.header(&first.finalized_root),
+            first.finalized_header.as_ref()
+        );
+        assert!(store.partial(&second.head_state_root).is_some());
+
+        // A head which extends the previous one is not a reorg.
+        let mut third = second.clone();
+        third.head_header.slot += 1;
+        third.head_header.previous_block_root = new_head_root;
+        import(&mut store, &third).unwrap();
+        assert_eq!(store.reorgs().count(), 1);
+    }
+
+    #[test]
+    fn persists() {
+        let mut store = HeaderStore::default();
+        let imported = update(1, &block(8));
+        import(&mut store, &imported).unwrap();
+
+        let restored = HeaderStore::from_persisted(store.as_persisted());
+        assert_eq!(restored.finalized(), store.finalized());
+        assert_eq!(
+            restored.header(&imported.finalized_root),
+            imported.finalized_header.as_ref()
+        );
+        assert_eq!(
+            restored.partial(&imported.head_state_root),
+            store.partial(&imported.head_state_root)
+        );
+    }
+
+    #[test]
+    fn rejects_reverted_and_conflicting_finality() {
+        let mut store = HeaderStore::default();
+        import(&mut store, &update(2, &block(16))).unwrap();
+
+        assert_eq!(
+            import(&mut store, &update(1, &block(8))),
+            Err(UpdateError::FinalityReverted {
+                known: Epoch::new(2),
+                update: Epoch::new(1)
+            })
+        );
+        assert_eq!(
+            import(&mut store, &update(2, &block(17))),
+            Err(UpdateError::ConflictingFinality(Epoch::new(2)))
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
-use compare_fields::{CompareFields, Comparison, FieldComparison};
+use compare_fields::CompareFields;
 use std::fmt::Debug;
+use std::time::Duration;
 use types::BeaconState;
 
 pub const MAX_VALUE_STRING_LEN: usize = 500;
@@ -10,14 +11,22 @@ pub struct CaseResult {
     pub case_index: usize,
     pub desc: String,
     pub result: Result<(), Error>,
+    /// How long the case took to run.
+    pub duration: Duration,
 }
 
 impl CaseResult {
-    pub fn new(case_index: usize, case: &impl Case, result: Result<(), Error>) -> Self {
+    pub fn new(
+        case_index: usize,
+        case: &impl Case,
+        result: Result<(), Error>,
+        duration: Duration,
+    ) -> Self {
         CaseResult {
             case_index,
             desc: case.description(),
             result,
+            duration,
         }
     }
 }
@@ -36,8 +45,8 @@ pub fn compare_beacon_state_results_without_caches<T: EthSpec, E: Debug>(
     compare_result_detailed(&result, &expected)
 }
 
-/// Same as `compare_result`, however utilizes the `CompareFields` trait to give a list of
-/// mismatching fields when `Ok(result) != Some(expected)`.
+/// Same as `compare_result`, however utilizes the `CompareFields` trait to give the path of each
+/// mismatching leaf when `Ok(result) != Some(expected)`.
 pub fn compare_result_detailed<T, E>(
     result: &Result<T, E>,
     expected: &Option<T>,
@@ -48,21 +57,16 @@ where
 {
     match (result, expected) {
         (Ok(result), Some(expected)) => {
-            let mut mismatching_fields: Vec<Comparison> = expected
-                .compare_fields(result)
-                .into_iter()
-                // Filter all out all fields that are equal.
-                .filter(Comparison::not_equal)
+            let mismatching_fields: Vec<String> = expected
+                .diff(result)
+                .iter()
+                .map(|diff| format!("{}: {} != {}", diff.path_string(), diff.a, diff.b))
                 .collect();
 
-            mismatching_fields
-                .iter_mut()
-                .for_each(|f| f.retain_children(FieldComparison::not_equal));
-
             if !mismatching_fields.is_empty() {
                 Err(Error::NotEqual(format!(
-                    "Fields not equal (a = expected, b = result): {:#?}",
-                    mismatching_fields
+                    "Fields not equal (a = expected, b = result):\n{}",
+                    mismatching_fields.join("\n")
                 )))
             } else {
                 Ok(())
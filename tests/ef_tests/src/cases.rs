@@ -1,5 +1,6 @@
 use super::*;
 use std::fmt::Debug;
+use std::time::Instant;
 
 mod bls_aggregate_pubkeys;
 mod bls_aggregate_sigs;
@@ -71,7 +72,11 @@ where
         self.test_cases
             .iter()
             .enumerate()
-            .map(|(i, tc)| CaseResult::new(i, tc, tc.result(i)))
+            .map(|(i, tc)| {
+                let start = Instant::now();
+                let result = tc.result(i);
+                CaseResult::new(i, tc, result, start.elapsed())
+            })
             .collect()
     }
 }
@@ -2,10 +2,12 @@ use crate::case_result::CaseResult;
 use crate::cases::*;
 use crate::doc_header::DocHeader;
 use crate::error::Error;
+use crate::spec_version::SpecVersion;
+use crate::time_budget::TimeBudgets;
 use crate::yaml_decode::{yaml_split_header_and_cases, YamlDecode};
 use crate::EfTest;
 use serde_derive::Deserialize;
-use std::{fs::File, io::prelude::*, path::PathBuf};
+use std::{fs::File, io::prelude::*, path::PathBuf, time::Duration};
 use types::{MainnetEthSpec, MinimalEthSpec};
 
 #[derive(Debug, Deserialize)]
@@ -13,10 +15,12 @@ pub struct Doc {
     pub header_yaml: String,
     pub cases_yaml: String,
     pub path: PathBuf,
+    /// The name of the release of the spec tests which holds this document.
+    pub spec_version: String,
 }
 
 impl Doc {
-    fn from_path(path: PathBuf) -> Self {
+    fn from_path(path: PathBuf, spec_version: &SpecVersion) -> Self {
         let mut file = File::open(path.clone()).unwrap();
 
         let mut yaml = String::new();
@@ -28,6 +32,7 @@ impl Doc {
             header_yaml,
             cases_yaml,
             path,
+            spec_version: spec_version.name.to_string(),
         }
     }
 
@@ -119,8 +124,20 @@ impl Doc {
         }
     }
 
-    pub fn assert_tests_pass(path: PathBuf) {
-        let doc = Self::from_path(path);
+    /// Runs the cases of the document at `path`, from release `spec_version`, panicking if any
+    /// fails.
+    pub fn assert_tests_pass(spec_version: SpecVersion, path: PathBuf) {
+        let doc = Self::from_path(path, &spec_version);
+
+        let header: DocHeader = serde_yaml::from_str(&doc.header_yaml).unwrap();
+        if !spec_version.supports(&header.runner, &header.handler) {
+            println!(
+                "Skipped {:?}, which {} does not yet support",
+                doc.path, spec_version.name
+            );
+            return;
+        }
+
         let results = doc.test_results();
 
         let (failed, skipped_bls, skipped_known_failures) = categorize_results(&results);
@@ -137,7 +154,35 @@ impl Doc {
                 panic!("Tests failed (see above)");
             }
         } else {
-            println!("Passed {} tests in {:?}", results.len(), doc.path);
+            println!(
+                "Passed {} tests in {:?} ({})",
+                results.len(),
+                doc.path,
+                doc.spec_version
+            );
+        }
+
+        if let Some(budgets) = TimeBudgets::from_env() {
+            doc.assert_within_time_budget(&budgets, &results);
+        }
+    }
+
+    /// Panics with a timing report if any case took longer than the budget of its handler.
+    fn assert_within_time_budget(&self, budgets: &TimeBudgets, results: &[CaseResult]) {
+        let header: DocHeader = serde_yaml::from_str(&self.header_yaml).unwrap();
+        let budget = match budgets.budget(&header.runner, &header.handler) {
+            Some(budget) => budget,
+            None => return,
+        };
+
+        let over_budget: Vec<&CaseResult> = results
+            .iter()
+            .filter(|case| case.duration > budget)
+            .collect();
+
+        if !over_budget.is_empty() {
+            print_timing_report(self, budget, &over_budget, results);
+            panic!("Tests exceeded time budget (see above)");
         }
     }
 }
@@ -190,6 +235,7 @@ pub fn print_results(
     );
     println!("Title: {}", header.title);
     println!("File: {:?}", doc.path);
+    println!("Spec version: {}", doc.spec_version);
     println!();
     println!(
         "{} tests, {} failed, {} skipped (known failure), {} skipped (bls), {} passed.",
@@ -222,3 +268,50 @@ pub fn print_results(
     }
     println!();
 }
+
+/// The number of slowest cases listed by `print_timing_report`.
+const SLOWEST_CASES: usize = 5;
+
+pub fn print_timing_report(
+    doc: &Doc,
+    budget: Duration,
+    over_budget: &[&CaseResult],
+    results: &[CaseResult],
+) {
+    let header: DocHeader = serde_yaml::from_str(&doc.header_yaml).unwrap();
+    let total: Duration = results.iter().map(|case| case.duration).sum();
+
+    println!("--------------------------------------------------");
+    println!("Timing Failure");
+    println!("Title: {}", header.title);
+    println!("File: {:?}", doc.path);
+    println!("Spec version: {}", doc.spec_version);
+    println!();
+    println!(
+        "{} of {} tests exceeded the budget of {:?} per test ({}/{}), taking {:?} in total.",
+        over_budget.len(),
+        results.len(),
+        budget,
+        header.runner,
+        header.handler,
+        total
+    );
+    println!();
+
+    let mut slowest: Vec<&CaseResult> = results.iter().collect();
+    slowest.sort_by(|a, b| b.duration.cmp(&a.duration));
+    for case in slowest.into_iter().take(SLOWEST_CASES) {
+        println!(
+            "case[{}] ({}) took {:?}{}",
+            case.case_index,
+            case.desc,
+            case.duration,
+            if case.duration > budget {
+                " (over budget)"
+            } else {
+                ""
+            }
+        );
+    }
+    println!();
+}
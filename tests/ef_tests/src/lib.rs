@@ -4,6 +4,8 @@ pub use case_result::CaseResult;
 pub use cases::Case;
 pub use doc::Doc;
 pub use error::Error;
+pub use spec_version::{SpecVersion, SPEC_VERSIONS, SPEC_VERSIONS_ENV_VAR};
+pub use time_budget::{TimeBudgets, TIME_BUDGETS_ENV_VAR};
 pub use yaml_decode::YamlDecode;
 
 mod bls_setting;
@@ -12,6 +14,8 @@ mod cases;
 mod doc;
 mod doc_header;
 mod error;
+mod spec_version;
+mod time_budget;
 mod yaml_decode;
 
 /// Defined where an object can return the results of some test(s) adhering to the Ethereum
@@ -0,0 +1,76 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Restricts the tests to the named releases when set, e.g. `v0.6.3` or `v0.6.3,v0.7.1`, so that
+/// an upgrade may be tested against the new release alone.
+pub const SPEC_VERSIONS_ENV_VAR: &str = "EF_TESTS_SPEC_VERSIONS";
+
+/// A pinned release of the spec tests.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SpecVersion {
+    /// The release tag, e.g. `v0.6.3`.
+    pub name: &'static str,
+    /// The directory of the release (a git submodule), relative to this crate.
+    pub dir: &'static str,
+    /// The runners and handlers whose tests in this release are not yet passed by the consensus
+    /// code. They are skipped.
+    pub unsupported: &'static [(&'static str, &'static str)],
+}
+
+/// Every pinned release, oldest first.
+///
+/// To upgrade the consensus code, add the new release as a submodule and list it here, then run
+/// the tests: each handler is run against both releases, and failures name the release which
+/// produced them.
+pub const SPEC_VERSIONS: &[SpecVersion] = &[SpecVersion {
+    name: "v0.6.3",
+    dir: "eth2.0-spec-tests",
+    unsupported: &[],
+}];
+
+impl SpecVersion {
+    /// Returns the releases to test, i.e. those named by `SPEC_VERSIONS_ENV_VAR`, or every release
+    /// if it is not set.
+    ///
+    /// Panics if it names a release which is not pinned, so that a typo does not silently skip
+    /// every test.
+    pub fn enabled() -> Vec<SpecVersion> {
+        let names = match env::var(SPEC_VERSIONS_ENV_VAR) {
+            Ok(names) => names,
+            Err(_) => return SPEC_VERSIONS.to_vec(),
+        };
+
+        names
+            .split(',')
+            .map(|name| {
+                SPEC_VERSIONS
+                    .iter()
+                    .find(|version| version.name == name.trim())
+                    .cloned()
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "{} names unknown release {:?}, expected one of {:?}",
+                            SPEC_VERSIONS_ENV_VAR,
+                            name,
+                            SPEC_VERSIONS.iter().map(|v| v.name).collect::<Vec<_>>()
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// The `tests` directory of the release.
+    pub fn tests_path(&self) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(self.dir)
+            .join("tests")
+    }
+
+    /// Returns `true` if the tests of `runner` and `handler` in this release should pass.
+    pub fn supports(&self, runner: &str, handler: &str) -> bool {
+        !self
+            .unsupported
+            .iter()
+            .any(|(r, h)| *r == runner && *h == handler)
+    }
+}
@@ -0,0 +1,65 @@
+use std::env;
+use std::time::Duration;
+
+/// Enables time budgets when set. Its value, e.g. `1` or `2.5`, scales every budget, so that a
+/// slower machine may loosen them.
+pub const TIME_BUDGETS_ENV_VAR: &str = "EF_TESTS_TIME_BUDGETS";
+
+/// The most time, in milliseconds, a single case of a runner and handler may take on the CI
+/// profile (`--release`, with `fake_crypto` where the test allows it).
+///
+/// Handlers which are not listed have no budget.
+const BUDGETS_MS: &[(&str, &str, u64)] = &[
+    ("sanity", "blocks", 2_000),
+    ("sanity", "slots", 1_000),
+    ("epoch_processing", "crosslinks", 1_000),
+    ("epoch_processing", "registry_updates", 1_000),
+    ("operations", "attestation", 500),
+    ("operations", "attester_slashing", 500),
+    ("operations", "block_header", 500),
+    ("operations", "deposit", 500),
+    ("operations", "proposer_slashing", 500),
+    ("operations", "transfer", 500),
+    ("operations", "voluntary_exit", 500),
+    ("shuffling", "core", 200),
+    ("ssz", "static", 100),
+    ("ssz", "uint", 10),
+];
+
+/// Per-case time budgets, which turn a case which passes but runs too slowly into a failure.
+///
+/// Opt-in, since timings are only meaningful on the CI profile.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TimeBudgets {
+    scale: f64,
+}
+
+impl TimeBudgets {
+    /// Returns the budgets scaled by `scale`.
+    pub fn new(scale: f64) -> Self {
+        Self { scale }
+    }
+
+    /// Returns the budgets if enabled by `TIME_BUDGETS_ENV_VAR`.
+    ///
+    /// Panics if its value is not a positive number, so that a typo does not silently disable the
+    /// budgets.
+    pub fn from_env() -> Option<Self> {
+        let value = env::var(TIME_BUDGETS_ENV_VAR).ok()?;
+        match value.parse::<f64>() {
+            Ok(scale) if scale > 0.0 => Some(Self::new(scale)),
+            _ => panic!(
+                "{} must be a positive number, not {:?}",
+                TIME_BUDGETS_ENV_VAR, value
+            ),
+        }
+    }
+
+    /// The most time a single case of `runner` and `handler` may take, if it has a budget.
+    pub fn budget(&self, runner: &str, handler: &str) -> Option<Duration> {
+        BUDGETS_MS
+            .iter()
+            .find(|(r, h, _)| *r == runner && *h == handler)
+            .map(|(_, _, ms)| Duration::from_micros((*ms as f64 * self.scale * 1_000.0) as u64))
+    }
+}
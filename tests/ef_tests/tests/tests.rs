@@ -3,12 +3,20 @@ use rayon::prelude::*;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-fn yaml_files_in_test_dir(dir: &Path) -> Vec<PathBuf> {
-    let base_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("eth2.0-spec-tests")
-        .join("tests")
-        .join(dir);
+/// Returns the YAML files within `dir` of every enabled release of the spec tests, each with its
+/// release.
+fn yaml_files_in_test_dir(dir: &Path) -> Vec<(SpecVersion, PathBuf)> {
+    SpecVersion::enabled()
+        .into_iter()
+        .flat_map(|version| {
+            yaml_files_in_release(&version.tests_path().join(dir))
+                .into_iter()
+                .map(move |path| (version, path))
+        })
+        .collect()
+}
 
+fn yaml_files_in_release(base_path: &Path) -> Vec<PathBuf> {
     assert!(
         base_path.exists(),
         format!(
@@ -45,8 +53,8 @@ fn yaml_files_in_test_dir(dir: &Path) -> Vec<PathBuf> {
 fn ssz_generic() {
     yaml_files_in_test_dir(&Path::new("ssz_generic"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -55,8 +63,8 @@ fn ssz_generic() {
 fn ssz_static() {
     yaml_files_in_test_dir(&Path::new("ssz_static"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -64,8 +72,8 @@ fn ssz_static() {
 fn shuffling() {
     yaml_files_in_test_dir(&Path::new("shuffling").join("core"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -73,8 +81,8 @@ fn shuffling() {
 fn operations_deposit() {
     yaml_files_in_test_dir(&Path::new("operations").join("deposit"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -83,8 +91,8 @@ fn operations_transfer() {
     yaml_files_in_test_dir(&Path::new("operations").join("transfer"))
         .into_par_iter()
         .rev()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -92,8 +100,8 @@ fn operations_transfer() {
 fn operations_exit() {
     yaml_files_in_test_dir(&Path::new("operations").join("voluntary_exit"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -101,8 +109,8 @@ fn operations_exit() {
 fn operations_proposer_slashing() {
     yaml_files_in_test_dir(&Path::new("operations").join("proposer_slashing"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -110,8 +118,8 @@ fn operations_proposer_slashing() {
 fn operations_attester_slashing() {
     yaml_files_in_test_dir(&Path::new("operations").join("attester_slashing"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -119,8 +127,8 @@ fn operations_attester_slashing() {
 fn operations_attestation() {
     yaml_files_in_test_dir(&Path::new("operations").join("attestation"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -128,8 +136,8 @@ fn operations_attestation() {
 fn operations_block_header() {
     yaml_files_in_test_dir(&Path::new("operations").join("block_header"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -137,8 +145,8 @@ fn operations_block_header() {
 fn sanity_blocks() {
     yaml_files_in_test_dir(&Path::new("sanity").join("blocks"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -146,8 +154,8 @@ fn sanity_blocks() {
 fn sanity_slots() {
     yaml_files_in_test_dir(&Path::new("sanity").join("slots"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -156,8 +164,8 @@ fn sanity_slots() {
 fn bls() {
     yaml_files_in_test_dir(&Path::new("bls"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -165,8 +173,8 @@ fn bls() {
 fn epoch_processing_crosslinks() {
     yaml_files_in_test_dir(&Path::new("epoch_processing").join("crosslinks"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
 
@@ -174,7 +182,7 @@ fn epoch_processing_crosslinks() {
 fn epoch_processing_registry_updates() {
     yaml_files_in_test_dir(&Path::new("epoch_processing").join("registry_updates"))
         .into_par_iter()
-        .for_each(|file| {
-            Doc::assert_tests_pass(file);
+        .for_each(|(version, file)| {
+            Doc::assert_tests_pass(version, file);
         });
 }
@@ -0,0 +1,259 @@
+//! An in-process network of beacon nodes and validators, for integration testing.
+//!
+//! Nodes are connected using the libp2p in-memory transport and each uses a `TestingSlotClock`,
+//! so time only advances when `LocalNetwork::advance_slot` is called. This allows sync and gossip
+//! to be tested deterministically, without opening sockets or waiting on the system clock.
+mod validator;
+
+use beacon_chain::{BeaconChain, BeaconChainTypes};
+use client::{Client, ClientConfig, Eth2Config, InitialiseBeaconChain, TESTNET_VALIDATOR_COUNT};
+use fork_choice::OptimizedLMDGhost;
+use slog::{info, o};
+use slot_clock::TestingSlotClock;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use store::MemoryStore;
+use tokio::runtime::Runtime;
+use types::test_utils::TestingBeaconStateBuilder;
+use types::{EthSpec, Slot};
+use websocket_server::WebSocketSender;
+
+pub use validator::LocalValidator;
+
+/// The next unused port on the in-memory transport, shared by all networks in the process.
+static NEXT_MEMORY_PORT: AtomicUsize = AtomicUsize::new(1);
+
+/// How often `LocalNetwork::wait_until` checks its condition.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// `BeaconChainTypes` for a node in a `LocalNetwork`.
+#[derive(Clone)]
+pub struct LocalTypes<E> {
+    _phantom: PhantomData<E>,
+}
+
+impl<E: EthSpec + Clone> BeaconChainTypes for LocalTypes<E> {
+    type Store = MemoryStore;
+    type SlotClock = TestingSlotClock;
+    type ForkChoice = OptimizedLMDGhost<MemoryStore, E>;
+    type EthSpec = E;
+    type EventHandler = WebSocketSender<E>;
+}
+
+impl<E: EthSpec + Clone> InitialiseBeaconChain<LocalTypes<E>> for LocalTypes<E> {}
+
+/// A beacon node in a `LocalNetwork`.
+pub type LocalBeaconNode<E> = Client<LocalTypes<E>>;
+
+/// A set of beacon nodes and validators running in the current process.
+///
+/// All nodes start from the same testnet genesis state. The first node is the boot node for all
+/// others.
+pub struct LocalNetwork<E: EthSpec + Clone> {
+    nodes: Vec<LocalBeaconNode<E>>,
+    validators: Vec<LocalValidator>,
+    slot: Slot,
+    eth2_config: Eth2Config,
+    log: slog::Logger,
+    // Declared last so it is dropped after the nodes which spawned tasks on it.
+    _runtime: Runtime,
+}
+
+impl<E: EthSpec + Clone> LocalNetwork<E> {
+    /// Starts `node_count` beacon nodes and `validator_count` validators.
+    ///
+    /// The genesis validators are split evenly between the validators and validators are attached
+    /// to nodes in a round-robin fashion.
+    pub fn new(
+        node_count: usize,
+        validator_count: usize,
+        eth2_config: Eth2Config,
+        log: slog::Logger,
+    ) -> Result<Self, String> {
+        if node_count == 0 {
+            return Err("A local network requires at least one node".into());
+        }
+
+        let runtime = Runtime::new().map_err(|e| format!("Unable to start runtime: {:?}", e))?;
+
+        let mut nodes = Vec::with_capacity(node_count);
+        let mut boot_node = None;
+        for i in 0..node_count {
+            let listen_address = format!(
+                "/memory/{}",
+                NEXT_MEMORY_PORT.fetch_add(1, Ordering::SeqCst)
+            );
+
+            let mut client_config = ClientConfig::default();
+            client_config.db_type = "memory".to_string();
+            client_config.network =
+                network::NetworkConfig::new(boot_node.iter().cloned().collect());
+            client_config
+                .network
+                .set_listen_addresses(vec![listen_address.clone()]);
+
+            let node = Client::new(
+                client_config,
+                eth2_config.clone(),
+                MemoryStore::open(),
+                None,
+                log.new(o!("node" => i)),
+                &runtime.executor(),
+            )
+            .map_err(|e| format!("Unable to start node {}: {:?}", i, e))?;
+
+            nodes.push(node);
+            boot_node.get_or_insert(listen_address);
+        }
+
+        let keypairs = TestingBeaconStateBuilder::<E>::from_default_keypairs_file_if_exists(
+            TESTNET_VALIDATOR_COUNT,
+            &eth2_config.spec,
+        )
+        .build()
+        .1;
+        let validators = LocalValidator::split(keypairs, validator_count, node_count);
+
+        info!(
+            log,
+            "Started local network";
+            "nodes" => node_count,
+            "validators" => validators.len(),
+        );
+
+        Ok(Self {
+            nodes,
+            validators,
+            slot: eth2_config.spec.genesis_slot,
+            eth2_config,
+            log,
+            _runtime: runtime,
+        })
+    }
+
+    /// Returns all nodes in the network.
+    pub fn nodes(&self) -> &[LocalBeaconNode<E>] {
+        &self.nodes
+    }
+
+    /// Returns the beacon chain of the `i`th node.
+    ///
+    /// Panics if there is no such node.
+    pub fn beacon_chain(&self, i: usize) -> Arc<BeaconChain<LocalTypes<E>>> {
+        self.nodes[i].beacon_chain()
+    }
+
+    /// Returns the slot that all nodes are currently at.
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Moves all nodes to the next slot, then has each validator perform its duties for that slot
+    /// on the node it is attached to.
+    pub fn advance_slot(&mut self) -> Result<(), String> {
+        self.slot += 1;
+
+        for node in &self.nodes {
+            let beacon_chain = node.beacon_chain();
+            beacon_chain.slot_clock.set_slot(self.slot.as_u64());
+            beacon_chain
+                .catchup_state()
+                .map_err(|e| format!("Unable to advance state: {:?}", e))?;
+        }
+
+        for validator in &self.validators {
+            let node = &self.nodes[validator.node_index()];
+            validator.perform_duties(
+                &node.beacon_chain(),
+                &node.network,
+                &self.eth2_config.spec,
+            )?;
+        }
+
+        info!(self.log, "Advanced local network"; "slot" => self.slot.as_u64());
+
+        Ok(())
+    }
+
+    /// Blocks until `condition` holds or `timeout` elapses, returning `true` if `condition` held.
+    ///
+    /// Useful for waiting on gossip and sync, which happen on background tasks.
+    pub fn wait_until<F>(&self, timeout: Duration, condition: F) -> bool
+    where
+        F: Fn(&Self) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        while !condition(self) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        true
+    }
+
+    /// Returns `true` if every node has the same head block.
+    pub fn heads_agree(&self) -> bool {
+        let head_root = |node: &LocalBeaconNode<E>| node.beacon_chain().head().beacon_block_root;
+
+        self.nodes
+            .iter()
+            .all(|node| head_root(node) == head_root(&self.nodes[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sloggers::{null::NullLoggerBuilder, Build};
+    use types::MinimalEthSpec;
+
+    const GOSSIP_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn blocks_propagate_to_all_nodes() {
+        let log = NullLoggerBuilder.build().unwrap();
+        let mut network =
+            LocalNetwork::<MinimalEthSpec>::new(3, 1, Eth2Config::minimal(), log).unwrap();
+
+        for _ in 0..4 {
+            network.advance_slot().unwrap();
+        }
+
+        let produced_slot = network.beacon_chain(0).head().beacon_block.slot;
+        assert_eq!(produced_slot, network.slot());
+
+        assert!(network.wait_until(GOSSIP_TIMEOUT, |network| {
+            network.heads_agree()
+                && network.beacon_chain(2).head().beacon_block.slot == produced_slot
+        }));
+    }
+
+    #[test]
+    fn rejects_empty_network() {
+        let log = NullLoggerBuilder.build().unwrap();
+
+        assert!(LocalNetwork::<MinimalEthSpec>::new(0, 1, Eth2Config::minimal(), log).is_err());
+    }
+
+    #[test]
+    fn heads_agree_with_proposers_on_every_node() {
+        let log = NullLoggerBuilder.build().unwrap();
+        let mut network =
+            LocalNetwork::<MinimalEthSpec>::new(3, 3, Eth2Config::minimal(), log).unwrap();
+
+        for _ in 0..4 {
+            network.advance_slot().unwrap();
+        }
+
+        assert!(network.wait_until(GOSSIP_TIMEOUT, |network| {
+            network.heads_agree()
+                && network.beacon_chain(0).head().beacon_block.slot == network.slot()
+        }));
+    }
+}
@@ -0,0 +1,207 @@
+use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
+use eth2_libp2p::PubsubMessage;
+use network::Service as NetworkService;
+use tree_hash::{SignedRoot, TreeHash};
+use types::{
+    AggregateSignature, Attestation, AttestationDataAndCustodyBit, Bitfield, ChainSpec, Domain,
+    EthSpec, Keypair, RelativeEpoch, Signature,
+};
+
+/// A validator client which signs with a set of genesis keypairs and interacts directly with the
+/// `BeaconChain` and network service of a single node.
+pub struct LocalValidator {
+    node_index: usize,
+    /// The validator index and keypair of each validator controlled by this client.
+    keypairs: Vec<(usize, Keypair)>,
+}
+
+impl LocalValidator {
+    /// Splits `keypairs` as evenly as possible between `validator_count` validators, assigning
+    /// validators to `node_count` nodes in a round-robin fashion.
+    ///
+    /// Validators which would receive no keypairs are not created.
+    pub fn split(keypairs: Vec<Keypair>, validator_count: usize, node_count: usize) -> Vec<Self> {
+        let mut validators: Vec<Self> = (0..validator_count)
+            .map(|i| LocalValidator {
+                node_index: i % node_count,
+                keypairs: vec![],
+            })
+            .collect();
+
+        if validators.is_empty() {
+            return validators;
+        }
+
+        for (validator_index, keypair) in keypairs.into_iter().enumerate() {
+            validators[validator_index % validator_count]
+                .keypairs
+                .push((validator_index, keypair));
+        }
+
+        validators.retain(|validator| !validator.keypairs.is_empty());
+        validators
+    }
+
+    /// The index of the node this validator is attached to.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    /// Proposes a block and produces attestations for the present slot of `beacon_chain`, if any
+    /// of this validator's keypairs have duties at that slot.
+    ///
+    /// Produced blocks and attestations are imported into `beacon_chain` and published to the
+    /// network.
+    pub fn perform_duties<T: BeaconChainTypes + 'static>(
+        &self,
+        beacon_chain: &BeaconChain<T>,
+        network: &NetworkService<T>,
+        spec: &ChainSpec,
+    ) -> Result<(), String> {
+        self.propose_block(beacon_chain, network, spec)?;
+        self.attest(beacon_chain, network, spec)
+    }
+
+    fn propose_block<T: BeaconChainTypes + 'static>(
+        &self,
+        beacon_chain: &BeaconChain<T>,
+        network: &NetworkService<T>,
+        spec: &ChainSpec,
+    ) -> Result<(), String> {
+        let slot = beacon_chain.present_slot();
+        let proposer = beacon_chain
+            .block_proposer(slot)
+            .map_err(|e| format!("Unable to get block proposer: {:?}", e))?;
+
+        let keypair = match self.keypair(proposer) {
+            Some(keypair) => keypair,
+            None => return Ok(()),
+        };
+
+        let epoch = slot.epoch(T::EthSpec::slots_per_epoch());
+        let fork = beacon_chain.current_state().fork.clone();
+
+        let randao_reveal = Signature::new(
+            &epoch.tree_hash_root(),
+            spec.get_domain(epoch, Domain::Randao, &fork),
+            &keypair.sk,
+        );
+
+        let (mut block, _state) = beacon_chain
+            .produce_block(randao_reveal, [0; 32])
+            .map_err(|e| format!("Unable to produce block: {:?}", e))?;
+        block.signature = Signature::new(
+            &block.signed_root(),
+            spec.get_domain(epoch, Domain::BeaconProposer, &fork),
+            &keypair.sk,
+        );
+
+        match beacon_chain.process_block(block.clone()) {
+            Ok(BlockProcessingOutcome::Processed) => {}
+            other => return Err(format!("Produced block was not imported: {:?}", other)),
+        }
+
+        network
+            .publish(PubsubMessage::Block(block))
+            .map_err(|e| format!("Unable to publish block: {:?}", e))
+    }
+
+    fn attest<T: BeaconChainTypes + 'static>(
+        &self,
+        beacon_chain: &BeaconChain<T>,
+        network: &NetworkService<T>,
+        spec: &ChainSpec,
+    ) -> Result<(), String> {
+        // Importing a block may have replaced the state, and with it the committee caches.
+        beacon_chain
+            .ensure_state_caches_are_built()
+            .map_err(|e| format!("Unable to build state caches: {:?}", e))?;
+
+        let slot = beacon_chain.present_slot();
+        let fork = beacon_chain.current_state().fork.clone();
+
+        for (validator_index, keypair) in &self.keypairs {
+            let duty = beacon_chain
+                .current_state()
+                .get_attestation_duties(*validator_index, RelativeEpoch::Current)
+                .map_err(|e| format!("Unable to get attestation duties: {:?}", e))?;
+
+            let duty = match duty {
+                Some(duty) if duty.slot == slot => duty,
+                _ => continue,
+            };
+
+            let data = beacon_chain
+                .produce_attestation_data(duty.shard)
+                .map_err(|e| format!("Unable to produce attestation data: {:?}", e))?;
+
+            let message = AttestationDataAndCustodyBit {
+                data: data.clone(),
+                custody_bit: false,
+            }
+            .tree_hash_root();
+            let domain = spec.get_domain(data.target_epoch, Domain::Attestation, &fork);
+
+            let mut signature = AggregateSignature::new();
+            signature.add(&Signature::new(&message, domain, &keypair.sk));
+
+            let mut aggregation_bitfield = Bitfield::with_capacity(duty.committee_len);
+            aggregation_bitfield.set(duty.committee_index, true);
+
+            let attestation = Attestation {
+                aggregation_bitfield,
+                data,
+                custody_bitfield: Bitfield::with_capacity(duty.committee_len),
+                signature,
+            };
+
+            beacon_chain
+                .process_attestation(attestation.clone())
+                .map_err(|e| format!("Produced attestation was not imported: {:?}", e))?;
+
+            network
+                .publish(PubsubMessage::Attestation(attestation))
+                .map_err(|e| format!("Unable to publish attestation: {:?}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the keypair for `validator_index`, if it is controlled by this validator.
+    fn keypair(&self, validator_index: usize) -> Option<&Keypair> {
+        self.keypairs
+            .iter()
+            .find(|(index, _)| *index == validator_index)
+            .map(|(_, keypair)| keypair)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypairs(n: usize) -> Vec<Keypair> {
+        (0..n).map(|_| Keypair::random()).collect()
+    }
+
+    #[test]
+    fn splits_keypairs_round_robin() {
+        let validators = LocalValidator::split(keypairs(5), 2, 3);
+
+        assert_eq!(validators.len(), 2);
+        assert_eq!(validators[0].node_index(), 0);
+        assert_eq!(validators[1].node_index(), 1);
+
+        let indices = |validator: &LocalValidator| -> Vec<usize> {
+            validator.keypairs.iter().map(|(i, _)| *i).collect()
+        };
+        assert_eq!(indices(&validators[0]), vec![0, 2, 4]);
+        assert_eq!(indices(&validators[1]), vec![1, 3]);
+    }
+
+    #[test]
+    fn drops_validators_without_keypairs() {
+        assert!(LocalValidator::split(keypairs(3), 0, 2).is_empty());
+        assert_eq!(LocalValidator::split(keypairs(2), 4, 2).len(), 2);
+    }
+}
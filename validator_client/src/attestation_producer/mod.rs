@@ -2,22 +2,21 @@ mod beacon_node_attestation;
 mod grpc;
 
 use std::sync::Arc;
+use std::time::Instant;
 use types::{ChainSpec, Domain, Fork};
 //TODO: Move these higher up in the crate
 use super::block_producer::{BeaconNodeError, PublishOutcome, ValidatorEvent};
-use crate::signer::Signer;
+use crate::signer::{SignableMessage, Signer};
+use crate::slashing_protection::{NotSafe, SlashingDatabase};
 use beacon_node_attestation::BeaconNodeAttestation;
 use slog::{error, info, warn};
-use tree_hash::TreeHash;
-use types::{
-    AggregateSignature, Attestation, AttestationData, AttestationDataAndCustodyBit,
-    AttestationDuty, Bitfield,
-};
+use types::{AggregateSignature, Attestation, AttestationData, AttestationDuty, Bitfield};
 
 //TODO: Group these errors at a crate level
 #[derive(Debug, PartialEq)]
 pub enum Error {
     BeaconNodeError(BeaconNodeError),
+    SlashingProtection(NotSafe),
 }
 
 impl From<BeaconNodeError> for Error {
@@ -41,6 +40,10 @@ pub struct AttestationProducer<'a, B: BeaconNodeAttestation, S: Signer> {
     pub signer: &'a S,
     /// Used for caclulating epoch.
     pub slots_per_epoch: u64,
+    /// Records signed attestations, refusing any which would be slashable.
+    pub slashing_protection: Arc<SlashingDatabase>,
+    /// If set, the signed attestation is not published before this time.
+    pub publish_at: Option<Instant>,
 }
 
 impl<'a, B: BeaconNodeAttestation, S: Signer> AttestationProducer<'a, B, S> {
@@ -74,36 +77,50 @@ impl<'a, B: BeaconNodeAttestation, S: Signer> AttestationProducer<'a, B, S> {
     /// Assumes that an attestation is required at this slot (does not check the duties).
     ///
     /// Ensures the message is not slashable.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// The slash-protection code is not yet implemented. There is zero protection against
-    /// slashing.
     pub fn produce_attestation(&mut self) -> Result<ValidatorEvent, Error> {
         let epoch = self.duty.slot.epoch(self.slots_per_epoch);
 
         let attestation = self
             .beacon_node
             .produce_attestation_data(self.duty.slot, self.duty.shard)?;
-        if self.safe_to_produce(&attestation) {
-            let domain = self.spec.get_domain(epoch, Domain::Attestation, &self.fork);
-            if let Some(attestation) = self.sign_attestation(attestation, self.duty, domain) {
-                match self.beacon_node.publish_attestation(attestation) {
-                    Ok(PublishOutcome::InvalidAttestation(_string)) => {
-                        Ok(ValidatorEvent::InvalidAttestation)
-                    }
-                    Ok(PublishOutcome::Valid) => {
-                        Ok(ValidatorEvent::AttestationProduced(self.duty.slot))
-                    }
-                    Err(_) | Ok(_) => Ok(ValidatorEvent::PublishAttestationFailed),
+
+        match self
+            .slashing_protection
+            .check_and_insert_attestation(&self.signer.to_public(), &attestation)
+        {
+            Ok(_) => {}
+            Err(NotSafe::InvalidAttestation(_)) => {
+                return Ok(ValidatorEvent::IndexedAttestationNotProduced(
+                    self.duty.slot,
+                ))
+            }
+            Err(e) => return Err(Error::SlashingProtection(e)),
+        }
+
+        let domain = self.spec.get_domain(epoch, Domain::Attestation, &self.fork);
+        if let Some(attestation) = self.sign_attestation(attestation, self.duty, domain) {
+            self.wait_for_publication();
+            match self.beacon_node.publish_attestation(attestation) {
+                Ok(PublishOutcome::InvalidAttestation(_string)) => {
+                    Ok(ValidatorEvent::InvalidAttestation)
                 }
-            } else {
-                Ok(ValidatorEvent::SignerRejection(self.duty.slot))
+                Ok(PublishOutcome::Valid) => {
+                    Ok(ValidatorEvent::AttestationProduced(self.duty.slot))
+                }
+                Err(_) | Ok(_) => Ok(ValidatorEvent::PublishAttestationFailed),
             }
         } else {
-            Ok(ValidatorEvent::IndexedAttestationNotProduced(
-                self.duty.slot,
-            ))
+            Ok(ValidatorEvent::SignerRejection(self.duty.slot))
+        }
+    }
+
+    /// Blocks until `self.publish_at`, if it is in the future.
+    fn wait_for_publication(&self) {
+        if let Some(publish_at) = self.publish_at {
+            let now = Instant::now();
+            if publish_at > now {
+                std::thread::sleep(publish_at - now);
+            }
         }
     }
 
@@ -117,15 +134,12 @@ impl<'a, B: BeaconNodeAttestation, S: Signer> AttestationProducer<'a, B, S> {
         duties: AttestationDuty,
         domain: u64,
     ) -> Option<Attestation> {
-        self.store_produce(&attestation);
-
         // build the aggregate signature
         let aggregate_signature = {
-            let message = AttestationDataAndCustodyBit {
-                data: attestation.clone(),
-                custody_bit: false,
-            }
-            .tree_hash_root();
+            let message = SignableMessage::Attestation {
+                slot: duties.slot,
+                attestation: attestation.clone(),
+            };
 
             let sig = self.signer.sign_message(&message, domain)?;
 
@@ -145,23 +159,4 @@ impl<'a, B: BeaconNodeAttestation, S: Signer> AttestationProducer<'a, B, S> {
             signature: aggregate_signature,
         })
     }
-
-    /// Returns `true` if signing an attestation is safe (non-slashable).
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn safe_to_produce(&self, _attestation: &AttestationData) -> bool {
-        //TODO: Implement slash protection
-        true
-    }
-
-    /// Record that an attestation was produced so that slashable votes may not be made in the future.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn store_produce(&mut self, _attestation: &AttestationData) {
-        // TODO: Implement slash protection
-    }
 }
@@ -1,3 +1,4 @@
+use crate::graffiti::Graffiti;
 use types::{BeaconBlock, Signature, Slot};
 #[derive(Debug, PartialEq, Clone)]
 pub enum BeaconNodeError {
@@ -15,13 +16,14 @@ pub enum PublishOutcome {
 /// Defines the methods required to produce and publish blocks on a Beacon Node. Abstracts the
 /// actual beacon node.
 pub trait BeaconNodeBlock: Send + Sync {
-    /// Request that the node produces a block.
+    /// Request that the node produces a block, bearing `graffiti`.
     ///
     /// Returns Ok(None) if the Beacon Node is unable to produce at the given slot.
     fn produce_beacon_block(
         &self,
         slot: Slot,
         randao_reveal: &Signature,
+        graffiti: &Graffiti,
     ) -> Result<Option<BeaconBlock>, BeaconNodeError>;
 
     /// Request that the node publishes a block.
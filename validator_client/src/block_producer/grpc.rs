@@ -1,4 +1,5 @@
 use super::beacon_node_block::*;
+use crate::graffiti::Graffiti;
 use protos::services::{
     BeaconBlock as GrpcBeaconBlock, ProduceBeaconBlockRequest, PublishBeaconBlockRequest,
 };
@@ -29,11 +30,13 @@ impl BeaconNodeBlock for BeaconBlockGrpcClient {
         &self,
         slot: Slot,
         randao_reveal: &Signature,
+        graffiti: &Graffiti,
     ) -> Result<Option<BeaconBlock>, BeaconNodeError> {
         // request a beacon block from the node
         let mut req = ProduceBeaconBlockRequest::new();
         req.set_slot(slot.as_u64());
         req.set_randao_reveal(randao_reveal.as_ssz_bytes());
+        req.set_graffiti(graffiti.to_vec());
 
         //TODO: Determine if we want an explicit timeout
         let reply = self
@@ -4,15 +4,17 @@ mod grpc;
 use self::beacon_node_block::BeaconNodeBlock;
 pub use self::beacon_node_block::{BeaconNodeError, PublishOutcome};
 pub use self::grpc::BeaconBlockGrpcClient;
-use crate::signer::Signer;
+use crate::graffiti::Graffiti;
+use crate::signer::{SignableMessage, Signer};
+use crate::slashing_protection::{NotSafe, SlashingDatabase};
 use slog::{error, info, warn};
 use std::sync::Arc;
-use tree_hash::{SignedRoot, TreeHash};
 use types::{BeaconBlock, ChainSpec, Domain, Fork, Slot};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     BeaconNodeError(BeaconNodeError),
+    SlashingProtection(NotSafe),
 }
 
 #[derive(Debug, PartialEq)]
@@ -50,6 +52,10 @@ pub struct BlockProducer<'a, B: BeaconNodeBlock, S: Signer> {
     pub signer: &'a S,
     /// Used for caclulating epoch.
     pub slots_per_epoch: u64,
+    /// Records signed blocks, refusing any which would be slashable.
+    pub slashing_protection: Arc<SlashingDatabase>,
+    /// The graffiti to include in the block.
+    pub graffiti: Graffiti,
 }
 
 impl<'a, B: BeaconNodeBlock, S: Signer> BlockProducer<'a, B, S> {
@@ -80,15 +86,10 @@ impl<'a, B: BeaconNodeBlock, S: Signer> BlockProducer<'a, B, S> {
     /// Assumes that a block is required at this slot (does not check the duties).
     ///
     /// Ensures the message is not slashable.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// The slash-protection code is not yet implemented. There is zero protection against
-    /// slashing.
     pub fn produce_block(&mut self) -> Result<ValidatorEvent, Error> {
         let epoch = self.slot.epoch(self.slots_per_epoch);
 
-        let message = epoch.tree_hash_root();
+        let message = SignableMessage::RandaoReveal { epoch };
         let randao_reveal = match self.signer.sign_message(
             &message,
             self.spec.get_domain(epoch, Domain::Randao, &self.fork),
@@ -97,22 +98,29 @@ impl<'a, B: BeaconNodeBlock, S: Signer> BlockProducer<'a, B, S> {
             Some(signature) => signature,
         };
 
-        if let Some(block) = self
-            .beacon_node
-            .produce_beacon_block(self.slot, &randao_reveal)?
+        if let Some(block) =
+            self.beacon_node
+                .produce_beacon_block(self.slot, &randao_reveal, &self.graffiti)?
         {
-            if self.safe_to_produce(&block) {
-                let domain = self
-                    .spec
-                    .get_domain(epoch, Domain::BeaconProposer, &self.fork);
-                if let Some(block) = self.sign_block(block, domain) {
-                    self.beacon_node.publish_beacon_block(block)?;
-                    Ok(ValidatorEvent::BlockProduced(self.slot))
-                } else {
-                    Ok(ValidatorEvent::SignerRejection(self.slot))
+            match self
+                .slashing_protection
+                .check_and_insert_block_proposal(&self.signer.to_public(), &block)
+            {
+                Ok(_) => {}
+                Err(NotSafe::InvalidBlock(_)) => {
+                    return Ok(ValidatorEvent::SlashableBlockNotProduced(self.slot))
                 }
+                Err(e) => return Err(Error::SlashingProtection(e)),
+            }
+
+            let domain = self
+                .spec
+                .get_domain(epoch, Domain::BeaconProposer, &self.fork);
+            if let Some(block) = self.sign_block(block, domain) {
+                self.beacon_node.publish_beacon_block(block)?;
+                Ok(ValidatorEvent::BlockProduced(self.slot))
             } else {
-                Ok(ValidatorEvent::SlashableBlockNotProduced(self.slot))
+                Ok(ValidatorEvent::SignerRejection(self.slot))
             }
         } else {
             Ok(ValidatorEvent::BeaconNodeUnableToProduceBlock(self.slot))
@@ -124,9 +132,11 @@ impl<'a, B: BeaconNodeBlock, S: Signer> BlockProducer<'a, B, S> {
     /// Important: this function will not check to ensure the block is not slashable. This must be
     /// done upstream.
     fn sign_block(&mut self, mut block: BeaconBlock, domain: u64) -> Option<BeaconBlock> {
-        self.store_produce(&block);
-
-        match self.signer.sign_message(&block.signed_root()[..], domain) {
+        let message = SignableMessage::BeaconBlock {
+            slot: block.slot,
+            block: block.clone(),
+        };
+        match self.signer.sign_message(&message, domain) {
             None => None,
             Some(signature) => {
                 block.signature = signature;
@@ -134,27 +144,6 @@ impl<'a, B: BeaconNodeBlock, S: Signer> BlockProducer<'a, B, S> {
             }
         }
     }
-
-    /// Returns `true` if signing a block is safe (non-slashable).
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn safe_to_produce(&self, _block: &BeaconBlock) -> bool {
-        // TODO: ensure the producer doesn't produce slashable blocks.
-        // https://github.com/sigp/lighthouse/issues/160
-        true
-    }
-
-    /// Record that a block was produced so that slashable votes may not be made in the future.
-    ///
-    /// !!! UNSAFE !!!
-    ///
-    /// Important: this function is presently stubbed-out. It provides ZERO SAFETY.
-    fn store_produce(&mut self, _block: &BeaconBlock) {
-        // TODO: record this block production to prevent future slashings.
-        // https://github.com/sigp/lighthouse/issues/160
-    }
 }
 
 impl From<BeaconNodeError> for Error {
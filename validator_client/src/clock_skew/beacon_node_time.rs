@@ -0,0 +1,41 @@
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClockSkewError {
+    /// The beacon node could not be asked for its time, or did not report it.
+    RemoteFailure(String),
+    /// The NTP server could not be reached, or sent an invalid response.
+    NtpFailure(String),
+    /// The local clock could not be read.
+    SystemTimeError(String),
+}
+
+/// The timestamps of an NTP-style exchange with a remote clock, in milliseconds since the Unix
+/// epoch.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ClockSample {
+    /// Our time when the request was sent.
+    pub sent: i64,
+    /// The remote time when the request was received.
+    pub remote_received: i64,
+    /// The remote time when the response was sent.
+    pub remote_sent: i64,
+    /// Our time when the response was received.
+    pub received: i64,
+}
+
+impl ClockSample {
+    /// The remote clock minus our clock, assuming the network delay is the same both ways.
+    pub fn offset_ms(&self) -> i64 {
+        ((self.remote_received - self.sent) + (self.remote_sent - self.received)) / 2
+    }
+
+    /// The time spent on the network, which bounds the error of `offset_ms` to half of it.
+    pub fn round_trip_ms(&self) -> i64 {
+        (self.received - self.sent) - (self.remote_sent - self.remote_received)
+    }
+}
+
+/// Defines the methods required to compare our clock with the beacon node's.
+pub trait BeaconNodeTime: Send + Sync {
+    /// Requests the beacon node's current time.
+    fn request_time(&self) -> Result<ClockSample, ClockSkewError>;
+}
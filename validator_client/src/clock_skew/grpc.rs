@@ -0,0 +1,30 @@
+use super::beacon_node_time::{BeaconNodeTime, ClockSample, ClockSkewError};
+use super::unix_time_ms;
+use protos::services::Empty;
+use protos::services_grpc::BeaconNodeServiceClient;
+
+impl BeaconNodeTime for BeaconNodeServiceClient {
+    /// Requests the Beacon Node (BN) info, which reports the BN's clock.
+    fn request_time(&self) -> Result<ClockSample, ClockSkewError> {
+        let sent = unix_time_ms()?;
+        let reply = self
+            .info(&Empty::new())
+            .map_err(|err| ClockSkewError::RemoteFailure(format!("{:?}", err)))?;
+        let received = unix_time_ms()?;
+
+        // Older beacon nodes do not report their clock.
+        if reply.get_time_ms() == 0 {
+            return Err(ClockSkewError::RemoteFailure(
+                "Beacon node did not report its time".to_string(),
+            ));
+        }
+        let remote = reply.get_time_ms() as i64;
+
+        Ok(ClockSample {
+            sent,
+            remote_received: remote,
+            remote_sent: remote,
+            received,
+        })
+    }
+}
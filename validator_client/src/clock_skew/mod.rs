@@ -0,0 +1,200 @@
+//! Clock skew detection: compare our clock with the beacon node's, and optionally an NTP
+//! server's. A skewed clock signs at the wrong time, so duties are silently missed, or signed for
+//! a slot which the rest of the network has not reached.
+mod beacon_node_time;
+mod grpc;
+mod ntp;
+
+use self::beacon_node_time::{BeaconNodeTime, ClockSample, ClockSkewError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use types::Epoch;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClockSkewStatus {
+    /// The clocks have not yet been compared.
+    Unknown,
+    /// Our clock agrees with every other clock.
+    Synced,
+    /// The clock of `source` is `offset_ms` ahead of ours (or behind, if negative), which is more
+    /// than allowed.
+    Skewed { source: String, offset_ms: i64 },
+}
+
+/// A polling state machine which periodically compares our clock with others.
+pub struct ClockSkewMonitor {
+    max_skew_ms: u64,
+    /// An NTP server, i.e., `host` or `host:port`, to compare with as well as the beacon node.
+    ntp_server: Option<String>,
+    /// The epoch in which the clocks were last compared.
+    last_checked: Option<Epoch>,
+    status: ClockSkewStatus,
+}
+
+impl ClockSkewMonitor {
+    pub fn new(max_skew_ms: u64, ntp_server: Option<String>) -> Self {
+        Self {
+            max_skew_ms,
+            ntp_server,
+            last_checked: None,
+            status: ClockSkewStatus::Unknown,
+        }
+    }
+
+    /// Compares our clock with the beacon node's, and the NTP server's if there is one, returning
+    /// the new status.
+    ///
+    /// The clocks are compared once per epoch, or on every poll while skewed so that a corrected
+    /// clock is noticed promptly. If a clock cannot be read, the status is unchanged.
+    pub fn poll<B: BeaconNodeTime>(
+        &mut self,
+        current_epoch: Epoch,
+        beacon_node: &B,
+    ) -> Result<ClockSkewStatus, ClockSkewError> {
+        let due = match self.status {
+            ClockSkewStatus::Skewed { .. } => true,
+            _ => self
+                .last_checked
+                .map_or(true, |epoch| current_epoch > epoch),
+        };
+        if !due {
+            return Ok(self.status.clone());
+        }
+
+        let mut samples = vec![("beacon node".to_string(), beacon_node.request_time()?)];
+        if let Some(server) = &self.ntp_server {
+            samples.push((server.clone(), ntp::request_time(server)?));
+        }
+
+        self.last_checked = Some(current_epoch);
+        self.status = samples
+            .into_iter()
+            .filter(|(_, sample)| self.is_skewed(sample))
+            .max_by_key(|(_, sample)| sample.offset_ms().abs())
+            .map_or(ClockSkewStatus::Synced, |(source, sample)| {
+                ClockSkewStatus::Skewed {
+                    source,
+                    offset_ms: sample.offset_ms(),
+                }
+            });
+
+        Ok(self.status.clone())
+    }
+
+    /// Returns the status as of the last successful poll.
+    pub fn status(&self) -> &ClockSkewStatus {
+        &self.status
+    }
+
+    /// Returns `true` if the offset of `sample` exceeds the limit by more than the error of the
+    /// measurement, so that a slow response is not mistaken for skew.
+    fn is_skewed(&self, sample: &ClockSample) -> bool {
+        let error = sample.round_trip_ms().max(0) / 2;
+        sample.offset_ms().abs() - error > self.max_skew_ms as i64
+    }
+}
+
+/// Returns our time, in milliseconds since the Unix epoch.
+fn unix_time_ms() -> Result<i64, ClockSkewError> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as i64)
+        .map_err(|e| ClockSkewError::SystemTimeError(format!("{:?}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+
+    /// A beacon node whose clock is a fixed offset from ours, and whose responses take a fixed
+    /// time to arrive. It records every request.
+    struct TestBeaconNode {
+        offset_ms: RwLock<i64>,
+        round_trip_ms: i64,
+        requests: RwLock<usize>,
+    }
+
+    impl BeaconNodeTime for TestBeaconNode {
+        fn request_time(&self) -> Result<ClockSample, ClockSkewError> {
+            *self.requests.write().unwrap() += 1;
+            let sent = 1_000_000;
+            let remote = sent + self.round_trip_ms / 2 + *self.offset_ms.read().unwrap();
+            Ok(ClockSample {
+                sent,
+                remote_received: remote,
+                remote_sent: remote,
+                received: sent + self.round_trip_ms,
+            })
+        }
+    }
+
+    fn beacon_node(offset_ms: i64, round_trip_ms: i64) -> TestBeaconNode {
+        TestBeaconNode {
+            offset_ms: RwLock::new(offset_ms),
+            round_trip_ms,
+            requests: RwLock::new(0),
+        }
+    }
+
+    #[test]
+    fn measures_offsets() {
+        let sample = ClockSample {
+            sent: 1000,
+            remote_received: 1550,
+            remote_sent: 1560,
+            received: 1110,
+        };
+
+        assert_eq!(sample.offset_ms(), 500);
+        assert_eq!(sample.round_trip_ms(), 100);
+    }
+
+    #[test]
+    fn detects_skew() {
+        let mut monitor = ClockSkewMonitor::new(500, None);
+
+        assert_eq!(
+            monitor.poll(Epoch::new(1), &beacon_node(400, 20)),
+            Ok(ClockSkewStatus::Synced)
+        );
+        assert_eq!(
+            ClockSkewMonitor::new(500, None).poll(Epoch::new(1), &beacon_node(-800, 20)),
+            Ok(ClockSkewStatus::Skewed {
+                source: "beacon node".to_string(),
+                offset_ms: -800
+            })
+        );
+        // The offset may be entirely due to a slow response.
+        assert_eq!(
+            ClockSkewMonitor::new(500, None).poll(Epoch::new(1), &beacon_node(600, 400)),
+            Ok(ClockSkewStatus::Synced)
+        );
+    }
+
+    #[test]
+    fn polls_every_epoch_unless_skewed() {
+        let node = beacon_node(0, 20);
+        let mut monitor = ClockSkewMonitor::new(500, None);
+
+        for _ in 0..3 {
+            assert_eq!(
+                monitor.poll(Epoch::new(1), &node),
+                Ok(ClockSkewStatus::Synced)
+            );
+        }
+        assert_eq!(*node.requests.read().unwrap(), 1);
+
+        *node.offset_ms.write().unwrap() = 1000;
+        for _ in 0..3 {
+            assert!(monitor.poll(Epoch::new(2), &node).is_ok());
+        }
+        assert_eq!(*node.requests.read().unwrap(), 4);
+
+        *node.offset_ms.write().unwrap() = 0;
+        assert_eq!(
+            monitor.poll(Epoch::new(2), &node),
+            Ok(ClockSkewStatus::Synced)
+        );
+        assert_eq!(monitor.status(), &ClockSkewStatus::Synced);
+    }
+}
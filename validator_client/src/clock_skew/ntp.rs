@@ -0,0 +1,127 @@
+//! A minimal SNTP (RFC 4330) client, which reads the time of an NTP server.
+use super::beacon_node_time::{ClockSample, ClockSkewError};
+use super::unix_time_ms;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+/// The port on which NTP is served, if the server is given without one.
+pub const NTP_PORT: u16 = 123;
+
+/// Seconds from the NTP epoch, 1900, to the Unix epoch.
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+
+const NTP_PACKET_LEN: usize = 48;
+
+/// How long to wait for a response.
+const NTP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Requests the time of the NTP server at `server`, i.e., `host` or `host:port`.
+pub fn request_time(server: &str) -> Result<ClockSample, ClockSkewError> {
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:{}", server, NTP_PORT)
+    };
+    let ntp_error = |e: std::io::Error| ClockSkewError::NtpFailure(format!("{}: {:?}", server, e));
+
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(ntp_error)?;
+    socket
+        .set_read_timeout(Some(NTP_TIMEOUT))
+        .map_err(ntp_error)?;
+    socket.connect(address).map_err(ntp_error)?;
+
+    let sent = unix_time_ms()?;
+    socket.send(&encode_request()).map_err(ntp_error)?;
+    let mut response = [0; NTP_PACKET_LEN];
+    let len = socket.recv(&mut response).map_err(ntp_error)?;
+    let received = unix_time_ms()?;
+
+    let (remote_received, remote_sent) = decode_response(&response[..len])?;
+
+    Ok(ClockSample {
+        sent,
+        remote_received,
+        remote_sent,
+        received,
+    })
+}
+
+/// Encodes a client request: leap indicator 0, version 3, mode 3 (client).
+pub fn encode_request() -> [u8; NTP_PACKET_LEN] {
+    let mut request = [0; NTP_PACKET_LEN];
+    request[0] = 0x1b;
+    request
+}
+
+/// Decodes a server response, returning the server's receive and transmit timestamps in
+/// milliseconds since the Unix epoch.
+pub fn decode_response(response: &[u8]) -> Result<(i64, i64), ClockSkewError> {
+    if response.len() < NTP_PACKET_LEN {
+        return Err(ClockSkewError::NtpFailure(format!(
+            "Response of {} bytes is too short",
+            response.len()
+        )));
+    }
+    // Mode 4 is a server response.
+    if response[0] & 0x07 != 4 {
+        return Err(ClockSkewError::NtpFailure(format!(
+            "Unexpected mode {}",
+            response[0] & 0x07
+        )));
+    }
+    // Stratum 0 is a "kiss-o'-death", whose timestamps are meaningless.
+    if response[1] == 0 {
+        return Err(ClockSkewError::NtpFailure(
+            "Server refused the request".to_string(),
+        ));
+    }
+
+    Ok((
+        timestamp_ms(&response[32..40]),
+        timestamp_ms(&response[40..48]),
+    ))
+}
+
+/// Converts an NTP timestamp, i.e., 32 bits of seconds and 32 bits of fraction since 1900, to
+/// milliseconds since the Unix epoch.
+fn timestamp_ms(bytes: &[u8]) -> i64 {
+    let mut seconds = [0; 4];
+    let mut fraction = [0; 4];
+    seconds.copy_from_slice(&bytes[0..4]);
+    fraction.copy_from_slice(&bytes[4..8]);
+
+    let seconds = i64::from(u32::from_be_bytes(seconds)) - NTP_UNIX_OFFSET_SECS;
+    let millis = (i64::from(u32::from_be_bytes(fraction)) * 1000) >> 32;
+
+    seconds * 1000 + millis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_response() {
+        let mut response = [0; NTP_PACKET_LEN];
+        // Leap indicator 0, version 3, mode 4 (server), stratum 2.
+        response[0] = 0x1c;
+        response[1] = 2;
+        // 2019-07-01T00:00:00.500Z, received.
+        response[32..36].copy_from_slice(&(1_561_939_200u32 + 2_208_988_800).to_be_bytes());
+        response[36..40].copy_from_slice(&(1u32 << 31).to_be_bytes());
+        // 2019-07-01T00:00:01.000Z, sent.
+        response[40..44].copy_from_slice(&(1_561_939_201u32 + 2_208_988_800).to_be_bytes());
+
+        assert_eq!(
+            decode_response(&response),
+            Ok((1_561_939_200_500, 1_561_939_201_000))
+        );
+
+        response[1] = 0;
+        assert!(decode_response(&response).is_err());
+        response[1] = 2;
+        response[0] = 0x1b;
+        assert!(decode_response(&response).is_err());
+        assert!(decode_response(&response[..47]).is_err());
+    }
+}
@@ -1,11 +1,11 @@
-use bincode;
-use bls::Keypair;
+use crate::graffiti::GRAFFITI_BYTES_LEN;
+use crate::keystore::{create_private_dir, create_private_file, random_password, Kdf, Keystore};
+use bls::{Keypair, PublicKey};
 use clap::ArgMatches;
 use serde_derive::{Deserialize, Serialize};
 use slog::{debug, error, info};
 use std::fs;
-use std::fs::File;
-use std::io::{Error, ErrorKind};
+use std::io::{Error, ErrorKind, Write};
 use std::path::PathBuf;
 use types::{EthSpec, MainnetEthSpec};
 
@@ -18,9 +18,52 @@ pub struct Config {
     pub server: String,
     /// The number of slots per epoch.
     pub slots_per_epoch: u64,
+    /// The URL of a remote signing service. If set, the service's keys are used instead of any
+    /// keystores in `data_dir`.
+    pub remote_signer: Option<String>,
+    /// The number of epochs to watch for our own validators on the network before signing. Zero
+    /// disables doppelganger protection.
+    pub doppelganger_epochs: u64,
+    /// If non-zero, run as a standby which only begins signing once none of its validators have
+    /// been seen on the network for this many epochs. Replaces doppelganger protection.
+    pub standby_epochs: u64,
+    /// A slashing protection interchange exported by the primary, which a standby imports before
+    /// it begins signing.
+    pub standby_interchange: Option<PathBuf>,
+    /// If `true`, only accept duties accompanied by a valid Merkle proof of the Beacon Node's
+    /// state.
+    pub verify_duties: bool,
+    /// If `true`, serve the key manager API.
+    pub http_enabled: bool,
+    /// The address on which to serve the key manager API.
+    pub http_listen_address: String,
+    /// The port on which to serve the key manager API.
+    pub http_listen_port: String,
+    /// The graffiti of blocks proposed by validators without an entry in `graffiti_file`.
+    pub graffiti: Option<String>,
+    /// A file of per-validator graffiti, which is re-read whenever it changes.
+    pub graffiti_file: Option<PathBuf>,
+    /// Milliseconds after the start of each slot before duties are fetched and blocks produced.
+    pub block_delay_ms: u64,
+    /// Milliseconds after the start of a slot before its attestations are produced and signed.
+    pub attestation_delay_ms: u64,
+    /// Milliseconds after the start of a slot before its signed attestations are published to the
+    /// Beacon Node for aggregation. An attestation is published as soon as it is signed if this
+    /// time has already passed.
+    pub aggregate_publication_offset_ms: u64,
+    /// The largest offset, in milliseconds, of the Beacon Node's (or NTP server's) clock from ours
+    /// which is not warned of as skew.
+    pub max_clock_skew_ms: u64,
+    /// If `true`, refuse to sign while our clock is skewed.
+    pub refuse_on_clock_skew: bool,
+    /// An NTP server, `host` or `host:port`, to compare our clock with as well as the Beacon Node.
+    pub ntp_server: Option<String>,
 }
 
-const DEFAULT_PRIVATE_KEY_FILENAME: &str = "private.key";
+const DEFAULT_KEYSTORE_FILENAME: &str = "voting-keystore.json";
+const DEFAULT_SECRETS_DIR: &str = "secrets";
+const DEFAULT_API_TOKEN_FILENAME: &str = "api-token.txt";
+const DEFAULT_MAX_CLOCK_SKEW_MS: u64 = 500;
 
 impl Default for Config {
     /// Build a new configuration from defaults.
@@ -29,6 +72,22 @@ impl Default for Config {
             data_dir: PathBuf::from(".lighthouse-validator"),
             server: "localhost:5051".to_string(),
             slots_per_epoch: MainnetEthSpec::slots_per_epoch(),
+            remote_signer: None,
+            doppelganger_epochs: 0,
+            standby_epochs: 0,
+            standby_interchange: None,
+            verify_duties: false,
+            http_enabled: false,
+            http_listen_address: "127.0.0.1".to_string(),
+            http_listen_port: "5062".to_string(),
+            graffiti: None,
+            graffiti_file: None,
+            block_delay_ms: 200,
+            attestation_delay_ms: 200,
+            aggregate_publication_offset_ms: 200,
+            max_clock_skew_ms: DEFAULT_MAX_CLOCK_SKEW_MS,
+            refuse_on_clock_skew: false,
+            ntp_server: None,
         }
     }
 }
@@ -47,10 +106,102 @@ impl Config {
             self.server = srv.to_string();
         };
 
+        if let Some(url) = args.value_of("remote-signer") {
+            self.remote_signer = Some(url.to_string());
+        };
+
+        if let Some(epochs) = args.value_of("doppelganger-epochs") {
+            self.doppelganger_epochs = epochs
+                .parse()
+                .map_err(|_| "doppelganger-epochs is not a valid integer")?;
+        };
+
+        if let Some(epochs) = args.value_of("standby") {
+            self.standby_epochs = epochs
+                .parse()
+                .map_err(|_| "standby is not a valid integer")?;
+        };
+
+        if let Some(path) = args.value_of("standby-interchange") {
+            self.standby_interchange = Some(PathBuf::from(path));
+        };
+
+        if args.is_present("verify-duties") {
+            self.verify_duties = true;
+        }
+
+        if args.is_present("http") {
+            self.http_enabled = true;
+        }
+
+        if let Some(listen_address) = args.value_of("http-address") {
+            self.http_listen_address = listen_address.to_string();
+        }
+
+        if let Some(listen_port) = args.value_of("http-port") {
+            self.http_listen_port = listen_port.to_string();
+        }
+
+        if let Some(graffiti) = args.value_of("graffiti") {
+            if graffiti.len() > GRAFFITI_BYTES_LEN {
+                return Err("graffiti exceeds 32 bytes");
+            }
+            self.graffiti = Some(graffiti.to_string());
+        }
+
+        if let Some(graffiti_file) = args.value_of("graffiti-file") {
+            self.graffiti_file = Some(PathBuf::from(graffiti_file));
+        }
+
+        if let Some(delay) = args.value_of("block-delay") {
+            self.block_delay_ms = delay
+                .parse()
+                .map_err(|_| "block-delay is not a valid integer")?;
+        }
+
+        if let Some(delay) = args.value_of("attestation-delay") {
+            self.attestation_delay_ms = delay
+                .parse()
+                .map_err(|_| "attestation-delay is not a valid integer")?;
+        }
+
+        if let Some(offset) = args.value_of("aggregate-publication-offset") {
+            self.aggregate_publication_offset_ms = offset
+                .parse()
+                .map_err(|_| "aggregate-publication-offset is not a valid integer")?;
+        }
+
+        if let Some(skew) = args.value_of("max-clock-skew") {
+            self.max_clock_skew_ms = skew
+                .parse()
+                .map_err(|_| "max-clock-skew is not a valid integer")?;
+        }
+
+        if args.is_present("refuse-on-clock-skew") {
+            self.refuse_on_clock_skew = true;
+        }
+
+        if let Some(server) = args.value_of("ntp-server") {
+            self.ntp_server = Some(server.to_string());
+        }
+
         Ok(())
     }
 
+    /// The directory holding the password file of each keystore.
+    pub fn secrets_dir(&self) -> PathBuf {
+        self.data_dir.join(DEFAULT_SECRETS_DIR)
+    }
+
+    /// The file holding the bearer token which authorizes requests to the key manager API.
+    pub fn api_token_path(&self) -> PathBuf {
+        self.data_dir.join(DEFAULT_API_TOKEN_FILENAME)
+    }
+
     /// Try to load keys from validator_dir, returning None if none are found or an error.
+    ///
+    /// Each validator directory must contain an EIP-2335 keystore, the password of which is read
+    /// from the file in `secrets_dir` named after the keystore's public key.
     #[allow(dead_code)]
     pub fn fetch_keys(&self, log: &slog::Logger) -> Option<Vec<Keypair>> {
         let key_pairs: Vec<Keypair> = fs::read_dir(&self.data_dir)
@@ -63,33 +214,55 @@ impl Config {
                     return None;
                 }
 
-                let key_filename = validator_dir.path().join(DEFAULT_PRIVATE_KEY_FILENAME);
+                let keystore_filename = validator_dir.path().join(DEFAULT_KEYSTORE_FILENAME);
 
-                if !(key_filename.is_file()) {
+                if !(keystore_filename.is_file()) {
                     info!(
                         log,
-                        "Private key is not a file: {:?}",
-                        key_filename.to_str()
+                        "Keystore is not a file: {:?}",
+                        keystore_filename.to_str()
                     );
                     return None;
                 }
 
                 debug!(
                     log,
-                    "Deserializing private key from file: {:?}",
-                    key_filename.to_str()
+                    "Decrypting keystore from file: {:?}",
+                    keystore_filename.to_str()
                 );
 
-                let mut key_file = File::open(key_filename.clone()).ok()?;
+                let keystore = match Keystore::from_json_file(&keystore_filename) {
+                    Ok(keystore) => keystore,
+                    Err(e) => {
+                        error!(
+                            log,
+                            "Unable to read the keystore file {:?}: {:?}", keystore_filename, e
+                        );
+                        return None;
+                    }
+                };
 
-                let key: Keypair = if let Ok(key_ok) = bincode::deserialize_from(&mut key_file) {
-                    key_ok
-                } else {
-                    error!(
-                        log,
-                        "Unable to deserialize the private key file: {:?}", key_filename
-                    );
-                    return None;
+                let password_filename = self.secrets_dir().join(keystore.pubkey_hex());
+                let password = match fs::read_to_string(&password_filename) {
+                    Ok(password) => password,
+                    Err(e) => {
+                        error!(
+                            log,
+                            "Unable to read the password file {:?}: {:?}", password_filename, e
+                        );
+                        return None;
+                    }
+                };
+
+                let key = match keystore.decrypt_keypair(&password) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        error!(
+                            log,
+                            "Unable to decrypt the keystore {:?}: {:?}", keystore_filename, e
+                        );
+                        return None;
+                    }
                 };
 
                 let ki = key.identifier();
@@ -114,18 +287,60 @@ impl Config {
         }
     }
 
-    /// Saves a keypair to a file inside the appropriate validator directory. Returns the saved path filename.
+    /// Saves a keypair to a keystore inside the appropriate validator directory, encrypted with
+    /// a new random password which is written to `secrets_dir`. Returns the saved path filename.
     #[allow(dead_code)]
     pub fn save_key(&self, key: &Keypair) -> Result<PathBuf, Error> {
+        let password = random_password();
+        let keystore = Keystore::encrypt(key, &password, Kdf::scrypt())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{:?}", e)))?;
+
+        self.save_keystore(key, &keystore, &password)
+    }
+
+    /// Saves an existing keystore of `key`, and the password which decrypts it, to the locations
+    /// from which `fetch_keys` will load them. Returns the saved path filename.
+    ///
+    /// Only the owner may read the keystore, the password or the secrets directory.
+    pub fn save_keystore(
+        &self,
+        key: &Keypair,
+        keystore: &Keystore,
+        password: &str,
+    ) -> Result<PathBuf, Error> {
         let validator_config_path = self.data_dir.join(key.identifier());
-        let key_path = validator_config_path.join(DEFAULT_PRIVATE_KEY_FILENAME);
+        let keystore_path = validator_config_path.join(DEFAULT_KEYSTORE_FILENAME);
+        let password_path = self.secrets_dir().join(key.pk.as_hex_string());
 
         fs::create_dir_all(&validator_config_path)?;
+        create_private_dir(&self.secrets_dir())?;
 
-        let mut key_file = File::create(&key_path)?;
+        create_private_file(&password_path)?.write_all(password.as_bytes())?;
+        keystore
+            .to_json_file(&keystore_path)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("{:?}", e)))?;
+        Ok(keystore_path)
+    }
+
+    /// Deletes the keystore and password file of `public_key`, if they exist, so that the
+    /// validator is not loaded again on restart.
+    ///
+    /// Validator directories are named by a short prefix of the public key, so the keystore is
+    /// only deleted if it belongs to `public_key`.
+    pub fn delete_key(&self, public_key: &PublicKey) -> Result<(), Error> {
+        let validator_config_path = self.data_dir.join(public_key.concatenated_hex_id());
+        let keystore_path = validator_config_path.join(DEFAULT_KEYSTORE_FILENAME);
+        let password_path = self.secrets_dir().join(public_key.as_hex_string());
 
-        bincode::serialize_into(&mut key_file, &key)
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
-        Ok(key_path)
+        if let Ok(keystore) = Keystore::from_json_file(&keystore_path) {
+            if keystore.pubkey_hex() == public_key.as_hex_string() {
+                fs::remove_dir_all(&validator_config_path)?;
+            }
+        }
+
+        match fs::remove_file(&password_path) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
     }
 }
@@ -0,0 +1,17 @@
+use types::{Epoch, PublicKey};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BeaconNodeLivenessError {
+    RemoteFailure(String),
+}
+
+/// Defines the methods required to learn whether validators are active on the network.
+pub trait BeaconNodeLiveness: Send + Sync {
+    /// Returns whether each of `pub_keys` attested or proposed a block during `epoch`, in the
+    /// same order as `pub_keys`.
+    fn request_liveness(
+        &self,
+        epoch: Epoch,
+        pub_keys: &[PublicKey],
+    ) -> Result<Vec<bool>, BeaconNodeLivenessError>;
+}
@@ -0,0 +1,34 @@
+use super::beacon_node_liveness::{BeaconNodeLiveness, BeaconNodeLivenessError};
+use protos::services::{GetLivenessRequest, Validators};
+use protos::services_grpc::ValidatorServiceClient;
+use ssz::ssz_encode;
+use types::{Epoch, PublicKey};
+
+impl BeaconNodeLiveness for ValidatorServiceClient {
+    /// Requests the liveness of each validator from the Beacon Node (BN).
+    fn request_liveness(
+        &self,
+        epoch: Epoch,
+        pub_keys: &[PublicKey],
+    ) -> Result<Vec<bool>, BeaconNodeLivenessError> {
+        let mut req = GetLivenessRequest::new();
+        req.set_epoch(epoch.as_u64());
+        let mut validators = Validators::new();
+        validators.set_public_keys(pub_keys.iter().map(|v| ssz_encode(v)).collect());
+        req.set_validators(validators);
+
+        let reply = self
+            .get_validator_liveness(&req)
+            .map_err(|err| BeaconNodeLivenessError::RemoteFailure(format!("{:?}", err)))?;
+
+        if reply.get_is_live().len() != pub_keys.len() {
+            return Err(BeaconNodeLivenessError::RemoteFailure(format!(
+                "Expected {} liveness results, got {}",
+                pub_keys.len(),
+                reply.get_is_live().len()
+            )));
+        }
+
+        Ok(reply.get_is_live().to_vec())
+    }
+}
@@ -0,0 +1,204 @@
+//! Doppelganger protection: before signing anything, watch the network for a number of epochs
+//! for messages from our own validators. If any are seen, the same keys are being run elsewhere
+//! and signing with them here would risk a slashing.
+mod beacon_node_liveness;
+mod grpc;
+
+pub use self::beacon_node_liveness::{BeaconNodeLiveness, BeaconNodeLivenessError};
+use types::{Epoch, PublicKey};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum DoppelgangerStatus {
+    /// Signing must wait until `epoch`.
+    Waiting { until: Epoch },
+    /// No doppelganger was seen during the waiting period; signing is safe.
+    SigningEnabled,
+    /// The given validators were seen on the network.
+    Detected(Vec<PublicKey>),
+}
+
+/// A polling state machine which decides when it is safe for the validator client to begin
+/// signing.
+pub struct DoppelgangerProtection {
+    /// The first epoch which is checked for liveness.
+    ///
+    /// The epoch in which the client started is not checked, since this client may have signed
+    /// in it before a restart.
+    first_epoch: Epoch,
+    /// The last epoch which is checked for liveness.
+    last_epoch: Epoch,
+    status: DoppelgangerStatus,
+}
+
+impl DoppelgangerProtection {
+    /// Checks for doppelgangers for `epochs` full epochs following `start_epoch`, the epoch in
+    /// which the client started. If `epochs` is zero, signing is enabled immediately.
+    pub fn new(start_epoch: Epoch, epochs: u64) -> Self {
+        let status = if epochs == 0 {
+            DoppelgangerStatus::SigningEnabled
+        } else {
+            DoppelgangerStatus::Waiting {
+                until: start_epoch + epochs + 1,
+            }
+        };
+
+        Self {
+            first_epoch: start_epoch + 1,
+            last_epoch: start_epoch + epochs,
+            status,
+        }
+    }
+
+    /// Checks the previous and current epoch for liveness of any of `pub_keys`, returning the new
+    /// status.
+    ///
+    /// The previous epoch is re-checked since its attestations may be included in blocks during
+    /// the current epoch. Once a doppelganger is detected, or signing is enabled, the status does
+    /// not change again.
+    pub fn poll<B: BeaconNodeLiveness>(
+        &mut self,
+        current_epoch: Epoch,
+        beacon_node: &B,
+        pub_keys: &[PublicKey],
+    ) -> Result<DoppelgangerStatus, BeaconNodeLivenessError> {
+        let until = match self.status {
+            DoppelgangerStatus::Waiting { until } => until,
+            _ => return Ok(self.status.clone()),
+        };
+
+        let previous_epoch = current_epoch.saturating_sub(1u64);
+        for epoch in &[previous_epoch, current_epoch] {
+            let epoch = *epoch;
+            if epoch < self.first_epoch || epoch > self.last_epoch {
+                continue;
+            }
+
+            let live: Vec<PublicKey> = beacon_node
+                .request_liveness(epoch, pub_keys)?
+                .into_iter()
+                .zip(pub_keys)
+                .filter(|(is_live, _)| *is_live)
+                .map(|(_, pub_key)| pub_key.clone())
+                .collect();
+
+            if !live.is_empty() {
+                self.status = DoppelgangerStatus::Detected(live);
+                return Ok(self.status.clone());
+            }
+        }
+
+        if current_epoch >= until {
+            self.status = DoppelgangerStatus::SigningEnabled;
+        }
+
+        Ok(self.status.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::RwLock;
+    use types::Keypair;
+
+    /// A beacon node which reports a fixed set of validators as live in a fixed set of epochs,
+    /// and records every epoch it is asked about.
+    struct TestBeaconNode {
+        live_keys: Vec<PublicKey>,
+        live_epochs: Vec<Epoch>,
+        requested: RwLock<Vec<Epoch>>,
+    }
+
+    impl BeaconNodeLiveness for TestBeaconNode {
+        fn request_liveness(
+            &self,
+            epoch: Epoch,
+            pub_keys: &[PublicKey],
+        ) -> Result<Vec<bool>, BeaconNodeLivenessError> {
+            self.requested.write().unwrap().push(epoch);
+            Ok(pub_keys
+                .iter()
+                .map(|pk| self.live_epochs.contains(&epoch) && self.live_keys.contains(pk))
+                .collect())
+        }
+    }
+
+    fn beacon_node(live_keys: Vec<PublicKey>, live_epochs: Vec<u64>) -> TestBeaconNode {
+        TestBeaconNode {
+            live_keys,
+            live_epochs: live_epochs.into_iter().map(Epoch::new).collect(),
+            requested: RwLock::new(vec![]),
+        }
+    }
+
+    #[test]
+    fn enables_signing_after_waiting() {
+        let pub_keys = vec![Keypair::random().pk];
+        let node = beacon_node(vec![], vec![]);
+        let mut protection = DoppelgangerProtection::new(Epoch::new(10), 2);
+
+        for epoch in 10..13 {
+            assert_eq!(
+                protection.poll(Epoch::new(epoch), &node, &pub_keys),
+                Ok(DoppelgangerStatus::Waiting {
+                    until: Epoch::new(13)
+                })
+            );
+        }
+        assert_eq!(
+            protection.poll(Epoch::new(13), &node, &pub_keys),
+            Ok(DoppelgangerStatus::SigningEnabled)
+        );
+
+        // The start epoch is never checked.
+        let requested = node.requested.read().unwrap();
+        assert!(!requested.contains(&Epoch::new(10)));
+        assert!(requested.contains(&Epoch::new(11)));
+        assert!(requested.contains(&Epoch::new(12)));
+    }
+
+    #[test]
+    fn zero_epochs_enables_signing_immediately() {
+        let node = beacon_node(vec![], vec![]);
+        let mut protection = DoppelgangerProtection::new(Epoch::new(10), 0);
+
+        assert_eq!(
+            protection.poll(Epoch::new(10), &node, &[Keypair::random().pk]),
+            Ok(DoppelgangerStatus::SigningEnabled)
+        );
+        assert!(node.requested.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn detects_live_validator() {
+        let ours = Keypair::random().pk;
+        let theirs = Keypair::random().pk;
+        let pub_keys = vec![theirs.clone(), ours.clone()];
+        let node = beacon_node(vec![ours.clone()], vec![11]);
+        let mut protection = DoppelgangerProtection::new(Epoch::new(10), 2);
+
+        assert!(protection.poll(Epoch::new(10), &node, &pub_keys).is_ok());
+        assert_eq!(
+            protection.poll(Epoch::new(11), &node, &pub_keys),
+            Ok(DoppelgangerStatus::Detected(vec![ours.clone()]))
+        );
+        // Detection is permanent.
+        assert_eq!(
+            protection.poll(Epoch::new(20), &node, &pub_keys),
+            Ok(DoppelgangerStatus::Detected(vec![ours]))
+        );
+    }
+
+    #[test]
+    fn ignores_liveness_in_start_epoch() {
+        let pub_keys = vec![Keypair::random().pk];
+        let node = beacon_node(pub_keys.clone(), vec![10]);
+        let mut protection = DoppelgangerProtection::new(Epoch::new(10), 1);
+
+        assert!(protection.poll(Epoch::new(11), &node, &pub_keys).is_ok());
+        assert_eq!(
+            protection.poll(Epoch::new(12), &node, &pub_keys),
+            Ok(DoppelgangerStatus::SigningEnabled)
+        );
+    }
+}
@@ -1,9 +1,11 @@
-use super::EpochDuties;
+use super::{DutiesVerifier, EpochDuties};
 use types::{Epoch, PublicKey};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum BeaconNodeDutiesError {
     RemoteFailure(String),
+    /// The duties could not be verified against the proof supplied with them.
+    InvalidProof(String),
 }
 
 /// Defines the methods required to obtain a validators shuffling from a Beacon Node.
@@ -12,9 +14,13 @@ pub trait BeaconNodeDuties: Send + Sync {
     ///
     /// Returns a vector of EpochDuties for each validator public key. The entry will be None for
     /// validators that are not activated.
+    ///
+    /// If a `verifier` is supplied, the duties are requested with a proof and rejected unless it
+    /// is valid.
     fn request_duties(
         &self,
         epoch: Epoch,
         pub_keys: &[PublicKey],
+        verifier: Option<&DutiesVerifier>,
     ) -> Result<EpochDuties, BeaconNodeDutiesError>;
 }
@@ -1,13 +1,15 @@
 use super::beacon_node_duties::{BeaconNodeDuties, BeaconNodeDutiesError};
 use super::epoch_duties::{EpochDuties, EpochDuty};
+use super::DutiesVerifier;
+use merkle_proof::SerializedPartial;
 // to use if we manually specify a timeout
 //use grpcio::CallOption;
-use protos::services::{GetDutiesRequest, Validators};
+use protos::services::{GetDutiesRequest, GetDutiesResponse, Validators};
 use protos::services_grpc::ValidatorServiceClient;
 use ssz::ssz_encode;
 use std::collections::HashMap;
 // use std::time::Duration;
-use types::{AttestationDuty, Epoch, PublicKey, Slot};
+use types::{AttestationDuty, Epoch, Hash256, PublicKey, Slot};
 
 impl BeaconNodeDuties for ValidatorServiceClient {
     /// Requests all duties (block signing and committee attesting) from the Beacon Node (BN).
@@ -15,6 +17,7 @@ impl BeaconNodeDuties for ValidatorServiceClient {
         &self,
         epoch: Epoch,
         pub_keys: &[PublicKey],
+        verifier: Option<&DutiesVerifier>,
     ) -> Result<EpochDuties, BeaconNodeDutiesError> {
         // Get the required duties from all validators
         // build the request
@@ -23,6 +26,7 @@ impl BeaconNodeDuties for ValidatorServiceClient {
         let mut validators = Validators::new();
         validators.set_public_keys(pub_keys.iter().map(|v| ssz_encode(v)).collect());
         req.set_validators(validators);
+        req.set_include_proof(verifier.is_some());
 
         // set a timeout for requests
         // let call_opt = CallOption::default().timeout(Duration::from_secs(2));
@@ -33,6 +37,8 @@ impl BeaconNodeDuties for ValidatorServiceClient {
             .map_err(|err| BeaconNodeDutiesError::RemoteFailure(format!("{:?}", err)))?;
 
         let mut epoch_duties: HashMap<PublicKey, Option<EpochDuty>> = HashMap::new();
+        // the registry index claimed for each validator with duties
+        let mut claimed_indices: Vec<(usize, &PublicKey)> = vec![];
         for (index, validator_duty) in reply.get_active_validators().iter().enumerate() {
             if !validator_duty.has_duty() {
                 // validator is inactive
@@ -61,7 +67,47 @@ impl BeaconNodeDuties for ValidatorServiceClient {
                 attestation_duty,
             };
             epoch_duties.insert(pub_keys[index].clone(), Some(epoch_duty));
+            claimed_indices.push((active_duty.get_validator_index() as usize, &pub_keys[index]));
         }
+
+        if let Some(verifier) = verifier {
+            let (state_root, partial) = proof_from_reply(&reply)?;
+            verifier
+                .verify(&partial, state_root, &claimed_indices, epoch)
+                .map_err(|e| BeaconNodeDutiesError::InvalidProof(format!("{:?}", e)))?;
+        }
+
         Ok(epoch_duties)
     }
 }
+
+/// Reads the state root and proof from a reply to a request which included a proof.
+fn proof_from_reply(
+    reply: &GetDutiesResponse,
+) -> Result<(Hash256, SerializedPartial), BeaconNodeDutiesError> {
+    let to_hash = |bytes: &[u8]| {
+        if bytes.len() == 32 {
+            Ok(Hash256::from_slice(bytes))
+        } else {
+            Err(BeaconNodeDutiesError::InvalidProof(format!(
+                "Expected 32 byte chunks, got {}",
+                bytes.len()
+            )))
+        }
+    };
+
+    let state_root = to_hash(reply.get_state_root())?;
+    let chunks = reply
+        .get_proof_chunks()
+        .iter()
+        .map(|chunk| to_hash(chunk))
+        .collect::<Result<_, _>>()?;
+
+    Ok((
+        state_root,
+        SerializedPartial {
+            indices: reply.get_proof_indices().to_vec(),
+            chunks,
+        },
+    ))
+}
@@ -1,6 +1,7 @@
 mod beacon_node_duties;
 mod epoch_duties;
 mod grpc;
+mod verifier;
 // TODO: reintroduce tests
 //#[cfg(test)]
 //mod test_node;
@@ -8,6 +9,7 @@ mod grpc;
 pub use self::beacon_node_duties::{BeaconNodeDuties, BeaconNodeDutiesError};
 use self::epoch_duties::{EpochDuties, EpochDutiesMapError};
 pub use self::epoch_duties::{EpochDutiesMap, WorkInfo};
+pub use self::verifier::DutiesVerifier;
 use super::signer::Signer;
 use futures::Async;
 use slog::{debug, error, info};
@@ -42,8 +44,12 @@ pub enum Error {
 pub struct DutiesManager<U: BeaconNodeDuties, S: Signer> {
     pub duties_map: RwLock<EpochDutiesMap>,
     /// A list of all signer objects known to the validator service.
-    pub signers: Arc<Vec<S>>,
+    ///
+    /// Validators may be added or removed while the service is running.
+    pub signers: Arc<RwLock<Vec<S>>>,
     pub beacon_node: Arc<U>,
+    /// If set, duties are only accepted with a valid proof against the Beacon Node's state.
+    pub verifier: Option<DutiesVerifier>,
 }
 
 impl<U: BeaconNodeDuties, S: Signer + Display> DutiesManager<U, S> {
@@ -51,8 +57,11 @@ impl<U: BeaconNodeDuties, S: Signer + Display> DutiesManager<U, S> {
     ///
     /// be a wall-clock (e.g., system time, remote server time, etc.).
     fn update(&self, epoch: Epoch) -> Result<UpdateOutcome, Error> {
-        let public_keys: Vec<PublicKey> = self.signers.iter().map(Signer::to_public).collect();
-        let duties = self.beacon_node.request_duties(epoch, &public_keys)?;
+        let public_keys: Vec<PublicKey> =
+            self.signers.read()?.iter().map(Signer::to_public).collect();
+        let duties =
+            self.beacon_node
+                .request_duties(epoch, &public_keys, self.verifier.as_ref())?;
         {
             // If these duties were known, check to see if they're updates or identical.
             if let Some(known_duties) = self.duties_map.read()?.get(&epoch) {
@@ -91,17 +100,18 @@ impl<U: BeaconNodeDuties, S: Signer + Display> DutiesManager<U, S> {
         Ok(Async::Ready(()))
     }
 
-    /// Returns a list of (index, WorkInfo) indicating all the validators that have work to perform
-    /// this slot.
-    pub fn get_current_work(&self, slot: Slot) -> Option<Vec<(usize, WorkInfo)>> {
-        let mut current_work: Vec<(usize, WorkInfo)> = Vec::new();
+    /// Returns a list of (signer, WorkInfo) indicating all the validators that have work to
+    /// perform this slot.
+    pub fn get_current_work(&self, slot: Slot) -> Option<Vec<(S, WorkInfo)>> {
+        let mut current_work: Vec<(S, WorkInfo)> = Vec::new();
 
-        // if the map is poisoned, return None
+        // if either lock is poisoned, return None
         let duties = self.duties_map.read().ok()?;
+        let signers = self.signers.read().ok()?;
 
-        for (index, validator_signer) in self.signers.iter().enumerate() {
+        for validator_signer in signers.iter() {
             match duties.is_work_slot(slot, &validator_signer.to_public()) {
-                Ok(Some(work_type)) => current_work.push((index, work_type)),
+                Ok(Some(work_type)) => current_work.push((validator_signer.clone(), work_type)),
                 Ok(None) => {} // No work for this validator
                 //TODO: This should really log an error, as we shouldn't end up with an err here.
                 Err(_) => {} // Unknown epoch or validator, no work
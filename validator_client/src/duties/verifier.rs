@@ -0,0 +1,40 @@
+use merkle_proof::SerializedPartial;
+use types::{verify_duties_proof, ChainSpec, DutiesProofError, Epoch, EthSpec, Hash256, PublicKey};
+
+type VerifyFn = fn(
+    &SerializedPartial,
+    Hash256,
+    &[(usize, &PublicKey)],
+    Epoch,
+    &ChainSpec,
+) -> Result<(), DutiesProofError>;
+
+/// Checks the duties reported by a Beacon Node against a Merkle proof of its state.
+///
+/// Each validator with duties must be at the reported index in the registry and active in the
+/// epoch, and the inputs to the epoch's shuffling seed must be proven. The state root is the one
+/// reported by the node, so this detects a node which misreports its own state, not one which
+/// follows the wrong chain.
+pub struct DutiesVerifier {
+    spec: ChainSpec,
+    verify: VerifyFn,
+}
+
+impl DutiesVerifier {
+    pub fn new<T: EthSpec>(spec: ChainSpec) -> Self {
+        Self {
+            spec,
+            verify: verify_duties_proof::<T>,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        partial: &SerializedPartial,
+        state_root: Hash256,
+        validators: &[(usize, &PublicKey)],
+        epoch: Epoch,
+    ) -> Result<(), DutiesProofError> {
+        (self.verify)(partial, state_root, validators, epoch, &self.spec)
+    }
+}
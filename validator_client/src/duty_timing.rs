@@ -0,0 +1,124 @@
+use crate::config::Config;
+use slot_clock::SlotClock;
+use std::time::Duration;
+use types::Slot;
+
+/// When, relative to the start of a slot, each duty is performed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DutyTiming {
+    /// Time before duties are fetched and blocks are produced.
+    pub block_delay: Duration,
+    /// Time before attestations are produced and signed.
+    pub attestation_delay: Duration,
+    /// Time before signed attestations are published to the Beacon Node for aggregation.
+    pub aggregate_publication_offset: Duration,
+}
+
+impl DutyTiming {
+    /// Reads the timing from `config`, returning an error if any duty would fall outside a slot
+    /// of `seconds_per_slot`.
+    pub fn from_config(config: &Config, seconds_per_slot: u64) -> Result<Self, String> {
+        let slot_ms = seconds_per_slot.saturating_mul(1_000);
+        let within_slot = |name: &str, ms: u64| {
+            if ms < slot_ms {
+                Ok(Duration::from_millis(ms))
+            } else {
+                Err(format!(
+                    "{} of {}ms is not within a slot of {}ms",
+                    name, ms, slot_ms
+                ))
+            }
+        };
+
+        Ok(Self {
+            block_delay: within_slot("block-delay", config.block_delay_ms)?,
+            attestation_delay: within_slot("attestation-delay", config.attestation_delay_ms)?,
+            aggregate_publication_offset: within_slot(
+                "aggregate-publication-offset",
+                config.aggregate_publication_offset_ms,
+            )?,
+        })
+    }
+}
+
+/// The time from now until each duty of a slot is due, or `None` if it is already due.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DutyWaits {
+    pub attestation: Option<Duration>,
+    pub aggregate_publication: Option<Duration>,
+}
+
+impl DutyTiming {
+    /// Reads `slot_clock` to find how long until each duty of `slot` is due.
+    pub fn waits<C: SlotClock>(&self, slot_clock: &C, slot: Slot) -> Result<DutyWaits, C::Error> {
+        Ok(DutyWaits {
+            attestation: slot_clock.duration_to_slot_offset(slot, self.attestation_delay)?,
+            aggregate_publication: slot_clock
+                .duration_to_slot_offset(slot, self.aggregate_publication_offset)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slot_clock::TestingSlotClock;
+
+    #[test]
+    fn defaults_fit_within_a_slot() {
+        let timing = DutyTiming::from_config(&Config::default(), 6).unwrap();
+
+        assert_eq!(timing.block_delay, Duration::from_millis(200));
+        assert_eq!(timing.attestation_delay, Duration::from_millis(200));
+        assert_eq!(
+            timing.aggregate_publication_offset,
+            Duration::from_millis(200)
+        );
+    }
+
+    #[test]
+    fn rejects_delays_beyond_the_slot() {
+        let mut config = Config::default();
+        config.attestation_delay_ms = 2_000;
+        assert!(DutyTiming::from_config(&config, 6).is_ok());
+
+        config.attestation_delay_ms = 6_000;
+        assert!(DutyTiming::from_config(&config, 6).is_err());
+    }
+
+    #[test]
+    fn waits_for_duties_within_the_slot() {
+        let mut config = Config::default();
+        config.attestation_delay_ms = 2_000;
+        config.aggregate_publication_offset_ms = 4_000;
+        let timing = DutyTiming::from_config(&config, 6).unwrap();
+
+        let slot_clock = TestingSlotClock::new(Slot::new(0), 0, 6);
+        slot_clock.set_slot(5);
+        assert_eq!(
+            timing.waits(&slot_clock, Slot::new(5)),
+            Ok(DutyWaits {
+                attestation: Some(Duration::from_secs(2)),
+                aggregate_publication: Some(Duration::from_secs(4)),
+            })
+        );
+
+        slot_clock.advance(Duration::from_millis(2_500));
+        assert_eq!(
+            timing.waits(&slot_clock, Slot::new(5)),
+            Ok(DutyWaits {
+                attestation: None,
+                aggregate_publication: Some(Duration::from_millis(1_500)),
+            })
+        );
+
+        slot_clock.advance_slot();
+        assert_eq!(
+            timing.waits(&slot_clock, Slot::new(5)),
+            Ok(DutyWaits {
+                attestation: None,
+                aggregate_publication: None,
+            })
+        );
+    }
+}
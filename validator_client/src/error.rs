@@ -15,5 +15,10 @@ error_chain! {
         description("Error reading system time"),
         display("SystemTimeError: '{}'", t)
     }
+
+    DoppelgangerDetected(keys: Vec<String>) {
+        description("Validators are already active on the network"),
+        display("DoppelgangerDetected: '{:?}'", keys)
+    }
    }
 }
@@ -0,0 +1,185 @@
+//! Chooses the graffiti of each proposed block.
+//!
+//! Graffiti may be set per validator in a graffiti file, which is re-read whenever it is
+//! modified, so it may be changed without restarting the client. Each line of the file is either
+//! `default: <graffiti>` or `<0x-prefixed public key>: <graffiti>`; empty lines and lines starting
+//! with `#` are ignored.
+use slog::warn;
+use ssz::Decode;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use types::PublicKey;
+
+/// The length of a block's graffiti field.
+pub const GRAFFITI_BYTES_LEN: usize = 32;
+
+pub type Graffiti = [u8; GRAFFITI_BYTES_LEN];
+
+/// Converts `s` to graffiti, padding it with zeros. Returns an error if it exceeds 32 bytes.
+pub fn graffiti_from_str(s: &str) -> Result<Graffiti, String> {
+    let bytes = s.as_bytes();
+    if bytes.len() > GRAFFITI_BYTES_LEN {
+        return Err(format!(
+            "Graffiti is {} bytes, the maximum is {}",
+            bytes.len(),
+            GRAFFITI_BYTES_LEN
+        ));
+    }
+    let mut graffiti = [0; GRAFFITI_BYTES_LEN];
+    graffiti[..bytes.len()].copy_from_slice(bytes);
+    Ok(graffiti)
+}
+
+/// The parsed contents of a graffiti file.
+#[derive(Debug, Default, PartialEq)]
+struct GraffitiFileContents {
+    default: Option<Graffiti>,
+    validators: HashMap<PublicKey, Graffiti>,
+}
+
+impl GraffitiFileContents {
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut parsed = Self::default();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let separator = line
+                .find(':')
+                .ok_or_else(|| format!("Line {} has no ':' separator", i + 1))?;
+            let (key, value) = (line[..separator].trim(), line[separator + 1..].trim());
+            let graffiti =
+                graffiti_from_str(value).map_err(|e| format!("Line {}: {}", i + 1, e))?;
+
+            if key == "default" {
+                parsed.default = Some(graffiti);
+            } else {
+                let pubkey = hex::decode(key.trim_start_matches("0x"))
+                    .map_err(|e| format!("{:?}", e))
+                    .and_then(|bytes| {
+                        PublicKey::from_ssz_bytes(&bytes).map_err(|e| format!("{:?}", e))
+                    })
+                    .map_err(|e| format!("Line {} has an invalid public key: {}", i + 1, e))?;
+                parsed.validators.insert(pubkey, graffiti);
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn graffiti(&self, public_key: &PublicKey) -> Option<Graffiti> {
+        self.validators.get(public_key).cloned().or(self.default)
+    }
+}
+
+/// The most recently read version of a graffiti file.
+#[derive(Default)]
+struct LoadedFile {
+    modified: Option<SystemTime>,
+    contents: GraffitiFileContents,
+}
+
+/// Provides the graffiti of each validator, from the graffiti file if there is one and it has an
+/// entry for the validator, otherwise from the `--graffiti` flag.
+pub struct GraffitiSource {
+    file: Option<(PathBuf, Mutex<LoadedFile>)>,
+    default: Option<Graffiti>,
+}
+
+impl GraffitiSource {
+    pub fn new(file: Option<PathBuf>, default: Option<Graffiti>) -> Self {
+        Self {
+            file: file.map(|path| (path, Mutex::new(LoadedFile::default()))),
+            default,
+        }
+    }
+
+    /// Returns the graffiti for a block proposed by `public_key`, or zeros if none is configured.
+    ///
+    /// If the graffiti file has been modified since it was last read, it is read again. Should it
+    /// be unreadable or invalid, the previously read version continues to be used.
+    pub fn graffiti(&self, public_key: &PublicKey, log: &slog::Logger) -> Graffiti {
+        let from_file = self.file.as_ref().and_then(|(path, loaded)| {
+            let mut loaded = loaded.lock().expect("graffiti file lock poisoned");
+            if let Err(e) = reload_if_modified(path, &mut loaded) {
+                warn!(
+                    log,
+                    "Unable to read graffiti file";
+                    "path" => format!("{:?}", path),
+                    "error" => e
+                );
+            }
+            loaded.contents.graffiti(public_key)
+        });
+
+        from_file
+            .or(self.default)
+            .unwrap_or([0; GRAFFITI_BYTES_LEN])
+    }
+}
+
+fn reload_if_modified(path: &Path, loaded: &mut LoadedFile) -> Result<(), String> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("{:?}", e))?;
+
+    if loaded.modified == Some(modified) {
+        return Ok(());
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| format!("{:?}", e))?;
+    loaded.contents = GraffitiFileContents::parse(&contents)?;
+    loaded.modified = Some(modified);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Keypair;
+
+    #[test]
+    fn pads_graffiti() {
+        let graffiti = graffiti_from_str("lighthouse").unwrap();
+        assert_eq!(&graffiti[..10], b"lighthouse");
+        assert!(graffiti[10..].iter().all(|b| *b == 0));
+
+        assert!(graffiti_from_str(&"a".repeat(32)).is_ok());
+        assert!(graffiti_from_str(&"a".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn validator_entries_override_default() {
+        let with_entry = Keypair::random().pk;
+        let without_entry = Keypair::random().pk;
+
+        let contents = GraffitiFileContents::parse(&format!(
+            "# comment\n\ndefault: everyone\n{}: just me\n",
+            with_entry.as_hex_string()
+        ))
+        .unwrap();
+
+        assert_eq!(
+            contents.graffiti(&with_entry),
+            Some(graffiti_from_str("just me").unwrap())
+        );
+        assert_eq!(
+            contents.graffiti(&without_entry),
+            Some(graffiti_from_str("everyone").unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(GraffitiFileContents::parse("no separator").is_err());
+        assert!(GraffitiFileContents::parse("0x1234: bad key").is_err());
+        assert!(GraffitiFileContents::parse(&format!("default: {}", "a".repeat(33))).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! An HTTP API which lists, imports and removes validators while the client is running.
+//!
+//! Every request must carry the bearer token stored in `Config::api_token_path`, which is
+//! generated when the API is first started.
+use crate::config::Config as ValidatorConfig;
+use crate::keystore::{random_password, Keystore};
+use crate::signer::{Signer, ValidatorSigner};
+use crate::slashing_protection::{Interchange, SlashingDatabase};
+use iron::headers::{Authorization, Bearer, ContentType};
+use iron::prelude::*;
+use iron::typemap::Key;
+use iron::{status::Status, AfterMiddleware, BeforeMiddleware, Listening};
+use persistent::Read;
+use router::Router;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+use slog::{info, warn};
+use std::fs;
+use std::io::Read as IoRead;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use types::PublicKey;
+
+/// The state shared by all key manager requests.
+pub struct KeyManager {
+    /// The validators used by the duties manager.
+    pub signers: Arc<RwLock<Vec<ValidatorSigner>>>,
+    pub slashing_protection: Arc<SlashingDatabase>,
+    pub config: ValidatorConfig,
+    pub log: slog::Logger,
+}
+
+struct KeyManagerKey;
+
+impl Key for KeyManagerKey {
+    type Value = KeyManager;
+}
+
+/// Starts the key manager API on the address in `config`.
+///
+/// The server runs on its own threads until the returned `Listening` is closed.
+pub fn start_server(key_manager: KeyManager) -> Result<Listening, String> {
+    let config = key_manager.config.clone();
+    let log = key_manager.log.clone();
+
+    let token = load_or_create_token(&config.api_token_path())
+        .map_err(|e| format!("Unable to load API token: {:?}", e))?;
+
+    let mut router = Router::new();
+    router.get("/eth/v1/keystores", handle_list, "list_keystores");
+    router.post("/eth/v1/keystores", handle_import, "import_keystores");
+    router.delete("/eth/v1/keystores", handle_delete, "delete_keystores");
+
+    let mut chain = Chain::new(router);
+    chain.link_before(RequireToken { token });
+    chain.link(Read::<KeyManagerKey>::both(key_manager));
+    chain.link_after(SetJsonContentType);
+
+    let listen_address = format!("{}:{}", config.http_listen_address, config.http_listen_port);
+    let listening = Iron::new(chain)
+        .http(listen_address.clone())
+        .map_err(|e| format!("Unable to start key manager API: {:?}", e))?;
+
+    info!(
+        log,
+        "Key manager API running";
+        "address" => listen_address,
+        "token_file" => format!("{:?}", config.api_token_path())
+    );
+
+    Ok(listening)
+}
+
+/// Reads the API token from `path`, generating and saving a new one if the file does not exist.
+fn load_or_create_token(path: &Path) -> std::io::Result<String> {
+    if path.exists() {
+        return Ok(fs::read_to_string(path)?.trim().to_string());
+    }
+
+    let token = random_password();
+    fs::write(path, token.as_bytes())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(token)
+}
+
+/// Compares `a` and `b` in time independent of the position of their first difference.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Rejects any request which does not carry the API token.
+struct RequireToken {
+    token: String,
+}
+
+impl BeforeMiddleware for RequireToken {
+    fn before(&self, req: &mut Request) -> IronResult<()> {
+        let authorized = req
+            .headers
+            .get::<Authorization<Bearer>>()
+            .map_or(false, |auth| tokens_match(&auth.token, &self.token));
+
+        if authorized {
+            Ok(())
+        } else {
+            Err(IronError::new(
+                Unauthorized,
+                (
+                    Status::Unauthorized,
+                    json!({ "error": "Missing or invalid API token" }).to_string(),
+                ),
+            ))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl std::fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Unauthorized")
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+/// Sets the `content-type` headers on _all_ responses, unless they are already set.
+struct SetJsonContentType;
+impl AfterMiddleware for SetJsonContentType {
+    fn after(&self, _req: &mut Request, mut resp: Response) -> IronResult<Response> {
+        if resp.headers.get::<ContentType>() == None {
+            resp.headers.set(ContentType::json());
+        }
+        Ok(resp)
+    }
+}
+
+#[derive(Serialize)]
+struct KeystoreEntry {
+    validating_pubkey: PublicKey,
+}
+
+/// Lists the public key of every validator the client is signing for.
+fn handle_list(req: &mut Request) -> IronResult<Response> {
+    let key_manager = req
+        .get::<Read<KeyManagerKey>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let signers = match key_manager.signers.read() {
+        Ok(signers) => signers,
+        Err(_) => return Ok(server_error("Validator list lock poisoned".to_string())),
+    };
+    let data: Vec<KeystoreEntry> = signers
+        .iter()
+        .map(|signer| KeystoreEntry {
+            validating_pubkey: signer.to_public(),
+        })
+        .collect();
+
+    Ok(Response::with((
+        Status::Ok,
+        json!({ "data": data }).to_string(),
+    )))
+}
+
+/// The body of a `POST /eth/v1/keystores` request.
+#[derive(Deserialize)]
+struct ImportRequest {
+    /// EIP-2335 keystores, each JSON-encoded as a string.
+    keystores: Vec<String>,
+    /// The password of each of `keystores`.
+    passwords: Vec<String>,
+    /// A JSON-encoded EIP-3076 interchange, imported before any keystore.
+    slashing_protection: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ImportStatus {
+    Imported,
+    /// The validator is already in use.
+    Duplicate,
+    Error,
+}
+
+#[derive(Serialize)]
+struct ImportResult {
+    status: ImportStatus,
+    message: String,
+}
+
+impl ImportResult {
+    fn new(status: ImportStatus) -> Self {
+        Self {
+            status,
+            message: String::new(),
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            status: ImportStatus::Error,
+            message,
+        }
+    }
+}
+
+/// Imports keystores, saving them so they are loaded again on restart, and begins signing with
+/// them immediately.
+///
+/// The slashing protection history of the new validators may be supplied alongside. It is
+/// imported first, so that it is in force before any validator signs.
+fn handle_import(req: &mut Request) -> IronResult<Response> {
+    let key_manager = req
+        .get::<Read<KeyManagerKey>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let mut body = String::new();
+    if let Err(e) = req.body.read_to_string(&mut body) {
+        return Ok(bad_request(format!("Unable to read request body: {:?}", e)));
+    }
+
+    let request: ImportRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+
+    if request.keystores.len() != request.passwords.len() {
+        return Ok(bad_request(format!(
+            "Got {} keystores but {} passwords",
+            request.keystores.len(),
+            request.passwords.len()
+        )));
+    }
+
+    if let Some(interchange) = &request.slashing_protection {
+        let interchange: Interchange = match serde_json::from_str(interchange) {
+            Ok(interchange) => interchange,
+            Err(e) => return Ok(bad_request(format!("Invalid slashing protection: {:?}", e))),
+        };
+        if let Err(e) = interchange.check_version() {
+            return Ok(bad_request(e));
+        }
+        if let Err(e) = key_manager
+            .slashing_protection
+            .import_interchange(&interchange)
+        {
+            return Ok(server_error(format!(
+                "Unable to import slashing protection: {:?}",
+                e
+            )));
+        }
+    }
+
+    let data: Vec<ImportResult> = request
+        .keystores
+        .iter()
+        .zip(&request.passwords)
+        .map(|(keystore, password)| import_keystore(&key_manager, keystore, password))
+        .collect();
+
+    Ok(Response::with((
+        Status::Ok,
+        json!({ "data": data }).to_string(),
+    )))
+}
+
+fn import_keystore(key_manager: &KeyManager, keystore: &str, password: &str) -> ImportResult {
+    let keystore: Keystore = match serde_json::from_str(keystore) {
+        Ok(keystore) => keystore,
+        Err(e) => return ImportResult::error(format!("Invalid keystore: {:?}", e)),
+    };
+    let keypair = match keystore.decrypt_keypair(password) {
+        Ok(keypair) => keypair,
+        Err(e) => return ImportResult::error(format!("Unable to decrypt keystore: {:?}", e)),
+    };
+
+    // Hold the lock throughout, so that concurrent imports of one keystore cannot both succeed.
+    let mut signers = match key_manager.signers.write() {
+        Ok(signers) => signers,
+        Err(_) => return ImportResult::error("Validator list lock poisoned".to_string()),
+    };
+
+    if signers
+        .iter()
+        .any(|signer| signer.to_public() == keypair.pk)
+    {
+        return ImportResult::new(ImportStatus::Duplicate);
+    }
+
+    if let Err(e) = key_manager
+        .slashing_protection
+        .register_validators(std::iter::once(&keypair.pk))
+    {
+        return ImportResult::error(format!("Unable to register validator: {:?}", e));
+    }
+
+    if let Err(e) = key_manager
+        .config
+        .save_keystore(&keypair, &keystore, password)
+    {
+        return ImportResult::error(format!("Unable to save keystore: {:?}", e));
+    }
+
+    info!(key_manager.log, "Imported validator"; "pubkey" => keypair.pk.as_hex_string());
+    signers.push(ValidatorSigner::local(keypair));
+
+    ImportResult::new(ImportStatus::Imported)
+}
+
+/// The body of a `DELETE /eth/v1/keystores` request.
+#[derive(Deserialize)]
+struct DeleteRequest {
+    pubkeys: Vec<PublicKey>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DeleteStatus {
+    Deleted,
+    /// The validator is not in use, but has slashing protection history which is exported.
+    NotActive,
+    NotFound,
+    Error,
+}
+
+#[derive(Serialize)]
+struct DeleteResult {
+    status: DeleteStatus,
+    message: String,
+}
+
+/// Stops signing with the given validators and deletes their keystores, returning their slashing
+/// protection history so that they may safely be started elsewhere.
+///
+/// The slashing protection records are kept, so that a validator imported again cannot sign
+/// anything conflicting with its past messages.
+fn handle_delete(req: &mut Request) -> IronResult<Response> {
+    let key_manager = req
+        .get::<Read<KeyManagerKey>>()
+        .map_err(map_persistent_err_to_500)?;
+
+    let mut body = String::new();
+    if let Err(e) = req.body.read_to_string(&mut body) {
+        return Ok(bad_request(format!("Unable to read request body: {:?}", e)));
+    }
+
+    let request: DeleteRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => return Ok(bad_request(format!("Invalid request body: {:?}", e))),
+    };
+
+    // Remove the validators so that no further duties are started for them. Duties which were
+    // already started are refused by slashing protection once the keys are disabled below.
+    let removed: Vec<bool> = {
+        let mut signers = match key_manager.signers.write() {
+            Ok(signers) => signers,
+            Err(_) => return Ok(server_error("Validator list lock poisoned".to_string())),
+        };
+        request
+            .pubkeys
+            .iter()
+            .map(|pubkey| {
+                let len = signers.len();
+                signers.retain(|signer| signer.to_public() != *pubkey);
+                signers.len() < len
+            })
+            .collect()
+    };
+
+    // Anything the keys may still sign was checked before they were disabled, so is recorded in
+    // the export.
+    let interchange = match key_manager
+        .slashing_protection
+        .disable_and_export(&request.pubkeys)
+    {
+        Ok(interchange) => interchange,
+        Err(e) => {
+            return Ok(server_error(format!(
+                "Unable to export slashing protection: {:?}",
+                e
+            )))
+        }
+    };
+
+    let data: Vec<DeleteResult> = request
+        .pubkeys
+        .iter()
+        .zip(removed)
+        .map(|(pubkey, removed)| {
+            let has_history = interchange.data.iter().any(|r| r.pubkey == *pubkey);
+
+            if let Err(e) = key_manager.config.delete_key(pubkey) {
+                warn!(
+                    key_manager.log,
+                    "Unable to delete keystore";
+                    "pubkey" => pubkey.as_hex_string(),
+                    "error" => format!("{:?}", e)
+                );
+                return DeleteResult {
+                    status: DeleteStatus::Error,
+                    message: format!("Unable to delete keystore: {:?}", e),
+                };
+            }
+
+            let status = if removed {
+                info!(key_manager.log, "Removed validator"; "pubkey" => pubkey.as_hex_string());
+                DeleteStatus::Deleted
+            } else if has_history {
+                DeleteStatus::NotActive
+            } else {
+                DeleteStatus::NotFound
+            };
+
+            DeleteResult {
+                status,
+                message: String::new(),
+            }
+        })
+        .collect();
+
+    let slashing_protection = match serde_json::to_string(&interchange) {
+        Ok(json) => json,
+        Err(e) => {
+            return Ok(server_error(format!(
+                "Unable to serialize slashing protection: {:?}",
+                e
+            )))
+        }
+    };
+
+    Ok(Response::with((
+        Status::Ok,
+        json!({ "data": data, "slashing_protection": slashing_protection }).to_string(),
+    )))
+}
+
+/// Helper function for mapping a failure to read state to a 500 server error.
+fn map_persistent_err_to_500(e: persistent::PersistentError) -> IronError {
+    IronError {
+        error: Box::new(e),
+        response: Response::with(Status::InternalServerError),
+    }
+}
+
+/// Builds a `400 Bad Request` response with a JSON error message.
+fn bad_request(message: String) -> Response {
+    Response::with((Status::BadRequest, json!({ "error": message }).to_string()))
+}
+
+/// Builds a `500 Internal Server Error` response with a JSON error message.
+fn server_error(message: String) -> Response {
+    Response::with((
+        Status::InternalServerError,
+        json!({ "error": message }).to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_tokens() {
+        assert!(tokens_match("abcd", "abcd"));
+        assert!(!tokens_match("abcd", "abce"));
+        assert!(!tokens_match("abcd", "abc"));
+        assert!(!tokens_match("", "abcd"));
+    }
+
+    #[test]
+    fn statuses_serialize_as_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&ImportStatus::Duplicate).unwrap(),
+            "\"duplicate\""
+        );
+        assert_eq!(
+            serde_json::to_string(&DeleteStatus::NotActive).unwrap(),
+            "\"not_active\""
+        );
+    }
+}
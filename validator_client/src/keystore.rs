@@ -0,0 +1,660 @@
+//! Password-encrypted validator keys, stored as EIP-2335 JSON keystores.
+//!
+//! The secret key is encrypted with AES-128-CTR under a key derived from the password by either
+//! scrypt or PBKDF2. A SHA-256 checksum over the derived key and ciphertext detects an incorrect
+//! password before any decryption is attempted.
+use aes_ctr::stream_cipher::generic_array::GenericArray;
+use aes_ctr::stream_cipher::{NewStreamCipher, SyncStreamCipher};
+use aes_ctr::Aes128Ctr;
+use bls::{Keypair, PublicKey, SecretKey};
+use hmac::Hmac;
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ssz::Encode;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// The keystore version defined by EIP-2335.
+pub const KEYSTORE_VERSION: u32 = 4;
+
+/// The length of the key derived from the password.
+const DKLEN: u32 = 32;
+/// The length of a secret key within a keystore. Shorter than `BLS_SECRET_KEY_BYTE_SIZE`, which
+/// includes leading zero padding.
+const SECRET_KEY_LEN: usize = 32;
+
+/// The default scrypt cost parameter, as recommended by EIP-2335.
+const SCRYPT_N: u32 = 262_144;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+/// The default PBKDF2 iteration count, as recommended by EIP-2335.
+const PBKDF2_C: u32 = 262_144;
+
+/// The greatest scrypt `n * r` accepted, so that a keystore cannot demand more than 1 GiB of
+/// memory (`128 * n * r` bytes). This is four times the recommended parameters.
+const MAX_SCRYPT_NR: u64 = 1 << 23;
+/// The greatest scrypt parallelization parameter accepted.
+const MAX_SCRYPT_P: u32 = 16;
+/// The greatest PBKDF2 iteration count accepted, sixteen times the recommended count.
+const MAX_PBKDF2_C: u32 = 1 << 22;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The checksum did not match; the password is most likely incorrect.
+    InvalidPassword,
+    /// The decrypted bytes are not a valid secret key.
+    InvalidSecretKey(String),
+    /// The decrypted secret key does not belong to the keystore's public key.
+    PublicKeyMismatch,
+    InvalidKdfParams(String),
+    InvalidCipherParams(String),
+    Io(String),
+    Json(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(format!("{:?}", e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(format!("{:?}", e))
+    }
+}
+
+/// A password-derived key function, with its parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", content = "params")]
+pub enum Kdf {
+    #[serde(rename = "scrypt")]
+    Scrypt(Scrypt),
+    #[serde(rename = "pbkdf2")]
+    Pbkdf2(Pbkdf2),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scrypt {
+    pub dklen: u32,
+    /// The CPU/memory cost. Must be a power of two.
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+    #[serde(with = "hex_bytes")]
+    pub salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pbkdf2 {
+    pub dklen: u32,
+    /// The number of iterations.
+    pub c: u32,
+    pub prf: Prf,
+    #[serde(with = "hex_bytes")]
+    pub salt: Vec<u8>,
+}
+
+/// The pseudo-random function used by PBKDF2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Prf {
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+}
+
+impl Kdf {
+    /// Scrypt with the EIP-2335 recommended parameters and a random salt.
+    pub fn scrypt() -> Self {
+        Kdf::Scrypt(Scrypt {
+            dklen: DKLEN,
+            n: SCRYPT_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: rand::random::<[u8; 32]>().to_vec(),
+        })
+    }
+
+    /// PBKDF2 with the EIP-2335 recommended parameters and a random salt.
+    pub fn pbkdf2() -> Self {
+        Kdf::Pbkdf2(Pbkdf2 {
+            dklen: DKLEN,
+            c: PBKDF2_C,
+            prf: Prf::HmacSha256,
+            salt: rand::random::<[u8; 32]>().to_vec(),
+        })
+    }
+
+    /// Derives the decryption key from `password`, which must already be normalized.
+    fn derive_key(&self, password: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Kdf::Scrypt(params) => {
+                if params.dklen != DKLEN {
+                    return Err(Error::InvalidKdfParams(format!("dklen {}", params.dklen)));
+                }
+                if params.n < 2 || !params.n.is_power_of_two() {
+                    return Err(Error::InvalidKdfParams(format!("n {}", params.n)));
+                }
+                if params.r == 0 || u64::from(params.n) * u64::from(params.r) > MAX_SCRYPT_NR {
+                    return Err(Error::InvalidKdfParams(format!(
+                        "n {} and r {}",
+                        params.n, params.r
+                    )));
+                }
+                if params.p == 0 || params.p > MAX_SCRYPT_P {
+                    return Err(Error::InvalidKdfParams(format!("p {}", params.p)));
+                }
+                let log_n = params.n.trailing_zeros() as u8;
+                let scrypt_params = scrypt::ScryptParams::new(log_n, params.r, params.p)
+                    .map_err(|e| Error::InvalidKdfParams(format!("{:?}", e)))?;
+
+                let mut key = vec![0; DKLEN as usize];
+                scrypt::scrypt(password, &params.salt, &scrypt_params, &mut key)
+                    .map_err(|e| Error::InvalidKdfParams(format!("{:?}", e)))?;
+                Ok(key)
+            }
+            Kdf::Pbkdf2(params) => {
+                if params.dklen != DKLEN {
+                    return Err(Error::InvalidKdfParams(format!("dklen {}", params.dklen)));
+                }
+                if params.c == 0 || params.c > MAX_PBKDF2_C {
+                    return Err(Error::InvalidKdfParams(format!("c {}", params.c)));
+                }
+
+                let mut key = vec![0; DKLEN as usize];
+                pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &params.salt, params.c as usize, &mut key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct KdfModule {
+    #[serde(flatten)]
+    kdf: Kdf,
+    message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ChecksumFunction {
+    #[serde(rename = "sha256")]
+    Sha256,
+}
+
+/// The `params` of a module which takes none, serialized as `{}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct EmptyParams {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChecksumModule {
+    function: ChecksumFunction,
+    params: EmptyParams,
+    #[serde(with = "hex_bytes")]
+    message: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum CipherFunction {
+    #[serde(rename = "aes-128-ctr")]
+    Aes128Ctr,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CipherParams {
+    #[serde(with = "hex_bytes")]
+    iv: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CipherModule {
+    function: CipherFunction,
+    params: CipherParams,
+    #[serde(with = "hex_bytes")]
+    message: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Crypto {
+    kdf: KdfModule,
+    checksum: ChecksumModule,
+    cipher: CipherModule,
+}
+
+/// An EIP-2335 keystore, holding a single encrypted secret key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    crypto: Crypto,
+    #[serde(default)]
+    description: String,
+    #[serde(with = "hex_bytes")]
+    pubkey: Vec<u8>,
+    /// The EIP-2334 derivation path of the key, or empty if it was not derived.
+    path: String,
+    uuid: Uuid,
+    version: u32,
+}
+
+impl Keystore {
+    /// Encrypts the secret key of `keypair` with `password`, using `kdf` to derive the encryption
+    /// key.
+    pub fn encrypt(keypair: &Keypair, password: &str, kdf: Kdf) -> Result<Self, Error> {
+        let derived_key = Zeroizing::new(kdf.derive_key(&normalize_password(password))?);
+        let iv = rand::random::<[u8; 16]>().to_vec();
+
+        // The message holds the secret key until it is encrypted in place.
+        let secret_key = keypair.sk.as_bytes();
+        let mut message = secret_key[secret_key.len() - SECRET_KEY_LEN..].to_vec();
+        apply_cipher(&derived_key, &iv, &mut message)?;
+
+        Ok(Keystore {
+            crypto: Crypto {
+                kdf: KdfModule {
+                    kdf,
+                    message: String::new(),
+                },
+                checksum: ChecksumModule {
+                    function: ChecksumFunction::Sha256,
+                    params: EmptyParams {},
+                    message: checksum(&derived_key, &message),
+                },
+                cipher: CipherModule {
+                    function: CipherFunction::Aes128Ctr,
+                    params: CipherParams { iv },
+                    message,
+                },
+            },
+            description: String::new(),
+            pubkey: keypair.pk.as_ssz_bytes(),
+            path: String::new(),
+            uuid: Uuid::new_v4(),
+            version: KEYSTORE_VERSION,
+        })
+    }
+
+    /// Decrypts the keystore with `password`, returning the keypair it holds.
+    pub fn decrypt_keypair(&self, password: &str) -> Result<Keypair, Error> {
+        let crypto = &self.crypto;
+        let derived_key = Zeroizing::new(crypto.kdf.kdf.derive_key(&normalize_password(password))?);
+
+        if checksum(&derived_key, &crypto.cipher.message) != crypto.checksum.message {
+            return Err(Error::InvalidPassword);
+        }
+
+        let mut plaintext = Zeroizing::new(crypto.cipher.message.clone());
+        apply_cipher(&derived_key, &crypto.cipher.params.iv, &mut plaintext)?;
+        if plaintext.len() != SECRET_KEY_LEN {
+            return Err(Error::InvalidSecretKey(format!(
+                "{} bytes, expected {}",
+                plaintext.len(),
+                SECRET_KEY_LEN
+            )));
+        }
+
+        let mut secret_key =
+            Zeroizing::new(vec![0; bls::BLS_SECRET_KEY_BYTE_SIZE - SECRET_KEY_LEN]);
+        secret_key.extend_from_slice(&plaintext);
+        let sk = SecretKey::from_bytes(&secret_key)
+            .map_err(|e| Error::InvalidSecretKey(format!("{:?}", e)))?;
+        let pk = PublicKey::from_secret_key(&sk);
+
+        if pk.as_ssz_bytes() != self.pubkey {
+            return Err(Error::PublicKeyMismatch);
+        }
+
+        Ok(Keypair { sk, pk })
+    }
+
+    /// The public key of the encrypted secret key, as `0x`-prefixed hex.
+    pub fn pubkey_hex(&self) -> String {
+        serde_hex::encode(&self.pubkey)
+    }
+
+    pub fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    pub fn from_json_file(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Writes the keystore to `path`, readable only by its owner.
+    pub fn to_json_file(&self, path: &Path) -> Result<(), Error> {
+        let file = create_private_file(path)?;
+        Ok(serde_json::to_writer_pretty(file, self)?)
+    }
+}
+
+/// Creates or truncates the file at `path`, making it readable and writable only by its owner.
+pub fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let file = options.open(path)?;
+
+    // The mode only applies when the file is created.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(file)
+}
+
+/// Creates the directory at `path`, and any missing parents, making it accessible only by its
+/// owner.
+pub fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(())
+}
+
+/// Returns a new random password, suitable for a password file.
+pub fn random_password() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Normalizes `password` to NFKD and strips control codes, as required by EIP-2335.
+///
+/// Stripping control codes also removes any trailing newline read from a password file.
+fn normalize_password(password: &str) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(
+        password
+            .nfkd()
+            .filter(|c| !c.is_control())
+            .collect::<String>()
+            .into_bytes(),
+    )
+}
+
+/// The checksum of the ciphertext `message` under `derived_key`.
+fn checksum(derived_key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut preimage = derived_key[16..32].to_vec();
+    preimage.extend_from_slice(message);
+    Sha256::digest(&preimage).to_vec()
+}
+
+/// Encrypts or decrypts `data` in place with AES-128-CTR.
+fn apply_cipher(derived_key: &[u8], iv: &[u8], data: &mut [u8]) -> Result<(), Error> {
+    if iv.len() != 16 {
+        return Err(Error::InvalidCipherParams(format!(
+            "iv of {} bytes",
+            iv.len()
+        )));
+    }
+    let mut cipher = Aes128Ctr::new(
+        GenericArray::from_slice(&derived_key[0..16]),
+        GenericArray::from_slice(iv),
+    );
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+/// Serializes bytes as hex without a `0x` prefix, as used throughout EIP-2335.
+mod hex_bytes {
+    use serde::{Deserializer, Serializer};
+    use serde_hex::HexVisitor;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_str(HexVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scrypt parameters which are cheap enough for tests.
+    fn cheap_scrypt() -> Kdf {
+        Kdf::Scrypt(Scrypt {
+            dklen: DKLEN,
+            n: 16,
+            r: 8,
+            p: 1,
+            salt: vec![42; 32],
+        })
+    }
+
+    /// PBKDF2 parameters which are cheap enough for tests.
+    fn cheap_pbkdf2() -> Kdf {
+        Kdf::Pbkdf2(Pbkdf2 {
+            dklen: DKLEN,
+            c: 16,
+            prf: Prf::HmacSha256,
+            salt: vec![42; 32],
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        let keypair = Keypair::random();
+
+        for kdf in vec![cheap_scrypt(), cheap_pbkdf2()] {
+            let keystore = Keystore::encrypt(&keypair, "password", kdf).unwrap();
+            let json = serde_json::to_string(&keystore).unwrap();
+            let decoded: Keystore = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(decoded, keystore);
+            assert_eq!(decoded.decrypt_keypair("password").unwrap(), keypair);
+        }
+    }
+
+    #[test]
+    fn wrong_password() {
+        let keystore = Keystore::encrypt(&Keypair::random(), "password", cheap_scrypt()).unwrap();
+
+        assert_eq!(
+            keystore.decrypt_keypair("passw0rd"),
+            Err(Error::InvalidPassword)
+        );
+    }
+
+    #[test]
+    fn wrong_pubkey() {
+        let mut keystore =
+            Keystore::encrypt(&Keypair::random(), "password", cheap_scrypt()).unwrap();
+        keystore.pubkey = Keypair::random().pk.as_ssz_bytes();
+
+        assert_eq!(
+            keystore.decrypt_keypair("password"),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn password_is_normalized() {
+        let keypair = Keypair::random();
+        let keystore = Keystore::encrypt(&keypair, "pass\u{7f}word\n", cheap_scrypt()).unwrap();
+
+        assert_eq!(keystore.decrypt_keypair("password").unwrap(), keypair);
+        assert_eq!(*normalize_password("\u{2168}"), "IX".as_bytes().to_vec());
+    }
+
+    #[test]
+    fn rejects_invalid_scrypt_cost() {
+        let kdf = Kdf::Scrypt(Scrypt {
+            dklen: DKLEN,
+            n: 15,
+            r: 8,
+            p: 1,
+            salt: vec![],
+        });
+
+        assert!(Keystore::encrypt(&Keypair::random(), "password", kdf).is_err());
+    }
+
+    #[test]
+    fn rejects_excessive_kdf_costs() {
+        let scrypt = |n, r, p| {
+            Kdf::Scrypt(Scrypt {
+                dklen: DKLEN,
+                n,
+                r,
+                p,
+                salt: vec![],
+            })
+        };
+        let pbkdf2 = |c| {
+            Kdf::Pbkdf2(Pbkdf2 {
+                dklen: DKLEN,
+                c,
+                prf: Prf::HmacSha256,
+                salt: vec![],
+            })
+        };
+
+        for kdf in vec![
+            scrypt(1 << 24, 8, 1),
+            scrypt(1 << 20, 16, 1),
+            scrypt(16, 0, 1),
+            scrypt(16, 8, 0),
+            scrypt(16, 8, MAX_SCRYPT_P + 1),
+            pbkdf2(0),
+            pbkdf2(MAX_PBKDF2_C + 1),
+        ] {
+            match kdf.derive_key(b"password") {
+                Err(Error::InvalidKdfParams(_)) => {}
+                result => panic!("{:?} gave {:?}", kdf, result),
+            }
+        }
+    }
+
+    /// The password of the EIP-2335 test vectors.
+    const TEST_VECTOR_PASSWORD: &str = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+    /// The secret key of the EIP-2335 test vectors.
+    const TEST_VECTOR_SECRET: &str =
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+    const TEST_VECTOR_PUBKEY: &str = "0x9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07";
+
+    const SCRYPT_TEST_VECTOR: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "scrypt",
+                "params": {
+                    "dklen": 32,
+                    "n": 262144,
+                    "p": 1,
+                    "r": 8,
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "d2217fe5f3e9a1e34581ef8a78f7c9928e436d36dacc5e846690a5581e8ea484"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "06ae90d55fe0a6e9c5c3bc5b170827b2e5cce3929ed3f116c2811e6366dfe20f"
+            }
+        },
+        "description": "This is a test keystore that uses scrypt to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/3141592653/589793238",
+        "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+        "version": 4
+    }"#;
+
+    const PBKDF2_TEST_VECTOR: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    fn decrypts_test_vector(json: &str) {
+        let keystore: Keystore = serde_json::from_str(json).unwrap();
+
+        let keypair = keystore.decrypt_keypair(TEST_VECTOR_PASSWORD).unwrap();
+
+        let secret_key = keypair.sk.as_bytes();
+        assert_eq!(
+            hex::encode(&secret_key[secret_key.len() - SECRET_KEY_LEN..]),
+            TEST_VECTOR_SECRET
+        );
+        assert_eq!(keystore.pubkey_hex(), TEST_VECTOR_PUBKEY);
+        assert_eq!(
+            keystore.decrypt_keypair("testpassword"),
+            Err(Error::InvalidPassword)
+        );
+    }
+
+    #[test]
+    fn decrypts_scrypt_test_vector() {
+        decrypts_test_vector(SCRYPT_TEST_VECTOR);
+    }
+
+    #[test]
+    fn decrypts_pbkdf2_test_vector() {
+        decrypts_test_vector(PBKDF2_TEST_VECTOR);
+    }
+
+    #[test]
+    fn keystore_file_is_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = dir.path().join("secrets");
+        let path = secrets.join("keystore.json");
+        let keystore = Keystore::encrypt(&Keypair::random(), "password", cheap_pbkdf2()).unwrap();
+
+        create_private_dir(&secrets).unwrap();
+        keystore.to_json_file(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&secrets), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+        assert_eq!(Keystore::from_json_file(&path).unwrap(), keystore);
+    }
+}
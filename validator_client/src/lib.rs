@@ -1,3 +1,5 @@
 pub mod config;
+pub mod graffiti;
+pub mod keystore;
 
 pub use crate::config::Config;
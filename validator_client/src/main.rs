@@ -1,19 +1,34 @@
 mod attestation_producer;
 mod block_producer;
+mod clock_skew;
 mod config;
+mod doppelganger;
 mod duties;
+mod duty_timing;
 pub mod error;
+mod graffiti;
+mod http_api;
+mod keystore;
+mod remote_signer;
 mod service;
 mod signer;
+mod slashing_protection;
+mod standby;
 
 use crate::config::Config as ValidatorClientConfig;
 use crate::service::Service as ValidatorService;
-use clap::{App, Arg};
-use eth2_config::{get_data_dir, read_from_file, write_to_file, Eth2Config};
+use crate::signer::ValidatorSigner;
+use crate::slashing_protection::{Interchange, SlashingDatabase, SLASHING_PROTECTION_FILENAME};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use eth2_config::{
+    config_file_args, get_data_dir, get_matches_with_config_file, read_from_file,
+    write_effective_config, write_to_file, Eth2Config, DUMP_CONFIG_FLAG,
+};
 use protos::services_grpc::ValidatorServiceClient;
 use slog::{crit, error, info, o, Drain};
-use std::path::PathBuf;
-use types::{Keypair, MainnetEthSpec, MinimalEthSpec};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use types::{MainnetEthSpec, MinimalEthSpec};
 
 pub const DEFAULT_SPEC: &str = "minimal";
 pub const DEFAULT_DATA_DIR: &str = ".lighthouse-validator";
@@ -28,7 +43,7 @@ fn main() {
     let log = slog::Logger::root(drain, o!());
 
     // CLI
-    let matches = App::new("Lighthouse Validator Client")
+    let app = App::new("Lighthouse Validator Client")
         .version("0.0.1")
         .author("Sigma Prime <contact@sigmaprime.io>")
         .about("Eth 2.0 Validator Client")
@@ -54,6 +69,116 @@ fn main() {
                 .help("Address to connect to BeaconNode.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("remote-signer")
+                .long("remote-signer")
+                .value_name("URL")
+                .help("Sign with the keys held by the signing service at URL, rather than local keystores.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("doppelganger-epochs")
+                .long("doppelganger-epochs")
+                .value_name("EPOCHS")
+                .help("Watch the network for this many epochs before signing, exiting if any of our validators are seen. Protects against running the same keys twice.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("standby")
+                .long("standby")
+                .value_name("EPOCHS")
+                .help("Run as a standby for another validator client with the same keys, only signing once none of our validators have been seen on the network for this many epochs.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("standby-interchange")
+                .long("standby-interchange")
+                .value_name("FILE")
+                .help("A slashing protection interchange exported by the primary, imported before the standby begins signing.")
+                .requires("standby")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verify-duties")
+                .long("verify-duties")
+                .help("Request a Merkle proof with each validator's duties and reject them unless it is valid against the beacon node's state root.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("http")
+                .long("http")
+                .help("Serve the key manager API, which adds and removes validators at runtime.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("http-address")
+                .long("http-address")
+                .value_name("HTTPADDRESS")
+                .help("Listen address for the key manager API.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("http-port")
+                .long("http-port")
+                .value_name("HTTPPORT")
+                .help("Listen port for the key manager API.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("graffiti")
+                .long("graffiti")
+                .value_name("GRAFFITI")
+                .help("The graffiti of proposed blocks, at most 32 bytes. Overridden by any entry in the graffiti file.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("graffiti-file")
+                .long("graffiti-file")
+                .value_name("FILE")
+                .help("A file of `<pubkey>: <graffiti>` lines, and optionally a `default: <graffiti>` line, which is re-read whenever it changes.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("block-delay")
+                .long("block-delay")
+                .value_name("MILLISECONDS")
+                .help("Time after the start of each slot to wait before fetching duties and producing blocks.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("attestation-delay")
+                .long("attestation-delay")
+                .value_name("MILLISECONDS")
+                .help("Time after the start of a slot to wait before producing and signing its attestations.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("aggregate-publication-offset")
+                .long("aggregate-publication-offset")
+                .value_name("MILLISECONDS")
+                .help("Time after the start of a slot at which its signed attestations are published to the beacon node for aggregation.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-clock-skew")
+                .long("max-clock-skew")
+                .value_name("MILLISECONDS")
+                .help("The largest offset of the beacon node's (or NTP server's) clock from ours which is not warned of as skew.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("refuse-on-clock-skew")
+                .long("refuse-on-clock-skew")
+                .help("Refuse to sign while our clock is skewed, rather than only warning.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("ntp-server")
+                .long("ntp-server")
+                .value_name("HOST[:PORT]")
+                .help("An NTP server to compare our clock with, as well as the beacon node.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("spec-constants")
                 .long("spec-constants")
@@ -64,7 +189,44 @@ fn main() {
                 .possible_values(&["mainnet", "minimal"])
                 .default_value("minimal"),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("slashing-protection")
+                .about("Manages the record of signed blocks and attestations.")
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("Imports an EIP-3076 interchange file into the database.")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("The interchange JSON file to import.")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Exports the database as an EIP-3076 interchange file.")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("The path to write the interchange JSON file.")
+                                .required(true),
+                        ),
+                ),
+        )
+        .args(&config_file_args());
+
+    let matches = match get_matches_with_config_file(app, std::env::args_os()) {
+        Ok(matches) => matches,
+        Err(e) => {
+            crit!(log, "Failed to load config file"; "error" => e);
+            return;
+        }
+    };
+
+    if let Some(path) = matches.value_of(DUMP_CONFIG_FLAG) {
+        if let Err(e) = write_effective_config(&matches, Path::new(path)) {
+            crit!(log, "Failed to dump config"; "error" => e);
+            return;
+        }
+    }
 
     let data_dir = match get_data_dir(&matches, PathBuf::from(DEFAULT_DATA_DIR)) {
         Ok(dir) => dir,
@@ -74,6 +236,17 @@ fn main() {
         }
     };
 
+    if let Some(matches) = matches.subcommand_matches("slashing-protection") {
+        let db_path = data_dir.join(SLASHING_PROTECTION_FILENAME);
+        match run_slashing_protection_command(matches, &db_path) {
+            Ok(()) => {
+                info!(log, "Slashing protection command complete"; "database" => db_path.to_str())
+            }
+            Err(e) => crit!(log, "Slashing protection command failed"; "error" => e),
+        }
+        return;
+    }
+
     let client_config_path = data_dir.join(CLIENT_CONFIG_FILENAME);
 
     // Attempt to lead the `ClientConfig` from disk.
@@ -154,16 +327,12 @@ fn main() {
     );
 
     let result = match eth2_config.spec_constants.as_str() {
-        "mainnet" => ValidatorService::<ValidatorServiceClient, Keypair>::start::<MainnetEthSpec>(
-            client_config,
-            eth2_config,
-            log.clone(),
-        ),
-        "minimal" => ValidatorService::<ValidatorServiceClient, Keypair>::start::<MinimalEthSpec>(
-            client_config,
-            eth2_config,
-            log.clone(),
-        ),
+        "mainnet" => ValidatorService::<ValidatorServiceClient, ValidatorSigner>::start::<
+            MainnetEthSpec,
+        >(client_config, eth2_config, log.clone()),
+        "minimal" => ValidatorService::<ValidatorServiceClient, ValidatorSigner>::start::<
+            MinimalEthSpec,
+        >(client_config, eth2_config, log.clone()),
         other => {
             crit!(log, "Unknown spec constants"; "title" => other);
             return;
@@ -177,3 +346,29 @@ fn main() {
         Err(e) => crit!(log, "Validator client exited with error"; "error" => e.to_string()),
     }
 }
+
+/// Runs an `import` or `export` of the slashing protection database at `db_path`.
+fn run_slashing_protection_command(matches: &ArgMatches, db_path: &Path) -> Result<(), String> {
+    let db = SlashingDatabase::open_or_create(db_path)
+        .map_err(|e| format!("Unable to open database: {:?}", e))?;
+
+    match matches.subcommand() {
+        ("import", Some(matches)) => {
+            let path = matches.value_of("FILE").expect("FILE is required");
+            let interchange = Interchange::from_file(Path::new(path))?;
+            db.import_interchange(&interchange)
+                .map_err(|e| format!("Unable to import: {:?}", e))
+        }
+        ("export", Some(matches)) => {
+            let path = matches.value_of("FILE").expect("FILE is required");
+            let interchange = db
+                .export_interchange()
+                .map_err(|e| format!("Unable to export: {:?}", e))?;
+            let file =
+                File::create(path).map_err(|e| format!("Unable to create {}: {:?}", path, e))?;
+            serde_json::to_writer_pretty(file, &interchange)
+                .map_err(|e| format!("Unable to write {}: {:?}", path, e))
+        }
+        _ => Err("Expected an import or export subcommand".to_string()),
+    }
+}
@@ -0,0 +1,171 @@
+//! A `SignerBackend` which sends each signing request over HTTP to a separate signing service,
+//! so that secret keys need never be held by the validator client.
+//!
+//! The service must provide two endpoints:
+//!
+//! - `GET /keys`: returns a JSON array of the public keys it can sign for.
+//! - `POST /sign`: accepts a `SigningRequest` and returns a `SigningResponse`.
+use crate::signer::{SignableMessage, SignerBackend};
+use serde_derive::{Deserialize, Serialize};
+use std::time::Duration;
+use types::{Hash256, PublicKey, Signature};
+
+/// How long to wait for the signing service to respond.
+///
+/// Signing is on the critical path of block and attestation production, so a slow signer is
+/// treated as a failed one.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The body of a `POST /sign` request.
+///
+/// Besides the signing root, the request describes the message being signed: its `type`, its
+/// slot or epoch and the block or attestation data. A signing service may use these to refuse
+/// slashable messages.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SigningRequest {
+    pub pubkey: PublicKey,
+    pub signing_root: Hash256,
+    pub domain: u64,
+    #[serde(flatten)]
+    pub message: SignableMessage,
+}
+
+/// The body of a `POST /sign` response.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SigningResponse {
+    pub signature: Signature,
+}
+
+/// A client for a remote signing service.
+pub struct RemoteSigner {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl RemoteSigner {
+    /// Creates a client for the signing service at `url`, e.g., `http://localhost:9000`.
+    pub fn new(url: &str) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Unable to build HTTP client: {:?}", e))?;
+
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client,
+        })
+    }
+
+    /// Returns the public keys which the signing service holds secret keys for.
+    pub fn public_keys(&self) -> Result<Vec<PublicKey>, String> {
+        self.client
+            .get(&format!("{}/keys", self.url))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json())
+            .map_err(|e| format!("Unable to fetch public keys: {:?}", e))
+    }
+}
+
+impl SignerBackend for RemoteSigner {
+    fn sign(
+        &self,
+        public_key: &PublicKey,
+        message: &SignableMessage,
+        domain: u64,
+    ) -> Result<Signature, String> {
+        let signing_root = message.signing_root();
+        let request = SigningRequest {
+            pubkey: public_key.clone(),
+            signing_root: Hash256::from_slice(&signing_root),
+            domain,
+            message: message.clone(),
+        };
+
+        let response: SigningResponse = self
+            .client
+            .post(&format!("{}/sign", self.url))
+            .json(&request)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|mut response| response.json())
+            .map_err(|e| format!("Remote signing failed: {:?}", e))?;
+
+        // A signature for any other message or key is useless, and would only be rejected by the
+        // beacon node.
+        if !response.signature.verify(&signing_root, domain, public_key) {
+            return Err("Remote signer returned an invalid signature".to_string());
+        }
+
+        Ok(response.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{AttestationData, BeaconBlock, Epoch, Keypair, Slot};
+
+    fn request(message: SignableMessage) -> SigningRequest {
+        SigningRequest {
+            pubkey: Keypair::random().pk,
+            signing_root: Hash256::from_slice(&message.signing_root()),
+            domain: 7,
+            message,
+        }
+    }
+
+    #[test]
+    fn signing_request_json() {
+        let request = request(SignableMessage::RandaoReveal {
+            epoch: Epoch::new(3),
+        });
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["pubkey"], request.pubkey.as_hex_string());
+        assert_eq!(json["domain"], 7);
+        assert_eq!(json["type"], "randao_reveal");
+        assert_eq!(json["epoch"], 3);
+
+        let decoded: SigningRequest = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn signing_request_describes_message() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let block = BeaconBlock::random_for_test(&mut rng);
+        let attestation = AttestationData::random_for_test(&mut rng);
+
+        let requests = vec![
+            request(SignableMessage::BeaconBlock {
+                slot: block.slot,
+                block: block.clone(),
+            }),
+            request(SignableMessage::Attestation {
+                slot: Slot::new(9),
+                attestation: attestation.clone(),
+            }),
+        ];
+        let json: Vec<_> = requests
+            .iter()
+            .map(|request| serde_json::to_value(request).unwrap())
+            .collect();
+
+        assert_eq!(json[0]["type"], "beacon_block");
+        assert_eq!(json[0]["slot"], block.slot.as_u64());
+        assert_eq!(json[0]["block"], serde_json::to_value(&block).unwrap());
+        assert_eq!(json[1]["type"], "attestation");
+        assert_eq!(json[1]["slot"], 9);
+        assert_eq!(
+            json[1]["attestation"],
+            serde_json::to_value(&attestation).unwrap()
+        );
+
+        for (json, request) in json.into_iter().zip(requests) {
+            let decoded: SigningRequest = serde_json::from_value(json).unwrap();
+            assert_eq!(decoded, request);
+        }
+    }
+}
@@ -10,21 +10,30 @@
 /// node.
 use crate::attestation_producer::AttestationProducer;
 use crate::block_producer::{BeaconBlockGrpcClient, BlockProducer};
+use crate::clock_skew::{ClockSkewMonitor, ClockSkewStatus};
 use crate::config::Config as ValidatorConfig;
-use crate::duties::{BeaconNodeDuties, DutiesManager, EpochDutiesMap};
+use crate::doppelganger::{BeaconNodeLiveness, DoppelgangerProtection, DoppelgangerStatus};
+use crate::duties::{BeaconNodeDuties, DutiesManager, DutiesVerifier, EpochDutiesMap};
+use crate::duty_timing::{DutyTiming, DutyWaits};
 use crate::error as error_chain;
 use crate::error::ErrorKind;
-use crate::signer::Signer;
-use bls::Keypair;
+use crate::graffiti::{graffiti_from_str, GraffitiSource};
+use crate::http_api::{self, KeyManager};
+use crate::remote_signer::RemoteSigner;
+use crate::signer::{Signer, SignerBackend, ValidatorSigner};
+use crate::slashing_protection::{Interchange, SlashingDatabase, SLASHING_PROTECTION_FILENAME};
+use crate::standby::{StandbyMonitor, StandbyStatus};
 use eth2_config::Eth2Config;
 use grpcio::{ChannelBuilder, EnvBuilder};
+use iron::Listening;
 use protos::services::Empty;
 use protos::services_grpc::{
     AttestationServiceClient, BeaconBlockServiceClient, BeaconNodeServiceClient,
     ValidatorServiceClient,
 };
-use slog::{error, info, warn};
+use slog::{crit, error, info, warn};
 use slot_clock::{SlotClock, SystemTimeSlotClock};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
 use std::time::{Duration, Instant, SystemTime};
@@ -32,20 +41,20 @@ use tokio::prelude::*;
 use tokio::runtime::Builder;
 use tokio::timer::Interval;
 use tokio_timer::clock::Clock;
-use types::{ChainSpec, Epoch, EthSpec, Fork, Slot};
-
-/// A fixed amount of time after a slot to perform operations. This gives the node time to complete
-/// per-slot processes.
-const TIME_DELAY_FROM_SLOT: Duration = Duration::from_millis(200);
+use types::{ChainSpec, Epoch, EthSpec, Fork, PublicKey, Slot};
 
 /// The validator service. This is the main thread that executes and maintains validator
 /// duties.
 //TODO: Generalize the BeaconNode types to use testing
-pub struct Service<B: BeaconNodeDuties + 'static, S: Signer + 'static> {
+pub struct Service<
+    B: BeaconNodeDuties + 'static,
+    S: Signer + 'static,
+    C: SlotClock + 'static = SystemTimeSlotClock,
+> {
     /// The node's current fork version we are processing on.
     fork: Fork,
     /// The slot clock for this service.
-    slot_clock: SystemTimeSlotClock,
+    slot_clock: C,
     /// The current slot we are processing.
     current_slot: Slot,
     slots_per_epoch: u64,
@@ -58,11 +67,37 @@ pub struct Service<B: BeaconNodeDuties + 'static, S: Signer + 'static> {
     beacon_block_client: Arc<BeaconBlockGrpcClient>,
     /// The attester GRPC client.
     attestation_client: Arc<AttestationServiceClient>,
+    /// The record of signed messages, shared by all validators.
+    slashing_protection: Arc<SlashingDatabase>,
+    /// Decides when signing may begin, after checking that our validators are not already active.
+    doppelganger_protection: DoppelgangerProtection,
+    /// If running as a standby, decides when the primary has been absent for long enough to begin
+    /// signing. Removed once signing begins.
+    standby: Option<StandbyMonitor>,
+    /// The primary's slashing protection history, imported before a standby begins signing.
+    standby_interchange: Option<PathBuf>,
+    /// The beacon node GRPC client, which reports the node's clock.
+    beacon_node_client: Arc<BeaconNodeServiceClient>,
+    /// Compares our clock with the beacon node's.
+    clock_skew: ClockSkewMonitor,
+    /// If `true`, signing is refused while our clock is skewed.
+    refuse_on_clock_skew: bool,
+    /// When each duty is performed within its slot.
+    timing: DutyTiming,
+    /// Chooses the graffiti of each proposed block.
+    graffiti: Arc<GraffitiSource>,
+    /// The key manager API server, if enabled. It runs until the service is dropped.
+    _key_manager_api: Option<Listening>,
     /// The validator client logger.
     log: slog::Logger,
 }
 
-impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
+impl<B, S, C> Service<B, S, C>
+where
+    B: BeaconNodeDuties + BeaconNodeLiveness + 'static,
+    S: Signer + 'static,
+    C: SlotClock + 'static,
+{
     ///  Initial connection to the beacon node to determine its properties.
     ///
     ///  This tries to connect to a beacon node. Once connected, it initialised the gRPC clients
@@ -71,7 +106,7 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
         client_config: ValidatorConfig,
         eth2_config: Eth2Config,
         log: slog::Logger,
-    ) -> error_chain::Result<Service<ValidatorServiceClient, Keypair>> {
+    ) -> error_chain::Result<Service<ValidatorServiceClient, ValidatorSigner>> {
         // initialise the beacon node client to check for a connection
 
         let env = Arc::new(EnvBuilder::new().build());
@@ -172,16 +207,94 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
 
         /* Generate the duties manager */
 
-        // Load generated keypairs
-        let keypairs = match client_config.fetch_keys(&log) {
-            Some(kps) => Arc::new(kps),
-            None => {
+        // Load the validators, either from a remote signer or from local keystores.
+        let signers: Vec<ValidatorSigner> = if let Some(url) = &client_config.remote_signer {
+            let remote_signer = RemoteSigner::new(url)?;
+            let public_keys = remote_signer.public_keys()?;
+            info!(log, "Using remote signer"; "url" => url, "validators" => public_keys.len());
+
+            let backend: Arc<dyn SignerBackend> = Arc::new(remote_signer);
+            public_keys
+                .into_iter()
+                .map(|public_key| ValidatorSigner::new(public_key, backend.clone()))
+                .collect()
+        } else {
+            client_config
+                .fetch_keys(&log)
+                .unwrap_or_default()
+                .into_iter()
+                .map(ValidatorSigner::local)
+                .collect()
+        };
+        if signers.is_empty() {
+            if !client_config.http_enabled {
                 return Err("Unable to locate validator key pairs, nothing to do.".into());
             }
+            warn!(log, "No validators found, waiting for them to be imported");
+        }
+        // Open the slashing protection database, registering any newly-loaded validators.
+        let slashing_protection_path = client_config.data_dir.join(SLASHING_PROTECTION_FILENAME);
+        let slashing_protection = SlashingDatabase::open_or_create(&slashing_protection_path)
+            .map_err(|e| {
+                error_chain::Error::from(format!(
+                    "Unable to open slashing protection database: {:?}",
+                    e
+                ))
+            })?;
+        let public_keys: Vec<PublicKey> = signers.iter().map(Signer::to_public).collect();
+        let signers = Arc::new(RwLock::new(signers));
+        slashing_protection
+            .register_validators(public_keys.iter())
+            .map_err(|e| {
+                error_chain::Error::from(format!("Unable to register validators: {:?}", e))
+            })?;
+        let slashing_protection = Arc::new(slashing_protection);
+
+        let key_manager_api = if client_config.http_enabled {
+            let key_manager = KeyManager {
+                signers: signers.clone(),
+                slashing_protection: slashing_protection.clone(),
+                config: client_config.clone(),
+                log: log.clone(),
+            };
+            Some(http_api::start_server(key_manager)?)
+        } else {
+            None
         };
 
         let slots_per_epoch = T::slots_per_epoch();
 
+        let standby = if client_config.standby_epochs > 0 {
+            info!(
+                log,
+                "Running as a standby";
+                "epochs" => client_config.standby_epochs
+            );
+            Some(StandbyMonitor::new(
+                current_slot.epoch(slots_per_epoch),
+                client_config.standby_epochs,
+            ))
+        } else {
+            None
+        };
+
+        // A standby would detect the primary as a doppelganger. Waiting for the primary's absence
+        // already ensures our validators are not active elsewhere.
+        let doppelganger_epochs = if standby.is_some() {
+            0
+        } else {
+            client_config.doppelganger_epochs
+        };
+        let doppelganger_protection =
+            DoppelgangerProtection::new(current_slot.epoch(slots_per_epoch), doppelganger_epochs);
+        if doppelganger_epochs > 0 {
+            info!(
+                log,
+                "Doppelganger protection enabled";
+                "epochs" => doppelganger_epochs
+            );
+        }
+
         // TODO: keypairs are randomly generated; they should be loaded from a file or generated.
         // https://github.com/sigp/lighthouse/issues/160
         //let keypairs = Arc::new(generate_deterministic_keypairs(8));
@@ -193,13 +306,38 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
 
         // builds a manager which maintains the list of current duties for all known validators
         // and can check when a validator needs to perform a task.
+        let verifier = if client_config.verify_duties {
+            info!(log, "Verifying duties against Merkle proofs");
+            Some(DutiesVerifier::new::<T>(eth2_config.spec.clone()))
+        } else {
+            None
+        };
+
         let duties_manager = Arc::new(DutiesManager {
             duties_map,
             // these are abstract objects capable of signing
-            signers: keypairs,
+            signers,
             beacon_node: validator_client,
+            verifier,
         });
 
+        let graffiti = client_config
+            .graffiti
+            .as_ref()
+            .map(|graffiti| graffiti_from_str(graffiti))
+            .transpose()?;
+        let graffiti = Arc::new(GraffitiSource::new(
+            client_config.graffiti_file.clone(),
+            graffiti,
+        ));
+
+        let timing = DutyTiming::from_config(&client_config, eth2_config.spec.seconds_per_slot)?;
+
+        let clock_skew = ClockSkewMonitor::new(
+            client_config.max_clock_skew_ms,
+            client_config.ntp_server.clone(),
+        );
+
         let spec = Arc::new(eth2_config.spec);
 
         Ok(Service {
@@ -211,6 +349,16 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
             duties_manager,
             beacon_block_client,
             attestation_client,
+            slashing_protection,
+            doppelganger_protection,
+            standby,
+            standby_interchange: client_config.standby_interchange.clone(),
+            beacon_node_client: Arc::new(beacon_node_client),
+            clock_skew,
+            refuse_on_clock_skew: client_config.refuse_on_clock_skew,
+            timing,
+            graffiti,
+            _key_manager_api: key_manager_api,
             log,
         })
     }
@@ -223,11 +371,9 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
         log: slog::Logger,
     ) -> error_chain::Result<()> {
         // connect to the node and retrieve its properties and initialize the gRPC clients
-        let mut service = Service::<ValidatorServiceClient, Keypair>::initialize_service::<T>(
-            client_config,
-            eth2_config,
-            log,
-        )?;
+        let mut service = Service::<ValidatorServiceClient, ValidatorSigner>::initialize_service::<
+            T,
+        >(client_config, eth2_config, log)?;
 
         // we have connected to a node and established its parameters. Spin up the core service
 
@@ -257,15 +403,19 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
         /* kick off the core service */
         runtime.block_on(
             interval
+                .map_err(|e| error_chain::Error::from(format!("Service thread failed: {:?}", e)))
                 .for_each(move |_| {
                     // wait for node to process
-                    std::thread::sleep(TIME_DELAY_FROM_SLOT);
+                    std::thread::sleep(service.timing.block_delay);
                     // if a non-fatal error occurs, proceed to the next slot.
-                    let _ignore_error = service.per_slot_execution();
+                    if let Err(e) = service.per_slot_execution() {
+                        if let ErrorKind::DoppelgangerDetected(_) = e.kind() {
+                            return Err(e);
+                        }
+                    }
                     // completed a slot process
                     Ok(())
-                })
-                .map_err(|e| format!("Service thread failed: {:?}", e)),
+                }),
         )?;
         // validator client exited
         Ok(())
@@ -280,6 +430,21 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
         /* check for new duties */
         self.check_for_duties();
 
+        /* a standby does not sign until the primary is gone */
+        if !self.check_for_primary()? {
+            return Ok(());
+        }
+
+        /* do not sign until certain our validators are not running elsewhere */
+        if !self.check_for_doppelgangers()? {
+            return Ok(());
+        }
+
+        /* do not sign at the wrong time */
+        if !self.check_clock_skew() {
+            return Ok(());
+        }
+
         /* process any required duties for validators */
         self.process_duties();
 
@@ -311,6 +476,126 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
         Ok(())
     }
 
+    /// The public keys of all validators in use.
+    fn public_keys(&self) -> error_chain::Result<Vec<PublicKey>> {
+        match self.duties_manager.signers.read() {
+            Ok(signers) => Ok(signers.iter().map(Signer::to_public).collect()),
+            Err(_) => Err("Validator list lock poisoned".into()),
+        }
+    }
+
+    /// Returns `true` if this client is not a standby, or if the primary has been absent for long
+    /// enough that the standby may begin signing.
+    ///
+    /// Before a standby begins signing, the primary's slashing protection history is imported if
+    /// it was provided. If the import fails the standby keeps waiting, and retries next slot.
+    fn check_for_primary(&mut self) -> error_chain::Result<bool> {
+        if self.standby.is_none() {
+            return Ok(true);
+        }
+
+        let current_epoch = self.current_slot.epoch(self.slots_per_epoch);
+        let public_keys = self.public_keys()?;
+
+        let status = match self.standby.as_mut().expect("standby is set").poll(
+            current_epoch,
+            self.duties_manager.beacon_node.as_ref(),
+            &public_keys,
+        ) {
+            Ok(status) => status,
+            Err(e) => {
+                error!(self.log, "Failed to check for the primary"; "error" => format!("{:?}", e));
+                return Ok(false);
+            }
+        };
+
+        match status {
+            StandbyStatus::Standby { primary_last_seen } => {
+                info!(self.log, "Standing by"; "primary_last_seen_epoch" => primary_last_seen.as_u64());
+                Ok(false)
+            }
+            StandbyStatus::Promoted => {
+                if let Some(path) = &self.standby_interchange {
+                    let slashing_protection = &self.slashing_protection;
+                    let imported = Interchange::from_file(path).and_then(|interchange| {
+                        slashing_protection
+                            .import_interchange(&interchange)
+                            .map_err(|e| format!("{:?}", e))
+                    });
+                    if let Err(e) = imported {
+                        error!(self.log, "Unable to import the primary's slashing protection"; "error" => e);
+                        return Ok(false);
+                    }
+                }
+
+                warn!(self.log, "Primary is absent, standby is now signing");
+                self.standby = None;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Returns `true` if doppelganger protection permits signing in the current slot.
+    ///
+    /// Returns a `DoppelgangerDetected` error if any of our validators were seen on the network.
+    fn check_for_doppelgangers(&mut self) -> error_chain::Result<bool> {
+        let current_epoch = self.current_slot.epoch(self.slots_per_epoch);
+        let public_keys = self.public_keys()?;
+
+        let status = match self.doppelganger_protection.poll(
+            current_epoch,
+            self.duties_manager.beacon_node.as_ref(),
+            &public_keys,
+        ) {
+            Ok(status) => status,
+            Err(e) => {
+                // Remain waiting; the check is retried next slot.
+                error!(self.log, "Failed to check for doppelgangers"; "error" => format!("{:?}", e));
+                return Ok(false);
+            }
+        };
+
+        match status {
+            DoppelgangerStatus::SigningEnabled => Ok(true),
+            DoppelgangerStatus::Waiting { until } => {
+                info!(self.log, "Waiting for doppelganger protection"; "signing_epoch" => until.as_u64());
+                Ok(false)
+            }
+            DoppelgangerStatus::Detected(keys) => {
+                let keys: Vec<String> = keys.iter().map(|pk| pk.as_hex_string()).collect();
+                crit!(self.log, "Doppelganger detected, refusing to sign"; "validators" => format!("{:?}", keys));
+                Err(ErrorKind::DoppelgangerDetected(keys).into())
+            }
+        }
+    }
+
+    /// Returns `false` if our clock is skewed and signing is refused until it is corrected.
+    ///
+    /// Otherwise skew is only warned of. A failure to read a clock leaves the last status in
+    /// place.
+    fn check_clock_skew(&mut self) -> bool {
+        let current_epoch = self.current_slot.epoch(self.slots_per_epoch);
+
+        if let Err(e) = self
+            .clock_skew
+            .poll(current_epoch, self.beacon_node_client.as_ref())
+        {
+            warn!(self.log, "Unable to check for clock skew"; "error" => format!("{:?}", e));
+        }
+
+        match self.clock_skew.status() {
+            ClockSkewStatus::Skewed { source, offset_ms } if self.refuse_on_clock_skew => {
+                crit!(self.log, "Clock is skewed, refusing to sign"; "source" => source, "offset_ms" => offset_ms);
+                false
+            }
+            ClockSkewStatus::Skewed { source, offset_ms } => {
+                warn!(self.log, "Clock is skewed, duties may be missed"; "source" => source, "offset_ms" => offset_ms);
+                true
+            }
+            ClockSkewStatus::Synced | ClockSkewStatus::Unknown => true,
+        }
+    }
+
     /// For all known validator keypairs, update any known duties from the beacon node.
     fn check_for_duties(&mut self) {
         let cloned_manager = self.duties_manager.clone();
@@ -330,27 +615,31 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
     /// If there are any duties to process, spawn a separate thread and perform required actions.
     fn process_duties(&mut self) {
         if let Some(work) = self.duties_manager.get_current_work(self.current_slot) {
-            for (signer_index, work_type) in work {
+            for (signer, work_type) in work {
                 if work_type.produce_block {
                     // we need to produce a block
                     // spawns a thread to produce a beacon block
-                    let signers = self.duties_manager.signers.clone(); // this is an arc
+                    let signer = signer.clone();
                     let fork = self.fork.clone();
                     let slot = self.current_slot;
                     let spec = self.spec.clone();
                     let beacon_node = self.beacon_block_client.clone();
+                    let slashing_protection = self.slashing_protection.clone();
+                    let graffiti = self.graffiti.clone();
                     let log = self.log.clone();
                     let slots_per_epoch = self.slots_per_epoch;
                     std::thread::spawn(move || {
-                        info!(log, "Producing a block"; "Validator"=> format!("{}", signers[signer_index]));
-                        let signer = &signers[signer_index];
+                        info!(log, "Producing a block"; "Validator"=> format!("{}", signer));
+                        let graffiti = graffiti.graffiti(&signer.to_public(), &log);
                         let mut block_producer = BlockProducer {
                             fork,
                             slot,
                             spec,
                             beacon_node,
-                            signer,
+                            signer: &signer,
                             slots_per_epoch,
+                            slashing_protection,
+                            graffiti,
                         };
                         block_producer.handle_produce_block(log);
                     });
@@ -358,22 +647,32 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
                 if work_type.attestation_duty.is_some() {
                     // we need to produce an attestation
                     // spawns a thread to produce and sign an attestation
-                    let signers = self.duties_manager.signers.clone(); // this is an arc
+                    let signer = signer.clone();
                     let fork = self.fork.clone();
                     let spec = self.spec.clone();
                     let beacon_node = self.attestation_client.clone();
+                    let slashing_protection = self.slashing_protection.clone();
                     let log = self.log.clone();
                     let slots_per_epoch = self.slots_per_epoch;
+                    let waits = self.duty_waits();
+                    let attestation_wait = waits.attestation;
+                    let publish_at = waits
+                        .aggregate_publication
+                        .map(|wait| Instant::now() + wait);
                     std::thread::spawn(move || {
-                        info!(log, "Producing an attestation"; "Validator"=> format!("{}", signers[signer_index]));
-                        let signer = &signers[signer_index];
+                        if let Some(wait) = attestation_wait {
+                            std::thread::sleep(wait);
+                        }
+                        info!(log, "Producing an attestation"; "Validator"=> format!("{}", signer));
                         let mut attestation_producer = AttestationProducer {
                             fork,
                             duty: work_type.attestation_duty.expect("Should never be none"),
                             spec,
                             beacon_node,
-                            signer,
+                            signer: &signer,
                             slots_per_epoch,
+                            slashing_protection,
+                            publish_at,
                         };
                         attestation_producer.handle_produce_attestation(log);
                     });
@@ -381,4 +680,20 @@ impl<B: BeaconNodeDuties + 'static, S: Signer + 'static> Service<B, S> {
             }
         }
     }
+
+    /// Returns the time from now until each duty of the current slot is due.
+    ///
+    /// If the slot clock cannot be read, all duties are treated as already due.
+    fn duty_waits(&self) -> DutyWaits {
+        match self.timing.waits(&self.slot_clock, self.current_slot) {
+            Ok(waits) => waits,
+            Err(e) => {
+                error!(self.log, "SystemTimeError {:?}", e);
+                DutyWaits {
+                    attestation: None,
+                    aggregate_publication: None,
+                }
+            }
+        }
+    }
 }
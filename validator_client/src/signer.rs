@@ -1,13 +1,69 @@
-use std::fmt::Display;
-use types::{Keypair, PublicKey, Signature};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::sync::Arc;
+use tree_hash::{SignedRoot, TreeHash};
+use types::{
+    AttestationData, AttestationDataAndCustodyBit, BeaconBlock, Epoch, Keypair, PublicKey,
+    Signature, Slot,
+};
+
+/// A message for a validator to sign.
+///
+/// The whole message is given to the signer, rather than only its signing root, so that a signer
+/// may check what it is signing, e.g., against its own slashing protection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignableMessage {
+    /// The RANDAO reveal of a block proposal in `epoch`.
+    RandaoReveal {
+        epoch: Epoch,
+    },
+    BeaconBlock {
+        slot: Slot,
+        block: BeaconBlock,
+    },
+    /// The attestation of a validator assigned to attest at `slot`.
+    Attestation {
+        slot: Slot,
+        attestation: AttestationData,
+    },
+}
+
+impl SignableMessage {
+    /// The root of the message which is signed.
+    pub fn signing_root(&self) -> Vec<u8> {
+        match self {
+            SignableMessage::RandaoReveal { epoch } => epoch.tree_hash_root(),
+            SignableMessage::BeaconBlock { block, .. } => block.signed_root(),
+            SignableMessage::Attestation { attestation, .. } => AttestationDataAndCustodyBit {
+                data: attestation.clone(),
+                custody_bit: false,
+            }
+            .tree_hash_root(),
+        }
+    }
+}
 
 /// Signs message using an internally-maintained private key.
 pub trait Signer: Display + Send + Sync + Clone {
-    fn sign_message(&self, message: &[u8], domain: u64) -> Option<Signature>;
+    fn sign_message(&self, message: &SignableMessage, domain: u64) -> Option<Signature>;
     /// Returns a public key for the signer object.
     fn to_public(&self) -> PublicKey;
 }
 
+/// Holds the secret keys of some validators and produces signatures on their behalf.
+///
+/// The keys may be held in-process, or by a separate signing service.
+pub trait SignerBackend: Send + Sync {
+    /// Signs the signing root of `message` in `domain` with the secret key of `public_key`.
+    fn sign(
+        &self,
+        public_key: &PublicKey,
+        message: &SignableMessage,
+        domain: u64,
+    ) -> Result<Signature, String>;
+}
+
 /* Implements Display and Signer for Keypair */
 
 impl Signer for Keypair {
@@ -15,7 +71,60 @@ impl Signer for Keypair {
         self.pk.clone()
     }
 
-    fn sign_message(&self, message: &[u8], domain: u64) -> Option<Signature> {
-        Some(Signature::new(message, domain, &self.sk))
+    fn sign_message(&self, message: &SignableMessage, domain: u64) -> Option<Signature> {
+        Some(Signature::new(&message.signing_root(), domain, &self.sk))
+    }
+}
+
+impl SignerBackend for Keypair {
+    fn sign(
+        &self,
+        public_key: &PublicKey,
+        message: &SignableMessage,
+        domain: u64,
+    ) -> Result<Signature, String> {
+        if *public_key != self.pk {
+            return Err(format!("No secret key for {}", public_key));
+        }
+        Ok(Signature::new(&message.signing_root(), domain, &self.sk))
+    }
+}
+
+/// A single validator, which signs using whichever backend holds its secret key.
+#[derive(Clone)]
+pub struct ValidatorSigner {
+    public_key: PublicKey,
+    backend: Arc<dyn SignerBackend>,
+}
+
+impl ValidatorSigner {
+    pub fn new(public_key: PublicKey, backend: Arc<dyn SignerBackend>) -> Self {
+        Self {
+            public_key,
+            backend,
+        }
+    }
+
+    /// A validator whose secret key is held in-process.
+    pub fn local(keypair: Keypair) -> Self {
+        Self::new(keypair.pk.clone(), Arc::new(keypair))
+    }
+}
+
+impl Signer for ValidatorSigner {
+    fn to_public(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    /// Returns `None` if the backend fails to sign. The cause is not surfaced, as the producers
+    /// report any signer failure as a `SignerRejection`.
+    fn sign_message(&self, message: &SignableMessage, domain: u64) -> Option<Signature> {
+        self.backend.sign(&self.public_key, message, domain).ok()
+    }
+}
+
+impl Display for ValidatorSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.public_key)
     }
 }
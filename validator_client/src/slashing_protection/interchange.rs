@@ -0,0 +1,138 @@
+//! The EIP-3076 slashing protection interchange format.
+use serde::{de::Error, Deserializer, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::Path;
+use types::{Epoch, Hash256, PublicKey, Slot};
+
+/// The version of the interchange format which is produced and accepted.
+pub const INTERCHANGE_FORMAT_VERSION: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterchangeMetadata {
+    #[serde(with = "quoted_u64")]
+    pub interchange_format_version: u64,
+    /// Identifies the chain the records were made on.
+    ///
+    /// This chain has no `genesis_validators_root`, so it is always zero when exported and is not
+    /// checked when imported.
+    pub genesis_validators_root: Hash256,
+}
+
+impl Default for InterchangeMetadata {
+    fn default() -> Self {
+        Self {
+            interchange_format_version: INTERCHANGE_FORMAT_VERSION,
+            genesis_validators_root: Hash256::zero(),
+        }
+    }
+}
+
+/// The signing history of a single validator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterchangeData {
+    pub pubkey: PublicKey,
+    pub signed_blocks: Vec<SignedBlock>,
+    pub signed_attestations: Vec<SignedAttestation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedBlock {
+    #[serde(with = "quoted_slot")]
+    pub slot: Slot,
+    pub signing_root: Hash256,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    #[serde(with = "quoted_epoch")]
+    pub source_epoch: Epoch,
+    #[serde(with = "quoted_epoch")]
+    pub target_epoch: Epoch,
+    pub signing_root: Hash256,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interchange {
+    pub metadata: InterchangeMetadata,
+    pub data: Vec<InterchangeData>,
+}
+
+impl Interchange {
+    /// Reads an interchange from the JSON file at `path`, checking its format version.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Unable to open {:?}: {:?}", path, e))?;
+        let interchange: Interchange = serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse {:?}: {:?}", path, e))?;
+        interchange.check_version()?;
+        Ok(interchange)
+    }
+
+    /// Returns an error if the interchange uses an unsupported format version.
+    pub fn check_version(&self) -> Result<(), String> {
+        if self.metadata.interchange_format_version == INTERCHANGE_FORMAT_VERSION {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unsupported interchange format version {}, expected {}",
+                self.metadata.interchange_format_version, INTERCHANGE_FORMAT_VERSION
+            ))
+        }
+    }
+}
+
+/// Integers are encoded as decimal strings, as required by the interchange format.
+mod quoted_u64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s: String = serde::Deserialize::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|e| D::Error::custom(format!("Invalid integer {}: {:?}", s, e)))
+    }
+}
+
+mod quoted_slot {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(slot: &Slot, serializer: S) -> Result<S::Ok, S::Error> {
+        quoted_u64::serialize(&slot.as_u64(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Slot, D::Error> {
+        quoted_u64::deserialize(deserializer).map(Slot::from)
+    }
+}
+
+mod quoted_epoch {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(epoch: &Epoch, serializer: S) -> Result<S::Ok, S::Error> {
+        quoted_u64::serialize(&epoch.as_u64(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Epoch, D::Error> {
+        quoted_u64::deserialize(deserializer).map(Epoch::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_quoted() {
+        let block = SignedBlock {
+            slot: Slot::new(81952),
+            signing_root: Hash256::zero(),
+        };
+
+        let json = serde_json::to_value(&block).unwrap();
+        assert_eq!(json["slot"], "81952");
+        assert_eq!(serde_json::from_value::<SignedBlock>(json).unwrap(), block);
+    }
+}
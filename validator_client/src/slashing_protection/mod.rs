@@ -0,0 +1,813 @@
+//! A record of every block and attestation signed by each validator, which is checked before any
+//! further signing so that a validator can never be slashed, even across restarts.
+//!
+//! Records may be moved between clients using the EIP-3076 interchange format.
+mod interchange;
+
+pub use self::interchange::{Interchange, InterchangeData, InterchangeMetadata};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use ssz::{Decode, Encode};
+use std::path::Path;
+use std::sync::Mutex;
+use tree_hash::{SignedRoot, TreeHash};
+use types::{
+    AttestationData, AttestationDataAndCustodyBit, BeaconBlock, Epoch, Hash256, PublicKey, Slot,
+};
+
+/// The filename of the slashing protection database, within the validator data directory.
+pub const SLASHING_PROTECTION_FILENAME: &str = "slashing_protection.sqlite";
+
+/// A signing was refused.
+#[derive(Debug, PartialEq)]
+pub enum NotSafe {
+    /// The validator was never registered with the database.
+    UnregisteredValidator(PublicKey),
+    /// The validator was disabled, e.g., because its key was exported, and has not been registered
+    /// since.
+    DisabledValidator(PublicKey),
+    InvalidBlock(InvalidBlock),
+    InvalidAttestation(InvalidAttestation),
+    /// The database could not be read or written.
+    SQLError(String),
+}
+
+/// A block proposal was refused because signing it could be slashable.
+#[derive(Debug, PartialEq)]
+pub enum InvalidBlock {
+    /// A different block was already signed at this slot.
+    DoubleBlockProposal { slot: Slot },
+    /// The slot is prior to the earliest recorded proposal, for which history may be incomplete.
+    SlotViolatesLowerBound { slot: Slot, lower_bound: Slot },
+}
+
+/// An attestation was refused because signing it could be slashable.
+#[derive(Debug, PartialEq)]
+pub enum InvalidAttestation {
+    /// A different attestation was already signed with this target epoch.
+    DoubleVote { target_epoch: Epoch },
+    /// The new attestation surrounds one which was already signed.
+    NewSurroundsPrev {
+        prev_source: Epoch,
+        prev_target: Epoch,
+    },
+    /// An attestation which was already signed surrounds the new one.
+    PrevSurroundsNew {
+        prev_source: Epoch,
+        prev_target: Epoch,
+    },
+    /// The source epoch is greater than the target epoch.
+    SourceExceedsTarget,
+    /// The source epoch is prior to the earliest recorded source epoch.
+    SourceLessThanLowerBound { source: Epoch, lower_bound: Epoch },
+    /// The target epoch is not after the earliest recorded target epoch.
+    TargetLessThanOrEqLowerBound { target: Epoch, lower_bound: Epoch },
+}
+
+/// A signing was permitted.
+#[derive(Debug, PartialEq)]
+pub enum Safe {
+    /// Exactly the same message was signed before, so signing it again is harmless.
+    SameData,
+    /// The message has been recorded and may be signed.
+    Valid,
+}
+
+impl From<rusqlite::Error> for NotSafe {
+    fn from(e: rusqlite::Error) -> Self {
+        NotSafe::SQLError(format!("{:?}", e))
+    }
+}
+
+/// The slashing protection database, backed by SQLite.
+pub struct SlashingDatabase {
+    conn: Mutex<Connection>,
+}
+
+impl SlashingDatabase {
+    /// Opens the database at `path`, creating it if it does not exist.
+    pub fn open_or_create(path: &Path) -> Result<Self, NotSafe> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates an empty database which is not persisted.
+    pub fn open_in_memory() -> Result<Self, NotSafe> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, NotSafe> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS validators (
+                id INTEGER PRIMARY KEY,
+                public_key BLOB NOT NULL UNIQUE,
+                enabled BOOLEAN NOT NULL DEFAULT 1
+            );
+            CREATE TABLE IF NOT EXISTS signed_blocks (
+                validator_id INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                signing_root BLOB NOT NULL,
+                FOREIGN KEY(validator_id) REFERENCES validators(id),
+                UNIQUE (validator_id, slot)
+            );
+            CREATE TABLE IF NOT EXISTS signed_attestations (
+                validator_id INTEGER NOT NULL,
+                source_epoch INTEGER NOT NULL,
+                target_epoch INTEGER NOT NULL,
+                signing_root BLOB NOT NULL,
+                FOREIGN KEY(validator_id) REFERENCES validators(id),
+                UNIQUE (validator_id, target_epoch, source_epoch)
+            );",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Registers each of `public_keys`, so that they may sign. Keys which are already registered
+    /// keep their history, and are enabled if they were disabled.
+    pub fn register_validators<'a>(
+        &self,
+        public_keys: impl Iterator<Item = &'a PublicKey>,
+    ) -> Result<(), NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction()?;
+        for public_key in public_keys {
+            register_validator(&txn, public_key)?;
+            txn.execute(
+                "UPDATE validators SET enabled = 1 WHERE public_key = ?1",
+                params![public_key.as_ssz_bytes()],
+            )?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Disables each of `public_keys`, so that nothing further is checked as safe for them to sign,
+    /// and exports the complete history of those which are registered.
+    ///
+    /// Both happen in a single exclusive transaction, so every check which permitted a signing has
+    /// finished and its record is in the export. A signing which is still in flight can therefore
+    /// only produce a message which the export already records.
+    pub fn disable_and_export(&self, public_keys: &[PublicKey]) -> Result<Interchange, NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+
+        let mut validators = vec![];
+        for public_key in public_keys {
+            let public_key_bytes = public_key.as_ssz_bytes();
+            txn.execute(
+                "UPDATE validators SET enabled = 0 WHERE public_key = ?1",
+                params![public_key_bytes],
+            )?;
+            match validator_id(&txn, public_key) {
+                Ok(validator_id) => validators.push((validator_id, public_key_bytes)),
+                Err(NotSafe::UnregisteredValidator(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let interchange = export_validators(&txn, validators)?;
+        txn.commit()?;
+        Ok(interchange)
+    }
+
+    /// Checks that `block` is safe for the validator with `public_key` to sign and, if so, records
+    /// it as signed.
+    ///
+    /// The check and the insert happen in a single transaction, so two concurrent calls can never
+    /// both be permitted to sign conflicting blocks.
+    pub fn check_and_insert_block_proposal(
+        &self,
+        public_key: &PublicKey,
+        block: &BeaconBlock,
+    ) -> Result<Safe, NotSafe> {
+        let signing_root = Hash256::from_slice(&block.signed_root());
+        self.check_and_insert_block_signing_root(public_key, block.slot, signing_root)
+    }
+
+    fn check_and_insert_block_signing_root(
+        &self,
+        public_key: &PublicKey,
+        slot: Slot,
+        signing_root: Hash256,
+    ) -> Result<Safe, NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        let validator_id = enabled_validator_id(&txn, public_key)?;
+
+        let safe = check_block_proposal(&txn, validator_id, slot, signing_root)?;
+        if safe == Safe::Valid {
+            insert_block_proposal(&txn, validator_id, slot, signing_root)?;
+        }
+
+        txn.commit()?;
+        Ok(safe)
+    }
+
+    /// Checks that `attestation` is safe for the validator with `public_key` to sign and, if so,
+    /// records it as signed.
+    ///
+    /// The check and the insert happen in a single transaction, so two concurrent calls can never
+    /// both be permitted to sign conflicting attestations.
+    pub fn check_and_insert_attestation(
+        &self,
+        public_key: &PublicKey,
+        attestation: &AttestationData,
+    ) -> Result<Safe, NotSafe> {
+        let signing_root = Hash256::from_slice(
+            &AttestationDataAndCustodyBit {
+                data: attestation.clone(),
+                custody_bit: false,
+            }
+            .tree_hash_root(),
+        );
+        self.check_and_insert_attestation_signing_root(
+            public_key,
+            attestation.source_epoch,
+            attestation.target_epoch,
+            signing_root,
+        )
+    }
+
+    fn check_and_insert_attestation_signing_root(
+        &self,
+        public_key: &PublicKey,
+        source: Epoch,
+        target: Epoch,
+        signing_root: Hash256,
+    ) -> Result<Safe, NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+        let validator_id = enabled_validator_id(&txn, public_key)?;
+
+        let safe = check_attestation(&txn, validator_id, source, target, signing_root)?;
+        if safe == Safe::Valid {
+            insert_attestation(&txn, validator_id, source, target, signing_root)?;
+        }
+
+        txn.commit()?;
+        Ok(safe)
+    }
+
+    /// Imports all records in `interchange`, registering any unknown validators.
+    ///
+    /// Blocks at a slot which already has a record are skipped, since the existing record already
+    /// refuses every block at that slot. Attestations are kept even if another record has the same
+    /// target epoch, since a different source epoch surrounds different attestations. Only exact
+    /// duplicates of a source and target epoch are skipped.
+    pub fn import_interchange(&self, interchange: &Interchange) -> Result<(), NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+
+        for record in &interchange.data {
+            register_validator(&txn, &record.pubkey)?;
+            let validator_id = validator_id(&txn, &record.pubkey)?;
+
+            for block in &record.signed_blocks {
+                txn.execute(
+                    "INSERT OR IGNORE INTO signed_blocks (validator_id, slot, signing_root)
+                     VALUES (?1, ?2, ?3)",
+                    params![
+                        validator_id,
+                        block.slot.as_u64() as i64,
+                        block.signing_root.as_bytes()
+                    ],
+                )?;
+            }
+
+            for attestation in &record.signed_attestations {
+                txn.execute(
+                    "INSERT OR IGNORE INTO signed_attestations
+                     (validator_id, source_epoch, target_epoch, signing_root)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        validator_id,
+                        attestation.source_epoch.as_u64() as i64,
+                        attestation.target_epoch.as_u64() as i64,
+                        attestation.signing_root.as_bytes()
+                    ],
+                )?;
+            }
+        }
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Exports the complete history of every registered validator.
+    pub fn export_interchange(&self) -> Result<Interchange, NotSafe> {
+        let mut conn = self.conn.lock().expect("slashing protection lock poisoned");
+        let txn = conn.transaction()?;
+
+        let validators = {
+            let mut stmt = txn.prepare("SELECT id, public_key FROM validators ORDER BY id")?;
+            let rows = stmt.query_map(params![], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        export_validators(&txn, validators)
+    }
+}
+
+/// Exports the complete history of each of `validators`, given as their id and SSZ-encoded public
+/// key.
+fn export_validators(
+    txn: &Transaction,
+    validators: Vec<(i64, Vec<u8>)>,
+) -> Result<Interchange, NotSafe> {
+    let mut data = Vec::with_capacity(validators.len());
+    for (validator_id, public_key_bytes) in validators {
+        let pubkey = PublicKey::from_ssz_bytes(&public_key_bytes)
+            .map_err(|e| NotSafe::SQLError(format!("Invalid public key: {:?}", e)))?;
+
+        let signed_blocks = {
+            let mut stmt = txn.prepare(
+                "SELECT slot, signing_root FROM signed_blocks
+                 WHERE validator_id = ?1 ORDER BY slot",
+            )?;
+            let rows = stmt.query_map(params![validator_id], |row| {
+                Ok(interchange::SignedBlock {
+                    slot: Slot::from(row.get::<_, i64>(0)? as u64),
+                    signing_root: Hash256::from_slice(&row.get::<_, Vec<u8>>(1)?),
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let signed_attestations = {
+            let mut stmt = txn.prepare(
+                "SELECT source_epoch, target_epoch, signing_root FROM signed_attestations
+                 WHERE validator_id = ?1 ORDER BY target_epoch",
+            )?;
+            let rows = stmt.query_map(params![validator_id], |row| {
+                Ok(interchange::SignedAttestation {
+                    source_epoch: Epoch::from(row.get::<_, i64>(0)? as u64),
+                    target_epoch: Epoch::from(row.get::<_, i64>(1)? as u64),
+                    signing_root: Hash256::from_slice(&row.get::<_, Vec<u8>>(2)?),
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        data.push(InterchangeData {
+            pubkey,
+            signed_blocks,
+            signed_attestations,
+        });
+    }
+
+    Ok(Interchange {
+        metadata: InterchangeMetadata::default(),
+        data,
+    })
+}
+
+fn register_validator(txn: &Transaction, public_key: &PublicKey) -> Result<(), NotSafe> {
+    txn.execute(
+        "INSERT OR IGNORE INTO validators (public_key) VALUES (?1)",
+        params![public_key.as_ssz_bytes()],
+    )?;
+    Ok(())
+}
+
+/// As `validator_id`, but refusing a validator which is disabled.
+fn enabled_validator_id(txn: &Transaction, public_key: &PublicKey) -> Result<i64, NotSafe> {
+    txn.query_row(
+        "SELECT id, enabled FROM validators WHERE public_key = ?1",
+        params![public_key.as_ssz_bytes()],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)),
+    )
+    .optional()?
+    .ok_or_else(|| NotSafe::UnregisteredValidator(public_key.clone()))
+    .and_then(|(id, enabled)| {
+        if enabled {
+            Ok(id)
+        } else {
+            Err(NotSafe::DisabledValidator(public_key.clone()))
+        }
+    })
+}
+
+fn validator_id(txn: &Transaction, public_key: &PublicKey) -> Result<i64, NotSafe> {
+    txn.query_row(
+        "SELECT id FROM validators WHERE public_key = ?1",
+        params![public_key.as_ssz_bytes()],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or_else(|| NotSafe::UnregisteredValidator(public_key.clone()))
+}
+
+fn check_block_proposal(
+    txn: &Transaction,
+    validator_id: i64,
+    slot: Slot,
+    signing_root: Hash256,
+) -> Result<Safe, NotSafe> {
+    let existing: Option<Vec<u8>> = txn
+        .query_row(
+            "SELECT signing_root FROM signed_blocks WHERE validator_id = ?1 AND slot = ?2",
+            params![validator_id, slot.as_u64() as i64],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(existing) = existing {
+        return if existing == signing_root.as_bytes() {
+            Ok(Safe::SameData)
+        } else {
+            Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal {
+                slot,
+            }))
+        };
+    }
+
+    let lower_bound: Option<i64> = txn.query_row(
+        "SELECT MIN(slot) FROM signed_blocks WHERE validator_id = ?1",
+        params![validator_id],
+        |row| row.get(0),
+    )?;
+
+    match lower_bound.map(|s| Slot::from(s as u64)) {
+        Some(lower_bound) if slot < lower_bound => Err(NotSafe::InvalidBlock(
+            InvalidBlock::SlotViolatesLowerBound { slot, lower_bound },
+        )),
+        _ => Ok(Safe::Valid),
+    }
+}
+
+fn insert_block_proposal(
+    txn: &Transaction,
+    validator_id: i64,
+    slot: Slot,
+    signing_root: Hash256,
+) -> Result<(), NotSafe> {
+    txn.execute(
+        "INSERT INTO signed_blocks (validator_id, slot, signing_root) VALUES (?1, ?2, ?3)",
+        params![validator_id, slot.as_u64() as i64, signing_root.as_bytes()],
+    )?;
+    Ok(())
+}
+
+fn check_attestation(
+    txn: &Transaction,
+    validator_id: i64,
+    source: Epoch,
+    target: Epoch,
+    signing_root: Hash256,
+) -> Result<Safe, NotSafe> {
+    if source > target {
+        return Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::SourceExceedsTarget,
+        ));
+    }
+
+    // Imports may leave several records with this target epoch, so it is only the same data if it
+    // matches all of them.
+    let existing: Vec<Vec<u8>> = {
+        let mut stmt = txn.prepare(
+            "SELECT signing_root FROM signed_attestations
+             WHERE validator_id = ?1 AND target_epoch = ?2",
+        )?;
+        let rows = stmt.query_map(params![validator_id, target.as_u64() as i64], |row| {
+            row.get(0)
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    if !existing.is_empty() {
+        return if existing
+            .iter()
+            .all(|existing| existing == signing_root.as_bytes())
+        {
+            Ok(Safe::SameData)
+        } else {
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::DoubleVote {
+                    target_epoch: target,
+                },
+            ))
+        };
+    }
+
+    // Find an existing attestation which the new one surrounds.
+    let surrounded: Option<(i64, i64)> = txn
+        .query_row(
+            "SELECT source_epoch, target_epoch FROM signed_attestations
+             WHERE validator_id = ?1 AND source_epoch > ?2 AND target_epoch < ?3
+             LIMIT 1",
+            params![validator_id, source.as_u64() as i64, target.as_u64() as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if let Some((prev_source, prev_target)) = surrounded {
+        return Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::NewSurroundsPrev {
+                prev_source: Epoch::from(prev_source as u64),
+                prev_target: Epoch::from(prev_target as u64),
+            },
+        ));
+    }
+
+    // Find an existing attestation which surrounds the new one.
+    let surrounding: Option<(i64, i64)> = txn
+        .query_row(
+            "SELECT source_epoch, target_epoch FROM signed_attestations
+             WHERE validator_id = ?1 AND source_epoch < ?2 AND target_epoch > ?3
+             LIMIT 1",
+            params![validator_id, source.as_u64() as i64, target.as_u64() as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if let Some((prev_source, prev_target)) = surrounding {
+        return Err(NotSafe::InvalidAttestation(
+            InvalidAttestation::PrevSurroundsNew {
+                prev_source: Epoch::from(prev_source as u64),
+                prev_target: Epoch::from(prev_target as u64),
+            },
+        ));
+    }
+
+    // Imported histories may be incomplete, so nothing prior to the earliest record is signed.
+    let (min_source, min_target): (Option<i64>, Option<i64>) = txn.query_row(
+        "SELECT MIN(source_epoch), MIN(target_epoch) FROM signed_attestations
+         WHERE validator_id = ?1",
+        params![validator_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    if let Some(lower_bound) = min_source.map(|e| Epoch::from(e as u64)) {
+        if source < lower_bound {
+            return Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::SourceLessThanLowerBound {
+                    source,
+                    lower_bound,
+                },
+            ));
+        }
+    }
+    if let Some(lower_bound) = min_target.map(|e| Epoch::from(e as u64)) {
+        if target <= lower_bound {
+            return Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::TargetLessThanOrEqLowerBound {
+                    target,
+                    lower_bound,
+                },
+            ));
+        }
+    }
+
+    Ok(Safe::Valid)
+}
+
+fn insert_attestation(
+    txn: &Transaction,
+    validator_id: i64,
+    source: Epoch,
+    target: Epoch,
+    signing_root: Hash256,
+) -> Result<(), NotSafe> {
+    txn.execute(
+        "INSERT INTO signed_attestations (validator_id, source_epoch, target_epoch, signing_root)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            validator_id,
+            source.as_u64() as i64,
+            target.as_u64() as i64,
+            signing_root.as_bytes()
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Keypair;
+
+    fn database_with_validator() -> (SlashingDatabase, PublicKey) {
+        let db = SlashingDatabase::open_in_memory().unwrap();
+        let public_key = Keypair::random().pk;
+        db.register_validators(std::iter::once(&public_key))
+            .unwrap();
+        (db, public_key)
+    }
+
+    fn root(byte: u8) -> Hash256 {
+        Hash256::from_slice(&[byte; 32])
+    }
+
+    #[test]
+    fn unregistered_validator_is_refused() {
+        let db = SlashingDatabase::open_in_memory().unwrap();
+        let public_key = Keypair::random().pk;
+
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&public_key, Slot::new(1), root(1)),
+            Err(NotSafe::UnregisteredValidator(public_key))
+        );
+    }
+
+    #[test]
+    fn double_block_proposal_is_refused() {
+        let (db, pk) = database_with_validator();
+        let slot = Slot::new(10);
+
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, slot, root(1)),
+            Ok(Safe::Valid)
+        );
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, slot, root(1)),
+            Ok(Safe::SameData)
+        );
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, slot, root(2)),
+            Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal {
+                slot
+            }))
+        );
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, slot - 1, root(3)),
+            Err(NotSafe::InvalidBlock(
+                InvalidBlock::SlotViolatesLowerBound {
+                    slot: slot - 1,
+                    lower_bound: slot
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, slot + 1, root(3)),
+            Ok(Safe::Valid)
+        );
+    }
+
+    #[test]
+    fn slashable_attestations_are_refused() {
+        let (db, pk) = database_with_validator();
+        let epoch = Epoch::new;
+
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(2), epoch(5), root(1)),
+            Ok(Safe::Valid)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(2), epoch(5), root(1)),
+            Ok(Safe::SameData)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(3), epoch(5), root(2)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::DoubleVote {
+                    target_epoch: epoch(5)
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(3), epoch(4), root(2)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::TargetLessThanOrEqLowerBound {
+                    target: epoch(4),
+                    lower_bound: epoch(5)
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(6), epoch(8), root(3)),
+            Ok(Safe::Valid)
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(5), epoch(9), root(4)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::NewSurroundsPrev {
+                    prev_source: epoch(6),
+                    prev_target: epoch(8)
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(7), epoch(7), root(5)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::PrevSurroundsNew {
+                    prev_source: epoch(6),
+                    prev_target: epoch(8)
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(9), epoch(8), root(6)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::SourceExceedsTarget
+            ))
+        );
+    }
+
+    #[test]
+    fn interchange_round_trip() {
+        let (db, pk) = database_with_validator();
+        db.check_and_insert_block_signing_root(&pk, Slot::new(4), root(1))
+            .unwrap();
+        db.check_and_insert_attestation_signing_root(&pk, Epoch::new(1), Epoch::new(2), root(2))
+            .unwrap();
+
+        let exported = db.export_interchange().unwrap();
+        let json = serde_json::to_string(&exported).unwrap();
+        let imported: Interchange = serde_json::from_str(&json).unwrap();
+        assert_eq!(imported, exported);
+
+        let other = SlashingDatabase::open_in_memory().unwrap();
+        other.import_interchange(&imported).unwrap();
+
+        assert_eq!(other.export_interchange().unwrap(), exported);
+        assert_eq!(
+            other.check_and_insert_block_signing_root(&pk, Slot::new(4), root(3)),
+            Err(NotSafe::InvalidBlock(InvalidBlock::DoubleBlockProposal {
+                slot: Slot::new(4)
+            }))
+        );
+    }
+
+    #[test]
+    fn disabled_validator_is_refused_until_registered() {
+        let (db, pk) = database_with_validator();
+        let unknown = Keypair::random().pk;
+        db.check_and_insert_block_signing_root(&pk, Slot::new(4), root(1))
+            .unwrap();
+
+        let exported = db.disable_and_export(&[pk.clone(), unknown]).unwrap();
+
+        assert_eq!(exported.data.len(), 1);
+        assert_eq!(exported.data[0].pubkey, pk);
+        assert_eq!(exported.data[0].signed_blocks.len(), 1);
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, Slot::new(5), root(2)),
+            Err(NotSafe::DisabledValidator(pk.clone()))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(
+                &pk,
+                Epoch::new(1),
+                Epoch::new(2),
+                root(3)
+            ),
+            Err(NotSafe::DisabledValidator(pk.clone()))
+        );
+
+        db.register_validators(std::iter::once(&pk)).unwrap();
+        assert_eq!(
+            db.check_and_insert_block_signing_root(&pk, Slot::new(5), root(2)),
+            Ok(Safe::Valid)
+        );
+    }
+
+    #[test]
+    fn import_keeps_attestations_with_the_same_target() {
+        let (db, pk) = database_with_validator();
+        let epoch = Epoch::new;
+        db.check_and_insert_attestation_signing_root(&pk, epoch(0), epoch(1), root(1))
+            .unwrap();
+        db.check_and_insert_attestation_signing_root(&pk, epoch(4), epoch(10), root(2))
+            .unwrap();
+
+        let interchange = Interchange {
+            metadata: InterchangeMetadata::default(),
+            data: vec![InterchangeData {
+                pubkey: pk.clone(),
+                signed_blocks: vec![],
+                signed_attestations: vec![interchange::SignedAttestation {
+                    source_epoch: epoch(2),
+                    target_epoch: epoch(10),
+                    signing_root: root(3),
+                }],
+            }],
+        };
+        db.import_interchange(&interchange).unwrap();
+
+        // Only the imported attestation surrounds this one.
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(3), epoch(6), root(4)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::PrevSurroundsNew {
+                    prev_source: epoch(2),
+                    prev_target: epoch(10)
+                }
+            ))
+        );
+        assert_eq!(
+            db.check_and_insert_attestation_signing_root(&pk, epoch(4), epoch(10), root(2)),
+            Err(NotSafe::InvalidAttestation(
+                InvalidAttestation::DoubleVote {
+                    target_epoch: epoch(10)
+                }
+            ))
+        );
+        assert_eq!(
+            db.export_interchange().unwrap().data[0]
+                .signed_attestations
+                .len(),
+            3
+        );
+    }
+}
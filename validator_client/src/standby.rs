@@ -0,0 +1,184 @@
+//! Standby mode: a secondary validator client holds the same keys as a primary, but does not sign
+//! until none of its validators have been seen on the network for a number of epochs.
+//!
+//! Promotion is one-way. If the primary returns after the standby has begun signing, it is the
+//! primary's doppelganger protection which must stop it.
+use crate::doppelganger::{BeaconNodeLiveness, BeaconNodeLivenessError};
+use types::{Epoch, PublicKey};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StandbyStatus {
+    /// Our validators were last seen during `primary_last_seen`; signing waits until they have
+    /// been absent for long enough after it.
+    Standby { primary_last_seen: Epoch },
+    /// The primary has been absent for long enough; signing may begin.
+    Promoted,
+}
+
+/// A polling state machine which watches for the primary validator client and decides when the
+/// standby may take over.
+pub struct StandbyMonitor {
+    /// The number of full epochs the primary must be absent for.
+    epochs: u64,
+    status: StandbyStatus,
+}
+
+impl StandbyMonitor {
+    /// Waits for the primary to be absent for `epochs` full epochs following `start_epoch`, the
+    /// epoch in which the client started.
+    ///
+    /// The primary is assumed to have been present in `start_epoch`, since its messages may not
+    /// yet have been included on chain.
+    pub fn new(start_epoch: Epoch, epochs: u64) -> Self {
+        Self {
+            epochs,
+            status: StandbyStatus::Standby {
+                primary_last_seen: start_epoch,
+            },
+        }
+    }
+
+    /// Checks the previous and current epoch for liveness of any of `pub_keys`, returning the new
+    /// status.
+    ///
+    /// The previous epoch is re-checked since its attestations may be included in blocks during
+    /// the current epoch, so an epoch only counts towards the absence once it has been checked
+    /// after its end.
+    pub fn poll<B: BeaconNodeLiveness>(
+        &mut self,
+        current_epoch: Epoch,
+        beacon_node: &B,
+        pub_keys: &[PublicKey],
+    ) -> Result<StandbyStatus, BeaconNodeLivenessError> {
+        let mut last_seen = match self.status {
+            StandbyStatus::Standby { primary_last_seen } => primary_last_seen,
+            StandbyStatus::Promoted => return Ok(StandbyStatus::Promoted),
+        };
+
+        let previous_epoch = current_epoch.saturating_sub(1u64);
+        for epoch in &[previous_epoch, current_epoch] {
+            let epoch = *epoch;
+            if epoch <= last_seen {
+                continue;
+            }
+
+            if beacon_node
+                .request_liveness(epoch, pub_keys)?
+                .into_iter()
+                .any(|is_live| is_live)
+            {
+                last_seen = epoch;
+            }
+        }
+
+        self.status = if current_epoch > last_seen + self.epochs {
+            StandbyStatus::Promoted
+        } else {
+            StandbyStatus::Standby {
+                primary_last_seen: last_seen,
+            }
+        };
+
+        Ok(self.status.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Keypair;
+
+    /// A beacon node which reports every validator as live in a fixed set of epochs.
+    struct TestBeaconNode {
+        live_epochs: Vec<Epoch>,
+    }
+
+    impl BeaconNodeLiveness for TestBeaconNode {
+        fn request_liveness(
+            &self,
+            epoch: Epoch,
+            pub_keys: &[PublicKey],
+        ) -> Result<Vec<bool>, BeaconNodeLivenessError> {
+            Ok(vec![self.live_epochs.contains(&epoch); pub_keys.len()])
+        }
+    }
+
+    fn beacon_node(live_epochs: Vec<u64>) -> TestBeaconNode {
+        TestBeaconNode {
+            live_epochs: live_epochs.into_iter().map(Epoch::new).collect(),
+        }
+    }
+
+    fn standby(last_seen: u64) -> StandbyStatus {
+        StandbyStatus::Standby {
+            primary_last_seen: Epoch::new(last_seen),
+        }
+    }
+
+    #[test]
+    fn promotes_once_primary_is_absent() {
+        let pub_keys = vec![Keypair::random().pk];
+        let node = beacon_node(vec![]);
+        let mut monitor = StandbyMonitor::new(Epoch::new(10), 2);
+
+        for epoch in 10..13 {
+            assert_eq!(
+                monitor.poll(Epoch::new(epoch), &node, &pub_keys),
+                Ok(standby(10))
+            );
+        }
+        assert_eq!(
+            monitor.poll(Epoch::new(13), &node, &pub_keys),
+            Ok(StandbyStatus::Promoted)
+        );
+    }
+
+    #[test]
+    fn waits_while_primary_is_live() {
+        let pub_keys = vec![Keypair::random().pk];
+        let node = beacon_node((10..20).collect());
+        let mut monitor = StandbyMonitor::new(Epoch::new(10), 2);
+
+        for epoch in 10..20 {
+            assert_eq!(
+                monitor.poll(Epoch::new(epoch), &node, &pub_keys),
+                Ok(standby(epoch))
+            );
+        }
+        // Epoch 19 is re-checked in epoch 20, and the absence counted from there.
+        assert_eq!(
+            monitor.poll(Epoch::new(20), &node, &pub_keys),
+            Ok(standby(19))
+        );
+        assert_eq!(
+            monitor.poll(Epoch::new(21), &node, &pub_keys),
+            Ok(standby(19))
+        );
+        assert_eq!(
+            monitor.poll(Epoch::new(22), &node, &pub_keys),
+            Ok(StandbyStatus::Promoted)
+        );
+    }
+
+    #[test]
+    fn late_inclusion_delays_promotion() {
+        let pub_keys = vec![Keypair::random().pk];
+        // An attestation from epoch 12 is only seen when re-checking it in epoch 13.
+        let node = beacon_node(vec![12]);
+        let mut monitor = StandbyMonitor::new(Epoch::new(10), 2);
+
+        assert_eq!(
+            monitor.poll(Epoch::new(13), &node, &pub_keys),
+            Ok(standby(12))
+        );
+        assert_eq!(
+            monitor.poll(Epoch::new(15), &node, &pub_keys),
+            Ok(StandbyStatus::Promoted)
+        );
+        // Promotion is permanent.
+        assert_eq!(
+            monitor.poll(Epoch::new(16), &beacon_node(vec![16]), &pub_keys),
+            Ok(StandbyStatus::Promoted)
+        );
+    }
+}